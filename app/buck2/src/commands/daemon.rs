@@ -177,7 +177,8 @@ impl BuckdServerDependencies for BuckdServerDependenciesImpl {
         req: buck2_cli_proto::ProfileRequest,
     ) -> anyhow::Result<buck2_cli_proto::ProfileResponse> {
         match req.profile_opts.as_ref().expect("Missing profile opts") {
-            buck2_cli_proto::profile_request::ProfileOpts::TargetProfile(_) => {
+            buck2_cli_proto::profile_request::ProfileOpts::TargetProfile(_)
+            | buck2_cli_proto::profile_request::ProfileOpts::ProfileDiff(_) => {
                 profile_command(ctx, partial_result_dispatcher, req).await
             }
             buck2_cli_proto::profile_request::ProfileOpts::BxlProfile(_) => {
@@ -209,6 +209,7 @@ struct UnpackedRunActionValues<'v> {
     args: &'v dyn CommandLineArgLike,
     env: Vec<(&'v str, &'v dyn CommandLineArgLike)>,
     worker: Option<&'v dyn CommandLineArgLike>,
+    worker_concurrency: Option<usize>,
 }
 
 #[derive(Debug, Allocative)]
@@ -244,11 +245,10 @@ impl RunAction {
         };
         let worker: NoneOr<&WorkerInfo> = NoneOr::unpack_value(values.worker.to_value())?;
 
-        let worker = if let Some(worker) = worker.into_option() {
-            let worker_exe = worker.exe_command_line();
-            Some(worker_exe)
+        let (worker, worker_concurrency) = if let Some(worker) = worker.into_option() {
+            (Some(worker.exe_command_line()), worker.concurrency())
         } else {
-            None
+            (None, None)
         };
 
         Some(UnpackedRunActionValues {
@@ -256,6 +256,7 @@ impl RunAction {
             args,
             env,
             worker,
+            worker_concurrency,
         })
     }
 
@@ -281,6 +282,7 @@ impl RunAction {
             Some(WorkerSpec {
                 id: Self::unpack_worker_id(&self.starlark_values),
                 exe: worker_rendered,
+                concurrency: values.worker_concurrency,
             })
         } else {
             None
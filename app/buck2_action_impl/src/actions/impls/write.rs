@@ -36,6 +36,7 @@ use indexmap::indexmap;
 use indexmap::IndexMap;
 use indexmap::IndexSet;
 use once_cell::sync::Lazy;
+use starlark::values::bytes::StarlarkBytes;
 use starlark::values::OwnedFrozenValue;
 use thiserror::Error;
 
@@ -47,7 +48,7 @@ enum WriteActionValidationError {
     NoOutputs,
     #[error("WriteAction received more than one output")]
     TooManyOutputs,
-    #[error("Expected command line value, got {0}")]
+    #[error("Expected command line value or bytes, got {0}")]
     ContentsNotCommandLineValue(String),
 }
 
@@ -88,7 +89,7 @@ impl UnregisteredAction for UnregisteredWriteAction {
 
 #[derive(Debug, Allocative)]
 struct WriteAction {
-    contents: OwnedFrozenValue, // StarlarkCommandLine
+    contents: OwnedFrozenValue, // StarlarkCommandLine or StarlarkBytes
     is_executable: bool,
     macro_files: Option<IndexSet<Artifact>>,
     output: BuildArtifact,
@@ -114,7 +115,9 @@ impl WriteAction {
             return Err(WriteActionValidationError::TooManyInputs.into());
         }
 
-        if contents.value().as_command_line().is_none() {
+        if contents.value().as_command_line().is_none()
+            && contents.value().downcast_ref::<StarlarkBytes>().is_none()
+        {
             return Err(WriteActionValidationError::ContentsNotCommandLineValue(
                 contents.value().to_repr(),
             )
@@ -129,7 +132,11 @@ impl WriteAction {
         })
     }
 
-    fn get_contents(&self, fs: &ExecutorFs) -> anyhow::Result<String> {
+    fn get_contents(&self, fs: &ExecutorFs) -> anyhow::Result<Vec<u8>> {
+        if let Some(bytes) = self.contents.value().downcast_ref::<StarlarkBytes>() {
+            return Ok(bytes.as_bytes().to_vec());
+        }
+
         let mut cli = Vec::<String>::new();
 
         let mut ctx = if let Some(macro_files) = &self.macro_files {
@@ -144,7 +151,7 @@ impl WriteAction {
             .unwrap()
             .add_to_command_line(&mut cli, &mut ctx)?;
 
-        Ok(cli.join("\n"))
+        Ok(cli.join("\n").into_bytes())
     }
 }
 
@@ -180,7 +187,7 @@ impl Action for WriteAction {
         // TODO(cjhopman): We should change this api to support returning a Result.
         indexmap! {
             "contents".to_owned() => match self.get_contents(fs) {
-                Ok(v) => v,
+                Ok(v) => String::from_utf8_lossy(&v).into_owned(),
                 Err(e) => format!("ERROR: constructing contents ({})", e)
             }
         }
@@ -201,7 +208,7 @@ impl IncrementalActionExecutable for WriteAction {
             .materializer()
             .declare_write(Box::new(|| {
                 execution_start = Some(Instant::now());
-                let content = self.get_contents(&ctx.executor_fs())?.into_bytes();
+                let content = self.get_contents(&ctx.executor_fs())?;
                 Ok(vec![WriteRequest {
                     path: fs.resolve_build(self.output.get_path()),
                     content,
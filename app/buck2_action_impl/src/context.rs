@@ -56,6 +56,7 @@ use sha1::Sha1;
 use starlark::environment::MethodsBuilder;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
+use starlark::values::bytes::StarlarkBytes;
 use starlark::values::function::FUNCTION_TYPE;
 use starlark::values::none::NoneOr;
 use starlark::values::none::NoneType;
@@ -297,6 +298,7 @@ fn analysis_actions_methods_actions(builder: &mut MethodsBuilder) {
 
     /// Returns an `artifact` whose contents are content
     ///
+    /// * `content`: a command line, whose arguments are written one per line, or `bytes`, which are written as is
     /// * `is_executable` (optional): indicates whether the resulting file should be marked with executable permissions
     /// * `allow_args` (optional): must be set to `True` if you want to write parameter arguments to the file (in particular, macros that write to file)
     ///     * If it is true, the result will be a pair of the `artifact` containing content and a list of artifact values that were written by macros, which should be used in hidden fields or similar
@@ -375,6 +377,26 @@ fn analysis_actions_methods_actions(builder: &mut MethodsBuilder) {
         let (declaration, output_artifact) =
             this.get_or_declare_output(eval, output, "output", OutputType::File)?;
 
+        if content.downcast_ref::<StarlarkBytes>().is_some() {
+            // Bytes can't contain artifacts or macros, so there's nothing else to track.
+            this.register_action(
+                indexset![],
+                indexset![output_artifact],
+                UnregisteredWriteAction::new(is_executable, None),
+                Some(content),
+            )?;
+            let value = declaration
+                .into_declared_artifact(AssociatedArtifacts::new())
+                .to_value();
+            return if allow_args {
+                Ok(eval
+                    .heap()
+                    .alloc((value, Vec::<StarlarkDeclaredArtifact>::new())))
+            } else {
+                Ok(value)
+            };
+        }
+
         let (content_cli, written_macro_count, mut associated_artifacts) =
             if let Some(content_arg) = content.as_command_line() {
                 let count = count_write_to_file_macros(allow_args, content_arg)?;
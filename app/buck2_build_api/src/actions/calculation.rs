@@ -407,6 +407,10 @@ pub async fn command_details(
             queue_time: command.timing.re_queue_time.and_then(|d| d.try_into().ok()),
         }
         .into(),
+        CommandExecutionKind::LocalActionCache { digest } => buck2_data::LocalActionCacheCommand {
+            action_digest: digest.to_string(),
+        }
+        .into(),
     });
 
     buck2_data::CommandExecutionDetails {
@@ -44,6 +44,7 @@ use buck2_util::late_binding::LateBinding;
 use derive_more::Display;
 use dice::DiceComputations;
 use dice::Key;
+use dice::StorageType;
 use dupe::Dupe;
 use dupe::IterDupedExt;
 use futures::stream::FuturesOrdered;
@@ -131,6 +132,12 @@ impl RuleAnalysisCalculation for DiceComputations {
                 // TODO consider if we want analysis result to be eq
                 false
             }
+
+            fn storage_type() -> StorageType {
+                // analysis is deterministic, and its results are a large part of the daemon's
+                // memory, so they are dropped under memory pressure if not requested recently
+                StorageType::EvictableLastN(1)
+            }
         }
 
         self.compute(&AnalysisKey(target.dupe()))
@@ -384,8 +391,8 @@ pub async fn profile_analysis(
     .context("profile_data not set (internal error)")
 }
 
-fn all_deps(node: ConfiguredTargetNode) -> LabelIndexedSet<ConfiguredTargetNode> {
-    let mut stack = vec![node];
+fn all_deps(nodes: Vec<ConfiguredTargetNode>) -> LabelIndexedSet<ConfiguredTargetNode> {
+    let mut stack = nodes;
     let mut visited = LabelIndexedSet::new();
     while let Some(node) = stack.pop() {
         if visited.insert(node.dupe()) {
@@ -398,6 +405,15 @@ fn all_deps(node: ConfiguredTargetNode) -> LabelIndexedSet<ConfiguredTargetNode>
 pub async fn profile_analysis_recursively(
     ctx: &DiceComputations,
     target: &ConfiguredTargetLabel,
+) -> anyhow::Result<StarlarkProfileDataAndStats> {
+    profile_analysis_recursively_for_targets(ctx, std::slice::from_ref(target)).await
+}
+
+/// Merge the analysis profiles of `targets` and all their dependencies, counting the targets they
+/// share once.
+pub async fn profile_analysis_recursively_for_targets(
+    ctx: &DiceComputations,
+    targets: &[ConfiguredTargetLabel],
 ) -> anyhow::Result<StarlarkProfileDataAndStats> {
     // Self check.
     let profile_mode = ctx.get_profile_mode_for_intermediate_analysis().await?;
@@ -408,12 +424,16 @@ pub async fn profile_analysis_recursively(
         return Err(ProfileAnalysisError::RecursiveProfileConfiguredIncorrectly.into());
     }
 
-    let node = ctx
-        .get_configured_target_node(target)
-        .await?
-        .require_compatible()?;
+    let mut nodes = Vec::with_capacity(targets.len());
+    for target in targets {
+        nodes.push(
+            ctx.get_configured_target_node(target)
+                .await?
+                .require_compatible()?,
+        );
+    }
 
-    let all_deps = all_deps(node);
+    let all_deps = all_deps(nodes);
 
     let mut futures = all_deps
         .iter()
@@ -20,6 +20,7 @@ use buck2_core::target::label::ConfiguredTargetLabel;
 use buck2_core::unsafe_send_future::UnsafeSendFuture;
 use buck2_events::dispatch::get_dispatcher;
 use buck2_execute::digest_config::HasDigestConfig;
+use buck2_interpreter::dice::starlark_limits::HasStarlarkEvalLimits;
 use buck2_interpreter::print_handler::EventDispatcherPrintHandler;
 use buck2_interpreter::starlark_profiler::StarlarkProfileDataAndStats;
 use buck2_interpreter::starlark_profiler::StarlarkProfileModeOrInstrumentation;
@@ -332,9 +333,12 @@ async fn run_analysis_with_env_underlying(
         Some(profiler) => StarlarkProfilerOrInstrumentation::for_profiler(profiler),
     };
 
+    let limits = dice.get_analysis_eval_limits().await?;
+
     let analysis_registry = {
         let mut eval = Evaluator::new(&env);
         eval.set_print_handler(&print);
+        limits.initialize(&mut eval);
 
         let ctx = env.heap().alloc_typed(AnalysisContext::new(
             eval.heap(),
@@ -18,17 +18,22 @@ use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::digest_config::SetDigestConfig;
 use dice::DetectCycles;
 use dice::Dice;
+use dice::PersistentKeys;
 use dice::WhichDice;
 use dice::WhichSpawner;
 
 /// Utility to configure the dice globals.
 /// One place to not forget to initialize something in all places.
+///
+/// If a snapshot is given, it is restored before anything is injected. If it cannot be
+/// restored, DICE starts empty.
 pub async fn configure_dice_for_buck(
     io: Arc<dyn IoProvider>,
     digest_config: DigestConfig,
     root_config: Option<&LegacyBuckConfig>,
     detect_cycles: Option<DetectCycles>,
     which_dice: Option<WhichDice>,
+    snapshot: Option<(&PersistentKeys, &[u8])>,
 ) -> anyhow::Result<Arc<Dice>> {
     let detect_cycles = detect_cycles.map_or_else(
         || {
@@ -63,6 +68,15 @@ pub async fn configure_dice_for_buck(
     dice.set_digest_config(digest_config);
 
     let dice = dice.build_with_which_spawner(detect_cycles, which_spawner);
+
+    if let Some((keys, snapshot)) = snapshot {
+        match dice.restore(keys, snapshot).await {
+            // The restored graph already has the cell resolver and buckconfigs.
+            Ok(()) => return Ok(dice),
+            Err(e) => tracing::warn!("Failed to restore the DICE snapshot: {:#}", e),
+        }
+    }
+
     let mut dice_ctx = dice.updater();
     dice_ctx.set_none_cell_resolver()?;
     dice_ctx.set_none_legacy_configs()?;
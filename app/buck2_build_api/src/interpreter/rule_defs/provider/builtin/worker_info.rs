@@ -17,8 +17,11 @@ use starlark::coerce::Coerce;
 use starlark::environment::GlobalsBuilder;
 use starlark::eval::Evaluator;
 use starlark::values::list::AllocList;
+use starlark::values::none::NoneOr;
+use starlark::values::none::NoneType;
 use starlark::values::Freeze;
 use starlark::values::Trace;
+use starlark::values::UnpackValue;
 use starlark::values::Value;
 use starlark::values::ValueLike;
 
@@ -30,11 +33,15 @@ use crate::interpreter::rule_defs::cmd_args::StarlarkCommandLine;
 #[internal_provider(worker_info_creator)]
 #[derive(Clone, Debug, Trace, Coerce, Freeze, ProvidesStaticType, Allocative)]
 #[freeze(validator = validate_worker_info, bounds = "V: ValueLike<'freeze>")]
-#[repr(transparent)]
+#[repr(C)]
 pub struct WorkerInfoGen<V> {
     // Command to spawn a new worker
     #[provider(field_type = "StarlarkCommandLine")]
     pub exe: V,
+
+    // Maximum number of commands to run on this worker at once, or None for no limit
+    #[provider(field_type = "Option<i32>")]
+    pub concurrency: V,
 }
 
 #[starlark_module]
@@ -42,12 +49,15 @@ fn worker_info_creator(globals: &mut GlobalsBuilder) {
     #[starlark(dot_type = "WorkerInfo")]
     fn WorkerInfo<'v>(
         #[starlark(default = AllocList::EMPTY)] exe: Value<'v>,
+        #[starlark(require = named, default = NoneType)] concurrency: Value<'v>,
         eval: &mut Evaluator<'v, '_>,
     ) -> anyhow::Result<WorkerInfo<'v>> {
         let heap = eval.heap();
         let valid_exe = StarlarkCommandLine::try_from_value(exe)?;
         let exe = heap.alloc(valid_exe);
-        Ok(WorkerInfo { exe })
+        let res = WorkerInfo { exe, concurrency };
+        validate_worker_info(&res)?;
+        Ok(res)
     }
 }
 
@@ -58,6 +68,12 @@ impl<'v, V: ValueLike<'v>> WorkerInfoGen<V> {
             .as_command_line()
             .expect("validated at construction")
     }
+
+    pub fn concurrency(&self) -> Option<usize> {
+        NoneOr::<usize>::unpack_value(self.concurrency.to_value())
+            .expect("validated at construction")
+            .into_option()
+    }
 }
 
 fn validate_worker_info<'v, V>(info: &WorkerInfoGen<V>) -> anyhow::Result<()>
@@ -77,5 +93,21 @@ where
         ));
     }
 
+    let concurrency =
+        NoneOr::<i32>::unpack_value(info.concurrency.to_value()).with_context(|| {
+            format!(
+                "Value for `concurrency` field is not an int: `{}`",
+                info.concurrency
+            )
+        })?;
+    if let NoneOr::Other(concurrency) = concurrency {
+        if concurrency < 1 {
+            return Err(anyhow::anyhow!(
+                "Value for `concurrency` field must be at least 1: `{}`",
+                concurrency
+            ));
+        }
+    }
+
     Ok(())
 }
@@ -20,6 +20,7 @@ use buck2_interpreter::types::provider::callable::ProviderCallableLike;
 use dupe::Dupe;
 use once_cell::unsync;
 use starlark::any::ProvidesStaticType;
+use starlark::docs::Doc;
 use starlark::docs::DocItem;
 use starlark::docs::DocString;
 use starlark::environment::GlobalsBuilder;
@@ -265,6 +266,13 @@ impl<'v> StarlarkValue<'v> for UserProviderCallable {
             &return_types,
         )
     }
+
+    fn type_documentation(&self) -> Option<Doc> {
+        self.provider_type_documentation(
+            &self.fields.iter().map(|x| x.as_str()).collect::<Vec<_>>(),
+            &self.field_docs,
+        )
+    }
 }
 
 #[derive(Debug, ProvidesStaticType, NoSerialize, Allocative)]
@@ -358,6 +366,13 @@ impl<'v> StarlarkValue<'v> for FrozenUserProviderCallable {
             &return_types,
         )
     }
+
+    fn type_documentation(&self) -> Option<Doc> {
+        self.provider_type_documentation(
+            &self.fields.iter().map(|x| x.as_str()).collect::<Vec<_>>(),
+            &self.field_docs,
+        )
+    }
 }
 
 #[starlark_module]
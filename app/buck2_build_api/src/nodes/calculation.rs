@@ -56,6 +56,7 @@ use buck2_node::visibility::VisibilityError;
 use derive_more::Display;
 use dice::DiceComputations;
 use dice::Key;
+use dice::StorageType;
 use dupe::Dupe;
 use indexmap::IndexSet;
 use more_futures::cancellation::CancellationContext;
@@ -865,6 +866,12 @@ impl NodeCalculation for DiceComputations {
                     _ => false,
                 }
             }
+
+            fn storage_type() -> StorageType {
+                // configuring a target is deterministic, and configured nodes of targets not
+                // built recently are not worth keeping under memory pressure
+                StorageType::EvictableLastN(1)
+            }
         }
 
         self.compute(&ConfiguredTargetNodeKey(target.dupe()))
@@ -289,6 +289,7 @@ define_request!(MaterializeRequest, has(context));
 define_request!(AllocativeRequest, has(context));
 define_request!(CleanStaleRequest, has(context));
 define_request!(FileStatusRequest, has(context));
+define_request!(DiceExplainRequest, has(context));
 define_request!(TraceIoRequest, has(context));
 
 define_request!(InstallRequest, has(context, build_options));
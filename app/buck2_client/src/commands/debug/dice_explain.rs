@@ -0,0 +1,78 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::DiceExplainRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonDaemonCommandOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::NoPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct DiceExplainCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    /// Regex matched against the DICE keys to explain, e.g. `AnalysisKey.*//foo:bar`
+    #[clap(value_name = "KEY_PATTERN")]
+    key_pattern: String,
+}
+
+#[async_trait]
+impl StreamingCommand for DiceExplainCommand {
+    const COMMAND_NAME: &'static str = "dice-explain";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(
+            &self.common_opts.config_opts,
+            matches,
+            ctx.sanitized_argv.argv.clone(),
+        )?;
+        buckd
+            .with_flushing()
+            .dice_explain(
+                DiceExplainRequest {
+                    context: Some(context),
+                    key_pattern: self.key_pattern,
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonDaemonCommandOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn common_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+}
@@ -16,6 +16,7 @@ use buck2_client_ctx::streaming::BuckSubcommand;
 use chrome_trace::ChromeTraceCommand;
 use crash::CrashCommand;
 use dice_dump::DiceDumpCommand;
+use dice_explain::DiceExplainCommand;
 use file_status::FileStatusCommand;
 use flush_dep_files::FlushDepFilesCommand;
 use heap_dump::HeapDumpCommand;
@@ -41,6 +42,7 @@ mod chrome_trace;
 mod crash;
 mod daemon_dir;
 mod dice_dump;
+mod dice_explain;
 mod exe;
 mod file_status;
 mod flush_dep_files;
@@ -87,6 +89,9 @@ pub enum DebugCommand {
     UploadReLogs(UploadReLogsCommand),
     /// Validates that Buck2 and disk agree on the state of files.
     FileStatus(FileStatusCommand),
+    /// Shows which changed keys, e.g. files, caused the DICE keys matching a pattern to be
+    /// invalidated by recent commands.
+    DiceExplain(DiceExplainCommand),
     /// Shows the commands that buck ran
     #[clap(alias = "whatran", setting(clap::AppSettings::Hidden))]
     WhatRan(DebugWhatRanCommand),
@@ -129,6 +134,7 @@ impl DebugCommand {
             DebugCommand::Allocative(cmd) => cmd.exec(matches, ctx),
             DebugCommand::SetLogFilter(cmd) => cmd.exec(matches, ctx),
             DebugCommand::FileStatus(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::DiceExplain(cmd) => cmd.exec(matches, ctx),
             DebugCommand::LogPerf(cmd) => cmd.exec(matches, ctx),
             DebugCommand::TraceIo(cmd) => cmd.exec(matches, ctx),
             DebugCommand::PersistEventLogs(cmd) => cmd.exec(matches, ctx),
@@ -15,6 +15,7 @@ use buck2_cli_proto::profile_request::ProfileOpts;
 use buck2_cli_proto::profile_request::Profiler;
 use buck2_cli_proto::target_profile::Action;
 use buck2_cli_proto::BxlProfile;
+use buck2_cli_proto::ProfileDiff;
 use buck2_cli_proto::ProfileRequest;
 use buck2_cli_proto::ProfileResponse;
 use buck2_cli_proto::TargetProfile;
@@ -46,6 +47,9 @@ pub enum ProfileCommand {
 
     #[clap(about = "Profile BXL script")]
     Bxl(BxlProfileOptions),
+
+    #[clap(about = "Compare the heap summary profiles of two runs")]
+    Diff(ProfileDiffOptions),
 }
 
 pub enum ProfileOptionsType {
@@ -82,6 +86,7 @@ impl ProfileCommand {
                 },
                 profile_common_opts: opts.profile_common_opts,
             },
+            Self::Diff(opts) => return opts.exec(submatches, ctx),
         }
         .exec(submatches, ctx)
     }
@@ -102,6 +107,7 @@ enum BuckProfileMode {
     Bytecode,
     BytecodePairs,
     Typecheck,
+    Coverage,
 }
 
 #[derive(Debug, clap::Parser)]
@@ -154,10 +160,103 @@ pub struct ProfileCommonOptions {
     /// This is probably what you want when profiling analysis.
     ///
     /// `-allocated` means allocated memory, including memory which is later garbage collected.
+    ///
+    /// `coverage` writes the lines of each file that were executed to the output directory, as
+    /// an LCOV tracefile (`coverage.lcov`) and a Cobertura report (`coverage.xml`).
     #[clap(long, short = 'm', value_enum)]
     mode: BuckProfileMode,
 }
 
+/// Reports per-function regressions in self time, allocated bytes and retained bytes as CSV,
+/// e.g. to compare `buck2 profile analysis` before and after a prelude change.
+///
+/// The profiles are compared by the daemon, which has the Starlark profiler.
+#[derive(Debug, clap::Parser)]
+pub struct ProfileDiffOptions {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    /// `heap-summary-allocated` profile of the first run.
+    #[clap(value_name = "BEFORE")]
+    before: PathArg,
+
+    /// `heap-summary-allocated` profile of the second run.
+    #[clap(value_name = "AFTER")]
+    after: PathArg,
+
+    /// `heap-summary-retained` profile of the first run, to also compare retained bytes.
+    #[clap(long, value_name = "PATH", requires = "retained-after")]
+    retained_before: Option<PathArg>,
+
+    /// `heap-summary-retained` profile of the second run.
+    #[clap(long, value_name = "PATH", requires = "retained-before")]
+    retained_after: Option<PathArg>,
+
+    /// Output file path for the CSV.
+    ///
+    /// File will be created if it does not exist, and overwritten if it does.
+    #[clap(long, short = 'o', value_name = "PATH")]
+    output: PathArg,
+}
+
+#[async_trait]
+impl StreamingCommand for ProfileDiffOptions {
+    const COMMAND_NAME: &'static str = "profile";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(
+            &self.common_opts.config_opts,
+            matches,
+            ctx.sanitized_argv.argv.clone(),
+        )?;
+
+        let resolve = |path: &PathArg| path.resolve(&ctx.working_dir).into_string();
+        let diff_opts = ProfileDiff {
+            before: resolve(&self.before)?,
+            after: resolve(&self.after)?,
+            retained_before: self.retained_before.as_ref().map(resolve).transpose()?,
+            retained_after: self.retained_after.as_ref().map(resolve).transpose()?,
+        };
+        let destination_path = resolve(&self.output)?;
+
+        let console_opts = ctx.stdin().console_interaction_stream(self.console_opts());
+
+        buckd
+            .with_flushing()
+            .profile(
+                ProfileRequest {
+                    context: Some(context),
+                    profile_opts: Some(ProfileOpts::ProfileDiff(diff_opts)),
+                    destination_path,
+                    profiler: Profiler::HeapSummaryAllocated.into(),
+                },
+                console_opts,
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+
+        buck2_client_ctx::println!("Profile diff has been written to {}", self.output.display())?;
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonDaemonCommandOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn common_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+}
+
 pub struct ProfileSubcommand {
     opts: ProfileOptionsType,
     profile_common_opts: ProfileCommonOptions,
@@ -174,6 +273,7 @@ fn profile_mode_to_profile(mode: &BuckProfileMode) -> Profiler {
         BuckProfileMode::Bytecode => Profiler::Bytecode,
         BuckProfileMode::BytecodePairs => Profiler::BytecodePairs,
         BuckProfileMode::Typecheck => Profiler::Typecheck,
+        BuckProfileMode::Coverage => Profiler::Coverage,
     }
 }
 
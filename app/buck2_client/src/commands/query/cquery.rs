@@ -46,6 +46,10 @@ use crate::commands::query::common::CommonQueryOptions;
 /// List the deps of a target (special characters in a target will require quotes):
 ///
 /// `buck2 cquery 'deps("//java/com/example/app:amazing+more")'`
+///
+/// Explain why a target depends on another, including any configuration transitions:
+///
+/// `buck2 cquery 'somepath(//java/com/example/app:amazing, //third-party:guava)' --explain-paths`
 #[derive(Debug, clap::Parser)]
 #[clap(name = "cquery")]
 pub struct CqueryCommand {
@@ -72,6 +76,14 @@ pub struct CqueryCommand {
     )]
     show_providers: bool,
 
+    /// Print the dependency chains through the query result, along with the attribute each
+    /// dependency comes from, instead of the result itself.
+    ///
+    /// Most useful with `allpaths` or `somepath`, e.g. `'allpaths(//foo:bar, //baz:qux)'` to see
+    /// why `//foo:bar` depends on `//baz:qux`. Combine with `--json` or `--dot` for other formats.
+    #[clap(long)]
+    explain_paths: bool,
+
     #[allow(rustdoc::bare_urls)]
     /// Enable deprecated `owner()` function behavior.
     ///
@@ -129,6 +141,7 @@ impl StreamingCommand for CqueryCommand {
                     show_providers: self.show_providers,
                     unstable_output_format,
                     correct_owner,
+                    explain_paths: self.explain_paths,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
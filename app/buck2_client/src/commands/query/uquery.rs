@@ -43,6 +43,9 @@ use crate::commands::query::common::CommonQueryOptions;
 /// List the deps of a target (special characters in a target will require quotes):
 /// `buck2 uquery 'deps("//java/com/example/app:amazing+more")'`
 ///
+/// Explain why a target depends on another:
+/// `buck2 uquery 'somepath(//java/com/example/app:amazing, //third-party:guava)' --explain-paths`
+///
 /// select() encoding:
 ///
 /// When printed, values with `select()`s use a special json encoding.
@@ -58,6 +61,14 @@ pub struct UqueryCommand {
 
     #[clap(flatten)]
     query_common: CommonQueryOptions,
+
+    /// Print the dependency chains through the query result, along with the attribute each
+    /// dependency comes from, instead of the result itself.
+    ///
+    /// Most useful with `allpaths` or `somepath`, e.g. `'allpaths(//foo:bar, //baz:qux)'` to see
+    /// why `//foo:bar` depends on `//baz:qux`. Combine with `--json` or `--dot` for other formats.
+    #[clap(long)]
+    explain_paths: bool,
 }
 
 #[async_trait]
@@ -87,6 +98,7 @@ impl StreamingCommand for UqueryCommand {
                     query_args,
                     context: Some(context),
                     output_attributes,
+                    explain_paths: self.explain_paths,
                     unstable_output_format,
                 },
                 ctx.stdin()
@@ -123,6 +123,12 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(long, group = "re_options", alias = "unstable-force-tests-on-re")]
     unstable_allow_all_tests_on_re: bool,
 
+    /// Writes the line coverage of the Starlark evaluated to analyze the tests and their
+    /// dependencies to the provided directory, as an LCOV tracefile (`coverage.lcov`) and a
+    /// Cobertura report (`coverage.xml`).
+    #[clap(long, value_name = "DIR")]
+    starlark_coverage: Option<PathArg>,
+
     #[clap(name = "TARGET_PATTERNS", help = "Patterns to test")]
     patterns: Vec<String>,
 
@@ -173,6 +179,10 @@ impl StreamingCommand for TestCommand {
             matches,
             ctx.sanitized_argv.argv.clone(),
         )?;
+        let starlark_coverage_path = match &self.starlark_coverage {
+            Some(path) => path.resolve(&ctx.working_dir).into_string()?,
+            None => String::new(),
+        };
         let response = buckd
             .with_flushing()
             .test(
@@ -195,6 +205,7 @@ impl StreamingCommand for TestCommand {
                         force_use_project_relative_paths: self.unstable_allow_all_tests_on_re,
                         force_run_from_project_root: self.unstable_allow_all_tests_on_re,
                     }),
+                    starlark_coverage_path,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
@@ -518,6 +518,12 @@ impl<'a, 'b> FlushingBuckdClient<'a, 'b> {
         GenericResponse,
         NoPartialResult
     );
+    stream_method!(
+        dice_explain,
+        DiceExplainRequest,
+        GenericResponse,
+        NoPartialResult
+    );
     stream_method!(
         unstable_docs,
         UnstableDocsRequest,
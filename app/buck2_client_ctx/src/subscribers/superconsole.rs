@@ -32,6 +32,8 @@ use buck2_wrapper_common::invocation_id::TraceId;
 use dupe::Dupe;
 use gazebo::prelude::*;
 use superconsole::components::DrawVertical;
+use superconsole::input::Key;
+use superconsole::input::KeyDecoder;
 use superconsole::style::Attribute;
 use superconsole::style::Color;
 use superconsole::style::ContentStyle;
@@ -55,6 +57,7 @@ use crate::subscribers::superconsole::dice::DiceComponent;
 use crate::subscribers::superconsole::io::IoHeader;
 use crate::subscribers::superconsole::re::ReHeader;
 use crate::subscribers::superconsole::test::TestHeader;
+use crate::subscribers::superconsole::timed_list::drill_down::DrillDownState;
 use crate::subscribers::superconsole::timed_list::Cutoffs;
 use crate::subscribers::superconsole::timed_list::TimedList;
 
@@ -81,6 +84,7 @@ pub struct StatefulSuperConsole {
     state: SuperConsoleState,
     super_console: Option<SuperConsole>,
     verbosity: Verbosity,
+    key_decoder: KeyDecoder,
 }
 
 #[derive(Copy, Clone, Dupe, Debug)]
@@ -111,6 +115,8 @@ pub struct SuperConsoleState {
     /// This contains the SpanTracker, which is why it's part of the SuperConsoleState.
     simple_console: SimpleConsole<DebugEventObserverExtra>,
     config: SuperConsoleConfig,
+    /// Selection state of the interactive list of running spans.
+    drill_down: DrillDownState,
 }
 
 #[derive(Clone)]
@@ -298,6 +304,7 @@ impl StatefulSuperConsole {
             )?,
             super_console: Some(super_console),
             verbosity,
+            key_decoder: KeyDecoder::new(),
         })
     }
 
@@ -355,6 +362,7 @@ impl SuperConsoleState {
                 show_waiting_message,
             ),
             config,
+            drill_down: DrillDownState::default(),
         })
     }
 
@@ -383,6 +391,60 @@ impl StatefulSuperConsole {
         self.handle_stderr(&format!("{what}: {on_off}, press `{key}` to revert"))
             .await
     }
+
+    async fn handle_char(&mut self, c: char) -> anyhow::Result<()> {
+        if c == 'd' {
+            self.toggle("DICE component", 'd', |s| &mut s.state.config.enable_dice)
+                .await?;
+        } else if c == 'e' {
+            self.toggle("Debug events component", 'e', |s| {
+                &mut s.state.config.enable_debug_events
+            })
+            .await?;
+        } else if c == '2' {
+            self.toggle("Two lines mode", '2', |s| &mut s.state.config.two_lines)
+                .await?;
+        } else if c == 'r' {
+            self.toggle("Detailed RE", 'r', |s| {
+                &mut s.state.config.enable_detailed_re
+            })
+            .await?;
+        } else if c == 'i' {
+            self.toggle("I/O counters", 'i', |s| &mut s.state.config.enable_io)
+                .await?;
+        } else if c == 'p' {
+            self.toggle("Display target configurations", 'p', |s| {
+                &mut s.state.config.display_platform
+            })
+            .await?;
+        } else if c == 'c' {
+            self.toggle("Commands", 'c', |s| &mut s.state.config.enable_commands)
+                .await?;
+        } else if c == '+' {
+            self.state.config.max_lines = self.state.config.max_lines.saturating_add(1);
+        } else if c == '-' {
+            self.state.config.max_lines = self.state.config.max_lines.saturating_sub(1);
+        } else if c == '?' || c == 'h' {
+            self.handle_stderr(
+                "Help:\n\
+                `d` = toggle DICE\n\
+                `e` = toggle debug events\n\
+                `2` = toggle two lines mode\n\
+                `r` = toggle detailed RE\n\
+                `i` = toggle I/O counters\n\
+                `p` = display target configurations\n\
+                `+` = show more lines\n\
+                `-` = show fewer lines\n\
+                `Up`/`Down` = select a running action\n\
+                `Right` = show details of the selected action\n\
+                `Left` = go back\n\
+                `h` = show this help",
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
 }
 
 // TODO(brasselsprouts): after deprecating filetailers, simplify these code paths
@@ -494,51 +556,17 @@ impl UnpackingEventSubscriber for StatefulSuperConsole {
     }
 
     async fn handle_console_interaction(&mut self, c: char) -> anyhow::Result<()> {
-        if c == 'd' {
-            self.toggle("DICE component", 'd', |s| &mut s.state.config.enable_dice)
-                .await?;
-        } else if c == 'e' {
-            self.toggle("Debug events component", 'e', |s| {
-                &mut s.state.config.enable_debug_events
-            })
-            .await?;
-        } else if c == '2' {
-            self.toggle("Two lines mode", '2', |s| &mut s.state.config.two_lines)
-                .await?;
-        } else if c == 'r' {
-            self.toggle("Detailed RE", 'r', |s| {
-                &mut s.state.config.enable_detailed_re
-            })
-            .await?;
-        } else if c == 'i' {
-            self.toggle("I/O counters", 'i', |s| &mut s.state.config.enable_io)
-                .await?;
-        } else if c == 'p' {
-            self.toggle("Display target configurations", 'p', |s| {
-                &mut s.state.config.display_platform
-            })
-            .await?;
-        } else if c == 'c' {
-            self.toggle("Commands", 'c', |s| &mut s.state.config.enable_commands)
-                .await?;
-        } else if c == '+' {
-            self.state.config.max_lines = self.state.config.max_lines.saturating_add(1);
-        } else if c == '-' {
-            self.state.config.max_lines = self.state.config.max_lines.saturating_sub(1);
-        } else if c == '?' || c == 'h' {
-            self.handle_stderr(
-                "Help:\n\
-                `d` = toggle DICE\n\
-                `e` = toggle debug events\n\
-                `2` = toggle two lines mode\n\
-                `r` = toggle detailed RE\n\
-                `i` = toggle I/O counters\n\
-                `p` = display target configurations\n\
-                `+` = show more lines\n\
-                `-` = show fewer lines\n\
-                `h` = show this help",
-            )
-            .await?;
+        for key in self.key_decoder.push(c) {
+            if self
+                .state
+                .drill_down
+                .handle_key(key, self.state.simple_console.observer().spans())
+            {
+                continue;
+            }
+            if let Key::Char(c) = key {
+                self.handle_char(c).await?;
+            }
         }
 
         Ok(())
@@ -780,7 +808,9 @@ fn lines_for_command_details(
                 )]));
             }
         }
-        Some(Command::OmittedLocalCommand(..)) | None => {
+        Some(Command::OmittedLocalCommand(..))
+        | Some(Command::LocalActionCacheCommand(..))
+        | None => {
             // Nothing to show in this case.
         }
     };
@@ -7,7 +7,9 @@
  * of this source tree.
  */
 
+use buck2_common::convert::ProstDurationExt;
 use buck2_event_observer::dice_state::DiceState;
+use buck2_event_observer::fmt_duration;
 use gazebo::prelude::*;
 use superconsole::Component;
 use superconsole::Lines;
@@ -32,10 +34,18 @@ impl<'s> Component for DiceComponent<'s> {
 
         let mut lines = vec!["Dice Key States".to_owned()];
 
-        let header = format!("  {:<42}  {:>12}  {:>12}", "  Key", "Pending", "Finished");
+        let header = format!(
+            "  {:<42}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}",
+            "  Key", "Pending", "Finished", "Computed", "Reused", "Cutoff", "Time", "Max"
+        );
         let header_len = header.len();
         lines.push(header);
         lines.push("-".repeat(header_len));
+        let time = |d: &Option<prost_types::Duration>| {
+            d.as_ref()
+                .and_then(|d| d.try_into_duration().ok())
+                .map_or_else(String::new, |d| fmt_duration::fmt_duration(d, 1.0))
+        };
         for (k, v) in self.dice_state.key_states() {
             // We aren't guaranteed to get a final DiceStateUpdate and so we just assume all dice nodes that we
             // know about finished so that the final rendering doesn't look silly.
@@ -44,11 +54,16 @@ impl<'s> Component for DiceComponent<'s> {
                 superconsole::DrawMode::Final => (0, v.started),
             };
             lines.push(format!(
-                "    {:<40} |{:>12} |{:>12}",
+                "    {:<40} |{:>10} |{:>10} |{:>10} |{:>10} |{:>10} |{:>10} |{:>10}",
                 // Dice key states are all ascii
                 if k.len() > 40 { &k[..40] } else { k },
                 pending,
-                finished
+                finished,
+                v.computed,
+                v.reused,
+                v.early_cutoffs,
+                time(&v.total_compute_time),
+                time(&v.max_compute_time),
             ));
         }
         lines.push("-".repeat(header_len));
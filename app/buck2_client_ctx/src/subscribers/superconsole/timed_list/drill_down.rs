@@ -0,0 +1,269 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Interactive alternative to the timed list body: the arrow keys open a scrollable list of all the
+//! running spans, and any of them can be opened to see its stages, command line and stderr.
+
+use buck2_event_observer::fmt_duration;
+use buck2_event_observer::span_tracker::BuckEventSpanHandle;
+use buck2_event_observer::span_tracker::BuckEventSpanTracker;
+use buck2_events::span::SpanId;
+use superconsole::components::Bounded;
+use superconsole::components::ScrollState;
+use superconsole::components::ScrollableList;
+use superconsole::input::Focusable;
+use superconsole::input::Key;
+use superconsole::style::Stylize;
+use superconsole::Component;
+use superconsole::Dimensions;
+use superconsole::DrawMode;
+use superconsole::Line;
+use superconsole::Lines;
+use superconsole::Span;
+
+use crate::subscribers::superconsole::timed_list::table_builder::Row;
+use crate::subscribers::superconsole::timed_list::table_builder::Table;
+use crate::subscribers::superconsole::timed_list::table_builder::TimedRow;
+use crate::subscribers::superconsole::timed_list::Cutoffs;
+use crate::subscribers::superconsole::SuperConsoleState;
+
+/// Maximum number of lines used to show the command line of an action.
+const MAX_COMMAND_LINES: usize = 4;
+
+#[derive(Debug, Copy, Clone, Default)]
+enum View {
+    /// The usual timed list.
+    #[default]
+    Off,
+    List,
+    Details(SpanId),
+}
+
+/// Which view is open, and which span is selected in it.
+#[derive(Debug, Default)]
+pub(crate) struct DrillDownState {
+    view: View,
+    /// Selection among the running root spans, in the order the timed list shows them.
+    list: ScrollState,
+}
+
+impl DrillDownState {
+    pub(crate) fn is_active(&self) -> bool {
+        !matches!(self.view, View::Off)
+    }
+
+    /// Returns whether the key was used, so that the caller can otherwise handle it.
+    pub(crate) fn handle_key(&mut self, key: Key, spans: &BuckEventSpanTracker) -> bool {
+        self.list.set_len(spans.roots_ongoing());
+
+        // The span being detailed may have finished since it was opened.
+        if let View::Details(span_id) = self.view {
+            match position(spans, span_id) {
+                Some(index) => self.list.select(index),
+                None => self.view = View::List,
+            }
+        }
+
+        match (self.view, key) {
+            (
+                View::Off,
+                Key::Up | Key::Down | Key::PageUp | Key::PageDown | Key::Home | Key::End,
+            ) => {
+                self.view = View::List;
+                true
+            }
+            (View::Off, _) => false,
+            (View::List, Key::Right | Key::Enter) => {
+                if let Some(span_id) = self.selected_span(spans) {
+                    self.view = View::Details(span_id);
+                }
+                true
+            }
+            (View::List, Key::Left | Key::Escape | Key::Backspace) => {
+                self.view = View::Off;
+                true
+            }
+            (View::Details(..), Key::Left | Key::Escape | Key::Backspace) => {
+                self.view = View::List;
+                true
+            }
+            (View::List, key) => self.list.handle_key(key),
+            // Moving the selection while looking at details shows the details of the newly
+            // selected span.
+            (View::Details(..), key) => {
+                if !self.list.handle_key(key) {
+                    return false;
+                }
+                if let Some(span_id) = self.selected_span(spans) {
+                    self.view = View::Details(span_id);
+                }
+                true
+            }
+        }
+    }
+
+    fn selected_span(&self, spans: &BuckEventSpanTracker) -> Option<SpanId> {
+        let root = spans.iter_roots().nth(self.list.selected()?)?;
+        root.info().event.span_id()
+    }
+}
+
+fn position(spans: &BuckEventSpanTracker, span_id: SpanId) -> Option<usize> {
+    spans
+        .iter_roots()
+        .position(|root| root.info().event.span_id() == Some(span_id))
+}
+
+fn hint(text: &str) -> anyhow::Result<Line> {
+    Ok(Line::from_iter([Span::new_styled(
+        text.to_owned().italic(),
+    )?]))
+}
+
+fn section(title: &str) -> anyhow::Result<Row> {
+    Ok(Line::from_iter([Span::new_styled(title.to_owned().bold())?]).into())
+}
+
+fn indented(mut line: Line) -> Row {
+    line.pad_left(2);
+    line.into()
+}
+
+/// Draws the open view of a `DrillDownState`.
+pub(crate) struct DrillDownComponent<'c> {
+    pub(crate) cutoffs: &'c Cutoffs,
+    pub(crate) state: &'c SuperConsoleState,
+}
+
+impl<'c> DrillDownComponent<'c> {
+    fn timed_row(&self, padding: usize, span: &BuckEventSpanHandle) -> anyhow::Result<Row> {
+        Ok(TimedRow::span(
+            padding,
+            span.info(),
+            self.state.time_speed.speed(),
+            self.cutoffs,
+            self.state.config.display_platform,
+        )?
+        .into())
+    }
+
+    fn draw_list(&self, dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        let spans = self.state.simple_console.observer().spans();
+
+        let mut table = Table::new();
+        for root in spans.iter_roots() {
+            table.rows.push(self.timed_row(0, &root)?);
+        }
+        // All the rows, so that the list can scroll through them.
+        let lines = table.draw(
+            Dimensions {
+                height: usize::MAX,
+                ..dimensions
+            },
+            mode,
+        )?;
+
+        let mut output = Bounded::new(
+            ScrollableList::new(&self.state.drill_down.list, lines),
+            None,
+            Some(self.state.config.max_lines),
+        )
+        .draw(dimensions, mode)?;
+        output.push(hint("Up/Down: select, Right: details, Left: back")?);
+        Ok(output)
+    }
+
+    fn draw_details(
+        &self,
+        root: &BuckEventSpanHandle,
+        dimensions: Dimensions,
+        mode: DrawMode,
+    ) -> anyhow::Result<Lines> {
+        let time_speed = self.state.time_speed.speed();
+        let observer = self.state.simple_console.observer();
+        let details = root
+            .info()
+            .event
+            .span_id()
+            .and_then(|span_id| observer.extra().action_details().get(span_id));
+
+        let mut table = Table::new();
+        table.rows.push(self.timed_row(0, root)?);
+
+        // Elapsed time of each stage, finished ones first.
+        if let Some(details) = details {
+            for (stage, duration) in &details.finished_stages {
+                table.rows.push(
+                    TimedRow::text(
+                        2,
+                        (*stage).to_owned(),
+                        fmt_duration::fmt_duration(*duration, time_speed),
+                        duration.mul_f64(time_speed),
+                        self.cutoffs,
+                    )?
+                    .into(),
+                );
+            }
+        }
+        for child in root.children() {
+            table.rows.push(self.timed_row(2, &child)?);
+        }
+
+        if let Some(command) = details.and_then(|d| d.command.as_ref()) {
+            table.rows.push(section("Command:")?);
+            let command = shlex::join(command.argv.iter().map(|arg| arg.as_str()));
+            let width = dimensions.width.saturating_sub(2).max(1);
+            let chars: Vec<char> = command.chars().collect();
+            for chunk in chars.chunks(width).take(MAX_COMMAND_LINES) {
+                table
+                    .rows
+                    .push(indented(Line::sanitized(&chunk.iter().collect::<String>())));
+            }
+        }
+
+        if let Some(stderr) = details.and_then(|d| d.stderr_tail.as_ref()) {
+            table.rows.push(section("Stderr:")?);
+            let lines = Lines::from_colored_multiline_string(&stderr.tail);
+            let shown = lines.len().min(self.state.config.max_lines);
+            if stderr.truncated || shown < lines.len() {
+                table.rows.push(indented(hint("...")?));
+            }
+            let skipped = lines.len() - shown;
+            for line in lines.into_iter().skip(skipped) {
+                table.rows.push(indented(line));
+            }
+        }
+
+        table
+            .rows
+            .push(hint("Up/Down: previous/next, Left: back to the list")?.into());
+        table.draw(dimensions, mode)
+    }
+}
+
+impl<'c> Component for DrillDownComponent<'c> {
+    fn draw_unchecked(&self, dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        let spans = self.state.simple_console.observer().spans();
+
+        match self.state.drill_down.view {
+            View::Off => Ok(Lines::new()),
+            View::Details(span_id) => {
+                match spans
+                    .iter_roots()
+                    .find(|root| root.info().event.span_id() == Some(span_id))
+                {
+                    Some(root) => self.draw_details(&root, dimensions, mode),
+                    // Finished: fall back to the list until the next key press.
+                    None => self.draw_list(dimensions, mode),
+                }
+            }
+            View::List => self.draw_list(dimensions, mode),
+        }
+    }
+}
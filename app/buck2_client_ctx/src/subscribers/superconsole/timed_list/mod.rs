@@ -29,6 +29,7 @@ use superconsole::Line;
 use superconsole::Lines;
 use superconsole::Span;
 
+use self::drill_down::DrillDownComponent;
 use self::table_builder::Table;
 use crate::subscribers::superconsole::common::HeaderLineComponent;
 use crate::subscribers::superconsole::common::StaticStringComponent;
@@ -36,6 +37,7 @@ use crate::subscribers::superconsole::timed_list::table_builder::Row;
 use crate::subscribers::superconsole::timed_list::table_builder::TimedRow;
 use crate::subscribers::superconsole::SuperConsoleState;
 
+pub(crate) mod drill_down;
 mod table_builder;
 
 /// The minimum time disparity between a single subaction and a target elapsed time
@@ -319,14 +321,25 @@ impl<'a> Component for TimedList<'a> {
                     header: self.header,
                     state: self.state,
                 };
-                let body = TimedListBody {
-                    cutoffs: self.cutoffs,
-                    state: self.state,
-                };
-
                 let mut draw = DrawVertical::new(dimensions);
                 draw.draw(&header, mode)?;
-                draw.draw(&body, mode)?;
+                if self.state.drill_down.is_active() {
+                    draw.draw(
+                        &DrillDownComponent {
+                            cutoffs: self.cutoffs,
+                            state: self.state,
+                        },
+                        mode,
+                    )?;
+                } else {
+                    draw.draw(
+                        &TimedListBody {
+                            cutoffs: self.cutoffs,
+                            state: self.state,
+                        },
+                        mode,
+                    )?;
+                }
                 Ok(draw.finish())
             }
             // show a summary at the end
@@ -354,6 +367,7 @@ mod tests {
     use buck2_wrapper_common::invocation_id::TraceId;
     use dupe::Dupe;
     use itertools::Itertools;
+    use superconsole::input::Key;
 
     use super::*;
     use crate::subscribers::subscriber::Tick;
@@ -649,6 +663,7 @@ mod tests {
                                         finished: 2,
                                         check_deps_started: 2,
                                         check_deps_finished: 1,
+                                        ..Default::default()
                                     },
                                 );
                                 map
@@ -706,6 +721,107 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_drill_down() -> anyhow::Result<()> {
+        let tick = Tick::now();
+
+        let fake = |caramba: &str| {
+            Arc::new(BuckEvent::new(
+                UNIX_EPOCH,
+                TraceId::new(),
+                Some(SpanId::new()),
+                None,
+                buck2_data::buck_event::Data::SpanStart(SpanStartEvent {
+                    data: Some(buck2_data::span_start_event::Data::Fake(FakeStart {
+                        caramba: caramba.to_owned(),
+                    })),
+                }),
+            ))
+        };
+
+        let mut spans = BuckEventSpanTracker::new();
+        spans.start_at(&fake("foo"), fake_time(&tick, 3))?;
+        spans.start_at(&fake("bar"), fake_time(&tick, 1))?;
+
+        let mut state = super_console_state_for_test(
+            spans,
+            ActionStats::default(),
+            tick,
+            fake_time_speed(),
+            SuperConsoleConfig {
+                max_lines: 5,
+                ..Default::default()
+            },
+        );
+
+        let draw = |state: &SuperConsoleState| -> anyhow::Result<Vec<String>> {
+            let output = TimedList::new(&CUTOFFS, "test", state).draw(
+                Dimensions {
+                    width: 60,
+                    height: 10,
+                },
+                DrawMode::Normal,
+            )?;
+            Ok(output
+                .iter()
+                .map(|line| line.fmt_for_test().to_string())
+                .collect())
+        };
+        let press = |state: &mut SuperConsoleState, key| {
+            state
+                .drill_down
+                .handle_key(key, state.simple_console.observer().spans())
+        };
+
+        // Keys that are not for navigation are left to the other toggles.
+        assert!(!press(&mut state, Key::Char('d')));
+        assert!(!press(&mut state, Key::Right));
+
+        // The first press opens the list, the second one moves the selection.
+        assert!(press(&mut state, Key::Down));
+        assert!(press(&mut state, Key::Down));
+        let lines = draw(&state)?;
+        assert_eq!(lines.len(), 5, "{:#?}", lines);
+        assert!(!lines[2].contains("reverse"), "{:#?}", lines);
+        assert!(lines[3].contains("reverse"), "{:#?}", lines);
+        assert!(
+            lines[3].contains("bar -- speak of the devil"),
+            "{:#?}",
+            lines
+        );
+        assert!(lines[4].contains("Left: back"), "{:#?}", lines);
+
+        assert!(press(&mut state, Key::Right));
+        let lines = draw(&state)?;
+        assert!(
+            lines[2].contains("bar -- speak of the devil"),
+            "{:#?}",
+            lines
+        );
+        assert!(
+            lines.last().unwrap().contains("back to the list"),
+            "{:#?}",
+            lines
+        );
+
+        // Moving the selection shows the other span.
+        assert!(press(&mut state, Key::Up));
+        let lines = draw(&state)?;
+        assert!(
+            lines[2].contains("foo -- speak of the devil"),
+            "{:#?}",
+            lines
+        );
+
+        assert!(press(&mut state, Key::Left));
+        assert!(press(&mut state, Key::Left));
+        assert!(!state.drill_down.is_active());
+        let lines = draw(&state)?;
+        assert_eq!(lines.len(), 4, "{:#?}", lines);
+
+        Ok(())
+    }
+
     #[test]
     fn test_children() -> anyhow::Result<()> {
         let tick = Tick::now();
@@ -0,0 +1,28 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fs::File;
+
+use once_cell::sync::Lazy;
+
+static BINARY_ID: Lazy<Option<String>> = Lazy::new(|| {
+    if let Some(revision) = buck2_build_info::revision() {
+        return Some(revision.to_owned());
+    }
+    let mut file = File::open(std::env::current_exe().ok()?).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_hex().to_string())
+});
+
+/// Identifies the running buck2 binary, for data written by one daemon and read by another:
+/// the revision it was built at, or else a hash of the executable. `None` if neither is known.
+pub fn binary_id() -> Option<&'static str> {
+    BINARY_ID.as_deref()
+}
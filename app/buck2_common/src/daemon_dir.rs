@@ -37,4 +37,14 @@ impl DaemonDir {
     pub fn buckd_pid(&self) -> AbsNormPathBuf {
         self.path.join(FileName::new("buckd.pid").unwrap())
     }
+
+    /// Directory where evaluated `.bzl` modules are cached.
+    pub fn bzl_module_cache(&self) -> AbsNormPathBuf {
+        self.path.join(FileName::new("bzl_module_cache").unwrap())
+    }
+
+    /// Path to the DICE snapshot written when the daemon exits.
+    pub fn dice_snapshot(&self) -> AbsNormPathBuf {
+        self.path.join(FileName::new("dice_snapshot").unwrap())
+    }
 }
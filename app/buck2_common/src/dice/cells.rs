@@ -16,6 +16,7 @@ use derive_more::Display;
 use dice::DiceComputations;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
+use dice::PersistentKeys;
 use dupe::Dupe;
 
 #[async_trait]
@@ -69,3 +70,8 @@ impl SetCellResolver for DiceTransactionUpdater {
         Ok(self.changed_to(vec![(CellResolverKey, None)])?)
     }
 }
+
+/// The cell resolver is supplied by the daemon when restoring, see `crate::dice::persistence`.
+pub(crate) fn register_persistent_keys(keys: &mut PersistentKeys, cells: &CellResolver) {
+    keys.register_supplied("CellResolverKey", [(CellResolverKey, Some(cells.dupe()))]);
+}
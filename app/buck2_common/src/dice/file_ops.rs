@@ -30,6 +30,8 @@ use derive_more::Display;
 use dice::DiceComputations;
 use dice::DiceTransactionUpdater;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentKeys;
 use dupe::Dupe;
 use gazebo::cmp::PartialEqAny;
 use more_futures::cancellation::CancellationContext;
@@ -38,7 +40,9 @@ use crate::dice::cells::HasCellResolver;
 use crate::dice::data::HasIoProvider;
 use crate::dice::file_ops::keys::FileOpsKey;
 use crate::dice::file_ops::keys::FileOpsValue;
+use crate::dice::persistence::PersistedCellPath;
 use crate::file_ops::FileOps;
+use crate::file_ops::FileType;
 use crate::file_ops::RawDirEntry;
 use crate::file_ops::RawPathMetadata;
 use crate::file_ops::ReadDirOutput;
@@ -46,6 +50,7 @@ use crate::file_ops::SimpleDirEntry;
 use crate::ignores::all_cells::AllCellIgnores;
 use crate::ignores::all_cells::HasAllCellIgnores;
 use crate::io::IoProvider;
+use crate::legacy_configs::LegacyBuckConfigs;
 use crate::result::SharedResult;
 use crate::result::ToSharedResultExt;
 use crate::result::ToUnsharedResultExt;
@@ -95,144 +100,143 @@ pub mod keys {
     pub struct FileOpsValue(#[allocative(skip)] pub Arc<dyn FileOps>);
 }
 
-async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn FileOps>> {
-    #[derive(Clone, Dupe, Derivative, Allocative)]
-    #[derivative(PartialEq)]
-    struct DiceFileOpsDelegate {
-        // Safe to ignore because `io` does not change during the lifetime of the daemon.
-        #[derivative(PartialEq = "ignore")]
-        io: Arc<dyn IoProvider>,
-        cells: CellResolver,
-        ignores: Arc<AllCellIgnores>,
-    }
-
-    impl DiceFileOpsDelegate {
-        fn resolve(&self, path: CellPathRef) -> anyhow::Result<ProjectRelativePathBuf> {
-            let cell_root = self.resolve_cell_root(path.cell())?;
-            Ok(cell_root.project_relative_path().join(path.path()))
-        }
+#[derive(Clone, Dupe, Derivative, Allocative)]
+#[derivative(PartialEq)]
+struct DiceFileOpsDelegate {
+    // Safe to ignore because `io` does not change during the lifetime of the daemon.
+    #[derivative(PartialEq = "ignore")]
+    io: Arc<dyn IoProvider>,
+    cells: CellResolver,
+    ignores: Arc<AllCellIgnores>,
+}
 
-        fn resolve_cell_root(&self, cell: CellName) -> anyhow::Result<CellRootPathBuf> {
-            Ok(self.cells.get(cell).unwrap().path().to_buf())
-        }
+impl DiceFileOpsDelegate {
+    fn resolve(&self, path: CellPathRef) -> anyhow::Result<ProjectRelativePathBuf> {
+        let cell_root = self.resolve_cell_root(path.cell())?;
+        Ok(cell_root.project_relative_path().join(path.path()))
+    }
 
-        fn get_cell_path(&self, path: &ProjectRelativePath) -> anyhow::Result<CellPath> {
-            self.cells.get_cell_path(path)
-        }
+    fn resolve_cell_root(&self, cell: CellName) -> anyhow::Result<CellRootPathBuf> {
+        Ok(self.cells.get(cell).unwrap().path().to_buf())
+    }
 
-        fn io_provider(&self) -> &dyn IoProvider {
-            self.io.as_ref()
-        }
+    fn get_cell_path(&self, path: &ProjectRelativePath) -> anyhow::Result<CellPath> {
+        self.cells.get_cell_path(path)
     }
 
-    #[async_trait]
-    impl FileOps for DiceFileOpsDelegate {
-        async fn read_file_if_exists(
-            &self,
-            path: CellPathRef<'async_trait>,
-        ) -> anyhow::Result<Option<String>> {
-            // TODO(cjhopman): error on ignored paths, maybe.
-            let project_path = self.resolve(path)?;
-            self.io_provider().read_file_if_exists(project_path).await
-        }
+    fn io_provider(&self) -> &dyn IoProvider {
+        self.io.as_ref()
+    }
+}
 
-        async fn read_dir(&self, path: CellPathRef<'async_trait>) -> anyhow::Result<ReadDirOutput> {
-            // TODO(cjhopman): This should also probably verify that the parent chain is not ignored.
-            self.ignores
-                .check_ignored(path.cell(), UncheckedCellRelativePath::new(path.path()))?
-                .into_result()
-                .with_context(|| format!("Error checking whether dir `{}` is ignored", path))?;
-
-            let project_path = self.resolve(path)?;
-            let mut entries = self
-                .io_provider()
-                .read_dir(project_path)
-                .await
-                .with_context(|| format!("Error listing dir `{}`", path))?;
-
-            // Make sure entries are deterministic, since read_dir isn't.
-            entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
-
-            let is_ignored = |file_name: &str| {
-                let mut cell_relative_path_buf;
-                let cell_relative_path: &str = if path.path().is_empty() {
-                    file_name
-                } else {
-                    cell_relative_path_buf =
-                        String::with_capacity(path.path().as_str().len() + 1 + file_name.len());
-                    cell_relative_path_buf.push_str(path.path().as_str());
-                    cell_relative_path_buf.push('/');
-                    cell_relative_path_buf.push_str(file_name);
-                    &cell_relative_path_buf
-                };
+#[async_trait]
+impl FileOps for DiceFileOpsDelegate {
+    async fn read_file_if_exists(
+        &self,
+        path: CellPathRef<'async_trait>,
+    ) -> anyhow::Result<Option<String>> {
+        // TODO(cjhopman): error on ignored paths, maybe.
+        let project_path = self.resolve(path)?;
+        self.io_provider().read_file_if_exists(project_path).await
+    }
 
-                let cell_relative_path =
-                    UncheckedCellRelativePath::unchecked_new(cell_relative_path);
-                let is_ignored = self
-                    .ignores
-                    .check_ignored(path.cell(), cell_relative_path)?
-                    .is_ignored();
-                anyhow::Ok(is_ignored)
+    async fn read_dir(&self, path: CellPathRef<'async_trait>) -> anyhow::Result<ReadDirOutput> {
+        // TODO(cjhopman): This should also probably verify that the parent chain is not ignored.
+        self.ignores
+            .check_ignored(path.cell(), UncheckedCellRelativePath::new(path.path()))?
+            .into_result()
+            .with_context(|| format!("Error checking whether dir `{}` is ignored", path))?;
+
+        let project_path = self.resolve(path)?;
+        let mut entries = self
+            .io_provider()
+            .read_dir(project_path)
+            .await
+            .with_context(|| format!("Error listing dir `{}`", path))?;
+
+        // Make sure entries are deterministic, since read_dir isn't.
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        let is_ignored = |file_name: &str| {
+            let mut cell_relative_path_buf;
+            let cell_relative_path: &str = if path.path().is_empty() {
+                file_name
+            } else {
+                cell_relative_path_buf =
+                    String::with_capacity(path.path().as_str().len() + 1 + file_name.len());
+                cell_relative_path_buf.push_str(path.path().as_str());
+                cell_relative_path_buf.push('/');
+                cell_relative_path_buf.push_str(file_name);
+                &cell_relative_path_buf
             };
 
-            // Filter out any entries that are ignored.
-            let mut included_entries = Vec::new();
-            for e in entries {
-                let RawDirEntry {
-                    file_type,
+            let cell_relative_path = UncheckedCellRelativePath::unchecked_new(cell_relative_path);
+            let is_ignored = self
+                .ignores
+                .check_ignored(path.cell(), cell_relative_path)?
+                .is_ignored();
+            anyhow::Ok(is_ignored)
+        };
+
+        // Filter out any entries that are ignored.
+        let mut included_entries = Vec::new();
+        for e in entries {
+            let RawDirEntry {
+                file_type,
+                file_name,
+            } = e;
+
+            if !is_ignored(&file_name)? {
+                let file_name = match FileNameBuf::try_from_or_get_back(file_name) {
+                    Ok(file_name) => file_name,
+                    Err(file_name) => {
+                        console_message(format!(
+                            "File name `{file_name}` is not valid. \
+                                Add the path to `project.ignore` to mute this message",
+                        ));
+                        continue;
+                    }
+                };
+                included_entries.push(SimpleDirEntry {
                     file_name,
-                } = e;
-
-                if !is_ignored(&file_name)? {
-                    let file_name = match FileNameBuf::try_from_or_get_back(file_name) {
-                        Ok(file_name) => file_name,
-                        Err(file_name) => {
-                            console_message(format!(
-                                "File name `{file_name}` is not valid. \
-                                    Add the path to `project.ignore` to mute this message",
-                            ));
-                            continue;
-                        }
-                    };
-                    included_entries.push(SimpleDirEntry {
-                        file_name,
-                        file_type,
-                    });
-                }
+                    file_type,
+                });
             }
-
-            Ok(ReadDirOutput {
-                included: included_entries.into(),
-            })
         }
 
-        async fn read_path_metadata_if_exists(
-            &self,
-            path: CellPathRef<'async_trait>,
-        ) -> anyhow::Result<Option<RawPathMetadata>> {
-            let project_path = self.resolve(path)?;
-
-            let res = self
-                .io_provider()
-                .read_path_metadata_if_exists(project_path)
-                .await
-                .with_context(|| format!("Error accessing metadata for path `{}`", path))?;
-            res.map(|meta| meta.try_map(|path| Ok(Arc::new(self.get_cell_path(&path)?))))
-                .transpose()
-        }
+        Ok(ReadDirOutput {
+            included: included_entries.into(),
+        })
+    }
 
-        async fn is_ignored(&self, path: CellPathRef<'async_trait>) -> anyhow::Result<bool> {
-            Ok(self
-                .ignores
-                .check_ignored(path.cell(), UncheckedCellRelativePath::new(path.path()))?
-                .is_ignored())
-        }
+    async fn read_path_metadata_if_exists(
+        &self,
+        path: CellPathRef<'async_trait>,
+    ) -> anyhow::Result<Option<RawPathMetadata>> {
+        let project_path = self.resolve(path)?;
 
-        fn eq_token(&self) -> PartialEqAny {
-            PartialEqAny::new(self)
-        }
+        let res = self
+            .io_provider()
+            .read_path_metadata_if_exists(project_path)
+            .await
+            .with_context(|| format!("Error accessing metadata for path `{}`", path))?;
+        res.map(|meta| meta.try_map(|path| Ok(Arc::new(self.get_cell_path(&path)?))))
+            .transpose()
+    }
+
+    async fn is_ignored(&self, path: CellPathRef<'async_trait>) -> anyhow::Result<bool> {
+        Ok(self
+            .ignores
+            .check_ignored(path.cell(), UncheckedCellRelativePath::new(path.path()))?
+            .is_ignored())
     }
 
+    fn eq_token(&self) -> PartialEqAny {
+        PartialEqAny::new(self)
+    }
+}
+
+async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn FileOps>> {
     #[async_trait]
     impl Key for FileOpsKey {
         type Value = SharedResult<FileOpsValue>;
@@ -399,6 +403,51 @@ impl Key for ReadDirKey {
     }
 }
 
+impl PersistentKey for ReadDirKey {
+    const NAME: &'static str = "ReadDirKey";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(&PersistedCellPath::new(
+            self.0.as_ref(),
+        ))?)
+    }
+
+    fn deserialize_key(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(ReadDirKey(
+            bincode::deserialize::<PersistedCellPath>(data)?.into_cell_path()?,
+        ))
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        let output = match value {
+            Ok(output) => output,
+            Err(_) => return Ok(None),
+        };
+        let entries: Vec<(&str, &FileType)> = output
+            .included
+            .iter()
+            .map(|e| (e.file_name.as_str(), &e.file_type))
+            .collect();
+        Ok(Some(bincode::serialize(&entries)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        let entries: Vec<(String, FileType)> = bincode::deserialize(data)?;
+        let included = entries
+            .into_iter()
+            .map(|(file_name, file_type)| {
+                Ok(SimpleDirEntry {
+                    file_name: FileNameBuf::try_from(file_name)?,
+                    file_type,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Ok(ReadDirOutput {
+            included: included.into(),
+        }))
+    }
+}
+
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
 struct PathMetadataKey(CellPath);
 
@@ -485,6 +534,33 @@ impl<'c> FileOps for DiceFileOps<'c> {
     }
 }
 
+/// Dir listings are revalidated after restoring, and the file ops they are read with are
+/// supplied by the daemon, see `crate::dice::persistence`.
+pub(crate) fn register_persistent_keys(
+    keys: &mut PersistentKeys,
+    io: Arc<dyn IoProvider>,
+    cells: &CellResolver,
+    configs: &LegacyBuckConfigs,
+) -> anyhow::Result<()> {
+    let ignores = AllCellIgnores::new(cells, |cell_name| {
+        Ok(configs
+            .get(cell_name)?
+            .get("project", "ignore")
+            .map(Arc::from))
+    })?;
+    let file_ops = DiceFileOpsDelegate {
+        io,
+        cells: cells.dupe(),
+        ignores: Arc::new(ignores),
+    };
+    keys.register_supplied(
+        "FileOpsKey",
+        [(FileOpsKey(), Ok(FileOpsValue(Arc::new(file_ops))))],
+    );
+    keys.register_revalidated::<ReadDirKey>();
+    Ok(())
+}
+
 pub mod testing {
     pub use super::keys::FileOpsKey;
 }
@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The keys whose values are saved when the daemon exits and restored by the next daemon, see
+//! `Dice::snapshot`.
+//!
+//! Dir listings, and the package listings computed from them, are persisted. The cells,
+//! buckconfigs and the file ops they are read with are supplied by the daemon instead: a
+//! snapshot must only be restored with the cells and buckconfigs it was taken with, which
+//! `fingerprint` identifies. Dir listings are recomputed when next requested, since the file
+//! system may have changed while no daemon was watching it.
+
+use std::sync::Arc;
+
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::cells::cell_path::CellPathRef;
+use buck2_core::cells::name::CellName;
+use buck2_core::cells::paths::CellRelativePathBuf;
+use buck2_core::cells::CellResolver;
+use dice::PersistentKeys;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::binary_id::binary_id;
+use crate::io::IoProvider;
+use crate::legacy_configs::LegacyBuckConfigs;
+
+/// The keys to snapshot and restore, given the cells and buckconfigs of the daemon.
+pub fn persistent_keys(
+    io: Arc<dyn IoProvider>,
+    cells: &CellResolver,
+    configs: &LegacyBuckConfigs,
+) -> anyhow::Result<PersistentKeys> {
+    let mut keys = PersistentKeys::new();
+    crate::dice::cells::register_persistent_keys(&mut keys, cells);
+    crate::legacy_configs::dice::register_persistent_keys(&mut keys, configs);
+    crate::dice::file_ops::register_persistent_keys(&mut keys, io, cells, configs)?;
+    crate::package_listing::dice::register_persistent_keys(&mut keys);
+    Ok(keys)
+}
+
+/// Identifies the binary, cells and buckconfigs a snapshot is taken with, or `None` if the
+/// binary cannot be identified, in which case nothing should be persisted.
+pub fn fingerprint(cells: &CellResolver, configs: &LegacyBuckConfigs) -> Option<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(binary_id()?.as_bytes());
+
+    let mut cells: Vec<_> = cells.cells().collect();
+    cells.sort_by_key(|(name, _)| *name);
+    for (name, instance) in cells {
+        hasher.update(
+            format!(
+                "\0cell\0{}\0{}",
+                name,
+                instance.path().project_relative_path()
+            )
+            .as_bytes(),
+        );
+    }
+    let mut configs: Vec<_> = configs.iter().collect();
+    configs.sort_by_key(|(cell, _)| *cell);
+    for (cell, config) in configs {
+        for (section, values) in config.iter() {
+            for (key, value) in values {
+                hasher.update(
+                    format!("\0config\0{}\0{}\0{}\0{}", cell, section, key, value).as_bytes(),
+                );
+            }
+        }
+    }
+    Some(hasher.finalize().to_hex().to_string())
+}
+
+/// A `CellPath` as it is persisted.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PersistedCellPath {
+    cell: String,
+    path: String,
+}
+
+impl PersistedCellPath {
+    pub(crate) fn new(path: CellPathRef) -> Self {
+        Self {
+            cell: path.cell().as_str().to_owned(),
+            path: path.path().as_str().to_owned(),
+        }
+    }
+
+    pub(crate) fn into_cell_path(self) -> anyhow::Result<CellPath> {
+        Ok(CellPath::new(
+            CellName::unchecked_new(&self.cell)?,
+            CellRelativePathBuf::try_from(self.path)?,
+        ))
+    }
+}
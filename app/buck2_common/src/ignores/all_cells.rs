@@ -14,6 +14,7 @@ use allocative::Allocative;
 use async_trait::async_trait;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::unchecked_cell_rel_path::UncheckedCellRelativePath;
+use buck2_core::cells::CellResolver;
 use dice::DiceComputations;
 use itertools::Itertools;
 
@@ -29,6 +30,28 @@ pub(crate) struct AllCellIgnores {
 }
 
 impl AllCellIgnores {
+    /// The ignores of each cell, given the `project.ignore` config of a cell.
+    pub(crate) fn new(
+        cells: &CellResolver,
+        mut ignore_spec: impl FnMut(CellName) -> anyhow::Result<Option<Arc<str>>>,
+    ) -> anyhow::Result<Self> {
+        let mut ignores = HashMap::new();
+
+        for (cell_name, instance) in cells.cells() {
+            let ignore_spec = ignore_spec(cell_name)?;
+            let ignore_spec = ignore_spec.as_ref().map_or("", |s| &**s);
+
+            let cell_ignores = FileIgnores::new_for_interpreter(
+                ignore_spec,
+                instance.nested_cells().clone(),
+                cells.is_root_cell(cell_name),
+            )?;
+            ignores.insert(cell_name, cell_ignores);
+        }
+
+        Ok(AllCellIgnores { ignores })
+    }
+
     pub(crate) fn check_ignored(
         &self,
         cell: CellName,
@@ -59,21 +82,8 @@ impl HasAllCellIgnores for DiceComputations {
         let cells = self.get_cell_resolver().await?;
         let configs = self.get_legacy_configs_on_dice().await?;
 
-        let mut ignores = HashMap::new();
-
-        for (cell_name, instance) in cells.cells() {
-            let config = configs.get(cell_name).unwrap();
-            let ignore_spec = config.get("project", "ignore")?;
-            let ignore_spec = ignore_spec.as_ref().map_or("", |s| &**s);
-
-            let cell_ignores = FileIgnores::new_for_interpreter(
-                ignore_spec,
-                instance.nested_cells().clone(),
-                cells.is_root_cell(cell_name),
-            )?;
-            ignores.insert(cell_name, cell_ignores);
-        }
-
-        Ok(Arc::new(AllCellIgnores { ignores }))
+        Ok(Arc::new(AllCellIgnores::new(&cells, |cell_name| {
+            configs.get(cell_name).unwrap().get("project", "ignore")
+        })?))
     }
 }
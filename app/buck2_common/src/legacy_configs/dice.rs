@@ -23,6 +23,7 @@ use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
 use dice::Key;
 use dice::OpaqueValue;
+use dice::PersistentKeys;
 use dice::ProjectionKey;
 use dupe::Dupe;
 use dupe::OptionDupedExt;
@@ -392,6 +393,14 @@ impl SetLegacyConfigs for DiceTransactionUpdater {
     }
 }
 
+/// The buckconfigs are supplied by the daemon when restoring, see `crate::dice::persistence`.
+pub(crate) fn register_persistent_keys(keys: &mut PersistentKeys, configs: &LegacyBuckConfigs) {
+    keys.register_supplied(
+        "LegacyBuckConfigKey",
+        [(LegacyBuckConfigKey, Some(configs.dupe()))],
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use buck2_core::cells::name::CellName;
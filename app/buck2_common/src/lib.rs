@@ -24,6 +24,7 @@
 #[macro_use]
 extern crate maplit;
 
+pub mod binary_id;
 pub mod buckd_connection;
 pub mod cas_digest;
 pub mod client_utils;
@@ -15,17 +15,91 @@ use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::package::PackageLabel;
 use dice::DiceComputations;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentKeys;
 use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 
 use crate::dice::cells::HasCellResolver;
 use crate::dice::file_ops::HasFileOps;
+use crate::dice::persistence::PersistedCellPath;
 use crate::package_listing::interpreter::InterpreterPackageListingResolver;
 use crate::package_listing::listing::PackageListing;
 use crate::package_listing::resolver::PackageListingResolver;
 use crate::result::SharedResult;
 use crate::result::ToUnsharedResultExt;
 
+#[derive(
+    Clone,
+    Dupe,
+    derive_more::Display,
+    Debug,
+    Eq,
+    Hash,
+    PartialEq,
+    Allocative
+)]
+struct PackageListingKey(PackageLabel);
+
+#[async_trait]
+impl Key for PackageListingKey {
+    type Value = SharedResult<PackageListing>;
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let cell_resolver = ctx.get_cell_resolver().await?;
+        let file_ops = ctx.file_ops();
+        InterpreterPackageListingResolver::new(cell_resolver, Arc::new(file_ops))
+            .resolve(self.0.dupe())
+            .await
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+impl PersistentKey for PackageListingKey {
+    const NAME: &'static str = "PackageListingKey";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(&PersistedCellPath::new(
+            self.0.as_cell_path(),
+        ))?)
+    }
+
+    fn deserialize_key(data: &[u8]) -> anyhow::Result<Self> {
+        let path = bincode::deserialize::<PersistedCellPath>(data)?.into_cell_path()?;
+        Ok(PackageListingKey(PackageLabel::from_cell_path(
+            path.as_ref(),
+        )))
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        match value {
+            Ok(listing) => Ok(Some(bincode::serialize(&listing.to_persisted())?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(Ok(PackageListing::from_persisted(bincode::deserialize(
+            data,
+        )?)?))
+    }
+}
+
+/// Package listings only depend on the cell resolver and on dir listings, which are persisted
+/// too, see `crate::dice::persistence`.
+pub(crate) fn register_persistent_keys(keys: &mut PersistentKeys) {
+    keys.register::<PackageListingKey>();
+}
+
 #[async_trait]
 pub trait HasPackageListingResolver<'c> {
     type PL: PackageListingResolver + 'c;
@@ -60,41 +134,6 @@ pub struct DicePackageListingResolver<'compute>(&'compute DiceComputations);
 #[async_trait]
 impl<'c> PackageListingResolver for DicePackageListingResolver<'c> {
     async fn resolve(&self, package: PackageLabel) -> SharedResult<PackageListing> {
-        #[derive(
-            Clone,
-            Dupe,
-            derive_more::Display,
-            Debug,
-            Eq,
-            Hash,
-            PartialEq,
-            Allocative
-        )]
-        struct PackageListingKey(PackageLabel);
-
-        #[async_trait]
-        impl Key for PackageListingKey {
-            type Value = SharedResult<PackageListing>;
-            async fn compute(
-                &self,
-                ctx: &DiceComputations,
-                _cancellations: &CancellationContext,
-            ) -> Self::Value {
-                let cell_resolver = ctx.get_cell_resolver().await?;
-                let file_ops = ctx.file_ops();
-                InterpreterPackageListingResolver::new(cell_resolver, Arc::new(file_ops))
-                    .resolve(self.0.dupe())
-                    .await
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
-
         self.0.compute(&PackageListingKey(package.dupe())).await?
     }
 
@@ -17,6 +17,8 @@ use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_core::package::package_relative_path::PackageRelativePath;
 use buck2_util::arc_str::ArcS;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::package_listing::file_listing::PackageFileListing;
 
@@ -33,6 +35,15 @@ struct PackageListingData {
     buildfile: FileNameBuf,
 }
 
+/// A `PackageListing` as it is persisted, see `crate::dice::persistence`.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PersistedPackageListing {
+    files: Vec<String>,
+    directories: Vec<String>,
+    subpackages: Vec<String>,
+    buildfile: String,
+}
+
 impl PackageListing {
     pub(crate) fn new(
         files: SortedSet<ArcS<PackageRelativePath>>,
@@ -99,6 +110,34 @@ impl PackageListing {
     pub fn buildfile(&self) -> &FileName {
         &self.listing.buildfile
     }
+
+    pub(crate) fn to_persisted(&self) -> PersistedPackageListing {
+        fn paths<'a>(paths: impl Iterator<Item = &'a ArcS<PackageRelativePath>>) -> Vec<String> {
+            paths.map(|p| p.as_str().to_owned()).collect()
+        }
+        PersistedPackageListing {
+            files: paths(self.listing.files.files.iter()),
+            directories: paths(self.listing.directories.iter()),
+            subpackages: paths(self.listing.subpackages.iter()),
+            buildfile: self.listing.buildfile.as_str().to_owned(),
+        }
+    }
+
+    pub(crate) fn from_persisted(listing: PersistedPackageListing) -> anyhow::Result<Self> {
+        let paths = |paths: Vec<String>| {
+            paths
+                .iter()
+                .map(|p| Ok(PackageRelativePath::new(p)?.to_arc()))
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map(SortedVec::from)
+        };
+        Ok(Self::new(
+            SortedSet::from(paths(listing.files)?),
+            SortedSet::from(paths(listing.directories)?),
+            paths(listing.subpackages)?,
+            FileNameBuf::try_from(listing.buildfile)?,
+        ))
+    }
 }
 
 pub mod testing {
@@ -271,6 +271,21 @@ pub fn copy<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(from: P, to: Q) -> anyhow::Res
     })
 }
 
+pub fn hardlink<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(original: P, link: Q) -> anyhow::Result<()> {
+    let _guard = IoCounterKey::Hardlink.guard();
+    fs::hard_link(
+        original.as_ref().as_maybe_relativized(),
+        link.as_ref().as_maybe_relativized(),
+    )
+    .with_context(|| {
+        format!(
+            "hardlink(original={}, link={})",
+            P::as_ref(&original).display(),
+            Q::as_ref(&link).display()
+        )
+    })
+}
+
 pub fn read_link<P: AsRef<AbsPath>>(path: P) -> anyhow::Result<PathBuf> {
     let _guard = IoCounterKey::ReadLink.guard();
     fs::read_link(path.as_ref().as_maybe_relativized())
@@ -0,0 +1,284 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use buck2_events::span::SpanId;
+use buck2_events::BuckEvent;
+
+use crate::display;
+
+/// What we know about a running action beyond what the span tracker keeps: the span tracker
+/// forgets executor stages once they end, and doesn't look at their contents.
+#[derive(Debug, Default)]
+pub struct ActionDetails {
+    /// The executor stages that already ended, in order, with how long they took.
+    pub finished_stages: Vec<(&'static str, Duration)>,
+    /// The local command, once the action started executing it.
+    pub command: Option<buck2_data::LocalCommand>,
+    /// The latest stderr reported for the local command while it is running.
+    pub stderr_tail: Option<buck2_data::LocalCommandStderrTail>,
+}
+
+struct RunningStage {
+    action: SpanId,
+    label: &'static str,
+    start: Instant,
+}
+
+/// Tracks the `ActionDetails` of the actions that are currently running, so that a single action
+/// can be shown in detail.
+#[derive(Default)]
+pub struct ActionDetailsState {
+    actions: HashMap<SpanId, ActionDetails>,
+    stages: HashMap<SpanId, RunningStage>,
+}
+
+impl ActionDetailsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, action: SpanId) -> Option<&ActionDetails> {
+        self.actions.get(&action)
+    }
+
+    /// The action that contains the span `id`, if it's one we track.
+    fn action_of(&self, id: SpanId) -> Option<SpanId> {
+        if self.actions.contains_key(&id) {
+            Some(id)
+        } else {
+            self.stages.get(&id).map(|stage| stage.action)
+        }
+    }
+
+    pub fn handle_event(&mut self, receive_time: Instant, event: &Arc<BuckEvent>) {
+        use buck2_data::buck_event::Data;
+
+        let (span_id, parent_id) = (event.span_id(), event.parent_id());
+
+        match event.data() {
+            Data::SpanStart(start) => {
+                use buck2_data::span_start_event::Data;
+
+                match (&start.data, span_id) {
+                    (Some(Data::ActionExecution(..)), Some(span_id)) => {
+                        self.actions.insert(span_id, ActionDetails::default());
+                    }
+                    (Some(Data::ExecutorStage(stage)), Some(span_id)) => {
+                        let Some(action) = parent_id.and_then(|p| self.action_of(p)) else {
+                            return;
+                        };
+                        let Some(stage) = stage.stage.as_ref() else {
+                            return;
+                        };
+
+                        if let Some(command) = local_command(stage) {
+                            if let Some(details) = self.actions.get_mut(&action) {
+                                details.command = Some(command.clone());
+                            }
+                        }
+
+                        self.stages.insert(
+                            span_id,
+                            RunningStage {
+                                action,
+                                label: display::display_executor_stage(stage).unwrap_or("unknown"),
+                                start: receive_time,
+                            },
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            Data::SpanEnd(..) => {
+                let Some(span_id) = span_id else {
+                    return;
+                };
+
+                if self.actions.remove(&span_id).is_some() {
+                    self.stages.retain(|_, stage| stage.action != span_id);
+                } else if let Some(stage) = self.stages.remove(&span_id) {
+                    if let Some(details) = self.actions.get_mut(&stage.action) {
+                        details.finished_stages.push((
+                            stage.label,
+                            receive_time.saturating_duration_since(stage.start),
+                        ));
+                    }
+                }
+            }
+            Data::Instant(instant) => {
+                use buck2_data::instant_event::Data;
+
+                if let Some(Data::LocalCommandStderrTail(tail)) = &instant.data {
+                    let action = parent_id.and_then(|p| self.action_of(p));
+                    if let Some(details) = action.and_then(|a| self.actions.get_mut(&a)) {
+                        details.stderr_tail = Some(tail.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn local_command(
+    stage: &buck2_data::executor_stage_start::Stage,
+) -> Option<&buck2_data::LocalCommand> {
+    use buck2_data::executor_stage_start::Stage;
+    use buck2_data::local_stage::Stage as LocalStage;
+
+    match stage {
+        Stage::Local(buck2_data::LocalStage {
+            stage: Some(LocalStage::Execute(execute)),
+        }) => execute.command.as_ref(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use buck2_data::executor_stage_start::Stage;
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn event(
+        span_id: Option<SpanId>,
+        parent_id: Option<SpanId>,
+        data: buck2_data::buck_event::Data,
+    ) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::now(),
+            TraceId::null(),
+            span_id,
+            parent_id,
+            data,
+        ))
+    }
+
+    fn start(
+        span_id: SpanId,
+        parent_id: Option<SpanId>,
+        data: impl Into<buck2_data::span_start_event::Data>,
+    ) -> Arc<BuckEvent> {
+        event(
+            Some(span_id),
+            parent_id,
+            buck2_data::SpanStartEvent {
+                data: Some(data.into()),
+            }
+            .into(),
+        )
+    }
+
+    fn end(
+        span_id: SpanId,
+        parent_id: Option<SpanId>,
+        data: impl Into<buck2_data::span_end_event::Data>,
+    ) -> Arc<BuckEvent> {
+        event(
+            Some(span_id),
+            parent_id,
+            buck2_data::SpanEndEvent {
+                data: Some(data.into()),
+                ..Default::default()
+            }
+            .into(),
+        )
+    }
+
+    fn stage(stage: impl Into<buck2_data::local_stage::Stage>) -> buck2_data::ExecutorStageStart {
+        buck2_data::ExecutorStageStart {
+            stage: Some(Stage::Local(buck2_data::LocalStage {
+                stage: Some(stage.into()),
+            })),
+        }
+    }
+
+    #[test]
+    fn test_action_details() {
+        let t0 = Instant::now();
+        let mut state = ActionDetailsState::new();
+
+        let action = SpanId::new();
+        let prepare = SpanId::new();
+        let execute = SpanId::new();
+
+        state.handle_event(
+            t0,
+            &start(action, None, buck2_data::ActionExecutionStart::default()),
+        );
+        state.handle_event(
+            t0,
+            &start(
+                prepare,
+                Some(action),
+                stage(buck2_data::LocalPrepareOutputDirs {}),
+            ),
+        );
+        state.handle_event(
+            t0 + Duration::from_secs(2),
+            &end(prepare, Some(action), buck2_data::ExecutorStageEnd {}),
+        );
+        state.handle_event(
+            t0 + Duration::from_secs(2),
+            &start(
+                execute,
+                Some(action),
+                stage(buck2_data::LocalExecute {
+                    command: Some(buck2_data::LocalCommand {
+                        argv: vec!["cc".to_owned(), "-c".to_owned()],
+                        ..Default::default()
+                    }),
+                }),
+            ),
+        );
+        state.handle_event(
+            t0 + Duration::from_secs(3),
+            &event(
+                None,
+                Some(execute),
+                buck2_data::InstantEvent {
+                    data: Some(
+                        buck2_data::LocalCommandStderrTail {
+                            tail: "warning: unused".to_owned(),
+                            truncated: false,
+                        }
+                        .into(),
+                    ),
+                }
+                .into(),
+            ),
+        );
+
+        let details = state.get(action).unwrap();
+        assert_eq!(
+            details.finished_stages,
+            vec![("local_prepare_outputs", Duration::from_secs(2))]
+        );
+        assert_eq!(details.command.as_ref().unwrap().argv, vec!["cc", "-c"]);
+        assert_eq!(
+            details.stderr_tail.as_ref().unwrap().tail,
+            "warning: unused"
+        );
+
+        state.handle_event(
+            t0 + Duration::from_secs(4),
+            &end(action, None, buck2_data::ActionExecutionEnd::default()),
+        );
+        assert!(state.get(action).is_none());
+        assert!(state.stages.is_empty());
+    }
+}
@@ -578,6 +578,7 @@ fn failure_reason_for_command_execution(
 
     let locality = match command.command {
         Some(Command::RemoteCommand(..)) => "Remote ",
+        Some(Command::LocalActionCacheCommand(..)) => "Local action cache ",
         Some(Command::LocalCommand(..)) | Some(Command::OmittedLocalCommand(..)) => "Local ",
         None => "",
     };
@@ -14,6 +14,7 @@ use anyhow::Context;
 use buck2_events::BuckEvent;
 use buck2_wrapper_common::invocation_id::TraceId;
 
+use crate::action_details::ActionDetailsState;
 use crate::action_stats::ActionStats;
 use crate::debug_events::DebugEventsState;
 use crate::dice_state::DiceState;
@@ -175,6 +176,7 @@ pub trait EventObserverExtra: Send {
 pub struct DebugEventObserverExtra {
     dice_state: DiceState,
     debug_events: DebugEventsState,
+    action_details: ActionDetailsState,
 }
 
 impl EventObserverExtra for DebugEventObserverExtra {
@@ -182,11 +184,13 @@ impl EventObserverExtra for DebugEventObserverExtra {
         Self {
             dice_state: DiceState::new(),
             debug_events: DebugEventsState::new(),
+            action_details: ActionDetailsState::new(),
         }
     }
 
     fn observe(&mut self, receive_time: Instant, event: &Arc<BuckEvent>) -> anyhow::Result<()> {
         self.debug_events.handle_event(receive_time, event)?;
+        self.action_details.handle_event(receive_time, event);
 
         {
             use buck2_data::buck_event::Data::*;
@@ -222,6 +226,10 @@ impl DebugEventObserverExtra {
     pub fn debug_events(&self) -> &DebugEventsState {
         &self.debug_events
     }
+
+    pub fn action_details(&self) -> &ActionDetailsState {
+        &self.action_details
+    }
 }
 
 pub struct NoopEventObserverExtra;
@@ -9,6 +9,7 @@
 
 #![feature(try_blocks)]
 
+pub mod action_details;
 pub mod action_stats;
 pub mod debug_events;
 pub mod dice_state;
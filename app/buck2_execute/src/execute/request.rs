@@ -236,6 +236,8 @@ pub struct WorkerId(pub ValueIdentity<'static>);
 pub struct WorkerSpec {
     pub id: WorkerId,
     pub exe: Vec<String>,
+    /// Maximum number of commands to run on this worker at once, if limited.
+    pub concurrency: Option<usize>,
 }
 
 /// The data contains the information about the command to be executed.
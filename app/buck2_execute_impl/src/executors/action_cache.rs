@@ -26,6 +26,7 @@ use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 use tracing::info;
 
+use crate::executors::local_action_cache::LocalActionCache;
 use crate::re::download::download_action_results;
 use crate::re::download::DownloadResult;
 
@@ -34,6 +35,10 @@ pub struct ActionCacheChecker {
     pub materializer: Arc<dyn Materializer>,
     pub re_client: ManagedRemoteExecutionClient,
     pub re_use_case: RemoteExecutorUseCase,
+    /// Whether to use the RE action cache. If not, only the local action cache is used.
+    pub remote_cache_enabled: bool,
+    /// Checked before the RE action cache.
+    pub local_action_cache: Option<Arc<LocalActionCache>>,
     pub upload_all_actions: bool,
 }
 
@@ -49,6 +54,27 @@ impl PreparedCommandOptionalExecutor for ActionCacheChecker {
         let action_digest = &command.prepared_action.action;
         let action_blobs = &command.prepared_action.blobs;
         let digest_config = command.digest_config;
+
+        let manager = match &self.local_action_cache {
+            Some(local_action_cache) => {
+                local_action_cache
+                    .lookup(
+                        request,
+                        action_digest,
+                        &self.artifact_fs,
+                        &*self.materializer,
+                        digest_config,
+                        manager,
+                    )
+                    .await?
+            }
+            None => manager,
+        };
+
+        if !self.remote_cache_enabled {
+            return ControlFlow::Continue(manager);
+        }
+
         let re_client = &self.re_client;
 
         let action_cache_response = executor_stage_async(
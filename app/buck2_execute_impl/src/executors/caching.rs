@@ -57,6 +57,7 @@ use remote_execution::TStatus;
 use remote_execution::TTimestamp;
 use tracing::info;
 
+use crate::executors::local_action_cache::LocalActionCache;
 use crate::re::download::download_action_results;
 use crate::re::download::DownloadResult;
 
@@ -70,6 +71,12 @@ pub struct CachingExecutor {
     pub materializer: Arc<dyn Materializer>,
     pub re_client: ManagedRemoteExecutionClient,
     pub re_use_case: RemoteExecutorUseCase,
+    /// Whether to use the RE action cache. If not, only the local action cache is used.
+    pub remote_cache_enabled: bool,
+    /// Checked before the RE action cache, and populated with actions that ran locally.
+    pub local_action_cache: Option<Arc<LocalActionCache>>,
+    pub skip_local_cache_read: bool,
+    pub skip_local_cache_write: bool,
     pub upload_all_actions: bool,
     pub knobs: ExecutorGlobalKnobs,
     pub cache_upload_behavior: CacheUploadBehavior,
@@ -85,6 +92,26 @@ impl CachingExecutor {
         digest_config: DigestConfig,
         cancellations: &CancellationContext,
     ) -> ControlFlow<CommandExecutionResult, CommandExecutionManager> {
+        let manager = match &self.local_action_cache {
+            Some(local_action_cache) if !self.skip_local_cache_read => {
+                local_action_cache
+                    .lookup(
+                        request,
+                        action_digest,
+                        &self.artifact_fs,
+                        &*self.materializer,
+                        digest_config,
+                        manager,
+                    )
+                    .await?
+            }
+            _ => manager,
+        };
+
+        if !self.remote_cache_enabled {
+            return ControlFlow::Continue(manager);
+        }
+
         let re_client = &self.re_client;
         let action_cache_response = executor_stage_async(
             buck2_data::CacheQuery {
@@ -167,6 +194,10 @@ impl CachingExecutor {
         result: &CommandExecutionResult,
         digest_config: DigestConfig,
     ) -> anyhow::Result<Option<CacheUploadOutcome>> {
+        if !self.remote_cache_enabled {
+            return Ok(None);
+        }
+
         let max_bytes = match self.cache_upload_behavior {
             CacheUploadBehavior::Enabled { max_bytes } => max_bytes,
             CacheUploadBehavior::Disabled => return Ok(None),
@@ -450,6 +481,28 @@ impl PreparedCommandExecutor for CachingExecutor {
 
         let mut res = self.inner.exec_cmd(command, manager, cancellations).await;
 
+        if let Some(local_action_cache) = self
+            .local_action_cache
+            .as_ref()
+            .filter(|_| !self.skip_local_cache_write)
+        {
+            if let Err(e) = local_action_cache
+                .store(
+                    command.request,
+                    &command.prepared_action.action,
+                    &res,
+                    &self.artifact_fs,
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Storing `{}` in the local action cache failed: {:#}",
+                    command.prepared_action.action,
+                    e
+                );
+            }
+        }
+
         // TODO(bobyf, torozco) should these be critical sections?
         let upload_res = self
             .maybe_perform_cache_upload(
@@ -531,3 +584,236 @@ fn systemtime_to_ttimestamp(time: SystemTime) -> anyhow::Result<TTimestamp> {
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_common::liveliness_observer::NoopLivelinessObserver;
+    use buck2_core::base_deferred_key::BaseDeferredKey;
+    use buck2_core::buck_path::resolver::BuckPathResolver;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::CellResolver;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::fs::buck_out_path::BuckOutPath;
+    use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+    use buck2_core::fs::fs_util;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_core::target::label::ConfiguredTargetLabel;
+    use buck2_events::dispatch::with_dispatcher_async;
+    use buck2_events::dispatch::EventDispatcher;
+    use buck2_execute::artifact_value::ArtifactValue;
+    use buck2_execute::execute::claim::MutexClaimManager;
+    use buck2_execute::execute::prepared::PreparedAction;
+    use buck2_execute::execute::request::CommandExecutionOutput;
+    use buck2_execute::execute::request::CommandExecutionPaths;
+    use buck2_execute::execute::request::OutputType;
+    use buck2_execute::execute::result::CommandExecutionMetadata;
+    use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use indexmap::indexset;
+
+    use super::*;
+    use crate::executors::local_action_cache::LocalActionCacheLinkMethod;
+
+    /// Writes `contents` to all the outputs, counting how many commands it ran.
+    struct CountingExecutor {
+        artifact_fs: ArtifactFs,
+        runs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PreparedCommandExecutor for CountingExecutor {
+        async fn exec_cmd(
+            &self,
+            command: &PreparedCommand<'_, '_>,
+            manager: CommandExecutionManager,
+            _cancellations: &CancellationContext,
+        ) -> CommandExecutionResult {
+            let manager = manager.claim().await;
+            self.runs.fetch_add(1, Ordering::SeqCst);
+
+            let digest_config = command.digest_config;
+            let outputs = command
+                .request
+                .outputs()
+                .map(|output| {
+                    let path = output.resolve(&self.artifact_fs).into_path();
+                    self.artifact_fs.fs().write_file(&path, "contents", false)?;
+                    Ok((
+                        output.cloned(),
+                        ArtifactValue::file(FileMetadata {
+                            digest: TrackedFileDigest::from_content(
+                                b"contents",
+                                digest_config.cas_digest_config(),
+                            ),
+                            is_executable: false,
+                        }),
+                    ))
+                })
+                .collect::<anyhow::Result<_>>();
+
+            match outputs {
+                Ok(outputs) => manager.success(
+                    CommandExecutionKind::Local {
+                        digest: command.prepared_action.action.dupe(),
+                        command: Default::default(),
+                        env: Default::default(),
+                    },
+                    outputs,
+                    Default::default(),
+                    CommandExecutionMetadata::default(),
+                ),
+                Err(e) => manager.error("counting_executor", e),
+            }
+        }
+
+        fn is_local_execution_possible(&self, _executor_preference: ExecutorPreference) -> bool {
+            true
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestTarget;
+
+    impl CommandExecutionTarget for TestTarget {
+        fn re_action_key(&self) -> String {
+            "test".to_owned()
+        }
+
+        fn re_affinity_key(&self) -> String {
+            "test".to_owned()
+        }
+
+        fn as_proto_action_key(&self) -> buck2_data::ActionKey {
+            Default::default()
+        }
+
+        fn as_proto_action_name(&self) -> buck2_data::ActionName {
+            Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_action_cache_hit_and_miss() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let temp = ProjectRootTemp::new()?;
+        let project_fs = temp.path().dupe();
+        let artifact_fs = ArtifactFs::new(
+            BuckPathResolver::new(CellResolver::testing_with_name_and_path(
+                CellName::testing_new("cell"),
+                CellRootPathBuf::new(ProjectRelativePathBuf::unchecked_new("cell_path".into())),
+            )),
+            BuckOutPathResolver::new(ProjectRelativePathBuf::unchecked_new("buck-out/v2".into())),
+            project_fs.dupe(),
+        );
+
+        let inner = Arc::new(CountingExecutor {
+            artifact_fs: artifact_fs.clone(),
+            runs: AtomicUsize::new(0),
+        });
+        let executor = CachingExecutor {
+            inner: inner.dupe(),
+            artifact_fs: artifact_fs.clone(),
+            materializer: Arc::new(NoDiskMaterializer),
+            re_client: ManagedRemoteExecutionClient::testing_new_dummy(),
+            re_use_case: RemoteExecutorUseCase::buck2_default(),
+            remote_cache_enabled: false,
+            local_action_cache: Some(Arc::new(LocalActionCache::new(
+                project_fs
+                    .root()
+                    .join(ForwardRelativePath::new("local_action_cache")?),
+                LocalActionCacheLinkMethod::Copy,
+                u64::MAX,
+            ))),
+            skip_local_cache_read: false,
+            skip_local_cache_write: false,
+            upload_all_actions: false,
+            knobs: Default::default(),
+            cache_upload_behavior: CacheUploadBehavior::Disabled,
+        };
+
+        let output = BuckOutPath::new(
+            BaseDeferredKey::TargetLabel(ConfiguredTargetLabel::testing_parse(
+                "cell//pkg:target",
+                ConfigurationData::testing_new(),
+            )),
+            ForwardRelativePathBuf::unchecked_new("out".into()),
+        );
+        let request = CommandExecutionRequest::new(
+            Vec::new(),
+            vec!["write".to_owned()],
+            CommandExecutionPaths::new(
+                Vec::new(),
+                indexset![CommandExecutionOutput::BuildArtifact {
+                    path: output.dupe(),
+                    output_type: OutputType::File,
+                }],
+                &artifact_fs,
+                digest_config,
+            )?,
+            Default::default(),
+        );
+        let prepared_action = PreparedAction {
+            action: ActionDigest::from_content(b"write", digest_config.cas_digest_config()),
+            blobs: ActionBlobs::new(digest_config),
+            platform: Default::default(),
+        };
+        let command = PreparedCommand {
+            request: &request,
+            target: &TestTarget,
+            prepared_action: &prepared_action,
+            digest_config,
+        };
+
+        let executor = &executor;
+        let command = &command;
+        let exec = || {
+            with_dispatcher_async(EventDispatcher::null(), async move {
+                let manager = CommandExecutionManager::new(
+                    Box::new(MutexClaimManager::new()),
+                    EventDispatcher::null(),
+                    NoopLivelinessObserver::create(),
+                );
+                executor
+                    .exec_cmd(command, manager, CancellationContext::testing())
+                    .await
+            })
+        };
+
+        // The first run misses the cache and runs the command, which adds it to the cache.
+        let res = exec().await;
+        assert!(matches!(
+            res.report.status,
+            CommandExecutionStatus::Success {
+                execution_kind: CommandExecutionKind::Local { .. },
+            }
+        ));
+        assert_eq!(inner.runs.load(Ordering::SeqCst), 1);
+
+        let output_path = artifact_fs.resolve_build(&output);
+        project_fs.remove_path_recursive(&output_path)?;
+
+        // The second run is served from the cache, which restores the output.
+        let res = exec().await;
+        assert!(matches!(
+            res.report.status,
+            CommandExecutionStatus::Success {
+                execution_kind: CommandExecutionKind::LocalActionCache { .. },
+            }
+        ));
+        assert_eq!(inner.runs.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            fs_util::read_to_string(project_fs.resolve(&output_path))?,
+            "contents"
+        );
+
+        Ok(())
+    }
+}
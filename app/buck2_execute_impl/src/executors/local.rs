@@ -30,6 +30,7 @@ use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::tag_error;
 use buck2_core::tag_result;
 use buck2_events::dispatch::get_dispatcher;
+use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::directory::extract_artifact_value;
@@ -138,6 +139,7 @@ impl LocalExecutor {
         env_inheritance: Option<&'a EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         disable_miniperf: bool,
+        on_stderr: impl FnMut(&[u8]) + Send + 'a,
     ) -> impl futures::future::Future<
         Output = anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>,
     > + Send
@@ -164,13 +166,14 @@ impl LocalExecutor {
                             env_inheritance,
                             liveliness_observer,
                             self.knobs.enable_miniperf && !disable_miniperf,
+                            on_stderr,
                         )
                         .await
                     }
 
                     #[cfg(not(unix))]
                     {
-                        let _unused = (forkserver, disable_miniperf);
+                        let _unused = (forkserver, disable_miniperf, on_stderr);
                         Err(anyhow::anyhow!("Forkserver is not supported off-UNIX"))
                     }
                 }
@@ -194,7 +197,7 @@ impl LocalExecutor {
                     let cancellation =
                         select(timeout.boxed(), alive.boxed()).map(|r| r.factor_first().0);
 
-                    gather_output(cmd, cancellation).await
+                    gather_output(cmd, cancellation, on_stderr).await
                 }
                 .with_context(|| format!("Failed to gather output from command: {}", exe)),
             }
@@ -400,6 +403,7 @@ impl LocalExecutor {
                     unix::exec_via_worker(worker_pool, worker, request.args(), env, &self.root)
                         .await
                 } else {
+                    let mut stderr_tail = StderrTailReporter::new(get_dispatcher());
                     self.exec(
                         &args[0],
                         &args[1..],
@@ -409,8 +413,11 @@ impl LocalExecutor {
                         request.local_environment_inheritance(),
                         liveliness_observer,
                         request.disable_miniperf(),
+                        |bytes| stderr_tail.observe(bytes),
                     )
-                    .await
+                    .await;
+                    stderr_tail.finish();
+                    r
                 };
 
                 let execution_time = execution_start.elapsed();
@@ -631,6 +638,69 @@ impl PreparedCommandExecutor for LocalExecutor {
     }
 }
 
+/// Sends the end of a running command's stderr to the client, so that it can be shown before the
+/// command finishes. Events are rate limited, since a command may write a lot of small chunks,
+/// and whatever was held back is sent by `finish`.
+struct StderrTailReporter {
+    dispatcher: EventDispatcher,
+    tail: Vec<u8>,
+    truncated: bool,
+    last_sent: Option<Instant>,
+    /// Whether stderr was written since the last event.
+    unsent: bool,
+}
+
+impl StderrTailReporter {
+    const MAX_TAIL_BYTES: usize = 2048;
+    const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn new(dispatcher: EventDispatcher) -> Self {
+        Self {
+            dispatcher,
+            tail: Vec::new(),
+            truncated: false,
+            last_sent: None,
+            unsent: false,
+        }
+    }
+
+    fn observe(&mut self, bytes: &[u8]) {
+        self.tail.extend_from_slice(bytes);
+        if self.tail.len() > Self::MAX_TAIL_BYTES {
+            self.tail.drain(..self.tail.len() - Self::MAX_TAIL_BYTES);
+            self.truncated = true;
+        }
+        self.unsent = true;
+
+        let now = Instant::now();
+        if self
+            .last_sent
+            .map_or(false, |last_sent| now - last_sent < Self::MIN_INTERVAL)
+        {
+            return;
+        }
+        self.last_sent = Some(now);
+        self.send();
+    }
+
+    /// Sends the tail if the last chunks of stderr were held back by the rate limit. Called when
+    /// the command exits.
+    fn finish(mut self) {
+        if self.unsent {
+            self.send();
+        }
+    }
+
+    fn send(&mut self) {
+        self.unsent = false;
+        self.dispatcher
+            .instant_event(buck2_data::LocalCommandStderrTail {
+                tail: String::from_utf8_lossy(&self.tail).into_owned(),
+                truncated: self.truncated,
+            });
+    }
+}
+
 /// Either a str or a OsStr, so that we can turn it back into a String without having to check for
 /// valid utf-8, while using the same struct.
 #[derive(Copy, Clone, Dupe, From)]
@@ -911,6 +981,7 @@ mod unix {
         env_inheritance: Option<&EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         enable_miniperf: bool,
+        on_stderr: impl FnMut(&[u8]) + Send,
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)> {
         let exe = exe.as_ref();
 
@@ -929,7 +1000,11 @@ mod unix {
         };
         apply_local_execution_environment(&mut req, working_directory, env, env_inheritance);
         forkserver
-            .execute(req, async move { liveliness_observer.while_alive().await })
+            .execute(
+                req,
+                async move { liveliness_observer.while_alive().await },
+                on_stderr,
+            )
             .await
     }
 
@@ -987,10 +1062,7 @@ mod unix {
             .into_iter()
             .map(|(k, v)| (k.as_ref().to_owned(), v.as_ref().to_owned()))
             .collect();
-        let worker = worker_pool
-            .get_or_create_worker(worker, env.clone(), root)
-            .await?;
-        Ok(worker.exec_cmd(args, env).await)
+        worker_pool.exec_cmd(worker, args, env, root).await
     }
 }
 
@@ -1011,8 +1083,11 @@ mod tests {
     use buck2_core::fs::project::ProjectRoot;
     use buck2_core::fs::project::ProjectRootTemp;
     use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_events::create_source_sink_pair;
+    use buck2_events::EventSource;
     use buck2_execute::execute::blocking::testing::DummyBlockingExecutor;
     use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use buck2_wrapper_common::invocation_id::TraceId;
     use host_sharing::HostSharingStrategy;
 
     use super::*;
@@ -1026,7 +1101,8 @@ mod tests {
         };
         cmd.args(["-c", "echo hello"]);
 
-        let (status, stdout, stderr) = gather_output(cmd, futures::future::pending()).await?;
+        let (status, stdout, stderr) =
+            gather_output(cmd, futures::future::pending(), |_| {}).await?;
         assert!(matches!(status, GatherOutputStatus::Finished{ exit_code, .. } if exit_code == 0));
         assert_eq!(str::from_utf8(&stdout)?.trim(), "hello");
         assert_eq!(stderr, b"");
@@ -1055,6 +1131,7 @@ mod tests {
         let (status, stdout, stderr) = gather_output(
             cmd,
             timeout_into_cancellation(Some(Duration::from_secs(timeout))),
+            |_| {},
         )
         .await?;
         assert!(
@@ -1083,6 +1160,7 @@ mod tests {
         let (status, stdout, stderr) = gather_output(
             cmd,
             timeout_into_cancellation(Some(Duration::from_secs(timeout))),
+            |_| {},
         )
         .await?;
         assert!(
@@ -1148,6 +1226,7 @@ mod tests {
                 None,
                 NoopLivelinessObserver::create(),
                 false,
+                |_| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
@@ -1184,6 +1263,7 @@ mod tests {
                 Some(&EnvironmentInheritance::empty()),
                 NoopLivelinessObserver::create(),
                 false,
+                |_| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
@@ -1191,4 +1271,40 @@ mod tests {
 
         Ok(())
     }
+
+    fn stderr_tails(source: &mut impl EventSource) -> Vec<String> {
+        let mut tails = Vec::new();
+        while let Some(event) = source.try_receive() {
+            if let buck2_data::buck_event::Data::Instant(i) = event.unpack_buck().unwrap().data() {
+                if let Some(buck2_data::instant_event::Data::LocalCommandStderrTail(e)) = &i.data {
+                    tails.push(e.tail.clone());
+                }
+            }
+        }
+        tails
+    }
+
+    #[test]
+    fn test_stderr_tail_is_sent_when_the_command_exits() {
+        let (mut source, sink) = create_source_sink_pair();
+        let mut reporter = StderrTailReporter::new(EventDispatcher::new(TraceId::new(), sink));
+
+        reporter.observe(b"a");
+        // held back by the rate limit
+        reporter.observe(b"b");
+        assert_eq!(stderr_tails(&mut source), vec!["a"]);
+
+        reporter.finish();
+        assert_eq!(stderr_tails(&mut source), vec!["ab"]);
+    }
+
+    #[test]
+    fn test_stderr_tail_is_not_sent_again_when_the_command_exits() {
+        let (mut source, sink) = create_source_sink_pair();
+        let mut reporter = StderrTailReporter::new(EventDispatcher::new(TraceId::new(), sink));
+
+        reporter.observe(b"a");
+        reporter.finish();
+        assert_eq!(stderr_tails(&mut source), vec!["a"]);
+    }
 }
@@ -0,0 +1,949 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A content addressed store and action cache on local disk, which lets all the checkouts and
+//! daemons on a machine reuse the outputs of actions that any of them ran locally.
+//!
+//! The cache directory is laid out as follows:
+//!
+//! - `cas/<xx>/<hash>_<size>[.x]`: the contents of output files, keyed on their digest. Those are
+//!   read-only, and `.x` is appended for executable files since hard links share permissions.
+//! - `ac/<hash>_<size>`: the outputs of an action, keyed on its action digest, as JSON. Entries
+//!   are rewritten when they are used, so their modification time is the time they were last used.
+//!
+//! When the cache grows past its maximum size, the least recently used actions are deleted along
+//! with the blobs that no other action uses. Every daemon using the cache does this, based on the
+//! size it finds on disk. All the filesystem accesses happen on blocking threads.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use buck2_common::cas_digest::DigestAlgorithm;
+use buck2_common::file_ops::FileDigest;
+use buck2_common::file_ops::FileMetadata;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::directory::Directory;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest_config::DigestConfig;
+use buck2_execute::directory::extract_artifact_value;
+use buck2_execute::directory::insert_entry;
+use buck2_execute::directory::new_symlink;
+use buck2_execute::directory::ActionDirectory;
+use buck2_execute::directory::ActionDirectoryBuilder;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::execute::action_digest::ActionDigest;
+use buck2_execute::execute::executor_stage_async;
+use buck2_execute::execute::kind::CommandExecutionKind;
+use buck2_execute::execute::manager::CommandExecutionManager;
+use buck2_execute::execute::manager::CommandExecutionManagerExt;
+use buck2_execute::execute::output::CommandStdStreams;
+use buck2_execute::execute::request::CommandExecutionOutput;
+use buck2_execute::execute::request::CommandExecutionOutputRef;
+use buck2_execute::execute::request::CommandExecutionRequest;
+use buck2_execute::execute::result::CommandExecutionMetadata;
+use buck2_execute::execute::result::CommandExecutionResult;
+use buck2_execute::execute::result::CommandExecutionStatus;
+use buck2_execute::materialize::materializer::Materializer;
+use dupe::Dupe;
+use gazebo::prelude::VecExt;
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Debug, Error)]
+enum LocalActionCacheError {
+    #[error("Invalid local action cache link method `{0}`, expected one of `hardlink` or `copy`")]
+    InvalidLinkMethod(String),
+}
+
+/// How outputs are placed in `buck-out` when they are served from the local action cache.
+#[derive(Copy, Clone, Dupe, Debug, PartialEq, Eq)]
+pub enum LocalActionCacheLinkMethod {
+    /// Hard link the outputs to the cache, falling back to a copy if they are on different
+    /// devices. The outputs are read-only, since writing to them would corrupt the cache.
+    Hardlink,
+    /// Copy the outputs from the cache. This clones the files instead when the filesystem
+    /// supports it (e.g. btrfs, XFS or APFS).
+    Copy,
+}
+
+impl FromStr for LocalActionCacheLinkMethod {
+    type Err = LocalActionCacheError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hardlink" => Ok(Self::Hardlink),
+            "copy" => Ok(Self::Copy),
+            _ => Err(LocalActionCacheError::InvalidLinkMethod(s.to_owned())),
+        }
+    }
+}
+
+/// The outputs of an action, as stored in the action cache.
+#[derive(Serialize, Deserialize)]
+struct CachedActionResult {
+    outputs: Vec<CachedOutput>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    execution_time_us: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedOutput {
+    /// Project relative path of the output.
+    path: String,
+    entry: CachedEntry,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CachedEntry {
+    File {
+        digest: String,
+        executable: bool,
+    },
+    Symlink {
+        target: String,
+    },
+    Dir {
+        entries: BTreeMap<String, CachedEntry>,
+    },
+}
+
+impl CachedEntry {
+    /// Convert an output to its cached representation, recording the files to add to the CAS.
+    /// Returns `None` if the output cannot be cached.
+    fn new(
+        entry: DirectoryEntry<&dyn ActionDirectory, &ActionDirectoryMember>,
+        path: AbsNormPathBuf,
+        blobs: &mut Vec<(AbsNormPathBuf, TrackedFileDigest, bool)>,
+    ) -> anyhow::Result<Option<Self>> {
+        Ok(Some(match entry {
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => {
+                blobs.push((path, f.digest.dupe(), f.is_executable));
+                Self::File {
+                    digest: f.digest.to_string(),
+                    executable: f.is_executable,
+                }
+            }
+            DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(s)) => Self::Symlink {
+                target: s.target().as_str().to_owned(),
+            },
+            // Those point outside of the project, so we can't know that they are the same in
+            // another checkout.
+            DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(..)) => return Ok(None),
+            DirectoryEntry::Dir(d) => {
+                let mut entries = BTreeMap::new();
+                for (name, entry) in d.entries() {
+                    match Self::new(entry, path.join(name), blobs)? {
+                        Some(entry) => entries.insert(name.as_str().to_owned(), entry),
+                        None => return Ok(None),
+                    };
+                }
+                Self::Dir { entries }
+            }
+        }))
+    }
+}
+
+/// A cleanup deletes entries until the cache uses at most this percentage of its maximum size, so
+/// that it does not run again on the next write.
+const CLEANUP_TARGET_PERCENT: u64 = 80;
+
+/// Prefix of the temporary files, which are never counted or deleted by a cleanup since another
+/// process may be writing them.
+const TEMP_FILE_PREFIX: &str = ".tmp.";
+
+pub struct LocalActionCache {
+    root: AbsNormPathBuf,
+    link_method: LocalActionCacheLinkMethod,
+    /// The cache is cleaned up when it uses more than this.
+    max_bytes: u64,
+    /// The size of the cache, as of the last cleanup plus what this daemon wrote since. `None`
+    /// until the first write, which runs a cleanup to find it.
+    size_bytes: Mutex<Option<u64>>,
+    /// Used to name temporary files, which are renamed into place once complete.
+    next_temp_file: AtomicU64,
+}
+
+impl LocalActionCache {
+    pub fn new(
+        root: AbsNormPathBuf,
+        link_method: LocalActionCacheLinkMethod,
+        max_bytes: u64,
+    ) -> Self {
+        Self {
+            root,
+            link_method,
+            max_bytes,
+            size_bytes: Mutex::new(None),
+            next_temp_file: AtomicU64::new(0),
+        }
+    }
+
+    fn action_path(&self, action_digest: &ActionDigest) -> anyhow::Result<AbsNormPathBuf> {
+        Ok(self.root.join(ForwardRelativePath::new(&format!(
+            "ac/{}_{}",
+            action_digest.raw_digest(),
+            action_digest.size()
+        ))?))
+    }
+
+    fn blob_path(&self, digest: &FileDigest, executable: bool) -> anyhow::Result<AbsNormPathBuf> {
+        self.blob_path_for_hash(&digest.raw_digest().to_string(), digest.size(), executable)
+    }
+
+    fn blob_path_for_hash(
+        &self,
+        hash: &str,
+        size: u64,
+        executable: bool,
+    ) -> anyhow::Result<AbsNormPathBuf> {
+        Ok(self.root.join(ForwardRelativePath::new(&format!(
+            "cas/{}/{}_{}{}",
+            hash.get(..2).context("Digest is too short")?,
+            hash,
+            size,
+            if executable { ".x" } else { "" }
+        ))?))
+    }
+
+    /// Create the file at `path` with `write`, going through a temporary file so that other
+    /// processes using the cache never see a partial file.
+    fn write_atomic(
+        &self,
+        path: &AbsNormPath,
+        write: impl FnOnce(&AbsNormPath) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let dir = path.parent().context("Cache path has no parent")?;
+        fs_util::create_dir_all(dir)?;
+        let temp = dir.join(ForwardRelativePath::new(&format!(
+            "{}{}.{}",
+            TEMP_FILE_PREFIX,
+            std::process::id(),
+            self.next_temp_file.fetch_add(1, Ordering::Relaxed)
+        ))?);
+        let res = write(&temp).and_then(|()| fs_util::rename(&temp, path));
+        if res.is_err() {
+            let _ignored = fs_util::remove_file(&temp);
+        }
+        res
+    }
+
+    /// Actions that keep state in their outputs across runs, or that produce test outputs, are
+    /// not cached.
+    fn is_cacheable(request: &CommandExecutionRequest) -> bool {
+        request.outputs_cleanup
+            && request
+                .outputs()
+                .all(|o| matches!(o, CommandExecutionOutputRef::BuildArtifact { .. }))
+    }
+
+    /// Runs `f` on a blocking thread, since the cache is only accessed through the filesystem.
+    async fn blocking<R: Send + 'static>(
+        self: &Arc<Self>,
+        f: impl FnOnce(&Self) -> anyhow::Result<R> + Send + 'static,
+    ) -> anyhow::Result<R> {
+        let this = self.dupe();
+        tokio::task::spawn_blocking(move || f(&this)).await?
+    }
+
+    /// Whether the blob at `path` still has the contents it was stored with. A blob that was
+    /// modified (e.g. through a hard link in `buck-out` made writable) is removed, so that it can
+    /// be stored again.
+    fn verify_blob(
+        path: &AbsNormPath,
+        digest: &FileDigest,
+        algorithm: DigestAlgorithm,
+    ) -> anyhow::Result<bool> {
+        let file = match fs_util::open_file(path) {
+            Ok(file) => file,
+            Err(_) if !fs_util::try_exists(path)? => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        if FileDigest::from_reader_for_algorithm(file, algorithm)? == *digest {
+            return Ok(true);
+        }
+        tracing::warn!(
+            "Removing corrupted blob `{}` from the local action cache",
+            path
+        );
+        fs_util::remove_file(path)?;
+        Ok(false)
+    }
+
+    /// Reads the cached result of this action, if there is one and all its outputs are present in
+    /// the CAS with the expected contents, and records that it was used.
+    fn read(
+        &self,
+        action_digest: &ActionDigest,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<Option<CachedActionResult>> {
+        let action_path = self.action_path(action_digest)?;
+        let raw_entry = match fs_util::read_to_string_opt(&action_path)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let entry: CachedActionResult =
+            serde_json::from_str(&raw_entry).context("Invalid action cache entry")?;
+
+        fn blobs_valid(
+            cache: &LocalActionCache,
+            entry: &CachedEntry,
+            config: DigestConfig,
+        ) -> anyhow::Result<bool> {
+            Ok(match entry {
+                CachedEntry::File { digest, executable } => {
+                    let (digest, algorithm) =
+                        FileDigest::parse_digest(digest, config.cas_digest_config())?;
+                    LocalActionCache::verify_blob(
+                        &cache.blob_path(&digest, *executable)?,
+                        &digest,
+                        algorithm,
+                    )?
+                }
+                CachedEntry::Symlink { .. } => true,
+                CachedEntry::Dir { entries } => {
+                    for entry in entries.values() {
+                        if !blobs_valid(cache, entry, config)? {
+                            return Ok(false);
+                        }
+                    }
+                    true
+                }
+            })
+        }
+
+        for output in &entry.outputs {
+            if !blobs_valid(self, &output.entry, digest_config)? {
+                return Ok(None);
+            }
+        }
+
+        // Rewriting the entry updates its modification time, which cleanups use as the time it
+        // was last used.
+        self.write_atomic(&action_path, |temp| fs_util::write(temp, &raw_entry))?;
+        Ok(Some(entry))
+    }
+
+    /// Place `entry` at `path`, returning its value.
+    fn restore(
+        &self,
+        entry: &CachedEntry,
+        path: &AbsNormPath,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<ActionDirectoryEntry<ActionDirectoryBuilder>> {
+        Ok(match entry {
+            CachedEntry::File { digest, executable } => {
+                let (digest, _) =
+                    FileDigest::parse_digest(digest, digest_config.cas_digest_config())?;
+                let blob = self.blob_path(&digest, *executable)?;
+                let linked = match self.link_method {
+                    LocalActionCacheLinkMethod::Hardlink => fs_util::hardlink(&blob, path).is_ok(),
+                    LocalActionCacheLinkMethod::Copy => false,
+                };
+                if !linked {
+                    fs_util::copy(&blob, path)?;
+                    // Cache entries are read-only, but copies can safely be written to.
+                    make_writable(path)?;
+                }
+                DirectoryEntry::Leaf(ActionDirectoryMember::File(FileMetadata {
+                    digest: TrackedFileDigest::new(digest, digest_config.cas_digest_config()),
+                    is_executable: *executable,
+                }))
+            }
+            CachedEntry::Symlink { target } => {
+                fs_util::symlink(target, path)?;
+                DirectoryEntry::Leaf(new_symlink(target)?)
+            }
+            CachedEntry::Dir { entries } => {
+                fs_util::create_dir_all(path)?;
+                let mut builder = ActionDirectoryBuilder::empty();
+                for (name, entry) in entries {
+                    let name = ForwardRelativePath::new(name)?;
+                    let value = self.restore(entry, &path.join(name), digest_config)?;
+                    builder.insert(name, value)?;
+                }
+                DirectoryEntry::Dir(builder)
+            }
+        })
+    }
+
+    /// Serve this action from the cache if possible, placing its outputs in `buck-out`.
+    pub async fn lookup(
+        self: &Arc<Self>,
+        request: &CommandExecutionRequest,
+        action_digest: &ActionDigest,
+        artifact_fs: &ArtifactFs,
+        materializer: &dyn Materializer,
+        digest_config: DigestConfig,
+        manager: CommandExecutionManager,
+    ) -> ControlFlow<CommandExecutionResult, CommandExecutionManager> {
+        if !Self::is_cacheable(request) {
+            return ControlFlow::Continue(manager);
+        }
+
+        let start = Instant::now();
+        let start_time = SystemTime::now();
+
+        let cached = executor_stage_async(
+            buck2_data::CacheQuery {
+                action_digest: action_digest.to_string(),
+            },
+            {
+                let action_digest = action_digest.dupe();
+                self.blocking(move |cache| cache.read(&action_digest, digest_config))
+            },
+        )
+        .await;
+
+        let cached = match cached {
+            Ok(Some(cached)) => cached,
+            Ok(None) => return ControlFlow::Continue(manager),
+            Err(e) => {
+                tracing::warn!(
+                    "Error reading local action cache entry for `{}`: {:#}",
+                    action_digest,
+                    e
+                );
+                return ControlFlow::Continue(manager);
+            }
+        };
+
+        info!(
+            "Action result is cached locally, skipping execution of:\n```\n$ {}\n```\n for action `{}`",
+            request.all_args_str(),
+            action_digest,
+        );
+
+        let CachedActionResult {
+            outputs,
+            stdout,
+            stderr,
+            execution_time_us,
+        } = cached;
+
+        let manager = manager.claim().await;
+
+        let outputs = executor_stage_async(
+            buck2_data::CacheHit {
+                action_digest: action_digest.to_string(),
+            },
+            self.restore_outputs(request, outputs, artifact_fs, materializer, digest_config),
+        )
+        .await;
+
+        let outputs = match outputs {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                return ControlFlow::Break(manager.error(
+                    "local_action_cache",
+                    e.context(format!("action_digest={}", action_digest)),
+                ));
+            }
+        };
+
+        let timing = CommandExecutionMetadata {
+            wall_time: start.elapsed(),
+            re_queue_time: None,
+            execution_time: Duration::from_micros(execution_time_us),
+            start_time,
+            execution_stats: None,
+        };
+
+        ControlFlow::Break(manager.success(
+            CommandExecutionKind::LocalActionCache {
+                digest: action_digest.dupe(),
+            },
+            outputs,
+            CommandStdStreams::Local { stdout, stderr },
+            timing,
+        ))
+    }
+
+    async fn restore_outputs(
+        self: &Arc<Self>,
+        request: &CommandExecutionRequest,
+        cached_outputs: Vec<CachedOutput>,
+        artifact_fs: &ArtifactFs,
+        materializer: &dyn Materializer,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<IndexMap<CommandExecutionOutput, ArtifactValue>> {
+        let outputs: Vec<_> = request
+            .outputs()
+            .map(|output| (output.cloned(), output.resolve(artifact_fs).into_path()))
+            .collect();
+
+        materializer
+            .invalidate_many(outputs.iter().map(|(_, path)| path.clone()).collect())
+            .await?;
+
+        let project_fs = artifact_fs.fs().dupe();
+        let mut builder = request.paths().input_directory().clone().into_builder();
+        let (builder, restored) = self
+            .blocking(move |cache| {
+                let cached_outputs: BTreeMap<_, _> = cached_outputs
+                    .iter()
+                    .map(|o| (o.path.as_str(), &o.entry))
+                    .collect();
+
+                let mut restored = Vec::with_capacity(outputs.len());
+                for (output, path) in outputs {
+                    project_fs.remove_path_recursive(&path)?;
+                    let entry = match cached_outputs.get(path.as_str()) {
+                        Some(entry) => entry,
+                        None => continue,
+                    };
+                    let abspath = project_fs.resolve(&path);
+                    if let Some(parent) = abspath.parent() {
+                        fs_util::create_dir_all(parent)?;
+                    }
+                    let value = cache
+                        .restore(entry, &abspath, digest_config)
+                        .with_context(|| format!("Error restoring output `{}`", path))?;
+                    insert_entry(&mut builder, &path, value)?;
+                    restored.push((output, path));
+                }
+                Ok((builder, restored))
+            })
+            .await?;
+
+        let mut to_declare = Vec::with_capacity(restored.len());
+        let mut mapped_outputs = IndexMap::with_capacity(restored.len());
+        for (output, path) in restored {
+            if let Some(value) = extract_artifact_value(&builder, &path, digest_config)? {
+                to_declare.push((path, value.dupe()));
+                mapped_outputs.insert(output, value);
+            }
+        }
+
+        materializer.declare_existing(to_declare).await?;
+
+        Ok(mapped_outputs)
+    }
+
+    /// Add the outputs of this action to the cache, if it succeeded locally. Returns whether the
+    /// action was added.
+    pub async fn store(
+        self: &Arc<Self>,
+        request: &CommandExecutionRequest,
+        action_digest: &ActionDigest,
+        result: &CommandExecutionResult,
+        artifact_fs: &ArtifactFs,
+    ) -> anyhow::Result<bool> {
+        match &result.report.status {
+            CommandExecutionStatus::Success {
+                execution_kind: CommandExecutionKind::Local { .. },
+            } => {}
+            _ => return Ok(false),
+        }
+
+        if !Self::is_cacheable(request) {
+            return Ok(false);
+        }
+
+        let (stdout, stderr) = match &result.report.std_streams {
+            CommandStdStreams::Local { stdout, stderr } => (stdout.clone(), stderr.clone()),
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let mut blobs = Vec::new();
+        let mut outputs = Vec::with_capacity(result.outputs.len());
+        for (output, value) in result.resolve_outputs(artifact_fs) {
+            let path: &ProjectRelativePath = output.path();
+            let entry = value
+                .entry()
+                .as_ref()
+                .map_dir(|d| d as &dyn ActionDirectory);
+            match CachedEntry::new(entry, artifact_fs.fs().resolve(path), &mut blobs)? {
+                Some(entry) => outputs.push(CachedOutput {
+                    path: path.as_str().to_owned(),
+                    entry,
+                }),
+                None => return Ok(false),
+            }
+        }
+
+        let entry = CachedActionResult {
+            outputs,
+            stdout,
+            stderr,
+            execution_time_us: result.report.timing.execution_time.as_micros() as u64,
+        };
+        let action_digest = action_digest.dupe();
+        self.blocking(move |cache| cache.write(&action_digest, &entry, blobs))
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Adds the `blobs` to the CAS if they are missing, then records `entry` as the result of the
+    /// action.
+    fn write(
+        &self,
+        action_digest: &ActionDigest,
+        entry: &CachedActionResult,
+        blobs: Vec<(AbsNormPathBuf, TrackedFileDigest, bool)>,
+    ) -> anyhow::Result<()> {
+        let mut written_bytes = 0;
+        for (path, digest, executable) in blobs {
+            let blob = self.blob_path(digest.data(), executable)?;
+            if fs_util::try_exists(&blob)? {
+                continue;
+            }
+            self.write_atomic(&blob, |temp| {
+                fs_util::copy(&path, temp)?;
+                let mut perms = fs_util::metadata(temp)?.permissions();
+                perms.set_readonly(true);
+                fs_util::set_permissions(temp, perms)
+            })?;
+            written_bytes += digest.size();
+        }
+
+        let entry = serde_json::to_string(entry)?;
+        self.write_atomic(&self.action_path(action_digest)?, |temp| {
+            fs_util::write(temp, &entry)
+        })?;
+        written_bytes += entry.len() as u64;
+
+        let mut size_bytes = self.size_bytes.lock();
+        *size_bytes = Some(match *size_bytes {
+            Some(size) if size + written_bytes <= self.max_bytes => size + written_bytes,
+            _ => self.cleanup()?,
+        });
+        Ok(())
+    }
+
+    /// The blobs used by a cached action.
+    fn blob_paths(&self, entry: &CachedActionResult) -> anyhow::Result<Vec<AbsNormPathBuf>> {
+        fn collect(
+            cache: &LocalActionCache,
+            entry: &CachedEntry,
+            paths: &mut Vec<AbsNormPathBuf>,
+        ) -> anyhow::Result<()> {
+            match entry {
+                CachedEntry::File { digest, executable } => {
+                    // This is the format of `FileDigest`'s `Display`, which is how it was stored.
+                    let (hash, size) = digest
+                        .split_once(':')
+                        .with_context(|| format!("Invalid digest `{}`", digest))?;
+                    paths.push(cache.blob_path_for_hash(hash, size.parse()?, *executable)?);
+                }
+                CachedEntry::Symlink { .. } => {}
+                CachedEntry::Dir { entries } => {
+                    for entry in entries.values() {
+                        collect(cache, entry, paths)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut paths = Vec::new();
+        for output in &entry.outputs {
+            collect(self, &output.entry, &mut paths)?;
+        }
+        Ok(paths)
+    }
+
+    /// If the cache uses more than its maximum size, delete the blobs that no action uses, then
+    /// the least recently used actions along with the blobs that only they use, until it uses at
+    /// most `CLEANUP_TARGET_PERCENT` of it. Returns the size of the cache.
+    fn cleanup(&self) -> anyhow::Result<u64> {
+        let mut total_bytes = 0;
+
+        // Blob path to its size and the number of actions using it.
+        let mut blobs: HashMap<AbsNormPathBuf, (u64, usize)> = HashMap::new();
+        if let Some(shards) =
+            fs_util::read_dir_if_exists(self.root.join(ForwardRelativePath::new("cas")?))?
+        {
+            for shard in shards {
+                for blob in fs_util::read_dir(shard?.path())? {
+                    let blob = blob?;
+                    if is_temp_file(&blob) {
+                        continue;
+                    }
+                    let size = blob.metadata()?.len();
+                    total_bytes += size;
+                    blobs.insert(blob.path(), (size, 0));
+                }
+            }
+        }
+
+        let mut actions = Vec::new();
+        if let Some(entries) =
+            fs_util::read_dir_if_exists(self.root.join(ForwardRelativePath::new("ac")?))?
+        {
+            for entry in entries {
+                let entry = entry?;
+                if is_temp_file(&entry) {
+                    continue;
+                }
+                let metadata = entry.metadata()?;
+                total_bytes += metadata.len();
+                actions.push((metadata.modified()?, entry.path(), metadata.len()));
+            }
+        }
+
+        if total_bytes <= self.max_bytes {
+            return Ok(total_bytes);
+        }
+
+        // Only read the entries once we know some need to be deleted, since the size of the cache
+        // is found with a cleanup on the first write of every `LocalActionCache`.
+        let mut actions = actions.into_try_map(|(last_used, path, size)| {
+            // An entry that can't be read uses no blobs, and is deleted like any other.
+            let used = match fs_util::read_to_string_opt(&path)?
+                .and_then(|entry| serde_json::from_str::<CachedActionResult>(&entry).ok())
+            {
+                Some(entry) => self.blob_paths(&entry)?,
+                None => Vec::new(),
+            };
+            for blob in &used {
+                if let Some((_, users)) = blobs.get_mut(blob) {
+                    *users += 1;
+                }
+            }
+            anyhow::Ok((last_used, path, size, used))
+        })?;
+
+        let target_bytes = self.max_bytes / 100 * CLEANUP_TARGET_PERCENT;
+        let before_bytes = total_bytes;
+
+        for (path, (size, users)) in &blobs {
+            if total_bytes <= target_bytes {
+                break;
+            }
+            if *users == 0 {
+                remove_cached_file(path)?;
+                total_bytes -= size;
+            }
+        }
+
+        actions.sort_by_key(|(last_used, ..)| *last_used);
+        for (_, path, size, used) in actions {
+            if total_bytes <= target_bytes {
+                break;
+            }
+            remove_cached_file(&path)?;
+            total_bytes -= size;
+            for blob in used {
+                if let Some((size, users)) = blobs.get_mut(&blob) {
+                    *users -= 1;
+                    if *users == 0 {
+                        remove_cached_file(&blob)?;
+                        total_bytes -= *size;
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Cleaned up {} bytes from the local action cache, which now uses {} bytes",
+            before_bytes - total_bytes,
+            total_bytes
+        );
+        Ok(total_bytes)
+    }
+}
+
+fn is_temp_file(entry: &fs_util::DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map_or(false, |name| name.starts_with(TEMP_FILE_PREFIX))
+}
+
+/// Remove a file from the cache, which another process may have removed already.
+fn remove_cached_file(path: &AbsNormPath) -> anyhow::Result<()> {
+    // Read-only files can't be removed on Windows.
+    #[cfg(windows)]
+    let _ignored = make_writable(path);
+
+    match fs_util::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(_) if !fs_util::try_exists(path)? => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+fn make_writable(path: &AbsNormPath) -> anyhow::Result<()> {
+    let mut perms = fs_util::metadata(path)?.permissions();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Add u+w
+        perms.set_mode(perms.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    {
+        #[allow(clippy::permissions_set_readonly_false)]
+        perms.set_readonly(false);
+    }
+
+    fs_util::set_permissions(path, perms)
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::project::ProjectRootTemp;
+
+    use super::*;
+
+    #[test]
+    fn test_write_then_read() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let temp = ProjectRootTemp::new()?;
+        let root = temp.path().root();
+        let cache = LocalActionCache::new(
+            root.join(ForwardRelativePath::new("cache")?),
+            LocalActionCacheLinkMethod::Copy,
+            u64::MAX,
+        );
+
+        let output = root.join(ForwardRelativePath::new("out")?);
+        fs_util::write(&output, "contents")?;
+        let digest =
+            TrackedFileDigest::from_content(b"contents", digest_config.cas_digest_config());
+        let action_digest =
+            ActionDigest::from_content(b"action", digest_config.cas_digest_config());
+
+        assert!(cache.read(&action_digest, digest_config)?.is_none());
+
+        let entry = CachedActionResult {
+            outputs: vec![CachedOutput {
+                path: "out".to_owned(),
+                entry: CachedEntry::File {
+                    digest: digest.to_string(),
+                    executable: false,
+                },
+            }],
+            stdout: b"stdout".to_vec(),
+            stderr: Vec::new(),
+            execution_time_us: 1,
+        };
+        cache.write(&action_digest, &entry, vec![(output, digest.dupe(), false)])?;
+
+        let cached = cache
+            .read(&action_digest, digest_config)?
+            .context("Action should be cached")?;
+        assert_eq!(cached.stdout, b"stdout");
+        assert_eq!(cached.outputs.len(), 1);
+        assert_eq!(cached.outputs[0].path, "out");
+
+        let restored = root.join(ForwardRelativePath::new("restored")?);
+        match cache.restore(&cached.outputs[0].entry, &restored, digest_config)? {
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => {
+                assert_eq!(f.digest, digest);
+                assert!(!f.is_executable);
+            }
+            _ => panic!("Expected a file"),
+        }
+        assert_eq!(fs_util::read_to_string(&restored)?, "contents");
+
+        // A blob that no longer matches its digest is a miss, and is dropped from the cache.
+        let blob = cache.blob_path(digest.data(), false)?;
+        make_writable(&blob)?;
+        fs_util::write(&blob, "corrupted")?;
+        assert!(cache.read(&action_digest, digest_config)?.is_none());
+        assert!(!fs_util::try_exists(&blob)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cleanup_evicts_least_recently_used() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let temp = ProjectRootTemp::new()?;
+        let root = temp.path().root();
+        let cache = LocalActionCache::new(
+            root.join(ForwardRelativePath::new("cache")?),
+            LocalActionCacheLinkMethod::Copy,
+            // Each action below uses a bit more than 1000 bytes, so a cleanup keeps two of them.
+            3000,
+        );
+
+        let write_action = |name: &str| -> anyhow::Result<ActionDigest> {
+            let contents = name.repeat(1000 / name.len());
+            let output = root.join(ForwardRelativePath::new(name)?);
+            fs_util::write(&output, &contents)?;
+            let digest = TrackedFileDigest::from_content(
+                contents.as_bytes(),
+                digest_config.cas_digest_config(),
+            );
+            let action_digest =
+                ActionDigest::from_content(name.as_bytes(), digest_config.cas_digest_config());
+            let entry = CachedActionResult {
+                outputs: vec![CachedOutput {
+                    path: name.to_owned(),
+                    entry: CachedEntry::File {
+                        digest: digest.to_string(),
+                        executable: false,
+                    },
+                }],
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+                execution_time_us: 1,
+            };
+            cache.write(&action_digest, &entry, vec![(output, digest, false)])?;
+            // Make sure modification times are ordered.
+            std::thread::sleep(Duration::from_millis(10));
+            Ok(action_digest)
+        };
+
+        let first = write_action("first")?;
+        let second = write_action("second")?;
+        // Using the first action makes the second one the least recently used.
+        assert!(cache.read(&first, digest_config)?.is_some());
+        std::thread::sleep(Duration::from_millis(10));
+        let third = write_action("third")?;
+
+        assert!(cache.read(&first, digest_config)?.is_some());
+        assert!(cache.read(&second, digest_config)?.is_none());
+        assert!(cache.read(&third, digest_config)?.is_some());
+        assert!(cache.size_bytes.lock().unwrap() <= 3000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_link_method() {
+        assert_eq!(
+            "hardlink".parse::<LocalActionCacheLinkMethod>().unwrap(),
+            LocalActionCacheLinkMethod::Hardlink
+        );
+        assert_eq!(
+            "copy".parse::<LocalActionCacheLinkMethod>().unwrap(),
+            LocalActionCacheLinkMethod::Copy
+        );
+        assert!("reflink".parse::<LocalActionCacheLinkMethod>().is_err());
+    }
+}
@@ -11,14 +11,23 @@ pub mod action_cache;
 pub mod caching;
 pub mod hybrid;
 pub mod local;
+pub mod local_action_cache;
 pub mod re;
 #[cfg(unix)]
 pub mod worker;
 #[cfg(not(unix))]
 pub mod worker {
+    use std::time::Duration;
+
+    #[derive(Clone, Debug, Default)]
+    pub struct WorkerPoolConfig {
+        pub idle_timeout: Option<Duration>,
+        pub max_rss_bytes: Option<u64>,
+    }
+
     pub struct WorkerPool {}
     impl WorkerPool {
-        pub fn new() -> WorkerPool {
+        pub fn new(_config: WorkerPoolConfig) -> WorkerPool {
             WorkerPool {}
         }
     }
@@ -12,31 +12,54 @@ use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::fs::File;
 use std::os::unix::ffi::OsStrExt;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Weak;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
+use async_trait::async_trait;
 use buck2_common::client_utils::get_channel_uds;
 use buck2_common::client_utils::retrying;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::file_name::FileName;
 use buck2_events::dispatch::get_dispatcher_opt;
+use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::execute::request::WorkerId;
 use buck2_execute::execute::request::WorkerSpec;
 use buck2_forkserver::run::prepare_command;
 use buck2_forkserver::run::GatherOutputStatus;
 use buck2_util::process::background_command;
+use buck2_util::process_stats::process_rss_bytes;
 use buck2_worker_proto::execute_command::EnvironmentEntry;
+use buck2_worker_proto::execute_event;
 use buck2_worker_proto::worker_client::WorkerClient;
+use buck2_worker_proto::CapabilitiesRequest;
+use buck2_worker_proto::CapabilitiesResponse;
+use buck2_worker_proto::ExecuteCancel;
 use buck2_worker_proto::ExecuteCommand;
+use buck2_worker_proto::ExecuteEvent;
 use buck2_worker_proto::ExecuteResponse;
+use dupe::Dupe;
 use futures::future::FutureExt;
+use futures::Stream;
+use futures::StreamExt;
 use tokio::process::Child;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::sync::Semaphore;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tonic::transport::Channel;
 
 use crate::executors::local::apply_local_execution_environment;
 
+/// The newest version of the worker protocol we support, sent in the capabilities handshake.
+const WORKER_PROTOCOL_VERSION: u32 = 1;
+
 async fn exec_spawn(
     exe: &str,
     args: impl IntoIterator<Item = impl AsRef<OsStr> + Send> + Send,
@@ -61,11 +84,12 @@ async fn spawn_worker(
     args: &[String],
     env: impl IntoIterator<Item = (OsString, OsString)>,
     worker: &WorkerSpec,
+    instance: u64,
     root: &AbsNormPathBuf,
 ) -> anyhow::Result<(WorkerCommandHandle, WorkerCleanupHandle)> {
     let dispatcher = get_dispatcher_opt().context("No dispatcher")?;
     // Use fixed length path at /tmp to avoid 108 character limit for unix domain sockets
-    let dir_name = format!("{}-{}", dispatcher.trace_id(), worker.id);
+    let dir_name = format!("{}-{}-{}", dispatcher.trace_id(), worker.id, instance);
     let worker_dir = AbsNormPathBuf::from("/tmp/buck2_worker".to_owned())?
         .join(FileName::unchecked_new(&dir_name));
     let socket_path = worker_dir.join(FileName::unchecked_new("socket"));
@@ -124,8 +148,6 @@ async fn spawn_worker(
             if let Err(e) = check_exit() {
                 futures::future::ready(Err(e)).left_future()
             } else {
-                // TODO(ctolliday) T153604304
-                // add handshake over grpc before returning a handle, to make sure the worker is responding
                 get_channel_uds(socket_path, false).right_future()
             }
         })
@@ -134,10 +156,27 @@ async fn spawn_worker(
     };
 
     tracing::info!("Connected to socket for spawned worker: {:?}", socket_path);
-    let client = WorkerClient::new(channel);
+    let mut client = WorkerClient::new(channel);
+    let capabilities = handshake(&mut client).await?;
+    tracing::info!("Worker capabilities: {:?}", capabilities);
+    let stream = if capabilities.multiplex {
+        Some(WorkerStream::open(client.clone()))
+    } else {
+        None
+    };
+    let concurrency = match capabilities.max_concurrent_requests {
+        0 => None,
+        n => Some(Semaphore::new(n as usize)),
+    };
+    let sandbox_root = worker_dir.join(FileName::unchecked_new("sandbox"));
     Ok((
         WorkerCommandHandle {
             client,
+            capabilities,
+            stream,
+            concurrency,
+            next_request_id: AtomicU64::new(0),
+            sandbox_root,
             stdout_path,
             stderr_path,
         },
@@ -145,64 +184,476 @@ async fn spawn_worker(
     ))
 }
 
-pub struct WorkerPool {
-    workers: Arc<tokio::sync::Mutex<HashMap<WorkerId, Arc<WorkerCommandHandle>>>>,
-    cleanup: Arc<parking_lot::Mutex<Vec<WorkerCleanupHandle>>>,
+/// Starts the process for a worker. Tests replace this to run workers in memory.
+#[async_trait]
+trait WorkerSpawner: Send + Sync {
+    async fn spawn(
+        &self,
+        worker_spec: &WorkerSpec,
+        env: Vec<(OsString, OsString)>,
+        instance: u64,
+        root: &AbsNormPathBuf,
+    ) -> anyhow::Result<(WorkerCommandHandle, WorkerCleanupHandle)>;
 }
 
-impl Drop for WorkerPool {
-    fn drop(&mut self) {
-        tracing::info!("Dropping WorkerPool");
-        let cleanup_handles: Vec<WorkerCleanupHandle> = self.cleanup.lock().drain(..).collect();
+struct ProcessWorkerSpawner;
+
+#[async_trait]
+impl WorkerSpawner for ProcessWorkerSpawner {
+    async fn spawn(
+        &self,
+        worker_spec: &WorkerSpec,
+        env: Vec<(OsString, OsString)>,
+        instance: u64,
+        root: &AbsNormPathBuf,
+    ) -> anyhow::Result<(WorkerCommandHandle, WorkerCleanupHandle)> {
+        spawn_worker(&worker_spec.exe, env, worker_spec, instance, root).await
+    }
+}
+
+/// Ask the worker what it supports, which also checks that it is responding.
+async fn handshake(client: &mut WorkerClient<Channel>) -> anyhow::Result<CapabilitiesResponse> {
+    let request = CapabilitiesRequest {
+        protocol_version: WORKER_PROTOCOL_VERSION,
+    };
+    let capabilities = match client.capabilities(request).await {
+        Ok(response) => response.into_inner(),
+        // Workers written before the handshake was added only support unary `Execute`.
+        Err(status) if status.code() == tonic::Code::Unimplemented => {
+            CapabilitiesResponse::default()
+        }
+        Err(status) => return Err(status).context("Worker capabilities handshake failed"),
+    };
+    if capabilities.protocol_version > WORKER_PROTOCOL_VERSION {
+        return Err(anyhow::anyhow!(
+            "Worker uses protocol version {}, but the newest supported version is {}",
+            capabilities.protocol_version,
+            WORKER_PROTOCOL_VERSION
+        ));
+    }
+    Ok(capabilities)
+}
+
+type PendingResponses =
+    Arc<parking_lot::Mutex<Option<HashMap<u64, oneshot::Sender<ExecuteResponse>>>>>;
+
+/// A single `ExecuteStream` to a worker, which commands are multiplexed over.
+struct WorkerStream {
+    events: mpsc::UnboundedSender<ExecuteEvent>,
+    /// Requests waiting for a response, by request id. `None` once the stream has closed.
+    pending: PendingResponses,
+}
+
+impl WorkerStream {
+    fn open(mut client: WorkerClient<Channel>) -> WorkerStream {
+        let (events, receiver) = mpsc::unbounded_channel();
+        let pending: PendingResponses = Arc::new(parking_lot::Mutex::new(Some(HashMap::new())));
+        // The call is made in the background since a worker might not send response headers
+        // until it has a response, and commands can be queued on `events` in the meantime.
         tokio::spawn({
+            let pending = pending.dupe();
             async move {
-                for WorkerCleanupHandle {
-                    mut child,
-                    socket_path,
-                } in cleanup_handles
+                match client
+                    .execute_stream(UnboundedReceiverStream::new(receiver))
+                    .await
                 {
-                    tracing::info!("Killing worker {:?} {:?}", child, socket_path);
-                    let _unused = child.kill().await;
-                    let _unused = fs_util::remove_file(&socket_path);
+                    Ok(responses) => dispatch_responses(responses.into_inner(), &pending).await,
+                    Err(status) => {
+                        tracing::warn!("Failed to open worker stream: {:?}", status);
+                        pending.lock().take();
+                    }
                 }
             }
         });
+        WorkerStream { events, pending }
+    }
+
+    /// Send `command` and wait for its response. If the returned future is dropped before
+    /// completing, the worker is told to cancel the command if it supports that.
+    async fn execute(
+        &self,
+        command: ExecuteCommand,
+        cancel: bool,
+    ) -> anyhow::Result<ExecuteResponse> {
+        let request_id = command.request_id;
+        let (sender, receiver) = oneshot::channel();
+        match &mut *self.pending.lock() {
+            Some(pending) => pending.insert(request_id, sender),
+            None => return Err(anyhow::anyhow!("Worker stream is closed")),
+        };
+        let mut guard = PendingRequest {
+            stream: self,
+            request_id,
+            cancel,
+            finished: false,
+        };
+        self.events
+            .send(ExecuteEvent {
+                data: Some(execute_event::Data::Command(command)),
+            })
+            .map_err(|_| anyhow::anyhow!("Worker stream is closed"))?;
+        let response = receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("Worker stream closed before responding"))?;
+        guard.finished = true;
+        Ok(response)
+    }
+}
+
+/// Route each response from the worker to the request waiting for it. Once the stream
+/// ends, every request still waiting fails.
+async fn dispatch_responses(
+    mut responses: impl Stream<Item = Result<ExecuteResponse, tonic::Status>> + Unpin,
+    pending: &PendingResponses,
+) {
+    while let Some(response) = responses.next().await {
+        match response {
+            Ok(response) => {
+                let sender = pending
+                    .lock()
+                    .as_mut()
+                    .and_then(|pending| pending.remove(&response.request_id));
+                match sender {
+                    Some(sender) => {
+                        let _ignored = sender.send(response);
+                    }
+                    None => tracing::debug!(
+                        "Ignoring worker response for unknown or cancelled request {}",
+                        response.request_id
+                    ),
+                }
+            }
+            Err(status) => {
+                tracing::warn!("Worker stream failed: {:?}", status);
+                break;
+            }
+        }
+    }
+    pending.lock().take();
+}
+
+/// A request sent on a `WorkerStream` that is waiting for its response.
+struct PendingRequest<'a> {
+    stream: &'a WorkerStream,
+    request_id: u64,
+    cancel: bool,
+    finished: bool,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        if let Some(pending) = &mut *self.stream.pending.lock() {
+            pending.remove(&self.request_id);
+        }
+        if self.cancel {
+            tracing::info!("Cancelling worker request {}", self.request_id);
+            let _ignored = self.stream.events.send(ExecuteEvent {
+                data: Some(execute_event::Data::Cancel(ExecuteCancel {
+                    request_id: self.request_id,
+                })),
+            });
+        }
+    }
+}
+
+/// Settings that apply to every worker in a `WorkerPool`.
+#[derive(Clone, Debug, Default)]
+pub struct WorkerPoolConfig {
+    /// Stop workers that have not run a command for this long.
+    pub idle_timeout: Option<Duration>,
+    /// Restart workers whose resident memory is above this after running a command.
+    pub max_rss_bytes: Option<u64>,
+}
+
+/// How many times a command is retried on a new worker if the worker running it crashes.
+const MAX_CRASH_RETRIES: usize = 1;
+
+/// The workers for a single `WorkerSpec`.
+#[derive(Default)]
+struct WorkerSlot {
+    /// Limits the commands in flight for this spec, if it has a concurrency limit.
+    concurrency: Option<Arc<Semaphore>>,
+    /// The running worker, if any. Workers that have been replaced stay alive until the
+    /// commands they are running complete.
+    current: Option<Arc<WorkerProcess>>,
+}
+
+type WorkerSlots = tokio::sync::Mutex<HashMap<WorkerId, WorkerSlot>>;
+
+/// A spawned worker, which is killed when the last reference to it is dropped.
+struct WorkerProcess {
+    worker_id: WorkerId,
+    pid: u32,
+    handle: WorkerCommandHandle,
+    cleanup: parking_lot::Mutex<WorkerCleanupHandle>,
+    /// Commands currently running on this worker.
+    in_flight: AtomicUsize,
+    /// When a command last finished on this worker, or when it was spawned.
+    last_used: parking_lot::Mutex<Instant>,
+    /// Reported as the reason the worker stopped once it is dropped.
+    stop_kind: parking_lot::Mutex<buck2_data::WorkerLifecycleKind>,
+    dispatcher: Option<EventDispatcher>,
+}
+
+impl WorkerProcess {
+    fn report(&self, kind: buck2_data::WorkerLifecycleKind, rss_bytes: Option<u64>) {
+        if let Some(dispatcher) = &self.dispatcher {
+            dispatcher.instant_event(buck2_data::WorkerLifecycle {
+                worker_id: self.worker_id.to_string(),
+                pid: self.pid,
+                kind: kind as i32,
+                rss_bytes,
+            });
+        }
+    }
+
+    fn has_exited(&self) -> bool {
+        matches!(self.cleanup.lock().child.try_wait(), Ok(Some(_)))
+    }
+
+    /// Whether the worker has exited. A worker that is crashing can close its connection
+    /// slightly before it exits, so this waits a little for it to exit.
+    async fn has_crashed(&self) -> bool {
+        for _ in 0..10 {
+            if self.has_exited() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        self.has_exited()
+    }
+
+    fn is_idle(&self, timeout: Duration) -> bool {
+        self.in_flight.load(Ordering::Acquire) == 0 && self.last_used.lock().elapsed() >= timeout
+    }
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let kind = *self.stop_kind.lock();
+        let rss_bytes = process_rss_bytes(self.pid);
+        let cleanup = self.cleanup.get_mut();
+        tracing::info!(
+            "Stopping worker {:?} {:?} ({:?})",
+            cleanup.child,
+            cleanup.socket_path,
+            kind
+        );
+        // Dropping the child after this lets tokio reap it in the background.
+        let _unused = cleanup.child.start_kill();
+        let _unused = fs_util::remove_file(&cleanup.socket_path);
+        self.report(kind, rss_bytes);
+    }
+}
+
+/// Counts a command as running on a worker until dropped.
+struct InFlightGuard<'a>(&'a WorkerProcess);
+
+impl<'a> InFlightGuard<'a> {
+    fn new(worker: &'a WorkerProcess) -> Self {
+        worker.in_flight.fetch_add(1, Ordering::AcqRel);
+        Self(worker)
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.last_used.lock() = Instant::now();
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub struct WorkerPool {
+    config: WorkerPoolConfig,
+    spawner: Box<dyn WorkerSpawner>,
+    workers: Arc<WorkerSlots>,
+    /// Number of workers spawned so far, used to give each one its own directory.
+    spawned: AtomicU64,
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Workers are killed when they're dropped along with `workers`, or once the commands
+        // still running on them finish.
+        tracing::info!("Dropping WorkerPool");
     }
 }
 
 impl WorkerPool {
-    pub fn new() -> WorkerPool {
-        tracing::info!("Creating new WorkerPool");
+    pub fn new(config: WorkerPoolConfig) -> WorkerPool {
+        Self::with_spawner(config, Box::new(ProcessWorkerSpawner))
+    }
+
+    fn with_spawner(config: WorkerPoolConfig, spawner: Box<dyn WorkerSpawner>) -> WorkerPool {
+        tracing::info!("Creating new WorkerPool with {:?}", config);
+        let workers = Arc::new(WorkerSlots::default());
+        if let Some(idle_timeout) = config.idle_timeout {
+            tokio::spawn(evict_idle_workers(Arc::downgrade(&workers), idle_timeout));
+        }
         WorkerPool {
-            workers: Arc::new(tokio::sync::Mutex::new(HashMap::default())),
-            cleanup: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            config,
+            spawner,
+            workers,
+            spawned: AtomicU64::new(0),
+        }
+    }
+
+    /// Run a command on the worker for `worker_spec`, spawning it if necessary. If the worker
+    /// crashes while running the command, the command is retried on a new worker.
+    pub async fn exec_cmd(
+        &self,
+        worker_spec: &WorkerSpec,
+        args: &[String],
+        env: Vec<(OsString, OsString)>,
+        root: &AbsNormPathBuf,
+    ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)> {
+        let concurrency = self.concurrency(worker_spec).await;
+        let _permit = match &concurrency {
+            Some(concurrency) => Some(concurrency.acquire().await?),
+            None => None,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let worker = self
+                .get_or_create_worker(worker_spec, env.clone(), root)
+                .await?;
+            let response = {
+                let _in_flight = InFlightGuard::new(&worker);
+                worker.handle.exec_cmd(args, env.clone()).await
+            };
+            let err = match response {
+                Ok(response) => {
+                    self.recycle_if_too_large(&worker).await;
+                    return Ok(WorkerCommandHandle::command_result(response));
+                }
+                Err(err) => err,
+            };
+            if !worker.has_crashed().await {
+                return Ok(worker.handle.worker_failed(err));
+            }
+            tracing::warn!("Worker crashed while running a command: {:#}", err);
+            self.remove(&worker, buck2_data::WorkerLifecycleKind::WorkerCrashed)
+                .await;
+            if attempt == MAX_CRASH_RETRIES {
+                return Ok(worker.handle.worker_failed(err));
+            }
+            attempt += 1;
         }
     }
 
-    pub async fn get_or_create_worker(
+    async fn concurrency(&self, worker_spec: &WorkerSpec) -> Option<Arc<Semaphore>> {
+        let mut workers = self.workers.lock().await;
+        let slot = workers.entry(worker_spec.id).or_default();
+        if slot.concurrency.is_none() {
+            slot.concurrency = worker_spec
+                .concurrency
+                .map(|concurrency| Arc::new(Semaphore::new(concurrency)));
+        }
+        slot.concurrency.dupe()
+    }
+
+    async fn get_or_create_worker(
         &self,
         worker_spec: &WorkerSpec,
-        env: impl IntoIterator<Item = (OsString, OsString)>,
+        env: Vec<(OsString, OsString)>,
         root: &AbsNormPathBuf,
-    ) -> anyhow::Result<Arc<WorkerCommandHandle>> {
+    ) -> anyhow::Result<Arc<WorkerProcess>> {
         let mut workers = self.workers.lock().await;
-        if let Some(worker_handle) = workers.get(&worker_spec.id) {
-            Ok(worker_handle.clone())
+        let slot = workers.entry(worker_spec.id).or_default();
+        if let Some(worker) = &slot.current {
+            Ok(worker.dupe())
         } else {
             // TODO(ctolliday) keep track of workers that fail to start and don't try to spawn them again
             // TODO(ctolliday) do not lock the entire workers map while spawning/connecting
-            let (worker_handle, cleanup_handle) =
-                spawn_worker(&worker_spec.exe, env, worker_spec, root).await?;
-            let worker_handle = Arc::new(worker_handle);
-            workers.insert(worker_spec.id, worker_handle.clone());
-            self.cleanup.lock().push(cleanup_handle);
-            Ok(worker_handle)
+            let instance = self.spawned.fetch_add(1, Ordering::Relaxed);
+            let (handle, cleanup) = self.spawner.spawn(worker_spec, env, instance, root).await?;
+            let worker = Arc::new(WorkerProcess {
+                worker_id: worker_spec.id,
+                pid: cleanup.child.id().unwrap_or_default(),
+                handle,
+                cleanup: parking_lot::Mutex::new(cleanup),
+                in_flight: AtomicUsize::new(0),
+                last_used: parking_lot::Mutex::new(Instant::now()),
+                stop_kind: parking_lot::Mutex::new(buck2_data::WorkerLifecycleKind::WorkerShutdown),
+                dispatcher: get_dispatcher_opt(),
+            });
+            worker.report(buck2_data::WorkerLifecycleKind::WorkerSpawned, None);
+            slot.current = Some(worker.dupe());
+            Ok(worker)
+        }
+    }
+
+    /// Stop using `worker` for new commands. It is killed once the commands running on it finish.
+    async fn remove(&self, worker: &Arc<WorkerProcess>, kind: buck2_data::WorkerLifecycleKind) {
+        let mut workers = self.workers.lock().await;
+        if let Some(slot) = workers.get_mut(&worker.worker_id) {
+            if slot
+                .current
+                .as_ref()
+                .map_or(false, |current| Arc::ptr_eq(current, worker))
+            {
+                *worker.stop_kind.lock() = kind;
+                slot.current = None;
+            }
+        }
+    }
+
+    async fn recycle_if_too_large(&self, worker: &Arc<WorkerProcess>) {
+        if let Some(max_rss_bytes) = self.config.max_rss_bytes {
+            if let Some(rss_bytes) = process_rss_bytes(worker.pid) {
+                if rss_bytes > max_rss_bytes {
+                    tracing::info!(
+                        "Recycling worker {} using {} bytes, above the limit of {}",
+                        worker.pid,
+                        rss_bytes,
+                        max_rss_bytes
+                    );
+                    self.remove(worker, buck2_data::WorkerLifecycleKind::WorkerRssRecycled)
+                        .await;
+                }
+            }
         }
     }
 }
 
+/// Periodically stop workers that have been idle for longer than `idle_timeout`, until the
+/// pool is dropped.
+async fn evict_idle_workers(workers: Weak<WorkerSlots>, idle_timeout: Duration) {
+    let mut interval = tokio::time::interval(idle_timeout / 2);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let Some(workers) = workers.upgrade() else {
+            return;
+        };
+        // Collect the evicted workers so that they're dropped after releasing the lock.
+        let mut evicted = Vec::new();
+        for slot in workers.lock().await.values_mut() {
+            if let Some(worker) = &slot.current {
+                if worker.is_idle(idle_timeout) {
+                    *worker.stop_kind.lock() = buck2_data::WorkerLifecycleKind::WorkerIdleEvicted;
+                    evicted.extend(slot.current.take());
+                }
+            }
+        }
+        drop(evicted);
+    }
+}
+
 pub struct WorkerCommandHandle {
     client: WorkerClient<Channel>,
+    capabilities: CapabilitiesResponse,
+    /// Only present if the worker supports multiplexing.
+    stream: Option<WorkerStream>,
+    /// Limits the requests in flight, if the worker asked for that.
+    concurrency: Option<Semaphore>,
+    next_request_id: AtomicU64,
+    /// Directory holding per-request sandboxes, if the worker asked for them.
+    sandbox_root: AbsNormPathBuf,
     stdout_path: AbsNormPathBuf,
     stderr_path: AbsNormPathBuf,
 }
@@ -213,11 +664,11 @@ pub struct WorkerCleanupHandle {
 }
 
 impl WorkerCommandHandle {
-    pub async fn exec_cmd(
+    async fn exec_cmd(
         &self,
         args: &[String],
         env: Vec<(OsString, OsString)>,
-    ) -> (GatherOutputStatus, Vec<u8>, Vec<u8>) {
+    ) -> anyhow::Result<ExecuteResponse> {
         tracing::info!(
             "Sending worker command:\nExecuteCommand {{ argv: {:?}, env: {:?} }}\n",
             args,
@@ -232,33 +683,436 @@ impl WorkerCommandHandle {
             })
             .collect();
 
-        let request = ExecuteCommand { argv, env };
-        let response = self.client.clone().execute(request).await;
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .execute(ExecuteCommand {
+                argv,
+                env,
+                request_id,
+                sandbox_dir: String::new(),
+            })
+            .await?;
+        tracing::info!("Worker response:\n{:?}\n", response);
+        Ok(response)
+    }
 
-        match response {
-            Ok(response) => {
-                let exec_response: ExecuteResponse = response.into_inner();
-                tracing::info!("Worker response:\n{:?}\n", exec_response);
-                (
-                    GatherOutputStatus::Finished {
-                        exit_code: exec_response.exit_code,
-                        execution_stats: None,
-                    },
-                    vec![],
-                    exec_response.stderr.into(),
-                )
+    fn command_result(response: ExecuteResponse) -> (GatherOutputStatus, Vec<u8>, Vec<u8>) {
+        let status = if response.cancelled {
+            GatherOutputStatus::Cancelled
+        } else {
+            GatherOutputStatus::Finished {
+                exit_code: response.exit_code,
+                execution_stats: None,
+            }
+        };
+        (status, vec![], response.stderr.into())
+    }
+
+    fn worker_failed(&self, err: anyhow::Error) -> (GatherOutputStatus, Vec<u8>, Vec<u8>) {
+        (
+            GatherOutputStatus::SpawnFailed(format!(
+                "Error sending ExecuteCommand to worker: {:?}, see worker logs:\n{:?}\n{:?}",
+                err, self.stdout_path, self.stderr_path,
+            )),
+            // stdout/stderr logs for worker are for multiple commands, probably do not want to dump contents here
+            vec![],
+            vec![],
+        )
+    }
+
+    async fn execute(&self, mut command: ExecuteCommand) -> anyhow::Result<ExecuteResponse> {
+        let _permit = match &self.concurrency {
+            Some(concurrency) => Some(concurrency.acquire().await?),
+            None => None,
+        };
+        let _sandbox = if self.capabilities.sandbox {
+            let sandbox = self
+                .sandbox_root
+                .join(FileName::new(&command.request_id.to_string())?);
+            fs_util::create_dir_all(&sandbox)?;
+            command.sandbox_dir = sandbox.to_str()?.to_owned();
+            Some(SandboxGuard(sandbox))
+        } else {
+            None
+        };
+        let response = match &self.stream {
+            Some(stream) => stream.execute(command, self.capabilities.cancel).await?,
+            None => self.client.clone().execute(command).await?.into_inner(),
+        };
+        Ok(response)
+    }
+}
+
+/// Deletes a request's sandbox directory when the request finishes or is cancelled.
+struct SandboxGuard(AbsNormPathBuf);
+
+impl Drop for SandboxGuard {
+    fn drop(&mut self) {
+        if let Err(e) = fs_util::remove_dir_all(&self.0) {
+            tracing::warn!("Failed to remove worker sandbox {:?}: {:#}", self.0, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use async_trait::async_trait;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+    use buck2_execute::execute::request::WorkerId;
+    use buck2_execute::execute::request::WorkerSpec;
+    use buck2_forkserver::run::GatherOutputStatus;
+    use buck2_worker_proto::execute_event;
+    use buck2_worker_proto::worker_client::WorkerClient;
+    use buck2_worker_proto::CapabilitiesResponse;
+    use buck2_worker_proto::ExecuteCancel;
+    use buck2_worker_proto::ExecuteCommand;
+    use buck2_worker_proto::ExecuteEvent;
+    use buck2_worker_proto::ExecuteResponse;
+    use dupe::Dupe;
+    use futures::FutureExt;
+    use starlark::values::Value;
+    use tokio::sync::mpsc;
+    use tokio::sync::Semaphore;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+    use tonic::transport::Endpoint;
+
+    use super::dispatch_responses;
+    use super::PendingResponses;
+    use super::WorkerCleanupHandle;
+    use super::WorkerCommandHandle;
+    use super::WorkerPool;
+    use super::WorkerPoolConfig;
+    use super::WorkerSpawner;
+    use super::WorkerStream;
+
+    fn command(request_id: u64) -> ExecuteCommand {
+        ExecuteCommand {
+            argv: vec![],
+            env: vec![],
+            request_id,
+            sandbox_dir: String::new(),
+        }
+    }
+
+    fn response(request_id: u64, exit_code: i32) -> ExecuteResponse {
+        ExecuteResponse {
+            exit_code,
+            stderr: String::new(),
+            request_id,
+            cancelled: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiplexed_requests() -> anyhow::Result<()> {
+        let (events, mut worker_events) = mpsc::unbounded_channel();
+        let (worker_responses, responses) = mpsc::unbounded_channel();
+        let pending: PendingResponses = Arc::new(parking_lot::Mutex::new(Some(HashMap::new())));
+        let dispatch = tokio::spawn({
+            let pending = pending.dupe();
+            async move { dispatch_responses(UnboundedReceiverStream::new(responses), &pending).await }
+        });
+        let stream = WorkerStream { events, pending };
+
+        let first = stream.execute(command(1), true);
+        let second = stream.execute(command(2), true);
+        futures::pin_mut!(first);
+        futures::pin_mut!(second);
+        assert!((&mut first).now_or_never().is_none());
+        assert!((&mut second).now_or_never().is_none());
+
+        // Responses can arrive in any order.
+        worker_responses.send(Ok(response(2, 20)))?;
+        assert_eq!(20, second.await?.exit_code);
+        worker_responses.send(Ok(response(1, 10)))?;
+        assert_eq!(10, first.await?.exit_code);
+
+        // Dropping a request before it completes cancels it.
+        let third = stream.execute(command(3), true);
+        futures::pin_mut!(third);
+        assert!((&mut third).now_or_never().is_none());
+        drop(third);
+
+        let mut sent = Vec::new();
+        while let Ok(event) = worker_events.try_recv() {
+            sent.push(event);
+        }
+        assert_eq!(
+            vec![
+                ExecuteEvent {
+                    data: Some(execute_event::Data::Command(command(1)))
+                },
+                ExecuteEvent {
+                    data: Some(execute_event::Data::Command(command(2)))
+                },
+                ExecuteEvent {
+                    data: Some(execute_event::Data::Command(command(3)))
+                },
+                ExecuteEvent {
+                    data: Some(execute_event::Data::Cancel(ExecuteCancel { request_id: 3 }))
+                },
+            ],
+            sent
+        );
+
+        // Requests fail once the worker closes the stream.
+        let fourth = stream.execute(command(4), true);
+        futures::pin_mut!(fourth);
+        assert!((&mut fourth).now_or_never().is_none());
+        drop(worker_responses);
+        dispatch.await?;
+        assert!(fourth.await.is_err());
+        assert!(stream.execute(command(5), true).await.is_err());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct FakeWorkerState {
+        spawned: u64,
+        /// Commands received by the workers that they haven't responded to yet.
+        running: usize,
+        max_running: usize,
+    }
+
+    /// Spawns workers that run in memory, and respond to each command with their instance
+    /// number as the exit code.
+    struct FakeWorkerSpawner {
+        state: Arc<parking_lot::Mutex<FakeWorkerState>>,
+        /// The first `crashes` workers exit without responding to the first command they get.
+        crashes: u64,
+        /// Workers hold on to each command until they get a permit.
+        gate: Arc<Semaphore>,
+    }
+
+    impl FakeWorkerSpawner {
+        fn new(crashes: u64, gate: Arc<Semaphore>) -> Self {
+            Self {
+                state: Default::default(),
+                crashes,
+                gate,
             }
-            Err(err) => {
-                (
-                    GatherOutputStatus::SpawnFailed(format!(
-                        "Error sending ExecuteCommand to worker: {:?}, see worker logs:\n{:?}\n{:?}",
-                        err, self.stdout_path, self.stderr_path,
-                    )),
-                    // stdout/stderr logs for worker are for multiple commands, probably do not want to dump contents here
-                    vec![],
-                    vec![],
-                )
+        }
+    }
+
+    #[async_trait]
+    impl WorkerSpawner for FakeWorkerSpawner {
+        async fn spawn(
+            &self,
+            _worker_spec: &WorkerSpec,
+            _env: Vec<(OsString, OsString)>,
+            instance: u64,
+            _root: &AbsNormPathBuf,
+        ) -> anyhow::Result<(WorkerCommandHandle, WorkerCleanupHandle)> {
+            let crash = {
+                let mut state = self.state.lock();
+                state.spawned += 1;
+                instance < self.crashes
+            };
+            // The process stands in for the worker, so that the pool can tell whether it exited.
+            let child = if crash {
+                tokio::process::Command::new("true").spawn()?
+            } else {
+                tokio::process::Command::new("sleep").arg("600").spawn()?
+            };
+
+            let (events, mut commands) = mpsc::unbounded_channel();
+            let (responses, received) = mpsc::unbounded_channel();
+            let pending: PendingResponses = Arc::new(parking_lot::Mutex::new(Some(HashMap::new())));
+            tokio::spawn({
+                let pending = pending.dupe();
+                async move { dispatch_responses(UnboundedReceiverStream::new(received), &pending).await }
+            });
+            tokio::spawn({
+                let state = self.state.dupe();
+                let gate = self.gate.dupe();
+                async move {
+                    while let Some(event) = commands.recv().await {
+                        let Some(execute_event::Data::Command(command)) = event.data else {
+                            continue;
+                        };
+                        if crash {
+                            // Dropping `responses` closes the stream.
+                            return;
+                        }
+                        {
+                            let mut state = state.lock();
+                            state.running += 1;
+                            state.max_running = state.max_running.max(state.running);
+                        }
+                        tokio::spawn({
+                            let state = state.dupe();
+                            let gate = gate.dupe();
+                            let responses = responses.clone();
+                            async move {
+                                let _permit = gate.acquire().await;
+                                state.lock().running -= 1;
+                                let _ignored = responses
+                                    .send(Ok(response(command.request_id, instance as i32)));
+                            }
+                        });
+                    }
+                }
+            });
+
+            let dir = AbsNormPathBuf::from(format!("/tmp/buck2_fake_worker_{}", instance))?;
+            Ok((
+                WorkerCommandHandle {
+                    client: WorkerClient::new(
+                        Endpoint::from_static("http://127.0.0.1:1").connect_lazy(),
+                    ),
+                    capabilities: CapabilitiesResponse {
+                        multiplex: true,
+                        ..Default::default()
+                    },
+                    stream: Some(WorkerStream { events, pending }),
+                    concurrency: None,
+                    next_request_id: AtomicU64::new(0),
+                    sandbox_root: dir.clone(),
+                    stdout_path: dir.clone(),
+                    stderr_path: dir.clone(),
+                },
+                WorkerCleanupHandle {
+                    child,
+                    socket_path: dir,
+                },
+            ))
+        }
+    }
+
+    fn worker_spec(concurrency: Option<usize>) -> WorkerSpec {
+        WorkerSpec {
+            id: WorkerId(Value::new_none().identity()),
+            exe: vec!["worker".to_owned()],
+            concurrency,
+        }
+    }
+
+    async fn exec(
+        pool: &WorkerPool,
+        worker_spec: &WorkerSpec,
+    ) -> anyhow::Result<GatherOutputStatus> {
+        let root = AbsNormPathBuf::from("/tmp".to_owned())?;
+        let (status, _, _) = pool
+            .exec_cmd(worker_spec, &["cmd".to_owned()], Vec::new(), &root)
+            .await?;
+        Ok(status)
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_crash() -> anyhow::Result<()> {
+        let spawner = FakeWorkerSpawner::new(1, Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)));
+        let state = spawner.state.dupe();
+        let pool = WorkerPool::with_spawner(WorkerPoolConfig::default(), Box::new(spawner));
+        let worker_spec = worker_spec(None);
+
+        // The first worker crashes, so the command runs again on a second one.
+        assert_matches!(
+            exec(&pool, &worker_spec).await?,
+            GatherOutputStatus::Finished { exit_code: 1, .. }
+        );
+        assert_eq!(2, state.lock().spawned);
+
+        // Which is kept for later commands.
+        assert_matches!(
+            exec(&pool, &worker_spec).await?,
+            GatherOutputStatus::Finished { exit_code: 1, .. }
+        );
+        assert_eq!(2, state.lock().spawned);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_repeated_crashes() -> anyhow::Result<()> {
+        let spawner = FakeWorkerSpawner::new(2, Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)));
+        let state = spawner.state.dupe();
+        let pool = WorkerPool::with_spawner(WorkerPoolConfig::default(), Box::new(spawner));
+
+        assert_matches!(
+            exec(&pool, &worker_spec(None)).await?,
+            GatherOutputStatus::SpawnFailed(..)
+        );
+        assert_eq!(2, state.lock().spawned);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_idle_eviction() -> anyhow::Result<()> {
+        let spawner = FakeWorkerSpawner::new(0, Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)));
+        let state = spawner.state.dupe();
+        let pool = WorkerPool::with_spawner(
+            WorkerPoolConfig {
+                idle_timeout: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+            Box::new(spawner),
+        );
+        let worker_spec = worker_spec(None);
+        let is_running = || async {
+            pool.workers
+                .lock()
+                .await
+                .get(&worker_spec.id)
+                .map_or(false, |slot| slot.current.is_some())
+        };
+
+        assert_matches!(
+            exec(&pool, &worker_spec).await?,
+            GatherOutputStatus::Finished { exit_code: 0, .. }
+        );
+        assert!(is_running().await);
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!is_running().await);
+
+        // The next command starts a new worker.
+        assert_matches!(
+            exec(&pool, &worker_spec).await?,
+            GatherOutputStatus::Finished { exit_code: 1, .. }
+        );
+        assert_eq!(2, state.lock().spawned);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() -> anyhow::Result<()> {
+        let gate = Arc::new(Semaphore::new(0));
+        let spawner = FakeWorkerSpawner::new(0, gate.dupe());
+        let state = spawner.state.dupe();
+        let pool = Arc::new(WorkerPool::with_spawner(
+            WorkerPoolConfig::default(),
+            Box::new(spawner),
+        ));
+
+        let commands: Vec<_> = (0..3)
+            .map(|_| {
+                let pool = pool.dupe();
+                tokio::spawn(async move { exec(&pool, &worker_spec(Some(2))).await })
+            })
+            .collect();
+
+        // Only two of the commands reach the worker while it's holding on to them.
+        for _ in 0..100 {
+            if state.lock().running == 2 {
+                break;
             }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(2, state.lock().running);
+
+        gate.add_permits(3);
+        for command in commands {
+            assert_matches!(command.await??, GatherOutputStatus::Finished { .. });
         }
+        assert_eq!(2, state.lock().max_running);
+        assert_eq!(1, state.lock().spawned);
+        Ok(())
     }
 }
@@ -0,0 +1,139 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Keeps the artifacts tracked by the deferred materializer within a disk budget, by deleting
+//! the least recently used artifacts that can be fetched again from the CAS.
+
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use dupe::Dupe;
+
+use crate::materializers::deferred::clean_path;
+use crate::materializers::deferred::ArtifactMaterializationData;
+use crate::materializers::deferred::ArtifactMaterializationStage;
+use crate::materializers::deferred::ArtifactTree;
+use crate::materializers::deferred::DeferredMaterializerCommandProcessor;
+use crate::materializers::deferred::ExistingFutures;
+use crate::materializers::deferred::IoHandler;
+use crate::materializers::deferred::Processing;
+use crate::materializers::deferred::ProcessingFuture;
+
+pub struct DiskBudgetConfiguration {
+    /// How often to check the size of the tracked artifacts.
+    pub frequency: std::time::Duration,
+    /// Artifacts are evicted while the tracked artifacts use more than this.
+    pub max_bytes: u64,
+}
+
+impl ArtifactMaterializationData {
+    /// Whether this artifact is being deleted to stay within the disk budget. It is removed from
+    /// the tree once the deletion finishes, and until then must not be treated as present.
+    pub(super) fn is_evicting(&self) -> bool {
+        matches!(
+            (&self.stage, &self.processing),
+            (
+                ArtifactMaterializationStage::Materialized { .. },
+                Processing::Active {
+                    future: ProcessingFuture::Cleaning(..),
+                    ..
+                }
+            )
+        )
+    }
+}
+
+/// Find the artifacts to evict so that the artifacts in `tree` use at most `max_bytes`, least
+/// recently accessed first. Returns the artifacts to evict, the number of bytes that frees, and
+/// the number of bytes the tracked artifacts still use afterwards.
+///
+/// Only artifacts that were downloaded from the CAS by a previous daemon and are not being
+/// processed are evicted: they are only ever used again after being declared again, at which
+/// point they are re-fetched. Artifacts declared by this daemon may still be depended on by DICE,
+/// and other artifacts cannot be recreated without rerunning the actions that produced them, so
+/// those are never evicted, even if that means the budget cannot be met.
+fn find_artifacts_to_evict(
+    tree: &ArtifactTree,
+    max_bytes: u64,
+) -> (Vec<ProjectRelativePathBuf>, u64, u64) {
+    let mut total_bytes: u64 = 0;
+    let mut candidates = Vec::new();
+    for (path, data) in tree.iter_with_paths() {
+        if data.is_evicting() {
+            continue;
+        }
+        if let ArtifactMaterializationStage::Materialized {
+            metadata,
+            last_access_time,
+            active,
+            cas_backed,
+        } = &data.stage
+        {
+            total_bytes += metadata.size();
+            if !*active && *cas_backed && matches!(data.processing, Processing::Done(..)) {
+                candidates.push((*last_access_time, metadata.size(), path));
+            }
+        }
+    }
+
+    let mut evicted = Vec::new();
+    let mut evicted_bytes = 0;
+    if total_bytes > max_bytes {
+        candidates.sort_by_key(|(last_access_time, ..)| *last_access_time);
+        for (_, size, path) in candidates {
+            if total_bytes - evicted_bytes <= max_bytes {
+                break;
+            }
+            evicted_bytes += size;
+            evicted.push(ProjectRelativePathBuf::from(path));
+        }
+    }
+    (evicted, evicted_bytes, total_bytes - evicted_bytes)
+}
+
+impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
+    /// Start deleting least recently used artifacts until the tracked artifacts fit in
+    /// `max_bytes`. The deleted artifacts are removed from the tree and the materializer state
+    /// once their deletion finishes. If there are not enough artifacts that can safely be
+    /// deleted, this only logs a warning.
+    pub(super) fn enforce_disk_budget(&mut self, max_bytes: u64) {
+        let (paths, bytes, remaining_bytes) = find_artifacts_to_evict(&self.tree, max_bytes);
+        if remaining_bytes > max_bytes {
+            tracing::warn!(
+                "Artifacts use {} bytes after eviction, which exceeds the disk budget of {} bytes: \
+                the rest are in use by this daemon or cannot be fetched again from the CAS",
+                remaining_bytes,
+                max_bytes
+            );
+        }
+        if paths.is_empty() {
+            return;
+        }
+        tracing::info!(
+            "Evicting {} artifacts using {} bytes to stay within the disk budget of {} bytes",
+            paths.len(),
+            bytes,
+            max_bytes
+        );
+
+        for path in paths {
+            let version = self.version_tracker.next();
+            let future = ProcessingFuture::Cleaning(clean_path(
+                &self.io,
+                path.clone(),
+                version,
+                self.command_sender.dupe(),
+                ExistingFutures::empty(),
+                &self.rt,
+                self.cancellations,
+            ));
+            if let Some(data) = self.tree.prefix_get_mut(&mut path.iter()) {
+                data.processing = Processing::Active { future, version };
+            }
+        }
+    }
+}
@@ -8,6 +8,7 @@
  */
 
 mod clean_stale;
+mod disk_budget;
 mod extension;
 mod file_tree;
 mod io_handler;
@@ -96,6 +97,7 @@ use tokio::sync::oneshot::error::TryRecvError;
 use tokio::time::Interval;
 use tracing::instrument;
 
+pub use crate::materializers::deferred::disk_budget::DiskBudgetConfiguration;
 use crate::materializers::deferred::extension::ExtensionCommand;
 use crate::materializers::deferred::file_tree::FileTree;
 use crate::materializers::deferred::io_handler::DefaultIoHandler;
@@ -162,6 +164,8 @@ pub struct DeferredMaterializerConfigs {
     pub materialize_final_artifacts: bool,
     pub defer_write_actions: bool,
     pub ttl_refresh: TtlRefreshConfiguration,
+    /// If set, evict least recently used artifacts to keep buck-out within a budget.
+    pub disk_budget: Option<DiskBudgetConfiguration>,
 }
 
 pub struct TtlRefreshConfiguration {
@@ -569,6 +573,8 @@ enum ArtifactMaterializationStage {
         /// Should not be deleted without invalidating DICE nodes, which currently
         /// means killing the daemon.
         active: bool,
+        /// The artifact was downloaded from the CAS, so it can be fetched again if it is deleted.
+        cas_backed: bool,
     },
 }
 
@@ -904,7 +910,7 @@ impl DeferredMaterializer {
 
         let mut tree = ArtifactTree::new();
         if let Some(sqlite_state) = sqlite_state {
-            for (path, (metadata, last_access_time)) in sqlite_state.into_iter() {
+            for (path, (metadata, last_access_time, cas_backed)) in sqlite_state.into_iter() {
                 tree.insert(
                     path.iter().map(|f| f.to_owned()),
                     Box::new(ArtifactMaterializationData {
@@ -913,6 +919,7 @@ impl DeferredMaterializer {
                             metadata,
                             last_access_time,
                             active: false,
+                            cas_backed,
                         },
                         processing: Processing::Done(Version(0)),
                     }),
@@ -960,9 +967,11 @@ impl DeferredMaterializer {
 
                     let cancellations = CancellationContext::never_cancelled();
 
-                    rt.block_on(
-                        command_processor(cancellations).run(command_receiver, configs.ttl_refresh),
-                    );
+                    rt.block_on(command_processor(cancellations).run(
+                        command_receiver,
+                        configs.ttl_refresh,
+                        configs.disk_budget,
+                    ));
                 }
             })
             .context("Cannot start materializer thread")?;
@@ -1014,12 +1023,14 @@ struct CommandStream<T: 'static> {
     high_priority: UnboundedReceiver<MaterializerCommand<T>>,
     low_priority: UnboundedReceiver<LowPriorityMaterializerCommand>,
     refresh_ttl_ticker: Option<Interval>,
+    disk_budget_ticker: Option<Interval>,
 }
 
 enum Op<T: 'static> {
     Command(MaterializerCommand<T>),
     LowPriorityCommand(LowPriorityMaterializerCommand),
     RefreshTtls,
+    EnforceDiskBudget,
 }
 
 impl<T: 'static> Stream for CommandStream<T> {
@@ -1042,6 +1053,12 @@ impl<T: 'static> Stream for CommandStream<T> {
             }
         }
 
+        if let Some(ticker) = this.disk_budget_ticker.as_mut() {
+            if let Poll::Ready(..) = ticker.poll_tick(cx) {
+                return Poll::Ready(Some(Op::EnforceDiskBudget));
+            }
+        }
+
         // We can never be done because we never drop the senders, so let's not bother.
 
         Poll::Pending
@@ -1056,6 +1073,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
         mut self,
         commands: MaterializerReceiver<T>,
         ttl_refresh: TtlRefreshConfiguration,
+        disk_budget: Option<DiskBudgetConfiguration>,
     ) {
         let MaterializerReceiver {
             high_priority,
@@ -1072,10 +1090,18 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
             None
         };
 
+        let disk_budget_ticker = disk_budget.as_ref().map(|disk_budget| {
+            tokio::time::interval_at(
+                tokio::time::Instant::now() + disk_budget.frequency,
+                disk_budget.frequency,
+            )
+        });
+
         let mut stream = CommandStream {
             high_priority,
             low_priority,
             refresh_ttl_ticker,
+            disk_budget_ticker,
         };
 
         while let Some(op) = stream.next().await {
@@ -1127,6 +1153,11 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                         }
                     }
                 }
+                Op::EnforceDiskBudget => {
+                    if let Some(disk_budget) = &disk_budget {
+                        self.enforce_disk_budget(disk_budget.max_bytes);
+                    }
+                }
             }
         }
     }
@@ -1208,7 +1239,8 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                 version,
                 result,
             } => {
-                self.tree.cleanup_finished(path, version, result);
+                self.tree
+                    .cleanup_finished(path, version, result, self.sqlite_db.as_mut());
             }
         }
     }
@@ -1287,6 +1319,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
             path,
             &metadata,
             Utc::now(),
+            false,
             "materializer_declare_existing_error",
         );
 
@@ -1298,6 +1331,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                     metadata,
                     last_access_time: Utc::now(),
                     active: true,
+                    cas_backed: false,
                 },
                 processing: Processing::Done(self.version_tracker.next()),
             }),
@@ -1317,6 +1351,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                 ArtifactMaterializationStage::Materialized {
                     metadata,
                     last_access_time,
+                    cas_backed,
                     ..
                 } => {
                     // NOTE: This is for testing performance when hitting mismatches with disk
@@ -1331,6 +1366,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
 
                     if path_iter.next().is_none()
                         && metadata.matches_entry(value.entry())
+                        && !data.is_evicting()
                         && !force_mismatch
                     {
                         // In this case, the entry declared matches the already materialized
@@ -1345,6 +1381,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             metadata: metadata.dupe(),
                             last_access_time: *last_access_time,
                             active: true,
+                            cas_backed: *cas_backed,
                         };
                         data.deps = deps;
 
@@ -1433,6 +1470,11 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
             return false;
         }
 
+        if data.is_evicting() {
+            tracing::trace!("evicting");
+            return false;
+        }
+
         let is_match = match &data.stage {
             ArtifactMaterializationStage::Materialized { metadata, .. } => {
                 let is_match = value.entry();
@@ -1727,11 +1769,12 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             tracing::debug!("artifact is already materialized");
                             None
                         }
-                        ArtifactMaterializationStage::Declared {
-                            entry,
-                            method: _method,
-                        } => {
+                        ArtifactMaterializationStage::Declared { entry, method } => {
                             let metadata = ArtifactMetadata::new(entry);
+                            let cas_backed = matches!(
+                                **method,
+                                ArtifactMaterializationMethod::CasDownload { .. }
+                            );
                             // NOTE: We only insert this artifact if there isn't an in-progress cleanup
                             // future on this path.
                             on_materialization(
@@ -1741,6 +1784,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                                 &artifact_path,
                                 &metadata,
                                 timestamp,
+                                cas_backed,
                                 "materializer_finished_error",
                             );
 
@@ -1748,6 +1792,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                                 metadata,
                                 last_access_time: timestamp,
                                 active: true,
+                                cas_backed,
                             })
                         }
                     };
@@ -1775,12 +1820,13 @@ fn on_materialization(
     path: &ProjectRelativePath,
     metadata: &ArtifactMetadata,
     timestamp: DateTime<Utc>,
+    cas_backed: bool,
     error_name: &'static str,
 ) {
     if let Some(sqlite_db) = sqlite_db {
         if let Err(e) = sqlite_db
             .materializer_state_table()
-            .insert(path, metadata, timestamp)
+            .insert(path, metadata, timestamp, cas_backed)
         {
             soft_error!(error_name, e.context(log_buffer.clone()), quiet: true).unwrap();
         }
@@ -1877,12 +1923,13 @@ impl ArtifactTree {
         }
     }
 
-    #[instrument(level = "debug", skip(self, result), fields(path = %artifact_path))]
+    #[instrument(level = "debug", skip(self, result, sqlite_db), fields(path = %artifact_path))]
     fn cleanup_finished(
         &mut self,
         artifact_path: ProjectRelativePathBuf,
         version: Version,
         result: Result<(), SharedMaterializingError>,
+        sqlite_db: Option<&mut MaterializerStateSqliteDb>,
     ) {
         match self
             .prefix_get_mut(&mut artifact_path.iter())
@@ -1896,6 +1943,17 @@ impl ArtifactTree {
                     return;
                 }
 
+                if info.is_evicting() {
+                    // The artifact was deleted to stay within the disk budget. If that failed we
+                    // don't know what is left of it, so forget about it either way.
+                    if let Err(e) =
+                        self.invalidate_paths_and_collect_futures(vec![artifact_path], sqlite_db)
+                    {
+                        soft_error!("evict_finished", e, quiet: true).unwrap();
+                    }
+                    return;
+                }
+
                 if result.is_err() {
                     // Leave it alone, don't keep retrying.
                 } else {
@@ -661,4 +661,92 @@ mod state_machine {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_enforce_disk_budget() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+
+        let (mut dm, mut channel) = make_processor(digest_config, Default::default());
+
+        let file = |content: &[u8]| {
+            ArtifactValue::file(FileMetadata {
+                digest: TrackedFileDigest::from_content(content, digest_config.cas_digest_config()),
+                is_executable: false,
+            })
+        };
+
+        let now = Utc::now();
+        let mut insert = |path: &ProjectRelativePathBuf,
+                          value: ArtifactValue,
+                          age: i64,
+                          active: bool,
+                          cas_backed: bool| {
+            dm.tree.insert(
+                path.iter().map(|f| f.to_owned()),
+                Box::new(ArtifactMaterializationData {
+                    deps: None,
+                    stage: ArtifactMaterializationStage::Materialized {
+                        metadata: ArtifactMetadata::new(value.entry()),
+                        last_access_time: now - Duration::hours(age),
+                        active,
+                        cas_backed,
+                    },
+                    processing: Processing::Done(Version(0)),
+                }),
+            );
+        };
+
+        let active = make_path("active");
+        let local = make_path("local");
+        let oldest = make_path("oldest");
+        let older = make_path("older");
+        let newest = make_path("newest");
+        insert(&active, file(&[0; 5]), 5, true, true);
+        insert(&local, file(&[0; 3]), 4, false, false);
+        insert(&oldest, file(&[0; 10]), 3, false, true);
+        insert(&older, file(&[0; 20]), 2, false, true);
+        insert(&newest, file(&[0; 40]), 1, false, true);
+
+        // Within the budget, nothing is evicted.
+        dm.enforce_disk_budget(78);
+        assert_eq!(dm.io.take_log(), &[]);
+
+        // Artifacts declared by this daemon or not downloaded from the CAS are older, but are
+        // never evicted.
+        dm.enforce_disk_budget(68);
+        assert_eq!(dm.io.take_log(), &[(Op::Clean, oldest.clone())]);
+
+        // While the eviction is in progress, the artifact is not considered present or evicted
+        // again.
+        assert!(!dm.match_artifact(oldest.clone(), file(&[0; 10])));
+        dm.enforce_disk_budget(68);
+        assert_eq!(dm.io.take_log(), &[]);
+
+        let future = match &dm.tree.prefix_get(&mut oldest.iter()).unwrap().processing {
+            Processing::Active {
+                future: ProcessingFuture::Cleaning(future),
+                ..
+            } => future.clone(),
+            _ => panic!("Expected a cleaning future"),
+        };
+        future.await?;
+
+        while let Ok(cmd) = channel.low_priority.try_recv() {
+            dm.process_one_low_priority_command(cmd);
+        }
+
+        assert!(dm.tree.prefix_get(&mut oldest.iter()).is_none());
+        assert!(dm.tree.prefix_get(&mut older.iter()).is_some());
+
+        // If the budget cannot be met, only the artifacts that can be fetched again are evicted.
+        dm.enforce_disk_budget(0);
+        assert_eq!(
+            dm.io.take_log(),
+            &[(Op::Clean, older.clone()), (Op::Clean, newest.clone())]
+        );
+        assert!(dm.tree.prefix_get(&mut active.iter()).is_some());
+        assert!(dm.tree.prefix_get(&mut local.iter()).is_some());
+
+        Ok(())
+    }
 }
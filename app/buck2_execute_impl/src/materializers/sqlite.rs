@@ -52,12 +52,17 @@ pub struct MaterializerStateIdentity(String);
 /// materializer state sqlite db schema! If you forget to bump this version,
 /// then you can fix forward by bumping the `buck2.sqlite_materializer_state_version`
 /// buckconfig in the project root's .buckconfig.
-pub const DB_SCHEMA_VERSION: u64 = 6;
+pub const DB_SCHEMA_VERSION: u64 = 7;
 
 const STATE_TABLE_NAME: &str = "materializer_state";
 const IDENTITY_KEY: &str = "timestamp_on_initialization";
 
-pub type MaterializerState = Vec<(ProjectRelativePathBuf, (ArtifactMetadata, DateTime<Utc>))>;
+/// The materialized artifacts, with their last access time and whether they were downloaded from
+/// the CAS.
+pub type MaterializerState = Vec<(
+    ProjectRelativePathBuf,
+    (ArtifactMetadata, DateTime<Utc>, bool),
+)>;
 
 #[derive(Error, Debug, PartialEq, Eq)]
 pub(crate) enum ArtifactMetadataSqliteConversionError {
@@ -317,7 +322,8 @@ impl MaterializerStateSqliteTable {
                 file_is_executable      INTEGER NULL DEFAULT NULL,
                 symlink_target          TEXT NULL DEFAULT NULL,
                 last_access_time        INTEGER NOT NULL,
-                directory_size          INTEGER NULL DEFAULT NULL
+                directory_size          INTEGER NULL DEFAULT NULL,
+                cas_backed              INTEGER NOT NULL DEFAULT 0
             )",
             STATE_TABLE_NAME,
         );
@@ -334,11 +340,12 @@ impl MaterializerStateSqliteTable {
         path: &ProjectRelativePath,
         metadata: &ArtifactMetadata,
         timestamp: DateTime<Utc>,
+        cas_backed: bool,
     ) -> anyhow::Result<()> {
         let entry: ArtifactMetadataSqliteEntry = metadata.into();
         static SQL: Lazy<String> = Lazy::new(|| {
             format!(
-                "INSERT INTO {} (path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                "INSERT INTO {} (path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time, cas_backed) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 STATE_TABLE_NAME
             )
         });
@@ -357,6 +364,7 @@ impl MaterializerStateSqliteTable {
                     entry.symlink_target,
                     entry.directory_size,
                     timestamp.timestamp(),
+                    cas_backed,
                 ],
             )
             .with_context(|| {
@@ -396,7 +404,7 @@ impl MaterializerStateSqliteTable {
     ) -> anyhow::Result<MaterializerState> {
         static SQL: Lazy<String> = Lazy::new(|| {
             format!(
-                "SELECT path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time FROM {}",
+                "SELECT path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time, cas_backed FROM {}",
                 STATE_TABLE_NAME,
             )
         });
@@ -406,7 +414,7 @@ impl MaterializerStateSqliteTable {
         let result = stmt
             .query_map(
                 [],
-                |row| -> rusqlite::Result<(String, ArtifactMetadataSqliteEntry, i64)> {
+                |row| -> rusqlite::Result<(String, ArtifactMetadataSqliteEntry, i64, bool)> {
                     Ok((
                         row.get(0)?,
                         ArtifactMetadataSqliteEntry::new(
@@ -419,6 +427,7 @@ impl MaterializerStateSqliteTable {
                             row.get(7)?,
                         ),
                         row.get(8)?,
+                        row.get(9)?,
                     ))
                 },
             )?
@@ -427,9 +436,9 @@ impl MaterializerStateSqliteTable {
 
         result
             .into_try_map(
-                |(path, entry, last_access_time)| -> anyhow::Result<(
+                |(path, entry, last_access_time, cas_backed)| -> anyhow::Result<(
                     ProjectRelativePathBuf,
-                    (ArtifactMetadata, DateTime<Utc>),
+                    (ArtifactMetadata, DateTime<Utc>, bool),
                 )> {
                     let path = ProjectRelativePathBuf::unchecked_new(path);
                     let metadata = convert_artifact_metadata(entry, digest_config)?;
@@ -437,7 +446,7 @@ impl MaterializerStateSqliteTable {
                         .timestamp_opt(last_access_time, 0)
                         .single()
                         .with_context(|| "invalid timestamp")?;
-                    Ok((path, (metadata, timestamp)))
+                    Ok((path, (metadata, timestamp, cas_backed)))
                 },
             )
             .with_context(|| format!("error reading row of sqlite table {}", STATE_TABLE_NAME))
@@ -847,17 +856,23 @@ mod tests {
                 (
                     ArtifactMetadata(DirectoryEntry::Dir(dir_metadata)),
                     now_seconds(),
+                    true,
                 ),
             ),
             (
                 ProjectRelativePath::unchecked_new("b/c").to_owned(),
-                (ArtifactMetadata(DirectoryEntry::Leaf(file)), now_seconds()),
+                (
+                    ArtifactMetadata(DirectoryEntry::Leaf(file)),
+                    now_seconds(),
+                    false,
+                ),
             ),
             (
                 ProjectRelativePath::unchecked_new("d").to_owned(),
                 (
                     ArtifactMetadata(DirectoryEntry::Leaf(symlink)),
                     now_seconds(),
+                    false,
                 ),
             ),
             (
@@ -865,12 +880,15 @@ mod tests {
                 (
                     ArtifactMetadata(DirectoryEntry::Leaf(external_symlink)),
                     now_seconds(),
+                    false,
                 ),
             ),
         ]);
 
         for (path, metadata) in artifacts.iter() {
-            table.insert(path, &metadata.0, metadata.1).unwrap();
+            table
+                .insert(path, &metadata.0, metadata.1, metadata.2)
+                .unwrap();
         }
 
         let state = table.read_all(digest_config).unwrap();
@@ -978,7 +996,7 @@ mod tests {
             assert_metadata_matches(db.tables.last_read_by_table.read_all()?, &metadatas[0]);
 
             db.materializer_state_table()
-                .insert(&path, &artifact_metadata, timestamp)
+                .insert(&path, &artifact_metadata, timestamp, false)
                 .unwrap();
         }
 
@@ -989,7 +1007,7 @@ mod tests {
             assert_matches!(
                 loaded_state,
                 Ok(v) => {
-                    assert_eq!(v, vec![(path.clone(), (artifact_metadata.clone(), timestamp))]);
+                    assert_eq!(v, vec![(path.clone(), (artifact_metadata.clone(), timestamp, false))]);
                 }
             );
             assert_metadata_matches(db.tables.created_by_table.read_all()?, &metadatas[0]);
@@ -1020,7 +1038,7 @@ mod tests {
             assert_metadata_matches(db.tables.last_read_by_table.read_all()?, &metadatas[2]);
 
             db.materializer_state_table()
-                .insert(&path, &artifact_metadata, timestamp)
+                .insert(&path, &artifact_metadata, timestamp, false)
                 .unwrap();
         }
 
@@ -1035,7 +1053,7 @@ mod tests {
             assert_matches!(
                 loaded_state,
                 Ok(v) => {
-                    assert_eq!(v, vec![(path, (artifact_metadata, timestamp))]);
+                    assert_eq!(v, vec![(path, (artifact_metadata, timestamp, false))]);
                 }
             );
             assert_metadata_matches(db.tables.created_by_table.read_all()?, &metadatas[2]);
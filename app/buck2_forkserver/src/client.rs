@@ -54,6 +54,7 @@ impl ForkserverClient {
         &self,
         req: buck2_forkserver_proto::CommandRequest,
         cancel: C,
+        on_stderr: impl FnMut(&[u8]) + Send,
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
     where
         C: Future<Output = ()> + Send + 'static,
@@ -76,7 +77,7 @@ impl ForkserverClient {
             .context("Error dispatching command to Forkserver")?
             .into_inner();
         let stream = decode_event_stream(stream);
-        decode_command_event_stream(stream).await
+        decode_command_event_stream(stream, on_stderr).await
     }
 
     pub async fn set_log_filter(&self, log_filter: String) -> anyhow::Result<()> {
@@ -252,8 +252,11 @@ where
     Ok(CommandEventStream::new(status, stdio).right_stream())
 }
 
+/// Collects the output of a command. `on_stderr` is called with each chunk of stderr as it
+/// arrives, which lets callers report on commands that are still running.
 pub(crate) async fn decode_command_event_stream<S>(
     stream: S,
+    mut on_stderr: impl FnMut(&[u8]),
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     S: Stream<Item = anyhow::Result<CommandEvent>>,
@@ -266,7 +269,10 @@ where
     while let Some(event) = stream.try_next().await? {
         match event {
             CommandEvent::Stdout(bytes) => stdout.extend(&bytes),
-            CommandEvent::Stderr(bytes) => stderr.extend(&bytes),
+            CommandEvent::Stderr(bytes) => {
+                on_stderr(&bytes);
+                stderr.extend(&bytes);
+            }
             CommandEvent::Exit(exit) => return Ok((exit, stdout, stderr)),
         }
     }
@@ -279,6 +285,7 @@ where
 pub async fn gather_output<T>(
     cmd: Command,
     cancellation: T,
+    on_stderr: impl FnMut(&[u8]) + Send,
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
@@ -292,7 +299,7 @@ where
         DefaultStatusDecoder,
         DefaultKillProcess,
     )?;
-    decode_command_event_stream(stream).await
+    decode_command_event_stream(stream, on_stderr).await
 }
 
 /// Dependency injection for kill. We use this in testing.
@@ -414,7 +421,8 @@ mod tests {
         };
         cmd.args(["-c", "echo hello"]);
 
-        let (status, stdout, stderr) = gather_output(cmd, futures::future::pending()).await?;
+        let (status, stdout, stderr) =
+            gather_output(cmd, futures::future::pending(), |_| {}).await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
         assert_eq!(str::from_utf8(&stdout)?.trim(), "hello");
         assert_eq!(stderr, b"");
@@ -422,6 +430,24 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_gather_output_observes_stderr() -> anyhow::Result<()> {
+        let mut cmd = background_command("sh");
+        cmd.args(["-c", "echo hello >&2; echo bye >&2"]);
+
+        let mut observed = Vec::new();
+        let (status, _stdout, stderr) = gather_output(cmd, futures::future::pending(), |bytes| {
+            observed.extend_from_slice(bytes)
+        })
+        .await?;
+        assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
+        assert_eq!(str::from_utf8(&stderr)?, "hello\nbye\n");
+        assert_eq!(observed, stderr);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_gather_does_not_wait_for_children() -> anyhow::Result<()> {
         // If we wait for sleep, this will time out.
@@ -443,6 +469,7 @@ mod tests {
         let (status, stdout, stderr) = gather_output(
             cmd,
             timeout_into_cancellation(Some(Duration::from_secs(timeout))),
+            |_| {},
         )
         .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
@@ -467,6 +494,7 @@ mod tests {
         let (status, stdout, stderr) = gather_output(
             cmd,
             timeout_into_cancellation(Some(Duration::from_secs(timeout))),
+            |_| {},
         )
         .await?;
         assert!(matches!(status, GatherOutputStatus::TimedOut(..)));
@@ -537,8 +565,12 @@ mod tests {
         // This command will spawn 2 subprocesses (subshells) and print the PID of the 2nd shell.
         let mut cmd = background_command("sh");
         cmd.arg("-c").arg("( ( echo $$ && sleep 1000 ) )");
-        let (_status, stdout, _stderr) =
-            gather_output(cmd, timeout_into_cancellation(Some(Duration::from_secs(1)))).await?;
+        let (_status, stdout, _stderr) = gather_output(
+            cmd,
+            timeout_into_cancellation(Some(Duration::from_secs(1))),
+            |_| {},
+        )
+        .await?;
         let pid = i32::from_str(std::str::from_utf8(&stdout)?.trim())?;
 
         for _ in 0..10 {
@@ -585,7 +617,8 @@ mod tests {
 
         let mut cmd = background_command("sh");
         cmd.arg("-c").arg("kill -KILL \"$$\"");
-        let (status, _stdout, _stderr) = gather_output(cmd, futures::future::pending()).await?;
+        let (status, _stdout, _stderr) =
+            gather_output(cmd, futures::future::pending(), |_| {}).await?;
 
         assert_matches!(
             status,
@@ -654,7 +687,7 @@ mod tests {
             },
         )?;
 
-        let (status, _stdout, _stderr) = decode_command_event_stream(stream).await?;
+        let (status, _stdout, _stderr) = decode_command_event_stream(stream, |_| {}).await?;
         assert!(matches!(status, GatherOutputStatus::TimedOut(..)));
 
         assert!(*killed.lock().unwrap());
@@ -0,0 +1,85 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Limits on the bytecode instructions executed and the heap bytes allocated by a single
+//! Starlark evaluation, so that a runaway `.bzl` file fails instead of hanging the daemon.
+//!
+//! They are configured in the root cell buckconfig:
+//!
+//! ```ini
+//! [starlark]
+//! loading_max_instructions = 100000000
+//! loading_max_heap_bytes = 1000000000
+//! analysis_max_instructions = 100000000
+//! analysis_max_heap_bytes = 1000000000
+//! ```
+//!
+//! Every limit is unset by default.
+
+use async_trait::async_trait;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use dice::DiceComputations;
+use dupe::Dupe;
+use starlark::eval::Evaluator;
+
+/// Instruction and heap limits for one kind of evaluation.
+#[derive(Debug, Default, Clone, Copy, Dupe, Eq, PartialEq)]
+pub struct StarlarkEvalLimits {
+    pub max_instructions: Option<u64>,
+    pub max_heap_bytes: Option<usize>,
+}
+
+impl StarlarkEvalLimits {
+    /// Set the limits on a freshly created evaluator.
+    pub fn initialize(&self, eval: &mut Evaluator) {
+        if let Some(max) = self.max_instructions {
+            eval.set_max_instructions(max);
+        }
+        if let Some(max) = self.max_heap_bytes {
+            eval.set_max_heap_bytes(max);
+        }
+    }
+}
+
+async fn get_eval_limits(
+    ctx: &DiceComputations,
+    prefix: &str,
+) -> anyhow::Result<StarlarkEvalLimits> {
+    let cells = ctx.get_cell_resolver().await?;
+    let root = cells.root_cell();
+    Ok(StarlarkEvalLimits {
+        max_instructions: ctx
+            .parse_legacy_config_property(root, "starlark", &format!("{}_max_instructions", prefix))
+            .await?,
+        max_heap_bytes: ctx
+            .parse_legacy_config_property(root, "starlark", &format!("{}_max_heap_bytes", prefix))
+            .await?,
+    })
+}
+
+#[async_trait]
+pub trait HasStarlarkEvalLimits {
+    /// Limits for evaluating build files, `PACKAGE` files and the `.bzl` files they load.
+    async fn get_loading_eval_limits(&self) -> anyhow::Result<StarlarkEvalLimits>;
+
+    /// Limits for running rule implementations.
+    async fn get_analysis_eval_limits(&self) -> anyhow::Result<StarlarkEvalLimits>;
+}
+
+#[async_trait]
+impl HasStarlarkEvalLimits for DiceComputations {
+    async fn get_loading_eval_limits(&self) -> anyhow::Result<StarlarkEvalLimits> {
+        get_eval_limits(self, "loading").await
+    }
+
+    async fn get_analysis_eval_limits(&self) -> anyhow::Result<StarlarkEvalLimits> {
+        get_eval_limits(self, "analysis").await
+    }
+}
@@ -68,6 +68,8 @@ struct LoadedModuleData {
     loaded_modules: LoadedModules,
     #[derivative(Debug = "ignore")]
     env: FrozenModule,
+    /// The key the module is stored under in the on-disk module cache, if it can be cached.
+    cache_key: Option<String>,
 }
 
 impl LoadedModule {
@@ -80,6 +82,22 @@ impl LoadedModule {
             path,
             loaded_modules,
             env,
+            cache_key: None,
+        }))
+    }
+
+    /// A module that is stored in the on-disk module cache under `cache_key`.
+    pub fn new_cached(
+        path: OwnedStarlarkModulePath,
+        loaded_modules: LoadedModules,
+        env: FrozenModule,
+        cache_key: String,
+    ) -> Self {
+        Self(Arc::new(LoadedModuleData {
+            path,
+            loaded_modules,
+            env,
+            cache_key: Some(cache_key),
         }))
     }
 
@@ -98,6 +116,10 @@ impl LoadedModule {
     pub fn env(&self) -> &FrozenModule {
         &self.0.env
     }
+
+    pub fn cache_key(&self) -> Option<&str> {
+        self.0.cache_key.as_deref()
+    }
 }
 
 pub struct InterpreterFileLoader {
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use buck2_common::result::SharedError;
+use buck2_common::result::SharedResult;
+use buck2_core::bzl::ImportPath;
+use buck2_core::cells::build_file_cell::BuildFileCell;
+use buck2_core::cells::name::CellName;
+use dice::DiceTransaction;
+use dupe::Dupe;
+use starlark::docs::DocItem;
+use starlark::typing::OracleDocs;
+
+use crate::file_loader::LoadedModule;
+use crate::file_type::StarlarkFileType;
+use crate::import_paths::HasImportPaths;
+use crate::load_module::InterpreterCalculation;
+use crate::load_module::INTERPRETER_CALCULATION_IMPL;
+use crate::path::StarlarkPath;
+
+/// The "globals" for a path are defined by its CellName and its path type.
+///
+/// To compute the globals we need the Rust-level globals, the prelude, and
+/// any pre-imported paths. Figuring out the names in those requires evaluating
+/// Starlark code, which might fail.
+pub struct CachedGlobals<'a> {
+    dice: &'a DiceTransaction,
+    cached: HashMap<(CellName, StarlarkFileType), SharedResult<Arc<HashSet<String>>>>,
+    oracles: HashMap<(CellName, StarlarkFileType), SharedResult<Arc<OracleDocs>>>,
+}
+
+impl<'a> CachedGlobals<'a> {
+    pub fn new(dice: &'a DiceTransaction) -> CachedGlobals<'a> {
+        Self {
+            dice,
+            cached: HashMap::new(),
+            oracles: HashMap::new(),
+        }
+    }
+
+    async fn load_module(&self, path: &ImportPath) -> anyhow::Result<LoadedModule> {
+        self.dice.get_loaded_module_from_import_path(path).await
+    }
+
+    /// The prelude, unless we are in the prelude cell and not a build file.
+    async fn prelude(
+        &self,
+        cell: CellName,
+        path: StarlarkFileType,
+    ) -> anyhow::Result<Option<LoadedModule>> {
+        match INTERPRETER_CALCULATION_IMPL
+            .get()?
+            .prelude_import(self.dice)
+            .await?
+        {
+            Some(prelude) if path == StarlarkFileType::Buck || prelude.cell() != cell => {
+                Ok(Some(self.load_module(&prelude).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The module pre-loaded into every file in the cell, if any.
+    async fn root_import(&self, cell: CellName) -> anyhow::Result<Option<LoadedModule>> {
+        let import_paths = self
+            .dice
+            .import_paths_for_cell(BuildFileCell::new(cell))
+            .await?;
+        match import_paths.root_import() {
+            Some(root) => Ok(Some(self.load_module(root).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn compute_names(
+        &self,
+        cell: CellName,
+        path: StarlarkFileType,
+    ) -> anyhow::Result<HashSet<String>> {
+        let mut res = HashSet::new();
+
+        // Find the information from the globals
+        let globals = INTERPRETER_CALCULATION_IMPL
+            .get()?
+            .global_env_for_file_type(self.dice, path)
+            .await?;
+        for x in globals.names() {
+            res.insert(x.as_str().to_owned());
+        }
+
+        // Next grab the prelude
+        if let Some(env) = self.prelude(cell, path).await? {
+            for x in env.env().names() {
+                res.insert(x.as_str().to_owned());
+            }
+            if path == StarlarkFileType::Buck {
+                if let Some(native) = env.env().get_option("native")? {
+                    let native = native.value();
+                    for attr in native.dir_attr() {
+                        res.insert(attr.to_owned());
+                    }
+                }
+            }
+        }
+
+        // Now grab the pre-load things
+        if let Some(env) = self.root_import(cell).await? {
+            for x in env.env().names() {
+                res.insert(x.as_str().to_owned());
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Like `compute_names`, but with the types of the names, taken from their documentation.
+    async fn compute_oracle(
+        &self,
+        cell: CellName,
+        path: StarlarkFileType,
+    ) -> anyhow::Result<OracleDocs> {
+        let globals = INTERPRETER_CALCULATION_IMPL
+            .get()?
+            .global_env_for_file_type(self.dice, path)
+            .await?;
+        let mut res = OracleDocs::new_object(&globals.documentation());
+
+        if let Some(env) = self.prelude(cell, path).await? {
+            res.add_object(&DocItem::Module(env.env().documentation()));
+            if path == StarlarkFileType::Buck {
+                if let Some(native) = env.env().get_option("native")? {
+                    if let Some(docs) = native.value().documentation() {
+                        res.add_object(&docs);
+                    }
+                }
+            }
+        }
+
+        if let Some(env) = self.root_import(cell).await? {
+            res.add_object(&DocItem::Module(env.env().documentation()));
+        }
+
+        Ok(res)
+    }
+
+    pub async fn get_names(
+        &mut self,
+        path: &StarlarkPath<'_>,
+    ) -> SharedResult<Arc<HashSet<String>>> {
+        let path_type = path.file_type();
+        let cell = path.cell();
+        if let Some(res) = self.cached.get(&(cell, path_type)) {
+            return res.dupe();
+        }
+        let res = match self.compute_names(cell, path_type).await {
+            Ok(v) => Ok(Arc::new(v)),
+            Err(e) => Err(SharedError::new(e)),
+        };
+        self.cached.insert((cell, path_type), res.dupe());
+        res
+    }
+
+    /// The types of the globals for a path, for use when type checking it.
+    pub async fn get_oracle(&mut self, path: &StarlarkPath<'_>) -> SharedResult<Arc<OracleDocs>> {
+        let path_type = path.file_type();
+        let cell = path.cell();
+        if let Some(res) = self.oracles.get(&(cell, path_type)) {
+            return res.dupe();
+        }
+        let res = match self.compute_oracle(cell, path_type).await {
+            Ok(v) => Ok(Arc::new(v)),
+            Err(e) => Err(SharedError::new(e)),
+        };
+        self.oracles.insert((cell, path_type), res.dupe());
+        res
+    }
+}
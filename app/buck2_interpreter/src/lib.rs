@@ -30,6 +30,7 @@ pub mod factory;
 pub mod file_loader;
 pub mod file_type;
 pub mod functions;
+pub mod globals;
 pub mod globspec;
 pub mod import_paths;
 pub mod load_module;
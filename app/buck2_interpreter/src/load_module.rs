@@ -18,8 +18,10 @@ use starlark::environment::Globals;
 use crate::file_loader::LoadedModule;
 use crate::file_loader::ModuleDeps;
 use crate::file_type::StarlarkFileType;
+use crate::path::OwnedStarlarkModulePath;
 use crate::path::PackageFilePath;
 use crate::path::StarlarkModulePath;
+use crate::path::StarlarkPath;
 
 #[async_trait]
 pub trait InterpreterCalculationImpl: Send + Sync + 'static {
@@ -50,6 +52,14 @@ pub trait InterpreterCalculationImpl: Send + Sync + 'static {
     ) -> anyhow::Result<Globals>;
 
     async fn prelude_import(&self, ctx: &DiceComputations) -> anyhow::Result<Option<ImportPath>>;
+
+    /// Resolve the module string of a `load()` in `path` to the module it refers to.
+    async fn resolve_load(
+        &self,
+        ctx: &DiceComputations,
+        path: StarlarkPath<'_>,
+        load: &str,
+    ) -> anyhow::Result<OwnedStarlarkModulePath>;
 }
 
 pub static INTERPRETER_CALCULATION_IMPL: LateBinding<&'static dyn InterpreterCalculationImpl> =
@@ -7,12 +7,14 @@
  * of this source tree.
  */
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use buck2_core::provider::id::ProviderId;
 use dupe::Dupe;
 use itertools::Itertools;
 use starlark::any::ProvidesStaticType;
+use starlark::docs::Doc;
 use starlark::docs::DocFunction;
 use starlark::docs::DocItem;
 use starlark::docs::DocMember;
@@ -21,6 +23,7 @@ use starlark::docs::DocProperty;
 use starlark::docs::DocReturn;
 use starlark::docs::DocString;
 use starlark::docs::DocType;
+use starlark::docs::Identifier;
 use starlark::environment::GlobalsBuilder;
 use starlark::values::ValueLike;
 
@@ -41,6 +44,36 @@ pub trait ProviderCallableLike {
         }
     }
 
+    /// The fields of the providers created by this callable, for the typechecker.
+    /// Provider fields are untyped, so only their names and docs are known.
+    fn provider_type_documentation(
+        &self,
+        fields: &[&str],
+        field_docs: &[Option<DocString>],
+    ) -> Option<Doc> {
+        let id = self.id()?;
+        let members = std::iter::zip(fields, field_docs)
+            .map(|(name, docs)| {
+                let prop = DocProperty {
+                    docs: docs.clone(),
+                    typ: None,
+                };
+                ((*name).to_owned(), DocMember::Property(prop))
+            })
+            .collect();
+        Some(Doc {
+            id: Identifier {
+                name: id.name.clone(),
+                location: None,
+            },
+            item: DocItem::Object(DocObject {
+                docs: None,
+                members,
+            }),
+            custom_attrs: HashMap::new(),
+        })
+    }
+
     fn provider_callable_documentation(
         &self,
         creator: Option<for<'a> fn(&'a mut GlobalsBuilder)>,
@@ -30,6 +30,7 @@ use buck2_interpreter::file_loader::ModuleDeps;
 use buck2_interpreter::file_type::StarlarkFileType;
 use buck2_interpreter::load_module::InterpreterCalculationImpl;
 use buck2_interpreter::load_module::INTERPRETER_CALCULATION_IMPL;
+use buck2_interpreter::path::OwnedStarlarkModulePath;
 use buck2_interpreter::path::PackageFilePath;
 use buck2_interpreter::path::StarlarkModulePath;
 use buck2_interpreter::path::StarlarkPath;
@@ -41,6 +42,7 @@ use buck2_node::nodes::frontend::TARGET_GRAPH_CALCULATION_IMPL;
 use derive_more::Display;
 use dice::DiceComputations;
 use dice::Key;
+use dice::StorageType;
 use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 use smallvec::SmallVec;
@@ -120,6 +122,12 @@ impl TargetGraphCalculationImpl for TargetGraphCalculationInstance {
             fn validity(x: &Self::Value) -> bool {
                 x.is_ok()
             }
+
+            fn storage_type() -> StorageType {
+                // evaluating a build file is deterministic given the files it reads, and the
+                // target graph is a large part of the daemon's memory
+                StorageType::EvictableLastN(1)
+            }
         }
 
         ctx.compute(&InterpreterResultsKey(package.dupe()))
@@ -210,6 +218,18 @@ impl InterpreterCalculationImpl for InterpreterCalculationInstance {
             .prelude_import()
             .cloned())
     }
+
+    async fn resolve_load(
+        &self,
+        ctx: &DiceComputations,
+        path: StarlarkPath<'_>,
+        load: &str,
+    ) -> anyhow::Result<OwnedStarlarkModulePath> {
+        ctx.get_interpreter_calculator(path.cell(), path.build_file_cell())
+            .await?
+            .resolve_load(path, load)
+            .await
+    }
 }
 
 pub struct IntepreterResultsKeyActivationData {
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -32,6 +34,7 @@ use buck2_core::cells::name::CellName;
 use buck2_core::package::PackageLabel;
 use buck2_events::dispatch::span;
 use buck2_events::dispatch::span_async;
+use buck2_interpreter::dice::starlark_limits::HasStarlarkEvalLimits;
 use buck2_interpreter::dice::starlark_provider::with_starlark_eval_provider;
 use buck2_interpreter::file_loader::LoadedModule;
 use buck2_interpreter::file_loader::ModuleDeps;
@@ -56,6 +59,9 @@ use crate::interpreter::dice_calculation_delegate::keys::EvalImportKey;
 use crate::interpreter::global_interpreter_state::HasGlobalInterpreterState;
 use crate::interpreter::interpreter_for_cell::InterpreterForCell;
 use crate::interpreter::interpreter_for_cell::ParseResult;
+use crate::interpreter::module_cache::BzlModuleCache;
+use crate::interpreter::module_cache::HasBzlModuleCache;
+use crate::interpreter::module_cache::RecordConfigReads;
 use crate::super_package::data::SuperPackage;
 
 #[derive(Debug, thiserror::Error)]
@@ -258,38 +264,70 @@ impl<'c> DiceCalculationDelegate<'c> {
         &self,
         starlark_file: StarlarkModulePath<'_>,
     ) -> anyhow::Result<LoadedModule> {
-        let (ast, deps) = self.prepare_eval(starlark_file.into()).await?;
+        let starlark_path: StarlarkPath = starlark_file.into();
+        let content =
+            <dyn FileOps>::read_file(&self.fs, starlark_path.path().as_ref().as_ref()).await?;
+        let cache = self.ctx.get_bzl_module_cache();
+        // Only needed to compute the cache key, once the loads are evaluated.
+        let source = cache.map(|_| content.clone());
+        let ParseResult(ast, imports) = self.configs.parse(starlark_path, content)?;
+        let deps = LoadCycleDescriptor::guard_this(self.ctx, self.eval_deps(&imports)).await???;
         let loaded_modules = deps.get_loaded_modules();
+
+        let cache_key =
+            source.and_then(|source| BzlModuleCache::key(starlark_file, &source, &loaded_modules));
+        if let (Some(cache), Some(key)) = (cache, &cache_key) {
+            if let Some(env) = cache.get(key).await {
+                return Ok(LoadedModule::new_cached(
+                    OwnedStarlarkModulePath::new(starlark_file),
+                    loaded_modules,
+                    env,
+                    key.clone(),
+                ));
+            }
+        }
+
         let buckconfig = self.get_legacy_buck_config_for_starlark().await?;
         let root_buckconfig = self.ctx.get_legacy_root_config_on_dice().await?;
+        let config_read = AtomicBool::new(false);
 
-        with_starlark_eval_provider(
+        let limits = self.ctx.get_loading_eval_limits().await?;
+        let env = with_starlark_eval_provider(
             self.ctx,
             &mut StarlarkProfilerOrInstrumentation::disabled(),
+            limits,
             format!("load:{}", &starlark_file),
-            move |provider| {
-                let evaluation = self
-                    .configs
+            |provider| {
+                self.configs
                     .eval_module(
                         starlark_file,
-                        &buckconfig,
-                        &root_buckconfig,
+                        &RecordConfigReads {
+                            inner: &buckconfig,
+                            read: &config_read,
+                        },
+                        &RecordConfigReads {
+                            inner: &root_buckconfig,
+                            read: &config_read,
+                        },
                         ast,
                         loaded_modules.clone(),
                         provider,
                     )
                     .with_context(|| {
                         DiceCalculationDelegateError::EvalModuleError(starlark_file.to_string())
-                    })?;
-
-                Ok(LoadedModule::new(
-                    OwnedStarlarkModulePath::new(starlark_file),
-                    loaded_modules,
-                    evaluation,
-                ))
+                    })
             },
         )
-        .await
+        .await?;
+
+        let path = OwnedStarlarkModulePath::new(starlark_file);
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            // Buckconfig is not part of the key, so modules that read it can't be cached.
+            if !config_read.load(Ordering::Relaxed) && cache.put(&key, &env).await {
+                return Ok(LoadedModule::new_cached(path, loaded_modules, env, key));
+            }
+        }
+        Ok(LoadedModule::new(path, loaded_modules, env))
     }
 
     /// Eval parent `PACKAGE` file for given `PACKAGE` file.
@@ -344,9 +382,11 @@ impl<'c> DiceCalculationDelegate<'c> {
 
         let buckconfig = self.get_legacy_buck_config_for_starlark().await?;
         let root_buckconfig = self.ctx.get_legacy_root_config_on_dice().await?;
+        let limits = self.ctx.get_loading_eval_limits().await?;
         with_starlark_eval_provider(
             self.ctx,
             &mut StarlarkProfilerOrInstrumentation::disabled(),
+            limits,
             format!("load:{}", path),
             move |provider| {
                 self.configs
@@ -475,9 +515,11 @@ impl<'c> DiceCalculationDelegate<'c> {
             cell: cell_str.clone(),
             module_id: module_id.clone(),
         };
+        let limits = self.ctx.get_loading_eval_limits().await?;
         with_starlark_eval_provider(
             self.ctx,
             profiler_instrumentation,
+            limits,
             format!("load_buildfile:{}", &package),
             move |provider| {
                 span(start_event, move || {
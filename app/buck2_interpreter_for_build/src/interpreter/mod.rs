@@ -18,6 +18,7 @@ pub mod functions;
 pub mod global_interpreter_state;
 pub mod interpreter_for_cell;
 pub mod interpreter_setup;
+pub mod module_cache;
 pub mod module_internals;
 pub mod natives;
 pub mod testing;
@@ -0,0 +1,339 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! An on-disk cache of evaluated `.bzl` modules under the daemon dir, so that a restarted daemon
+//! can load them without evaluating them again. It is disabled by setting
+//! `buck2.bzl_module_cache = false`.
+//!
+//! Only modules that export plain data can be cached (see `FrozenModule::serialize`), e.g. large
+//! generated tables of constants. Frozen functions are not serialized, so modules exporting
+//! functions or rules, which is most of the prelude, are evaluated as usual: this does not make
+//! loading the prelude faster.
+//!
+//! A module is cached if it exports plain data, all the modules it loads were cached too, and it
+//! did not read buckconfig while being evaluated. The entries are keyed on a hash of the buck2
+//! binary, the module path and source, and the keys of the modules it loads, which identify their
+//! exports in turn.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_common::binary_id::binary_id;
+use buck2_common::legacy_configs::view::LegacyBuckConfigView;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_interpreter::file_loader::LoadedModules;
+use buck2_interpreter::path::StarlarkModulePath;
+use dice::DiceComputations;
+use dice::UserComputationData;
+use starlark::environment::FrozenModule;
+
+/// Used to name temporary files, which are renamed into place once complete.
+/// The cache is created for each command, so this is shared between them.
+static NEXT_TEMP_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// The entries are stored in a directory per binary, so that those written by other binaries
+/// can be removed when the daemon starts.
+pub struct BzlModuleCache {
+    root: AbsNormPathBuf,
+}
+
+impl BzlModuleCache {
+    pub fn new(root: AbsNormPathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Remove the entries written by other buck2 binaries, which this one never reads.
+    pub fn remove_stale_entries(root: &AbsNormPath) -> anyhow::Result<()> {
+        let binary_id = match binary_id() {
+            Some(binary_id) => binary_id,
+            None => return Ok(()),
+        };
+        if let Some(entries) = fs_util::read_dir_if_exists(root)? {
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_name().to_str() != Some(binary_id) {
+                    fs_util::remove_all(entry.path())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The key of the module at `path`, or `None` if it cannot be cached because one of the
+    /// modules it loads was not cached.
+    pub(crate) fn key(
+        path: StarlarkModulePath<'_>,
+        source: &str,
+        loaded_modules: &LoadedModules,
+    ) -> Option<String> {
+        Self::key_for_binary(binary_id()?, path, source, loaded_modules)
+    }
+
+    fn key_for_binary(
+        binary_id: &str,
+        path: StarlarkModulePath<'_>,
+        source: &str,
+        loaded_modules: &LoadedModules,
+    ) -> Option<String> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(binary_id.as_bytes());
+        hasher.update(format!("\0{:?}\0", path).as_bytes());
+        hasher.update(source.as_bytes());
+        for (loaded_path, loaded) in loaded_modules.map.iter() {
+            hasher.update(format!("\0{:?}\0", loaded_path).as_bytes());
+            hasher.update(loaded.cache_key()?.as_bytes());
+        }
+        Some(hasher.finalize().to_hex().to_string())
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<AbsNormPathBuf> {
+        let binary_id = binary_id().context("Cannot identify the buck2 binary")?;
+        Ok(self.root.join(ForwardRelativePath::new(&format!(
+            "{}/{}/{}",
+            binary_id,
+            &key[..2],
+            key
+        ))?))
+    }
+
+    /// The cached module, if there is one. Errors only mean the module is evaluated again.
+    pub(crate) async fn get(&self, key: &str) -> Option<FrozenModule> {
+        let res = async {
+            let path = self.path(key)?;
+            let data = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || -> anyhow::Result<Option<Vec<u8>>> {
+                    if !fs_util::try_exists(&path)? {
+                        return Ok(None);
+                    }
+                    fs_util::read(&path).map(Some)
+                }
+            })
+            .await??;
+            match data {
+                Some(data) => FrozenModule::deserialize(&data, key)
+                    .with_context(|| format!("Invalid cached module `{}`", path))
+                    .map(Some),
+                None => Ok(None),
+            }
+        };
+        res.await.unwrap_or_else(|e| {
+            tracing::debug!("Error reading cached module: {:#}", e);
+            None
+        })
+    }
+
+    /// Store the module. Returns `false` if it cannot be cached because it can't be serialized.
+    /// Errors writing it are ignored, since a concurrent command may have written it already.
+    pub(crate) async fn put(&self, key: &str, module: &FrozenModule) -> bool {
+        let data = match module.serialize(key) {
+            Ok(data) => data,
+            // Most modules export functions, this is expected.
+            Err(_) => return false,
+        };
+        let res = async {
+            let path = self.path(key)?;
+            tokio::task::spawn_blocking(move || write_atomic(&path, &data)).await?
+        };
+        if let Err(e) = res.await {
+            tracing::debug!("Error writing cached module: {:#}", e);
+        }
+        true
+    }
+}
+
+/// Write the file through a temporary file so that a concurrent command or the next daemon never
+/// sees a partial file.
+fn write_atomic(path: &AbsNormPath, data: &[u8]) -> anyhow::Result<()> {
+    let dir = path.parent().context("Cache path has no parent")?;
+    fs_util::create_dir_all(dir)?;
+    let temp = dir.join(ForwardRelativePath::new(&format!(
+        ".tmp.{}.{}",
+        std::process::id(),
+        NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+    ))?);
+    let res = fs_util::write(&temp, data).and_then(|()| fs_util::rename(&temp, path));
+    if res.is_err() {
+        let _ignored = fs_util::remove_file(&temp);
+    }
+    res
+}
+
+/// Wraps the buckconfig a module is evaluated with, to record whether it read any property,
+/// since those are not part of the cache key.
+#[derive(Debug)]
+pub(crate) struct RecordConfigReads<'a> {
+    pub(crate) inner: &'a dyn LegacyBuckConfigView,
+    pub(crate) read: &'a AtomicBool,
+}
+
+impl LegacyBuckConfigView for RecordConfigReads<'_> {
+    fn get(&self, section: &str, key: &str) -> anyhow::Result<Option<Arc<str>>> {
+        self.read.store(true, Ordering::Relaxed);
+        self.inner.get(section, key)
+    }
+}
+
+pub trait HasBzlModuleCache {
+    fn get_bzl_module_cache(&self) -> Option<&BzlModuleCache>;
+}
+
+pub trait SetBzlModuleCache {
+    fn set_bzl_module_cache(&mut self, cache: Option<BzlModuleCache>);
+}
+
+impl HasBzlModuleCache for DiceComputations {
+    fn get_bzl_module_cache(&self) -> Option<&BzlModuleCache> {
+        self.per_transaction_data()
+            .data
+            .get::<BzlModuleCacheHolder>()
+            .ok()?
+            .cache
+            .as_ref()
+    }
+}
+
+impl SetBzlModuleCache for UserComputationData {
+    fn set_bzl_module_cache(&mut self, cache: Option<BzlModuleCache>) {
+        self.data.set(BzlModuleCacheHolder { cache })
+    }
+}
+
+struct BzlModuleCacheHolder {
+    cache: Option<BzlModuleCache>,
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::bzl::ImportPath;
+    use buck2_interpreter::file_loader::LoadedModule;
+    use buck2_interpreter::path::OwnedStarlarkModulePath;
+    use starlark::environment::Globals;
+    use starlark::environment::Module;
+    use starlark::eval::Evaluator;
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+
+    use super::*;
+
+    fn eval(program: &str) -> FrozenModule {
+        let module = Module::new();
+        {
+            let mut eval = Evaluator::new(&module);
+            let ast = AstModule::parse("x.bzl", program.to_owned(), &Dialect::Extended).unwrap();
+            eval.eval_module(ast, &Globals::extended()).unwrap();
+        }
+        module.freeze().unwrap()
+    }
+
+    fn cache(dir: &tempfile::TempDir) -> BzlModuleCache {
+        BzlModuleCache::new(AbsNormPathBuf::try_from(dir.path().to_owned()).unwrap())
+    }
+
+    fn loaded(path: &str, cache_key: Option<&str>) -> LoadedModules {
+        let path = OwnedStarlarkModulePath::LoadFile(ImportPath::testing_new(path));
+        let env = eval("a = 1");
+        let module = match cache_key {
+            Some(key) => LoadedModule::new_cached(
+                path.clone(),
+                LoadedModules::default(),
+                env,
+                key.to_owned(),
+            ),
+            None => LoadedModule::new(path.clone(), LoadedModules::default(), env),
+        };
+        let mut loaded_modules = LoadedModules::default();
+        loaded_modules.map.insert(path, module);
+        loaded_modules
+    }
+
+    #[tokio::test]
+    async fn test_put_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        let key = "0123456789abcdef";
+
+        assert!(cache.get(key).await.is_none());
+        assert!(cache.put(key, &eval("a = [1, \"x\"]")).await);
+        let module = cache.get(key).await.unwrap();
+        assert_eq!(module.get("a").unwrap().value().to_repr(), "[1, \"x\"]");
+
+        // Another key is a miss, even though it lands in the same directory.
+        assert!(cache.get("0123456789abcdee").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_put_functions() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        let key = "0123456789abcdef";
+
+        assert!(!cache.put(key, &eval("def f(): pass")).await);
+        assert!(cache.get(key).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        let key = "0123456789abcdef";
+
+        write_atomic(&cache.path(key).unwrap(), b"garbage").unwrap();
+        assert!(cache.get(key).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remove_stale_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(&dir);
+        let key = "0123456789abcdef";
+
+        assert!(cache.put(key, &eval("a = 1")).await);
+        let stale = cache
+            .root
+            .join(ForwardRelativePath::new("other_binary/01/0123456789abcdef").unwrap());
+        write_atomic(&stale, b"stale").unwrap();
+
+        BzlModuleCache::remove_stale_entries(&cache.root).unwrap();
+        assert!(!fs_util::try_exists(&stale).unwrap());
+        assert!(cache.get(key).await.is_some());
+    }
+
+    #[test]
+    fn test_key() {
+        let path = ImportPath::testing_new("root//pkg:b.bzl");
+        let path = StarlarkModulePath::LoadFile(&path);
+        let key = |binary_id, source, loaded_modules: &LoadedModules| {
+            BzlModuleCache::key_for_binary(binary_id, path, source, loaded_modules)
+        };
+
+        let loaded_1 = loaded("root//pkg:a.bzl", Some("1"));
+        let base = key("bin", "b = 1", &loaded_1).unwrap();
+        assert_eq!(Some(&base), key("bin", "b = 1", &loaded_1).as_ref());
+        assert_ne!(Some(&base), key("other", "b = 1", &loaded_1).as_ref());
+        assert_ne!(Some(&base), key("bin", "b = 2", &loaded_1).as_ref());
+        assert_ne!(
+            Some(&base),
+            key("bin", "b = 1", &loaded("root//pkg:a.bzl", Some("2"))).as_ref()
+        );
+        assert_ne!(
+            Some(&base),
+            key("bin", "b = 1", &loaded("root//pkg:c.bzl", Some("1"))).as_ref()
+        );
+
+        // Loading a module that was not cached makes this one uncacheable.
+        assert_eq!(None, key("bin", "b = 1", &loaded("root//pkg:a.bzl", None)));
+    }
+}
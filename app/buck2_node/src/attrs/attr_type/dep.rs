@@ -75,7 +75,7 @@ impl DepAttr<ConfiguredProvidersLabel> {
             DepAttrTransition::Identity => traversal.dep(&self.label),
             DepAttrTransition::Exec => traversal.exec_dep(&self.label),
             DepAttrTransition::Toolchain => traversal.toolchain_dep(&self.label),
-            DepAttrTransition::Transition(..) => traversal.dep(&self.label),
+            DepAttrTransition::Transition(tr) => traversal.transition_dep(&self.label, tr),
         }
     }
 }
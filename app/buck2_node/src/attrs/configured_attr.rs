@@ -184,8 +184,8 @@ impl ConfiguredAttr {
             ConfiguredAttr::Visibility(..) => Ok(()),
             ConfiguredAttr::ExplicitConfiguredDep(dep) => dep.as_ref().traverse(traversal),
             ConfiguredAttr::SplitTransitionDep(deps) => {
-                for target in deps.deps.values() {
-                    traversal.dep(target)?;
+                for (split, target) in deps.deps.iter() {
+                    traversal.split_transition_dep(target, split)?;
                 }
                 Ok(())
             }
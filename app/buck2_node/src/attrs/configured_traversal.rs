@@ -7,7 +7,10 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use buck2_core::buck_path::path::BuckPathRef;
+use buck2_core::configuration::transition::id::TransitionId;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_core::target::label::TargetLabel;
 
@@ -26,6 +29,25 @@ pub trait ConfiguredAttrTraversal {
         self.dep(dep)
     }
 
+    fn transition_dep(
+        &mut self,
+        dep: &ConfiguredProvidersLabel,
+        _tr: &Arc<TransitionId>,
+    ) -> anyhow::Result<()> {
+        // By default, just treat it as a dep. Most things don't care about the distinction.
+        self.dep(dep)
+    }
+
+    /// A dep configured by a split transition, `split` is the key of the split it belongs to.
+    fn split_transition_dep(
+        &mut self,
+        dep: &ConfiguredProvidersLabel,
+        _split: &str,
+    ) -> anyhow::Result<()> {
+        // By default, just treat it as a dep. Most things don't care about the distinction.
+        self.dep(dep)
+    }
+
     fn configuration_dep(&mut self, _dep: &TargetLabel) -> anyhow::Result<()> {
         Ok(())
     }
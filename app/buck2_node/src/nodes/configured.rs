@@ -31,6 +31,8 @@ use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::provider::label::ProvidersName;
 use buck2_core::target::label::ConfiguredTargetLabel;
 use buck2_core::target::label::TargetLabel;
+use buck2_query::query::environment::DepEdge;
+use buck2_query::query::environment::DepKind;
 use buck2_util::arc_str::ArcStr;
 use dupe::Dupe;
 use either::Either;
@@ -411,6 +413,79 @@ impl ConfiguredTargetNode {
         self.0.exec_deps.iter()
     }
 
+    /// The attributes through which this target depends on `dep`, see `QueryTarget::dep_edges`.
+    pub fn dep_edges(&self, dep: &ConfiguredTargetLabel) -> anyhow::Result<Vec<DepEdge>> {
+        struct EdgeCollector<'d> {
+            dep: &'d ConfiguredTargetLabel,
+            attr: &'d str,
+            edges: Vec<DepEdge>,
+        }
+
+        impl<'d> EdgeCollector<'d> {
+            fn add(
+                &mut self,
+                dep: &ConfiguredProvidersLabel,
+                kind: DepKind,
+                transition: Option<String>,
+            ) {
+                if dep.target() == self.dep {
+                    self.edges.push(DepEdge {
+                        attr: self.attr.to_owned(),
+                        kind,
+                        transition,
+                    });
+                }
+            }
+        }
+
+        impl<'d> ConfiguredAttrTraversal for EdgeCollector<'d> {
+            fn dep(&mut self, dep: &ConfiguredProvidersLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, None);
+                Ok(())
+            }
+
+            fn exec_dep(&mut self, dep: &ConfiguredProvidersLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::ExecDep, None);
+                Ok(())
+            }
+
+            fn toolchain_dep(&mut self, dep: &ConfiguredProvidersLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::ToolchainDep, None);
+                Ok(())
+            }
+
+            fn transition_dep(
+                &mut self,
+                dep: &ConfiguredProvidersLabel,
+                tr: &Arc<TransitionId>,
+            ) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, Some(tr.to_string()));
+                Ok(())
+            }
+
+            fn split_transition_dep(
+                &mut self,
+                dep: &ConfiguredProvidersLabel,
+                split: &str,
+            ) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, Some(format!("split:{}", split)));
+                Ok(())
+            }
+        }
+
+        let mut edges = Vec::new();
+        for a in self.attrs(AttrInspectOptions::All) {
+            let mut traversal = EdgeCollector {
+                dep,
+                attr: a.name,
+                edges: Vec::new(),
+            };
+            a.traverse(self.label().pkg(), &mut traversal)?;
+            edges.extend(traversal.edges);
+        }
+        Ok(edges)
+    }
+
     /// Return the `tests` declared for this target.
     pub fn tests(&self) -> impl Iterator<Item = ConfiguredProvidersLabel> {
         #[derive(Default)]
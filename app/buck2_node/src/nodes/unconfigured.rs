@@ -18,6 +18,8 @@ use buck2_core::cells::cell_path::CellPath;
 use buck2_core::configuration::transition::id::TransitionId;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_core::target::label::TargetLabel;
+use buck2_query::query::environment::DepEdge;
+use buck2_query::query::environment::DepKind;
 use buck2_util::arc_str::ArcStr;
 use dupe::Dupe;
 
@@ -401,6 +403,91 @@ impl TargetNode {
         traversal.inputs.into_iter()
     }
 
+    /// The attributes through which this target depends on `dep`, see `QueryTarget::dep_edges`.
+    pub fn dep_edges(&self, dep: &TargetLabel) -> anyhow::Result<Vec<DepEdge>> {
+        struct EdgeCollector<'d> {
+            dep: &'d TargetLabel,
+            attr: &'d str,
+            edges: Vec<DepEdge>,
+        }
+
+        impl<'d> EdgeCollector<'d> {
+            fn add(
+                &mut self,
+                dep: &TargetLabel,
+                kind: DepKind,
+                transition: Option<&Arc<TransitionId>>,
+            ) {
+                if dep == self.dep {
+                    self.edges.push(DepEdge {
+                        attr: self.attr.to_owned(),
+                        kind,
+                        transition: transition.map(|tr| tr.to_string()),
+                    });
+                }
+            }
+        }
+
+        impl<'a, 'd> CoercedAttrTraversal<'a> for EdgeCollector<'d> {
+            fn input(&mut self, _path: BuckPathRef) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn dep(&mut self, dep: &'a TargetLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, None);
+                Ok(())
+            }
+
+            fn exec_dep(&mut self, dep: &'a TargetLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::ExecDep, None);
+                Ok(())
+            }
+
+            fn toolchain_dep(&mut self, dep: &'a TargetLabel) -> anyhow::Result<()> {
+                self.add(dep, DepKind::ToolchainDep, None);
+                Ok(())
+            }
+
+            fn transition_dep(
+                &mut self,
+                dep: &'a TargetLabel,
+                tr: &Arc<TransitionId>,
+            ) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, Some(tr));
+                Ok(())
+            }
+
+            fn split_transition_dep(
+                &mut self,
+                dep: &'a TargetLabel,
+                tr: &Arc<TransitionId>,
+            ) -> anyhow::Result<()> {
+                self.add(dep, DepKind::Dep, Some(tr));
+                Ok(())
+            }
+
+            fn platform_dep(&mut self, _dep: &'a TargetLabel) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn configuration_dep(&mut self, _dep: &'a TargetLabel) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut edges = Vec::new();
+        for a in self.attrs(AttrInspectOptions::All) {
+            let mut traversal = EdgeCollector {
+                dep,
+                attr: a.name,
+                edges: Vec::new(),
+            };
+            a.traverse(self.label().pkg(), &mut traversal)?;
+            edges.extend(traversal.edges);
+        }
+        Ok(edges)
+    }
+
     pub fn call_stack(&self) -> Option<String> {
         self.0.call_stack.as_ref().map(|s| s.to_string())
     }
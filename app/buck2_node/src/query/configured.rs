@@ -12,6 +12,7 @@ use std::borrow::Cow;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::target::label::ConfiguredTargetLabel;
+use buck2_query::query::environment::DepEdge;
 use buck2_query::query::environment::LabeledNode;
 use buck2_query::query::environment::QueryTarget;
 use dupe::Dupe;
@@ -61,6 +62,10 @@ impl QueryTarget for ConfiguredTargetNode {
         Some(Box::new(self.tests().map(|t| t.target().dupe())))
     }
 
+    fn dep_edges(&self, dep: &Self::NodeRef) -> anyhow::Result<Vec<DepEdge>> {
+        ConfiguredTargetNode::dep_edges(self, dep)
+    }
+
     fn special_attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
         &self,
         mut func: F,
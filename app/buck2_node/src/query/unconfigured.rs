@@ -12,6 +12,7 @@ use std::borrow::Cow;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::target::label::TargetLabel;
+use buck2_query::query::environment::DepEdge;
 use buck2_query::query::environment::LabeledNode;
 use buck2_query::query::environment::QueryTarget;
 use dupe::Dupe;
@@ -60,6 +61,10 @@ impl QueryTarget for TargetNode {
         Some(Box::new(self.tests().map(|t| t.target().dupe())))
     }
 
+    fn dep_edges(&self, dep: &Self::NodeRef) -> anyhow::Result<Vec<DepEdge>> {
+        TargetNode::dep_edges(self, dep)
+    }
+
     fn attr_any_matches(
         attr: &Self::Attr<'_>,
         filter: &dyn Fn(&str) -> anyhow::Result<bool>,
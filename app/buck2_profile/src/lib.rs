@@ -7,7 +7,9 @@
  * of this source tree.
  */
 
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Context;
 use buck2_cli_proto::profile_request::ProfileOpts;
@@ -16,6 +18,8 @@ use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_interpreter::dice::starlark_profiler::StarlarkProfilerConfiguration;
 use buck2_interpreter::starlark_profiler::StarlarkProfileDataAndStats;
+use starlark::eval::ProfileData;
+use starlark::eval::ProfileDiff;
 use starlark::eval::ProfileMode;
 
 pub fn starlark_profiler_configuration_from_request(
@@ -34,6 +38,7 @@ pub fn starlark_profiler_configuration_from_request(
         Profiler::Bytecode => ProfileMode::Bytecode,
         Profiler::BytecodePairs => ProfileMode::BytecodePairs,
         Profiler::Typecheck => ProfileMode::Typecheck,
+        Profiler::Coverage => ProfileMode::Coverage,
     };
 
     match req.profile_opts.as_ref().expect("Missing profile opts") {
@@ -58,6 +63,9 @@ pub fn starlark_profiler_configuration_from_request(
             })
         }
         ProfileOpts::BxlProfile(_) => Ok(StarlarkProfilerConfiguration::ProfileBxl(profile_mode)),
+        ProfileOpts::ProfileDiff(_) => Err(anyhow::anyhow!(
+            "Profile diff does not run the profiler (internal error)"
+        )),
     }
 }
 
@@ -90,6 +98,7 @@ pub fn get_profile_response(
                 .context("Failed to write profile")?;
             fs_util::write(output.join("flame.svg"), &svg).context("Failed to write profile")?;
         }
+        Profiler::Coverage => write_coverage(&profile_data.profile_data, output)?,
         _ => {
             let profile = profile_data.profile_data.gen()?;
             fs_util::write(output, profile).context("Failed to write profile")?;
@@ -101,3 +110,35 @@ pub fn get_profile_response(
         total_retained_bytes: profile_data.total_retained_bytes() as u64,
     })
 }
+
+/// Compare the heap summary profiles of two runs, and write the per-function regressions to
+/// `output` as CSV.
+pub fn write_profile_diff(
+    opts: &buck2_cli_proto::ProfileDiff,
+    output: &AbsPath,
+) -> anyhow::Result<buck2_cli_proto::ProfileResponse> {
+    let start = Instant::now();
+    let read = |path: &str| fs_util::read_to_string(AbsPath::new(Path::new(path))?);
+
+    let mut diff = ProfileDiff::new(&read(&opts.before)?, &read(&opts.after)?)?;
+    if let (Some(before), Some(after)) = (&opts.retained_before, &opts.retained_after) {
+        diff = diff.with_retained(&read(before)?, &read(after)?)?;
+    }
+    fs_util::write(output, diff.gen_csv()).context("Failed to write profile diff")?;
+
+    Ok(buck2_cli_proto::ProfileResponse {
+        elapsed: Some(start.elapsed().try_into()?),
+        total_retained_bytes: 0,
+    })
+}
+
+/// Write line coverage to the `output` directory, as an LCOV tracefile and a Cobertura report.
+pub fn write_coverage(profile_data: &ProfileData, output: &AbsPath) -> anyhow::Result<()> {
+    fs_util::create_dir_if_not_exists(output)?;
+
+    fs_util::write(output.join("coverage.lcov"), profile_data.gen()?)
+        .context("Failed to write coverage")?;
+    fs_util::write(output.join("coverage.xml"), profile_data.gen_cobertura()?)
+        .context("Failed to write coverage")?;
+    Ok(())
+}
@@ -24,6 +24,7 @@ use buck2_core::target::label::TargetLabel;
 use dupe::Dupe;
 use futures::stream::FuturesUnordered;
 use futures::stream::TryStreamExt;
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::query::syntax::simple::eval::error::QueryError;
@@ -68,6 +69,51 @@ pub trait LabeledNode: Dupe + Send + Sync + 'static {
     fn node_ref(&self) -> &Self::NodeRef;
 }
 
+/// How a dependency is declared, see [`DepEdge`].
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Dupe,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Display,
+    Serialize
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DepKind {
+    #[display(fmt = "dep")]
+    Dep,
+    #[display(fmt = "exec_dep")]
+    ExecDep,
+    #[display(fmt = "toolchain_dep")]
+    ToolchainDep,
+}
+
+/// Why a target depends on another: the attribute that declares the dependency, and how the
+/// dependency is configured.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct DepEdge {
+    pub attr: String,
+    pub kind: DepKind,
+    /// The configuration transition applied to the dependency, if any.
+    pub transition: Option<String>,
+}
+
+impl Display for DepEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.attr)?;
+        if self.kind != DepKind::Dep {
+            write!(f, " ({})", self.kind)?;
+        }
+        if let Some(transition) = &self.transition {
+            write!(f, " (transition `{}`)", transition)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct QueryTargets {}
 
 impl QueryTargets {
@@ -108,6 +154,13 @@ pub trait QueryTarget: LabeledNode + Dupe + Send + Sync + 'static {
         None
     }
 
+    /// Explains why this target depends on `dep`, with one entry per attribute that declares the
+    /// dependency. Empty if the dependency doesn't come from an attribute, or if this kind of
+    /// target can't tell.
+    fn dep_edges(&self, _dep: &Self::NodeRef) -> anyhow::Result<Vec<DepEdge>> {
+        Ok(Vec::new())
+    }
+
     fn attr_to_string_alternate(&self, attr: &Self::Attr<'_>) -> String;
 
     fn attr_serialize<S: serde::Serializer>(
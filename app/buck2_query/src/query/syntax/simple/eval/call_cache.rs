@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_core::cells::cell_path::CellPath;
+
+use crate::query::environment::QueryTarget;
+use crate::query::syntax::simple::eval::values::QueryValue;
+
+/// The value of a function argument, as far as identifying a call goes.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Allocative)]
+pub enum ArgKey<R> {
+    String(String),
+    Integer(u64),
+    TargetSet(Vec<R>),
+    FileSet(Vec<CellPath>),
+}
+
+impl<R: fmt::Display> fmt::Display for ArgKey<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn set<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+            write!(f, "set(")?;
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+            write!(f, ")")
+        }
+
+        match self {
+            ArgKey::String(s) => write!(f, "{:?}", s),
+            ArgKey::Integer(i) => write!(f, "{}", i),
+            ArgKey::TargetSet(targets) => set(f, targets),
+            ArgKey::FileSet(files) => set(f, files),
+        }
+    }
+}
+
+/// A function call: the function and the values of its arguments.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Allocative)]
+pub struct CallKey<R> {
+    function: &'static str,
+    args: Vec<ArgKey<R>>,
+}
+
+impl<R: Clone> CallKey<R> {
+    pub(crate) fn new<T: QueryTarget<NodeRef = R>>(
+        function: &'static str,
+        args: &[QueryValue<T>],
+    ) -> Self {
+        let args = args
+            .iter()
+            .map(|arg| match arg {
+                QueryValue::String(s) => ArgKey::String(s.clone()),
+                QueryValue::Integer(i) => ArgKey::Integer(*i),
+                QueryValue::TargetSet(targets) => {
+                    ArgKey::TargetSet(targets.iter_names().cloned().collect())
+                }
+                QueryValue::FileSet(files) => ArgKey::FileSet(files.iter().cloned().collect()),
+            })
+            .collect();
+        Self { function, args }
+    }
+
+    pub fn function(&self) -> &'static str {
+        self.function
+    }
+
+    pub fn args(&self) -> &[ArgKey<R>] {
+        &self.args
+    }
+}
+
+impl<R: fmt::Display> fmt::Display for CallKey<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.function)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", arg)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Results of the function calls made while evaluating queries, keyed on the function and the
+/// values of its arguments. A sub-expression that appears several times, within a query or across
+/// queries, is then only computed once.
+///
+/// Functions whose result depends on more than their arguments and the environment (e.g. the
+/// functions available in the filter of `deps`) are never looked up in a cache.
+#[async_trait]
+pub trait QueryCallCache<T: QueryTarget>: Send + Sync {
+    /// The result of the call, if it is cached. An implementation may also compute the result
+    /// itself (e.g. in DICE), in which case it is never `insert`ed.
+    async fn get(&self, key: &CallKey<T::NodeRef>) -> anyhow::Result<Option<QueryValue<T>>>;
+
+    /// Record the result of a call that `get` returned `None` for.
+    fn insert(&self, key: CallKey<T::NodeRef>, result: QueryValue<T>);
+}
+
+/// A `QueryCallCache` in memory.
+///
+/// Target sets are identified by their labels, so a cache must not outlive the nodes it was filled
+/// with: it is meant to be shared by the evaluations of a single command.
+pub struct InMemoryQueryCallCache<T: QueryTarget> {
+    results: Mutex<HashMap<CallKey<T::NodeRef>, QueryValue<T>>>,
+}
+
+impl<T: QueryTarget> Default for InMemoryQueryCallCache<T> {
+    fn default() -> Self {
+        Self {
+            results: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: QueryTarget> InMemoryQueryCallCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl<T: QueryTarget> QueryCallCache<T> for InMemoryQueryCallCache<T> {
+    async fn get(&self, key: &CallKey<T::NodeRef>) -> anyhow::Result<Option<QueryValue<T>>> {
+        Ok(self.results.lock().unwrap().get(key).cloned())
+    }
+
+    fn insert(&self, key: CallKey<T::NodeRef>, result: QueryValue<T>) {
+        self.results.lock().unwrap().insert(key, result);
+    }
+}
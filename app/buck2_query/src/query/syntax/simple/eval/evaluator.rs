@@ -18,6 +18,8 @@ use gazebo::variants::VariantName;
 
 use crate::__derive_refs::indexmap::IndexSet;
 use crate::query::environment::QueryEnvironment;
+use crate::query::syntax::simple::eval::call_cache::CallKey;
+use crate::query::syntax::simple::eval::call_cache::QueryCallCache;
 use crate::query::syntax::simple::eval::error::QueryError;
 use crate::query::syntax::simple::eval::file_set::FileNode;
 use crate::query::syntax::simple::eval::file_set::FileSet;
@@ -25,15 +27,35 @@ use crate::query::syntax::simple::eval::set::TargetSet;
 use crate::query::syntax::simple::eval::values::QueryEvaluationValue;
 use crate::query::syntax::simple::eval::values::QueryResult;
 use crate::query::syntax::simple::eval::values::QueryValue;
+use crate::query::syntax::simple::functions::helpers::QueryArgType;
+use crate::query::syntax::simple::functions::helpers::QueryFunction;
 use crate::query::syntax::simple::functions::QueryFunctions;
 pub struct QueryEvaluator<'e, Env: QueryEnvironment> {
     env: &'e Env,
     functions: &'e dyn QueryFunctions<Env = Env>,
+    /// When set, function calls reuse the results of previous calls with the same arguments.
+    cache: Option<&'e dyn QueryCallCache<Env::Target>>,
 }
 
 impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
     pub fn new(env: &'e Env, functions: &'e dyn QueryFunctions<Env = Env>) -> Self {
-        Self { env, functions }
+        Self {
+            env,
+            functions,
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(
+        env: &'e Env,
+        functions: &'e dyn QueryFunctions<Env = Env>,
+        cache: &'e dyn QueryCallCache<Env::Target>,
+    ) -> Self {
+        Self {
+            env,
+            functions,
+            cache: Some(cache),
+        }
     }
 
     pub fn env(&self) -> &Env {
@@ -48,6 +70,51 @@ impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
         self.env.eval_literals(&[literal]).await
     }
 
+    /// Invokes the function named `function` with the values of its arguments.
+    pub async fn invoke_function(
+        &self,
+        function: &str,
+        args: Vec<QueryValue<Env::Target>>,
+    ) -> anyhow::Result<QueryValue<Env::Target>> {
+        let func = self
+            .functions
+            .get(function)
+            .ok_or_else(|| QueryError::UnknownFunction(function.to_owned()))?;
+        Ok(func
+            .invoke_with_values(self, args.into_iter().map(Some).collect())
+            .await?)
+    }
+
+    /// Invokes `func`, or returns the result of a previous call with the same argument values.
+    async fn invoke_cached(
+        &self,
+        cache: &dyn QueryCallCache<Env::Target>,
+        func: &dyn QueryFunction<Env>,
+        args: &[Spanned<Expr<'_>>],
+    ) -> Result<QueryValue<Env::Target>, QueryError> {
+        // An expression argument is evaluated by the function itself in some other context, so
+        // it has no value to identify the call with.
+        let cacheable = (0..args.len())
+            .all(|i| !matches!(func.arg_type(i), Ok(QueryArgType::Expression) | Err(..)));
+        if !cacheable {
+            return func.invoke(self, args).await;
+        }
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.eval(arg).await?.value);
+        }
+        let key = CallKey::new(func.name(), &values);
+        if let Some(result) = cache.get(&key).await? {
+            return Ok(result);
+        }
+        let result = func
+            .invoke_with_values(self, values.into_iter().map(Some).collect())
+            .await?;
+        cache.insert(key, result.clone());
+        Ok(result)
+    }
+
     async fn eval_internal(&self, expr: &Expr<'_>) -> Result<QueryValue<Env::Target>, QueryError> {
         // TODO(cjhopman): We should extract these functions to a map of name->functionobj and attach
         // more information to them like documentation and signature. Potentially we could generalize
@@ -60,7 +127,10 @@ impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
                 function_name,
                 args,
             } => match self.functions.get(function_name) {
-                Some(func) => func.invoke(self, args).await,
+                Some(func) => match self.cache {
+                    Some(cache) => self.invoke_cached(cache, func, args).await,
+                    None => func.invoke(self, args).await,
+                },
                 None => Err(QueryError::UnknownFunction(
                     (*function_name.fragment()).to_owned(),
                 )),
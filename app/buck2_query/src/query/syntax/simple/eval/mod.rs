@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+pub mod call_cache;
 pub mod error;
 pub mod evaluator;
 pub mod file_set;
@@ -12,12 +12,15 @@
 #![cfg(test)]
 
 use std::borrow::Cow;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use async_trait::async_trait;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_query::query::environment::LabeledNode;
+use buck2_query_derive::query_module;
 use buck2_query_parser::parse_expr;
 use derive_more::Display;
 use dupe::Dupe;
@@ -26,10 +29,12 @@ use serde::Serializer;
 
 use crate::query::environment::QueryEnvironment;
 use crate::query::environment::QueryTarget;
+use crate::query::syntax::simple::eval::call_cache::InMemoryQueryCallCache;
 use crate::query::syntax::simple::eval::error::QueryError;
 use crate::query::syntax::simple::eval::evaluator::QueryEvaluator;
 use crate::query::syntax::simple::eval::file_set::FileSet;
 use crate::query::syntax::simple::eval::set::TargetSet;
+use crate::query::syntax::simple::eval::values::QueryValue;
 use crate::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use crate::query::traversal::AsyncTraversalDelegate;
 
@@ -185,3 +190,63 @@ pub async fn test_missing_arg() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[derive(Debug, Default)]
+struct CountingFunctions {
+    calls: AtomicUsize,
+}
+
+#[query_module(Env)]
+impl CountingFunctions {
+    /// Returns how many times `count` was invoked, this invocation included.
+    async fn count(&self, _name: String) -> Result<QueryValue<Target>, QueryError> {
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(QueryValue::Integer(calls as u64))
+    }
+
+    async fn add(&self, a: u64, b: u64) -> Result<QueryValue<Target>, QueryError> {
+        Ok(QueryValue::Integer(a + b))
+    }
+}
+
+async fn eval(
+    evaluator: &QueryEvaluator<'_, Env>,
+    input: &str,
+) -> anyhow::Result<QueryValue<Target>> {
+    let parsed = parse_expr(input)?;
+    match evaluator.eval(&parsed).await {
+        Ok(v) => Ok(v.value),
+        Err(e) => Err(QueryError::convert_error(e, input)),
+    }
+}
+
+#[tokio::test]
+pub async fn test_call_cache() -> anyhow::Result<()> {
+    let functions = CountingFunctions::default();
+    let evaluator = QueryEvaluator::new(&Env, &functions);
+    assert_eq!(
+        eval(&evaluator, "add(count(a), count(a))").await?,
+        QueryValue::Integer(1 + 2)
+    );
+
+    let functions = CountingFunctions::default();
+    let cache = InMemoryQueryCallCache::new();
+    let evaluator = QueryEvaluator::with_cache(&Env, &functions, &cache);
+    assert_eq!(
+        eval(&evaluator, "add(count(a), count(a))").await?,
+        QueryValue::Integer(1 + 1)
+    );
+    assert_eq!(functions.calls.load(Ordering::SeqCst), 1);
+
+    // `count(a)` is reused from the previous query, `count(b)` is not.
+    assert_eq!(
+        eval(&evaluator, "add(count(a), count(b))").await?,
+        QueryValue::Integer(1 + 2)
+    );
+    assert_eq!(functions.calls.load(Ordering::SeqCst), 2);
+
+    // Missing arguments are reported like without a cache.
+    let err = eval(&evaluator, "add(count(a))").await.unwrap_err();
+    assert!(format!("{:#}", err).contains("too few args"));
+    Ok(())
+}
@@ -9,6 +9,7 @@
 
 //! Implementation of the cli and query_* attr query language.
 
+use allocative::Allocative;
 use buck2_query_parser::spanned::Spanned;
 use gazebo::variants::VariantName;
 
@@ -24,7 +25,7 @@ pub enum QueryEvaluationResult<T: QueryTarget> {
 }
 
 /// Used as a value in query evaluation, may appear in arguments to functions, results of functions etc.
-#[derive(Debug, VariantName, Eq, PartialEq)]
+#[derive(Debug, Clone, VariantName, Eq, PartialEq)]
 pub enum QueryValue<T: QueryTarget> {
     String(String),
     Integer(u64),
@@ -42,7 +43,7 @@ pub enum QueryValueSet<T: QueryTarget> {
 /// Used as the final result of evaluating a query. A literal at the top-level is treated specially and so this has
 /// a more limited set of possibilities than a general QueryValue (for example `//foo/...` becomes a TargetSet in
 /// `buck query //foo/...` rather than being a String).
-#[derive(Debug, VariantName)]
+#[derive(Debug, Clone, VariantName, Allocative)]
 pub enum QueryEvaluationValue<T: QueryTarget> {
     TargetSet(TargetSet<T>),
     FileSet(FileSet),
@@ -120,6 +120,14 @@ pub trait QueryFunction<Env: QueryEnvironment>: Send + Sync {
         args: &[SpannedExpr<'_>],
     ) -> Result<QueryValue<Env::Target>, QueryError>;
 
+    /// Like `invoke`, with arguments that were already evaluated. Used when the result of the call
+    /// may be cached, which requires none of the arguments to be an `Expression`.
+    async fn invoke_with_values(
+        &self,
+        evaluator: &QueryEvaluator<Env>,
+        args: Vec<Option<QueryValue<Env::Target>>>,
+    ) -> Result<QueryValue<Env::Target>, QueryError>;
+
     fn arg_type(&self, idx: usize) -> Result<QueryArgType, QueryError>;
 }
 
@@ -322,3 +330,23 @@ pub async fn eval_arg<'a, Env: QueryEnvironment, A: QueryFunctionArg<'a, Env>>(
         },
     }
 }
+
+// Like `eval_arg`, for an arg that was already evaluated. Takes the value out of `args`.
+pub async fn accept_arg<'a, Env: QueryEnvironment, A: QueryFunctionArg<'a, Env>>(
+    func_name: &str,
+    env: &Env,
+    args: &mut [Option<QueryValue<Env::Target>>],
+    idx: usize,
+) -> Result<A, QueryError> {
+    match args.get_mut(idx).and_then(Option::take) {
+        Some(v) => A::accept(env, v).await,
+        None => match A::accept_none() {
+            Some(v) => Ok(v),
+            None => Err(QueryError::TooFewArgs {
+                function: func_name.to_owned(),
+                min: idx + 1,
+                actual: args.len(),
+            }),
+        },
+    }
+}
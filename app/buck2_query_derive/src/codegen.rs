@@ -168,6 +168,7 @@ fn gen_for_method(parsed: &Parsed, method: &Method) -> syn::Result<MethodCodegen
 
     let mut describe_args = Vec::new();
     let mut pass_args = Vec::new();
+    let mut accept_args = Vec::new();
     let mut arg_type_match = Vec::new();
     for (i, arg) in value_args.iter().enumerate() {
         let arg_type = &arg.ty;
@@ -176,6 +177,8 @@ fn gen_for_method(parsed: &Parsed, method: &Method) -> syn::Result<MethodCodegen
         arg_type_match.push(quote_spanned!(arg.span => #i => Ok(#as_arg_type::ARG_TYPE)));
         pass_args
             .push(quote_spanned!(arg.span => eval_arg(self.name(), evaluator, args, #i).await?));
+        accept_args.push(quote_spanned!(arg.span =>
+            accept_arg(self.name(), evaluator.env(), &mut args, #i).await?));
         describe_args.push(quote_spanned!(arg.span => ArgDescription {
             name: #arg_name.to_owned(),
             repr_format: #as_arg_type::describe_format(),
@@ -264,6 +267,18 @@ fn gen_for_method(parsed: &Parsed, method: &Method) -> syn::Result<MethodCodegen
                         ).await
                     }
 
+                    #[allow(unused_mut, unused_variables)]
+                    async fn invoke_with_values(
+                        &self,
+                        evaluator: &QueryEvaluator<#env_ident>,
+                        mut args: Vec<Option<QueryValue<#env_target>>>,
+                    ) -> Result<QueryValue<#env_target>, QueryError> {
+                        self.0.#func_ident(
+                            #pass_ctx
+                            #(#accept_args,)*
+                        ).await
+                    }
+
                     fn arg_type(&self, idx: usize) -> Result<QueryArgType, QueryError> {
                         match idx {
                             #(#arg_type_match,)*
@@ -332,7 +347,8 @@ pub(crate) fn codegen(parsed: Parsed) -> syn::Result<TokenStream> {
                     functions::docs::{ModuleDescription, ArgDescription, FunctionDescription},
                     functions::{QueryFunctions, HasModuleDescription},
                     functions::helpers::{
-                        eval_arg, QueryArgType, QueryBinaryOp, QueryFunction, QueryFunctionArg,
+                        accept_arg, eval_arg, QueryArgType, QueryBinaryOp, QueryFunction,
+                        QueryFunctionArg,
                     },
                 },
             };
@@ -10,6 +10,8 @@
 //! Implementation of common cquery/uquery pieces.
 
 use buck2_query::query::environment::QueryEnvironment;
+use buck2_query::query::syntax::simple::eval::call_cache::InMemoryQueryCallCache;
+use buck2_query::query::syntax::simple::eval::call_cache::QueryCallCache;
 use buck2_query::query::syntax::simple::eval::evaluator::QueryEvaluator;
 use buck2_query::query::syntax::simple::eval::literals::extract_target_literals;
 use buck2_query::query::syntax::simple::eval::multi_query::process_multi_query;
@@ -38,6 +40,27 @@ pub async fn eval_query<
     query: &str,
     query_args: &[A],
     environment: impl FnOnce(Vec<String>) -> Fut,
+) -> anyhow::Result<QueryEvaluationResult<Env::Target>> {
+    eval_query_with_cache(functions, query, query_args, |literals| async move {
+        let env = environment(literals).await?;
+        let cache: Box<dyn QueryCallCache<Env::Target>> = Box::new(InMemoryQueryCallCache::new());
+        Ok((env, cache))
+    })
+    .await
+}
+
+/// Like `eval_query`, but the function calls are cached in the cache returned along with the
+/// environment rather than in memory.
+pub async fn eval_query_with_cache<
+    'c,
+    Env: QueryEnvironment,
+    Fut: Future<Output = anyhow::Result<(Env, Box<dyn QueryCallCache<Env::Target> + 'c>)>>,
+    A: AsRef<str>,
+>(
+    functions: &DefaultQueryFunctionsModule<Env>,
+    query: &str,
+    query_args: &[A],
+    environment: impl FnOnce(Vec<String>) -> Fut,
 ) -> anyhow::Result<QueryEvaluationResult<Env::Target>> {
     let mut literals = SmallSet::new();
     if query.contains(QUERY_PERCENT_S_PLACEHOLDER) {
@@ -57,9 +80,10 @@ pub async fn eval_query<
                 &mut literals,
             )?;
         }
-        let env = environment(literals.into_iter().collect()).await?;
+        // The cache is shared by the queries, which typically differ only in a few literals.
+        let (env, cache) = environment(literals.into_iter().collect()).await?;
         let results = process_multi_query(query, query_args, |input, query| {
-            let evaluator = QueryEvaluator::new(&env, functions);
+            let evaluator = QueryEvaluator::with_cache(&env, functions, &*cache);
             async move { (input, evaluator.eval_query(&query).await) }
         })
         .await;
@@ -71,9 +95,9 @@ pub async fn eval_query<
         )
     } else {
         extract_target_literals(functions, query, &mut literals)?;
-        let env = environment(literals.into_iter().collect()).await?;
+        let (env, cache) = environment(literals.into_iter().collect()).await?;
         Ok(QueryEvaluationResult::Single(
-            QueryEvaluator::new(&env, functions)
+            QueryEvaluator::with_cache(&env, functions, &*cache)
                 .eval_query(query)
                 .await?,
         ))
@@ -78,7 +78,7 @@ pub struct CqueryEnvironment<'c> {
     //   ```
     //   buck2 cquery 'deps(//foo:bar)'
     //   ```
-    universe: Option<CqueryUniverse>,
+    universe: Option<Arc<CqueryUniverse>>,
     owner_behavior: CqueryOwnerBehavior,
 }
 
@@ -86,7 +86,7 @@ impl<'c> CqueryEnvironment<'c> {
     pub fn new(
         delegate: Arc<dyn CqueryDelegate + 'c>,
         literals: Arc<dyn QueryLiterals<ConfiguredTargetNode> + 'c>,
-        universe: Option<CqueryUniverse>,
+        universe: Option<Arc<CqueryUniverse>>,
         owner_behavior: CqueryOwnerBehavior,
     ) -> Self {
         Self {
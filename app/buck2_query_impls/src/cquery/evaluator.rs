@@ -18,6 +18,8 @@ use buck2_core::target::label::TargetLabel;
 use buck2_events::dispatch::console_message;
 use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_query::query::syntax::simple::eval::call_cache::InMemoryQueryCallCache;
+use buck2_query::query::syntax::simple::eval::call_cache::QueryCallCache;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use dice::DiceComputations;
@@ -26,9 +28,10 @@ use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use gazebo::prelude::*;
 
-use crate::analysis::evaluator::eval_query;
+use crate::analysis::evaluator::eval_query_with_cache;
 use crate::cquery::environment::CqueryEnvironment;
 use crate::dice::get_dice_query_delegate;
+use crate::dice::query_cache::DiceQueryCallCache;
 use crate::dice::DiceQueryDelegate;
 use crate::uquery::environment::PreresolvedQueryLiterals;
 use crate::uquery::environment::QueryLiterals;
@@ -38,17 +41,19 @@ pub struct CqueryEvaluator<'c> {
     dice_query_delegate: Arc<DiceQueryDelegate<'c>>,
     functions: DefaultQueryFunctionsModule<CqueryEnvironment<'c>>,
     owner_behavior: CqueryOwnerBehavior,
+    /// Where function calls and universes are cached, if not just for the evaluation.
+    call_cache: Option<DiceQueryCallCache<'c>>,
 }
 
-impl CqueryEvaluator<'_> {
+impl<'c> CqueryEvaluator<'c> {
     pub async fn eval_query<A: AsRef<str>, U: AsRef<str>>(
         &self,
         query: &str,
         query_args: &[A],
         target_universe: Option<&[U]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
-        eval_query(&self.functions, query, query_args, async move |literals| {
-            let (universe, resolved_literals) = match target_universe {
+        eval_query_with_cache(&self.functions, query, query_args, async move |literals| {
+            let universe_patterns = match target_universe {
                 None => {
                     if literals.is_empty() {
                         console_message(
@@ -59,20 +64,34 @@ impl CqueryEvaluator<'_> {
                     }
                     // In the absence of a user-provided target universe, we use the target
                     // literals in the cquery as the universe.
-                    resolve_literals_in_universe(&self.dice_query_delegate, &literals, &literals)
-                        .await?
-                }
-                Some(universe) => {
-                    resolve_literals_in_universe(&self.dice_query_delegate, &literals, universe)
-                        .await?
+                    literals.clone()
                 }
+                Some(universe) => universe.map(|v| v.as_ref().to_owned()),
             };
-            Ok(CqueryEnvironment::new(
+            let (universe, cache): (_, Box<dyn QueryCallCache<ConfiguredTargetNode> + 'c>) =
+                match &self.call_cache {
+                    Some(call_cache) => (
+                        call_cache.cquery_universe(&universe_patterns).await?,
+                        Box::new(call_cache.for_cquery(self.owner_behavior, &universe_patterns)),
+                    ),
+                    None => (
+                        Arc::new(
+                            build_cquery_universe(&self.dice_query_delegate, &universe_patterns)
+                                .await?,
+                        ),
+                        Box::new(InMemoryQueryCallCache::new()),
+                    ),
+                };
+            let resolved_literals =
+                resolve_literals_in_universe(&self.dice_query_delegate, &literals, &universe)
+                    .await;
+            let env = CqueryEnvironment::new(
                 self.dice_query_delegate.dupe(),
                 Arc::new(resolved_literals),
                 Some(universe),
                 self.owner_behavior,
-            ))
+            );
+            Ok((env, cache))
         })
         .await
     }
@@ -99,6 +118,8 @@ pub async fn get_cquery_evaluator<'a, 'c: 'a>(
     global_target_platform: Option<TargetLabel>,
     owner_behavior: CqueryOwnerBehavior,
 ) -> anyhow::Result<CqueryEvaluator<'c>> {
+    let call_cache =
+        DiceQueryCallCache::new(ctx, working_dir, global_target_platform.dupe()).await?;
     let dice_query_delegate =
         Arc::new(get_dice_query_delegate(ctx, working_dir, global_target_platform).await?);
     let functions = DefaultQueryFunctionsModule::new();
@@ -106,29 +127,29 @@ pub async fn get_cquery_evaluator<'a, 'c: 'a>(
         dice_query_delegate,
         functions,
         owner_behavior,
+        call_cache,
     })
 }
 
-// This will first resolve the universe to configured nodes and then gather all
-// the deps. From there, it resolves the literals to any matching nodes in the universe deps.
-async fn resolve_literals_in_universe<L: AsRef<str>, U: AsRef<str>>(
+/// Resolves the universe patterns to configured nodes and gathers all their deps.
+pub(crate) async fn build_cquery_universe<U: AsRef<str>>(
     dice_query_delegate: &DiceQueryDelegate<'_>,
-    literals: &[L],
     universe: &[U],
-) -> anyhow::Result<(
-    CqueryUniverse,
-    PreresolvedQueryLiterals<ConfiguredTargetNode>,
-)> {
+) -> anyhow::Result<CqueryUniverse> {
     // TODO(cjhopman): We should probably also resolve the literals to TargetNode so that
     // we can get errors for packages or targets that don't exist or fail to load.
     let refs: Vec<_> = universe.map(|v| v.as_ref());
     let universe_resolved = dice_query_delegate.eval_literals(&refs).await?;
 
-    let universe = CqueryUniverse::build(&universe_resolved).await?;
-
-    // capture a reference so the ref can be moved into the future below.
-    let universe_ref = &universe;
+    CqueryUniverse::build(&universe_resolved).await
+}
 
+// This resolves the literals to any matching nodes in the universe deps.
+async fn resolve_literals_in_universe<L: AsRef<str>>(
+    dice_query_delegate: &DiceQueryDelegate<'_>,
+    literals: &[L],
+    universe: &CqueryUniverse,
+) -> PreresolvedQueryLiterals<ConfiguredTargetNode> {
     // TODO(cjhopman): Using the default resolution for recursive literals is inefficient.
     // If we can have a package-trie or cellpath-trie we can do the resolution directly
     // against the universe.
@@ -138,7 +159,7 @@ async fn resolve_literals_in_universe<L: AsRef<str>, U: AsRef<str>>(
             let lit = lit.as_ref();
             let result: anyhow::Result<_> = try {
                 let resolved_pattern = dice_query_delegate.resolve_target_patterns(&[lit]).await?;
-                universe_ref.get(&resolved_pattern)
+                universe.get(&resolved_pattern)
             };
 
             (lit.to_owned(), result.shared_error())
@@ -146,5 +167,5 @@ async fn resolve_literals_in_universe<L: AsRef<str>, U: AsRef<str>>(
         .collect();
 
     let resolved = resolution_futs.collect().await;
-    Ok((universe, PreresolvedQueryLiterals::new(resolved)))
+    PreresolvedQueryLiterals::new(resolved)
 }
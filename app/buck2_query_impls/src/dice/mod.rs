@@ -64,6 +64,7 @@ use crate::uquery::environment::QueryLiterals;
 use crate::uquery::environment::UqueryDelegate;
 
 pub mod aquery;
+pub(crate) mod query_cache;
 
 #[derive(Debug, thiserror::Error)]
 enum LiteralParserError {
@@ -0,0 +1,533 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Caches the results of `uquery` and `cquery` expressions in DICE.
+//!
+//! The query is evaluated within the computation of a DICE key, so the result depends on
+//! exactly the package listings, build files and target nodes the evaluation looked at, and it is
+//! only evaluated again once one of them changes. This is enabled with `buck2.cache_query_results`.
+//!
+//! With the same setting, the results of the function calls made by the queries are also cached in
+//! DICE, keyed on the function and the values of its arguments, so sub-expressions shared between
+//! queries are only computed once. Without it, function calls repeated with the same arguments are
+//! still computed once within an evaluation (see `InMemoryQueryCallCache`).
+//!
+//! Query results can be large, so all these keys are evictable.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_build_api::query::oneshot::CqueryOwnerBehavior;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::result::SharedResult;
+use buck2_common::result::ToSharedResultExt;
+use buck2_common::result::ToUnsharedResultExt;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_core::target::label::ConfiguredTargetLabel;
+use buck2_core::target::label::TargetLabel;
+use buck2_node::configured_universe::CqueryUniverse;
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::environment::LabeledNode;
+use buck2_query::query::environment::QueryEnvironment;
+use buck2_query::query::environment::QueryTarget;
+use buck2_query::query::syntax::simple::eval::call_cache::ArgKey;
+use buck2_query::query::syntax::simple::eval::call_cache::CallKey;
+use buck2_query::query::syntax::simple::eval::call_cache::QueryCallCache;
+use buck2_query::query::syntax::simple::eval::evaluator::QueryEvaluator;
+use buck2_query::query::syntax::simple::eval::file_set::FileNode;
+use buck2_query::query::syntax::simple::eval::file_set::FileSet;
+use buck2_query::query::syntax::simple::eval::multi_query::MultiQueryResult;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
+use buck2_query::query::syntax::simple::eval::values::QueryValue;
+use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::Key;
+use dice::StorageType;
+use dupe::Dupe;
+use futures::future;
+use more_futures::cancellation::CancellationContext;
+
+use crate::cquery::environment::CqueryEnvironment;
+use crate::cquery::evaluator::build_cquery_universe;
+use crate::cquery::evaluator::get_cquery_evaluator;
+use crate::dice::get_dice_query_delegate;
+use crate::uquery::environment::PreresolvedQueryLiterals;
+use crate::uquery::environment::UqueryEnvironment;
+use crate::uquery::evaluator::get_uquery_evaluator;
+
+/// A query result that can be stored in DICE and handed out again.
+#[derive(Allocative)]
+enum CachedQueryResult<T: QueryTarget> {
+    Single(QueryEvaluationValue<T>),
+    Multiple(#[allocative(skip)] Vec<(String, SharedResult<QueryEvaluationValue<T>>)>),
+}
+
+impl<T: QueryTarget> CachedQueryResult<T> {
+    fn new(result: QueryEvaluationResult<T>) -> Self {
+        match result {
+            QueryEvaluationResult::Single(value) => CachedQueryResult::Single(value),
+            QueryEvaluationResult::Multiple(results) => CachedQueryResult::Multiple(
+                results
+                    .0
+                    .into_iter()
+                    .map(|(literal, result)| (literal, result.shared_error()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn to_result(&self) -> QueryEvaluationResult<T> {
+        match self {
+            CachedQueryResult::Single(value) => QueryEvaluationResult::Single(value.clone()),
+            CachedQueryResult::Multiple(results) => {
+                QueryEvaluationResult::Multiple(MultiQueryResult(
+                    results
+                        .iter()
+                        .map(|(literal, result)| {
+                            let result = result.clone().unshared_error();
+                            (literal.clone(), result)
+                        })
+                        .collect(),
+                ))
+            }
+        }
+    }
+}
+
+/// Whether `uquery` and `cquery` results are cached in DICE.
+pub(crate) async fn query_cache_enabled(ctx: &DiceComputations) -> anyhow::Result<bool> {
+    let cells = ctx.get_cell_resolver().await?;
+    Ok(ctx
+        .parse_legacy_config_property(cells.root_cell(), "buck2", "cache_query_results")
+        .await?
+        .unwrap_or(false))
+}
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "uquery `{}` ({})", query, "query_args.join(\", \")")]
+struct UqueryResultKey {
+    working_dir: ProjectRelativePathBuf,
+    query: String,
+    query_args: Vec<String>,
+    global_target_platform: Option<TargetLabel>,
+}
+
+#[async_trait]
+impl Key for UqueryResultKey {
+    type Value = SharedResult<Arc<CachedQueryResult<TargetNode>>>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let evaluator =
+            get_uquery_evaluator(ctx, &self.working_dir, self.global_target_platform.dupe())
+                .await?;
+        let result = evaluator.eval_query(&self.query, &self.query_args).await?;
+        Ok(Arc::new(CachedQueryResult::new(result)))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        // Nothing depends on query results, so there's nothing to save by comparing them.
+        false
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "cquery `{}` ({})", query, "query_args.join(\", \")")]
+struct CqueryResultKey {
+    working_dir: ProjectRelativePathBuf,
+    correct_owner: bool,
+    query: String,
+    query_args: Vec<String>,
+    global_target_platform: Option<TargetLabel>,
+    target_universe: Option<Vec<String>>,
+}
+
+#[async_trait]
+impl Key for CqueryResultKey {
+    type Value = SharedResult<Arc<CachedQueryResult<ConfiguredTargetNode>>>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let owner_behavior = if self.correct_owner {
+            CqueryOwnerBehavior::Correct
+        } else {
+            CqueryOwnerBehavior::Deprecated
+        };
+        let evaluator = get_cquery_evaluator(
+            ctx,
+            &self.working_dir,
+            self.global_target_platform.dupe(),
+            owner_behavior,
+        )
+        .await?;
+        let result = evaluator
+            .eval_query(
+                &self.query,
+                &self.query_args,
+                self.target_universe.as_ref().map(|v| &v[..]),
+            )
+            .await?;
+        Ok(Arc::new(CachedQueryResult::new(result)))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        // Nothing depends on query results, so there's nothing to save by comparing them.
+        false
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+pub(crate) async fn eval_uquery_cached(
+    ctx: &DiceComputations,
+    working_dir: &ProjectRelativePath,
+    query: &str,
+    query_args: &[String],
+    global_target_platform: Option<TargetLabel>,
+) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
+    let key = UqueryResultKey {
+        working_dir: working_dir.to_owned(),
+        query: query.to_owned(),
+        query_args: query_args.to_vec(),
+        global_target_platform,
+    };
+    Ok(ctx.compute(&key).await??.to_result())
+}
+
+pub(crate) async fn eval_cquery_cached(
+    ctx: &DiceComputations,
+    working_dir: &ProjectRelativePath,
+    owner_behavior: CqueryOwnerBehavior,
+    query: &str,
+    query_args: &[String],
+    global_target_platform: Option<TargetLabel>,
+    target_universe: Option<&[String]>,
+) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+    let key = CqueryResultKey {
+        working_dir: working_dir.to_owned(),
+        correct_owner: matches!(owner_behavior, CqueryOwnerBehavior::Correct),
+        query: query.to_owned(),
+        query_args: query_args.to_vec(),
+        global_target_platform,
+        target_universe: target_universe.map(|v| v.to_vec()),
+    };
+    Ok(ctx.compute(&key).await??.to_result())
+}
+
+/// The result of a query function call that can be stored in DICE.
+#[derive(Allocative)]
+struct CachedCallResult<T: QueryTarget>(#[allocative(skip)] QueryValue<T>);
+
+/// Invokes a function with the arguments of `call`. Target sets are passed by label, so their
+/// nodes are looked up again.
+async fn invoke_call<Env: QueryEnvironment>(
+    env: &Env,
+    call: &CallKey<<Env::Target as LabeledNode>::NodeRef>,
+) -> anyhow::Result<QueryValue<Env::Target>> {
+    let mut args = Vec::with_capacity(call.args().len());
+    for arg in call.args() {
+        args.push(match arg {
+            ArgKey::String(s) => QueryValue::String(s.clone()),
+            ArgKey::Integer(i) => QueryValue::Integer(*i),
+            ArgKey::TargetSet(labels) => QueryValue::TargetSet(
+                future::try_join_all(labels.iter().map(|label| env.get_node(label)))
+                    .await?
+                    .into_iter()
+                    .collect(),
+            ),
+            ArgKey::FileSet(paths) => QueryValue::FileSet(FileSet::new(
+                paths.iter().map(|path| FileNode(path.clone())).collect(),
+            )),
+        });
+    }
+    let functions = DefaultQueryFunctionsModule::new();
+    QueryEvaluator::new(env, &functions)
+        .invoke_function(call.function(), args)
+        .await
+}
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "uquery call `{}`", call)]
+struct UqueryCallKey {
+    working_dir: ProjectRelativePathBuf,
+    global_target_platform: Option<TargetLabel>,
+    call: CallKey<TargetLabel>,
+}
+
+#[async_trait]
+impl Key for UqueryCallKey {
+    type Value = SharedResult<Arc<CachedCallResult<TargetNode>>>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let delegate = Arc::new(
+            get_dice_query_delegate(ctx, &self.working_dir, self.global_target_platform.dupe())
+                .await?,
+        );
+        // Function calls only get values, so there are no literals to resolve.
+        let env = UqueryEnvironment::new(
+            delegate,
+            Arc::new(PreresolvedQueryLiterals::new(HashMap::new())),
+        );
+        Ok(Arc::new(CachedCallResult(
+            invoke_call(&env, &self.call).await?,
+        )))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        // Comparing target sets means comparing the nodes, which is not worth it.
+        false
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "cquery call `{}`", call)]
+struct CqueryCallKey {
+    working_dir: ProjectRelativePathBuf,
+    global_target_platform: Option<TargetLabel>,
+    correct_owner: bool,
+    /// The patterns of the universe, which `owner` looks up files in. Only set when it is used.
+    universe: Option<Vec<String>>,
+    call: CallKey<ConfiguredTargetLabel>,
+}
+
+#[async_trait]
+impl Key for CqueryCallKey {
+    type Value = SharedResult<Arc<CachedCallResult<ConfiguredTargetNode>>>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let (owner_behavior, universe) = match &self.universe {
+            Some(patterns) if self.correct_owner => {
+                let key = CqueryUniverseKey {
+                    working_dir: self.working_dir.clone(),
+                    global_target_platform: self.global_target_platform.dupe(),
+                    patterns: patterns.clone(),
+                };
+                let universe = ctx.compute(&key).await??;
+                (CqueryOwnerBehavior::Correct, Some(universe.0.dupe()))
+            }
+            _ => (CqueryOwnerBehavior::Deprecated, None),
+        };
+        let delegate = Arc::new(
+            get_dice_query_delegate(ctx, &self.working_dir, self.global_target_platform.dupe())
+                .await?,
+        );
+        // Function calls only get values, so there are no literals to resolve.
+        let env = CqueryEnvironment::new(
+            delegate,
+            Arc::new(PreresolvedQueryLiterals::new(HashMap::new())),
+            universe,
+            owner_behavior,
+        );
+        Ok(Arc::new(CachedCallResult(
+            invoke_call(&env, &self.call).await?,
+        )))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        // Comparing target sets means comparing the nodes, which is not worth it.
+        false
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+/// A `CqueryUniverse` that can be stored in DICE.
+#[derive(Allocative, Clone, Dupe)]
+struct SharedCqueryUniverse(#[allocative(skip)] Arc<CqueryUniverse>);
+
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "cquery universe ({})", "patterns.join(\", \")")]
+struct CqueryUniverseKey {
+    working_dir: ProjectRelativePathBuf,
+    global_target_platform: Option<TargetLabel>,
+    patterns: Vec<String>,
+}
+
+#[async_trait]
+impl Key for CqueryUniverseKey {
+    type Value = SharedResult<SharedCqueryUniverse>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let delegate =
+            get_dice_query_delegate(ctx, &self.working_dir, self.global_target_platform.dupe())
+                .await?;
+        let universe = build_cquery_universe(&delegate, &self.patterns).await?;
+        Ok(SharedCqueryUniverse(Arc::new(universe)))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        // Comparing universes means comparing the nodes, which is not worth it.
+        false
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+/// Caches the function calls made by `uquery` and `cquery` evaluations in DICE.
+#[derive(Clone)]
+pub(crate) struct DiceQueryCallCache<'c> {
+    ctx: &'c DiceComputations,
+    working_dir: ProjectRelativePathBuf,
+    global_target_platform: Option<TargetLabel>,
+}
+
+impl<'c> DiceQueryCallCache<'c> {
+    /// The cache for the queries evaluated in `ctx`, if query results are cached in DICE.
+    pub(crate) async fn new(
+        ctx: &'c DiceComputations,
+        working_dir: &ProjectRelativePath,
+        global_target_platform: Option<TargetLabel>,
+    ) -> anyhow::Result<Option<Self>> {
+        if !query_cache_enabled(ctx).await? {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            ctx,
+            working_dir: working_dir.to_owned(),
+            global_target_platform,
+        }))
+    }
+
+    /// The universe of the targets matching `patterns`.
+    pub(crate) async fn cquery_universe(
+        &self,
+        patterns: &[String],
+    ) -> anyhow::Result<Arc<CqueryUniverse>> {
+        let key = CqueryUniverseKey {
+            working_dir: self.working_dir.clone(),
+            global_target_platform: self.global_target_platform.dupe(),
+            patterns: patterns.to_vec(),
+        };
+        Ok(self.ctx.compute(&key).await??.0)
+    }
+
+    /// The cache for `cquery` evaluations in the universe of the targets matching `universe`.
+    pub(crate) fn for_cquery(
+        &self,
+        owner_behavior: CqueryOwnerBehavior,
+        universe: &[String],
+    ) -> DiceCqueryCallCache<'c> {
+        let correct_owner = matches!(owner_behavior, CqueryOwnerBehavior::Correct);
+        DiceCqueryCallCache {
+            cache: self.clone(),
+            correct_owner,
+            universe: correct_owner.then(|| universe.to_vec()),
+        }
+    }
+}
+
+#[async_trait]
+impl QueryCallCache<TargetNode> for DiceQueryCallCache<'_> {
+    async fn get(
+        &self,
+        call: &CallKey<TargetLabel>,
+    ) -> anyhow::Result<Option<QueryValue<TargetNode>>> {
+        let key = UqueryCallKey {
+            working_dir: self.working_dir.clone(),
+            global_target_platform: self.global_target_platform.dupe(),
+            call: call.clone(),
+        };
+        Ok(Some(self.ctx.compute(&key).await??.0.clone()))
+    }
+
+    fn insert(&self, _call: CallKey<TargetLabel>, _result: QueryValue<TargetNode>) {
+        // `get` computes every call in DICE.
+    }
+}
+
+/// Caches the function calls made by `cquery` evaluations in DICE.
+pub(crate) struct DiceCqueryCallCache<'c> {
+    cache: DiceQueryCallCache<'c>,
+    correct_owner: bool,
+    universe: Option<Vec<String>>,
+}
+
+#[async_trait]
+impl QueryCallCache<ConfiguredTargetNode> for DiceCqueryCallCache<'_> {
+    async fn get(
+        &self,
+        call: &CallKey<ConfiguredTargetLabel>,
+    ) -> anyhow::Result<Option<QueryValue<ConfiguredTargetNode>>> {
+        let key = CqueryCallKey {
+            working_dir: self.cache.working_dir.clone(),
+            global_target_platform: self.cache.global_target_platform.dupe(),
+            correct_owner: self.correct_owner,
+            universe: self.universe.clone(),
+            call: call.clone(),
+        };
+        Ok(Some(self.cache.ctx.compute(&key).await??.0.clone()))
+    }
+
+    fn insert(
+        &self,
+        _call: CallKey<ConfiguredTargetLabel>,
+        _result: QueryValue<ConfiguredTargetNode>,
+    ) {
+        // `get` computes every call in DICE.
+    }
+}
@@ -24,6 +24,9 @@ use crate::aquery::evaluator::get_aquery_evaluator;
 use crate::cquery::evaluator::get_cquery_evaluator;
 use crate::cquery::evaluator::preresolve_literals_and_build_universe;
 use crate::dice::get_dice_query_delegate;
+use crate::dice::query_cache::eval_cquery_cached;
+use crate::dice::query_cache::eval_uquery_cached;
+use crate::dice::query_cache::query_cache_enabled;
 use crate::uquery::evaluator::get_uquery_evaluator;
 
 struct QueryFrontendImpl;
@@ -42,6 +45,11 @@ impl QueryFrontend for QueryFrontendImpl {
         query_args: &[String],
         global_target_platform: Option<TargetLabel>,
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
+        if query_cache_enabled(ctx).await? {
+            return eval_uquery_cached(ctx, working_dir, query, query_args, global_target_platform)
+                .await;
+        }
+
         let evaluator = get_uquery_evaluator(ctx, working_dir, global_target_platform).await?;
 
         evaluator.eval_query(query, query_args).await
@@ -57,6 +65,19 @@ impl QueryFrontend for QueryFrontendImpl {
         global_target_platform: Option<TargetLabel>,
         target_universe: Option<&[String]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
+        if query_cache_enabled(ctx).await? {
+            return eval_cquery_cached(
+                ctx,
+                working_dir,
+                owner_behavior,
+                query,
+                query_args,
+                global_target_platform,
+                target_universe,
+            )
+            .await;
+        }
+
         let evaluator =
             get_cquery_evaluator(ctx, working_dir, global_target_platform, owner_behavior).await?;
 
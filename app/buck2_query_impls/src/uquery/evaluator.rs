@@ -13,13 +13,16 @@ use std::sync::Arc;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::target::label::TargetLabel;
 use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::syntax::simple::eval::call_cache::InMemoryQueryCallCache;
+use buck2_query::query::syntax::simple::eval::call_cache::QueryCallCache;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use dice::DiceComputations;
 use dupe::Dupe;
 
-use crate::analysis::evaluator::eval_query;
+use crate::analysis::evaluator::eval_query_with_cache;
 use crate::dice::get_dice_query_delegate;
+use crate::dice::query_cache::DiceQueryCallCache;
 use crate::dice::DiceQueryDelegate;
 use crate::uquery::environment::PreresolvedQueryLiterals;
 use crate::uquery::environment::UqueryEnvironment;
@@ -27,21 +30,28 @@ use crate::uquery::environment::UqueryEnvironment;
 pub struct UqueryEvaluator<'c> {
     dice_query_delegate: Arc<DiceQueryDelegate<'c>>,
     functions: DefaultQueryFunctionsModule<UqueryEnvironment<'c>>,
+    /// Where function calls are cached, if not just for the evaluation.
+    call_cache: Option<DiceQueryCallCache<'c>>,
 }
 
-impl UqueryEvaluator<'_> {
+impl<'c> UqueryEvaluator<'c> {
     pub async fn eval_query(
         &self,
         query: &str,
         query_args: &[String],
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
-        eval_query(&self.functions, query, query_args, async move |literals| {
+        eval_query_with_cache(&self.functions, query, query_args, async move |literals| {
             let resolved_literals =
                 PreresolvedQueryLiterals::pre_resolve(&*self.dice_query_delegate, &literals).await;
-            Ok(UqueryEnvironment::new(
+            let env = UqueryEnvironment::new(
                 self.dice_query_delegate.dupe(),
                 Arc::new(resolved_literals),
-            ))
+            );
+            let cache: Box<dyn QueryCallCache<TargetNode> + 'c> = match &self.call_cache {
+                Some(call_cache) => Box::new(call_cache.clone()),
+                None => Box::new(InMemoryQueryCallCache::new()),
+            };
+            Ok((env, cache))
         })
         .await
     }
@@ -54,6 +64,8 @@ pub async fn get_uquery_evaluator<'a, 'c: 'a>(
     working_dir: &'a ProjectRelativePath,
     global_target_platform: Option<TargetLabel>,
 ) -> anyhow::Result<UqueryEvaluator<'c>> {
+    let call_cache =
+        DiceQueryCallCache::new(ctx, working_dir, global_target_platform.dupe()).await?;
     let dice_query_delegate =
         Arc::new(get_dice_query_delegate(ctx, working_dir, global_target_platform).await?);
     let functions = DefaultQueryFunctionsModule::new();
@@ -61,5 +73,6 @@ pub async fn get_uquery_evaluator<'a, 'c: 'a>(
     Ok(UqueryEvaluator {
         dice_query_delegate,
         functions,
+        call_cache,
     })
 }
@@ -389,6 +389,7 @@ mod tests {
                                     finished: 2,
                                     check_deps_started: 0,
                                     check_deps_finished: 0,
+                                    ..Default::default()
                                 },
                             );
                             map
@@ -13,6 +13,7 @@ use std::io::BufWriter;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
 
 use allocative::Allocative;
@@ -79,7 +80,10 @@ use buck2_execute::re::client::RemoteExecutionClient;
 use buck2_execute::re::manager::ReConnectionHandle;
 use buck2_execute::re::manager::ReConnectionManager;
 use buck2_execute::re::manager::ReConnectionObserver;
+use buck2_execute_impl::executors::local_action_cache::LocalActionCache;
+use buck2_execute_impl::executors::local_action_cache::LocalActionCacheLinkMethod;
 use buck2_execute_impl::executors::worker::WorkerPool;
+use buck2_execute_impl::executors::worker::WorkerPoolConfig;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
 use buck2_forkserver::client::ForkserverClient;
 use buck2_interpreter::bxl::CONFIGURE_BXL_FILE_GLOBALS;
@@ -91,9 +95,13 @@ use buck2_interpreter::extra::InterpreterHostPlatform;
 use buck2_interpreter_for_build::interpreter::configuror::BuildInterpreterConfiguror;
 use buck2_interpreter_for_build::interpreter::cycles::LoadCycleDescriptor;
 use buck2_interpreter_for_build::interpreter::interpreter_setup::setup_interpreter;
+use buck2_interpreter_for_build::interpreter::module_cache::BzlModuleCache;
+use buck2_interpreter_for_build::interpreter::module_cache::SetBzlModuleCache;
+use buck2_server_ctx::concurrency::CommandMaxComputeTimes;
 use buck2_server_ctx::concurrency::ConcurrencyHandler;
 use buck2_server_ctx::concurrency::DiceDataProvider;
 use buck2_server_ctx::concurrency::DiceUpdater;
+use buck2_server_ctx::concurrency::HasCommandMaxComputeTimes;
 use buck2_server_ctx::ctx::DiceAccessor;
 use buck2_server_ctx::ctx::PrivateStruct;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
@@ -170,6 +178,8 @@ pub struct BaseServerCommandContext {
     pub create_unhashed_outputs_lock: Arc<Mutex<()>>,
     /// Http client used during run actions; shared with materializer.
     pub http_client: Arc<dyn HttpClient>,
+    /// Where evaluated `.bzl` modules are cached for the next daemon.
+    pub bzl_module_cache_dir: AbsNormPathBuf,
 }
 
 /// ServerCommandContext provides access to the global daemon state and information about the calling client for
@@ -415,6 +425,7 @@ impl<'a> ServerCommandContext<'a> {
                 .as_ref()
                 .map_or(false, |opts| opts.keep_going),
             http_client: self.base_context.http_client.dupe(),
+            bzl_module_cache_dir: self.base_context.bzl_module_cache_dir.clone(),
         }
     }
 
@@ -514,6 +525,7 @@ struct DiceCommandDataProvider {
     starlark_debugger: Option<BuckStarlarkDebuggerHandle>,
     keep_going: bool,
     http_client: Arc<dyn HttpClient>,
+    bzl_module_cache_dir: AbsNormPathBuf,
 }
 
 #[async_trait]
@@ -582,15 +594,53 @@ impl DiceDataProvider for DiceCommandDataProvider {
             run_action_knobs.enforce_re_timeouts = enforce_re_timeouts;
         }
 
+        let max_compute_times = Arc::new(CommandMaxComputeTimes::default());
         let mut data = UserComputationData {
             data,
-            tracker: Arc::new(BuckDiceTracker::new(self.events.dupe())),
+            tracker: Arc::new(BuckDiceTracker::new(
+                self.events.dupe(),
+                max_compute_times.dupe(),
+            )),
             cycle_detector,
             activation_tracker: Some(self.build_signals.activation_tracker.dupe()),
             ..Default::default()
         };
 
-        let worker_pool = Arc::new(WorkerPool::new());
+        let worker_pool = Arc::new(WorkerPool::new(WorkerPoolConfig {
+            idle_timeout: root_config
+                .parse::<u64>("build", "worker_idle_timeout_s")?
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            max_rss_bytes: root_config
+                .parse::<u64>("build", "worker_max_rss_mb")?
+                .map(|mb| mb * 1024 * 1024),
+        }));
+
+        let local_action_cache = root_config
+            .get("buck2", "local_action_cache_dir")
+            .map(|dir| {
+                let dir = AbsNormPathBuf::try_from(dir.to_owned())
+                    .with_context(|| format!("Invalid buck2.local_action_cache_dir: `{}`", dir))?;
+                let link_method = root_config
+                    .parse("buck2", "local_action_cache_link_method")?
+                    .unwrap_or(LocalActionCacheLinkMethod::Hardlink);
+                let max_bytes = root_config
+                    .parse::<u64>("buck2", "local_action_cache_max_mb")?
+                    .unwrap_or(10 * 1024)
+                    * 1024
+                    * 1024;
+                anyhow::Ok(Arc::new(LocalActionCache::new(dir, link_method, max_bytes)))
+            })
+            .transpose()?;
+
+        let bzl_module_cache = if root_config
+            .parse::<bool>("buck2", "bzl_module_cache")?
+            .unwrap_or(true)
+        {
+            Some(BzlModuleCache::new(self.bzl_module_cache_dir.clone()))
+        } else {
+            None
+        };
 
         set_fallback_executor_config(&mut data.data, self.executor_config.dupe());
         data.set_re_client(self.re_connection.get_client());
@@ -611,6 +661,7 @@ impl DiceDataProvider for DiceCommandDataProvider {
                 .project_root()
                 .to_owned(),
             worker_pool,
+            local_action_cache,
         )));
         data.set_blocking_executor(self.blocking_executor.dupe());
         data.set_http_client(self.http_client.dupe());
@@ -618,8 +669,10 @@ impl DiceDataProvider for DiceCommandDataProvider {
         data.set_build_signals(self.build_signals.build_signals.dupe());
         data.set_run_action_knobs(run_action_knobs);
         data.set_create_unhashed_symlink_lock(self.create_unhashed_symlink_lock.dupe());
+        data.set_bzl_module_cache(bzl_module_cache);
         data.set_starlark_debugger_handle(self.starlark_debugger.clone().map(|v| Box::new(v) as _));
         data.set_keep_going(self.keep_going);
+        data.set_command_max_compute_times(max_compute_times);
         data.spawner = Arc::new(BuckSpawner::default());
 
         let tags = vec![
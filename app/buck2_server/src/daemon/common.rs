@@ -31,6 +31,7 @@ use buck2_execute::execute::dice_data::CommandExecutorResponse;
 use buck2_execute::execute::dice_data::HasCommandExecutor;
 use buck2_execute::execute::prepared::NoOpCommandExecutor;
 use buck2_execute::execute::prepared::PreparedCommandExecutor;
+use buck2_execute::execute::prepared::PreparedCommandOptionalExecutor;
 use buck2_execute::execute::request::ExecutorPreference;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::Materializer;
@@ -39,6 +40,7 @@ use buck2_execute_impl::executors::action_cache::ActionCacheChecker;
 use buck2_execute_impl::executors::caching::CachingExecutor;
 use buck2_execute_impl::executors::hybrid::HybridExecutor;
 use buck2_execute_impl::executors::local::LocalExecutor;
+use buck2_execute_impl::executors::local_action_cache::LocalActionCache;
 use buck2_execute_impl::executors::re::ReExecutor;
 use buck2_execute_impl::executors::worker::WorkerPool;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
@@ -78,6 +80,7 @@ pub struct CommandExecutorFactory {
     pub skip_cache_write: bool,
     project_root: ProjectRoot,
     worker_pool: Arc<WorkerPool>,
+    local_action_cache: Option<Arc<LocalActionCache>>,
 }
 
 impl CommandExecutorFactory {
@@ -95,6 +98,7 @@ impl CommandExecutorFactory {
         skip_cache_write: bool,
         project_root: ProjectRoot,
         worker_pool: Arc<WorkerPool>,
+        local_action_cache: Option<Arc<LocalActionCache>>,
     ) -> Self {
         Self {
             re_connection,
@@ -110,6 +114,7 @@ impl CommandExecutorFactory {
             skip_cache_write,
             project_root,
             worker_pool,
+            local_action_cache,
         }
     }
 }
@@ -138,6 +143,72 @@ impl HasCommandExecutor for CommandExecutorFactory {
             )
         };
 
+        // NOTE: While we now have a legit flag for this, we keep the env var. This has been used
+        // in remediating prod incidents in the past, and this is the kind of thing that can easily
+        // become tribal knowledge. Keeping this does not hurt us.
+        static DISABLE_CACHING: EnvHelper<bool> = EnvHelper::new("BUCK2_TEST_DISABLE_CACHING");
+
+        let disable_caching = DISABLE_CACHING
+            .get_copied()?
+            .unwrap_or(self.skip_cache_read);
+
+        // Wraps `inner` so that it goes through the local action cache, and the RE action cache if
+        // enabled.
+        let caching_executor_new =
+            |inner: Arc<dyn PreparedCommandExecutor>,
+             re_use_case: RemoteExecutorUseCase,
+             remote_cache_enabled: bool,
+             cache_upload_behavior: CacheUploadBehavior| {
+                let executor = CachingExecutor {
+                    inner,
+                    artifact_fs: artifact_fs.clone(),
+                    materializer: self.materializer.dupe(),
+                    re_client: self.re_connection.get_client(),
+                    re_use_case,
+                    remote_cache_enabled,
+                    local_action_cache: self.local_action_cache.dupe(),
+                    skip_local_cache_read: disable_caching,
+                    skip_local_cache_write: self.skip_cache_write,
+                    upload_all_actions: self.upload_all_actions,
+                    knobs: self.executor_global_knobs.dupe(),
+                    cache_upload_behavior,
+                };
+                let cache_checker = ActionCacheChecker {
+                    artifact_fs: artifact_fs.clone(),
+                    materializer: self.materializer.dupe(),
+                    re_client: self.re_connection.get_client(),
+                    re_use_case,
+                    remote_cache_enabled,
+                    local_action_cache: if disable_caching {
+                        None
+                    } else {
+                        self.local_action_cache.dupe()
+                    },
+                    upload_all_actions: self.upload_all_actions,
+                };
+                (
+                    Arc::new(executor) as Arc<dyn PreparedCommandExecutor>,
+                    Arc::new(cache_checker) as Arc<dyn PreparedCommandOptionalExecutor>,
+                )
+            };
+
+        // Local executors only go through the local action cache, if there is one.
+        let local_caching_executor_new = |executor: LocalExecutor| {
+            if self.local_action_cache.is_some() {
+                caching_executor_new(
+                    Arc::new(executor),
+                    RemoteExecutorUseCase::buck2_default(),
+                    false,
+                    CacheUploadBehavior::Disabled,
+                )
+            } else {
+                (
+                    Arc::new(executor) as _,
+                    Arc::new(NoOpCommandExecutor {}) as _,
+                )
+            }
+        };
+
         if !buck2_core::is_open_source() && !cfg!(fbcode_build) {
             static WARN: OnceCell<()> = OnceCell::new();
             WARN.get_or_init(|| {
@@ -151,10 +222,13 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 ));
             }
 
+            let (executor, cache_checker) =
+                local_caching_executor_new(local_executor_new(&LocalExecutorOptions::default()));
+
             return Ok(CommandExecutorResponse {
-                executor: Arc::new(local_executor_new(&LocalExecutorOptions::default())),
+                executor,
                 platform: Default::default(),
-                cache_checker: Arc::new(NoOpCommandExecutor {}),
+                cache_checker,
             });
         }
 
@@ -186,10 +260,13 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 if self.strategy.ban_local() {
                     None
                 } else {
+                    let (executor, cache_checker) =
+                        local_caching_executor_new(local_executor_new(local));
+
                     Some(CommandExecutorResponse {
-                        executor: Arc::new(local_executor_new(local)),
+                        executor,
                         platform: Default::default(),
-                        cache_checker: Arc::new(NoOpCommandExecutor {}),
+                        cache_checker,
                     })
                 }
             }
@@ -225,41 +302,25 @@ impl HasCommandExecutor for CommandExecutorFactory {
                     _ => None,
                 };
 
-                // NOTE: While we now have a legit flag for this, we keep the env var. This has been used
-                // in remediating prod incidents in the past, and this is the kind of thing that can easily
-                // become tribal knowledge. Keeping this does not hurt us.
-                static DISABLE_CACHING: EnvHelper<bool> =
-                    EnvHelper::new("BUCK2_TEST_DISABLE_CACHING");
-
-                let disable_caching = DISABLE_CACHING
-                    .get_copied()?
-                    .unwrap_or(self.skip_cache_read);
+                let remote_cache_enabled = !disable_caching && *remote_cache_enabled;
 
-                let (executor, cache_checker) = if disable_caching || !remote_cache_enabled {
-                    (inner_executor, Arc::new(NoOpCommandExecutor {}) as _)
-                } else {
-                    (
-                        inner_executor.map(|inner_executor| {
-                            Arc::new(CachingExecutor {
-                                inner: inner_executor,
-                                artifact_fs: artifact_fs.clone(),
-                                materializer: self.materializer.dupe(),
-                                re_client: self.re_connection.get_client(),
-                                re_use_case: *re_use_case,
-                                upload_all_actions: self.upload_all_actions,
-                                knobs: self.executor_global_knobs.dupe(),
-                                cache_upload_behavior: *cache_upload_behavior,
-                            }) as _
-                        }),
-                        Arc::new(ActionCacheChecker {
-                            artifact_fs: artifact_fs.clone(),
-                            materializer: self.materializer.dupe(),
-                            re_client: self.re_connection.get_client(),
-                            re_use_case: *re_use_case,
-                            upload_all_actions: self.upload_all_actions,
-                        }) as _,
-                    )
-                };
+                let (executor, cache_checker) =
+                    if !remote_cache_enabled && self.local_action_cache.is_none() {
+                        (inner_executor, Arc::new(NoOpCommandExecutor {}) as _)
+                    } else {
+                        match inner_executor {
+                            Some(inner_executor) => {
+                                let (executor, cache_checker) = caching_executor_new(
+                                    inner_executor,
+                                    *re_use_case,
+                                    remote_cache_enabled,
+                                    *cache_upload_behavior,
+                                );
+                                (Some(executor), cache_checker)
+                            }
+                            None => (None, Arc::new(NoOpCommandExecutor {}) as _),
+                        }
+                    };
 
                 let platform = RE::Platform {
                     properties: re_properties
@@ -9,6 +9,7 @@
 
 use std::fs::File;
 use std::io::BufWriter;
+use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -50,7 +51,12 @@ pub(crate) fn tar_dice_dump(dice_dump_folder: &Path) -> anyhow::Result<()> {
     let tar_gz = File::create(format!("{}.tar.gz", dice_dump_folder.display()))?;
     let enc = GzEncoder::new(tar_gz, Compression::default());
     let mut tar = tar::Builder::new(enc);
-    let files = vec!["nodes.gz", "edges.gz", "nodes_currently_running.gz"];
+    let files = vec![
+        "nodes.gz",
+        "edges.gz",
+        "nodes_currently_running.gz",
+        "key_types.gz",
+    ];
     for file_name in files {
         let mut file = File::open(dice_dump_folder.join(file_name)).context(format!(
             "Failed to open file `{}` for compressing",
@@ -71,6 +77,7 @@ fn dice_dump_tsv(dice: &Arc<Dice>, path: &Path) -> anyhow::Result<()> {
     let nodes_path = path.join("nodes.gz");
     let edges_path = path.join("edges.gz");
     let nodes_currently_running_path = path.join("nodes_currently_running.gz");
+    let key_types_path = path.join("key_types.gz");
 
     std::fs::create_dir_all(path).context("Failed to create directory")?;
 
@@ -95,8 +102,16 @@ fn dice_dump_tsv(dice: &Arc<Dice>, path: &Path) -> anyhow::Result<()> {
         Compression::default(),
     );
 
+    let key_types = File::create(&key_types_path).context(format!(
+        "Failed to open DICE key types dumpfile {:?}",
+        &key_types_path
+    ))?;
+    let mut key_types = GzEncoder::new(BufWriter::new(key_types), Compression::default());
+
     dice.serialize_tsv(&mut nodes, &mut edges, &mut nodes_currently_running)
         .context("Failed to serialize")?;
+    write_key_type_metrics_tsv(dice, &mut key_types)
+        .context("Failed to serialize key type metrics")?;
 
     nodes
         .try_finish()
@@ -108,7 +123,33 @@ fn dice_dump_tsv(dice: &Arc<Dice>, path: &Path) -> anyhow::Result<()> {
         "Failed to flush DICE nodes currently running to {:?}",
         &nodes_currently_running_path
     ))?;
+    key_types.try_finish().context(format!(
+        "Failed to flush DICE key types to {:?}",
+        &key_types_path
+    ))?;
+
+    Ok(())
+}
 
+/// Writes the compute statistics of each key type since the daemon started, with times in
+/// microseconds.
+fn write_key_type_metrics_tsv(dice: &Dice, mut out: impl Write) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "key_type\tcomputes\tcache_hits\tearly_cutoffs\ttotal_compute_time_us\tmax_compute_time_us"
+    )?;
+    for (key_type, metrics) in dice.metrics().key_types {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            key_type,
+            metrics.computes,
+            metrics.cache_hits,
+            metrics.early_cutoffs,
+            metrics.total_compute_time.as_micros(),
+            metrics.max_compute_time.as_micros(),
+        )?;
+    }
     Ok(())
 }
 
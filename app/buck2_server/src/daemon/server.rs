@@ -57,6 +57,7 @@ use buck2_server_ctx::streaming_request_handler::StreamingRequestHandler;
 use buck2_server_starlark_debug::run::run_dap_server_command;
 use dice::DetectCycles;
 use dice::Dice;
+use dice::PersistentKeys;
 use dice::WhichDice;
 use dupe::Dupe;
 use futures::channel::mpsc;
@@ -74,6 +75,7 @@ use more_futures::drop::DropTogether;
 use more_futures::spawn::spawn_cancellable;
 use rand::RngCore;
 use rand::SeedableRng;
+use starlark::eval::ProfileMode;
 use tokio::sync::oneshot;
 use tonic::service::interceptor;
 use tonic::service::Interceptor;
@@ -91,6 +93,7 @@ use crate::ctx::ServerCommandContext;
 use crate::daemon::multi_event_stream::MultiEventStream;
 use crate::daemon::server_allocative::spawn_allocative;
 use crate::daemon::state::DaemonState;
+use crate::dice_explain::dice_explain_command;
 use crate::file_status::file_status_command;
 use crate::lsp::run_lsp_server_command;
 use crate::materialize::materialize_command;
@@ -151,6 +154,7 @@ impl BuckdServerInitPreferences {
         io: Arc<dyn IoProvider>,
         digest_config: DigestConfig,
         root_config: &LegacyBuckConfig,
+        snapshot: Option<(&PersistentKeys, &[u8])>,
     ) -> anyhow::Result<Arc<Dice>> {
         configure_dice_for_buck(
             io,
@@ -158,6 +162,7 @@ impl BuckdServerInitPreferences {
             Some(root_config),
             self.detect_cycles,
             self.which_dice,
+            snapshot,
         )
         .await
     }
@@ -346,7 +351,7 @@ impl BuckdServer {
                 delegate,
                 shutdown_channel,
             },
-            daemon_state,
+            daemon_state: daemon_state.dupe(),
             command_channel,
             callbacks,
             log_reload_handle,
@@ -364,6 +369,8 @@ impl BuckdServer {
 
         server.await?;
 
+        daemon_state.write_dice_snapshot().await;
+
         Ok(())
     }
 
@@ -932,6 +939,21 @@ impl DaemonApi for BuckdServer {
         .await
     }
 
+    type DiceExplainStream = ResponseStream;
+    async fn dice_explain(
+        &self,
+        req: Request<DiceExplainRequest>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        self.run_streaming(
+            req,
+            DefaultCommandOptions,
+            |context, partial_result_dispatcher, req| {
+                dice_explain_command(context, partial_result_dispatcher, req).boxed()
+            },
+        )
+        .await
+    }
+
     type BuildStream = ResponseStream;
     async fn build(&self, req: Request<BuildRequest>) -> Result<Response<ResponseStream>, Status> {
         let callbacks = self.0.callbacks;
@@ -960,10 +982,30 @@ impl DaemonApi for BuckdServer {
 
     type TestStream = ResponseStream;
     async fn test(&self, req: Request<TestRequest>) -> Result<Response<ResponseStream>, Status> {
+        struct TestCommandOptions;
+
+        impl OneshotCommandOptions for TestCommandOptions {}
+
+        impl StreamingCommandOptions<TestRequest> for TestCommandOptions {
+            fn starlark_profiler_instrumentation_override(
+                &self,
+                req: &TestRequest,
+            ) -> anyhow::Result<StarlarkProfilerConfiguration> {
+                // Coverage is collected by profiling the analysis of the tests recursively.
+                if req.starlark_coverage_path.is_empty() {
+                    Ok(StarlarkProfilerConfiguration::None)
+                } else {
+                    Ok(StarlarkProfilerConfiguration::ProfileAnalysisRecursively(
+                        ProfileMode::Coverage,
+                    ))
+                }
+            }
+        }
+
         let callbacks = self.0.callbacks;
         self.run_streaming(
             req,
-            DefaultCommandOptions,
+            TestCommandOptions,
             |ctx, partial_result_dispatcher, req| {
                 callbacks.test(ctx, partial_result_dispatcher, req)
             },
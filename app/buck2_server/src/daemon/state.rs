@@ -8,6 +8,7 @@
  */
 
 use std::collections::HashMap;
+use std::num::NonZeroU64;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -20,6 +21,7 @@ use buck2_build_api::build_signals::CriticalPathBackendName;
 use buck2_cli_proto::unstable_dice_dump_request::DiceDumpFormat;
 use buck2_common::cas_digest::DigestAlgorithm;
 use buck2_common::cas_digest::DigestAlgorithmKind;
+use buck2_common::dice::persistence::persistent_keys;
 use buck2_common::http::http_client;
 use buck2_common::http::HttpClient;
 use buck2_common::ignores::ignore_set::IgnoreSet;
@@ -49,12 +51,14 @@ use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionManager;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializer;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
+use buck2_execute_impl::materializers::deferred::DiskBudgetConfiguration;
 use buck2_execute_impl::materializers::deferred::TtlRefreshConfiguration;
 use buck2_execute_impl::materializers::immediate::ImmediateMaterializer;
 use buck2_execute_impl::materializers::sqlite::MaterializerState;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateIdentity;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateSqliteDb;
 use buck2_forkserver::client::ForkserverClient;
+use buck2_interpreter_for_build::interpreter::module_cache::BzlModuleCache;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
 use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use buck2_server_ctx::concurrency::ConcurrencyHandler;
@@ -62,6 +66,7 @@ use buck2_server_ctx::concurrency::DiceCleanup;
 use buck2_server_ctx::concurrency::NestedInvocation;
 use buck2_server_ctx::concurrency::ParallelInvocation;
 use buck2_wrapper_common::invocation_id::TraceId;
+use dice::WhichDice;
 use dupe::Dupe;
 use fbinit::FacebookInit;
 use gazebo::prelude::*;
@@ -77,6 +82,12 @@ use crate::daemon::disk_state::DiskStateOptions;
 use crate::daemon::forkserver::maybe_launch_forkserver;
 use crate::daemon::panic::DaemonStatePanicDiceDump;
 use crate::daemon::server::BuckdServerInitPreferences;
+use crate::dice_eviction::spawn_dice_eviction;
+use crate::dice_eviction::DiceEvictionConfiguration;
+use crate::dice_eviction::DiceEvictionError;
+use crate::dice_persistence::read_dice_snapshot;
+use crate::dice_persistence::write_dice_snapshot;
+use crate::dice_persistence::DicePersistenceError;
 use crate::file_watcher::FileWatcher;
 
 /// For a buckd process there is a single DaemonState created at startup and never destroyed.
@@ -162,6 +173,9 @@ pub struct DaemonStateData {
 
     /// Are we using buck-out as our cwd?
     pub cwd_buck_out: bool,
+
+    /// Whether to snapshot the DICE graph when the daemon exits.
+    pub(crate) persist_dice: bool,
 }
 
 impl DaemonStateData {
@@ -242,6 +256,14 @@ impl DaemonState {
             buck2_core::fs::cwd::cwd_will_not_change().context("Error initializing static cwd")?;
         }
 
+        // Cached `.bzl` modules written by other binaries are never read again.
+        let bzl_module_cache_dir = paths.daemon_dir()?.bzl_module_cache();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = BzlModuleCache::remove_stale_entries(&bzl_module_cache_dir) {
+                tracing::warn!("Error removing stale cached `.bzl` modules: {:#}", e);
+            }
+        });
+
         static DEFAULT_DIGEST_ALGORITHM: EnvHelper<DigestAlgorithmKind> =
             EnvHelper::new("BUCK_DEFAULT_DIGEST_ALGORITHM");
 
@@ -327,6 +349,18 @@ impl DaemonState {
                 .unwrap_or_else(RolloutPercentage::never)
                 .roll();
 
+            // Zero is rejected here rather than panicking in the materializer's ticker.
+            let disk_budget_frequency = root_config
+                .parse::<NonZeroU64>("buck2", "materializer_disk_budget_frequency_seconds")?
+                .map_or(300, NonZeroU64::get);
+
+            let disk_budget = root_config
+                .parse::<u64>("buck2", "materializer_disk_budget_mb")?
+                .map(|max_mb| DiskBudgetConfiguration {
+                    frequency: std::time::Duration::from_secs(disk_budget_frequency),
+                    max_bytes: max_mb * 1024 * 1024,
+                });
+
             DeferredMaterializerConfigs {
                 materialize_final_artifacts: matches!(
                     materialization_method,
@@ -338,6 +372,7 @@ impl DaemonState {
                     min_ttl: chrono::Duration::seconds(ttl_refresh_min_ttl),
                     enabled: ttl_refresh_enabled,
                 },
+                disk_budget,
             }
         };
 
@@ -402,10 +437,65 @@ impl DaemonState {
         let forkserver =
             maybe_launch_forkserver(root_config, &paths.forkserver_state_dir()).await?;
 
+        // The snapshot left by the previous daemon, restored before the first command runs.
+        let persist_dice = root_config
+            .parse::<bool>("buck2", "persist_dice")?
+            .unwrap_or(false);
+        let dice_snapshot = if persist_dice {
+            match read_dice_snapshot(
+                &paths.daemon_dir()?.dice_snapshot(),
+                &cells,
+                &legacy_configs,
+            ) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    tracing::warn!("Error reading the DICE snapshot: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let persistent_keys = match &dice_snapshot {
+            Some(_) => Some(persistent_keys(io.dupe(), &cells, &legacy_configs)?),
+            None => None,
+        };
+
         let dice = init_ctx
-            .construct_dice(io.dupe(), digest_config, root_config)
+            .construct_dice(
+                io.dupe(),
+                digest_config,
+                root_config,
+                persistent_keys.as_ref().zip(dice_snapshot.as_deref()),
+            )
             .await?;
 
+        if persist_dice && matches!(dice.which_dice(), WhichDice::Legacy) {
+            return Err(DicePersistenceError::RequiresModernDice.into());
+        }
+
+        // Evicting values that can be recomputed keeps large graphs within a memory limit.
+        // Only the modern DICE implementation supports eviction.
+        if let Some(max_mb) = root_config.parse::<u64>("buck2", "dice_eviction_memory_limit_mb")? {
+            if matches!(dice.which_dice(), WhichDice::Legacy) {
+                return Err(DiceEvictionError::RequiresModernDice.into());
+            }
+            let frequency = root_config
+                .parse::<NonZeroU64>("buck2", "dice_eviction_frequency_seconds")?
+                .map_or(30, NonZeroU64::get);
+            let not_requested_for = root_config
+                .parse("buck2", "dice_eviction_min_idle_seconds")?
+                .unwrap_or(600);
+            spawn_dice_eviction(
+                dice.dupe(),
+                DiceEvictionConfiguration {
+                    frequency: Duration::from_secs(frequency),
+                    max_allocated_bytes: max_mb * 1024 * 1024,
+                    not_requested_for: Duration::from_secs(not_requested_for),
+                },
+            );
+        }
+
         // TODO(cjhopman): We want to use Expr::True here, but we need to workaround
         // https://github.com/facebook/watchman/issues/911. Adding other filetypes to
         // this list should be safe until we can revert it to Expr::True.
@@ -505,6 +595,7 @@ impl DaemonState {
             enable_restarter,
             http_client,
             cwd_buck_out,
+            persist_dice,
         }))
     }
 
@@ -696,6 +787,7 @@ impl DaemonState {
             daemon_start_time: data.start_time,
             create_unhashed_outputs_lock: data.create_unhashed_outputs_lock.dupe(),
             http_client: data.http_client.dupe(),
+            bzl_module_cache_dir: self.paths.daemon_dir()?.bzl_module_cache(),
         })
     }
 
@@ -703,6 +795,28 @@ impl DaemonState {
         Ok(self.data.dupe()?)
     }
 
+    /// Writes the snapshot the next daemon restores, if enabled. Called when the daemon exits.
+    pub(crate) async fn write_dice_snapshot(&self) {
+        let data = match self.data() {
+            Ok(data) if data.persist_dice => data,
+            _ => return,
+        };
+        let res = match self.paths.daemon_dir() {
+            Ok(daemon_dir) => {
+                write_dice_snapshot(
+                    data.dice_manager.unsafe_dice(),
+                    data.io.dupe(),
+                    &daemon_dir.dice_snapshot(),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            tracing::warn!("Error writing the DICE snapshot: {:#}", e);
+        }
+    }
+
     fn validate_buck_out_mount(&self) -> anyhow::Result<()> {
         #[cfg(any(fbcode_build, cargo_internal_build))]
         {
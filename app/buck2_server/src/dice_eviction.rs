@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Drops DICE values that were not requested recently when the daemon allocates too much memory.
+//!
+//! Enabled by setting `buck2.dice_eviction_memory_limit_mb`, which requires the modern DICE
+//! implementation (`buck2.dice = modern`): the legacy one cannot evict values.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dice::Dice;
+
+use crate::jemalloc_stats::get_allocator_stats;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DiceEvictionError {
+    #[error(
+        "`buck2.dice_eviction_memory_limit_mb` requires the modern DICE implementation, set `buck2.dice = modern`"
+    )]
+    RequiresModernDice,
+}
+
+pub(crate) struct DiceEvictionConfiguration {
+    /// How often the allocated memory is checked. Must not be zero.
+    pub(crate) frequency: Duration,
+    /// Evict when more than this many bytes are allocated.
+    pub(crate) max_allocated_bytes: u64,
+    /// Only values not requested for this long are evicted.
+    pub(crate) not_requested_for: Duration,
+}
+
+/// Periodically checks the allocator stats, and evicts the evictable DICE values not requested
+/// recently whenever the daemon is over its memory limit. The task stops if the allocator stats
+/// are unavailable, e.g. when not built with jemalloc, or if DICE doesn't support eviction.
+pub(crate) fn spawn_dice_eviction(dice: Arc<Dice>, config: DiceEvictionConfiguration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.frequency);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            let allocated = match get_allocator_stats() {
+                Ok(stats) => stats.bytes_allocated,
+                Err(e) => {
+                    tracing::warn!(
+                        "Disabling DICE eviction, allocator stats unavailable: {:#}",
+                        e
+                    );
+                    return;
+                }
+            };
+            let Some(allocated) = allocated else {
+                tracing::warn!("Disabling DICE eviction, allocated bytes unavailable");
+                return;
+            };
+            if allocated <= config.max_allocated_bytes {
+                continue;
+            }
+
+            match dice.evict(config.not_requested_for).await {
+                Ok(evicted) => tracing::info!(
+                    "{} bytes allocated, over the limit of {}: evicted {} DICE values",
+                    allocated,
+                    config.max_allocated_bytes,
+                    evicted
+                ),
+                Err(e) => {
+                    tracing::warn!("Disabling DICE eviction: {:#}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
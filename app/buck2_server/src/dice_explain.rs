@@ -0,0 +1,139 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::template::run_server_command;
+use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::introspection::invalidation::InvalidatedKey;
+use dice::Dice;
+use dice::DiceTransaction;
+use dupe::Dupe;
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::ctx::ServerCommandContext;
+
+/// Number of matching keys explained per transaction, since broad patterns can match most of
+/// the graph.
+const MAX_EXPLAINED_KEYS: usize = 100;
+
+pub(crate) async fn dice_explain_command(
+    ctx: &ServerCommandContext<'_>,
+    partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
+    req: buck2_cli_proto::DiceExplainRequest,
+) -> anyhow::Result<buck2_cli_proto::GenericResponse> {
+    run_server_command(
+        DiceExplainServerCommand {
+            req,
+            dice: ctx.base_context.dice_manager.unsafe_dice().dupe(),
+        },
+        ctx,
+        partial_result_dispatcher,
+    )
+    .await
+}
+
+struct DiceExplainServerCommand {
+    req: buck2_cli_proto::DiceExplainRequest,
+    dice: Arc<Dice>,
+}
+
+#[async_trait]
+impl ServerCommandTemplate for DiceExplainServerCommand {
+    type StartEvent = buck2_data::DiceExplainCommandStart;
+    type EndEvent = buck2_data::DiceExplainCommandEnd;
+    type Response = buck2_cli_proto::GenericResponse;
+    type PartialResult = NoPartialResult;
+
+    async fn command(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        _partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
+        _ctx: DiceTransaction,
+    ) -> anyhow::Result<Self::Response> {
+        let pattern = Regex::new(&self.req.key_pattern)
+            .with_context(|| format!("Invalid key pattern `{}`", self.req.key_pattern))?;
+        let mut stderr = server_ctx.stderr()?;
+
+        // Starting the command synced the pending changes, so the latest trace includes them.
+        let traces = self.dice.invalidation_traces().await;
+        if traces.is_empty() {
+            writeln!(stderr, "No invalidations recorded since the daemon started")?;
+        }
+
+        let mut explained = 0;
+        for trace in traces.iter().rev() {
+            let matches = trace
+                .keys
+                .iter()
+                .enumerate()
+                .filter(|(_, k)| pattern.is_match(&k.key))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                continue;
+            }
+
+            let changed = trace.keys.iter().filter(|k| k.dirtied_by.is_none()).count();
+            writeln!(
+                stderr,
+                "Version {}: {} keys changed, {} dirtied, {} matching",
+                trace.version,
+                changed,
+                trace.keys.len() - changed,
+                matches.len(),
+            )?;
+            for index in matches.iter().take(MAX_EXPLAINED_KEYS) {
+                writeln!(
+                    stderr,
+                    "  {}",
+                    trace
+                        .chain(*index)
+                        .into_iter()
+                        .map(display_key)
+                        .join(" -> ")
+                )?;
+            }
+            if matches.len() > MAX_EXPLAINED_KEYS {
+                writeln!(
+                    stderr,
+                    "  ... and {} more",
+                    matches.len() - MAX_EXPLAINED_KEYS
+                )?;
+            }
+            explained += 1;
+        }
+
+        if explained == 0 && !traces.is_empty() {
+            writeln!(
+                stderr,
+                "No key matching `{}` was invalidated by the last {} changes",
+                self.req.key_pattern,
+                traces.len()
+            )?;
+        }
+
+        Ok(buck2_cli_proto::GenericResponse {})
+    }
+
+    fn is_success(&self, _response: &Self::Response) -> bool {
+        true
+    }
+}
+
+fn display_key(key: &InvalidatedKey) -> String {
+    format!("{} ({})", key.key, key.key_type)
+}
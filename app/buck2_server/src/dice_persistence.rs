@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Saves the DICE graph under the daemon dir when the daemon shuts down, and restores it when
+//! the next daemon starts, so that dir and package listings are not computed again. See
+//! `buck2_common::dice::persistence` for what is persisted.
+//!
+//! Enabled by setting `buck2.persist_dice = true`, which requires the modern DICE
+//! implementation (`buck2.dice = modern`). A snapshot is only restored by a daemon running the
+//! same binary with the same cells and buckconfigs, and only once.
+
+use std::sync::Arc;
+
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::persistence::fingerprint;
+use buck2_common::dice::persistence::persistent_keys;
+use buck2_common::io::IoProvider;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::legacy_configs::LegacyBuckConfigs;
+use buck2_core::cells::CellResolver;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use dice::Dice;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DicePersistenceError {
+    #[error(
+        "`buck2.persist_dice` requires the modern DICE implementation, set `buck2.dice = modern`"
+    )]
+    RequiresModernDice,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile {
+    /// See `buck2_common::dice::persistence::fingerprint`.
+    fingerprint: String,
+    snapshot: Vec<u8>,
+}
+
+/// Reads and removes the snapshot written by the previous daemon, returning it if it was taken
+/// with the given cells and buckconfigs.
+pub(crate) fn read_dice_snapshot(
+    path: &AbsNormPath,
+    cells: &CellResolver,
+    configs: &LegacyBuckConfigs,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    if !fs_util::try_exists(path)? {
+        return Ok(None);
+    }
+    let data = fs_util::read(path)?;
+    // The graph will change as soon as this daemon runs a command, so the snapshot is stale
+    // from now on, whether it is restored or not.
+    fs_util::remove_file(path)?;
+
+    let file: SnapshotFile = bincode::deserialize(&data)?;
+    if Some(file.fingerprint) != fingerprint(cells, configs) {
+        tracing::info!("Not restoring the DICE snapshot: cells or buckconfigs changed");
+        return Ok(None);
+    }
+    Ok(Some(file.snapshot))
+}
+
+/// Writes a snapshot of the values computed with the cells and buckconfigs of the last command.
+/// Nothing is written if no command ran.
+pub(crate) async fn write_dice_snapshot(
+    dice: &Dice,
+    io: Arc<dyn IoProvider>,
+    path: &AbsNormPath,
+) -> anyhow::Result<()> {
+    let ctx = dice.updater().commit().await;
+    if !(ctx.is_cell_resolver_key_set().await? && ctx.is_legacy_configs_key_set().await?) {
+        return Ok(());
+    }
+    let cells = ctx.get_cell_resolver().await?;
+    let configs = ctx.get_legacy_configs().await?;
+    drop(ctx);
+
+    let fingerprint = match fingerprint(&cells, &configs) {
+        Some(fingerprint) => fingerprint,
+        None => return Ok(()),
+    };
+    let keys = persistent_keys(io, &cells, &configs)?;
+    let snapshot = dice.snapshot(&keys).await?;
+    let data = bincode::serialize(&SnapshotFile {
+        fingerprint,
+        snapshot,
+    })?;
+
+    // Written to a temporary file first so that a daemon starting concurrently never reads a
+    // partial snapshot.
+    let temp = AbsNormPathBuf::try_from(format!("{}.tmp", path))?;
+    if let Some(dir) = path.parent() {
+        fs_util::create_dir_all(dir)?;
+    }
+    fs_util::write(&temp, data)?;
+    fs_util::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buck2_build_api::configure_dice::configure_dice_for_buck;
+    use buck2_common::dice::cells::SetCellResolver;
+    use buck2_common::dice::persistence::persistent_keys;
+    use buck2_common::io::fs::FsIoProvider;
+    use buck2_common::io::IoProvider;
+    use buck2_common::legacy_configs::dice::SetLegacyConfigs;
+    use buck2_common::legacy_configs::LegacyBuckConfig;
+    use buck2_common::legacy_configs::LegacyBuckConfigs;
+    use buck2_common::package_listing::dice::HasPackageListingResolver;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::paths::CellRelativePath;
+    use buck2_core::cells::CellResolver;
+    use buck2_core::fs::fs_util;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+    use buck2_core::fs::project::ProjectRoot;
+    use buck2_core::package::PackageLabel;
+    use buck2_execute::digest_config::DigestConfig;
+    use dice::Dice;
+    use dice::PersistentKeys;
+    use dice::WhichDice;
+    use dupe::Dupe;
+    use maplit::hashmap;
+
+    use crate::dice_persistence::read_dice_snapshot;
+    use crate::dice_persistence::write_dice_snapshot;
+
+    async fn new_dice(
+        io: Arc<dyn IoProvider>,
+        snapshot: Option<(&PersistentKeys, &[u8])>,
+    ) -> anyhow::Result<Arc<Dice>> {
+        configure_dice_for_buck(
+            io,
+            DigestConfig::testing_default(),
+            None,
+            None,
+            Some(WhichDice::Modern),
+            snapshot,
+        )
+        .await
+    }
+
+    /// The files of the package `pkg`, computed as a command would.
+    async fn files(
+        dice: &Dice,
+        cells: &CellResolver,
+        configs: &LegacyBuckConfigs,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut updater = dice.updater();
+        updater.set_cell_resolver(cells.dupe())?;
+        updater.set_legacy_configs(configs.dupe())?;
+        let ctx = updater.commit().await;
+        let listing = ctx
+            .resolve_package_listing(PackageLabel::new(
+                cells.root_cell(),
+                CellRelativePath::unchecked_new("pkg"),
+            ))
+            .await?;
+        Ok(listing
+            .files()
+            .files()
+            .map(|f| f.as_str().to_owned())
+            .collect())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_package_listings_are_restored_and_revalidated() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let root = AbsNormPathBuf::try_from(tempdir.path().to_owned())?;
+        let project_root = root.join(ForwardRelativePath::new("repo")?);
+        let pkg = project_root.join(ForwardRelativePath::new("pkg")?);
+        fs_util::create_dir_all(&pkg)?;
+        fs_util::write(pkg.join(ForwardRelativePath::new("BUCK")?), "")?;
+        fs_util::write(pkg.join(ForwardRelativePath::new("a.txt")?), "")?;
+        let snapshot_path = root.join(ForwardRelativePath::new("dice_snapshot")?);
+
+        let io: Arc<dyn IoProvider> = Arc::new(FsIoProvider::new(
+            ProjectRoot::new(project_root)?,
+            DigestConfig::testing_default().cas_digest_config(),
+        ));
+        let cells = CellResolver::testing_with_names_and_paths(&[(
+            CellName::testing_new("root"),
+            CellRootPathBuf::testing_new(""),
+        )]);
+        let configs = LegacyBuckConfigs::new(hashmap![
+            CellName::testing_new("root") => LegacyBuckConfig::empty(),
+        ]);
+
+        let dice = new_dice(io.dupe(), None).await?;
+        assert_eq!(files(&dice, &cells, &configs).await?, vec!["BUCK", "a.txt"]);
+        write_dice_snapshot(&dice, io.dupe(), &snapshot_path).await?;
+        drop(dice);
+
+        // Added while no daemon was watching the file system.
+        fs_util::write(pkg.join(ForwardRelativePath::new("b.txt")?), "")?;
+
+        let snapshot = read_dice_snapshot(&snapshot_path, &cells, &configs)?
+            .expect("the snapshot was taken with the same cells and buckconfigs");
+        assert!(!fs_util::try_exists(&snapshot_path)?);
+
+        let keys = persistent_keys(io.dupe(), &cells, &configs)?;
+        let dice = new_dice(io.dupe(), Some((&keys, &snapshot))).await?;
+        assert!(dice.metrics().key_count > 0);
+        assert_eq!(
+            files(&dice, &cells, &configs).await?,
+            vec!["BUCK", "a.txt", "b.txt"]
+        );
+
+        // A snapshot taken with other buckconfigs is removed without being restored.
+        write_dice_snapshot(&dice, io.dupe(), &snapshot_path).await?;
+        let other_configs = LegacyBuckConfigs::new(hashmap![
+            CellName::testing_new("root") => LegacyBuckConfig::empty(),
+            CellName::testing_new("other") => LegacyBuckConfig::empty(),
+        ]);
+        assert!(read_dice_snapshot(&snapshot_path, &cells, &other_configs)?.is_none());
+        assert!(!fs_util::try_exists(&snapshot_path)?);
+
+        Ok(())
+    }
+}
@@ -8,12 +8,14 @@
  */
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use allocative::Allocative;
 use buck2_data::*;
 use buck2_events::dispatch::with_dispatcher_async;
 use buck2_events::dispatch::EventDispatcher;
+use buck2_server_ctx::concurrency::CommandMaxComputeTimes;
 use dice::DiceEvent;
 use dice::DiceEventListener;
 use dupe::Dupe;
@@ -22,7 +24,7 @@ use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
 use futures::StreamExt;
 
-/// The BuckDiceTracker keeps track of the started/finished events for a dice computation and periodically sends a snapshot to the client.
+/// The BuckDiceTracker keeps track of the started/finished events and compute statistics for a dice computation and periodically sends a snapshot to the client.
 ///
 /// There are too many events coming out of dice for us to forward them all to the client, so we need to aggregate
 /// them in some way in the daemon.
@@ -34,12 +36,24 @@ use futures::StreamExt;
 pub struct BuckDiceTracker {
     #[allocative(skip)]
     event_forwarder: UnboundedSender<DiceEvent>,
+    /// Recorded as events are received rather than by the snapshot task, so that they are
+    /// complete when the command ends.
+    #[allocative(skip)]
+    max_compute_times: Arc<CommandMaxComputeTimes>,
 }
 
 const DICE_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
 
+/// Compute times of a key type, kept alongside its `DiceKeyState` which only holds the proto
+/// durations.
+#[derive(Default)]
+struct ComputeTimes {
+    total: Duration,
+    max: Duration,
+}
+
 impl BuckDiceTracker {
-    pub fn new(events: EventDispatcher) -> Self {
+    pub fn new(events: EventDispatcher, max_compute_times: Arc<CommandMaxComputeTimes>) -> Self {
         let (event_forwarder, receiver) = mpsc::unbounded();
 
         std::thread::spawn(move || {
@@ -53,12 +67,16 @@ impl BuckDiceTracker {
             ))
         });
 
-        Self { event_forwarder }
+        Self {
+            event_forwarder,
+            max_compute_times,
+        }
     }
 
     async fn run_task(events: EventDispatcher, mut receiver: UnboundedReceiver<DiceEvent>) {
         let mut needs_update = false;
         let mut states = HashMap::new();
+        let mut compute_times = HashMap::new();
         let mut interval = tokio::time::interval(DICE_SNAPSHOT_INTERVAL);
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         // This will loop until the sender side of the channel is dropped.
@@ -79,6 +97,21 @@ impl BuckDiceTracker {
                         Some(DiceEvent::CheckDepsFinished{key_type}) => {
                             states.entry(key_type).or_insert_with(DiceKeyState::default).check_deps_finished += 1;
                         }
+                        Some(DiceEvent::Computed{key_type, duration, early_cutoff}) => {
+                            let state = states.entry(key_type).or_insert_with(DiceKeyState::default);
+                            state.computed += 1;
+                            if early_cutoff {
+                                state.early_cutoffs += 1;
+                            }
+                            let times = compute_times.entry(key_type).or_insert_with(ComputeTimes::default);
+                            times.total += duration;
+                            times.max = times.max.max(duration);
+                            state.total_compute_time = times.total.try_into().ok();
+                            state.max_compute_time = times.max.try_into().ok();
+                        }
+                        Some(DiceEvent::Reused{key_type}) => {
+                            states.entry(key_type).or_insert_with(DiceKeyState::default).reused += 1;
+                        }
                         None => {
                             // This indicates that the sender side has been dropped and we can exit.
                             break;
@@ -103,6 +136,12 @@ impl BuckDiceTracker {
 
 impl DiceEventListener for BuckDiceTracker {
     fn event(&self, event: DiceEvent) {
+        if let DiceEvent::Computed {
+            key_type, duration, ..
+        } = &event
+        {
+            self.max_compute_times.record(key_type, *duration);
+        }
         let _ = self.event_forwarder.unbounded_send(event);
     }
 }
@@ -16,6 +16,9 @@ mod clean_stale;
 mod configs;
 mod ctx;
 pub mod daemon;
+mod dice_eviction;
+mod dice_persistence;
+mod dice_explain;
 mod dice_tracker;
 mod file_status;
 mod file_watcher;
@@ -12,6 +12,7 @@ use std::future::Future;
 use std::io;
 use std::io::ErrorKind;
 use std::path::Path;
+use std::sync::Arc;
 use std::thread;
 
 use buck2_cli_proto::*;
@@ -23,6 +24,8 @@ use buck2_common::result::SharedResult;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::cell_path::CellPathRef;
+use buck2_core::cells::name::CellName;
+use buck2_core::cells::paths::CellRelativePath;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
@@ -35,6 +38,8 @@ use buck2_core::target::name::TargetName;
 use buck2_events::dispatch::span_async;
 use buck2_events::dispatch::with_dispatcher;
 use buck2_events::dispatch::with_dispatcher_async;
+use buck2_interpreter::globals::CachedGlobals;
+use buck2_interpreter::load_module::INTERPRETER_CALCULATION_IMPL;
 use buck2_interpreter::path::BxlFilePath;
 use buck2_interpreter::path::OwnedStarlarkModulePath;
 use buck2_interpreter::path::StarlarkPath;
@@ -60,6 +65,8 @@ use lsp_types::Range;
 use lsp_types::Url;
 use starlark::docs::Doc;
 use starlark::docs::DocItem;
+use starlark::docs::DocMember;
+use starlark::docs::DocModule;
 use starlark::docs::Identifier;
 use starlark::docs::Location;
 use starlark::errors::EvalMessage;
@@ -69,6 +76,9 @@ use starlark::lsp::server::LspEvalResult;
 use starlark::lsp::server::LspUrl;
 use starlark::lsp::server::StringLiteralResult;
 use starlark::syntax::AstModule;
+use starlark::typing::OracleDocs;
+use starlark::typing::OracleStandard;
+use starlark::typing::TypingOracle;
 use tokio::runtime::Handle;
 use tokio::sync::Mutex;
 use tokio::sync::MutexGuard;
@@ -93,7 +103,7 @@ struct DocsCacheManager {
     fs: ProjectRoot,
     /// Used for checking if the DocsCache need refreshing. We need to refresh the DocsCache
     /// if the previous dice version does not match the current one.
-    valid_at: DiceEquality,
+    valid_at: std::sync::Mutex<DiceEquality>,
 }
 
 impl DocsCacheManager {
@@ -101,7 +111,7 @@ impl DocsCacheManager {
         Ok(Self {
             docs_cache: Mutex::new(Self::new_docs_cache(&fs, &dice_ctx).await?),
             fs,
-            valid_at: dice_ctx.equality_token(),
+            valid_at: std::sync::Mutex::new(dice_ctx.equality_token()),
         })
     }
 
@@ -116,7 +126,8 @@ impl DocsCacheManager {
             true => (),
             false => {
                 let new_docs_cache = Self::new_docs_cache(fs, &current_dice_ctx).await?;
-                *docs_cache = new_docs_cache
+                *docs_cache = new_docs_cache;
+                *self.valid_at.lock().unwrap() = current_dice_ctx.equality_token();
             }
         };
 
@@ -124,7 +135,7 @@ impl DocsCacheManager {
     }
 
     fn is_reusable(&self, dice_ctx: &DiceTransaction) -> bool {
-        dice_ctx.equivalent(&self.valid_at)
+        dice_ctx.equivalent(&self.valid_at.lock().unwrap())
     }
 
     async fn new_docs_cache<'v>(
@@ -172,6 +183,9 @@ struct DocsCache {
     global_urls: HashMap<String, LspUrl>,
     /// Mapping of starlark: urls to a synthesized starlark representation.
     native_starlark_files: HashMap<LspUrl, String>,
+    /// Documentation for each global function and property, used for hover and completion.
+    /// Shared with the LSP server, which asks for it on every hover and completion.
+    environment: Arc<DocModule>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -242,7 +256,21 @@ impl DocsCache {
     ) -> anyhow::Result<Self> {
         let mut global_urls = HashMap::with_capacity(builtin_symbols.len());
         let mut native_starlark_files = HashMap::new();
+        let mut environment = DocModule::default();
         for doc in builtin_symbols {
+            match &doc.item {
+                DocItem::Function(f) => {
+                    environment
+                        .members
+                        .insert(doc.id.name.clone(), DocMember::Function(f.clone()));
+                }
+                DocItem::Property(p) => {
+                    environment
+                        .members
+                        .insert(doc.id.name.clone(), DocMember::Property(p.clone()));
+                }
+                DocItem::Module(_) | DocItem::Object(_) => {}
+            }
             let url = match &doc.id.location {
                 Some(l) => location_lookup(l).await?,
                 None => {
@@ -274,6 +302,7 @@ impl DocsCache {
         Ok(Self {
             global_urls,
             native_starlark_files,
+            environment: Arc::new(environment),
         })
     }
 
@@ -286,6 +315,10 @@ impl DocsCache {
     fn url_for_symbol(&self, symbol: &str) -> Option<&LspUrl> {
         self.global_urls.get(symbol)
     }
+
+    fn environment(&self) -> &Arc<DocModule> {
+        &self.environment
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -363,10 +396,20 @@ pub fn output_subdir_for_doc(doc: &Doc) -> anyhow::Result<ForwardRelativePathBuf
     }
 }
 
+/// The Starlark files in each cell, as listed for workspace wide requests (e.g. find references
+/// or workspace symbols). Listing a cell walks all of its directories, so the result is kept until
+/// the DICE state changes.
+#[derive(Default)]
+struct WorkspaceFilesCache {
+    valid_at: Option<DiceEquality>,
+    cells: HashMap<CellName, Arc<Vec<LspUrl>>>,
+}
+
 struct BuckLspContext<'a> {
     server_ctx: &'a dyn ServerCommandContextTrait,
     fs: ProjectRoot,
     docs_cache_manager: DocsCacheManager,
+    workspace_files: Mutex<WorkspaceFilesCache>,
     runtime: Handle,
 }
 
@@ -375,6 +418,8 @@ enum BuckLspContextError {
     /// The scheme provided was not correct or supported.
     #[error("Url `{}` was expected to be of type `{}`", .1, .0)]
     WrongScheme(String, LspUrl),
+    #[error("Path `{0}` could not be converted to a file URL")]
+    NotAFileUrl(String),
 }
 
 impl<'a> BuckLspContext<'a> {
@@ -395,6 +440,7 @@ impl<'a> BuckLspContext<'a> {
             server_ctx,
             fs,
             docs_cache_manager,
+            workspace_files: Mutex::new(WorkspaceFilesCache::default()),
             runtime: Handle::current(),
         })
     }
@@ -490,8 +536,37 @@ impl<'a> BuckLspContext<'a> {
                 let module_path = import_path.borrow();
                 let path = module_path.starlark_path();
                 let ast = calculator.prepare_eval_with_content(path, content)?;
+
+                // Failing to compute the globals (e.g. because the prelude doesn't evaluate)
+                // shouldn't stop us reporting everything else, so only run the checks that
+                // need them if they are available.
+                let names = CachedGlobals::new(&dice_ctx).get_names(&path).await.ok();
+                let mut diagnostics: Vec<_> = ast
+                    .lint(names.as_deref())
+                    .into_iter()
+                    .map(|lint| EvalMessage::from(lint).into())
+                    .collect();
+
+                if let Ok(globals) = INTERPRETER_CALCULATION_IMPL
+                    .get()?
+                    .global_env_for_file_type(&dice_ctx, path.file_type())
+                    .await
+                {
+                    let oracle: Vec<Box<dyn TypingOracle>> = vec![
+                        Box::new(OracleStandard::new(&[])),
+                        Box::new(OracleDocs::new_object(&globals.documentation())),
+                    ];
+                    // Type checking consumes the module, so check a copy.
+                    let (errors, ..) = ast.clone().typecheck(&oracle, &HashMap::new());
+                    diagnostics.extend(
+                        errors
+                            .iter()
+                            .map(|e| EvalMessage::from_anyhow(uri.path(), e).into()),
+                    );
+                }
+
                 Ok(LspEvalResult {
-                    diagnostics: vec![],
+                    diagnostics,
                     ast: Some(ast),
                 })
             })
@@ -569,6 +644,75 @@ impl<'a> BuckLspContext<'a> {
         }
     }
 
+    /// Find all of the `.bzl`, `.bxl` and build files in `cells`.
+    async fn starlark_files(&self, cells: Option<Vec<CellName>>) -> anyhow::Result<Vec<LspUrl>> {
+        self.with_dice_ctx(async move |dice_ctx| {
+            let cell_resolver = dice_ctx.get_cell_resolver().await?;
+            let cells =
+                cells.unwrap_or_else(|| cell_resolver.cells().map(|(name, _)| name).collect());
+
+            let mut cache = self.workspace_files.lock().await;
+            let valid = match &cache.valid_at {
+                Some(valid_at) => dice_ctx.equivalent(valid_at),
+                None => false,
+            };
+            if !valid {
+                cache.valid_at = Some(dice_ctx.equality_token());
+                cache.cells.clear();
+            }
+
+            let mut res = Vec::new();
+            for cell in cells {
+                let files = match cache.cells.get(&cell) {
+                    Some(files) => files.dupe(),
+                    None => {
+                        let files = Arc::new(
+                            self.cell_starlark_files(&dice_ctx, &cell_resolver, cell)
+                                .await?,
+                        );
+                        cache.cells.insert(cell, files.dupe());
+                        files
+                    }
+                };
+                res.extend(files.iter().cloned());
+            }
+            // Nested cells would otherwise be listed by each cell that contains them.
+            Ok(res.into_iter().unique().collect())
+        })
+        .await
+    }
+
+    async fn cell_starlark_files(
+        &self,
+        dice_ctx: &DiceTransaction,
+        cell_resolver: &CellResolver,
+        cell: CellName,
+    ) -> anyhow::Result<Vec<LspUrl>> {
+        let file_ops = dice_ctx.file_ops();
+        let buildfiles = cell_resolver.get(cell)?.buildfiles();
+        let mut res = Vec::new();
+        let mut dirs = vec![CellPathRef::new(cell, CellRelativePath::empty()).to_owned()];
+        while let Some(dir) = dirs.pop() {
+            let entries = <dyn FileOps>::read_dir(&file_ops, dir.as_ref()).await?;
+            for entry in entries.included.iter() {
+                let path = dir.join(&entry.file_name);
+                let name = entry.file_name.as_str();
+                if entry.file_type.is_dir() {
+                    dirs.push(path);
+                } else if name.ends_with(".bzl")
+                    || name.ends_with(".bxl")
+                    || buildfiles.iter().any(|x| x.as_str() == name)
+                {
+                    let abs_path = self.fs.resolve(&cell_resolver.resolve_path(path.as_ref())?);
+                    let url = Url::from_file_path(&abs_path)
+                        .map_err(|()| BuckLspContextError::NotAFileUrl(abs_path.to_string()))?;
+                    res.push(url.try_into()?);
+                }
+            }
+        }
+        Ok(res)
+    }
+
     fn find_target(ast: &AstModule, target: TargetName) -> Option<Range> {
         ast.find_function_call_with_name(target.as_str())
             .map(Range::from)
@@ -623,7 +767,10 @@ impl<'a> LspContext for BuckLspContext<'a> {
                                     .await?
                                     .resolve_path(loaded_import_path.borrow().path().as_ref())?;
                                 let abs_path = self.fs.resolve(&relative_path);
-                                Ok(Url::from_file_path(abs_path).unwrap().try_into()?)
+                                let url = Url::from_file_path(&abs_path).map_err(|()| {
+                                    BuckLspContextError::NotAFileUrl(abs_path.to_string())
+                                })?;
+                                Ok(url.try_into()?)
                             })
                             .await?;
 
@@ -736,6 +883,45 @@ impl<'a> LspContext for BuckLspContext<'a> {
                 Ok(docs_cache.url_for_symbol(symbol).cloned())
             }))
     }
+
+    fn get_environment(&self, _uri: &LspUrl) -> Arc<DocModule> {
+        let dispatcher = self.server_ctx.events().dupe();
+        let result: anyhow::Result<Arc<DocModule>> =
+            self.runtime
+                .block_on(with_dispatcher_async(dispatcher, async {
+                    let docs_cache = self
+                        .with_dice_ctx(|dice_ctx| async {
+                            self.docs_cache_manager.get_cache(dice_ctx).await
+                        })
+                        .await?;
+                    Ok(docs_cache.environment().dupe())
+                }));
+        match result {
+            Ok(environment) => environment,
+            Err(e) => {
+                tracing::warn!("Error getting the global environment: {:#}", e);
+                Arc::new(DocModule::default())
+            }
+        }
+    }
+
+    fn get_workspace_files(&self, uri: Option<&LspUrl>) -> anyhow::Result<Vec<LspUrl>> {
+        let dispatcher = self.server_ctx.events().dupe();
+        self.runtime
+            .block_on(with_dispatcher_async(dispatcher, async {
+                // Other cells can load from a file too, but that's rare enough that it's not
+                // worth listing every file in the repo, so just search its own cell.
+                let cells = match uri {
+                    Some(LspUrl::File(path)) => {
+                        Some(vec![self.import_path(path).await?.borrow().cell()])
+                    }
+                    // Nothing can load a `starlark:` file, they're the builtins.
+                    Some(_) => return Ok(Vec::new()),
+                    None => None,
+                };
+                self.starlark_files(cells).await
+            }))
+    }
 }
 
 pub(crate) async fn run_lsp_server_command(
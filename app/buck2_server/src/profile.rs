@@ -35,6 +35,7 @@ use buck2_interpreter::starlark_profiler::StarlarkProfilerOrInstrumentation;
 use buck2_interpreter_for_build::interpreter::dice_calculation_delegate::HasCalculationDelegate;
 use buck2_profile::get_profile_response;
 use buck2_profile::starlark_profiler_configuration_from_request;
+use buck2_profile::write_profile_diff;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
@@ -136,8 +137,6 @@ impl ServerCommandTemplate for ProfileServerCommand {
     ) -> anyhow::Result<Self::Response> {
         let output = AbsPath::new(Path::new(&self.req.destination_path))?;
 
-        let profile_mode = starlark_profiler_configuration_from_request(&self.req)?;
-
         match self
             .req
             .profile_opts
@@ -158,6 +157,8 @@ impl ServerCommandTemplate for ProfileServerCommand {
                     .as_ref()
                     .context("Missing client context")?;
 
+                let profile_mode = starlark_profiler_configuration_from_request(&self.req)?;
+
                 let profile_data = generate_profile(
                     server_ctx,
                     ctx,
@@ -170,6 +171,7 @@ impl ServerCommandTemplate for ProfileServerCommand {
 
                 get_profile_response(profile_data, &self.req, output)
             }
+            ProfileOpts::ProfileDiff(opts) => write_profile_diff(opts, output),
             _ => {
                 return Err(anyhow::anyhow!(
                     "Expected target profile opts, not BXL profile opts"
@@ -145,26 +145,30 @@ async fn cquery(
         ShouldPrintProviders::No
     };
 
-    let result = match query_result {
-        QueryEvaluationResult::Single(targets) => {
-            output_configuration
-                .print_single_output(
-                    &mut stdout,
-                    targets,
-                    target_call_stacks,
-                    should_print_providers,
-                )
-                .await
-        }
-        QueryEvaluationResult::Multiple(results) => {
-            output_configuration
-                .print_multi_output(
-                    &mut stdout,
-                    results,
-                    target_call_stacks,
-                    should_print_providers,
-                )
-                .await
+    let result = if request.explain_paths {
+        output_configuration.print_paths_output(&mut stdout, query_result)
+    } else {
+        match query_result {
+            QueryEvaluationResult::Single(targets) => {
+                output_configuration
+                    .print_single_output(
+                        &mut stdout,
+                        targets,
+                        target_call_stacks,
+                        should_print_providers,
+                    )
+                    .await
+            }
+            QueryEvaluationResult::Multiple(results) => {
+                output_configuration
+                    .print_multi_output(
+                        &mut stdout,
+                        results,
+                        target_call_stacks,
+                        should_print_providers,
+                    )
+                    .await
+            }
         }
     };
 
@@ -11,6 +11,7 @@ use thiserror::Error;
 
 pub mod aquery;
 pub mod cquery;
+pub(crate) mod paths;
 pub mod printer;
 pub mod uquery;
 
@@ -20,4 +21,8 @@ enum QueryCommandError {
         "query result was a set of files and one or more --output-attribute was requested, but files have not attributes"
     )]
     FileSetHasNoAttributes,
+    #[error(
+        "query result was a set of files, but only dependency paths between targets can be explained"
+    )]
+    FileSetHasNoPaths,
 }
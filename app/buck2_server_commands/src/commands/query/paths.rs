@@ -0,0 +1,399 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Explains a query result as the dependency chains through it, e.g. to show why `//a:a` depends
+//! on `//b:b` with `allpaths(//a:a, //b:b)`.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use buck2_query::query::environment::DepEdge;
+use buck2_query::query::environment::LabeledNode;
+use buck2_query::query::environment::QueryTarget;
+use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use serde::ser::SerializeMap;
+use serde::Serialize;
+use serde::Serializer;
+
+/// Enumerating every path through a graph can take exponential time, so stop after this many.
+const MAX_PATHS: usize = 1000;
+
+/// A target in a dependency chain, along with why the previous target in the chain depends on it.
+struct PathStep<'a, T: QueryTarget> {
+    target: &'a T,
+    edges: Vec<DepEdge>,
+}
+
+/// The dependency chains through a target set, from the targets that nothing else in the set
+/// depends on to the targets that don't depend on anything else in the set.
+pub(crate) struct DepPaths<'a, T: QueryTarget> {
+    paths: Vec<Vec<PathStep<'a, T>>>,
+    truncated: bool,
+}
+
+impl<'a, T: QueryTarget> DepPaths<'a, T> {
+    pub(crate) fn new(targets: &'a TargetSet<T>) -> anyhow::Result<Self> {
+        let children = |target: &'a T| -> Vec<&'a T> {
+            let mut seen = HashSet::new();
+            target
+                .deps()
+                .filter(|dep| seen.insert(*dep))
+                .filter_map(|dep| targets.get(dep))
+                .collect()
+        };
+
+        let mut has_parent = HashSet::new();
+        for target in targets.iter() {
+            for child in children(target) {
+                has_parent.insert(child.node_ref());
+            }
+        }
+
+        let mut paths = DepPaths {
+            paths: Vec::new(),
+            truncated: false,
+        };
+        let mut stack = Vec::new();
+        for root in targets.iter() {
+            if has_parent.contains(root.node_ref()) {
+                continue;
+            }
+            stack.push(PathStep {
+                target: root,
+                edges: Vec::new(),
+            });
+            paths.visit(&mut stack, &children)?;
+            stack.pop();
+            if paths.truncated {
+                break;
+            }
+        }
+        Ok(paths)
+    }
+
+    fn visit(
+        &mut self,
+        stack: &mut Vec<PathStep<'a, T>>,
+        children: &dyn Fn(&'a T) -> Vec<&'a T>,
+    ) -> anyhow::Result<()> {
+        let target = stack.last().expect("stack is never empty").target;
+        // Dependency cycles are reported elsewhere, just don't follow them: the path ends where it
+        // would loop.
+        let deps: Vec<_> = children(target)
+            .into_iter()
+            .filter(|dep| {
+                !stack
+                    .iter()
+                    .any(|step| step.target.node_ref() == dep.node_ref())
+            })
+            .collect();
+        if deps.is_empty() {
+            if self.paths.len() == MAX_PATHS {
+                self.truncated = true;
+                return Ok(());
+            }
+            self.paths.push(
+                stack
+                    .iter()
+                    .map(|step| PathStep {
+                        target: step.target,
+                        edges: step.edges.clone(),
+                    })
+                    .collect(),
+            );
+            return Ok(());
+        }
+        for dep in deps {
+            stack.push(PathStep {
+                target: dep,
+                edges: target.dep_edges(dep.node_ref())?,
+            });
+            self.visit(stack, children)?;
+            stack.pop();
+            if self.truncated {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: QueryTarget> Display for DepPaths<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, path) in self.paths.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            for (j, step) in path.iter().enumerate() {
+                if j == 0 {
+                    writeln!(f, "{}", step.target.node_ref())?;
+                } else {
+                    writeln!(
+                        f,
+                        "  -> {} [{}]",
+                        step.target.node_ref(),
+                        explain_edges(&step.edges)
+                    )?;
+                }
+            }
+        }
+        if self.truncated {
+            writeln!(
+                f,
+                "\nOnly the first {} paths are shown, use `--dot` to see the whole graph.",
+                MAX_PATHS
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes a dependency edge, e.g. `deps, exec_deps (exec_dep)`.
+pub(crate) fn explain_edges(edges: &[DepEdge]) -> String {
+    if edges.is_empty() {
+        "<no attribute>".to_owned()
+    } else {
+        edges
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl<'a, T: QueryTarget> Serialize for PathStep<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("target", &self.target.node_ref().to_string())?;
+        map.serialize_entry("edges", &self.edges)?;
+        map.end()
+    }
+}
+
+impl<'a, T: QueryTarget> Serialize for DepPaths<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("paths", &self.paths)?;
+        map.serialize_entry("truncated", &self.truncated)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    use buck2_core::build_file_path::BuildFilePath;
+    use buck2_core::cells::cell_path::CellPath;
+    use buck2_query::query::environment::DepKind;
+    use dupe::Dupe;
+    use serde::Serializer;
+
+    use super::*;
+
+    /// A target, and its deps with the attribute each one comes from.
+    #[derive(Debug, Clone, Dupe)]
+    struct Node(&'static str, Arc<Vec<(&'static str, DepEdge)>>);
+
+    impl LabeledNode for Node {
+        type NodeRef = &'static str;
+
+        fn node_ref(&self) -> &Self::NodeRef {
+            &self.0
+        }
+    }
+
+    impl QueryTarget for Node {
+        type Attr<'a> = str;
+
+        fn inputs_for_each<E, F: FnMut(CellPath) -> Result<(), E>>(
+            &self,
+            _func: F,
+        ) -> Result<(), E> {
+            unimplemented!()
+        }
+
+        fn rule_type(&self) -> Cow<str> {
+            unimplemented!()
+        }
+
+        fn buildfile_path(&self) -> &BuildFilePath {
+            unimplemented!()
+        }
+
+        fn deps<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::NodeRef> + Send + 'a> {
+            Box::new(self.1.iter().map(|(dep, _)| dep))
+        }
+
+        fn exec_deps<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::NodeRef> + Send + 'a> {
+            unimplemented!()
+        }
+
+        fn target_deps<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::NodeRef> + Send + 'a> {
+            unimplemented!()
+        }
+
+        fn dep_edges(&self, dep: &Self::NodeRef) -> anyhow::Result<Vec<DepEdge>> {
+            Ok(self
+                .1
+                .iter()
+                .filter(|(d, _)| d == dep)
+                .map(|(_, edge)| edge.clone())
+                .collect())
+        }
+
+        fn attr_to_string_alternate(&self, _attr: &Self::Attr<'_>) -> String {
+            unimplemented!("not needed for tests")
+        }
+
+        fn attr_serialize<S: Serializer>(
+            &self,
+            _attr: &Self::Attr<'_>,
+            _serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            unimplemented!("not needed for tests")
+        }
+
+        fn attr_any_matches(
+            _attr: &Self::Attr<'_>,
+            _filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+        ) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        fn special_attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
+            &self,
+            _func: F,
+        ) -> Result<(), E> {
+            unimplemented!()
+        }
+
+        fn attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
+            &self,
+            _func: F,
+        ) -> Result<(), E> {
+            unimplemented!()
+        }
+
+        fn map_attr<R, F: FnMut(Option<&Self::Attr<'_>>) -> R>(&self, _key: &str, _func: F) -> R {
+            unimplemented!()
+        }
+
+        fn call_stack(&self) -> Option<String> {
+            None
+        }
+    }
+
+    fn edge(attr: &str, kind: DepKind, transition: Option<&str>) -> DepEdge {
+        DepEdge {
+            attr: attr.to_owned(),
+            kind,
+            transition: transition.map(|t| t.to_owned()),
+        }
+    }
+
+    fn node(label: &'static str, deps: Vec<(&'static str, DepEdge)>) -> Node {
+        Node(label, Arc::new(deps))
+    }
+
+    /// `a` depends on `d` through both `b` and `c`, and `e` is outside of the target set.
+    fn targets() -> TargetSet<Node> {
+        [
+            node(
+                "a",
+                vec![
+                    ("b", edge("deps", DepKind::Dep, None)),
+                    ("c", edge("compiler", DepKind::ExecDep, None)),
+                    ("c", edge("tools", DepKind::Dep, Some("cfg"))),
+                ],
+            ),
+            node(
+                "b",
+                vec![
+                    ("d", edge("deps", DepKind::Dep, None)),
+                    ("e", edge("deps", DepKind::Dep, None)),
+                ],
+            ),
+            node("c", vec![("d", edge("srcs", DepKind::Dep, None))]),
+            node("d", vec![]),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_dep_paths_text() -> anyhow::Result<()> {
+        let targets = targets();
+        assert_eq!(
+            DepPaths::new(&targets)?.to_string(),
+            "a\n\
+             \x20 -> b [deps]\n\
+             \x20 -> d [deps]\n\
+             \n\
+             a\n\
+             \x20 -> c [compiler (exec_dep), tools (transition `cfg`)]\n\
+             \x20 -> d [srcs]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dep_paths_json() -> anyhow::Result<()> {
+        let targets = targets();
+        assert_eq!(
+            serde_json::to_value(DepPaths::new(&targets)?)?,
+            serde_json::json!({
+                "paths": [
+                    [
+                        {"target": "a", "edges": []},
+                        {"target": "b", "edges": [{"attr": "deps", "kind": "dep", "transition": null}]},
+                        {"target": "d", "edges": [{"attr": "deps", "kind": "dep", "transition": null}]},
+                    ],
+                    [
+                        {"target": "a", "edges": []},
+                        {
+                            "target": "c",
+                            "edges": [
+                                {"attr": "compiler", "kind": "exec_dep", "transition": null},
+                                {"attr": "tools", "kind": "dep", "transition": "cfg"},
+                            ],
+                        },
+                        {"target": "d", "edges": [{"attr": "srcs", "kind": "dep", "transition": null}]},
+                    ],
+                ],
+                "truncated": false,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dep_paths_cycle() -> anyhow::Result<()> {
+        let targets: TargetSet<Node> = [
+            node("a", vec![("b", edge("deps", DepKind::Dep, None))]),
+            node("b", vec![("c", edge("deps", DepKind::Dep, None))]),
+            node("c", vec![("b", edge("deps", DepKind::Dep, None))]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            DepPaths::new(&targets)?.to_string(),
+            "a\n  -> b [deps]\n  -> c [deps]\n"
+        );
+        Ok(())
+    }
+}
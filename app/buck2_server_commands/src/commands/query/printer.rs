@@ -25,6 +25,7 @@ use buck2_query::query::environment::QueryTargets;
 use buck2_query::query::syntax::simple::eval::file_set::FileSet;
 use buck2_query::query::syntax::simple::eval::multi_query::MultiQueryResult;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
 use buck2_util::indent::indent;
 use dupe::Clone_;
@@ -38,6 +39,7 @@ use serde::ser::SerializeSeq;
 use serde::Serialize;
 use serde::Serializer;
 
+use crate::commands::query::paths::DepPaths;
 use crate::commands::query::QueryCommandError;
 use crate::dot::targets::DotTargetGraph;
 use crate::dot::Dot;
@@ -319,6 +321,59 @@ impl<'a> QueryResultPrinter<'a> {
         }
     }
 
+    /// Print the dependency chains through the result along with the attribute each dependency
+    /// comes from, rather than the result itself. Most useful with `allpaths` or `somepath`.
+    pub fn print_paths_output<T: QueryTarget, W: std::io::Write>(
+        &self,
+        mut output: W,
+        result: QueryEvaluationResult<T>,
+    ) -> anyhow::Result<()> {
+        let targets = match result {
+            QueryEvaluationResult::Single(result) => result,
+            QueryEvaluationResult::Multiple(results) => results.merged()?,
+        };
+        let targets = match targets {
+            QueryEvaluationValue::TargetSet(targets) => targets,
+            QueryEvaluationValue::FileSet(..) => {
+                return Err(QueryCommandError::FileSetHasNoPaths.into());
+            }
+        };
+
+        match self.output_format {
+            QueryOutputFormat::Default => {
+                write!(&mut output, "{}", DepPaths::new(&targets)?)?;
+            }
+            QueryOutputFormat::Json => {
+                let mut ser = serde_json::Serializer::pretty(&mut output);
+                DepPaths::new(&targets)?.serialize(&mut ser)?;
+                std::mem::drop(ser);
+                // need to add a newline to flush the output.
+                writeln!(&mut output)?;
+            }
+            QueryOutputFormat::Dot => {
+                Dot::render(
+                    &DotTargetGraph {
+                        targets,
+                        attributes: self.attributes.clone(),
+                        explain_edges: true,
+                    },
+                    &mut output,
+                )?;
+            }
+            QueryOutputFormat::DotCompact => {
+                DotCompact::render(
+                    &DotTargetGraph {
+                        targets,
+                        attributes: self.attributes.clone(),
+                        explain_edges: true,
+                    },
+                    &mut output,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn print_single_output<'b, T: QueryTarget, W: std::io::Write>(
         &self,
         mut output: W,
@@ -355,6 +410,7 @@ impl<'a> QueryResultPrinter<'a> {
                         &DotTargetGraph {
                             targets,
                             attributes: self.attributes.clone(),
+                            explain_edges: false,
                         },
                         &mut output,
                     )?;
@@ -364,6 +420,7 @@ impl<'a> QueryResultPrinter<'a> {
                         &DotTargetGraph {
                             targets,
                             attributes: self.attributes.clone(),
+                            explain_edges: false,
                         },
                         &mut output,
                     )?;
@@ -105,26 +105,30 @@ async fn uquery(
         )
         .await?;
 
-    let result = match query_result {
-        QueryEvaluationResult::Single(targets) => {
-            output_configuration
-                .print_single_output(
-                    &mut stdout,
-                    targets,
-                    target_call_stacks,
-                    ShouldPrintProviders::No,
-                )
-                .await
-        }
-        QueryEvaluationResult::Multiple(results) => {
-            output_configuration
-                .print_multi_output(
-                    &mut stdout,
-                    results,
-                    target_call_stacks,
-                    ShouldPrintProviders::No,
-                )
-                .await
+    let result = if request.explain_paths {
+        output_configuration.print_paths_output(&mut stdout, query_result)
+    } else {
+        match query_result {
+            QueryEvaluationResult::Single(targets) => {
+                output_configuration
+                    .print_single_output(
+                        &mut stdout,
+                        targets,
+                        target_call_stacks,
+                        ShouldPrintProviders::No,
+                    )
+                    .await
+            }
+            QueryEvaluationResult::Multiple(results) => {
+                output_configuration
+                    .print_multi_output(
+                        &mut stdout,
+                        results,
+                        target_call_stacks,
+                        ShouldPrintProviders::No,
+                    )
+                    .await
+            }
         }
     };
 
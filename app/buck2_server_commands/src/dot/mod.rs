@@ -70,6 +70,16 @@ pub trait DotNode {
 pub struct DotEdge<'a> {
     from: &'a str,
     to: &'a str,
+    label: Option<&'a str>,
+}
+
+impl<'a> DotEdge<'a> {
+    fn attrs(&self) -> String {
+        match self.label {
+            Some(label) => format!(" [label={}]", escape_id(label)),
+            None => String::new(),
+        }
+    }
 }
 
 pub trait DotDigraph<'a> {
@@ -120,7 +130,13 @@ impl Dot {
             let attrs = node.attrs()?;
             writeln!(w, "  {} [{}];", escape_id(&node.id()), attrs)?;
             graph.for_each_edge(node, |edge| {
-                writeln!(w, "  {} -> {};", escape_id(edge.from), escape_id(edge.to))?;
+                writeln!(
+                    w,
+                    "  {} -> {}{};",
+                    escape_id(edge.from),
+                    escape_id(edge.to),
+                    edge.attrs()
+                )?;
                 Ok(())
             })?;
             Ok(())
@@ -163,9 +179,10 @@ impl DotCompact {
             graph.for_each_edge(node, |edge| {
                 writeln!(
                     w,
-                    "  {} -> {};",
+                    "  {} -> {}{};",
                     name_to_number(&escape_id(edge.from)),
-                    name_to_number(&escape_id(edge.to))
+                    name_to_number(&escape_id(edge.to)),
+                    edge.attrs()
                 )?;
                 Ok(())
             })?;
@@ -13,6 +13,7 @@ use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use regex::RegexSet;
 use starlark_map::small_map::SmallMap;
 
+use crate::commands::query::paths::explain_edges;
 use crate::dot::DotDigraph;
 use crate::dot::DotEdge;
 use crate::dot::DotNode;
@@ -24,6 +25,8 @@ pub struct DotTargetGraphNode<'a, T: QueryTarget>(&'a T, &'a DotTargetGraph<T>);
 pub struct DotTargetGraph<T: QueryTarget> {
     pub targets: TargetSet<T>,
     pub attributes: Option<RegexSet>,
+    /// Label each edge with the attributes it comes from.
+    pub explain_edges: bool,
 }
 
 impl<'a, T: QueryTarget> DotDigraph<'a> for DotTargetGraph<T> {
@@ -51,9 +54,15 @@ impl<'a, T: QueryTarget> DotDigraph<'a> for DotTargetGraph<T> {
         for dep in node.0.deps() {
             // Only include edges to other nodes within the subgraph.
             if self.targets.contains(dep) {
+                let label = if self.explain_edges {
+                    Some(explain_edges(&node.0.dep_edges(dep)?))
+                } else {
+                    None
+                };
                 f(&DotEdge {
                     from: &node.0.node_ref().to_string(),
                     to: &dep.to_string(),
+                    label: label.as_deref(),
                 })?;
             }
         }
@@ -13,11 +13,14 @@
 //! If there are no buckconfig changes, nor file changes, then commands can be allowed to execute
 //! concurrently. Otherwise, `buck2` will block waiting for other commands to finish.
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use allocative::Allocative;
 use anyhow::Context;
@@ -26,8 +29,12 @@ use async_trait::async_trait;
 use buck2_core::soft_error;
 use buck2_data::DiceBlockConcurrentCommandEnd;
 use buck2_data::DiceBlockConcurrentCommandStart;
+use buck2_data::DiceChangedKey;
 use buck2_data::DiceConcurrentCommands;
 use buck2_data::DiceEqualityCheck;
+use buck2_data::DiceInvalidationSummary;
+use buck2_data::DiceKeyTypeMetrics;
+use buck2_data::DiceKeyTypeStats;
 use buck2_data::DiceSynchronizeSectionEnd;
 use buck2_data::DiceSynchronizeSectionStart;
 use buck2_data::ExclusiveCommandWaitEnd;
@@ -38,11 +45,13 @@ use buck2_events::dispatch::EventDispatcher;
 use buck2_util::truncate::truncate;
 use buck2_wrapper_common::invocation_id::TraceId;
 use derive_more::Display;
+use dice::introspection::invalidation::InvalidationSummary;
 use dice::Dice;
 use dice::DiceComputations;
 use dice::DiceEquality;
 use dice::DiceTransaction;
 use dice::DiceTransactionUpdater;
+use dice::KeyTypeMetrics;
 use dice::UserComputationData;
 use dupe::Dupe;
 use futures::future::BoxFuture;
@@ -53,6 +62,7 @@ use itertools::Itertools;
 use more_futures::cancellation::CancellationContext;
 use parking_lot::lock_api::MutexGuard;
 use parking_lot::FairMutex;
+use parking_lot::Mutex;
 use parking_lot::RawFairMutex;
 use starlark_map::small_map::SmallMap;
 use starlark_map::small_set::SmallSet;
@@ -465,7 +475,25 @@ impl ConcurrencyHandler {
             })
             .await?;
 
-        Ok(exec(transaction).await)
+        // DICE only keeps daemon-wide metrics, so the computes of concurrent commands are counted
+        // here too. Only the max compute times are recorded for this command alone.
+        let key_types_before = self.dice.metrics().key_types;
+        let max_compute_times = transaction
+            .per_transaction_data()
+            .get_command_max_compute_times()
+            .cloned();
+        let res = exec(transaction).await;
+
+        let key_type_metrics = key_type_metrics_event(
+            &key_types_before,
+            self.dice.metrics().key_types,
+            max_compute_times.as_ref().map(|times| times.get()),
+        );
+        if !key_type_metrics.key_types.is_empty() {
+            event_dispatcher.instant_event(key_type_metrics);
+        }
+
+        Ok(res)
     }
 
     #[allow(clippy::await_holding_lock)]
@@ -526,6 +554,12 @@ impl ConcurrencyHandler {
                     // this might cause some churn, but concurrent commands don't happen much and
                     // isn't a big perf bottleneck. Dice should be able to resurrect nodes properly.
 
+                    let previous_invalidation = self
+                        .dice
+                        .latest_invalidation_summary()
+                        .await
+                        .map(|summary| summary.version);
+
                     let transaction = async {
                         let updater = self.dice.updater();
                         let user_data = user_data
@@ -550,6 +584,12 @@ impl ConcurrencyHandler {
                     }
                     .await?;
 
+                    if let Some(summary) = self.dice.latest_invalidation_summary().await {
+                        if previous_invalidation != Some(summary.version) {
+                            event_dispatcher.instant_event(invalidation_summary_event(summary));
+                        }
+                    }
+
                     if let Some(active) = active {
                         let is_same_state = transaction.equivalent(&active.version);
 
@@ -719,6 +759,91 @@ fn format_traces(
     trace_ids.iter().join(", ")
 }
 
+fn invalidation_summary_event(summary: InvalidationSummary) -> DiceInvalidationSummary {
+    DiceInvalidationSummary {
+        version: summary.version as u64,
+        changed_keys: summary.changed_keys as u64,
+        dirtied_keys: summary.dirtied_keys as u64,
+        top_changed_keys: summary
+            .top_changed_keys
+            .into_iter()
+            .map(|key| DiceChangedKey {
+                key: key.key,
+                key_type: key.key_type,
+                dirtied_keys: key.dirtied_keys as u64,
+            })
+            .collect(),
+    }
+}
+
+/// The per key type metrics accumulated between `before` and `after`, most compute time first,
+/// with the max compute times of the command if they were recorded.
+fn key_type_metrics_event(
+    before: &BTreeMap<&'static str, KeyTypeMetrics>,
+    after: BTreeMap<&'static str, KeyTypeMetrics>,
+    max_compute_times: Option<HashMap<&'static str, Duration>>,
+) -> DiceKeyTypeMetrics {
+    let mut key_types: Vec<_> = after
+        .into_iter()
+        .map(|(key_type, metrics)| match before.get(key_type) {
+            Some(before) => (key_type, metrics.since(before)),
+            None => (key_type, metrics),
+        })
+        .filter(|(_, metrics)| metrics.computes != 0 || metrics.cache_hits != 0)
+        .collect();
+    key_types.sort_by(|(_, a), (_, b)| b.total_compute_time.cmp(&a.total_compute_time));
+
+    DiceKeyTypeMetrics {
+        key_types: key_types
+            .into_iter()
+            .map(|(key_type, metrics)| DiceKeyTypeStats {
+                key_type: key_type.to_owned(),
+                computes: metrics.computes,
+                cache_hits: metrics.cache_hits,
+                early_cutoffs: metrics.early_cutoffs,
+                total_compute_time: metrics.total_compute_time.try_into().ok(),
+                max_compute_time: max_compute_times
+                    .as_ref()
+                    .and_then(|times| times.get(key_type))
+                    .and_then(|max| (*max).try_into().ok()),
+            })
+            .collect(),
+    }
+}
+
+/// The longest compute of each key type in a command, recorded by the DICE event listener of the
+/// command since DICE itself only keeps daemon-wide metrics.
+#[derive(Default)]
+pub struct CommandMaxComputeTimes(Mutex<HashMap<&'static str, Duration>>);
+
+impl CommandMaxComputeTimes {
+    pub fn record(&self, key_type: &'static str, duration: Duration) {
+        let mut times = self.0.lock();
+        let max = times.entry(key_type).or_default();
+        *max = (*max).max(duration);
+    }
+
+    fn get(&self) -> HashMap<&'static str, Duration> {
+        self.0.lock().clone()
+    }
+}
+
+pub trait HasCommandMaxComputeTimes {
+    fn set_command_max_compute_times(&mut self, times: Arc<CommandMaxComputeTimes>);
+
+    fn get_command_max_compute_times(&self) -> Option<&Arc<CommandMaxComputeTimes>>;
+}
+
+impl HasCommandMaxComputeTimes for UserComputationData {
+    fn set_command_max_compute_times(&mut self, times: Arc<CommandMaxComputeTimes>) {
+        self.data.set(times);
+    }
+
+    fn get_command_max_compute_times(&self) -> Option<&Arc<CommandMaxComputeTimes>> {
+        self.data.get::<Arc<CommandMaxComputeTimes>>().ok()
+    }
+}
+
 /// Held to execute a command so that when the command is canceled, we properly remove its state
 /// from the handler so that it's no longer registered as a ongoing command.
 struct OnExecExit(ConcurrencyHandler, CommandId);
@@ -86,6 +86,9 @@ pub(crate) trait DebugServer {
     /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Evaluate>
     fn evaluate(&mut self, x: dap::EvaluateArguments) -> anyhow::Result<dap::EvaluateResponseBody>;
 
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_SetVariable>
+    fn set_variable(&mut self, x: SetVariableArguments) -> anyhow::Result<SetVariableResponseBody>;
+
     /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Disconnect>
     fn disconnect(&mut self, x: dap::DisconnectArguments) -> anyhow::Result<()>;
 
@@ -102,6 +105,24 @@ pub(crate) struct ContinueArguments {
     pub(crate) single_thread: Option<bool>,
 }
 
+/// DAP SetVariableArguments (we don't support the `format` field)
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SetVariableArguments {
+    pub(crate) variables_reference: i64,
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+/// DAP SetVariableResponseBody (we only ever set variables with no children)
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SetVariableResponseBody {
+    pub(crate) value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) type_: Option<String>,
+}
+
 /// Create a dap Event with the given body. The user is responsible for updating the `seq` field.
 pub(crate) fn dap_event<T: Serialize>(event: &str, body: Option<&T>) -> dap::Event {
     dap::Event {
@@ -174,6 +195,7 @@ pub(crate) fn dispatch(
         "variables" => ret_some(r, server.variables(arg(r)?)),
         "continue" => ret_some(r, server.continue_(arg(r)?)),
         "evaluate" => ret_some(r, server.evaluate(arg(r)?)),
+        "setVariable" => ret_some(r, server.set_variable(arg(r)?)),
         "disconnect" => ret_none(r, server.disconnect(arg(r)?)),
         "source" => ret_some(r, server.source(arg(r)?)),
         "next" => ret_none(r, server.next(arg(r)?)),
@@ -25,6 +25,7 @@ use dupe::Dupe;
 use futures::StreamExt;
 use gazebo::prelude::*;
 use itertools::Itertools;
+use starlark::debug::exception_breakpoint_filters;
 use starlark::debug::prepare_dap_adapter;
 use starlark::debug::resolve_breakpoints;
 use starlark::debug::DapAdapter;
@@ -50,6 +51,8 @@ use crate::dap_api::dispatch;
 use crate::dap_api::err_response;
 use crate::dap_api::ContinueArguments;
 use crate::dap_api::DebugServer;
+use crate::dap_api::SetVariableArguments;
+use crate::dap_api::SetVariableResponseBody;
 use crate::error::StarlarkDebuggerError;
 use crate::run::ToClientMessage;
 use crate::BuckStarlarkDebuggerHandle;
@@ -69,6 +72,9 @@ fn capabilities() -> serde_json::Value {
         "supports_set_variable": true,
         "supports_step_in_targets_request": true,
         "supports_conditional_breakpoints": true,
+        "supports_hit_conditional_breakpoints": true,
+        "supports_log_points": true,
+        "exception_breakpoint_filters": exception_breakpoint_filters(),
 
         // This is different from starlark's `dap_capabilities`. The buck starlark debugger treats
         // each ongoing starlark Evaluation as a separate thread and handles requests appropriately.
@@ -204,7 +210,25 @@ impl BuckStarlarkDebuggerServer {
 
     /// Called when a starlark evaluation is paused (e.g. at a breakpoint).
     pub(crate) fn event_stopped(&self, hook_id: HookId) {
-        self.maybe_to_state(ServerMessage::EvalStopped { hook_id });
+        self.maybe_to_state(ServerMessage::EvalStopped {
+            hook_id,
+            exception: None,
+        });
+    }
+
+    /// Called when a starlark evaluation is paused at an error, because of the exception breakpoints.
+    pub(crate) fn event_stopped_on_exception(&self, hook_id: HookId, description: &str) {
+        self.maybe_to_state(ServerMessage::EvalStopped {
+            hook_id,
+            exception: Some(description.to_owned()),
+        });
+    }
+
+    /// Called when a starlark evaluation reaches a logpoint.
+    pub(crate) fn event_output(&self, output: &str) {
+        self.maybe_to_state(ServerMessage::Output {
+            output: output.to_owned(),
+        });
     }
 
     /// Called to forward along requests from the DAP client.
@@ -245,6 +269,11 @@ enum ServerMessage {
     },
     EvalStopped {
         hook_id: HookId,
+        /// Set when stopped at an error rather than a breakpoint or step.
+        exception: Option<String>,
+    },
+    Output {
+        output: String,
     },
     Detach,
 }
@@ -263,6 +292,9 @@ struct ServerState {
     /// The currently set breakpoints. New hooks will be initialized with these.
     set_breakpoints: HashMap<String, ResolvedBreakpoints>,
 
+    /// The currently set exception breakpoint filters. New hooks will be initialized with these.
+    exception_filters: Vec<String>,
+
     /// The project root is used to get the current source code to resolve breakpoints.
     project_root: ProjectRoot,
 
@@ -320,11 +352,13 @@ impl DebugServer for ServerState {
 
     fn set_exception_breakpoints(
         &mut self,
-        _x: dap::SetExceptionBreakpointsArguments,
+        x: dap::SetExceptionBreakpointsArguments,
     ) -> anyhow::Result<()> {
-        // TODO(cjhopman): This may not make sense in starlark and could be a more informative error. Or possibly we
-        // could use it to break on `fail()`.
-        Err(StarlarkDebuggerError::Unimplemented.into())
+        for hook_state in self.current_hooks.values() {
+            hook_state.adapter.set_exception_breakpoints(&x.filters)?;
+        }
+        self.exception_filters = x.filters;
+        Ok(())
     }
 
     fn attach(&mut self, _x: dap::AttachRequestArguments) -> anyhow::Result<()> {
@@ -467,6 +501,22 @@ impl DebugServer for ServerState {
         hook.adapter.evaluate(&x.expression)
     }
 
+    fn set_variable(&mut self, x: SetVariableArguments) -> anyhow::Result<SetVariableResponseBody> {
+        let thread_id = x.variables_reference >> 16;
+        let variables_id = x.variables_reference & 0xFFFF;
+        // We only send back locals, see `variables`.
+        if variables_id != TOP_FRAME_LOCALS_ID {
+            return Err(StarlarkDebuggerError::Unimplemented.into());
+        }
+
+        let hook = self.find_hook_by_pseudo_thread(thread_id)?;
+        let var = hook.adapter.set_variable(&x.name, &x.value)?;
+        Ok(SetVariableResponseBody {
+            value: var.value,
+            type_: Some(var.type_),
+        })
+    }
+
     fn disconnect(&mut self, _x: dap::DisconnectArguments) -> anyhow::Result<()> {
         Ok(())
     }
@@ -483,6 +533,7 @@ impl ServerState {
             next_pseudo_thread: 0,
             next_hook_id: HookId(0),
             set_breakpoints: HashMap::new(),
+            exception_filters: Vec::new(),
         }
     }
 
@@ -583,7 +634,10 @@ impl ServerState {
                 };
                 self.to_client.send(ToClientMessage::Response(response))?;
             }
-            ServerMessage::EvalStopped { hook_id } => self.eval_stopped(hook_id)?,
+            ServerMessage::EvalStopped { hook_id, exception } => {
+                self.eval_stopped(hook_id, exception)?
+            }
+            ServerMessage::Output { output } => self.output(output)?,
             ServerMessage::Detach => {
                 self.detach();
                 return Ok(false);
@@ -630,6 +684,9 @@ impl ServerState {
         for (source, breakpoints) in &self.set_breakpoints {
             hook_state.adapter.set_breakpoints(source, breakpoints)?;
         }
+        hook_state
+            .adapter
+            .set_exception_breakpoints(&self.exception_filters)?;
         self.current_hooks.insert(hook_id, hook_state);
 
         self.to_client.send(ToClientMessage::Event(dap_event(
@@ -665,7 +722,7 @@ impl ServerState {
         self.current_commands.remove(&handle_id);
     }
 
-    fn eval_stopped(&mut self, hook_id: HookId) -> anyhow::Result<()> {
+    fn eval_stopped(&mut self, hook_id: HookId, exception: Option<String>) -> anyhow::Result<()> {
         debug!("eval stopped {}", hook_id);
         let mut state = self.current_hooks.get_mut(&hook_id).unwrap();
         let top_frame = state.adapter.top_frame();
@@ -676,13 +733,23 @@ impl ServerState {
         state.stopped_at = Some(description);
         let thread_id = state.pseudo_thread_id;
 
-        let msg = dap::StoppedEventBody {
-            reason: "breakpoint".to_owned(),
-            thread_id: Some(thread_id as i64),
-            description: Some("Hello".to_owned()),
-            all_threads_stopped: Some(false),
-            preserve_focus_hint: None,
-            text: None,
+        let msg = match exception {
+            Some(exception) => dap::StoppedEventBody {
+                reason: "exception".to_owned(),
+                thread_id: Some(thread_id as i64),
+                description: Some("Paused on error".to_owned()),
+                all_threads_stopped: Some(false),
+                preserve_focus_hint: None,
+                text: Some(exception),
+            },
+            None => dap::StoppedEventBody {
+                reason: "breakpoint".to_owned(),
+                thread_id: Some(thread_id as i64),
+                description: Some("Hello".to_owned()),
+                all_threads_stopped: Some(false),
+                preserve_focus_hint: None,
+                text: None,
+            },
         };
 
         self.to_client
@@ -690,6 +757,22 @@ impl ServerState {
         Ok(())
     }
 
+    /// Sends the message of a logpoint to the DAP client's console.
+    fn output(&mut self, output: String) -> anyhow::Result<()> {
+        let body = dap::OutputEventBody {
+            output: format!("{}\n", output),
+            category: Some("console".to_owned()),
+            column: None,
+            data: None,
+            line: None,
+            source: None,
+            variables_reference: None,
+        };
+        self.to_client
+            .send(ToClientMessage::Event(dap_event("output", Some(&body))))?;
+        Ok(())
+    }
+
     fn detach(&mut self) {
         // Dropping the DapAdapter should make any hooked Evaluator continue freely.
         self.current_hooks.clear();
@@ -739,6 +822,17 @@ impl DapAdapterClient for BuckStarlarkDapAdapterClient {
     fn event_stopped(&self) {
         self.handle.0.server.event_stopped(self.hook_id)
     }
+
+    fn event_stopped_on_exception(&self, description: &str) {
+        self.handle
+            .0
+            .server
+            .event_stopped_on_exception(self.hook_id, description)
+    }
+
+    fn event_output(&self, output: &str) {
+        self.handle.0.server.event_output(output)
+    }
 }
 
 /// Information about ongoing commands held by the debugger server.
@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::ClientContext;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::data::HasIoProvider;
+use buck2_common::dice::file_ops::HasFileOps;
+use buck2_common::io::IoProvider;
+use buck2_core::cells::CellResolver;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_interpreter::path::StarlarkPath;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use starlark::syntax::AstModule;
+
+use crate::util::paths::starlark_files;
+use crate::StarlarkCommandCommonOptions;
+use crate::StarlarkOpaqueSubcommand;
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "starlark-format",
+    about = "Format Starlark files in place, keeping their comments."
+)]
+pub struct StarlarkFormatCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    /// Don't change any files, just list those that are not formatted, and fail if there are any.
+    #[clap(long)]
+    check: bool,
+
+    #[clap(value_name = "PATH", required = true)]
+    paths: Vec<PathArg>,
+}
+
+/// Returns the path of the file, along with its original and formatted contents.
+async fn format_file(
+    path: &StarlarkPath<'_>,
+    cell_resolver: &CellResolver,
+    io: &dyn IoProvider,
+) -> anyhow::Result<(ProjectRelativePathBuf, String, String)> {
+    let dialect = path.file_type().dialect(false);
+    let proj_path = cell_resolver.resolve_path(path.path().as_ref().as_ref())?;
+    let path_str = proj_path.to_string();
+    let content = io
+        .read_file_if_exists(proj_path.clone())
+        .await?
+        .with_context(|| format!("File not found: `{}`", path_str))?;
+    let formatted = AstModule::parse(&path_str, content.clone(), &dialect)?.format();
+    Ok((proj_path, content, formatted))
+}
+
+#[async_trait]
+impl StarlarkOpaqueSubcommand for StarlarkFormatCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(async move |server_ctx, ctx| {
+                let cell_resolver = ctx.get_cell_resolver().await?;
+                let fs = ctx.file_ops();
+                let io = ctx.global_data().get_io_provider();
+
+                let mut stdout = stdout.as_writer();
+                let mut changed = 0;
+                let files =
+                    starlark_files(&self.paths, server_ctx, &cell_resolver, &fs, &*io).await?;
+                for file in &files {
+                    let (proj_path, content, formatted) =
+                        format_file(&file.borrow(), &cell_resolver, &*io).await?;
+                    if content == formatted {
+                        continue;
+                    }
+                    changed += 1;
+                    if self.check {
+                        writeln!(stdout, "{}", proj_path)?;
+                    } else {
+                        fs_util::write(server_ctx.project_root().resolve(&proj_path), formatted)?;
+                        writeln!(stdout, "Formatted {}", proj_path)?;
+                    }
+                }
+                if self.check && changed > 0 {
+                    Err(anyhow::anyhow!("Found {} unformatted files", changed))
+                } else {
+                    writeln!(
+                        server_ctx.stderr()?,
+                        "Formatted {} of {} files",
+                        changed,
+                        files.len()
+                    )?;
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    fn common_opts(&self) -> &StarlarkCommandCommonOptions {
+        &self.common_opts
+    }
+}
@@ -28,11 +28,15 @@ use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 
 use crate::debug::StarlarkDebugAttachCommand;
+use crate::format::StarlarkFormatCommand;
 use crate::lint::StarlarkLintCommand;
+use crate::typecheck::StarlarkTypecheckCommand;
 
 mod debug;
+mod format;
 mod lint;
 pub mod server;
+mod typecheck;
 mod util;
 
 #[derive(Debug, clap::Subcommand)]
@@ -49,6 +53,8 @@ pub enum StarlarkCommand {
 #[derive(Debug, clap::Subcommand, serde::Serialize, serde::Deserialize)]
 pub enum StarlarkOpaqueCommand {
     Lint(StarlarkLintCommand),
+    Format(StarlarkFormatCommand),
+    Typecheck(StarlarkTypecheckCommand),
 }
 
 #[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize, Default)]
@@ -89,6 +95,8 @@ impl StarlarkOpaqueCommand {
     fn as_subcommand(&self) -> &dyn StarlarkOpaqueSubcommand {
         match self {
             Self::Lint(cmd) => cmd,
+            Self::Format(cmd) => cmd,
+            Self::Typecheck(cmd) => cmd,
         }
     }
 }
@@ -18,6 +18,7 @@ use buck2_common::dice::data::HasIoProvider;
 use buck2_common::dice::file_ops::HasFileOps;
 use buck2_common::io::IoProvider;
 use buck2_core::cells::CellResolver;
+use buck2_interpreter::globals::CachedGlobals;
 use buck2_interpreter::path::StarlarkPath;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
@@ -27,7 +28,6 @@ use starlark::errors::Diagnostic;
 use starlark::errors::Lint;
 use starlark::syntax::AstModule;
 
-use crate::util::globals::CachedGlobals;
 use crate::util::paths::starlark_files;
 use crate::StarlarkCommandCommonOptions;
 use crate::StarlarkOpaqueSubcommand;
@@ -0,0 +1,243 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::ClientContext;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::data::HasIoProvider;
+use buck2_common::dice::file_ops::HasFileOps;
+use buck2_common::io::IoProvider;
+use buck2_core::cells::CellResolver;
+use buck2_interpreter::file_loader::LoadedModule;
+use buck2_interpreter::globals::CachedGlobals;
+use buck2_interpreter::load_module::InterpreterCalculation;
+use buck2_interpreter::load_module::INTERPRETER_CALCULATION_IMPL;
+use buck2_interpreter::path::OwnedStarlarkModulePath;
+use buck2_interpreter::path::StarlarkPath;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dice::DiceComputations;
+use dupe::Dupe;
+use starlark::errors::EvalMessage;
+use starlark::stdlib::LibraryExtension;
+use starlark::syntax::AstModule;
+use starlark::typing::Interface;
+use starlark::typing::OracleDocs;
+use starlark::typing::OracleStandard;
+use starlark::typing::TypingOracle;
+
+use crate::util::paths::starlark_files;
+use crate::StarlarkCommandCommonOptions;
+use crate::StarlarkOpaqueSubcommand;
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "starlark-typecheck",
+    about = "Type check Starlark files, using the types of the symbols they load and of the globals."
+)]
+pub struct StarlarkTypecheckCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    /// Print the errors as JSON, one object per line.
+    #[clap(long)]
+    json: bool,
+
+    #[clap(value_name = "PATH", required = true)]
+    paths: Vec<PathArg>,
+}
+
+/// A type error, as printed with `--json`.
+#[derive(serde::Serialize)]
+struct TypecheckErrorJson<'a> {
+    path: &'a str,
+    /// 1-based, absent if the error has no location.
+    line: Option<usize>,
+    /// 1-based.
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: String,
+    name: &'a str,
+    message: &'a str,
+}
+
+impl<'a> TypecheckErrorJson<'a> {
+    fn new(x: &'a EvalMessage) -> Self {
+        Self {
+            path: &x.path,
+            line: x.span.map(|s| s.begin_line + 1),
+            column: x.span.map(|s| s.begin_column + 1),
+            end_line: x.span.map(|s| s.end_line + 1),
+            end_column: x.span.map(|s| s.end_column + 1),
+            severity: x.severity.to_string(),
+            name: &x.name,
+            message: &x.description,
+        }
+    }
+}
+
+/// The interfaces of loaded modules, shared between all the files being checked,
+/// since most of them load the same few modules.
+struct CachedInterfaces<'a> {
+    dice: &'a DiceComputations,
+    cached: HashMap<OwnedStarlarkModulePath, Interface>,
+    /// The types, e.g. records and providers, defined by all the modules loaded so far,
+    /// directly or transitively, since values of those types can be returned from any of them.
+    types: OracleDocs,
+    types_added: HashSet<OwnedStarlarkModulePath>,
+}
+
+impl<'a> CachedInterfaces<'a> {
+    fn new(dice: &'a DiceComputations) -> Self {
+        Self {
+            dice,
+            cached: HashMap::new(),
+            types: OracleDocs::default(),
+            types_added: HashSet::new(),
+        }
+    }
+
+    fn add_types(&mut self, path: &OwnedStarlarkModulePath, module: &LoadedModule) {
+        if !self.types_added.insert(path.clone()) {
+            return;
+        }
+        for doc in module.env().type_documentation() {
+            self.types.add_doc(&doc);
+        }
+        for (path, module) in module.loaded_modules().map.iter() {
+            self.add_types(path, module);
+        }
+    }
+
+    /// The interface of the module `load` refers to, resolved relative to `path`.
+    async fn get(&mut self, path: StarlarkPath<'_>, load: &str) -> anyhow::Result<Interface> {
+        let module = INTERPRETER_CALCULATION_IMPL
+            .get()?
+            .resolve_load(self.dice, path, load)
+            .await?;
+        if let Some(res) = self.cached.get(&module) {
+            return Ok(res.dupe());
+        }
+        let loaded = self.dice.get_loaded_module(module.borrow()).await?;
+        let res = Interface::from_docs(&loaded.env().documentation());
+        self.add_types(&module, &loaded);
+        self.cached.insert(module, res.dupe());
+        Ok(res)
+    }
+}
+
+async fn typecheck_file(
+    path: &StarlarkPath<'_>,
+    cell_resolver: &CellResolver,
+    io: &dyn IoProvider,
+    cached_globals: &mut CachedGlobals<'_>,
+    cached_interfaces: &mut CachedInterfaces<'_>,
+) -> anyhow::Result<Vec<EvalMessage>> {
+    let dialect = path.file_type().dialect(false);
+    let proj_path = cell_resolver.resolve_path(path.path().as_ref().as_ref())?;
+    let path_str = proj_path.to_string();
+    let content = io
+        .read_file_if_exists(proj_path)
+        .await?
+        .with_context(|| format!("File not found: `{}`", path_str))?;
+    let ast = match AstModule::parse(&path_str, content, &dialect) {
+        Ok(ast) => ast,
+        Err(err) => return Ok(vec![EvalMessage::from_anyhow(Path::new(&path_str), &err)]),
+    };
+
+    let mut loads = HashMap::new();
+    for load in ast.loads() {
+        let interface = cached_interfaces
+            .get(*path, load.module_id)
+            .await
+            .with_context(|| format!("Loading `{}` from `{}`", load.module_id, path))?;
+        loads.insert(load.module_id.to_owned(), interface);
+    }
+
+    let globals = cached_globals.get_oracle(path).await?;
+    let standard = OracleStandard::new(LibraryExtension::all());
+    // The buck2 globals take priority, then the types defined by loaded modules, and the
+    // standard oracle knows the attributes of builtin types.
+    let oracle: [&dyn TypingOracle; 3] = [&*globals, &cached_interfaces.types, &standard];
+    let (errors, _, _, _) = ast.typecheck(&oracle.as_slice(), &loads);
+    Ok(errors
+        .iter()
+        .map(|e| EvalMessage::from_anyhow(Path::new(&path_str), e))
+        .collect())
+}
+
+#[async_trait]
+impl StarlarkOpaqueSubcommand for StarlarkTypecheckCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(async move |server_ctx, ctx| {
+                let cell_resolver = ctx.get_cell_resolver().await?;
+                let fs = ctx.file_ops();
+                let io = ctx.global_data().get_io_provider();
+                let mut cached_globals = CachedGlobals::new(&ctx);
+                let mut cached_interfaces = CachedInterfaces::new(&ctx);
+
+                let mut stdout = stdout.as_writer();
+                let mut error_count = 0;
+                let files =
+                    starlark_files(&self.paths, server_ctx, &cell_resolver, &fs, &*io).await?;
+                for file in &files {
+                    let errors = typecheck_file(
+                        &file.borrow(),
+                        &cell_resolver,
+                        &*io,
+                        &mut cached_globals,
+                        &mut cached_interfaces,
+                    )
+                    .await?;
+                    error_count += errors.len();
+                    for error in &errors {
+                        if self.json {
+                            writeln!(
+                                stdout,
+                                "{}",
+                                serde_json::to_string(&TypecheckErrorJson::new(error))?
+                            )?;
+                        } else {
+                            writeln!(stdout, "{}", error)?;
+                        }
+                    }
+                }
+                if error_count > 0 {
+                    Err(anyhow::anyhow!("Found {} type errors", error_count))
+                } else {
+                    writeln!(
+                        server_ctx.stderr()?,
+                        "Found no type errors in {} files",
+                        files.len()
+                    )?;
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    fn common_opts(&self) -> &StarlarkCommandCommonOptions {
+        &self.common_opts
+    }
+}
@@ -7,5 +7,4 @@
  * of this source tree.
  */
 
-pub(crate) mod globals;
 pub(crate) mod paths;
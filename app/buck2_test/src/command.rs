@@ -9,11 +9,13 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_build_api::analysis::calculation::profile_analysis_recursively_for_targets;
 use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
 use buck2_build_api::artifact_groups::calculation::ArtifactGroupCalculation;
 use buck2_build_api::artifact_groups::ArtifactGroup;
@@ -39,6 +41,7 @@ use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_core::env_helper::EnvHelper;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
 use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
@@ -100,6 +103,8 @@ pub(crate) struct TestReport {
 
 struct TestOutcome {
     error_messages: Vec<String>,
+    /// The test targets that were run.
+    tested: Vec<ConfiguredProvidersLabel>,
     executor_report: ExecutorReport,
     executor_stdout: String,
     executor_stderr: String,
@@ -305,7 +310,7 @@ async fn test(
     });
 
     let test_outcome = test_targets(
-        ctx,
+        ctx.dupe(),
         resolved_pattern,
         global_target_platform,
         request.test_executor_args.clone(),
@@ -322,6 +327,13 @@ async fn test(
     )
     .await?;
 
+    if !request.starlark_coverage_path.is_empty() && !test_outcome.tested.is_empty() {
+        let targets = test_outcome.tested.map(|label| label.target().dupe());
+        let profile_data = profile_analysis_recursively_for_targets(&ctx, &targets).await?;
+        let output = AbsPath::new(Path::new(&request.starlark_coverage_path))?;
+        buck2_profile::write_coverage(&profile_data.profile_data, output)?;
+    }
+
     // TODO(bobyf) remap exit code for buck reserved exit code
     let exit_code = test_outcome.exit_code().context("No exit code available")?;
 
@@ -509,7 +521,7 @@ async fn test_targets(
 
                     // And finally return our results;
 
-                    anyhow::Ok((driver.build_errors, test_statuses))
+                    anyhow::Ok((driver.build_errors, driver.tested, test_statuses))
                 },
             )
         });
@@ -533,12 +545,13 @@ async fn test_targets(
     )));
 
     // TODO(bobyf, torozco) we can use cancellation handle here instead of liveliness observer
-    let (build_errors, executor_report) = test_server
+    let (build_errors, tested, executor_report) = test_server
         .await
         .context("Failed to collect executor report")??;
 
     Ok(TestOutcome {
         error_messages: build_errors,
+        tested,
         executor_stdout: executor_output.stdout,
         executor_stderr: executor_output.stderr,
         executor_report,
@@ -557,6 +570,9 @@ enum TestDriverTask {
     TestTargets {
         labels: Vec<ConfiguredProvidersLabel>,
     },
+    Tested {
+        label: ConfiguredProvidersLabel,
+    },
     Done,
 }
 
@@ -577,6 +593,7 @@ struct TestDriver<'a, 'e> {
     work: FuturesUnordered<BoxFuture<'a, anyhow::Result<TestDriverTask>>>,
     labels_seen: HashSet<ConfiguredProvidersLabel>,
     build_errors: Vec<String>,
+    tested: Vec<ConfiguredProvidersLabel>,
 }
 
 impl<'a, 'e> TestDriver<'a, 'e> {
@@ -586,6 +603,7 @@ impl<'a, 'e> TestDriver<'a, 'e> {
             work: FuturesUnordered::new(),
             labels_seen: HashSet::new(),
             build_errors: Vec::new(),
+            tested: Vec::new(),
         }
     }
 
@@ -615,6 +633,9 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                 Ok(TestDriverTask::TestTargets { labels }) => {
                     self.test_targets(labels);
                 }
+                Ok(TestDriverTask::Tested { label }) => {
+                    self.tested.push(label);
+                }
                 Ok(TestDriverTask::Done) => {
                     // Nothing to do here
                 }
@@ -696,7 +717,7 @@ impl<'a, 'e> TestDriver<'a, 'e> {
             let state = self.state;
 
             let fut = async move {
-                test_target(
+                let tested = test_target(
                     state.ctx,
                     label,
                     state.test_executor.dupe(),
@@ -707,7 +728,10 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                 )
                 .await?;
 
-                anyhow::Ok(TestDriverTask::Done)
+                anyhow::Ok(match tested {
+                    Some(label) => TestDriverTask::Tested { label },
+                    None => TestDriverTask::Done,
+                })
             }
             .boxed();
 
@@ -98,6 +98,17 @@ pub fn process_stats() -> ProcessStats {
     ProcessStats::default()
 }
 
+/// Resident set size of the process with the given pid. Only supported on Linux.
+pub fn process_rss_bytes(pid: u32) -> Option<u64> {
+    use crate::process_stats::proc_self_stat::ProcSelfStat;
+
+    if cfg!(target_os = "linux") {
+        ProcSelfStat::read_pid(pid).map(|stat| stat.rss * 4096)
+    } else {
+        None
+    }
+}
+
 pub fn process_cpu_time_us() -> Option<u64> {
     let stats = process_stats();
     if let (Some(user_cpu_us), Some(system_cpu_us)) = (stats.user_cpu_us, stats.system_cpu_us) {
@@ -125,7 +136,16 @@ mod proc_self_stat {
         }
 
         pub fn read() -> Option<ProcSelfStat> {
-            fs::read_to_string("/proc/self/stat")
+            Self::read_path("/proc/self/stat")
+        }
+
+        /// Read the stat file of another process, which has the same format.
+        pub fn read_pid(pid: u32) -> Option<ProcSelfStat> {
+            Self::read_path(&format!("/proc/{}/stat", pid))
+        }
+
+        fn read_path(path: &str) -> Option<ProcSelfStat> {
+            fs::read_to_string(path)
                 .ok()
                 .and_then(|s| ProcSelfStat::parse(&s))
         }
@@ -135,6 +155,7 @@ mod proc_self_stat {
 #[cfg(test)]
 mod tests {
     use crate::process_stats::proc_self_stat::ProcSelfStat;
+    use crate::process_stats::process_rss_bytes;
     use crate::process_stats::process_stats;
 
     #[test]
@@ -166,6 +187,13 @@ mod tests {
         assert_eq!(215, ProcSelfStat::parse(stat).unwrap().rss);
     }
 
+    #[test]
+    fn test_process_rss_bytes() {
+        if cfg!(target_os = "linux") {
+            assert!(process_rss_bytes(std::process::id()).unwrap() > 0);
+        }
+    }
+
     #[test]
     fn test_proc_self_stat_read() {
         if cfg!(target_os = "linux") {
@@ -188,15 +188,19 @@
 use std::fmt::Debug;
 use std::io::Write;
 use std::sync::Arc;
+use std::time::Duration;
 
 use allocative::Allocative;
 use futures::future::Future;
 use serde::Serializer;
 
 use crate::api::cycles::DetectCycles;
+use crate::api::persistence::PersistentKeys;
 use crate::api::transaction::DiceTransactionUpdater;
 use crate::api::user_data::UserComputationData;
 use crate::api::which::WhichSpawner;
+use crate::introspection::invalidation::InvalidationSummary;
+use crate::introspection::invalidation::InvalidationTrace;
 use crate::metrics::Metrics;
 use crate::DiceDataBuilderImpl;
 use crate::DiceImplementation;
@@ -274,6 +278,42 @@ impl Dice {
     pub async fn is_idle(&self) -> bool {
         self.implementation.is_idle().await
     }
+
+    /// Serializes the values verified at the current version whose keys, and the keys of all of
+    /// their transitive deps, are registered in `keys`, together with their deps.
+    pub async fn snapshot(&self, keys: &PersistentKeys) -> anyhow::Result<Vec<u8>> {
+        self.implementation.snapshot(keys).await
+    }
+
+    /// Loads a snapshot taken by `snapshot`, possibly by another process, as a new version.
+    /// This must be called before anything is computed or injected.
+    ///
+    /// Restored values are not recomputed: changes injected afterwards invalidate their rdeps
+    /// as usual, and rdeps are only recomputed if the value of a dep actually changed. So any
+    /// state that may have changed since the snapshot, e.g. the file system, must be re-injected,
+    /// or read by keys registered with `PersistentKeys::register_revalidated`.
+    pub async fn restore(&self, keys: &PersistentKeys, snapshot: &[u8]) -> anyhow::Result<()> {
+        self.implementation.restore(keys, snapshot).await
+    }
+
+    /// The keys changed and dirtied by each of the most recent transactions that changed
+    /// anything, oldest first, with the key that caused each key to be dirtied.
+    pub async fn invalidation_traces(&self) -> Vec<InvalidationTrace> {
+        self.implementation.invalidation_traces().await
+    }
+
+    /// Counts of the keys changed and dirtied by the most recent transaction that changed
+    /// anything, if any.
+    pub async fn latest_invalidation_summary(&self) -> Option<InvalidationSummary> {
+        self.implementation.latest_invalidation_summary().await
+    }
+
+    /// Frees memory by dropping the values of keys with an evictable `StorageType` that were not
+    /// requested in the given duration, returning the number of keys whose values were dropped.
+    /// The dropped values are recomputed when next requested.
+    pub async fn evict(&self, not_requested_for: Duration) -> anyhow::Result<usize> {
+        self.implementation.evict(not_requested_for).await
+    }
 }
 
 pub struct DiceDataBuilder(DiceDataBuilderImpl);
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::time::Duration;
+
 use allocative::Allocative;
 
 #[derive(Allocative, PartialEq, Eq, Debug)]
@@ -22,6 +24,17 @@ pub enum DiceEvent {
 
     /// Checking dependencies has finished.
     CheckDepsFinished { key_type: &'static str },
+
+    /// A value was computed, taking `duration` of wall time. `early_cutoff` is set when the value
+    /// was equal to the previously computed one.
+    Computed {
+        key_type: &'static str,
+        duration: Duration,
+        early_cutoff: bool,
+    },
+
+    /// A previously computed value was reused without being recomputed.
+    Reused { key_type: &'static str },
 }
 
 pub trait DiceEventListener: Allocative + Send + Sync + 'static {
@@ -19,6 +19,7 @@ pub mod events;
 pub mod injected;
 pub mod key;
 pub mod opaque;
+pub mod persistence;
 pub mod projection;
 pub mod storage_type;
 pub mod transaction;
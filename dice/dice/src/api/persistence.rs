@@ -0,0 +1,104 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Opt-in persistence of computed values across DICE instances, e.g. across daemon restarts.
+
+use std::any::TypeId;
+use std::sync::Arc;
+
+use crate::api::key::Key;
+use crate::impls::persistence::PersistentKeyDyn;
+use crate::impls::persistence::PersistentKeyImpl;
+use crate::impls::persistence::SuppliedKeyImpl;
+use crate::HashMap;
+
+/// A `Key` whose values can be saved with `Dice::snapshot` and loaded with `Dice::restore`.
+///
+/// A restored value is reused for as long as its deps are unchanged, so only keys whose value
+/// is entirely determined by the key and the values of its deps may opt in. Keys that read any
+/// other state, e.g. the file system directly, must not implement this trait.
+///
+/// A node is persisted only if its key type and the key types of all of its transitive deps
+/// are persistent. Since the leaves of the graph are usually injected keys, e.g. file state,
+/// those must opt in too, be registered with `PersistentKeys::register_supplied`, or be
+/// re-injected after restoring to revalidate everything that depends on them.
+pub trait PersistentKey: Key {
+    /// Identifies this key type in snapshots. It must be unique among the registered key types,
+    /// and must change whenever the serialized format of the key or value changes.
+    const NAME: &'static str;
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>>;
+
+    fn deserialize_key(data: &[u8]) -> anyhow::Result<Self>;
+
+    /// Returns `None` if this value should not be persisted, e.g. because it is an error, in
+    /// which case neither this key nor its rdeps are saved.
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>>;
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value>;
+}
+
+/// The set of `PersistentKey` types to save and load. Keys of other types are never persisted,
+/// and snapshots containing key types that are not registered cannot be restored.
+#[derive(Default)]
+pub struct PersistentKeys {
+    by_type: HashMap<TypeId, Arc<dyn PersistentKeyDyn>>,
+    by_name: HashMap<&'static str, Arc<dyn PersistentKeyDyn>>,
+}
+
+impl PersistentKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<K: PersistentKey>(&mut self) {
+        self.insert::<K>(K::NAME, Arc::new(PersistentKeyImpl::<K>::new(false)));
+    }
+
+    /// Registers a key type that reads state outside of DICE, e.g. the file system. Its
+    /// restored values are recomputed when next requested, and its rdeps are only recomputed if
+    /// the recomputed value differs from the restored one.
+    pub fn register_revalidated<K: PersistentKey>(&mut self) {
+        self.insert::<K>(K::NAME, Arc::new(PersistentKeyImpl::<K>::new(true)));
+    }
+
+    /// Registers a key type whose values cannot be serialized, e.g. because they hold handles,
+    /// but can be supplied when restoring. Keys are identified by their `Display`, and only the
+    /// keys in `values` whose value at snapshot time equals the supplied one are saved.
+    ///
+    /// The same values must be supplied for `snapshot` and `restore`, and it is up to the caller
+    /// to only restore a snapshot taken with equal values. Computed keys registered this way are
+    /// revalidated like keys registered with `register_revalidated`, and the deps they were
+    /// computed with are not saved.
+    pub fn register_supplied<K: Key>(
+        &mut self,
+        name: &'static str,
+        values: impl IntoIterator<Item = (K, K::Value)>,
+    ) {
+        self.insert::<K>(name, Arc::new(SuppliedKeyImpl::<K>::new(name, values)));
+    }
+
+    fn insert<K: Key>(&mut self, name: &'static str, persistence: Arc<dyn PersistentKeyDyn>) {
+        let previous = self.by_name.insert(name, persistence.clone());
+        assert!(
+            previous.is_none() || self.by_type.contains_key(&TypeId::of::<K>()),
+            "persistent key name `{}` registered for two different key types",
+            name
+        );
+        self.by_type.insert(TypeId::of::<K>(), persistence);
+    }
+
+    pub(crate) fn get_by_type(&self, type_id: TypeId) -> Option<&dyn PersistentKeyDyn> {
+        self.by_type.get(&type_id).map(|p| &**p)
+    }
+
+    pub(crate) fn get_by_name(&self, name: &str) -> Option<&dyn PersistentKeyDyn> {
+        self.by_name.get(name).map(|p| &**p)
+    }
+}
@@ -18,4 +18,26 @@ use gazebo::variants::UnpackVariants;
 #[derive(UnpackVariants, Debug, Clone, Copy, Dupe, Allocative)]
 pub enum StorageType {
     LastN(usize),
+    /// Like `LastN`, except that the stored values may also be dropped by `Dice::evict` if the
+    /// key was not requested recently. Only the values are dropped: the deps and history are
+    /// kept so that invalidations still propagate, and the value is recomputed when it is next
+    /// requested.
+    ///
+    /// A recomputed value replaces the evicted one without invalidating anything that depends on
+    /// it, so keys may only opt in if recomputing them with the same deps gives an equivalent
+    /// value, even if `equality` can't tell.
+    EvictableLastN(usize),
+}
+
+impl StorageType {
+    /// The number of versions of the value to keep.
+    pub fn num_to_keep(self) -> usize {
+        match self {
+            StorageType::LastN(n) | StorageType::EvictableLastN(n) => n,
+        }
+    }
+
+    pub fn is_evictable(self) -> bool {
+        matches!(self, StorageType::EvictableLastN(_))
+    }
 }
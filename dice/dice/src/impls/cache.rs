@@ -57,6 +57,12 @@ impl SharedCache {
         self.data.storage.len()
     }
 
+    /// The keys requested in this transaction so far. Only the first request for each key goes
+    /// through the core state, the others are served from this cache.
+    pub(crate) fn requested_keys(&self) -> impl Iterator<Item = DiceKey> + '_ {
+        self.data.storage.iter().map(|entry| *entry.key())
+    }
+
     /// This function gets the termination observer for all running tasks when transaction is
     /// cancelled and prevents further tasks from being added
     pub(crate) fn cancel_pending_tasks(self) -> Vec<TerminationObserver> {
@@ -68,6 +68,13 @@ impl VersionedGraph {
                     // TODO(bobyf) should probably write the metadata of vacant
                     None
                 }
+                VersionedGraphNode::Evicted(e) => Some(SerializedGraphNode {
+                    node_id: NodeID(key.index as usize),
+                    kind: GraphNodeKind::Evicted,
+                    history: e.metadata.hist.to_introspectable(),
+                    deps: Some(visit_deps(e.metadata.deps.deps())),
+                    rdeps: Some(visit_rdeps(e.metadata.rdeps.rdeps())),
+                }),
             }
         }
 
@@ -91,9 +98,9 @@ impl VersionedGraph {
                 edges.insert(
                     dyn_k.clone(),
                     last.1
-                        .unpack_occupied()
-                        .map(|node| {
-                            node.metadata()
+                        .metadata()
+                        .map(|metadata| {
+                            metadata
                                 .deps
                                 .deps()
                                 .map(|k| key_map.get(k).expect("key should exist").clone())
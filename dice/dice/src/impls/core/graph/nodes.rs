@@ -18,6 +18,8 @@
 //! up-to-date-ness of cache entries.
 //!
 
+use std::mem;
+
 use allocative::Allocative;
 use dupe::Dupe;
 use gazebo::variants::UnpackVariants;
@@ -26,6 +28,7 @@ use crate::arc::Arc;
 use crate::impls::core::graph::dependencies::VersionedDependencies;
 use crate::impls::core::graph::dependencies::VersionedRevDependencies;
 use crate::impls::core::graph::history::CellHistory;
+use crate::impls::core::graph::history::HistoryState;
 use crate::impls::key::DiceKey;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
@@ -38,6 +41,7 @@ use crate::versions::VersionNumber;
 pub(crate) enum VersionedGraphNode {
     Occupied(OccupiedGraphNode),
     Vacant(VacantGraphNode),
+    Evicted(EvictedGraphNode),
 }
 
 impl VersionedGraphNode {
@@ -45,6 +49,7 @@ impl VersionedGraphNode {
         match self {
             VersionedGraphNode::Occupied(e) => e.metadata.hist.force_dirty(v),
             VersionedGraphNode::Vacant(e) => e.hist.force_dirty(v),
+            VersionedGraphNode::Evicted(e) => e.metadata.hist.force_dirty(v),
         }
     }
 
@@ -52,6 +57,7 @@ impl VersionedGraphNode {
         match self {
             VersionedGraphNode::Occupied(e) => e.metadata.hist.mark_invalidated(v),
             VersionedGraphNode::Vacant(e) => e.hist.mark_invalidated(v),
+            VersionedGraphNode::Evicted(e) => e.metadata.hist.mark_invalidated(v),
         }
     }
 
@@ -59,7 +65,76 @@ impl VersionedGraphNode {
         match self {
             VersionedGraphNode::Occupied(o) => &o.metadata().hist,
             VersionedGraphNode::Vacant(v) => &v.hist,
+            VersionedGraphNode::Evicted(e) => &e.metadata.hist,
+        }
+    }
+
+    /// The edges and history of the node, if it was ever computed.
+    pub(crate) fn metadata(&self) -> Option<&NodeMetadata> {
+        match self {
+            VersionedGraphNode::Occupied(o) => Some(o.metadata()),
+            VersionedGraphNode::Vacant(_) => None,
+            VersionedGraphNode::Evicted(e) => Some(&e.metadata),
+        }
+    }
+
+    pub(crate) fn metadata_mut(&mut self) -> Option<&mut NodeMetadata> {
+        match self {
+            VersionedGraphNode::Occupied(o) => Some(o.metadata_mut()),
+            VersionedGraphNode::Vacant(_) => None,
+            VersionedGraphNode::Evicted(e) => Some(&mut e.metadata),
+        }
+    }
+
+    /// Drops the value of an occupied node, keeping its edges and history. Returns whether there
+    /// was a value to drop.
+    pub(crate) fn evict(&mut self) -> bool {
+        let key = match self {
+            VersionedGraphNode::Occupied(o) => o.key,
+            _ => return false,
+        };
+        if let VersionedGraphNode::Occupied(o) = self.take(key) {
+            *self = VersionedGraphNode::Evicted(EvictedGraphNode {
+                key,
+                metadata: o.metadata,
+            });
         }
+        true
+    }
+
+    /// Puts back the value of an evicted node that was recomputed at a version its history says
+    /// it was verified at. Returns whether the node was restored.
+    ///
+    /// Evictable keys recompute to values equivalent to the ones they had, so the node, and
+    /// everything that depends on it, remains valid with the new value.
+    pub(crate) fn restore_evicted(&mut self, v: VersionNumber, value: DiceValidValue) -> bool {
+        let key = match self {
+            VersionedGraphNode::Evicted(e)
+                if matches!(e.metadata.hist.get_history(&v), HistoryState::Verified) =>
+            {
+                e.key
+            }
+            _ => return false,
+        };
+        if let VersionedGraphNode::Evicted(e) = self.take(key) {
+            *self = VersionedGraphNode::Occupied(OccupiedGraphNode {
+                key,
+                res: value,
+                metadata: e.metadata,
+            });
+        }
+        true
+    }
+
+    /// Moves the node out, leaving an empty placeholder to be overwritten.
+    fn take(&mut self, key: DiceKey) -> VersionedGraphNode {
+        mem::replace(
+            self,
+            VersionedGraphNode::Vacant(VacantGraphNode {
+                key,
+                hist: CellHistory::empty(),
+            }),
+        )
     }
 }
 
@@ -97,6 +172,10 @@ impl OccupiedGraphNode {
         }
     }
 
+    pub(crate) fn key(&self) -> DiceKey {
+        self.key
+    }
+
     pub(crate) fn metadata(&self) -> &NodeMetadata {
         &self.metadata
     }
@@ -153,6 +232,15 @@ pub(crate) struct VacantGraphNode {
     pub(crate) hist: CellHistory,
 }
 
+/// An `OccupiedGraphNode` whose value was dropped to free memory. Its edges are kept so that
+/// invalidations still propagate through it, and it is replaced by an `OccupiedGraphNode` when
+/// the value is recomputed.
+#[derive(Allocative, Clone)]
+pub(crate) struct EvictedGraphNode {
+    pub(crate) key: DiceKey,
+    pub(crate) metadata: NodeMetadata,
+}
+
 #[cfg(test)]
 mod tests {
     use allocative::Allocative;
@@ -39,6 +39,7 @@ use crate::impls::core::graph::types::VersionedGraphResultMismatch;
 use crate::impls::key::DiceKey;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
+use crate::introspection::invalidation::InvalidationCauses;
 use crate::versions::VersionNumber;
 use crate::HashMap;
 
@@ -91,6 +92,11 @@ impl VersionedGraph {
             VersionedGraphResult::Compute
         }
 
+        fn handle_evicted() -> VersionedGraphResult {
+            // there is no value to reuse, even if the deps are unchanged
+            VersionedGraphResult::Compute
+        }
+
         if let Some(versioned) = self.last_n.get(&key.k) {
             let mut potential = versioned.range((
                 Bound::Included(VersionNumber::new(0)),
@@ -99,6 +105,7 @@ impl VersionedGraph {
             if let Some(found) = potential.next_back().map(|e| match e.1 {
                 VersionedGraphNode::Occupied(entry) => handle_occupied(key, entry),
                 VersionedGraphNode::Vacant(_) => handle_vacant(),
+                VersionedGraphNode::Evicted(_) => handle_evicted(),
             }) {
                 found
             } else {
@@ -110,7 +117,7 @@ impl VersionedGraph {
                     .range((Bound::Included(key.v), Bound::Unbounded))
                     .find_map(|(v, e)| match e {
                         VersionedGraphNode::Occupied(e) => Some((v, e)),
-                        VersionedGraphNode::Vacant(_) => None,
+                        VersionedGraphNode::Vacant(_) | VersionedGraphNode::Evicted(_) => None,
                     })
                     .map_or_else(
                         || VersionedGraphResult::Compute,
@@ -128,6 +135,42 @@ impl VersionedGraph {
         }
     }
 
+    /// The nodes whose values are verified at the given version, which is expected to be the
+    /// latest version.
+    pub(crate) fn verified_at(
+        &self,
+        v: VersionNumber,
+    ) -> impl Iterator<Item = &OccupiedGraphNode> + '_ {
+        self.last_n.values().filter_map(move |versioned| {
+            match versioned
+                .range((Bound::Unbounded, Bound::Included(v)))
+                .next_back()
+            {
+                Some((_, VersionedGraphNode::Occupied(entry)))
+                    if matches!(
+                        entry.metadata().hist.get_history(&v),
+                        HistoryState::Verified
+                    ) =>
+                {
+                    Some(entry)
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Drops every value stored for the key, keeping its edges and history so that it is
+    /// recomputed when next requested. Returns whether any value was dropped.
+    pub(crate) fn evict(&mut self, key: DiceKey) -> bool {
+        let mut evicted = false;
+        if let Some(versioned) = self.last_n.get_mut(&key) {
+            for (_, node) in versioned.range_mut((Bound::Unbounded, Bound::Unbounded)) {
+                evicted |= node.evict();
+            }
+        }
+        evicted
+    }
+
     /// gets the cache entry corresponding to the cache entry if up to date.
     /// returns 'None' if entry is missing or versions are out of date.
     fn get_internal<'a>(
@@ -172,7 +215,7 @@ impl VersionedGraph {
         deps: Arc<Vec<DiceKey>>,
         storage_type: StorageType,
     ) -> (DiceComputedValue, bool) {
-        let StorageType::LastN(num_to_keep) = storage_type;
+        let num_to_keep = storage_type.num_to_keep();
         // persistent keys, if any changes, are committed at the moment when the version
         // is increased. therefore, it must be the case that the current update for the
         // persistent key is the largest/newest version. it's also the case that they are
@@ -196,21 +239,22 @@ impl VersionedGraph {
                 None => {
                     unreachable!("dependency should exist")
                 }
-                Some(node) => match node {
-                    VersionedGraphNode::Occupied(occ) => {
-                        if let Some(dep_v) = occ.metadata().hist.latest_verified_before(key.v) {
+                // the dep may have been evicted since it was computed, but its edges are kept
+                Some(node) => match node.metadata_mut() {
+                    Some(metadata) => {
+                        if let Some(dep_v) = metadata.hist.latest_verified_before(key.v) {
                             latest_dep_verified = cmp::max(latest_dep_verified, Some(dep_v));
 
-                            let dep_d_v = occ.metadata().hist.first_dirty_after(key.v);
+                            let dep_d_v = metadata.hist.first_dirty_after(key.v);
                             first_dep_dirtied = cmp::min(first_dep_dirtied.or(dep_d_v), dep_d_v);
 
-                            occ.metadata_mut().rdeps.add_rdep(key.k, key.v);
+                            metadata.rdeps.add_rdep(key.k, key.v);
                         } else {
-                            let dep_d_v = occ.metadata().hist.first_verified_after(key.v);
+                            let dep_d_v = metadata.hist.first_verified_after(key.v);
                             first_dep_dirtied = cmp::min(first_dep_dirtied.or(dep_d_v), dep_d_v);
                         }
                     }
-                    VersionedGraphNode::Vacant(_) => {
+                    None => {
                         unreachable!("dependency should exist")
                     }
                 },
@@ -311,8 +355,11 @@ impl VersionedGraph {
         num_to_keep: usize,
     ) -> (DiceComputedValue, bool) {
         let versioned_map = self.last_n.get_mut(&key.k).unwrap();
-        let (ret, map_fixup) = match versioned_map.get_mut(&key_of_e).unwrap() {
-            VersionedGraphNode::Occupied(entry) if value.equality(entry.val()) => {
+        let node = versioned_map.get_mut(&key_of_e).unwrap();
+        // a value recomputed after eviction replaces the evicted one without a new history
+        let restored = node.restore_evicted(key.v, value.dupe());
+        let (ret, map_fixup) = match node {
+            VersionedGraphNode::Occupied(entry) if restored || value.equality(entry.val()) => {
                 let since =
                     entry.mark_unchanged(key.v, latest_dep_verified, first_dep_dirtied, deps);
 
@@ -356,10 +403,22 @@ impl VersionedGraph {
 
     /// Invalidates an entry and its transitive rdeps. Returning true if this caused any type of
     /// change
+    #[cfg(test)]
     pub(crate) fn invalidate(
         &mut self,
         key: VersionedGraphKey,
         invalidate: InvalidateKind,
+    ) -> bool {
+        self.invalidate_traced(key, invalidate, &mut InvalidationCauses::default())
+    }
+
+    /// Like `invalidate`, also recording in `causes` the rdeps it dirtied and which key dirtied
+    /// each of them. The changed key itself is left for the caller to record.
+    pub(crate) fn invalidate_traced(
+        &mut self,
+        key: VersionedGraphKey,
+        invalidate: InvalidateKind,
+        causes: &mut InvalidationCauses<DiceKey>,
     ) -> bool {
         let rdeps = {
             match invalidate {
@@ -380,14 +439,13 @@ impl VersionedGraph {
                         };
 
                         if dirtied {
-                            if let Some(e) = e.unpack_occupied() {
+                            if let Some(metadata) = e.metadata() {
                                 let queue = {
-                                    let metadata = e.metadata();
                                     let rdeps = metadata.rdeps.rdeps();
 
                                     rdeps
                                         .iter()
-                                        .map(|(r, v)| (r.dupe(), *v))
+                                        .map(|(r, v)| (r.dupe(), *v, key.k))
                                         .collect::<Vec<_>>()
                                 };
 
@@ -411,7 +469,8 @@ impl VersionedGraph {
                         return true;
                     }
                 }
-                InvalidateKind::Update(value, StorageType::LastN(num_to_keep)) => {
+                InvalidateKind::Update(value, storage_type) => {
+                    let num_to_keep = storage_type.num_to_keep();
                     let rdeps = {
                         let entry = self.last_n.get(&key.k).and_then(|versioned_map| {
                             versioned_map
@@ -427,12 +486,20 @@ impl VersionedGraph {
                                         .rdeps
                                         .rdeps()
                                         .iter()
-                                        .map(|(r, v)| (r.dupe(), *v))
+                                        .map(|(r, v)| (r.dupe(), *v, key.k))
                                         .collect::<Vec<_>>()
                                 } else {
                                     return false;
                                 }
                             }
+                            // without the old value, the new one has to be assumed to differ
+                            Some(VersionedGraphNode::Evicted(evicted)) => evicted
+                                .metadata
+                                .rdeps
+                                .rdeps()
+                                .iter()
+                                .map(|(r, v)| (r.dupe(), *v, key.k))
+                                .collect::<Vec<_>>(),
                             _ => vec![],
                         }
                     };
@@ -481,18 +548,23 @@ impl VersionedGraph {
             }
         };
 
-        self.invalidate_rdeps(key.v, rdeps);
+        self.invalidate_rdeps(key.v, rdeps, causes);
         true
     }
 
+    /// Invalidates each queued rdep, at its relevant version, along with the key whose
+    /// invalidation dirtied it.
     fn invalidate_rdeps(
         &mut self,
         version: VersionNumber,
-        mut queue: Vec<(DiceKey, VersionNumber)>,
+        mut queue: Vec<(DiceKey, VersionNumber, DiceKey)>,
+        causes: &mut InvalidationCauses<DiceKey>,
     ) {
-        while let Some((rdep, relevant_version)) = queue.pop() {
+        while let Some((rdep, relevant_version, cause)) = queue.pop() {
             if let Some(node) = self.get_internal(VersionedGraphKey::new(relevant_version, rdep)) {
                 if node.mark_invalidated(version) {
+                    causes.dirtied(rdep, &cause);
+
                     // since dirty always occurs in increasing order, it must be the case that if
                     // the history was already dirtied, it was by a version number less than the
                     // current version number.
@@ -500,13 +572,13 @@ impl VersionedGraph {
                     // the version it was dirtied at, it may no longer depend on the current node
                     // so we skip marking it as dirty, and rely on delayed propagation of dirty
 
-                    if let Some(node) = node.unpack_occupied() {
+                    if let Some(metadata) = node.metadata() {
                         queue.extend({
-                            let rdeps = node.metadata().rdeps.rdeps();
+                            let rdeps = metadata.rdeps.rdeps();
 
                             rdeps
                                 .iter()
-                                .map(|(r, v)| (r.dupe(), *v))
+                                .map(|(r, v)| (r.dupe(), *v, rdep))
                                 .collect::<Vec<_>>()
                         })
                     }
@@ -558,7 +630,8 @@ impl MapFixup {
                 num_to_keep,
             } => {
                 match versioned_map.get(&key_of_e).unwrap() {
-                    VersionedGraphNode::Occupied(occ) => {
+                    previous @ (VersionedGraphNode::Occupied(_)
+                    | VersionedGraphNode::Evicted(_)) => {
                         if let Some(end) = end {
                             // if there is newer data, we also need to store that at a newer
                             // key to make it reachable.
@@ -575,7 +648,16 @@ impl MapFixup {
                             }
 
                             // TODO change storage so that we don't clone
-                            versioned_map.insert(end, VersionedGraphNode::Occupied(occ.clone()));
+                            let previous = match previous {
+                                VersionedGraphNode::Occupied(occ) => {
+                                    VersionedGraphNode::Occupied(occ.clone())
+                                }
+                                VersionedGraphNode::Evicted(evicted) => {
+                                    VersionedGraphNode::Evicted(evicted.clone())
+                                }
+                                VersionedGraphNode::Vacant(_) => unreachable!("matched above"),
+                            };
+                            versioned_map.insert(end, previous);
                         }
 
                         if versioned_map.len() == num_to_keep {
@@ -7,6 +7,10 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use dupe::Dupe;
 use more_futures::cancellation::future::TerminationObserver;
 
 use crate::api::storage_type::StorageType;
@@ -16,15 +20,21 @@ use crate::impls::core::graph::storage::InvalidateKind;
 use crate::impls::core::graph::storage::VersionedGraph;
 use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
+use crate::impls::core::state::PersistedNode;
 use crate::impls::core::versions::VersionEpoch;
 use crate::impls::core::versions::VersionTracker;
 use crate::impls::key::DiceKey;
+use crate::impls::persistence::PersistenceError;
 use crate::impls::transaction::ChangeType;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
 use crate::introspection::graph::AnyKey;
 use crate::introspection::graph::GraphIntrospectable;
 use crate::introspection::graph::ModernIntrospectable;
+use crate::introspection::invalidation::InvalidationCauses;
+use crate::introspection::invalidation::RecordedInvalidations;
+use crate::introspection::invalidation::UndescribedSummary;
+use crate::introspection::invalidation::UndescribedTrace;
 use crate::metrics::Metrics;
 use crate::result::CancellableResult;
 use crate::result::Cancelled;
@@ -36,6 +46,11 @@ pub(super) struct CoreState {
     version_tracker: VersionTracker,
     graph: VersionedGraph,
     pending_termination_tasks: Vec<TerminationObserver>,
+    invalidations: RecordedInvalidations<DiceKey>,
+    /// When each key with an evictable value still stored was last requested. Requests served
+    /// from the cache of a transaction are accounted for when the transaction ends, or when
+    /// evicting while it is still running.
+    last_requested: HashMap<DiceKey, Instant>,
 }
 
 impl CoreState {
@@ -44,6 +59,8 @@ impl CoreState {
             version_tracker: VersionTracker::new(),
             graph: VersionedGraph::new(),
             pending_termination_tasks: Vec::new(),
+            invalidations: RecordedInvalidations::default(),
+            last_requested: HashMap::default(),
         }
     }
 
@@ -55,8 +72,9 @@ impl CoreState {
         let v = version_update.version();
 
         let mut changes_recorded = false;
+        let mut causes = InvalidationCauses::default();
         for (key, change) in updates {
-            changes_recorded |= self.graph.invalidate(
+            let changed = self.graph.invalidate_traced(
                 VersionedGraphKey::new(v, key),
                 match change {
                     ChangeType::Invalidate => InvalidateKind::ForceDirty,
@@ -64,15 +82,30 @@ impl CoreState {
                     #[cfg(test)]
                     ChangeType::TestingSoftDirty => InvalidateKind::Invalidate,
                 },
+                &mut causes,
             );
+            if changed {
+                causes.changed(key);
+            }
+            changes_recorded |= changed;
         }
         if changes_recorded {
-            version_update.commit()
+            let v = version_update.commit();
+            self.invalidations.record(v, causes);
+            v
         } else {
             version_update.undo()
         }
     }
 
+    pub(super) fn invalidation_traces(&self) -> Vec<UndescribedTrace<DiceKey>> {
+        self.invalidations.traces()
+    }
+
+    pub(super) fn latest_invalidation_summary(&self) -> Option<UndescribedSummary<DiceKey>> {
+        self.invalidations.latest_summary()
+    }
+
     pub(super) fn ctx_at_version(&mut self, v: VersionNumber) -> (VersionEpoch, SharedCache) {
         self.version_tracker.at(v)
     }
@@ -83,6 +116,11 @@ impl CoreState {
 
     pub(super) fn drop_ctx_at_version(&mut self, v: VersionNumber) {
         if let Some(evicted_cache) = self.version_tracker.drop_at_version(v) {
+            mark_requested(
+                &mut self.last_requested,
+                evicted_cache.requested_keys(),
+                Instant::now(),
+            );
             self.pending_termination_tasks
                 .retain(|task| !task.is_terminated());
             self.pending_termination_tasks
@@ -91,6 +129,7 @@ impl CoreState {
     }
 
     pub(super) fn lookup_key(&mut self, key: VersionedGraphKey) -> VersionedGraphResult {
+        mark_requested(&mut self.last_requested, [key.k], Instant::now());
         self.graph.get(key)
     }
 
@@ -105,6 +144,9 @@ impl CoreState {
         if self.version_tracker.is_relevant(key.v, epoch) {
             debug!(msg = "update graph entry", k = ?key.k, v = %key.v, v_epoch = %epoch);
 
+            if storage.is_evictable() {
+                self.last_requested.insert(key.k, Instant::now());
+            }
             Ok(self.graph.update(key, value, deps, storage).0)
         } else {
             debug!(msg = "update is rejected due to outdated epoch", k = ?key.k, v = %key.v, v_epoch = %epoch);
@@ -123,6 +165,80 @@ impl CoreState {
     pub(super) fn unstable_drop_everything(&mut self) {
         self.version_tracker.write().commit();
         self.graph.last_n.clear();
+        self.last_requested.clear();
+    }
+
+    /// Drops the values of the evictable keys not requested since the given time, returning the
+    /// number of keys whose values were dropped.
+    pub(super) fn evict(&mut self, not_requested_since: Instant) -> usize {
+        // Keys in the caches of running transactions are in use.
+        let now = Instant::now();
+        for (_, cache) in self.version_tracker.currently_active() {
+            mark_requested(&mut self.last_requested, cache.requested_keys(), now);
+        }
+
+        let graph = &mut self.graph;
+        let mut evicted = 0;
+        self.last_requested.retain(|key, requested| {
+            if *requested >= not_requested_since {
+                return true;
+            }
+            if graph.evict(*key) {
+                evicted += 1;
+            }
+            false
+        });
+        evicted
+    }
+
+    /// The nodes verified at the current version, with the deps they were computed with.
+    pub(super) fn snapshot(&self) -> Vec<PersistedNode> {
+        self.graph
+            .verified_at(self.version_tracker.current())
+            .map(|node| PersistedNode {
+                key: node.key(),
+                value: node.val().dupe(),
+                deps: node.metadata().deps.deps(),
+            })
+            .collect()
+    }
+
+    /// Records the given nodes as computed at a new version, then dirties the keys in
+    /// `revalidate` at another version so that they are recomputed when next requested. Every
+    /// node must come after all of its deps, and the graph must be empty.
+    pub(super) fn restore(
+        &mut self,
+        nodes: Vec<(PersistedNode, StorageType)>,
+        revalidate: Vec<DiceKey>,
+    ) -> Result<VersionNumber, PersistenceError> {
+        if !self.graph.last_n.is_empty() {
+            return Err(PersistenceError::NotEmpty);
+        }
+
+        let version_update = self.version_tracker.write();
+        let v = version_update.version();
+        if nodes.is_empty() {
+            return Ok(version_update.undo());
+        }
+
+        for (node, storage) in nodes {
+            if storage.is_evictable() {
+                self.last_requested.insert(node.key, Instant::now());
+            }
+            self.graph.update(
+                VersionedGraphKey::new(v, node.key),
+                node.value,
+                node.deps,
+                storage,
+            );
+        }
+        version_update.commit();
+
+        Ok(self.update_state(
+            revalidate
+                .into_iter()
+                .map(|key| (key, ChangeType::Invalidate)),
+        ))
     }
 
     pub(super) fn metrics(&self) -> Metrics {
@@ -139,6 +255,8 @@ impl CoreState {
             key_count: self.graph.last_n.len(),
             currently_active_key_count: currently_running_key_count,
             active_transaction_count: active_transaction_count as u32, // probably won't support more than u32 transactions
+            // recorded outside of the core state, see `DiceModern::metrics`
+            key_types: BTreeMap::new(),
         }
     }
 
@@ -156,6 +274,19 @@ impl CoreState {
     }
 }
 
+/// Records that the given keys were requested at `now`, for those that have evictable values.
+fn mark_requested(
+    last_requested: &mut HashMap<DiceKey, Instant>,
+    keys: impl IntoIterator<Item = DiceKey>,
+    now: Instant,
+) {
+    for key in keys {
+        if let Some(requested) = last_requested.get_mut(&key) {
+            *requested = now;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::any::Any;
@@ -88,6 +88,28 @@ impl StateProcessor {
             StateRequest::Introspection { resp, key_map } => {
                 let _ignored = resp.send(self.state.introspection(key_map));
             }
+            StateRequest::InvalidationTraces { resp } => {
+                let _ignored = resp.send(self.state.invalidation_traces());
+            }
+            StateRequest::LatestInvalidationSummary { resp } => {
+                let _ignored = resp.send(self.state.latest_invalidation_summary());
+            }
+            StateRequest::Snapshot { resp } => {
+                let _ignored = resp.send(self.state.snapshot());
+            }
+            StateRequest::Evict {
+                not_requested_since,
+                resp,
+            } => {
+                let _ignored = resp.send(self.state.evict(not_requested_since));
+            }
+            StateRequest::Restore {
+                nodes,
+                revalidate,
+                resp,
+            } => {
+                let _ignored = resp.send(self.state.restore(nodes, revalidate));
+            }
         }
     }
 }
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::time::Instant;
+
 use allocative::Allocative;
 use derivative::Derivative;
 use dupe::Dupe;
@@ -22,12 +24,15 @@ use crate::impls::core::processor::StateProcessor;
 use crate::impls::core::versions::VersionEpoch;
 use crate::impls::ctx::SharedLiveTransactionCtx;
 use crate::impls::key::DiceKey;
+use crate::impls::persistence::PersistenceError;
 use crate::impls::transaction::ActiveTransactionGuard;
 use crate::impls::transaction::ChangeType;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
 use crate::introspection::graph::AnyKey;
 use crate::introspection::graph::GraphIntrospectable;
+use crate::introspection::invalidation::UndescribedSummary;
+use crate::introspection::invalidation::UndescribedTrace;
 use crate::metrics::Metrics;
 use crate::result::CancellableResult;
 use crate::versions::VersionNumber;
@@ -87,6 +92,41 @@ pub(crate) enum StateRequest {
         #[derivative(Debug = "ignore")]
         key_map: HashMap<DiceKey, AnyKey>,
     },
+    /// Collects the causes of the invalidations of the most recent transactions
+    InvalidationTraces {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Vec<UndescribedTrace<DiceKey>>>,
+    },
+    /// Summarizes the invalidations of the most recent transaction
+    LatestInvalidationSummary {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Option<UndescribedSummary<DiceKey>>>,
+    },
+    /// Collects the nodes verified at the current version, to be persisted
+    Snapshot {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Vec<PersistedNode>>,
+    },
+    /// Drops the values of the evictable keys not requested since the given time
+    Evict {
+        not_requested_since: Instant,
+        resp: Sender<usize>,
+    },
+    /// Records previously persisted nodes as computed at a new version, then dirties the ones
+    /// to revalidate at another version
+    Restore {
+        #[derivative(Debug = "ignore")]
+        nodes: Vec<(PersistedNode, StorageType)>,
+        revalidate: Vec<DiceKey>,
+        resp: Sender<Result<VersionNumber, PersistenceError>>,
+    },
+}
+
+/// A node of the graph as saved by `Snapshot` and loaded by `Restore`.
+pub(crate) struct PersistedNode {
+    pub(crate) key: DiceKey,
+    pub(crate) value: DiceValidValue,
+    pub(crate) deps: Arc<Vec<DiceKey>>,
 }
 
 /// A handle to the core state that allows sending requests
@@ -10,9 +10,12 @@
 use std::fmt::Debug;
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use thiserror::Error;
 
 use crate::api::cycles::DetectCycles;
 use crate::api::data::DiceData;
@@ -23,13 +26,23 @@ use crate::impls::core::state::StateRequest;
 use crate::impls::key_index::DiceKeyIndex;
 use crate::impls::transaction::TransactionUpdater;
 use crate::introspection::graph::GraphIntrospectable;
+use crate::introspection::invalidation::InvalidationSummary;
+use crate::introspection::invalidation::InvalidationTrace;
+use crate::metrics::KeyTypeMetricsRecorder;
 use crate::metrics::Metrics;
 
+#[derive(Debug, Error)]
+pub(crate) enum EvictionError {
+    #[error("DICE eviction is only supported by modern DICE")]
+    Legacy,
+}
+
 #[derive(Allocative)]
 pub(crate) struct DiceModern {
     pub(crate) key_index: DiceKeyIndex,
     pub(crate) state_handle: CoreStateHandle,
     pub(crate) global_data: DiceData,
+    pub(crate) key_type_metrics: KeyTypeMetricsRecorder,
 }
 
 impl Debug for DiceModern {
@@ -62,6 +75,7 @@ impl DiceModern {
             key_index: Default::default(),
             state_handle,
             global_data,
+            key_type_metrics: Default::default(),
         })
     }
 
@@ -86,7 +100,12 @@ impl DiceModern {
 
         // Modern dice can just run on a blocking runtime and block waiting for the channel.
         // This is safe since the processing dice thread is dedicated, and never awaits any other tasks.
-        tokio::task::block_in_place(|| rx.blocking_recv().unwrap())
+        let metrics = tokio::task::block_in_place(|| rx.blocking_recv().unwrap());
+
+        Metrics {
+            key_types: self.key_type_metrics.collect(),
+            ..metrics
+        }
     }
 
     pub fn to_introspectable(&self) -> GraphIntrospectable {
@@ -100,6 +119,39 @@ impl DiceModern {
         rx.blocking_recv().unwrap()
     }
 
+    pub(crate) async fn invalidation_traces(&self) -> Vec<InvalidationTrace> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle
+            .request(StateRequest::InvalidationTraces { resp: tx });
+        rx.await
+            .unwrap()
+            .into_iter()
+            .map(|trace| trace.describe(|k| self.key_index.get(k).introspect()))
+            .collect()
+    }
+
+    pub(crate) async fn latest_invalidation_summary(&self) -> Option<InvalidationSummary> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle
+            .request(StateRequest::LatestInvalidationSummary { resp: tx });
+        rx.await
+            .unwrap()
+            .map(|summary| summary.describe(|k| self.key_index.get(k).introspect()))
+    }
+
+    pub(crate) async fn evict(&self, not_requested_for: Duration) -> usize {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        // a duration too long to subtract from now can't have elapsed, so nothing is evicted
+        let Some(not_requested_since) = Instant::now().checked_sub(not_requested_for) else {
+            return 0;
+        };
+        self.state_handle.request(StateRequest::Evict {
+            not_requested_since,
+            resp: tx,
+        });
+        rx.await.unwrap()
+    }
+
     /// Note: modern dice does not support cycle detection yet
     pub fn detect_cycles(&self) -> &DetectCycles {
         // TODO(bobyf) actually have cycles for dice modern
@@ -8,6 +8,7 @@
  */
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use dupe::Dupe;
 
@@ -52,4 +53,24 @@ impl DiceEventDispatcher {
         self.tracker
             .event(DiceEvent::CheckDepsFinished { key_type: desc })
     }
+
+    pub(crate) fn computed(&self, k: DiceKey, duration: Duration, early_cutoff: bool) {
+        let desc = self.dice.key_index.get(k).key_type_name();
+
+        self.dice
+            .key_type_metrics
+            .computed(desc, duration, early_cutoff);
+        self.tracker.event(DiceEvent::Computed {
+            key_type: desc,
+            duration,
+            early_cutoff,
+        })
+    }
+
+    pub(crate) fn reused(&self, k: DiceKey) {
+        let desc = self.dice.key_index.get(k).key_type_name();
+
+        self.dice.key_type_metrics.reused(desc);
+        self.tracker.event(DiceEvent::Reused { key_type: desc })
+    }
 }
@@ -17,6 +17,7 @@
 use std::any::Any;
 use std::borrow::Cow;
 use std::fmt::Debug;
+use std::time::Instant;
 
 use allocative::Allocative;
 use dupe::Dupe;
@@ -166,7 +167,10 @@ impl IncrementalEngine {
 
             debug!(msg = "running projection");
 
+            let start = Instant::now();
             let eval_result = eval.evaluate(k);
+            // the cache update isn't awaited, so we can't tell whether it was an early cutoff
+            event_dispatcher.computed(k, start.elapsed(), false);
 
             debug!(msg = "projection finished. updating caches");
 
@@ -220,6 +224,7 @@ impl IncrementalEngine {
                 debug!(
                     msg = "found existing entry with matching version in cache. reusing result.",
                 );
+                events_dispatcher.reused(k);
                 Ok((entry, None))
             }
             VersionedGraphResult::Compute => {
@@ -279,6 +284,7 @@ impl IncrementalEngine {
                             deps.iter().copied(),
                             ActivationData::Reused,
                         );
+                        events_dispatcher.reused(k);
 
                         // report reuse
                         let (tx, rx) = tokio::sync::oneshot::channel();
@@ -320,9 +326,11 @@ impl IncrementalEngine {
         // TODO(bobyf) these also make good locations where we want to perform instrumentation
         debug!(msg = "running evaluator");
 
+        let start = Instant::now();
         let eval_result = eval
             .evaluate(k, cycles, task_handle.cancellation_ctx())
             .await?;
+        let duration = start.elapsed();
 
         let guard = match task_handle.cancellation_ctx().try_to_disable_cancellation() {
             Some(g) => g,
@@ -350,17 +358,26 @@ impl IncrementalEngine {
                         key: VersionedGraphKey::new(v, k),
                         epoch: self.version_epoch,
                         storage: eval_result.storage,
-                        value,
+                        value: value.dupe(),
                         deps: Arc::new(eval_result.deps.into_iter().collect()),
                         resp: tx,
                     });
 
-                    rx.await.unwrap()
+                    let res = rx.await.unwrap();
+                    if let Ok(computed) = &res {
+                        // the graph hands back the previous instance when the values are equal
+                        let early_cutoff = !computed.value().is_same_instance(&value);
+                        event_dispatcher.computed(k, duration, early_cutoff);
+                    }
+                    res
+                }
+                Err(value) => {
+                    event_dispatcher.computed(k, duration, false);
+                    CancellableResult::Ok(DiceComputedValue::new(
+                        value,
+                        Arc::new(CellHistory::verified(v)),
+                    ))
                 }
-                Err(value) => CancellableResult::Ok(DiceComputedValue::new(
-                    value,
-                    Arc::new(CellHistory::verified(v)),
-                )),
             }
         };
 
@@ -0,0 +1,388 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Snapshots of the verified part of the graph, restricted to `PersistentKey`s.
+//!
+//! A snapshot holds the nodes verified at the current version in dependency order, each with the
+//! indices of its deps in the snapshot. Restoring replays them as computations at a single new
+//! version, which rebuilds the dep edges and rdeps so that later invalidations of the restored
+//! injected keys propagate as usual, and unchanged values are reused without recomputation.
+//! Nodes that must be revalidated are then dirtied at a second version, so that they are
+//! recomputed when next requested.
+
+use std::any::Any;
+use std::marker::PhantomData;
+
+use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::api::key::Key;
+use crate::api::persistence::PersistentKey;
+use crate::api::persistence::PersistentKeys;
+use crate::api::storage_type::StorageType;
+use crate::arc::Arc;
+use crate::impls::core::state::PersistedNode;
+use crate::impls::core::state::StateRequest;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::DiceKey;
+use crate::impls::key::DiceKeyErased;
+use crate::impls::key_index::DiceKeyIndex;
+use crate::impls::value::DiceKeyValue;
+use crate::impls::value::DiceValidValue;
+use crate::impls::value::DiceValidity;
+use crate::impls::value::MaybeValidDiceValue;
+use crate::HashMap;
+use crate::HashSet;
+
+/// Bumped whenever the layout of `Snapshot` changes.
+const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub(crate) enum PersistenceError {
+    #[error("DICE snapshot has format version {0}, but version {1} is expected")]
+    FormatVersionMismatch(u32, u32),
+    #[error("DICE snapshot contains key type `{0}`, which is not registered as persistent")]
+    UnknownKeyType(String),
+    #[error("DICE snapshot is malformed: a node depends on a node that does not precede it")]
+    DepNotRestored,
+    #[error("DICE snapshot contains a transient value for key type `{0}`")]
+    TransientValue(&'static str),
+    #[error("DICE snapshot contains key `{1}` of type `{0}`, but no value was supplied for it")]
+    NotSupplied(&'static str, String),
+    #[error("DICE snapshots can only be restored before anything is computed or injected")]
+    NotEmpty,
+    #[error("DICE snapshots are only supported by modern DICE")]
+    Legacy,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    format_version: u32,
+    nodes: Vec<SnapshotNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotNode {
+    key_type: String,
+    key: Vec<u8>,
+    /// Empty for supplied keys.
+    value: Vec<u8>,
+    /// Indices of the deps in `Snapshot::nodes`, all smaller than the index of this node.
+    deps: Vec<usize>,
+    /// Whether the value must be recomputed before being reused.
+    revalidate: bool,
+}
+
+/// Type erased `PersistentKey`, or key type registered with `PersistentKeys::register_supplied`.
+pub(crate) trait PersistentKeyDyn: Send + Sync + 'static {
+    fn name(&self) -> &'static str;
+
+    /// Whether values are supplied when restoring rather than serialized. The deps of such keys
+    /// are not saved.
+    fn is_supplied(&self) -> bool;
+
+    /// Whether restored values must be recomputed before being reused.
+    fn revalidate(&self) -> bool;
+
+    /// Serializes the key and the value, or returns `None` if they must not be persisted.
+    /// Panics if they are not of this key type.
+    fn serialize(
+        &self,
+        key: &dyn Any,
+        value: &DiceValidValue,
+    ) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>>;
+
+    fn deserialize(
+        &self,
+        key_index: &DiceKeyIndex,
+        key: &[u8],
+        value: &[u8],
+    ) -> anyhow::Result<(DiceKey, DiceValidValue, StorageType)>;
+}
+
+fn valid_value<K: Key>(name: &'static str, value: K::Value) -> anyhow::Result<DiceValidValue> {
+    Ok(MaybeValidDiceValue::new(
+        std::sync::Arc::new(DiceKeyValue::<K>::new(value)),
+        DiceValidity::Valid,
+    )
+    .into_valid_value()
+    .map_err(|_| PersistenceError::TransientValue(name))?)
+}
+
+pub(crate) struct PersistentKeyImpl<K> {
+    revalidate: bool,
+    _key: PhantomData<fn() -> K>,
+}
+
+impl<K> PersistentKeyImpl<K> {
+    pub(crate) fn new(revalidate: bool) -> Self {
+        Self {
+            revalidate,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K> PersistentKeyDyn for PersistentKeyImpl<K>
+where
+    K: PersistentKey,
+{
+    fn name(&self) -> &'static str {
+        K::NAME
+    }
+
+    fn is_supplied(&self) -> bool {
+        false
+    }
+
+    fn revalidate(&self) -> bool {
+        self.revalidate
+    }
+
+    fn serialize(
+        &self,
+        key: &dyn Any,
+        value: &DiceValidValue,
+    ) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let key = key
+            .downcast_ref::<K>()
+            .expect("persistent key of the wrong type");
+        let value = value
+            .downcast_ref::<K::Value>()
+            .expect("persistent value of the wrong type");
+        Ok(match K::serialize_value(value)? {
+            Some(value) => Some((key.serialize_key()?, value)),
+            None => None,
+        })
+    }
+
+    fn deserialize(
+        &self,
+        key_index: &DiceKeyIndex,
+        key: &[u8],
+        value: &[u8],
+    ) -> anyhow::Result<(DiceKey, DiceValidValue, StorageType)> {
+        let key = K::deserialize_key(key)?;
+        let value = valid_value::<K>(K::NAME, K::deserialize_value(value)?)?;
+        Ok((key_index.index_key(key), value, K::storage_type()))
+    }
+}
+
+pub(crate) struct SuppliedKeyImpl<K: Key> {
+    name: &'static str,
+    /// By the `Display` of the key.
+    values: HashMap<String, (K, K::Value)>,
+}
+
+impl<K: Key> SuppliedKeyImpl<K> {
+    pub(crate) fn new(name: &'static str, values: impl IntoIterator<Item = (K, K::Value)>) -> Self {
+        Self {
+            name,
+            values: values
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), (key, value)))
+                .collect(),
+        }
+    }
+}
+
+impl<K: Key> PersistentKeyDyn for SuppliedKeyImpl<K> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn is_supplied(&self) -> bool {
+        true
+    }
+
+    fn revalidate(&self) -> bool {
+        false
+    }
+
+    fn serialize(
+        &self,
+        key: &dyn Any,
+        value: &DiceValidValue,
+    ) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let key = key
+            .downcast_ref::<K>()
+            .expect("persistent key of the wrong type")
+            .to_string();
+        let value = value
+            .downcast_ref::<K::Value>()
+            .expect("persistent value of the wrong type");
+        Ok(match self.values.get(&key) {
+            Some((_, supplied)) if K::equality(value, supplied) => {
+                Some((key.into_bytes(), Vec::new()))
+            }
+            _ => None,
+        })
+    }
+
+    fn deserialize(
+        &self,
+        key_index: &DiceKeyIndex,
+        key: &[u8],
+        _value: &[u8],
+    ) -> anyhow::Result<(DiceKey, DiceValidValue, StorageType)> {
+        let key = String::from_utf8_lossy(key);
+        let (key, value) = self
+            .values
+            .get(&*key)
+            .ok_or_else(|| PersistenceError::NotSupplied(self.name, key.into_owned()))?;
+        let value = valid_value::<K>(self.name, value.dupe())?;
+        Ok((key_index.index_key(key.clone()), value, K::storage_type()))
+    }
+}
+
+impl DiceModern {
+    pub(crate) async fn snapshot(&self, keys: &PersistentKeys) -> anyhow::Result<Vec<u8>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle
+            .request(StateRequest::Snapshot { resp: tx });
+        let nodes = rx.await.unwrap();
+
+        let persistence_of = move |key: DiceKey| match self.key_index.get(key) {
+            DiceKeyErased::Key(k) => keys.get_by_type(k.as_any().type_id()),
+            // projections are cheap to recompute from their base
+            DiceKeyErased::Projection(_) => None,
+        };
+
+        let nodes: HashMap<DiceKey, &PersistedNode> =
+            nodes.iter().map(|node| (node.key, node)).collect();
+
+        enum Visit {
+            Enter(DiceKey),
+            Exit(DiceKey),
+        }
+
+        // Index in the snapshot of each visited node, or `None` if it, or any of its transitive
+        // deps, cannot be persisted.
+        let mut indices: HashMap<DiceKey, Option<usize>> = HashMap::default();
+        let mut entered = HashSet::default();
+        let mut snapshot = Vec::new();
+
+        for root in nodes.keys() {
+            let mut stack = vec![Visit::Enter(*root)];
+            while let Some(visit) = stack.pop() {
+                match visit {
+                    Visit::Enter(key) => {
+                        if !entered.insert(key) {
+                            continue;
+                        }
+                        match (nodes.get(&key), persistence_of(key)) {
+                            (Some(node), Some(persistence)) => {
+                                stack.push(Visit::Exit(key));
+                                if !persistence.is_supplied() {
+                                    stack.extend(node.deps.iter().map(|dep| Visit::Enter(*dep)));
+                                }
+                            }
+                            _ => {
+                                indices.insert(key, None);
+                            }
+                        }
+                    }
+                    Visit::Exit(key) => {
+                        let node = nodes[&key];
+                        let persistence = persistence_of(key).unwrap();
+                        let deps = if persistence.is_supplied() {
+                            Some(Vec::new())
+                        } else {
+                            node.deps
+                                .iter()
+                                .map(|dep| indices.get(dep).copied().flatten())
+                                .collect::<Option<Vec<_>>>()
+                        };
+                        let serialized = match deps {
+                            Some(deps) => persistence
+                                .serialize(self.key_index.get(key).as_any(), &node.value)?
+                                .map(|data| (deps, data)),
+                            None => None,
+                        };
+                        let index = match serialized {
+                            Some((deps, (key_data, value_data))) => {
+                                snapshot.push(SnapshotNode {
+                                    key_type: persistence.name().to_owned(),
+                                    key: key_data,
+                                    value: value_data,
+                                    deps,
+                                    // supplied keys computed from deps that were not saved
+                                    revalidate: persistence.revalidate()
+                                        || (persistence.is_supplied() && !node.deps.is_empty()),
+                                });
+                                Some(snapshot.len() - 1)
+                            }
+                            None => None,
+                        };
+                        indices.insert(key, index);
+                    }
+                }
+            }
+        }
+
+        Ok(bincode::serialize(&Snapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            nodes: snapshot,
+        })?)
+    }
+
+    pub(crate) async fn restore(&self, keys: &PersistentKeys, data: &[u8]) -> anyhow::Result<()> {
+        let snapshot: Snapshot = bincode::deserialize(data)?;
+        if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(PersistenceError::FormatVersionMismatch(
+                snapshot.format_version,
+                SNAPSHOT_FORMAT_VERSION,
+            )
+            .into());
+        }
+
+        let mut restored: Vec<(PersistedNode, StorageType)> =
+            Vec::with_capacity(snapshot.nodes.len());
+        let mut revalidate = Vec::new();
+        for node in snapshot.nodes {
+            let persistence = keys
+                .get_by_name(&node.key_type)
+                .ok_or_else(|| PersistenceError::UnknownKeyType(node.key_type.clone()))?;
+            let deps = node
+                .deps
+                .iter()
+                .map(|dep| {
+                    restored
+                        .get(*dep)
+                        .map(|(dep, _)| dep.key)
+                        .ok_or(PersistenceError::DepNotRestored)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (key, value, storage) =
+                persistence.deserialize(&self.key_index, &node.key, &node.value)?;
+            if node.revalidate || persistence.revalidate() {
+                revalidate.push(key);
+            }
+            restored.push((
+                PersistedNode {
+                    key,
+                    value,
+                    deps: Arc::new(deps),
+                },
+                storage,
+            ));
+        }
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle.request(StateRequest::Restore {
+            nodes: restored,
+            revalidate,
+            resp: tx,
+        });
+        rx.await.unwrap()?;
+        Ok(())
+    }
+}
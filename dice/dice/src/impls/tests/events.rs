@@ -33,7 +33,11 @@ struct Tracker {
 
 impl DiceEventListener for Tracker {
     fn event(&self, event: DiceEvent) {
-        self.state.lock().unwrap().push(event);
+        match event {
+            // these carry timings, and are covered by the metrics tests
+            DiceEvent::Computed { .. } | DiceEvent::Reused { .. } => {}
+            event => self.state.lock().unwrap().push(event),
+        }
     }
 }
 
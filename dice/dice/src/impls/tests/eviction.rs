@@ -0,0 +1,209 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use allocative::Allocative;
+use assert_matches::assert_matches;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::cycles::DetectCycles;
+use crate::api::dice::Dice;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::api::storage_type::StorageType;
+use crate::impls::dice::DiceModern;
+use crate::impls::dice::EvictionError;
+
+/// Number of calls to `compute` of each key type, shared by all the keys of one DICE instance.
+#[derive(Clone, Dupe, Default)]
+struct ComputeCounts {
+    evictable: Arc<AtomicUsize>,
+    dependent: Arc<AtomicUsize>,
+}
+
+impl ComputeCounts {
+    fn get(&self) -> (usize, usize) {
+        (
+            self.evictable.load(Ordering::SeqCst),
+            self.dependent.load(Ordering::SeqCst),
+        )
+    }
+
+    fn of(ctx: &DiceComputations) -> &ComputeCounts {
+        ctx.global_data().get::<ComputeCounts>().unwrap()
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Source(u32);
+
+impl InjectedKey for Source {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Evictable(u32);
+
+#[async_trait]
+impl Key for Evictable {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCounts::of(ctx)
+            .evictable
+            .fetch_add(1, Ordering::SeqCst);
+        ctx.compute(&Source(self.0)).await.unwrap() * 10
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+
+    fn storage_type() -> StorageType {
+        StorageType::EvictableLastN(1)
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Dependent(u32);
+
+#[async_trait]
+impl Key for Dependent {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCounts::of(ctx)
+            .dependent
+            .fetch_add(1, Ordering::SeqCst);
+        ctx.compute(&Evictable(self.0)).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+fn new_dice() -> (Arc<DiceModern>, ComputeCounts) {
+    let counts = ComputeCounts::default();
+    let mut builder = DiceModern::builder();
+    builder.set(counts.dupe());
+    (builder.build(DetectCycles::Disabled), counts)
+}
+
+#[tokio::test]
+async fn evicted_values_are_recomputed_on_demand() -> anyhow::Result<()> {
+    let (dice, counts) = new_dice();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Dependent(1)).await?, 11);
+    assert_eq!(counts.get(), (1, 1));
+    drop(ctx);
+
+    // recently requested values are kept
+    assert_eq!(dice.evict(Duration::from_secs(3600)).await, 0);
+    assert_eq!(dice.evict(Duration::ZERO).await, 1);
+    // and the value is only evicted once
+    assert_eq!(dice.evict(Duration::ZERO).await, 0);
+
+    // keys depending on the evicted value are still valid
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&Dependent(1)).await?, 11);
+    assert_eq!(counts.get(), (1, 1));
+
+    assert_eq!(ctx.compute(&Evictable(1)).await?, 10);
+    assert_eq!(counts.get(), (2, 1));
+    drop(ctx);
+
+    // the recomputed value can be evicted again
+    assert_eq!(dice.evict(Duration::ZERO).await, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn changes_propagate_through_evicted_values() -> anyhow::Result<()> {
+    let (dice, counts) = new_dice();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Dependent(1)).await?, 11);
+    drop(ctx);
+
+    assert_eq!(dice.evict(Duration::ZERO).await, 1);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 2)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Dependent(1)).await?, 21);
+    assert_eq!(counts.get(), (2, 2));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn values_in_use_by_transactions_are_kept() -> anyhow::Result<()> {
+    let (dice, counts) = new_dice();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Evictable(1)).await?, 10);
+
+    // the value is in the cache of a running transaction
+    assert_eq!(dice.evict(Duration::ZERO).await, 0);
+
+    // requests served from the cache of the transaction count as requests when it ends
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(ctx.compute(&Evictable(1)).await?, 10);
+    drop(ctx);
+    assert_eq!(dice.evict(Duration::from_millis(50)).await, 0);
+    assert_eq!(counts.get(), (1, 0));
+
+    assert_eq!(dice.evict(Duration::ZERO).await, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn legacy_does_not_evict() {
+    let dice = Dice::builder().build(DetectCycles::Disabled);
+    assert_matches!(
+        dice.evict(Duration::ZERO)
+            .await
+            .unwrap_err()
+            .downcast_ref::<EvictionError>(),
+        Some(EvictionError::Legacy)
+    );
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::cycles::DetectCycles;
+use crate::api::dice::Dice;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Source(u32);
+
+impl InjectedKey for Source {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Double(u32);
+
+#[async_trait]
+impl Key for Double {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Source(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Quadruple(u32);
+
+#[async_trait]
+impl Key for Quadruple {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Double(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+async fn check_traces(dice: Arc<Dice>) -> anyhow::Result<()> {
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 1), (Source(2), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Quadruple(1)).await?, 4);
+    assert_eq!(ctx.compute(&Double(2)).await?, 2);
+    drop(ctx);
+
+    // setting the same value changes nothing, so records nothing
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 1)])?;
+    drop(updater.commit().await);
+    assert_eq!(dice.invalidation_traces().await.len(), 1);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 2)])?;
+    drop(updater.commit().await);
+
+    let traces = dice.invalidation_traces().await;
+    assert_eq!(traces.len(), 2);
+    let trace = &traces[1];
+    let quadruple = trace
+        .keys
+        .iter()
+        .position(|k| k.key == "Quadruple(1)")
+        .unwrap();
+    let chain: Vec<_> = trace
+        .chain(quadruple)
+        .into_iter()
+        .map(|k| k.key.as_str())
+        .collect();
+    assert_eq!(chain, vec!["Source(1)", "Double(1)", "Quadruple(1)"]);
+    assert!(!trace.keys.iter().any(|k| k.key == "Double(2)"));
+
+    let summary = dice.latest_invalidation_summary().await.unwrap();
+    assert_eq!(summary.version, trace.version);
+    assert_eq!(summary.changed_keys, 1);
+    assert_eq!(summary.dirtied_keys, 2);
+    assert_eq!(summary.top_changed_keys[0].key, "Source(1)");
+    assert_eq!(summary.top_changed_keys[0].key_type, "Source");
+    assert_eq!(summary.top_changed_keys[0].dirtied_keys, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalidation_traces_modern() -> anyhow::Result<()> {
+    check_traces(Dice::modern().build(DetectCycles::Disabled)).await
+}
+
+#[tokio::test]
+async fn invalidation_traces_legacy() -> anyhow::Result<()> {
+    check_traces(Dice::builder().build(DetectCycles::Disabled)).await
+}
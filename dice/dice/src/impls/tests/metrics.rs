@@ -0,0 +1,157 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::cycles::DetectCycles;
+use crate::api::dice::Dice;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::api::user_data::UserComputationData;
+use crate::DiceDataBuilder;
+use crate::DiceEvent;
+use crate::DiceEventListener;
+
+/// Records the `Computed` events as `(key_type, early_cutoff)`.
+#[derive(Default, Allocative)]
+struct Tracker {
+    computed: Mutex<Vec<(&'static str, bool)>>,
+}
+
+impl DiceEventListener for Tracker {
+    fn event(&self, event: DiceEvent) {
+        if let DiceEvent::Computed {
+            key_type,
+            early_cutoff,
+            ..
+        } = event
+        {
+            self.computed.lock().unwrap().push((key_type, early_cutoff));
+        }
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Source;
+
+impl InjectedKey for Source {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Parity;
+
+#[async_trait]
+impl Key for Parity {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Source).await.unwrap() % 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Describe;
+
+#[async_trait]
+impl Key for Describe {
+    type Value = Arc<String>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let parity = ctx.compute(&Parity).await.unwrap();
+        Arc::new(if parity == 0 { "even" } else { "odd" }.to_owned())
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+async fn test_key_type_metrics_impl(builder: DiceDataBuilder) -> anyhow::Result<()> {
+    let dice = builder.build(DetectCycles::Enabled);
+
+    let compute_with_source = |dice: &Arc<Dice>, source: u32| {
+        let dice = dice.dupe();
+        async move {
+            let tracker = Arc::new(Tracker::default());
+            let mut updater = dice.updater_with_data(UserComputationData {
+                tracker: tracker.dupe(),
+                ..Default::default()
+            });
+            updater.changed_to(vec![(Source, source)])?;
+            let value = updater.commit().await.compute(&Describe).await?;
+            let computed = tracker.computed.lock().unwrap().clone();
+            anyhow::Ok((value, computed))
+        }
+    };
+
+    let (value, computed) = compute_with_source(&dice, 1).await?;
+    assert_eq!(*value, "odd");
+    assert_eq!(computed, vec![("Parity", false), ("Describe", false)]);
+
+    // `Parity` is recomputed to the same value, so `Describe` is reused.
+    let (value, computed) = compute_with_source(&dice, 3).await?;
+    assert_eq!(*value, "odd");
+    assert_eq!(computed, vec![("Parity", true)]);
+
+    let (value, computed) = compute_with_source(&dice, 4).await?;
+    assert_eq!(*value, "even");
+    assert_eq!(computed, vec![("Parity", false), ("Describe", false)]);
+
+    let metrics = dice.metrics().key_types;
+
+    let parity = &metrics["Parity"];
+    assert_eq!(parity.computes, 3);
+    assert_eq!(parity.early_cutoffs, 1);
+    assert!(parity.max_compute_time <= parity.total_compute_time);
+
+    let describe = &metrics["Describe"];
+    assert_eq!(describe.computes, 2);
+    assert_eq!(describe.early_cutoffs, 0);
+    assert!(describe.cache_hits >= 1);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_key_type_metrics_legacy() -> anyhow::Result<()> {
+    test_key_type_metrics_impl(Dice::builder()).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_key_type_metrics_modern() -> anyhow::Result<()> {
+    test_key_type_metrics_impl(Dice::modern()).await
+}
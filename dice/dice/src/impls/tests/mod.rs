@@ -10,8 +10,12 @@
 mod activation_tracker;
 mod demo;
 mod events;
+mod eviction;
 mod general;
+mod invalidation;
 mod keys;
+mod metrics;
+mod persistence;
 mod spawner;
 mod transients;
 mod user_data;
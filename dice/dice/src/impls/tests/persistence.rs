@@ -0,0 +1,508 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use assert_matches::assert_matches;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::cycles::DetectCycles;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::api::persistence::PersistentKey;
+use crate::api::persistence::PersistentKeys;
+use crate::impls::dice::DiceModern;
+use crate::impls::persistence::PersistenceError;
+
+/// Number of calls to `compute`, shared by all the keys of one DICE instance.
+#[derive(Clone, Dupe, Default)]
+struct ComputeCount(Arc<AtomicUsize>);
+
+impl ComputeCount {
+    fn get(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    fn record(ctx: &DiceComputations) {
+        ctx.global_data()
+            .get::<ComputeCount>()
+            .unwrap()
+            .0
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Source(u32);
+
+impl InjectedKey for Source {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for Source {
+    const NAME: &'static str = "Source";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(&self.0)?)
+    }
+
+    fn deserialize_key(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Source(bincode::deserialize(data)?))
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Double(u32);
+
+#[async_trait]
+impl Key for Double {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        ctx.compute(&Source(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for Double {
+    const NAME: &'static str = "Double";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(&self.0)?)
+    }
+
+    fn deserialize_key(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Double(bincode::deserialize(data)?))
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+/// Not persistent, so neither it nor the keys depending on it are snapshotted.
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Volatile;
+
+#[async_trait]
+impl Key for Volatile {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct DependsOnVolatile;
+
+#[async_trait]
+impl Key for DependsOnVolatile {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        ctx.compute(&Volatile).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for DependsOnVolatile {
+    const NAME: &'static str = "DependsOnVolatile";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn deserialize_key(_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(DependsOnVolatile)
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+/// State outside of DICE, read by `ReadsExternal`.
+#[derive(Clone, Dupe, Default)]
+struct External(Arc<AtomicU32>);
+
+/// Reads `External`, so it must be revalidated after restoring.
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct ReadsExternal;
+
+#[async_trait]
+impl Key for ReadsExternal {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        ctx.global_data()
+            .get::<External>()
+            .unwrap()
+            .0
+            .load(Ordering::SeqCst)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for ReadsExternal {
+    const NAME: &'static str = "ReadsExternal";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn deserialize_key(_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(ReadsExternal)
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct DependsOnExternal;
+
+#[async_trait]
+impl Key for DependsOnExternal {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        ctx.compute(&ReadsExternal).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for DependsOnExternal {
+    const NAME: &'static str = "DependsOnExternal";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn deserialize_key(_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(DependsOnExternal)
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+/// An injected key whose value is supplied when restoring rather than serialized.
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct Handle;
+
+impl InjectedKey for Handle {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Clone, Dupe, Debug, Display, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct FromHandle;
+
+#[async_trait]
+impl Key for FromHandle {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ComputeCount::record(ctx);
+        ctx.compute(&Handle).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for FromHandle {
+    const NAME: &'static str = "FromHandle";
+
+    fn serialize_key(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn deserialize_key(_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(FromHandle)
+    }
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(Some(bincode::serialize(value)?))
+    }
+
+    fn deserialize_value(data: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(data)?)
+    }
+}
+
+fn persistent_keys() -> PersistentKeys {
+    let mut keys = PersistentKeys::new();
+    keys.register::<Source>();
+    keys.register::<Double>();
+    keys.register::<DependsOnVolatile>();
+    keys.register_revalidated::<ReadsExternal>();
+    keys.register::<DependsOnExternal>();
+    keys
+}
+
+fn supplied_keys(handle: Option<u32>) -> PersistentKeys {
+    let mut keys = PersistentKeys::new();
+    keys.register_supplied("Handle", handle.map(|value| (Handle, value)));
+    keys.register::<FromHandle>();
+    keys
+}
+
+fn new_dice() -> (Arc<DiceModern>, ComputeCount) {
+    new_dice_with_external(0)
+}
+
+fn new_dice_with_external(external: u32) -> (Arc<DiceModern>, ComputeCount) {
+    let count = ComputeCount::default();
+    let mut builder = DiceModern::builder();
+    builder.set(count.dupe());
+    builder.set(External(Arc::new(AtomicU32::new(external))));
+    (builder.build(DetectCycles::Disabled), count)
+}
+
+async fn snapshot_with_source(value: u32) -> anyhow::Result<Vec<u8>> {
+    let (dice, _count) = new_dice();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), value)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Double(1)).await?, value * 2);
+    assert_eq!(ctx.compute(&DependsOnVolatile).await?, 2);
+    drop(ctx);
+
+    dice.snapshot(&persistent_keys()).await
+}
+
+#[tokio::test]
+async fn restored_values_are_reused_until_deps_change() -> anyhow::Result<()> {
+    let snapshot = snapshot_with_source(5).await?;
+
+    let (dice, count) = new_dice();
+    dice.restore(&persistent_keys(), &snapshot).await?;
+
+    // re-injecting the same file state keeps the restored values
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 5)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Double(1)).await?, 10);
+    assert_eq!(count.get(), 0);
+    drop(ctx);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(1), 6)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Double(1)).await?, 12);
+    assert_eq!(count.get(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn keys_with_non_persistent_deps_are_recomputed() -> anyhow::Result<()> {
+    let snapshot = snapshot_with_source(5).await?;
+
+    let (dice, count) = new_dice();
+    dice.restore(&persistent_keys(), &snapshot).await?;
+
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&DependsOnVolatile).await?, 2);
+    assert_eq!(count.get(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_requires_registered_keys_and_empty_graph() -> anyhow::Result<()> {
+    let snapshot = snapshot_with_source(5).await?;
+
+    let (dice, _count) = new_dice();
+    let mut keys = PersistentKeys::new();
+    keys.register::<Source>();
+    assert_matches!(
+        dice.restore(&keys, &snapshot).await.unwrap_err().downcast_ref::<PersistenceError>(),
+        Some(PersistenceError::UnknownKeyType(name)) => assert_eq!(name, "Double")
+    );
+
+    let (dice, _count) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Source(2), 1)])?;
+    drop(updater.commit().await);
+    assert_matches!(
+        dice.restore(&persistent_keys(), &snapshot)
+            .await
+            .unwrap_err()
+            .downcast_ref::<PersistenceError>(),
+        Some(PersistenceError::NotEmpty)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn revalidated_keys_are_recomputed_and_keep_unchanged_rdeps() -> anyhow::Result<()> {
+    let (dice, _count) = new_dice_with_external(5);
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&DependsOnExternal).await?, 6);
+    drop(ctx);
+    let snapshot = dice.snapshot(&persistent_keys()).await?;
+
+    // the external state is unchanged, so only the key reading it is recomputed
+    let (dice, count) = new_dice_with_external(5);
+    dice.restore(&persistent_keys(), &snapshot).await?;
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&DependsOnExternal).await?, 6);
+    assert_eq!(count.get(), 1);
+
+    let (dice, count) = new_dice_with_external(7);
+    dice.restore(&persistent_keys(), &snapshot).await?;
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&DependsOnExternal).await?, 8);
+    assert_eq!(count.get(), 2);
+
+    Ok(())
+}
+
+/// Snapshots `FromHandle` computed with the given handle, supplying a handle of 3.
+async fn snapshot_with_handle(handle: u32) -> anyhow::Result<Vec<u8>> {
+    let (dice, _count) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Handle, handle)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&FromHandle).await?, handle + 1);
+    drop(ctx);
+
+    dice.snapshot(&supplied_keys(Some(3))).await
+}
+
+#[tokio::test]
+async fn supplied_keys_are_restored_with_the_supplied_values() -> anyhow::Result<()> {
+    let snapshot = snapshot_with_handle(3).await?;
+
+    let (dice, count) = new_dice();
+    dice.restore(&supplied_keys(Some(3)), &snapshot).await?;
+    let ctx = dice.updater().commit().await;
+    assert_eq!(ctx.compute(&Handle).await?, 3);
+    assert_eq!(ctx.compute(&FromHandle).await?, 4);
+    assert_eq!(count.get(), 0);
+
+    let (dice, _count) = new_dice();
+    assert_matches!(
+        dice.restore(&supplied_keys(None), &snapshot)
+            .await
+            .unwrap_err()
+            .downcast_ref::<PersistenceError>(),
+        Some(PersistenceError::NotSupplied(name, key)) => {
+            assert_eq!(*name, "Handle");
+            assert_eq!(key, "Handle");
+        }
+    );
+
+    // values that differ from the supplied ones are not saved, nor are their rdeps
+    let snapshot = snapshot_with_handle(4).await?;
+    let (dice, _count) = new_dice();
+    dice.restore(&supplied_keys(None), &snapshot).await?;
+
+    Ok(())
+}
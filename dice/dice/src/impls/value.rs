@@ -31,7 +31,6 @@ impl Debug for DiceValidValue {
 }
 
 impl DiceValidValue {
-    #[cfg(test)]
     pub(crate) fn downcast_ref<V: Any>(&self) -> Option<&V> {
         self.0.downcast_ref()
     }
@@ -92,6 +91,14 @@ impl MaybeValidDiceValue {
         self.value.equality(&*other.0)
     }
 
+    /// Whether this is the same instance as `other`, rather than just an equal value.
+    pub(crate) fn is_same_instance(&self, other: &DiceValidValue) -> bool {
+        std::ptr::eq(
+            std::sync::Arc::as_ptr(&self.value) as *const (),
+            std::sync::Arc::as_ptr(&other.0) as *const (),
+        )
+    }
+
     pub(crate) fn into_valid_value(self) -> Result<DiceValidValue, MaybeValidDiceValue> {
         match self.validity {
             DiceValidity::Valid => Ok(DiceValidValue(self.value)),
@@ -187,6 +187,7 @@ pub enum GraphNodeKind {
     Occupied,
     Transient,
     Vacant,
+    Evicted,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
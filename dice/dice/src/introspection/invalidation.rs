@@ -0,0 +1,315 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Records of which changed keys caused which keys to be dirtied, to explain recomputations.
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::introspection::graph::AnyKey;
+use crate::versions::VersionNumber;
+use crate::HashMap;
+
+/// Number of transactions whose invalidations are kept. Each record holds every key dirtied by
+/// the transaction, so this is kept small.
+const MAX_RECORDED_TRANSACTIONS: usize = 8;
+
+/// Number of changed keys listed in an `InvalidationSummary`.
+const SUMMARY_TOP_CHANGED_KEYS: usize = 10;
+
+/// Every key changed or dirtied by the changes committed at one version.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvalidationTrace {
+    /// The version the changes were committed at.
+    pub version: usize,
+    pub keys: Vec<InvalidatedKey>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvalidatedKey {
+    pub key: String,
+    pub key_type: String,
+    /// Index in `InvalidationTrace::keys` of the key that dirtied this one when it was
+    /// invalidated, or `None` if this key was changed directly.
+    pub dirtied_by: Option<usize>,
+}
+
+impl InvalidationTrace {
+    /// The chain of keys from a changed key to the key at `index`, which is the last element.
+    pub fn chain(&self, index: usize) -> Vec<&InvalidatedKey> {
+        let mut chain = vec![&self.keys[index]];
+        let mut next = self.keys[index].dirtied_by;
+        while let Some(index) = next {
+            chain.push(&self.keys[index]);
+            next = self.keys[index].dirtied_by;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// Short description of the changes committed at one version, suitable for logging.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InvalidationSummary {
+    /// The version the changes were committed at.
+    pub version: usize,
+    pub changed_keys: usize,
+    pub dirtied_keys: usize,
+    /// The changed keys that dirtied the most keys, most first.
+    pub top_changed_keys: Vec<ChangedKeySummary>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangedKeySummary {
+    pub key: String,
+    pub key_type: String,
+    /// Number of keys dirtied by this key, directly or transitively.
+    pub dirtied_keys: usize,
+}
+
+/// The cause of every key invalidated by one transaction, as it is being committed.
+pub(crate) struct InvalidationCauses<K: Hash + Eq> {
+    /// Each invalidated key, mapped to the key that dirtied it, or `None` if it was changed.
+    causes: HashMap<K, Option<K>>,
+}
+
+impl<K: Hash + Eq> Default for InvalidationCauses<K> {
+    fn default() -> Self {
+        Self {
+            causes: HashMap::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> InvalidationCauses<K> {
+    pub(crate) fn changed(&mut self, key: K) {
+        self.causes.insert(key, None);
+    }
+
+    /// Records that invalidating `by` dirtied `key`. Only the first cause is kept.
+    pub(crate) fn dirtied(&mut self, key: K, by: &K) {
+        self.causes.entry(key).or_insert_with(|| Some(by.clone()));
+    }
+
+    /// For each changed key, the number of keys it dirtied, directly or transitively.
+    fn dirtied_per_changed(&self) -> HashMap<&K, usize> {
+        let mut roots: HashMap<&K, &K> = HashMap::default();
+        let mut counts: HashMap<&K, usize> = HashMap::default();
+        for (key, cause) in &self.causes {
+            if cause.is_none() {
+                counts.entry(key).or_default();
+                continue;
+            }
+            // Follow the causes up to a changed key, or a key whose root is already known.
+            let mut path = vec![key];
+            let mut root = key;
+            while let Some(Some(cause)) = self.causes.get(root) {
+                if let Some(known) = roots.get(cause) {
+                    root = *known;
+                    break;
+                }
+                path.push(cause);
+                root = cause;
+            }
+            for key in path {
+                roots.insert(key, root);
+            }
+            *counts.entry(root).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Causes of the invalidations of the most recent transactions that changed anything.
+pub(crate) struct RecordedInvalidations<K: Hash + Eq> {
+    records: VecDeque<InvalidationRecord<K>>,
+}
+
+struct InvalidationRecord<K: Hash + Eq> {
+    version: VersionNumber,
+    causes: InvalidationCauses<K>,
+    changed_keys: usize,
+    /// Changed keys with the number of keys they dirtied, computed upfront since the summary is
+    /// requested for every command.
+    top_changed_keys: Vec<(K, usize)>,
+}
+
+impl<K: Hash + Eq> Default for RecordedInvalidations<K> {
+    fn default() -> Self {
+        Self {
+            records: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> RecordedInvalidations<K> {
+    pub(crate) fn record(&mut self, version: VersionNumber, causes: InvalidationCauses<K>) {
+        if causes.causes.is_empty() {
+            return;
+        }
+
+        let dirtied = causes.dirtied_per_changed();
+        let changed_keys = dirtied.len();
+        let mut top_changed_keys: Vec<(K, usize)> =
+            dirtied.into_iter().map(|(k, n)| (k.clone(), n)).collect();
+        top_changed_keys.sort_by(|x, y| y.1.cmp(&x.1));
+        top_changed_keys.truncate(SUMMARY_TOP_CHANGED_KEYS);
+
+        if self.records.len() == MAX_RECORDED_TRANSACTIONS {
+            self.records.pop_front();
+        }
+        self.records.push_back(InvalidationRecord {
+            version,
+            causes,
+            changed_keys,
+            top_changed_keys,
+        });
+    }
+
+    /// Traces of the recorded transactions, oldest first.
+    pub(crate) fn traces(&self) -> Vec<UndescribedTrace<K>> {
+        self.records
+            .iter()
+            .map(|record| {
+                let indices: HashMap<&K, usize> = record
+                    .causes
+                    .causes
+                    .keys()
+                    .enumerate()
+                    .map(|(i, k)| (k, i))
+                    .collect();
+                UndescribedTrace {
+                    version: record.version,
+                    keys: record
+                        .causes
+                        .causes
+                        .iter()
+                        .map(|(key, cause)| {
+                            (
+                                key.clone(),
+                                cause.as_ref().and_then(|cause| indices.get(cause).copied()),
+                            )
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn latest_summary(&self) -> Option<UndescribedSummary<K>> {
+        self.records.back().map(|record| UndescribedSummary {
+            version: record.version,
+            changed_keys: record.changed_keys,
+            dirtied_keys: record.causes.causes.len() - record.changed_keys,
+            top_changed_keys: record.top_changed_keys.clone(),
+        })
+    }
+}
+
+/// An `InvalidationTrace` with the keys not yet rendered, so that it can be taken out of the
+/// state holding the graph before looking the keys up.
+pub(crate) struct UndescribedTrace<K> {
+    version: VersionNumber,
+    /// Each key with the index of the key that dirtied it.
+    keys: Vec<(K, Option<usize>)>,
+}
+
+impl<K> UndescribedTrace<K> {
+    pub(crate) fn describe(self, describe: impl Fn(K) -> AnyKey) -> InvalidationTrace {
+        InvalidationTrace {
+            version: self.version.0,
+            keys: self
+                .keys
+                .into_iter()
+                .map(|(key, dirtied_by)| {
+                    let key = describe(key);
+                    InvalidatedKey {
+                        key: key.to_string(),
+                        key_type: key.short_type_name().to_owned(),
+                        dirtied_by,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An `InvalidationSummary` with the keys not yet rendered.
+pub(crate) struct UndescribedSummary<K> {
+    version: VersionNumber,
+    changed_keys: usize,
+    dirtied_keys: usize,
+    top_changed_keys: Vec<(K, usize)>,
+}
+
+impl<K> UndescribedSummary<K> {
+    pub(crate) fn describe(self, describe: impl Fn(K) -> AnyKey) -> InvalidationSummary {
+        InvalidationSummary {
+            version: self.version.0,
+            changed_keys: self.changed_keys,
+            dirtied_keys: self.dirtied_keys,
+            top_changed_keys: self
+                .top_changed_keys
+                .into_iter()
+                .map(|(key, dirtied_keys)| {
+                    let key = describe(key);
+                    ChangedKeySummary {
+                        key: key.to_string(),
+                        key_type: key.short_type_name().to_owned(),
+                        dirtied_keys,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::introspection::graph::AnyKey;
+    use crate::introspection::invalidation::InvalidationCauses;
+    use crate::introspection::invalidation::RecordedInvalidations;
+    use crate::versions::VersionNumber;
+
+    #[test]
+    fn trace_chains_and_summary() {
+        let mut causes = InvalidationCauses::default();
+        causes.changed(1);
+        causes.dirtied(2, &1);
+        causes.dirtied(3, &2);
+        // the first cause wins
+        causes.dirtied(3, &1);
+        causes.changed(10);
+        causes.dirtied(11, &10);
+        causes.dirtied(4, &3);
+
+        let mut recorded = RecordedInvalidations::default();
+        recorded.record(VersionNumber::new(3), causes);
+        let trace = recorded.traces().pop().unwrap().describe(AnyKey::new);
+        assert_eq!(trace.version, 3);
+        let index = trace.keys.iter().position(|k| k.key == "4").unwrap();
+        let chain: Vec<_> = trace
+            .chain(index)
+            .into_iter()
+            .map(|k| k.key.as_str())
+            .collect();
+        assert_eq!(chain, vec!["1", "2", "3", "4"]);
+
+        let summary = recorded.latest_summary().unwrap().describe(AnyKey::new);
+        assert_eq!(summary.changed_keys, 2);
+        assert_eq!(summary.dirtied_keys, 4);
+        assert_eq!(summary.top_changed_keys[0].key, "1");
+        assert_eq!(summary.top_changed_keys[0].dirtied_keys, 3);
+        assert_eq!(summary.top_changed_keys[1].key, "10");
+        assert_eq!(summary.top_changed_keys[1].dirtied_keys, 1);
+    }
+}
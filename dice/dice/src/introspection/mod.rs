@@ -18,6 +18,7 @@ use crate::DiceImplementation;
 
 pub mod graph;
 pub(crate) mod introspect;
+pub mod invalidation;
 
 pub use crate::introspection::introspect::serialize_dense_graph;
 pub use crate::introspection::introspect::serialize_graph;
@@ -35,6 +35,8 @@ use crate::api::projection::ProjectionKey;
 use crate::api::user_data::UserComputationData;
 use crate::api::user_data::UserCycleDetectorGuard;
 use crate::ctx::DiceComputationsImpl;
+use crate::introspection::graph::AnyKey;
+use crate::introspection::invalidation::InvalidationCauses;
 use crate::legacy::cycles::CycleDetector;
 use crate::legacy::incremental::dep_trackers::BothDepTrackers;
 use crate::legacy::incremental::dep_trackers::BothDeps;
@@ -257,10 +259,11 @@ impl DiceComputationsImplLegacy {
             let dice = self.dice.dupe();
             changes.change(
                 k.clone(),
-                Box::new(move |version| {
+                Box::new(move |version, causes: &mut InvalidationCauses<AnyKey>| {
                     debug!(msg = "marking value as changed", version = %version, key = %k);
                     let cache = dice.find_cache::<K>();
-                    cache.dirty(k, version, true);
+                    causes.changed(AnyKey::new(k.clone()));
+                    cache.dirty_traced(k, version, true, causes);
 
                     true
                 }),
@@ -282,10 +285,15 @@ impl DiceComputationsImplLegacy {
             let dice = self.dice.dupe();
             changes.change(
                 k.clone(),
-                Box::new(move |version| {
+                Box::new(move |version, causes: &mut InvalidationCauses<AnyKey>| {
                     let cache = dice.find_cache::<K>();
                     debug!(msg = "marking value as updated", version = %version, key = %k);
-                    cache.update_injected_value(k, version, v)
+                    let key = AnyKey::new(k.clone());
+                    let changed = cache.update_injected_value_traced(k, version, v, causes);
+                    if changed {
+                        causes.changed(key);
+                    }
+                    changed
                 }),
             )
         })
@@ -304,7 +312,7 @@ impl DiceComputationsImplLegacy {
 
         // hold onto the prev version until we get the new one below so we don't increment minor
         // version needlessly.
-        let _prev_v = eval.commit();
+        let _prev_v = eval.commit(&this.dice.invalidations);
 
         this.dice.make_ctx(this.extra)
     }
@@ -324,7 +332,7 @@ impl DiceComputationsImplLegacy {
 
         // hold onto the prev version until we get the new one below so we don't increment minor
         // version needlessly.
-        let _prev_v = eval.commit();
+        let _prev_v = eval.commit(&this.dice.invalidations);
 
         this.dice.make_ctx(ComputationData {
             user_data: Arc::new(extra),
@@ -46,7 +46,6 @@ use parking_lot::RwLockReadGuard;
 use parking_lot::RwLockWriteGuard;
 use sorted_vector_map::SortedVectorMap;
 
-use crate::api::storage_type::StorageType;
 use crate::impls::core::graph::history::HistoryState;
 use crate::introspection::graph::AnyKey;
 use crate::legacy::incremental::dep_trackers::BothDeps;
@@ -781,7 +780,8 @@ where
         key: VersionedGraphKey<K::Key>,
         entry_updater: EntryUpdater<K>,
     ) -> (GraphNode<K>, Option<GraphNode<K>>) {
-        let StorageType::LastN(num_to_keep) = self.storage_properties.storage_type();
+        // legacy DICE never evicts, so evictable keys are stored like any other key
+        let num_to_keep = self.storage_properties.storage_type().num_to_keep();
         // persistent keys, if any changes, are committed at the moment when the version
         // is increased. therefore, it must be the case that the current update for the
         // persistent key is the largest/newest version. it's also the case that they are
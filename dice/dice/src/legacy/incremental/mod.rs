@@ -29,6 +29,8 @@ use std::hash::Hash;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use allocative::Allocative;
 use async_trait::async_trait;
@@ -49,12 +51,15 @@ use tracing::Span;
 
 use crate::api::error::DiceResult;
 use crate::api::events::DiceEvent;
+use crate::api::events::DiceEventListener;
 use crate::api::key::Key;
 use crate::api::projection::DiceProjectionComputations;
 use crate::api::projection::ProjectionKey;
 use crate::api::user_data::UserComputationData;
 use crate::impls::core::graph::history::CellHistory;
+use crate::introspection::graph::AnyKey;
 use crate::introspection::graph::EngineForIntrospection;
+use crate::introspection::invalidation::InvalidationCauses;
 use crate::legacy::ctx::ComputationData;
 use crate::legacy::dice_futures::dice_future::DiceFuture;
 use crate::legacy::dice_futures::dice_task::DiceTask;
@@ -179,8 +184,20 @@ where
     }
 
     /// Dirties the value at K
-    #[instrument(level = "info", skip(self), fields(k = %k, version = %version))]
     pub(crate) fn dirty(&self, k: K::Key, version: VersionNumber, force_dirty: bool) {
+        self.dirty_traced(k, version, force_dirty, &mut InvalidationCauses::default())
+    }
+
+    /// Like `dirty`, also recording in `causes` the rdeps it dirtied and which key dirtied each
+    /// of them.
+    #[instrument(level = "info", skip(self, causes), fields(k = %k, version = %version))]
+    pub(crate) fn dirty_traced(
+        &self,
+        k: K::Key,
+        version: VersionNumber,
+        force_dirty: bool,
+        causes: &mut InvalidationCauses<AnyKey>,
+    ) {
         // It is crucial that we dirty first before updating the rdeps.
         // This is related to the race condition where we invalidate while nodes are being inserted
         // into the graph at the same time:
@@ -205,24 +222,31 @@ where
             // if we actually did something, invalidate the rdeps of occupied entries
             if let Some(node) = node.unpack_occupied() {
                 debug!("dirtying rdeps");
-                Self::invalidate_rdeps(version, GraphNode::occupied(node.dupe()))
+                Self::invalidate_rdeps(version, GraphNode::occupied(node.dupe()), causes)
             }
         }
     }
 
-    fn invalidate_rdeps(version: VersionNumber, invalidated: GraphNode<K>) {
+    /// Invalidates the transitive rdeps of `invalidated`, recording in `causes` the key whose
+    /// invalidation dirtied each of them.
+    fn invalidate_rdeps(
+        version: VersionNumber,
+        invalidated: GraphNode<K>,
+        causes: &mut InvalidationCauses<AnyKey>,
+    ) {
         let mut queue = {
             let metadata = invalidated.read_meta();
             let rdeps = metadata.rdeps.rdeps();
+            let cause = AnyKey::new(invalidated.key().clone());
 
             rdeps
                 .rdeps
                 .iter()
-                .map(|(r, v)| (r.dupe(), *v))
+                .map(|(r, v)| (r.dupe(), *v, cause.clone()))
                 .collect::<Vec<_>>()
         };
 
-        while let Some((rdep, relevant_version)) = queue.pop() {
+        while let Some((rdep, relevant_version, cause)) = queue.pop() {
             if let Some(node) = rdep.0.upgrade() {
                 let mut metadata = node.writable();
 
@@ -238,13 +262,15 @@ where
                     // the version it was dirtied at, it may no longer depend on the current node
                     // so we skip marking it as dirty, and rely on delayed propagation of dirty
                     if metadata.hist.mark_invalidated(version) {
+                        let key = node.key();
+                        causes.dirtied(key.clone(), &cause);
                         queue.extend({
                             let rdeps = metadata.rdeps.rdeps();
 
                             rdeps
                                 .rdeps
                                 .iter()
-                                .map(|(r, v)| (r.dupe(), *v))
+                                .map(|(r, v)| (r.dupe(), *v, key.clone()))
                                 .collect::<Vec<_>>()
                         })
                     }
@@ -269,12 +295,25 @@ where
     }
 
     /// Updates the value at K. Returns whether this injected value actually causes a change
-    #[instrument(level = "info", skip(self, res, ), fields(k = %k, version = %version))]
+    #[cfg(test)]
     pub(crate) fn update_injected_value(
         self: &Arc<Self>,
         k: K::Key,
         version: VersionNumber,
         res: K::Value,
+    ) -> bool {
+        self.update_injected_value_traced(k, version, res, &mut InvalidationCauses::default())
+    }
+
+    /// Like `update_injected_value`, also recording in `causes` the rdeps it dirtied and which
+    /// key dirtied each of them.
+    #[instrument(level = "info", skip(self, res, causes), fields(k = %k, version = %version))]
+    pub(crate) fn update_injected_value_traced(
+        self: &Arc<Self>,
+        k: K::Key,
+        version: VersionNumber,
+        res: K::Value,
+        causes: &mut InvalidationCauses<AnyKey>,
     ) -> bool {
         // It is crucial that we `dirty` first before updating the `rdeps`.
         // See `IncrementalEngine::dirty` below for details.
@@ -290,7 +329,7 @@ where
 
         if let Some(invalidated) = invalidated {
             debug!("dirtying rdeps");
-            Self::invalidate_rdeps(version, invalidated)
+            Self::invalidate_rdeps(version, invalidated, causes)
         }
 
         let is_changed = new.get_history().latest_verified_before(version) == Some(version);
@@ -311,6 +350,7 @@ where
             transaction_ctx.get_minor_version(),
         ) {
             debug!( k = %k ,msg = "found existing entry with matching version in cache. reusing result.",);
+            Self::report_reused(transaction_ctx, &*extra.user_data.tracker);
             DiceFuture::Ready(Some(entry))
         } else {
             let this = self.dupe();
@@ -434,6 +474,7 @@ where
             ) {
                 VersionedGraphResult::Match(entry) => {
                     debug!("found existing entry with matching version in cache. reusing result.");
+                    Self::report_reused(&eval_ctx, &*extra.user_data.tracker);
                     CancellableResult::Ok(entry)
                 }
                 VersionedGraphResult::Mismatch(mismatch) => {
@@ -463,11 +504,18 @@ where
                         DidDepsChange::Changed | DidDepsChange::NoDeps => {
                             debug!("dependencies changed. recomputing...");
                             ev.engine
-                                .compute(&ev.k, eval_ctx, extra, &cancellation)
+                                .compute(
+                                    &ev.k,
+                                    eval_ctx,
+                                    extra,
+                                    Some(mismatch.entry),
+                                    &cancellation,
+                                )
                                 .await
                         }
                         DidDepsChange::NoChange(unchanged_both_deps) => {
                             debug!("dependencies are unchanged, reusing entry");
+                            Self::report_reused(&eval_ctx, &*extra.user_data.tracker);
                             extra.finished_computing_key::<K>(&ev.k, &unchanged_both_deps, true);
                             CancellableResult::Ok(ev.engine.reuse(
                                 ev.k.clone(),
@@ -484,7 +532,7 @@ where
 
                     debug!("dirtied. recomputing...");
                     ev.engine
-                        .compute(&ev.k, eval_ctx, extra, &cancellation)
+                        .compute(&ev.k, eval_ctx, extra, None, &cancellation)
                         .await
                 }
             };
@@ -541,7 +589,7 @@ where
 
     #[instrument(
         level = "debug",
-        skip(self, transaction_ctx, extra, cancellation),
+        skip(self, transaction_ctx, extra, previous, cancellation),
         fields(k = %k, version = %transaction_ctx.get_version()),
     )]
     async fn compute(
@@ -549,6 +597,7 @@ where
         k: &K::Key,
         transaction_ctx: Arc<TransactionCtx>,
         extra: ComputationData,
+        previous: Option<GraphNode<K>>,
         cancellation: &CancellationContext,
     ) -> CancellableResult<GraphNode<K>> {
         let desc = K::key_type_name();
@@ -564,10 +613,12 @@ where
 
         let v = transaction_ctx.get_version();
         let m_v = transaction_ctx.get_minor_version();
+        let eval_ctx = transaction_ctx.dupe();
 
         // TODO(bobyf) these also make good locations where we want to perform instrumentation
         debug!(msg = "running evaluator");
 
+        let start = Instant::now();
         let EvaluationResult {
             value,
             both_deps,
@@ -577,6 +628,7 @@ where
             .storage_properties
             .eval(k, transaction_ctx, cancellation, extra)
             .await;
+        let duration = start.elapsed();
 
         let _guard = match cancellation.try_to_disable_cancellation() {
             Some(g) => g,
@@ -596,6 +648,11 @@ where
             both_deps,
         );
 
+        // the cache keeps the previous node when the new value is equal to it
+        let early_cutoff =
+            previous.map_or(false, |previous| std::ptr::eq(entry.val(), previous.val()));
+        Self::report_computed(&eval_ctx, &*tracker, duration, early_cutoff);
+
         // This feels like it should move in the scopeguard above to notify the cycle detector on
         // cancellation, but our cycle detector does not currently support being notified multiple
         // times about the same key, so we don't.
@@ -644,6 +701,7 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
             VersionedGraphKeyRef::new(transaction_ctx.get_version(), k),
             transaction_ctx.get_minor_version(),
         ) {
+            Self::report_reused(transaction_ctx, &*extra.user_data.tracker);
             entry.dupe()
         } else {
             enum Val<P: ProjectionKey> {
@@ -708,13 +766,17 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
             VersionedGraphKeyRef::new(transaction_ctx.get_version(), k),
             transaction_ctx.get_minor_version(),
         ) {
-            VersionedGraphResult::Match(entry) => entry.dupe(),
+            VersionedGraphResult::Match(entry) => {
+                Self::report_reused(transaction_ctx, &*extra.user_data.tracker);
+                entry.dupe()
+            }
             VersionedGraphResult::Mismatch(mismatch) => {
                 // Async key evaluation calls `compute_whether_dependencies_changed`,
                 // but we cannot do that because the function is synchronous.
                 // So we do simpler check here: if `derive_from` versions are compatible with
                 // cached node versions, we reuse the cached node and recompute otherwise.
                 if !Self::check_whether_opaque_value_changed(derive_from, &mismatch) {
+                    Self::report_reused(transaction_ctx, &*extra.user_data.tracker);
                     self.reuse(
                         k.clone(),
                         transaction_ctx,
@@ -792,7 +854,9 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
             data: &dice.data,
         };
 
+        let start = Instant::now();
         let value = key.k.compute(derive_from, &ctx);
+        let duration = start.elapsed();
 
         let (entry, _old) = self.versioned_cache.update_computed_value(
             VersionedGraphKey::new(transaction_ctx.get_version(), key.clone()),
@@ -801,6 +865,10 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
             derive_from_as_deps,
         );
 
+        // projections are only recomputed when the value they derive from changed, so we don't
+        // track the previous node to detect early cutoffs
+        Self::report_computed(transaction_ctx, &*extra.user_data.tracker, duration, false);
+
         entry
     }
 
@@ -817,6 +885,7 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
         ) {
             VersionedGraphResult::Match(entry) => {
                 debug!("found existing entry with matching version in cache. reusing result.");
+                Self::report_reused(transaction_ctx, &*extra.user_data.tracker);
                 Ok(entry)
             }
             VersionedGraphResult::Mismatch(mismatch) => {
@@ -840,6 +909,7 @@ impl<P: ProjectionKey> IncrementalEngine<ProjectionKeyProperties<P>> {
                     DidDepsChange::NoChange(unchanged_both_deps) => {
                         debug!("dependencies are unchanged, reusing entry");
 
+                        Self::report_reused(&eval_ctx, &*extra.user_data.tracker);
                         Ok(self.reuse(key, &eval_ctx, mismatch.entry, unchanged_both_deps))
                     }
                 }
@@ -956,6 +1026,35 @@ impl<K: IncrementalComputeProperties> IncrementalEngine<K> {
         }
     }
 
+    /// Reports a value that was reused without recomputing it to the Dice metrics and the user's
+    /// event listener.
+    fn report_reused(transaction_ctx: &TransactionCtx, tracker: &dyn DiceEventListener) {
+        let key_type = K::key_type_name();
+        if let Some(dice) = transaction_ctx.dice() {
+            dice.key_type_metrics.reused(key_type);
+        }
+        tracker.event(DiceEvent::Reused { key_type });
+    }
+
+    /// Reports a computed value to the Dice metrics and the user's event listener.
+    fn report_computed(
+        transaction_ctx: &TransactionCtx,
+        tracker: &dyn DiceEventListener,
+        duration: Duration,
+        early_cutoff: bool,
+    ) {
+        let key_type = K::key_type_name();
+        if let Some(dice) = transaction_ctx.dice() {
+            dice.key_type_metrics
+                .computed(key_type, duration, early_cutoff);
+        }
+        tracker.event(DiceEvent::Computed {
+            key_type,
+            duration,
+            early_cutoff,
+        });
+    }
+
     #[instrument(
     level = "debug",
     skip(self, transaction_ctx, value_to_reuse, both_deps),
@@ -19,6 +19,9 @@ use parking_lot::MutexGuard;
 use crate::api::error::DiceError;
 use crate::api::error::DiceResult;
 use crate::api::key::Key;
+use crate::introspection::graph::AnyKey;
+use crate::introspection::invalidation::InvalidationCauses;
+use crate::introspection::invalidation::RecordedInvalidations;
 use crate::legacy::incremental::versions::MinorVersion;
 use crate::legacy::incremental::versions::VersionForWrites;
 use crate::legacy::incremental::versions::VersionGuard;
@@ -66,7 +69,7 @@ pub(crate) struct TransactionCtx {
     version_guard: VersionGuard,
     version_for_writes: VersionForWrites,
     changes: Mutex<Changes>,
-    _active_transaction_count_guard: ActiveTransactionCountGuard,
+    active_transaction_count_guard: ActiveTransactionCountGuard,
 }
 
 impl TransactionCtx {
@@ -80,7 +83,7 @@ impl TransactionCtx {
             version_guard,
             version_for_writes,
             changes: Mutex::new(changes),
-            _active_transaction_count_guard: active_transaction_count_guard,
+            active_transaction_count_guard,
         }
     }
 
@@ -96,6 +99,11 @@ impl TransactionCtx {
         self.version_for_writes.get()
     }
 
+    /// The Dice this transaction belongs to, unless it was already dropped.
+    pub(crate) fn dice(&self) -> Option<Arc<DiceLegacy>> {
+        self.active_transaction_count_guard.dice.upgrade()
+    }
+
     pub(crate) fn changes(&self) -> MutexGuard<'_, Changes> {
         self.changes.lock()
     }
@@ -110,11 +118,16 @@ impl TransactionCtx {
             ),
             version_for_writes: VersionForWrites::testing_new(v),
             changes: Mutex::new(Changes::new()),
-            _active_transaction_count_guard: ActiveTransactionCountGuard::testing_new(),
+            active_transaction_count_guard: ActiveTransactionCountGuard::testing_new(),
         }
     }
 
-    pub(crate) fn commit(self) -> VersionGuard {
+    /// Applies the changes, recording their invalidations in `invalidations` if anything changed.
+    pub(crate) fn commit(
+        self,
+        invalidations: &Mutex<RecordedInvalidations<AnyKey>>,
+    ) -> VersionGuard {
+        let mut causes = InvalidationCauses::default();
         let is_changed = {
             let mut changed = self.changes();
             let version_for_writes = self.get_version_for_writes();
@@ -127,11 +140,14 @@ impl TransactionCtx {
             );
 
             changed.ops().drain(..).fold(false, |has_change, change| {
-                change(version_for_writes) || has_change
+                change(version_for_writes, &mut causes) || has_change
             })
         };
 
         if is_changed {
+            invalidations
+                .lock()
+                .record(self.get_version_for_writes(), causes);
             debug!(
                 old_version = %self.version_guard.version,
                 version_for_writes = %self.get_version_for_writes(),
@@ -151,7 +167,7 @@ pub(crate) struct Changes {
     #[allocative(skip)] // TODO(nga): measure.
     keys: Map<dyn Any + Sync + Send>,
     #[allocative(skip)] // TODO(nga): measure.
-    changes: Vec<Box<dyn FnOnce(VersionNumber) -> bool + Send>>,
+    changes: Vec<Box<dyn FnOnce(VersionNumber, &mut InvalidationCauses<AnyKey>) -> bool + Send>>,
 }
 
 impl Changes {
@@ -165,7 +181,7 @@ impl Changes {
     pub(crate) fn change<K: Key>(
         &mut self,
         key: K,
-        change: Box<dyn FnOnce(VersionNumber) -> bool + Send>,
+        change: Box<dyn FnOnce(VersionNumber, &mut InvalidationCauses<AnyKey>) -> bool + Send>,
     ) -> DiceResult<()> {
         let map = self
             .keys
@@ -179,7 +195,10 @@ impl Changes {
         }
     }
 
-    pub fn ops(&mut self) -> &mut Vec<Box<dyn FnOnce(VersionNumber) -> bool + Send>> {
+    pub fn ops(
+        &mut self,
+    ) -> &mut Vec<Box<dyn FnOnce(VersionNumber, &mut InvalidationCauses<AnyKey>) -> bool + Send>>
+    {
         &mut self.changes
     }
 }
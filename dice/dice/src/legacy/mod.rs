@@ -28,6 +28,7 @@ use incremental::IncrementalEngine;
 use key::StoragePropertiesForKey;
 use map::DiceMap;
 use more_futures::cancellation::CancellationContext;
+use parking_lot::Mutex;
 use parking_lot::RwLock;
 use projection::ProjectionKeyProperties;
 use tokio::sync::watch;
@@ -42,9 +43,14 @@ use crate::api::transaction::DiceTransactionUpdater;
 use crate::api::user_data::UserComputationData;
 use crate::api::which::WhichSpawner;
 use crate::ctx::DiceComputationsImpl;
+use crate::introspection::graph::AnyKey;
+use crate::introspection::invalidation::InvalidationSummary;
+use crate::introspection::invalidation::InvalidationTrace;
+use crate::introspection::invalidation::RecordedInvalidations;
 use crate::legacy::ctx::ComputationData;
 use crate::legacy::ctx::DiceComputationsImplLegacy;
 use crate::legacy::incremental::dep_trackers::BothDeps;
+use crate::metrics::KeyTypeMetricsRecorder;
 use crate::metrics::Metrics;
 use crate::transaction_update::DiceTransactionUpdaterImpl;
 
@@ -74,6 +80,9 @@ pub(crate) struct DiceLegacy {
     #[allocative(skip)]
     active_versions_observer: watch::Receiver<usize>,
     which_spawner: WhichSpawner,
+    #[allocative(skip)]
+    pub(crate) invalidations: Mutex<RecordedInvalidations<AnyKey>>,
+    pub(crate) key_type_metrics: KeyTypeMetricsRecorder,
 }
 
 impl Debug for DiceLegacy {
@@ -141,6 +150,8 @@ impl DiceLegacy {
             which_spawner,
             active_transaction_count: AtomicU32::new(0),
             active_versions_observer,
+            invalidations: Mutex::new(RecordedInvalidations::default()),
+            key_type_metrics: KeyTypeMetricsRecorder::default(),
         })
     }
 
@@ -217,6 +228,19 @@ impl DiceLegacy {
         &self.detect_cycles
     }
 
+    pub(crate) fn invalidation_traces(&self) -> Vec<InvalidationTrace> {
+        let traces = self.invalidations.lock().traces();
+        traces
+            .into_iter()
+            .map(|trace| trace.describe(|k| k))
+            .collect()
+    }
+
+    pub(crate) fn latest_invalidation_summary(&self) -> Option<InvalidationSummary> {
+        let summary = self.invalidations.lock().latest_summary();
+        summary.map(|summary| summary.describe(|k| k))
+    }
+
     pub fn metrics(&self) -> Metrics {
         let dice_map = self.map.read();
         Metrics {
@@ -225,6 +249,7 @@ impl DiceLegacy {
             active_transaction_count: self
                 .active_transaction_count
                 .load(std::sync::atomic::Ordering::SeqCst),
+            key_types: self.key_type_metrics.collect(),
         }
     }
 
@@ -220,6 +220,7 @@ mod versions;
 use std::fmt::Debug;
 use std::io::Write;
 use std::sync::Arc;
+use std::time::Duration;
 
 use allocative::Allocative;
 pub(crate) use fnv::FnvHashMap as HashMap;
@@ -253,8 +254,11 @@ pub use crate::api::events::DiceEventListener;
 pub use crate::api::injected::InjectedKey;
 pub use crate::api::key::Key;
 pub use crate::api::opaque::OpaqueValue;
+pub use crate::api::persistence::PersistentKey;
+pub use crate::api::persistence::PersistentKeys;
 pub use crate::api::projection::DiceProjectionComputations;
 pub use crate::api::projection::ProjectionKey;
+pub use crate::api::storage_type::StorageType;
 pub use crate::api::transaction::DiceEquality;
 pub use crate::api::transaction::DiceTransaction;
 pub use crate::api::transaction::DiceTransactionUpdater;
@@ -265,11 +269,16 @@ pub use crate::api::which::WhichDice;
 pub use crate::api::which::WhichSpawner;
 use crate::impls::dice::DiceModern;
 use crate::impls::dice::DiceModernDataBuilder;
+use crate::impls::dice::EvictionError;
+use crate::impls::persistence::PersistenceError;
 use crate::introspection::graph::GraphIntrospectable;
+use crate::introspection::invalidation::InvalidationSummary;
+use crate::introspection::invalidation::InvalidationTrace;
 use crate::introspection::serialize_dense_graph;
 use crate::introspection::serialize_graph;
 use crate::legacy::DiceLegacy;
 use crate::legacy::DiceLegacyDataBuilder;
+pub use crate::metrics::KeyTypeMetrics;
 use crate::transaction_update::DiceTransactionUpdaterImpl;
 
 #[derive(Allocative, Debug)]
@@ -354,6 +363,41 @@ impl DiceImplementation {
             DiceImplementation::Modern(dice) => dice.is_idle().await,
         }
     }
+
+    pub async fn snapshot(&self, keys: &PersistentKeys) -> anyhow::Result<Vec<u8>> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(PersistenceError::Legacy.into()),
+            DiceImplementation::Modern(dice) => dice.snapshot(keys).await,
+        }
+    }
+
+    pub async fn restore(&self, keys: &PersistentKeys, snapshot: &[u8]) -> anyhow::Result<()> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(PersistenceError::Legacy.into()),
+            DiceImplementation::Modern(dice) => dice.restore(keys, snapshot).await,
+        }
+    }
+
+    pub async fn invalidation_traces(&self) -> Vec<InvalidationTrace> {
+        match self {
+            DiceImplementation::Legacy(dice) => dice.invalidation_traces(),
+            DiceImplementation::Modern(dice) => dice.invalidation_traces().await,
+        }
+    }
+
+    pub async fn latest_invalidation_summary(&self) -> Option<InvalidationSummary> {
+        match self {
+            DiceImplementation::Legacy(dice) => dice.latest_invalidation_summary(),
+            DiceImplementation::Modern(dice) => dice.latest_invalidation_summary().await,
+        }
+    }
+
+    pub async fn evict(&self, not_requested_for: Duration) -> anyhow::Result<usize> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(EvictionError::Legacy.into()),
+            DiceImplementation::Modern(dice) => Ok(dice.evict(not_requested_for).await),
+        }
+    }
 }
 
 pub(crate) enum DiceDataBuilderImpl {
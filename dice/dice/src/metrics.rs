@@ -7,6 +7,12 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use allocative::Allocative;
+use dashmap::DashMap;
+
 /// Dice metrics.
 #[derive(Debug)]
 pub struct Metrics {
@@ -14,4 +20,115 @@ pub struct Metrics {
     /// The number of keys currently active in the per transaction cache
     pub currently_active_key_count: usize,
     pub active_transaction_count: u32,
+    /// Compute statistics since Dice was created, per `Key::key_type_name`.
+    pub key_types: BTreeMap<&'static str, KeyTypeMetrics>,
+}
+
+/// Compute statistics for all the keys of a single key type.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KeyTypeMetrics {
+    /// Number of values computed by running the key's compute function.
+    pub computes: u64,
+    /// Number of times a previously computed value was reused without recomputing it, either
+    /// because it was already up to date or because none of its deps changed.
+    pub cache_hits: u64,
+    /// Number of computes whose value was equal to the previously stored value, so that its
+    /// dependents did not need to be recomputed.
+    pub early_cutoffs: u64,
+    /// Total wall time spent in computes. This includes the time waiting on dependencies.
+    pub total_compute_time: Duration,
+    /// The longest wall time of a single compute.
+    pub max_compute_time: Duration,
+}
+
+impl KeyTypeMetrics {
+    fn record_compute(&mut self, duration: Duration, early_cutoff: bool) {
+        self.computes += 1;
+        if early_cutoff {
+            self.early_cutoffs += 1;
+        }
+        self.total_compute_time += duration;
+        self.max_compute_time = self.max_compute_time.max(duration);
+    }
+
+    /// The metrics accumulated since `earlier`, which must have been taken from the same Dice.
+    /// The max compute time cannot be subtracted, so this keeps the overall max.
+    pub fn since(&self, earlier: &KeyTypeMetrics) -> KeyTypeMetrics {
+        KeyTypeMetrics {
+            computes: self.computes.saturating_sub(earlier.computes),
+            cache_hits: self.cache_hits.saturating_sub(earlier.cache_hits),
+            early_cutoffs: self.early_cutoffs.saturating_sub(earlier.early_cutoffs),
+            total_compute_time: self
+                .total_compute_time
+                .saturating_sub(earlier.total_compute_time),
+            max_compute_time: self.max_compute_time,
+        }
+    }
+}
+
+/// Accumulates the `KeyTypeMetrics` of a Dice instance. Recording happens concurrently from all
+/// the computing tasks.
+#[derive(Allocative, Default)]
+pub(crate) struct KeyTypeMetricsRecorder {
+    #[allocative(skip)]
+    key_types: DashMap<&'static str, KeyTypeMetrics>,
+}
+
+impl KeyTypeMetricsRecorder {
+    pub(crate) fn computed(&self, key_type: &'static str, duration: Duration, early_cutoff: bool) {
+        self.key_types
+            .entry(key_type)
+            .or_default()
+            .record_compute(duration, early_cutoff);
+    }
+
+    pub(crate) fn reused(&self, key_type: &'static str) {
+        self.key_types.entry(key_type).or_default().cache_hits += 1;
+    }
+
+    pub(crate) fn collect(&self) -> BTreeMap<&'static str, KeyTypeMetrics> {
+        self.key_types
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::metrics::KeyTypeMetricsRecorder;
+
+    #[test]
+    fn records_per_key_type() {
+        let recorder = KeyTypeMetricsRecorder::default();
+        recorder.computed("Foo", Duration::from_millis(3), false);
+        recorder.computed("Foo", Duration::from_millis(5), true);
+        recorder.reused("Foo");
+        recorder.reused("Bar");
+
+        let metrics = recorder.collect();
+        assert_eq!(metrics.len(), 2);
+
+        let foo = &metrics["Foo"];
+        assert_eq!(foo.computes, 2);
+        assert_eq!(foo.cache_hits, 1);
+        assert_eq!(foo.early_cutoffs, 1);
+        assert_eq!(foo.total_compute_time, Duration::from_millis(8));
+        assert_eq!(foo.max_compute_time, Duration::from_millis(5));
+
+        let bar = &metrics["Bar"];
+        assert_eq!(bar.computes, 0);
+        assert_eq!(bar.cache_hits, 1);
+
+        let later = {
+            recorder.computed("Foo", Duration::from_millis(1), false);
+            recorder.collect()
+        };
+        let delta = later["Foo"].since(foo);
+        assert_eq!(delta.computes, 1);
+        assert_eq!(delta.cache_hits, 0);
+        assert_eq!(delta.total_compute_time, Duration::from_millis(1));
+    }
 }
@@ -60,6 +60,29 @@ impl DapAdapterClient for Client {
             text: None,
         });
     }
+
+    fn event_stopped_on_exception(&self, description: &str) {
+        self.event_stopped(StoppedEventBody {
+            reason: "exception".to_owned(),
+            thread_id: Some(0),
+            description: Some("Paused on error".to_owned()),
+            all_threads_stopped: Some(true),
+            preserve_focus_hint: None,
+            text: Some(description.to_owned()),
+        });
+    }
+
+    fn event_output(&self, output: &str) {
+        self.event_output(OutputEventBody {
+            output: format!("{}\n", output),
+            category: Some("console".to_owned()),
+            column: None,
+            data: None,
+            line: None,
+            source: None,
+            variables_reference: None,
+        });
+    }
 }
 
 fn get_ast(source: &str) -> anyhow::Result<Arc<AstModule>> {
@@ -131,9 +154,8 @@ impl DebugServer for Backend {
         Ok(resolved.to_response())
     }
 
-    fn set_exception_breakpoints(&self, _: SetExceptionBreakpointsArguments) -> anyhow::Result<()> {
-        // We just assume that break on error is always useful
-        Ok(())
+    fn set_exception_breakpoints(&self, x: SetExceptionBreakpointsArguments) -> anyhow::Result<()> {
+        self.adapter.set_exception_breakpoints(&x.filters)
     }
 
     fn launch(&self, _: LaunchRequestArguments, args: Map<String, Value>) -> anyhow::Result<()> {
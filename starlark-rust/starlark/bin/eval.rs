@@ -22,7 +22,9 @@ use std::io;
 use std::iter;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use dupe::Dupe;
 use itertools::Either;
 use lsp_types::Diagnostic;
 use lsp_types::Url;
@@ -30,6 +32,7 @@ use starlark::docs::get_registered_starlark_docs;
 use starlark::docs::render_docs_as_code;
 use starlark::docs::Doc;
 use starlark::docs::DocItem;
+use starlark::docs::DocModule;
 use starlark::environment::FrozenModule;
 use starlark::environment::Globals;
 use starlark::environment::Module;
@@ -66,6 +69,8 @@ pub(crate) struct Context {
     pub(crate) module: Option<Module>,
     pub(crate) builtin_docs: HashMap<LspUrl, String>,
     pub(crate) builtin_symbols: HashMap<String, LspUrl>,
+    /// Documentation for the globals and the prelude.
+    pub(crate) environment: Arc<DocModule>,
 }
 
 /// The outcome of evaluating (checking, parsing or running) given starlark code.
@@ -126,6 +131,7 @@ impl Context {
             .into_iter()
             .map(|(u, ds)| (u, render_docs_as_code(&ds)))
             .collect();
+        let environment = Arc::new(Self::environment(&globals, &prelude));
 
         Ok(Self {
             mode,
@@ -134,9 +140,21 @@ impl Context {
             module,
             builtin_docs,
             builtin_symbols,
+            environment,
         })
     }
 
+    fn environment(globals: &Globals, prelude: &[FrozenModule]) -> DocModule {
+        let mut environment = match globals.documentation() {
+            DocItem::Module(module) => module,
+            _ => DocModule::default(),
+        };
+        for modu in prelude {
+            environment.members.extend(modu.documentation().members);
+        }
+        environment
+    }
+
     fn url_for_doc(doc: &Doc) -> LspUrl {
         let url = match &doc.item {
             DocItem::Module(_) => Url::parse("starlark:/native/builtins.bzl").unwrap(),
@@ -345,6 +363,10 @@ impl LspContext for Context {
     ) -> anyhow::Result<Option<LspUrl>> {
         Ok(self.builtin_symbols.get(symbol).cloned())
     }
+
+    fn get_environment(&self, _uri: &LspUrl) -> Arc<DocModule> {
+        self.environment.dupe()
+    }
 }
 
 pub(crate) fn globals() -> Globals {
@@ -216,6 +216,14 @@ impl LspModule {
         Self { ast }
     }
 
+    /// Convert a zero based line and column into a position within the module.
+    ///
+    /// Returns `None` if the line is past the end of the module.
+    pub(crate) fn position(&self, line: u32, col: u32) -> Option<Pos> {
+        let line_span = self.ast.codemap.line_span_opt(line as usize)?;
+        Some(std::cmp::min(line_span.begin() + col, line_span.end()))
+    }
+
     /// Attempts to find the location where a symbol is defined in the module.
     ///
     /// `line` and `col` are zero based indexes of a location of the symbol to attempt to lookup.
@@ -229,14 +237,13 @@ impl LspModule {
         //            LSPModule doesn't need to reparse anything.
 
         let scope = scope(&self.ast);
-        let line_span = match self.ast.codemap.line_span_opt(line as usize) {
+        let current_pos = match self.position(line, col) {
             None => {
                 // The document got edited to add new lines, just bail out
                 return Definition::Identifier(IdentifierDefinition::NotFound);
             }
-            Some(line_span) => line_span,
+            Some(pos) => pos,
         };
-        let current_pos = std::cmp::min(line_span.begin() + col, line_span.end());
 
         // Finalize the results after recursing down from and back up to the the top level scope.
         match Self::find_definition_in_scope(&scope, current_pos) {
@@ -65,6 +65,7 @@ fn duplicate_dictionary_key(module: &AstModule, res: &mut Vec<LintT<Dubious>>) {
         BigInt(&'a BigInt),
         Float(u64),
         String(&'a str),
+        Bytes(&'a [u8]),
         Identifier(&'a str),
     }
 
@@ -88,6 +89,7 @@ fn duplicate_dictionary_key(module: &AstModule, res: &mut Vec<LintT<Dubious>>) {
                     }
                 }
                 AstLiteral::String(x) => Some((Key::String(&x.node), x.span)),
+                AstLiteral::Bytes(x) => Some((Key::Bytes(&x.node), x.span)),
             },
             Expr::Identifier(x, ()) => Some((Key::Identifier(&x.node), x.span)),
             _ => None,
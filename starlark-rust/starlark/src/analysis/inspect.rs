@@ -0,0 +1,289 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Documentation for symbols defined in a module, extracted from the AST without
+//! evaluating it.
+
+use starlark_map::small_map::SmallMap;
+
+use crate::analysis::definition::LspModule;
+use crate::codemap::Span;
+use crate::docs::DocFunction;
+use crate::docs::DocItem;
+use crate::docs::DocMember;
+use crate::docs::DocObject;
+use crate::docs::DocParam;
+use crate::docs::DocProperty;
+use crate::docs::DocString;
+use crate::docs::DocStringKind;
+use crate::docs::DocType;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AssignP;
+use crate::syntax::ast::AstArgument;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstNoPayload;
+use crate::syntax::ast::AstParameter;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::DefP;
+use crate::syntax::ast::Expr;
+use crate::syntax::ast::ParameterP;
+use crate::syntax::ast::Stmt;
+
+fn doc_type(x: Option<&AstExpr>) -> Option<DocType> {
+    x.map(|x| DocType {
+        raw_type: x.node.to_string(),
+    })
+}
+
+fn doc_params(params: &[AstParameter]) -> Vec<DocParam> {
+    params
+        .iter()
+        .map(|p| match &p.node {
+            ParameterP::Normal(name, typ) => DocParam::Arg {
+                name: name.0.clone(),
+                docs: None,
+                typ: doc_type(typ.as_deref()),
+                default_value: None,
+            },
+            ParameterP::WithDefaultValue(name, typ, default) => DocParam::Arg {
+                name: name.0.clone(),
+                docs: None,
+                typ: doc_type(typ.as_deref()),
+                default_value: Some(default.node.to_string()),
+            },
+            ParameterP::NoArgs => DocParam::NoArgs,
+            ParameterP::Args(name, typ) => DocParam::Args {
+                name: format!("*{}", name.0),
+                docs: None,
+                typ: doc_type(typ.as_deref()),
+            },
+            ParameterP::KwArgs(name, typ) => DocParam::Kwargs {
+                name: format!("**{}", name.0),
+                docs: None,
+                typ: doc_type(typ.as_deref()),
+            },
+        })
+        .collect()
+}
+
+fn doc_lambda(x: &AstExpr) -> Option<DocFunction> {
+    match &**x {
+        Expr::Lambda(lambda) => Some(DocFunction::from_docstring(
+            DocStringKind::Starlark,
+            doc_params(&lambda.params),
+            None,
+            None,
+        )),
+        _ => None,
+    }
+}
+
+/// Build the documentation for a `def` from its signature and docstring.
+fn doc_function(def: &DefP<AstNoPayload>) -> DocFunction {
+    DocFunction::from_docstring(
+        DocStringKind::Starlark,
+        doc_params(&def.params),
+        doc_type(def.return_type.as_deref()),
+        DocString::extract_raw_starlark_docstring(&def.body).as_deref(),
+    )
+}
+
+impl LspModule {
+    /// Find the `def` or assignment statement that binds the symbol at `span`.
+    fn find_binding_statement(&self, span: Span) -> Option<&AstStmt> {
+        fn walk<'a>(span: Span, stmt: &'a AstStmt, res: &mut Option<&'a AstStmt>) {
+            if res.is_some() {
+                return;
+            }
+            match &**stmt {
+                Stmt::Def(def) if def.name.span == span => *res = Some(stmt),
+                Stmt::Assign(lhs, _) => match &**lhs {
+                    AssignP::Identifier(name) if name.span == span => *res = Some(stmt),
+                    _ => {}
+                },
+                _ => stmt.visit_stmt(|x| walk(span, x, res)),
+            }
+        }
+
+        let mut res = None;
+        walk(span, &self.ast.statement, &mut res);
+        res
+    }
+
+    /// Get the documentation for the symbol whose binding is at `span`.
+    ///
+    /// Functions are documented from their signature and docstring, and structs (i.e.
+    /// a call to `struct()`) list their fields. Any other assignment is reported as a
+    /// property, with a type if the assignment was annotated with one.
+    pub(crate) fn find_docs_for_binding(&self, span: Span) -> Option<DocItem> {
+        match &**self.find_binding_statement(span)? {
+            Stmt::Def(def) => Some(DocItem::Function(doc_function(def))),
+            Stmt::Assign(_, ty_rhs) => {
+                let (ty, rhs) = &**ty_rhs;
+                let docs = match &**rhs {
+                    Expr::Call(f, args) => match &f.node {
+                        Expr::Identifier(f, _) if f.node == "struct" => {
+                            Some(DocItem::Object(self.docs_for_struct(args)))
+                        }
+                        _ => None,
+                    },
+                    _ => doc_lambda(rhs).map(DocItem::Function),
+                };
+                Some(docs.unwrap_or_else(|| {
+                    DocItem::Property(DocProperty {
+                        docs: None,
+                        typ: doc_type(ty.as_ref()),
+                    })
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    fn docs_for_struct(&self, args: &[AstArgument]) -> DocObject {
+        let mut members = SmallMap::new();
+        for arg in args {
+            if let ArgumentP::Named(name, value) = &**arg {
+                members.insert(name.node.clone(), self.docs_for_member(value));
+            }
+        }
+        DocObject {
+            docs: None,
+            members,
+        }
+    }
+
+    /// Struct members are commonly defined as `foo = _foo`, so look through the identifier
+    /// to find the function it refers to. Only functions are looked through, so that
+    /// self-referential structs cannot cause infinite recursion.
+    fn docs_for_member(&self, x: &AstExpr) -> DocMember {
+        let function = match &**x {
+            Expr::Identifier(name, _) => self
+                .find_top_level_binding(&name.node)
+                .and_then(|binding| self.find_binding_statement(binding.span))
+                .and_then(|stmt| match &**stmt {
+                    Stmt::Def(def) => Some(doc_function(def)),
+                    Stmt::Assign(_, ty_rhs) => doc_lambda(&ty_rhs.1),
+                    _ => None,
+                }),
+            _ => doc_lambda(x),
+        };
+        match function {
+            Some(f) => DocMember::Function(f),
+            None => DocMember::Property(DocProperty::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+
+    fn module(program: &str) -> LspModule {
+        LspModule::new(AstModule::parse("foo.star", dedent(program), &Dialect::Extended).unwrap())
+    }
+
+    fn symbol_docs(module: &LspModule, name: &str) -> Option<DocItem> {
+        let binding = module.find_top_level_binding(name)?;
+        module.find_docs_for_binding(binding.span)
+    }
+
+    #[test]
+    fn finds_function_docs() {
+        let module = module(
+            r#"
+            def foo(a: "string", b = 1, *args, **kwargs) -> "string":
+                """ Summary for foo.
+
+                Args:
+                    a: The first argument.
+                """
+                return a
+            "#,
+        );
+        let docs = match symbol_docs(&module, "foo") {
+            Some(DocItem::Function(f)) => f,
+            x => panic!("Expected function docs, got {:?}", x),
+        };
+        assert_eq!("Summary for foo.", docs.docs.unwrap().summary);
+        assert_eq!(
+            Some("\"string\"".to_owned()),
+            docs.ret.typ.map(|t| t.raw_type)
+        );
+        assert_eq!(
+            vec![
+                DocParam::Arg {
+                    name: "a".to_owned(),
+                    docs: DocString::from_docstring(DocStringKind::Starlark, "The first argument."),
+                    typ: Some(DocType {
+                        raw_type: "\"string\"".to_owned()
+                    }),
+                    default_value: None,
+                },
+                DocParam::Arg {
+                    name: "b".to_owned(),
+                    docs: None,
+                    typ: None,
+                    default_value: Some("1".to_owned()),
+                },
+                DocParam::Args {
+                    name: "*args".to_owned(),
+                    docs: None,
+                    typ: None,
+                },
+                DocParam::Kwargs {
+                    name: "**kwargs".to_owned(),
+                    docs: None,
+                    typ: None,
+                },
+            ],
+            docs.params
+        );
+    }
+
+    #[test]
+    fn finds_struct_members() {
+        let module = module(
+            r#"
+            def _bar(x):
+                """ Bar docs """
+                pass
+
+            foo = struct(bar = _bar, baz = 1)
+            "#,
+        );
+        let docs = match symbol_docs(&module, "foo") {
+            Some(DocItem::Object(o)) => o,
+            x => panic!("Expected object docs, got {:?}", x),
+        };
+        assert_eq!(
+            vec!["bar", "baz"],
+            docs.members.keys().map(|k| k.as_str()).collect::<Vec<_>>()
+        );
+        match docs.members.get("bar") {
+            Some(DocMember::Function(f)) => {
+                assert_eq!("Bar docs", f.docs.as_ref().unwrap().summary)
+            }
+            x => panic!("Expected function member, got {:?}", x),
+        }
+        assert!(symbol_docs(&module, "missing").is_none());
+    }
+}
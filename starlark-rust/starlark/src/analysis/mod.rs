@@ -31,8 +31,11 @@ pub(crate) mod exported;
 mod find_call_name;
 mod flow;
 mod incompatible;
+mod inspect;
 mod names;
 mod performance;
+pub(crate) mod quick_fix;
+pub(crate) mod references;
 mod types;
 mod underscore;
 
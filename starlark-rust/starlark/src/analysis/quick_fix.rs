@@ -0,0 +1,193 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Mechanical fixes for some of the issues reported by the linter.
+
+use crate::analysis::definition::LspModule;
+use crate::codemap::ResolvedSpan;
+use crate::codemap::Span;
+use crate::syntax::ast::AssignP;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::Stmt;
+
+/// A fix for a lint, as a set of replacements within the module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuickFix {
+    /// A short description of the fix, suitable for showing in a menu.
+    pub(crate) title: String,
+    /// The text to replace at each location. Locations do not overlap.
+    pub(crate) edits: Vec<(ResolvedSpan, String)>,
+}
+
+impl LspModule {
+    /// The span of a whole statement, from the start of the line it begins on to the end of the
+    /// line it ends on, including the line terminator.
+    fn statement_lines(&self, stmt: &AstStmt) -> ResolvedSpan {
+        let codemap = &self.ast.codemap;
+        let begin = codemap
+            .line_span(codemap.find_line(stmt.span.begin()))
+            .begin();
+        // Statements with a body (e.g. `def`) already end after the newline of their last line.
+        let end = if codemap.source()[..stmt.span.end().get() as usize].ends_with('\n') {
+            stmt.span.end()
+        } else {
+            codemap.line_span(codemap.find_line(stmt.span.end())).end()
+        };
+        codemap.resolve_span(Span::new(begin, end))
+    }
+
+    /// Find a fix for the lint named `short_name` (e.g. `unused-load`) that was reported at `span`.
+    ///
+    /// Returns `None` if the lint cannot be fixed mechanically.
+    pub(crate) fn find_quick_fix(&self, short_name: &str, span: ResolvedSpan) -> Option<QuickFix> {
+        let resolve = |x: Span| self.ast.codemap.resolve_span(x);
+        match short_name {
+            "unused-load" => self
+                .ast
+                .top_level_statements()
+                .into_iter()
+                .find_map(|stmt| {
+                    let load = match &**stmt {
+                        Stmt::Load(load) => load,
+                        _ => return None,
+                    };
+                    let i = load
+                        .args
+                        .iter()
+                        .position(|(name, _)| resolve(name.span) == span)?;
+                    let name = &load.args[i].0.0;
+                    let arg_span = |i: usize| {
+                        let (name, symbol) = &load.args[i];
+                        name.span.merge(symbol.span)
+                    };
+                    let removed = if load.args.len() == 1 {
+                        self.statement_lines(stmt)
+                    } else if i == 0 {
+                        resolve(Span::new(arg_span(0).begin(), arg_span(1).begin()))
+                    } else {
+                        resolve(Span::new(arg_span(i - 1).end(), arg_span(i).end()))
+                    };
+                    Some(QuickFix {
+                        title: format!("Remove unused load of `{}`", name),
+                        edits: vec![(removed, String::new())],
+                    })
+                }),
+            // Unused top level assignments are only reported for private symbols, as the
+            // rest are exported, so can always be deleted.
+            "unused-assign" => self
+                .ast
+                .top_level_statements()
+                .into_iter()
+                .find_map(|stmt| {
+                    let name = match &**stmt {
+                        Stmt::Def(def) => &def.name,
+                        Stmt::Assign(lhs, _) => match &**lhs {
+                            AssignP::Identifier(name) => name,
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                    if resolve(name.span) != span || !name.0.starts_with('_') {
+                        return None;
+                    }
+                    Some(QuickFix {
+                        title: format!("Remove unused definition of `{}`", name.0),
+                        edits: vec![(self.statement_lines(stmt), String::new())],
+                    })
+                }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+
+    /// Apply all of the fixes for the lints in `program` to it.
+    fn fix_all(program: &str) -> String {
+        let program = dedent(program);
+        let module = LspModule::new(
+            AstModule::parse("foo.star", program.clone(), &Dialect::Extended).unwrap(),
+        );
+        let mut edits: Vec<_> = module
+            .ast
+            .lint(None)
+            .into_iter()
+            .filter_map(|lint| {
+                module.find_quick_fix(&lint.short_name, lint.location.resolve_span())
+            })
+            .flat_map(|fix| fix.edits)
+            .collect();
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(program.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let offset = |line: usize, column: usize| {
+            line_starts
+                .get(line)
+                .map_or(program.len(), |start| start + column)
+        };
+        // Apply from the end of the file backwards, so earlier edits don't move later ones.
+        edits.sort_by_key(|(span, _)| (span.begin_line, span.begin_column));
+        let mut res = program.clone();
+        for (span, replacement) in edits.into_iter().rev() {
+            res.replace_range(
+                offset(span.begin_line, span.begin_column)..offset(span.end_line, span.end_column),
+                &replacement,
+            );
+        }
+        res
+    }
+
+    #[test]
+    fn removes_unused_loads() {
+        assert_eq!(
+            "load(\"a.star\", \"used\")\nload(\"b.star\", \"used2\")\nused(used2)",
+            fix_all(
+                r#"
+                load("a.star", "unused", "used", renamed = "unused2")
+                load("b.star", "used2", "unused3")
+                load("c.star", "unused4")
+                used(used2)
+                "#
+            )
+            .trim()
+        );
+    }
+
+    #[test]
+    fn removes_unused_private_definitions() {
+        assert_eq!(
+            "def public():\n    pass",
+            fix_all(
+                r#"
+                _unused = 1
+                def _unused_function():
+                    pass
+                def public():
+                    pass
+                "#
+            )
+            .trim()
+        );
+    }
+}
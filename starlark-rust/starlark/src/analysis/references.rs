@@ -0,0 +1,274 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Resolve variables to the place they are bound, and find all of the uses of a binding.
+
+use crate::analysis::bind::scope;
+use crate::analysis::bind::Assigner;
+use crate::analysis::bind::Bind;
+use crate::analysis::bind::Scope;
+use crate::analysis::definition::LspModule;
+use crate::codemap::ResolvedSpan;
+use crate::codemap::Span;
+use crate::syntax::ast::AstAssignIdent;
+use crate::syntax::ast::AstString;
+use crate::syntax::ast::Stmt;
+
+/// The place where a variable is bound.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Binding {
+    /// The name of the variable.
+    pub(crate) name: String,
+    /// The first place the variable is assigned in its scope.
+    pub(crate) span: Span,
+    /// Whether the variable is bound in the module's top level scope.
+    pub(crate) top_level: bool,
+    /// If the variable was bound by a `load()`, the path that was loaded and the name
+    /// of the symbol within that file.
+    pub(crate) loaded_from: Option<(String, String)>,
+}
+
+impl Binding {
+    fn new(name: &str, depth: usize, assigner: &Assigner, span: Span) -> Self {
+        Self {
+            name: name.to_owned(),
+            span,
+            top_level: depth == 0,
+            loaded_from: match assigner {
+                Assigner::Load { path, name } => Some((path.node.clone(), name.node.clone())),
+                Assigner::Argument | Assigner::Assign => None,
+            },
+        }
+    }
+}
+
+/// Look through the stack of scopes (innermost last) to find where `name` is bound.
+fn resolve<'a>(stack: &[&'a Scope], name: &str) -> Option<(usize, &'a Assigner, Span)> {
+    stack.iter().enumerate().rev().find_map(|(depth, scope)| {
+        scope
+            .bound
+            .get(name)
+            .map(|(assigner, span)| (depth, assigner, *span))
+    })
+}
+
+/// Call `f` with every assignment and access of a variable in the innermost scope of `stack`
+/// (and its child scopes), along with the span it occurs at and what it resolves to.
+fn visit_names<'a>(
+    stack: &mut Vec<&'a Scope>,
+    f: &mut dyn FnMut(&'a str, Span, Option<(usize, &'a Assigner, Span)>),
+) {
+    let current = *stack.last().expect("at least one scope");
+    for bind in &current.inner {
+        match bind {
+            Bind::Set(_, x) => f(&x.0, x.span, resolve(stack, &x.0)),
+            Bind::Get(x) => f(&x.node, x.span, resolve(stack, &x.node)),
+            Bind::GetDotted(x) => f(
+                &x.variable.node,
+                x.variable.span,
+                resolve(stack, &x.variable.node),
+            ),
+            Bind::Scope(inner) => {
+                stack.push(inner);
+                visit_names(stack, f);
+                stack.pop();
+            }
+            Bind::Flow => {}
+        }
+    }
+}
+
+impl LspModule {
+    /// Find the binding of the variable at the given zero based line and column, whether
+    /// the position is at an assignment of that variable or a use of it.
+    ///
+    /// Returns `None` if the position is not a variable, or the variable is not bound within
+    /// this module (e.g. it is a global).
+    pub(crate) fn find_binding(&self, line: u32, col: u32) -> Option<Binding> {
+        let pos = self.position(line, col)?;
+        let scope = scope(&self.ast);
+        let mut res = None;
+        visit_names(&mut vec![&scope], &mut |name, span, resolved| {
+            if res.is_none() && span.contains(pos) {
+                if let Some((depth, assigner, binding_span)) = resolved {
+                    res = Some(Binding::new(name, depth, assigner, binding_span));
+                }
+            }
+        });
+        res
+    }
+
+    /// Find the binding of a variable in the top level scope of this module.
+    pub(crate) fn find_top_level_binding(&self, name: &str) -> Option<Binding> {
+        let scope = scope(&self.ast);
+        let (assigner, span) = scope.bound.get(name)?;
+        Some(Binding::new(name, 0, assigner, *span))
+    }
+
+    /// Find all of the top level bindings that were introduced by a `load()`.
+    pub(crate) fn find_loaded_bindings(&self) -> Vec<Binding> {
+        let scope = scope(&self.ast);
+        let mut res: Vec<_> = scope
+            .bound
+            .iter()
+            .filter(|(_, (assigner, _))| matches!(assigner, Assigner::Load { .. }))
+            .map(|(name, (assigner, span))| Binding::new(name, 0, assigner, *span))
+            .collect();
+        res.sort_by_key(|binding| binding.span.begin());
+        res
+    }
+
+    /// Find every assignment and use of the variable bound at `binding`, in source order.
+    pub(crate) fn find_references(&self, binding: &Binding) -> Vec<ResolvedSpan> {
+        let scope = scope(&self.ast);
+        let mut res = Vec::new();
+        visit_names(&mut vec![&scope], &mut |name, span, resolved| {
+            if let Some((_, _, binding_span)) = resolved {
+                if binding_span == binding.span && name == binding.name {
+                    res.push(span);
+                }
+            }
+        });
+        res.sort_by_key(|span| span.begin());
+        res.dedup();
+        res.into_iter()
+            .map(|span| self.ast.codemap.resolve_span(span))
+            .collect()
+    }
+
+    /// Find the argument of a top level `load()` whose local name is at `span`.
+    fn find_load_arg(&self, span: Span) -> Option<&(AstAssignIdent, AstString)> {
+        self.ast
+            .top_level_statements()
+            .into_iter()
+            .find_map(|stmt| match &**stmt {
+                Stmt::Load(load) => load.args.iter().find(|(name, _)| name.span == span),
+                _ => None,
+            })
+    }
+
+    /// The edits needed to rename the variable bound at `binding` to `new_name` throughout
+    /// this module. Variables bound by a `load()` should use
+    /// [`rename_loaded_symbol`](LspModule::rename_loaded_symbol) instead.
+    pub(crate) fn rename_binding(
+        &self,
+        binding: &Binding,
+        new_name: &str,
+    ) -> Vec<(ResolvedSpan, String)> {
+        self.find_references(binding)
+            .into_iter()
+            .map(|span| (span, new_name.to_owned()))
+            .collect()
+    }
+
+    /// The edits needed in this module when the symbol that it loads as `binding` is renamed
+    /// to `new_name` in the module it is loaded from.
+    pub(crate) fn rename_loaded_symbol(
+        &self,
+        binding: &Binding,
+        new_name: &str,
+    ) -> Vec<(ResolvedSpan, String)> {
+        match self.find_load_arg(binding.span) {
+            // Loaded without an alias, so the new name has to be used locally too.
+            Some((name, symbol)) if name.span == symbol.span => self
+                .find_references(binding)
+                .into_iter()
+                .map(|span| {
+                    if span == self.ast.codemap.resolve_span(name.span) {
+                        (span, format!("\"{}\"", new_name))
+                    } else {
+                        (span, new_name.to_owned())
+                    }
+                })
+                .collect(),
+            Some((_, symbol)) => vec![(
+                self.ast.codemap.resolve_span(symbol.span),
+                format!("\"{}\"", new_name),
+            )],
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use crate::analysis::definition::helpers::FixtureWithRanges;
+
+    #[test]
+    fn finds_references_in_scope() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            load("foo.star", <loaded>"loaded"</loaded>)
+            <x_def>x</x_def> = 1
+
+            def f(<p_def>x</p_def>):
+                return <p_use>x</p_use> + <loaded_use>loaded</loaded_use>
+
+            <x_use>x</x_use> += 1
+            print(<x_dot>x</x_dot>.y)
+            "#,
+        );
+        let fixture = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = fixture.module()?;
+
+        let binding = module
+            .find_binding(fixture.begin_line("x_use"), fixture.begin_column("x_use"))
+            .unwrap();
+        assert!(binding.top_level);
+        assert_eq!(None, binding.loaded_from);
+        assert_eq!(
+            vec![
+                fixture.span("x_def"),
+                fixture.span("x_use"),
+                fixture.span("x_dot")
+            ],
+            module.find_references(&binding)
+        );
+
+        let binding = module
+            .find_binding(fixture.begin_line("p_use"), fixture.begin_column("p_use"))
+            .unwrap();
+        assert!(!binding.top_level);
+        assert_eq!(
+            vec![fixture.span("p_def"), fixture.span("p_use")],
+            module.find_references(&binding)
+        );
+
+        let binding = module
+            .find_binding(
+                fixture.begin_line("loaded_use"),
+                fixture.begin_column("loaded_use"),
+            )
+            .unwrap();
+        assert_eq!(
+            Some(("foo.star".to_owned(), "loaded".to_owned())),
+            binding.loaded_from
+        );
+        assert_eq!(
+            vec![fixture.span("loaded"), fixture.span("loaded_use")],
+            module.find_references(&binding)
+        );
+        assert_eq!(
+            Some(binding.clone()),
+            module.find_top_level_binding("loaded")
+        );
+        assert_eq!(vec![binding], module.find_loaded_bindings());
+        Ok(())
+    }
+}
@@ -30,6 +30,7 @@ use crate::codemap::FileSpan;
 use crate::codemap::ResolvedSpan;
 use crate::codemap::Span;
 use crate::errors::Diagnostic;
+use crate::typing::ctx::TypingError;
 
 pub(crate) trait LintWarning: Display {
     fn is_serious(&self) -> bool;
@@ -161,6 +162,18 @@ impl Display for EvalMessage {
 impl EvalMessage {
     /// Convert from an `anyhow::Error`, including some type checking, to an `EvalMessage`
     pub fn from_anyhow(file: &Path, x: &anyhow::Error) -> Self {
+        if let Some(e) = x.downcast_ref::<TypingError>() {
+            let loc = e.loc();
+            return Self {
+                path: loc.file.clone(),
+                span: Some(loc.span),
+                severity: EvalSeverity::Warning,
+                name: "typecheck".to_owned(),
+                description: e.to_string(),
+                full_error_with_span: None,
+                original: None,
+            };
+        }
         match x.downcast_ref::<Diagnostic>() {
             Some(
                 d @ Diagnostic {
@@ -17,6 +17,7 @@
 
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::fmt::Write;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc::channel;
@@ -33,6 +34,8 @@ use crate::codemap::FileSpanRef;
 use crate::codemap::Span;
 use crate::debug::adapter::Breakpoint;
 use crate::debug::adapter::ResolvedBreakpoints;
+use crate::debug::adapter::ERROR_EXCEPTION_FILTER;
+use crate::debug::adapter::FAIL_EXCEPTION_FILTER;
 use crate::debug::DapAdapter;
 use crate::debug::DapAdapterClient;
 use crate::debug::DapAdapterEvalHook;
@@ -40,13 +43,26 @@ use crate::debug::ScopesInfo;
 use crate::debug::StepKind;
 use crate::debug::Variable;
 use crate::debug::VariablesInfo;
+use crate::errors::Diagnostic;
 use crate::eval::BeforeStmtFuncDyn;
 use crate::eval::Evaluator;
 use crate::slice_vec_ext::SliceExt;
+use crate::stdlib::funcs::FailError;
+use crate::syntax::lexer::is_identifier;
 use crate::syntax::AstModule;
 use crate::syntax::Dialect;
 use crate::values::Value;
 
+#[derive(Debug, thiserror::Error)]
+enum DebugAdapterError {
+    #[error("Invalid hit condition `{0}`, expected e.g. `5`, `>= 5` or `% 5`")]
+    InvalidHitCondition(String),
+    #[error("Unknown exception breakpoint filter `{0}`")]
+    UnknownExceptionFilter(String),
+    #[error("Can only set variables by name, got `{0}`")]
+    InvalidVariableName(String),
+}
+
 pub(crate) fn prepare_dap_adapter(
     client: Box<dyn DapAdapterClient>,
 ) -> (impl DapAdapter, impl DapAdapterEvalHook) {
@@ -54,6 +70,7 @@ pub(crate) fn prepare_dap_adapter(
     let state = Arc::new(SharedAdapterState {
         client,
         breakpoints: Arc::new(Mutex::new(BreakpointConfig::new())),
+        exception_breakpoints: Mutex::new(ExceptionBreakpoints::default()),
         disable_breakpoints: Arc::new(0usize.into()),
     });
 
@@ -96,23 +113,74 @@ fn evaluate_expr<'v>(
     res
 }
 
+/// Interpolate the `{expr}` parts of a logpoint message, with `{{` and `}}` for literal braces.
+fn interpolate_log_message(
+    state: &SharedAdapterState,
+    eval: &mut Evaluator,
+    message: &str,
+) -> String {
+    let mut res = String::new();
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                res.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                res.push('}');
+            }
+            '{' => {
+                let expr: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                match evaluate_expr(state, eval, expr) {
+                    Ok(v) => write!(res, "{}", v).unwrap(),
+                    Err(e) => write!(res, "<error: {:#}>", e).unwrap(),
+                }
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// The error itself, without the span and call stack attached by the evaluator.
+fn error_message(e: &anyhow::Error) -> &anyhow::Error {
+    match e.downcast_ref::<Diagnostic>() {
+        Some(d) => &d.message,
+        None => e,
+    }
+}
+
 impl<'a> BeforeStmtFuncDyn<'a> for DapAdapterEvalHookImpl {
     fn call<'v>(&mut self, span_loc: FileSpanRef, eval: &mut Evaluator<'v, 'a>) {
         let stop = if self.state.disable_breakpoints.load(Ordering::SeqCst) > 0 {
             false
         } else {
-            let breaks = self.state.breakpoints.lock().unwrap();
-            let breakpoint = breaks.at(span_loc);
-            match breakpoint {
-                Some(Breakpoint {
-                    condition: Some(condition),
-                    ..
-                }) => match evaluate_expr(&self.state, eval, condition.to_owned()) {
-                    Ok(v) => v.to_bool(),
-                    _ => true,
-                },
-                Some(..) => true,
+            let mut breaks = self.state.breakpoints.lock().unwrap();
+            match breaks.at(span_loc).cloned() {
                 None => false,
+                Some(breakpoint) => {
+                    let condition = match &breakpoint.condition {
+                        Some(condition) => {
+                            match evaluate_expr(&self.state, eval, condition.to_owned()) {
+                                Ok(v) => v.to_bool(),
+                                _ => true,
+                            }
+                        }
+                        None => true,
+                    };
+                    // Only count the hits where the condition holds.
+                    if !condition || !breaks.hit(span_loc, breakpoint.hit_condition) {
+                        false
+                    } else if let Some(message) = &breakpoint.log_message {
+                        let output = interpolate_log_message(&self.state, eval, message);
+                        self.state.client.event_output(&output);
+                        false
+                    } else {
+                        true
+                    }
+                }
             }
         };
 
@@ -127,23 +195,27 @@ impl<'a> BeforeStmtFuncDyn<'a> for DapAdapterEvalHookImpl {
         };
 
         if stop || step_stop {
-            self.step = None;
             self.state.client.event_stopped();
-            loop {
-                let msg = self.receiver.recv();
-                match msg.map(|msg| msg(span_loc, eval)) {
-                    Ok(Next::Continue) => break,
-                    Ok(Next::Step(kind)) => {
-                        self.step = Some((kind, eval.call_stack_count()));
-                        break;
-                    }
-                    Ok(Next::RemainPaused) => continue,
-                    Err(..) => {
-                        // DapAdapter has been dropped so we'll continue.
-                        break;
-                    }
-                }
-            }
+            self.pause(span_loc, eval);
+        }
+    }
+
+    fn on_error<'v>(
+        &mut self,
+        span_loc: FileSpanRef,
+        error: &anyhow::Error,
+        eval: &mut Evaluator<'v, 'a>,
+    ) {
+        if self.state.disable_breakpoints.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+        let error = error_message(error);
+        let filters = *self.state.exception_breakpoints.lock().unwrap();
+        if filters.error || (filters.fail && error.is::<FailError>()) {
+            self.state
+                .client
+                .event_stopped_on_exception(&format!("{:#}", error));
+            self.pause(span_loc, eval);
         }
     }
 }
@@ -162,6 +234,26 @@ impl DapAdapterEvalHookImpl {
             step: None,
         }
     }
+
+    /// Handle messages from the DapAdapter until it tells us to resume.
+    fn pause(&mut self, span_loc: FileSpanRef, eval: &mut Evaluator) {
+        self.step = None;
+        loop {
+            let msg = self.receiver.recv();
+            match msg.map(|msg| msg(span_loc, eval)) {
+                Ok(Next::Continue) => break,
+                Ok(Next::Step(kind)) => {
+                    self.step = Some((kind, eval.call_stack_count()));
+                    break;
+                }
+                Ok(Next::RemainPaused) => continue,
+                Err(..) => {
+                    // DapAdapter has been dropped so we'll continue.
+                    break;
+                }
+            }
+        }
+    }
 }
 
 impl DapAdapterEvalHook for DapAdapterEvalHookImpl {
@@ -170,19 +262,78 @@ impl DapAdapterEvalHook for DapAdapterEvalHookImpl {
     }
 }
 
+/// When to stop at a breakpoint, given how many times it has been reached.
+#[derive(Debug, Clone, Copy, Dupe, Hash, Eq, PartialEq)]
+pub(crate) enum HitCondition {
+    Eq(u64),
+    Gt(u64),
+    Ge(u64),
+    Lt(u64),
+    Le(u64),
+    /// Every n-th hit.
+    Multiple(u64),
+}
+
+impl HitCondition {
+    /// Parses hit conditions like `5`, `== 5`, `> 5`, `>= 5`, `< 5`, `<= 5` and `% 5`.
+    fn parse(s: &str) -> anyhow::Result<HitCondition> {
+        let s = s.trim();
+        let ops: [(&str, fn(u64) -> HitCondition); 6] = [
+            (">=", HitCondition::Ge),
+            ("<=", HitCondition::Le),
+            ("==", HitCondition::Eq),
+            (">", HitCondition::Gt),
+            ("<", HitCondition::Lt),
+            ("%", HitCondition::Multiple),
+        ];
+        let (op, n): (fn(u64) -> HitCondition, &str) = ops
+            .iter()
+            .find_map(|(prefix, op)| Some((*op, s.strip_prefix(prefix)?)))
+            .unwrap_or((HitCondition::Eq, s));
+        match n.trim().parse().map(op) {
+            // Every zeroth hit makes no sense.
+            Ok(HitCondition::Multiple(0)) | Err(_) => {
+                Err(DebugAdapterError::InvalidHitCondition(s.to_owned()).into())
+            }
+            Ok(c) => Ok(c),
+        }
+    }
+
+    fn matches(self, hits: u64) -> bool {
+        match self {
+            HitCondition::Eq(n) => hits == n,
+            HitCondition::Gt(n) => hits > n,
+            HitCondition::Ge(n) => hits >= n,
+            HitCondition::Lt(n) => hits < n,
+            HitCondition::Le(n) => hits <= n,
+            HitCondition::Multiple(n) => hits % n == 0,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct BreakpointConfig {
     // maps a source filename to the breakpoint spans for the file
     breakpoints: HashMap<String, HashMap<Span, Breakpoint>>,
+    // how many times each breakpoint has been reached with its condition (if any) true
+    hits: HashMap<FileSpan, u64>,
 }
 
 impl BreakpointConfig {
     fn new() -> Self {
         Self {
             breakpoints: HashMap::new(),
+            hits: HashMap::new(),
         }
     }
 
+    /// Count a hit of the breakpoint at `span_loc`, and return whether to stop at it.
+    fn hit(&mut self, span_loc: FileSpanRef, hit_condition: Option<HitCondition>) -> bool {
+        let hits = self.hits.entry(span_loc.to_file_span()).or_default();
+        *hits += 1;
+        hit_condition.map_or(true, |c| c.matches(*hits))
+    }
+
     fn at(&self, span_loc: FileSpanRef) -> Option<&Breakpoint> {
         self.breakpoints
             .get(span_loc.filename())
@@ -194,6 +345,7 @@ impl BreakpointConfig {
         source: &str,
         breakpoints: &ResolvedBreakpoints,
     ) -> anyhow::Result<()> {
+        self.hits.retain(|span, _| span.filename() != source);
         if breakpoints.0.is_empty() {
             self.breakpoints.remove(source);
         } else {
@@ -219,6 +371,16 @@ struct SharedAdapterState {
     breakpoints: Arc<Mutex<BreakpointConfig>>,
     // Set while we are doing evaluate calls (>= 1 means disable)
     disable_breakpoints: Arc<AtomicUsize>,
+    // Which errors to stop at.
+    exception_breakpoints: Mutex<ExceptionBreakpoints>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Dupe)]
+struct ExceptionBreakpoints {
+    // Stop at errors raised by `fail()`.
+    fail: bool,
+    // Stop at any error.
+    error: bool,
 }
 
 #[derive(Debug, Clone, Copy, Dupe)]
@@ -267,6 +429,19 @@ impl DapAdapter for DapAdapterImpl {
             .set_breakpoints(source, breakpoints)
     }
 
+    fn set_exception_breakpoints(&self, filters: &[String]) -> anyhow::Result<()> {
+        let mut exception_breakpoints = ExceptionBreakpoints::default();
+        for filter in filters {
+            match filter.as_str() {
+                FAIL_EXCEPTION_FILTER => exception_breakpoints.fail = true,
+                ERROR_EXCEPTION_FILTER => exception_breakpoints.error = true,
+                _ => return Err(DebugAdapterError::UnknownExceptionFilter(filter.clone()).into()),
+            }
+        }
+        *self.state.exception_breakpoints.lock().unwrap() = exception_breakpoints;
+        Ok(())
+    }
+
     fn top_frame(&self) -> anyhow::Result<Option<StackFrame>> {
         self.with_ctx(Box::new(|span, eval| {
             let frame = eval.call_stack_top_frame();
@@ -348,6 +523,24 @@ impl DapAdapter for DapAdapterImpl {
             })
         }))
     }
+
+    fn set_variable(&self, name: &str, value: &str) -> anyhow::Result<Variable> {
+        if !is_identifier(name) {
+            return Err(DebugAdapterError::InvalidVariableName(name.to_owned()).into());
+        }
+        let state = self.state.dupe();
+        let name = name.to_owned();
+        let assign = format!("{} = {}", name, value);
+        self.with_ctx(Box::new(move |_, eval| {
+            evaluate_expr(&state, eval, assign.clone())?;
+            let value = evaluate_expr(&state, eval, name.clone())?;
+            Ok(Variable {
+                name: name.clone(),
+                value: value.to_string(),
+                type_: value.get_type().to_owned(),
+            })
+        }))
+    }
 }
 
 impl DapAdapterImpl {
@@ -402,17 +595,23 @@ pub(crate) fn resolve_breakpoints(
         .iter()
         .map(|span| (span.resolve_span().begin_line, span.dupe()))
         .collect();
-    Ok(ResolvedBreakpoints(args.breakpoints.as_ref().map_or(
-        Vec::new(),
-        |v| {
-            v.map(|x| {
-                poss.get(&(x.line as usize - 1)).map(|span| Breakpoint {
-                    span: span.clone(),
-                    condition: x.condition.clone(),
-                })
-            })
-        },
-    )))
+    let breakpoints = match &args.breakpoints {
+        None => Vec::new(),
+        Some(v) => v.try_map(|x| -> anyhow::Result<_> {
+            let hit_condition = x
+                .hit_condition
+                .as_deref()
+                .map(HitCondition::parse)
+                .transpose()?;
+            Ok(poss.get(&(x.line as usize - 1)).map(|span| Breakpoint {
+                span: span.clone(),
+                condition: x.condition.clone(),
+                hit_condition,
+                log_message: x.log_message.clone(),
+            }))
+        })?,
+    };
+    Ok(ResolvedBreakpoints(breakpoints))
 }
 
 pub(crate) fn resolved_breakpoints_to_dap(
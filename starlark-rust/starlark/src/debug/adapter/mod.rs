@@ -25,16 +25,29 @@ use debugserver_types::*;
 use dupe::Dupe;
 
 use crate::codemap::FileSpan;
+use crate::debug::adapter::implementation::HitCondition;
 use crate::eval::Evaluator;
 use crate::syntax::AstModule;
 
 mod implementation;
 mod tests;
 
+/// Exception breakpoint filter to stop at calls to `fail()`.
+const FAIL_EXCEPTION_FILTER: &str = "fail";
+/// Exception breakpoint filter to stop at any error raised by the evaluation.
+const ERROR_EXCEPTION_FILTER: &str = "error";
+
 /// The DapAdapterClient is implemented by the user and provides functionality required by the DapAdapter.
 pub trait DapAdapterClient: Debug + Send + Sync + 'static {
     /// Indicates that the evaluation stopped at a breakpoint.
     fn event_stopped(&self);
+
+    /// Indicates that the evaluation stopped at an error, because of the exception breakpoints.
+    fn event_stopped_on_exception(&self, description: &str);
+
+    /// Indicates that a logpoint was reached, with its message after interpolation. Evaluation
+    /// continues without stopping.
+    fn event_output(&self, output: &str);
 }
 
 /// Information about the variables scopes
@@ -100,6 +113,12 @@ pub trait DapAdapter: Debug + Send + 'static {
         breakpoints: &ResolvedBreakpoints,
     ) -> anyhow::Result<()>;
 
+    /// Sets which errors to stop at, as the ids of [`exception_breakpoint_filters`] (and clears
+    /// existing ones).
+    ///
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_SetExceptionBreakpoints>
+    fn set_exception_breakpoints(&self, filters: &[String]) -> anyhow::Result<()>;
+
     /// Gets the top stack frame, may be None if entered from native.
     fn top_frame(&self) -> anyhow::Result<Option<StackFrame>>;
 
@@ -133,12 +152,21 @@ pub trait DapAdapter: Debug + Send + 'static {
     ///
     /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Evaluate>
     fn evaluate(&self, expr: &str) -> anyhow::Result<EvaluateResponseBody>;
+
+    /// Assigns the result of the expression `value` to the variable `name` in the top-most
+    /// frame, and returns the variable.
+    ///
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_SetVariable>
+    fn set_variable(&self, name: &str, value: &str) -> anyhow::Result<Variable>;
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct Breakpoint {
     span: FileSpan,
     condition: Option<String>,
+    hit_condition: Option<HitCondition>,
+    /// For logpoints, the message to log instead of stopping.
+    log_message: Option<String>,
 }
 
 /// Breakpoints resolved to their spans.
@@ -168,6 +196,22 @@ pub trait DapAdapterEvalHook: Debug + Send + 'static {
     fn add_dap_hooks<'v, 'a>(self: Box<Self>, eval: &mut Evaluator<'v, 'a>);
 }
 
+/// The errors that exception breakpoints can stop at.
+pub fn exception_breakpoint_filters() -> Vec<ExceptionBreakpointsFilter> {
+    vec![
+        ExceptionBreakpointsFilter {
+            filter: FAIL_EXCEPTION_FILTER.to_owned(),
+            label: "Calls to fail()".to_owned(),
+            default: Some(false),
+        },
+        ExceptionBreakpointsFilter {
+            filter: ERROR_EXCEPTION_FILTER.to_owned(),
+            label: "All errors".to_owned(),
+            default: Some(false),
+        },
+    ]
+}
+
 /// The DAP capabilities that the adapter supports.
 pub fn dap_capabilities() -> Capabilities {
     Capabilities {
@@ -176,6 +220,9 @@ pub fn dap_capabilities() -> Capabilities {
         supports_set_variable: Some(true),
         supports_step_in_targets_request: Some(true),
         supports_conditional_breakpoints: Some(true),
+        supports_hit_conditional_breakpoints: Some(true),
+        supports_log_points: Some(true),
+        exception_breakpoint_filters: Some(exception_breakpoint_filters()),
         ..Capabilities::default()
     }
 }
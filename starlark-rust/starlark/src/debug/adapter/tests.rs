@@ -21,6 +21,7 @@ mod t {
     use std::sync::atomic::AtomicUsize;
     use std::sync::atomic::Ordering;
     use std::sync::Arc;
+    use std::sync::Mutex;
     use std::thread::ScopedJoinHandle;
     use std::time::Duration;
     use std::time::Instant;
@@ -47,11 +48,15 @@ mod t {
     #[derive(Debug)]
     struct Client {
         breakpoints_hit: Arc<AtomicUsize>,
+        output: Arc<Mutex<Vec<String>>>,
     }
 
     impl Client {
-        pub fn new(breakpoints_hit: Arc<AtomicUsize>) -> Self {
-            Self { breakpoints_hit }
+        pub fn new(breakpoints_hit: Arc<AtomicUsize>, output: Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                breakpoints_hit,
+                output,
+            }
         }
     }
 
@@ -60,21 +65,32 @@ mod t {
             println!("stopped!");
             self.breakpoints_hit.fetch_add(1, Ordering::SeqCst);
         }
+
+        fn event_stopped_on_exception(&self, description: &str) {
+            println!("stopped on exception: {}", description);
+            self.breakpoints_hit.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn event_output(&self, output: &str) {
+            self.output.lock().unwrap().push(output.to_owned());
+        }
     }
 
     struct BreakpointController {
         breakpoints_hit: Arc<AtomicUsize>,
+        output: Arc<Mutex<Vec<String>>>,
     }
 
     impl BreakpointController {
         fn new() -> Self {
             Self {
                 breakpoints_hit: Arc::new(AtomicUsize::new(0)),
+                output: Arc::new(Mutex::new(Vec::new())),
             }
         }
 
         fn get_client(&self) -> Box<dyn DapAdapterClient> {
-            Box::new(Client::new(self.breakpoints_hit.dupe()))
+            Box::new(Client::new(self.breakpoints_hit.dupe(), self.output.dupe()))
         }
 
         fn wait_for_eval_stopped(&self, breakpoint_count: usize, timeout: Duration) {
@@ -99,13 +115,21 @@ mod t {
     }
 
     fn breakpoints_args(path: &str, lines: &[(i64, Option<&str>)]) -> SetBreakpointsArguments {
+        source_breakpoints_args(
+            path,
+            lines
+                .iter()
+                .map(|(line, condition)| breakpoint(*line, condition.as_deref()))
+                .collect(),
+        )
+    }
+
+    fn source_breakpoints_args(
+        path: &str,
+        breakpoints: Vec<SourceBreakpoint>,
+    ) -> SetBreakpointsArguments {
         SetBreakpointsArguments {
-            breakpoints: Some(
-                lines
-                    .iter()
-                    .map(|(line, condition)| breakpoint(*line, condition.as_deref()))
-                    .collect(),
-            ),
+            breakpoints: Some(breakpoints),
             lines: None,
             source: Source {
                 adapter_data: None,
@@ -403,4 +427,196 @@ print(x)
             Ok(())
         })
     }
+
+    #[test]
+    fn test_logpoint() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def adjust(y):
+    y.append(len(y)) # line 3
+    return y
+x = []
+adjust(x)
+adjust(x)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            let breakpoints = resolve_breakpoints(
+                &source_breakpoints_args(
+                    "test.bzl",
+                    vec![SourceBreakpoint {
+                        log_message: Some("y is {y}, {{braces}}".to_owned()),
+                        ..breakpoint(3, None)
+                    }],
+                ),
+                &ast,
+            )?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            join_timeout(eval_result, TIMEOUT)?;
+            assert_eq!(0, controller.breakpoints_hit.load(Ordering::SeqCst));
+            assert_eq!(
+                vec!["y is [], {braces}", "y is [0], {braces}"],
+                *controller.output.lock().unwrap()
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_hit_condition() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def adjust(y):
+    y.append(len(y)) # line 3
+x = []
+for _ in range(5):
+    adjust(x)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            let breakpoints = resolve_breakpoints(
+                &source_breakpoints_args(
+                    "test.bzl",
+                    vec![SourceBreakpoint {
+                        hit_condition: Some("% 2".to_owned()),
+                        ..breakpoint(3, None)
+                    }],
+                ),
+                &ast,
+            )?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert_eq!("[0]", adapter.evaluate("y")?.result);
+            adapter.continue_()?;
+            controller.wait_for_eval_stopped(2, TIMEOUT);
+            assert_eq!("[0, 1, 2]", adapter.evaluate("y")?.result);
+            adapter.continue_()?;
+            join_timeout(eval_result, TIMEOUT)?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_invalid_hit_condition() -> anyhow::Result<()> {
+        let ast = AstModule::parse("test.bzl", "x = 1".to_owned(), &Dialect::Extended)?;
+        for hit_condition in ["x", ">= -1", "% 0"] {
+            let args = source_breakpoints_args(
+                "test.bzl",
+                vec![SourceBreakpoint {
+                    hit_condition: Some(hit_condition.to_owned()),
+                    ..breakpoint(1, None)
+                }],
+            );
+            assert!(resolve_breakpoints(&args, &ast).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_exception_breakpoint() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def check(y):
+    if len(y) > 2:
+        fail('too long:', y)
+x = [1, 2, 3]
+check(x)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            adapter.set_exception_breakpoints(&["fail".to_owned()])?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            // Only stops in the frame which called `fail()`, not the ones the error passes through.
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert_eq!("[1, 2, 3]", adapter.evaluate("y")?.result);
+            adapter.continue_()?;
+            assert!(join_timeout(eval_result, TIMEOUT).is_err());
+            assert_eq!(1, controller.breakpoints_hit.load(Ordering::SeqCst));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_exception_breakpoint_filters() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+x = {}
+y = x['missing']
+        ";
+        assert!(
+            adapter
+                .set_exception_breakpoints(&["bad".to_owned()])
+                .is_err()
+        );
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            // Not a `fail()`, so only stops with the `error` filter.
+            adapter.set_exception_breakpoints(&["fail".to_owned(), "error".to_owned()])?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert_eq!("{}", adapter.evaluate("x")?.result);
+            adapter.continue_()?;
+            assert!(join_timeout(eval_result, TIMEOUT).is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_set_variable() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def inc(y):
+    y += 1 # line 3
+    return y
+inc(1)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            let breakpoints =
+                resolve_breakpoints(&breakpoints_args("test.bzl", &[(3, None)]), &ast)?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert!(adapter.set_variable("y[0]", "1").is_err());
+            let variable = adapter.set_variable("y", "10 * 2")?;
+            assert_eq!("20", variable.value);
+            assert_eq!("int", variable.type_);
+            adapter.continue_()?;
+            let res = join_timeout(eval_result, TIMEOUT)?;
+            assert_eq!(Some(21), res.value().unpack_int());
+            Ok(())
+        })
+    }
 }
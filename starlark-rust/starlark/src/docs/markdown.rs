@@ -243,11 +243,58 @@ fn render_doc_item(name: &str, item: &DocItem) -> String {
     }
 }
 
+/// Render a short description of a symbol for the LSP: its prototype, followed by its
+/// documentation, and for objects and modules, the names of their members.
+fn render_lsp_summary(name: &str, item: &DocItem) -> String {
+    let (prototype, docs, extra) = match item {
+        DocItem::Function(f) => (
+            Some(
+                TypeRenderer::Function {
+                    function_name: name,
+                    f,
+                }
+                .render_markdown(MarkdownFlavor::DocFile),
+            ),
+            &f.docs,
+            render_function_parameters(&f.params).map(|p| format!("#### Parameters\n\n{p}")),
+        ),
+        DocItem::Property(p) => (
+            Some(format!(
+                "{name}: {}",
+                TypeRenderer::Type(&p.typ).render_markdown(MarkdownFlavor::DocFile)
+            )),
+            &p.docs,
+            None,
+        ),
+        DocItem::Object(DocObject { docs, members })
+        | DocItem::Module(DocModule { docs, members }) => (
+            None,
+            docs,
+            if members.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "#### Members\n\n{}",
+                    members.keys().map(|k| format!("* `{k}`")).join("\n")
+                ))
+            },
+        ),
+    };
+    [
+        prototype.map(|p| render_code_block(&p)),
+        render_doc_string(DSOpts::Combined, docs),
+        extra,
+    ]
+    .into_iter()
+    .flatten()
+    .join("\n\n")
+}
+
 impl RenderMarkdown for Doc {
     fn render_markdown_opt(&self, flavor: MarkdownFlavor) -> Option<String> {
         match flavor {
             MarkdownFlavor::DocFile => Some(render_doc_item(&self.id.name, &self.item)),
-            MarkdownFlavor::LspSummary => None,
+            MarkdownFlavor::LspSummary => Some(render_lsp_summary(&self.id.name, &self.item)),
         }
     }
 }
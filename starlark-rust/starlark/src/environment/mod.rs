@@ -24,6 +24,7 @@
 
 mod globals;
 mod module_dump;
+mod module_serialize;
 mod modules;
 pub(crate) mod names;
 pub(crate) mod slots;
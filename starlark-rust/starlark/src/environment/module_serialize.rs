@@ -0,0 +1,316 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Serialization of frozen modules, so that the results of evaluating a module can be
+//! stored on disk and reused by another process.
+//!
+//! Only the symbols other modules can `load` are stored, and only when they are plain data:
+//! `None`, `bool`, `int`, `float`, `string`, `list`, `tuple`, `dict` and `struct`.
+//! Functions and other values refer to code and heaps of other modules, so a module
+//! which exports them cannot be serialized.
+
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use serde::Deserialize;
+use serde::Serialize;
+use starlark_map::small_map::SmallMap;
+
+use crate::environment::FrozenModule;
+use crate::environment::Module;
+use crate::syntax::ast::Visibility;
+use crate::values::dict::Dict;
+use crate::values::dict::DictRef;
+use crate::values::float::StarlarkFloat;
+use crate::values::list::AllocList;
+use crate::values::list::ListRef;
+use crate::values::structs::AllocStruct;
+use crate::values::structs::StructRef;
+use crate::values::tuple::AllocTuple;
+use crate::values::tuple::TupleRef;
+use crate::values::types::bigint::StarlarkBigInt;
+use crate::values::Heap;
+use crate::values::Value;
+use crate::values::ValueLike;
+
+/// Version of the serialized format, must be bumped whenever it changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Values nested deeper than that are not serialized, this also stops on lists containing themselves.
+const MAX_DEPTH: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+enum ModuleSerializeError {
+    #[error(
+        "Cannot serialize symbol `{0}` of type `{1}`, only `None`, `bool`, `int`, `float`, \
+        `string`, `list`, `tuple`, `dict` and `struct` values can be serialized"
+    )]
+    UnsupportedValue(String, &'static str),
+    #[error("Cannot serialize symbol `{0}`, it is nested too deeply")]
+    TooDeep(String),
+    #[error("Cannot serialize a module with an extra value")]
+    ExtraValue,
+    #[error("Serialized module has format version {0}, expected {1}")]
+    VersionMismatch(u32, u32),
+    #[error("Serialized module has hash `{0}`, expected `{1}`")]
+    HashMismatch(String, String),
+}
+
+/// Fields checked before the rest of the module is deserialized.
+#[derive(Deserialize)]
+struct SerializedModuleHeader {
+    version: u32,
+    hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedModule {
+    version: u32,
+    hash: String,
+    docstring: Option<String>,
+    symbols: Vec<(String, SerializedValue)>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SerializedValue {
+    None,
+    Bool(bool),
+    Int(i32),
+    /// Decimal representation.
+    BigInt(String),
+    /// Bits of the `f64`, since JSON cannot represent NaN and infinities.
+    Float(u64),
+    String(String),
+    List(Vec<SerializedValue>),
+    Tuple(Vec<SerializedValue>),
+    Dict(Vec<(SerializedValue, SerializedValue)>),
+    Struct(Vec<(String, SerializedValue)>),
+}
+
+impl SerializedValue {
+    fn new(value: Value, name: &str, depth: usize) -> anyhow::Result<SerializedValue> {
+        if depth > MAX_DEPTH {
+            return Err(ModuleSerializeError::TooDeep(name.to_owned()).into());
+        }
+        Ok(if value.is_none() {
+            SerializedValue::None
+        } else if let Some(x) = value.unpack_bool() {
+            SerializedValue::Bool(x)
+        } else if let Some(x) = value.unpack_int() {
+            SerializedValue::Int(x)
+        } else if let Some(x) = value.downcast_ref::<StarlarkBigInt>() {
+            SerializedValue::BigInt(x.get().to_string())
+        } else if let Some(x) = value.downcast_ref::<StarlarkFloat>() {
+            SerializedValue::Float(x.0.to_bits())
+        } else if let Some(x) = value.unpack_str() {
+            SerializedValue::String(x.to_owned())
+        } else if let Some(x) = ListRef::from_value(value) {
+            SerializedValue::List(Self::new_all(x.iter(), name, depth)?)
+        } else if let Some(x) = TupleRef::from_value(value) {
+            SerializedValue::Tuple(Self::new_all(x.iter(), name, depth)?)
+        } else if let Some(x) = DictRef::from_value(value) {
+            SerializedValue::Dict(
+                x.iter()
+                    .map(|(k, v)| {
+                        Ok((
+                            SerializedValue::new(k, name, depth + 1)?,
+                            SerializedValue::new(v, name, depth + 1)?,
+                        ))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            )
+        } else if let Some(x) = StructRef::from_value(value) {
+            SerializedValue::Struct(
+                x.iter()
+                    .map(|(k, v)| {
+                        Ok((
+                            k.as_str().to_owned(),
+                            SerializedValue::new(v, name, depth + 1)?,
+                        ))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            )
+        } else {
+            return Err(
+                ModuleSerializeError::UnsupportedValue(name.to_owned(), value.get_type()).into(),
+            );
+        })
+    }
+
+    fn new_all<'v>(
+        xs: impl Iterator<Item = Value<'v>>,
+        name: &str,
+        depth: usize,
+    ) -> anyhow::Result<Vec<SerializedValue>> {
+        xs.map(|x| SerializedValue::new(x, name, depth + 1))
+            .collect()
+    }
+
+    fn alloc<'v>(&self, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        let all = |xs: &[SerializedValue]| {
+            xs.iter()
+                .map(|x| x.alloc(heap))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        Ok(match self {
+            SerializedValue::None => Value::new_none(),
+            SerializedValue::Bool(x) => Value::new_bool(*x),
+            SerializedValue::Int(x) => heap.alloc(*x),
+            SerializedValue::BigInt(x) => StarlarkBigInt::alloc_bigint(BigInt::from_str(x)?, heap),
+            SerializedValue::Float(x) => heap.alloc(f64::from_bits(*x)),
+            SerializedValue::String(x) => heap.alloc(x.as_str()),
+            SerializedValue::List(xs) => heap.alloc(AllocList(all(xs)?)),
+            SerializedValue::Tuple(xs) => heap.alloc(AllocTuple(all(xs)?)),
+            SerializedValue::Dict(xs) => {
+                let mut map = SmallMap::with_capacity(xs.len());
+                for (k, v) in xs {
+                    map.insert_hashed(k.alloc(heap)?.get_hashed()?, v.alloc(heap)?);
+                }
+                heap.alloc(Dict::new(map))
+            }
+            SerializedValue::Struct(xs) => heap.alloc(AllocStruct(
+                xs.iter()
+                    .map(|(k, v)| Ok((k.as_str(), v.alloc(heap)?)))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            )),
+        })
+    }
+}
+
+impl FrozenModule {
+    /// Serialize the symbols of this module which can be loaded by other modules.
+    ///
+    /// Fails unless they are all plain data: `None`, `bool`, `int`, `float`, `string`,
+    /// `list`, `tuple`, `dict` or `struct`.
+    ///
+    /// `hash` should identify everything the evaluation of the module depended on,
+    /// e.g. its source and the modules it loaded,
+    /// and is checked by [`deserialize`](FrozenModule::deserialize).
+    pub fn serialize(&self, hash: &str) -> anyhow::Result<Vec<u8>> {
+        if self.extra_value().is_some() {
+            return Err(ModuleSerializeError::ExtraValue.into());
+        }
+        let symbols = self
+            .items()
+            .filter(|(name, _)| Module::default_visibility(name.as_str()) == Visibility::Public)
+            .map(|(name, value)| {
+                Ok((
+                    name.as_str().to_owned(),
+                    SerializedValue::new(value.to_value(), name.as_str(), 0)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(serde_json::to_vec(&SerializedModule {
+            version: FORMAT_VERSION,
+            hash: hash.to_owned(),
+            docstring: self.docstring().map(|d| d.to_owned()),
+            symbols,
+        })?)
+    }
+
+    /// Deserialize a module written by [`serialize`](FrozenModule::serialize).
+    ///
+    /// Fails if it was written with a different format version or a different `hash`.
+    pub fn deserialize(data: &[u8], hash: &str) -> anyhow::Result<FrozenModule> {
+        let header: SerializedModuleHeader = serde_json::from_slice(data)?;
+        if header.version != FORMAT_VERSION {
+            return Err(
+                ModuleSerializeError::VersionMismatch(header.version, FORMAT_VERSION).into(),
+            );
+        }
+        if header.hash != hash {
+            return Err(ModuleSerializeError::HashMismatch(header.hash, hash.to_owned()).into());
+        }
+        let serialized: SerializedModule = serde_json::from_slice(data)?;
+        let module = Module::new();
+        for (name, value) in &serialized.symbols {
+            module.set(name, value.alloc(module.heap())?);
+        }
+        if let Some(docstring) = serialized.docstring {
+            module.set_docstring(docstring);
+        }
+        module.freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::environment::FrozenModule;
+    use crate::environment::Globals;
+    use crate::environment::Module;
+    use crate::eval::Evaluator;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+
+    fn eval(program: &str) -> FrozenModule {
+        let module = Module::new();
+        {
+            let mut eval = Evaluator::new(&module);
+            let ast = AstModule::parse("x.star", program.to_owned(), &Dialect::Extended).unwrap();
+            eval.eval_module(ast, &Globals::extended()).unwrap();
+        }
+        module.freeze().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let module = eval(
+            r#"
+"""Docs."""
+a = None
+b = [True, 1, 10000000000000000000000, 1.5, float("nan")]
+c = {"x": (1, "y"), 2: struct(z = [])}
+_private = 1
+"#,
+        );
+        let data = module.serialize("h").unwrap();
+        let module2 = FrozenModule::deserialize(&data, "h").unwrap();
+        for name in ["a", "b", "c"] {
+            assert_eq!(
+                module.get(name).unwrap().value().to_repr(),
+                module2.get(name).unwrap().value().to_repr()
+            );
+        }
+        assert!(module2.get_option("_private").unwrap().is_none());
+        assert_eq!(Some("Docs."), module2.docstring());
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let data = eval("a = 1").serialize("h").unwrap();
+        let err = FrozenModule::deserialize(&data, "other").unwrap_err();
+        assert!(
+            err.to_string().contains("hash `h`, expected `other`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let err = eval("def f(): pass").serialize("h").unwrap_err();
+        assert!(
+            err.to_string().contains("symbol `f` of type `function`"),
+            "{}",
+            err
+        );
+        // Loaded and private symbols are not serialized.
+        eval("_f = lambda: 1\nx = _f()").serialize("h").unwrap();
+        let err = eval("x = []\nx.append(x)").serialize("h").unwrap_err();
+        assert!(err.to_string().contains("nested too deeply"), "{}", err);
+    }
+}
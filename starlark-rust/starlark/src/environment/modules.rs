@@ -32,6 +32,7 @@ use itertools::Itertools;
 
 use crate::cast::transmute;
 use crate::collections::Hashed;
+use crate::docs::Doc;
 use crate::docs::DocMember;
 use crate::docs::DocModule;
 use crate::docs::DocString;
@@ -202,6 +203,15 @@ impl FrozenModule {
         self.module.all_items()
     }
 
+    /// Symbols defined in this module, excluding the ones it loaded.
+    pub(crate) fn items(&self) -> impl Iterator<Item = (FrozenStringValue, FrozenValue)> + '_ {
+        self.module.items()
+    }
+
+    pub(crate) fn docstring(&self) -> Option<&str> {
+        self.module.docstring.as_deref()
+    }
+
     /// The documentation for the module, and all of its top level values
     ///
     /// Returns `(<module documentation>, { <symbol> : <that symbol's documentation> })`
@@ -218,6 +228,15 @@ impl FrozenModule {
         }
     }
 
+    /// The documentation of the types defined in the module, such as records, for the
+    /// typechecker to find the attributes of their values, e.g. with
+    /// [`OracleDocs::new`](crate::typing::OracleDocs::new).
+    pub fn type_documentation(&self) -> Vec<Doc> {
+        self.all_items()
+            .filter_map(|(_, v)| v.to_value().type_documentation())
+            .collect()
+    }
+
     /// Retained memory info, or error if not enabled.
     pub fn aggregated_heap_profile_info(&self) -> anyhow::Result<&AggregateHeapProfileInfo> {
         match &self.module.heap_profile {
@@ -58,6 +58,7 @@ use crate::syntax::ast::ExprP;
 use crate::syntax::ast::LambdaP;
 use crate::syntax::ast::StmtP;
 use crate::syntax::lexer::TokenInt;
+use crate::values::bytes::StarlarkBytes;
 use crate::values::function::BoundMethodGen;
 use crate::values::function::FrozenBoundMethod;
 use crate::values::layout::value_not_special::FrozenValueNotSpecial;
@@ -998,6 +999,7 @@ impl AstLiteral {
             },
             AstLiteral::Float(f) => heap.alloc(f.node),
             AstLiteral::String(x) => heap.alloc(x.node.as_str()),
+            AstLiteral::Bytes(x) => heap.alloc(StarlarkBytes::new(&x.node)),
         }
     }
 }
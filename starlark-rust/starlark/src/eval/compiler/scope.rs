@@ -881,7 +881,7 @@ impl ScopeData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ResolvedIdent {
     Slot((Slot, BindingId)),
     Global(FrozenValue),
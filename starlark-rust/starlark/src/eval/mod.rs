@@ -36,6 +36,7 @@ pub use runtime::params::ParametersParser;
 pub use runtime::params::ParametersSpec;
 pub use runtime::params::ParametersSpecBuilder;
 pub use runtime::profile::data::ProfileData;
+pub use runtime::profile::diff::ProfileDiff;
 pub use runtime::profile::ProfileMode;
 
 use crate::collections::symbol_map::Symbol;
@@ -49,6 +50,7 @@ use crate::eval::compiler::scope::ScopeId;
 use crate::eval::compiler::Compiler;
 use crate::eval::runtime::arguments::ArgNames;
 use crate::eval::runtime::arguments::ArgumentsFull;
+use crate::eval::runtime::profile::or_instrumentation::ProfileOrInstrumentationMode;
 use crate::hint::unlikely;
 use crate::slice_vec_ext::SliceExt;
 use crate::syntax::ast::AstModule;
@@ -67,6 +69,12 @@ impl<'v, 'a> Evaluator<'v, 'a> {
             dialect,
         } = ast;
 
+        if self.profile_or_instrumentation_mode
+            == ProfileOrInstrumentationMode::Profile(ProfileMode::Coverage)
+        {
+            self.stmt_profile.add_module_stmts(&codemap, &statement);
+        }
+
         let codemap = self
             .module_env
             .frozen_heap()
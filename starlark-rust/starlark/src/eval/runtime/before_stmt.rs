@@ -46,6 +46,18 @@ impl<'a> BeforeStmtFunc<'a> {
             BeforeStmtFunc::Dyn(d) => d.call(span, eval),
         }
     }
+
+    pub(crate) fn on_error<'v>(
+        &mut self,
+        span: FileSpanRef,
+        error: &anyhow::Error,
+        eval: &mut Evaluator<'v, 'a>,
+    ) {
+        match self {
+            BeforeStmtFunc::Fn(_) => {}
+            BeforeStmtFunc::Dyn(d) => d.on_error(span, error, eval),
+        }
+    }
 }
 
 /// This is used by DAP, and it is not public API.
@@ -56,6 +68,17 @@ pub trait BeforeStmtFuncDyn<'a> {
     // TODO(cjhopman): pull DAP into the crate, and hide this function.
     #[doc(hidden)]
     fn call<'v>(&mut self, span: FileSpanRef, eval: &mut Evaluator<'v, 'a>);
+
+    /// Called when evaluating the code at `span` raises an error.
+    /// This is used by DAP, and it is not public API.
+    #[doc(hidden)]
+    fn on_error<'v>(
+        &mut self,
+        _span: FileSpanRef,
+        _error: &anyhow::Error,
+        _eval: &mut Evaluator<'v, 'a>,
+    ) {
+    }
 }
 
 impl<'a> BeforeStmt<'a> {
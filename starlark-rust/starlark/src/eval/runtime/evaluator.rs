@@ -51,6 +51,7 @@ use crate::eval::runtime::before_stmt::BeforeStmtFunc;
 use crate::eval::runtime::call_stack::CheapCallStack;
 use crate::eval::runtime::frame_span::FrameSpan;
 use crate::eval::runtime::inlined_frame::InlinedFrames;
+use crate::eval::runtime::limits::EvalLimits;
 use crate::eval::runtime::profile::bc::BcProfile;
 use crate::eval::runtime::profile::data::ProfileData;
 use crate::eval::runtime::profile::heap::HeapProfile;
@@ -95,10 +96,6 @@ pub(crate) enum EvaluatorError {
     ProfileOrInstrumentationAlreadyEnabled,
     #[error("Top frame is not def (internal error)")]
     TopFrameNotDef,
-    #[error(
-        "Coverage profile generation not implemented (but can be obtained with `.coverage()` function)"
-    )]
-    CoverageNotImplemented,
     #[error("Coverage not enabled")]
     CoverageNotEnabled,
 }
@@ -137,7 +134,7 @@ pub struct Evaluator<'v, 'a> {
     // Profiling or instrumentation enabled.
     pub(crate) profile_or_instrumentation_mode: ProfileOrInstrumentationMode,
     // Used for line profiling
-    stmt_profile: StmtProfile,
+    pub(crate) stmt_profile: StmtProfile,
     // Holds things that require hooking into evaluation.
     eval_instrumentation: EvaluationInstrumentation<'a>,
     // Total time spent in runtime typechecking.
@@ -166,7 +163,9 @@ struct EvaluationInstrumentation<'a> {
     bc_profile: BcProfile,
     // Extra functions to run on each statement, usually empty
     before_stmt: BeforeStmt<'a>,
-    // Whether we need to instrument evaluation or not, should be set if before_stmt or bc_profile are enabled.
+    // Instruction and heap limits, usually unset.
+    limits: EvalLimits,
+    // Whether we need to instrument evaluation or not, should be set if before_stmt, bc_profile or limits are enabled.
     enabled: bool,
 }
 
@@ -175,13 +174,15 @@ impl<'a> EvaluationInstrumentation<'a> {
         Self {
             bc_profile: BcProfile::new(),
             before_stmt: BeforeStmt::default(),
+            limits: EvalLimits::default(),
             enabled: false,
         }
     }
 
     fn change<F: FnOnce(&mut EvaluationInstrumentation<'a>)>(&mut self, f: F) {
         f(self);
-        self.enabled = self.bc_profile.enabled() || self.before_stmt.enabled();
+        self.enabled =
+            self.bc_profile.enabled() || self.before_stmt.enabled() || self.limits.enabled();
     }
 }
 
@@ -325,7 +326,7 @@ impl<'v, 'a> Evaluator<'v, 'a> {
                 Err(EvaluatorError::RetainedMemoryProfilingCannotBeObtainedFromEvaluator.into())
             }
             ProfileMode::Statement => self.stmt_profile.gen(),
-            ProfileMode::Coverage => Err(EvaluatorError::CoverageNotImplemented.into()),
+            ProfileMode::Coverage => self.stmt_profile.gen_coverage(),
             ProfileMode::Bytecode => self.gen_bc_profile(),
             ProfileMode::BytecodePairs => self.gen_bc_pairs_profile(),
             ProfileMode::TimeFlame => self.flame_profile.gen(),
@@ -398,6 +399,28 @@ impl<'v, 'a> Evaluator<'v, 'a> {
         self.before_stmt(f)
     }
 
+    /// Abort evaluation with an error once more than `max` bytecode instructions
+    /// have been executed by this evaluator.
+    ///
+    /// Useful to stop untrusted code which loops forever. The count is cumulative
+    /// over everything this evaluator runs, and the error carries the call stack.
+    pub fn set_max_instructions(&mut self, max: u64) {
+        self.eval_instrumentation
+            .change(|v| v.limits.max_instructions = Some(max))
+    }
+
+    /// Abort evaluation with an error once more than `max` bytes are allocated on the heap.
+    ///
+    /// Operations whose allocation size depends on their arguments (e.g. string or list
+    /// repetition) fail before allocating past the limit, and the heap size is also checked
+    /// before each bytecode instruction. Memory freed by garbage collection is not counted.
+    /// The limit stays set on the module's heap.
+    pub fn set_max_heap_bytes(&mut self, max: usize) {
+        self.heap().set_max_allocated_bytes(Some(max));
+        self.eval_instrumentation
+            .change(|v| v.limits.max_heap_bytes = Some(max))
+    }
+
     /// Set the handler invoked when `print` function is used.
     pub fn set_print_handler(&mut self, handler: &'a (dyn PrintHandler + 'a)) {
         self.print_handler = handler;
@@ -743,6 +766,7 @@ impl<'v, 'a> Evaluator<'v, 'a> {
             &mut EvalCallbacksEnabled {
                 bc_profile: self.eval_instrumentation.bc_profile.enabled(),
                 before_stmt: self.eval_instrumentation.before_stmt.enabled(),
+                limits: self.eval_instrumentation.limits.enabled(),
                 stmt_locs: &bc.instrs.stmt_locs,
                 bc_start_ptr: bc.instrs.start_ptr(),
             },
@@ -760,19 +784,38 @@ impl<'v, 'a> Evaluator<'v, 'a> {
 }
 
 pub(crate) trait EvaluationCallbacks {
-    fn before_instr(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _opcode: BcOpcode);
+    /// Called before each instruction, an error aborts evaluation.
+    fn before_instr(
+        &mut self,
+        _eval: &mut Evaluator,
+        _ip: BcPtrAddr,
+        _opcode: BcOpcode,
+    ) -> anyhow::Result<()>;
+    /// Called when the instruction at `ip` fails, before the error is given its span.
+    fn on_error(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _e: &anyhow::Error);
 }
 
 pub(crate) struct EvalCallbacksDisabled;
 
 impl EvaluationCallbacks for EvalCallbacksDisabled {
     #[inline(always)]
-    fn before_instr(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _opcode: BcOpcode) {}
+    fn before_instr(
+        &mut self,
+        _eval: &mut Evaluator,
+        _ip: BcPtrAddr,
+        _opcode: BcOpcode,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn on_error(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _e: &anyhow::Error) {}
 }
 
 pub(crate) struct EvalCallbacksEnabled<'a> {
     pub(crate) bc_profile: bool,
     pub(crate) before_stmt: bool,
+    pub(crate) limits: bool,
     pub(crate) stmt_locs: &'a BcStatementLocations,
     pub(crate) bc_start_ptr: BcPtrAddr<'a>,
 }
@@ -788,13 +831,30 @@ impl<'a> EvalCallbacksEnabled<'a> {
 
 impl<'a> EvaluationCallbacks for EvalCallbacksEnabled<'a> {
     #[inline(always)]
-    fn before_instr(&mut self, eval: &mut Evaluator, ip: BcPtrAddr, opcode: BcOpcode) {
+    fn before_instr(
+        &mut self,
+        eval: &mut Evaluator,
+        ip: BcPtrAddr,
+        opcode: BcOpcode,
+    ) -> anyhow::Result<()> {
+        if self.limits {
+            let heap = eval.heap();
+            eval.eval_instrumentation.limits.before_instr(heap)?;
+        }
         if self.bc_profile {
             eval.eval_instrumentation.bc_profile.before_instr(opcode)
         }
         if self.before_stmt {
             self.before_stmt(eval, ip);
         }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn on_error(&mut self, eval: &mut Evaluator, ip: BcPtrAddr, e: &anyhow::Error) {
+        if self.before_stmt {
+            on_error(Bc::slow_arg_at_ptr(ip).span, e, eval);
+        }
     }
 }
 
@@ -817,3 +877,25 @@ pub(crate) fn before_stmt(span: FrameSpan, eval: &mut Evaluator) {
         "`before_stmt` cannot be modified during evaluation"
     );
 }
+
+// Tell the `before_stmt` functions about an error raised by the instruction at `span`,
+// so the debugger can stop on it while the failing frame is still live.
+//
+// Errors which already have a span were raised (and reported) in a callee, and are only
+// passing through this frame.
+#[cold]
+#[inline(never)]
+fn on_error(span: FrameSpan, e: &anyhow::Error, eval: &mut Evaluator) {
+    if let Some(Diagnostic { span: Some(_), .. }) = e.downcast_ref::<Diagnostic>() {
+        return;
+    }
+    let mut fs = mem::take(&mut eval.eval_instrumentation.before_stmt.before_stmt);
+    for f in &mut fs {
+        f.on_error(span.span.file_span_ref(), e, eval)
+    }
+    let added = mem::replace(&mut eval.eval_instrumentation.before_stmt.before_stmt, fs);
+    assert!(
+        added.is_empty(),
+        "`before_stmt` cannot be modified during evaluation"
+    );
+}
@@ -0,0 +1,70 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Limits on the resources a single evaluation can use.
+
+use thiserror::Error;
+
+use crate::values::Heap;
+
+#[derive(Error, Debug)]
+pub(crate) enum EvalLimitsError {
+    #[error("Instruction limit of {0} exceeded")]
+    InstructionLimitExceeded(u64),
+    #[error("Heap limit of {0} bytes exceeded, {1} bytes allocated")]
+    HeapLimitExceeded(usize, usize),
+    #[error(
+        "Heap limit of {0} bytes exceeded by allocating {1} bytes, {2} bytes already allocated"
+    )]
+    HeapLimitAllocation(usize, usize, usize),
+}
+
+/// Instruction and heap limits, checked before each bytecode instruction.
+///
+/// The heap limit is also set on the heap, to check large allocations as they happen.
+#[derive(Default)]
+pub(crate) struct EvalLimits {
+    /// Maximum number of bytecode instructions to execute.
+    pub(crate) max_instructions: Option<u64>,
+    /// Number of instructions executed so far, only counted if `max_instructions` is set.
+    pub(crate) instructions: u64,
+    /// Maximum number of bytes allocated on the evaluation heap.
+    pub(crate) max_heap_bytes: Option<usize>,
+}
+
+impl EvalLimits {
+    pub(crate) fn enabled(&self) -> bool {
+        self.max_instructions.is_some() || self.max_heap_bytes.is_some()
+    }
+
+    #[inline(always)]
+    pub(crate) fn before_instr(&mut self, heap: &Heap) -> anyhow::Result<()> {
+        if let Some(max) = self.max_instructions {
+            self.instructions += 1;
+            if self.instructions > max {
+                return Err(EvalLimitsError::InstructionLimitExceeded(max).into());
+            }
+        }
+        if let Some(max) = self.max_heap_bytes {
+            let allocated = heap.allocated_bytes();
+            if allocated > max {
+                return Err(EvalLimitsError::HeapLimitExceeded(max, allocated).into());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Line coverage, written in
+//! [LCOV](https://manpages.debian.org/unstable/lcov/geninfo.1.en.html#FILES) or
+//! [Cobertura](https://github.com/cobertura/web/blob/master/htdocs/xml/coverage-04.dtd) format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How many times each line with a statement was executed, by file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CoverageData {
+    /// Lines are 1-based. Lines with statements that never ran are present with a count of zero.
+    files: BTreeMap<String, BTreeMap<usize, usize>>,
+}
+
+impl CoverageData {
+    /// Record that the statement starting at `line` of `file` exists, and ran `count` times.
+    pub(crate) fn add(&mut self, file: &str, line: usize, count: usize) {
+        let lines = match self.files.get_mut(file) {
+            Some(lines) => lines,
+            None => self.files.entry(file.to_owned()).or_default(),
+        };
+        // With several statements on a line, report the line as often as any of them ran.
+        let c = lines.entry(line).or_default();
+        *c = (*c).max(count);
+    }
+
+    /// Merge coverage of separate evaluations, e.g. of the same file for different packages.
+    pub(crate) fn merge<'a>(xs: impl IntoIterator<Item = &'a CoverageData>) -> CoverageData {
+        let mut result = CoverageData::default();
+        for x in xs {
+            for (file, lines) in &x.files {
+                let result = result.files.entry(file.clone()).or_default();
+                for (line, count) in lines {
+                    *result.entry(*line).or_default() += count;
+                }
+            }
+        }
+        result
+    }
+
+    /// Generate an LCOV tracefile, with a record per file.
+    pub(crate) fn gen_lcov(&self) -> String {
+        let mut s = String::new();
+        for (file, lines) in &self.files {
+            writeln!(s, "TN:").unwrap();
+            writeln!(s, "SF:{}", file).unwrap();
+            for (line, count) in lines {
+                writeln!(s, "DA:{},{}", line, count).unwrap();
+            }
+            writeln!(s, "LF:{}", lines.len()).unwrap();
+            writeln!(s, "LH:{}", lines.values().filter(|c| **c != 0).count()).unwrap();
+            writeln!(s, "end_of_record").unwrap();
+        }
+        s
+    }
+
+    /// Generate a Cobertura XML report, with a class per file. Branches are not tracked.
+    pub(crate) fn gen_cobertura(&self) -> String {
+        fn covered(lines: &BTreeMap<usize, usize>) -> usize {
+            lines.values().filter(|c| **c != 0).count()
+        }
+
+        // Files without statements are fully covered.
+        fn rate(covered: usize, valid: usize) -> f64 {
+            if valid == 0 {
+                1.0
+            } else {
+                covered as f64 / valid as f64
+            }
+        }
+
+        let covered_total: usize = self.files.values().map(covered).sum();
+        let valid_total: usize = self.files.values().map(|lines| lines.len()).sum();
+        let rate_total = rate(covered_total, valid_total);
+
+        let mut s = String::new();
+        writeln!(s, r#"<?xml version="1.0" ?>"#).unwrap();
+        writeln!(
+            s,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        )
+        .unwrap();
+        writeln!(
+            s,
+            r#"<coverage line-rate="{}" branch-rate="0" lines-covered="{}" lines-valid="{}" branches-covered="0" branches-valid="0" complexity="0" version="0" timestamp="0">"#,
+            rate_total, covered_total, valid_total
+        )
+        .unwrap();
+        writeln!(s, r#"  <packages>"#).unwrap();
+        writeln!(
+            s,
+            r#"    <package name="" line-rate="{}" branch-rate="0" complexity="0">"#,
+            rate_total
+        )
+        .unwrap();
+        writeln!(s, r#"      <classes>"#).unwrap();
+        for (file, lines) in &self.files {
+            let file = xml_escape(file);
+            writeln!(
+                s,
+                r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="0" complexity="0">"#,
+                file,
+                file,
+                rate(covered(lines), lines.len())
+            )
+            .unwrap();
+            writeln!(s, r#"          <methods/>"#).unwrap();
+            writeln!(s, r#"          <lines>"#).unwrap();
+            for (line, count) in lines {
+                writeln!(
+                    s,
+                    r#"            <line number="{}" hits="{}"/>"#,
+                    line, count
+                )
+                .unwrap();
+            }
+            writeln!(s, r#"          </lines>"#).unwrap();
+            writeln!(s, r#"        </class>"#).unwrap();
+        }
+        writeln!(s, r#"      </classes>"#).unwrap();
+        writeln!(s, r#"    </package>"#).unwrap();
+        writeln!(s, r#"  </packages>"#).unwrap();
+        writeln!(s, r#"</coverage>"#).unwrap();
+        s
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut r = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => r.push_str("&amp;"),
+            '<' => r.push_str("&lt;"),
+            '>' => r.push_str("&gt;"),
+            '"' => r.push_str("&quot;"),
+            '\'' => r.push_str("&apos;"),
+            c => r.push(c),
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::environment::Globals;
+    use crate::environment::Module;
+    use crate::eval::runtime::profile::coverage::CoverageData;
+    use crate::eval::Evaluator;
+    use crate::eval::ProfileData;
+    use crate::eval::ProfileMode;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+
+    fn coverage(program: &str) -> ProfileData {
+        let module = Module::new();
+        let mut eval = Evaluator::new(&module);
+        eval.enable_profile(&ProfileMode::Coverage).unwrap();
+        let ast = AstModule::parse("cov.star", program.to_owned(), &Dialect::Extended).unwrap();
+        eval.eval_module(ast, &Globals::standard()).unwrap();
+        eval.gen_profile().unwrap()
+    }
+
+    #[test]
+    fn test_lcov() {
+        let profile = coverage(
+            r#"
+def f(x):
+    if x:
+        return 1
+    else:
+        return 2
+
+f(True); f(True)
+"#,
+        );
+        assert_eq!(
+            "\
+TN:
+SF:cov.star
+DA:2,1
+DA:3,2
+DA:4,2
+DA:6,0
+DA:8,1
+LF:5
+LH:4
+end_of_record
+",
+            profile.gen().unwrap()
+        );
+
+        let merged = ProfileData::merge([&profile, &profile]).unwrap();
+        assert!(merged.gen().unwrap().contains("DA:3,4\n"));
+    }
+
+    #[test]
+    fn test_cobertura() {
+        let profile = coverage(
+            r#"
+def f(x):
+    if x:
+        return 1
+    else:
+        return 2
+
+f(True)
+"#,
+        );
+        let report = profile.gen_cobertura().unwrap();
+        assert!(
+            report.contains(
+                r#"<coverage line-rate="0.8" branch-rate="0" lines-covered="4" lines-valid="5""#
+            ),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains(r#"<class name="cov.star" filename="cov.star" line-rate="0.8""#),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains(r#"<line number="3" hits="1"/>"#),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains(r#"<line number="6" hits="0"/>"#),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = CoverageData::default();
+        a.add("a.bzl", 1, 1);
+        a.add("a.bzl", 2, 0);
+        let mut b = CoverageData::default();
+        b.add("a.bzl", 2, 3);
+        b.add("b.bzl", 1, 0);
+        assert_eq!(
+            "\
+TN:
+SF:a.bzl
+DA:1,1
+DA:2,3
+LF:2
+LH:2
+end_of_record
+TN:
+SF:b.bzl
+DA:1,0
+LF:1
+LH:0
+end_of_record
+",
+            CoverageData::merge([&a, &b]).gen_lcov()
+        );
+    }
+}
@@ -15,7 +15,7 @@
  * limitations under the License.
  */
 
-//! Write CSV files.
+//! Write and read CSV files.
 
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -100,6 +100,42 @@ impl CsvWriter {
     }
 }
 
+/// Read the rows of a CSV file written by [`CsvWriter`], including the header row.
+pub(crate) fn read_csv(csv: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = csv.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow::anyhow!("Unterminated quoted field in CSV"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
 pub(crate) trait CsvValue {
     fn format_for_csv(&self) -> String;
 }
@@ -134,6 +170,19 @@ impl CsvValue for u64 {
     }
 }
 
+impl CsvValue for i64 {
+    fn format_for_csv(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Seconds, formatted like [`SmallDuration`], but may be negative.
+impl CsvValue for f64 {
+    fn format_for_csv(&self) -> String {
+        format!("{:.3}", self)
+    }
+}
+
 impl CsvValue for i32 {
     fn format_for_csv(&self) -> String {
         self.to_string()
@@ -149,6 +198,7 @@ impl CsvValue for u128 {
 #[cfg(test)]
 mod tests {
     use crate::eval::runtime::profile::csv::quote_str_for_csv;
+    use crate::eval::runtime::profile::csv::read_csv;
     use crate::eval::runtime::profile::csv::CsvWriter;
     use crate::eval::runtime::small_duration::SmallDuration;
 
@@ -173,6 +223,26 @@ File,Count,Duration
         )
     }
 
+    #[test]
+    fn test_read_csv() {
+        let mut csv = CsvWriter::new(["Function", "Count"]);
+        csv.write_value("a,\"b\"\nc");
+        csv.write_value(10);
+        csv.finish_row();
+        csv.write_value("");
+        csv.write_value(20);
+        csv.finish_row();
+        assert_eq!(
+            vec![
+                vec!["Function", "Count"],
+                vec!["a,\"b\"\nc", "10"],
+                vec!["", "20"],
+            ],
+            read_csv(&csv.finish()).unwrap()
+        );
+        assert!(read_csv("\"a").is_err());
+    }
+
     #[test]
     fn test_quote_str_for_csv() {
         assert_eq!("\"a\"", quote_str_for_csv("a"));
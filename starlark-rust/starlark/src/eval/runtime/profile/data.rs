@@ -23,6 +23,7 @@ use dupe::Dupe;
 
 use crate::eval::runtime::profile::bc::BcPairsProfileData;
 use crate::eval::runtime::profile::bc::BcProfileData;
+use crate::eval::runtime::profile::coverage::CoverageData;
 use crate::eval::runtime::profile::flamegraph::FlameGraphData;
 use crate::eval::ProfileMode;
 use crate::slice_vec_ext::SliceExt;
@@ -38,6 +39,8 @@ enum ProfileDataError {
     DifferentProfileModes,
     #[error("Merge of profile data for profile mode `{0}` is not implemented")]
     MergeNotImplemented(ProfileMode),
+    #[error("Profile mode `{0}` does not collect coverage")]
+    NotCoverage(ProfileMode),
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +50,7 @@ pub(crate) enum ProfileDataImpl {
     AggregateHeapProfileInfo(Box<AggregateHeapProfileInfo>),
     /// Flame graph data is in milliseconds.
     TimeFlameProfile(FlameGraphData),
+    Coverage(CoverageData),
     Other(String),
 }
 
@@ -87,6 +91,18 @@ impl ProfileData {
             (ProfileDataImpl::TimeFlameProfile(_), _) => {
                 Err(ProfileDataError::ProfileDataNotConsistent.into())
             }
+            (ProfileDataImpl::Coverage(data), ProfileMode::Coverage) => Ok(data.gen_lcov()),
+            (ProfileDataImpl::Coverage(_), _) => {
+                Err(ProfileDataError::ProfileDataNotConsistent.into())
+            }
+        }
+    }
+
+    /// Generate a Cobertura XML report from coverage profile data. `gen` generates LCOV.
+    pub fn gen_cobertura(&self) -> anyhow::Result<String> {
+        match &self.profile {
+            ProfileDataImpl::Coverage(data) => Ok(data.gen_cobertura()),
+            _ => Err(ProfileDataError::NotCoverage(self.profile_mode.dupe()).into()),
         }
     }
 
@@ -152,6 +168,14 @@ impl ProfileData {
                 let profile = FlameGraphData::merge(profiles);
                 ProfileDataImpl::TimeFlameProfile(profile)
             }
+            ProfileMode::Coverage => {
+                let profiles = profiles.try_map(|p| match &p.profile {
+                    ProfileDataImpl::Coverage(data) => Ok(data),
+                    _ => Err(ProfileDataError::ProfileDataNotConsistent),
+                })?;
+                let profile = CoverageData::merge(profiles);
+                ProfileDataImpl::Coverage(profile)
+            }
             profile_mode => {
                 return Err(ProfileDataError::MergeNotImplemented(profile_mode.dupe()).into());
             }
@@ -168,6 +192,7 @@ mod tests {
     use dupe::Dupe;
 
     use crate::eval::runtime::profile::bc::BcPairsProfileData;
+    use crate::eval::runtime::profile::coverage::CoverageData;
     use crate::eval::runtime::profile::data::ProfileDataImpl;
     use crate::eval::runtime::profile::flamegraph::FlameGraphData;
     use crate::eval::ProfileData;
@@ -210,6 +235,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merge_coverage() {
+        let profile = ProfileData {
+            profile_mode: ProfileMode::Coverage,
+            profile: ProfileDataImpl::Coverage(CoverageData::default()),
+        };
+        // Smoke.
+        ProfileData::merge([&profile, &profile]).unwrap();
+    }
+
     #[test]
     fn merge_time_flame() {
         let profile = ProfileData {
@@ -0,0 +1,287 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compare the heap summary profiles of two runs, e.g. before and after a change.
+
+use crate::collections::SmallMap;
+use crate::eval::runtime::profile::csv::read_csv;
+use crate::eval::runtime::profile::csv::CsvWriter;
+use crate::eval::runtime::small_duration::SmallDuration;
+
+#[derive(Debug, thiserror::Error)]
+enum ProfileDiffError {
+    #[error("Heap summary profile is empty")]
+    Empty,
+    #[error("Heap summary profile has no `{0}` column")]
+    MissingColumn(&'static str),
+    #[error("Heap summary profile row {0} has {1} columns, expected {2}")]
+    WrongColumnCount(usize, usize, usize),
+    #[error("Heap summary profile row {0} has invalid `{1}` value `{2}`")]
+    InvalidValue(usize, &'static str, String),
+}
+
+/// What a heap summary profile records for a function.
+#[derive(Default, Clone, Copy, Debug)]
+struct FuncSummary {
+    time: SmallDuration,
+    bytes: u64,
+}
+
+/// Per-function information read back from `heap-summary-*` profile output.
+#[derive(Default, Debug)]
+struct HeapSummary {
+    funcs: SmallMap<String, FuncSummary>,
+}
+
+impl HeapSummary {
+    fn parse(csv: &str) -> anyhow::Result<HeapSummary> {
+        let mut rows = read_csv(csv)?.into_iter();
+        let header = rows.next().ok_or(ProfileDiffError::Empty)?;
+        let column = |name: &'static str| {
+            header
+                .iter()
+                .position(|c| c == name)
+                .ok_or(ProfileDiffError::MissingColumn(name))
+        };
+        let function = column("Function")?;
+        let time = column("Time(s)")?;
+        let bytes = column("AllocBytes")?;
+
+        let mut funcs = SmallMap::new();
+        for (i, row) in rows.enumerate() {
+            // The header is row 1.
+            let row_number = i + 2;
+            if row.len() != header.len() {
+                return Err(ProfileDiffError::WrongColumnCount(
+                    row_number,
+                    row.len(),
+                    header.len(),
+                )
+                .into());
+            }
+            let secs: f64 = row[time].parse().map_err(|_| {
+                ProfileDiffError::InvalidValue(row_number, "Time(s)", row[time].clone())
+            })?;
+            let bytes: u64 = row[bytes].parse().map_err(|_| {
+                ProfileDiffError::InvalidValue(row_number, "AllocBytes", row[bytes].clone())
+            })?;
+            let summary: &mut FuncSummary = funcs.entry(row[function].clone()).or_default();
+            summary.time += SmallDuration {
+                nanos: (secs * 1e9).round() as u64,
+            };
+            summary.bytes += bytes;
+        }
+        Ok(HeapSummary { funcs })
+    }
+
+    fn get(&self, func: &str) -> FuncSummary {
+        self.funcs.get(func).copied().unwrap_or_default()
+    }
+}
+
+/// The same profile mode, before and after.
+#[derive(Debug)]
+struct HeapSummaryPair {
+    before: HeapSummary,
+    after: HeapSummary,
+}
+
+impl HeapSummaryPair {
+    fn parse(before: &str, after: &str) -> anyhow::Result<HeapSummaryPair> {
+        Ok(HeapSummaryPair {
+            before: HeapSummary::parse(before)?,
+            after: HeapSummary::parse(after)?,
+        })
+    }
+
+    fn funcs(&self) -> impl Iterator<Item = &str> {
+        self.before
+            .funcs
+            .keys()
+            .chain(self.after.funcs.keys())
+            .map(|f| f.as_str())
+    }
+}
+
+/// Per-function differences in self time, allocated bytes and retained bytes between two runs,
+/// computed from the CSV output of the `heap-summary-allocated` and `heap-summary-retained`
+/// profile modes.
+#[derive(Debug)]
+pub struct ProfileDiff {
+    allocated: HeapSummaryPair,
+    retained: Option<HeapSummaryPair>,
+}
+
+impl ProfileDiff {
+    /// Compare two `heap-summary-allocated` profiles, which provide self time and
+    /// allocated bytes.
+    pub fn new(before: &str, after: &str) -> anyhow::Result<ProfileDiff> {
+        Ok(ProfileDiff {
+            allocated: HeapSummaryPair::parse(before, after)?,
+            retained: None,
+        })
+    }
+
+    /// Also compare two `heap-summary-retained` profiles, which provide retained bytes.
+    pub fn with_retained(self, before: &str, after: &str) -> anyhow::Result<ProfileDiff> {
+        Ok(ProfileDiff {
+            retained: Some(HeapSummaryPair::parse(before, after)?),
+            ..self
+        })
+    }
+
+    /// Generate CSV with a row per function, the functions whose allocated bytes grew
+    /// the most first, and then those whose self time grew the most.
+    pub fn gen_csv(&self) -> String {
+        let mut funcs: Vec<&str> = self.allocated.funcs().collect();
+        if let Some(retained) = &self.retained {
+            funcs.extend(retained.funcs());
+        }
+        funcs.sort_unstable();
+        funcs.dedup();
+
+        let alloc_delta = |f: &str| {
+            self.allocated.after.get(f).bytes as i64 - self.allocated.before.get(f).bytes as i64
+        };
+        let time_delta = |f: &str| {
+            self.allocated.after.get(f).time.nanos as i64
+                - self.allocated.before.get(f).time.nanos as i64
+        };
+        // Keep the totals at the top, as in the heap summary itself.
+        funcs.sort_by_key(|f| (*f != "TOTALS", -alloc_delta(f), -time_delta(f)));
+
+        let mut csv = CsvWriter::new([
+            "Function",
+            "Time(s)Before",
+            "Time(s)After",
+            "Time(s)Delta",
+            "AllocBytesBefore",
+            "AllocBytesAfter",
+            "AllocBytesDelta",
+            "RetainedBytesBefore",
+            "RetainedBytesAfter",
+            "RetainedBytesDelta",
+        ]);
+        for f in funcs {
+            let before = self.allocated.before.get(f);
+            let after = self.allocated.after.get(f);
+            csv.write_value(f);
+            csv.write_value(before.time);
+            csv.write_value(after.time);
+            csv.write_value(time_delta(f) as f64 / 1e9);
+            csv.write_value(before.bytes);
+            csv.write_value(after.bytes);
+            csv.write_value(alloc_delta(f));
+            match &self.retained {
+                Some(retained) => {
+                    let before = retained.before.get(f).bytes;
+                    let after = retained.after.get(f).bytes;
+                    csv.write_value(before);
+                    csv.write_value(after);
+                    csv.write_value(after as i64 - before as i64);
+                }
+                None => {
+                    csv.write_value("");
+                    csv.write_value("");
+                    csv.write_value("");
+                }
+            }
+            csv.finish_row();
+        }
+        csv.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::environment::Globals;
+    use crate::environment::Module;
+    use crate::eval::runtime::profile::csv::read_csv;
+    use crate::eval::Evaluator;
+    use crate::eval::ProfileDiff;
+    use crate::eval::ProfileMode;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+
+    fn heap_summary(program: &str) -> String {
+        let ast = AstModule::parse("x.star", program.to_owned(), &Dialect::Extended).unwrap();
+        let module = Module::new();
+        let mut eval = Evaluator::new(&module);
+        eval.enable_profile(&ProfileMode::HeapSummaryAllocated)
+            .unwrap();
+        eval.eval_module(ast, &Globals::standard()).unwrap();
+        eval.gen_profile().unwrap().gen().unwrap()
+    }
+
+    /// Finds the row of a function, named as in the heap summary, i.e. qualified by its file.
+    fn row<'a>(rows: &'a [Vec<String>], name: &str) -> anyhow::Result<&'a [String]> {
+        rows.iter()
+            .find(|r| r[0] == name)
+            .map(|r| r.as_slice())
+            .ok_or_else(|| anyhow::anyhow!("No row for `{}` in {:?}", name, rows))
+    }
+
+    #[test]
+    fn test_profile_diff() -> anyhow::Result<()> {
+        let before = heap_summary(
+            r#"
+def f():
+    return [1]
+def g():
+    return [2]
+f()
+g()
+"#,
+        );
+        let after = heap_summary(
+            r#"
+def f():
+    return [[x] for x in range(100)]
+def g():
+    return [2]
+f()
+g()
+"#,
+        );
+
+        let rows = read_csv(&ProfileDiff::new(&before, &after)?.gen_csv())?;
+        assert_eq!("Function", rows[0][0]);
+        assert_eq!("TOTALS", rows[1][0]);
+        let alloc_delta = |name: &str| -> anyhow::Result<i64> { Ok(row(&rows, name)?[6].parse()?) };
+        assert!(alloc_delta("x.star.f")? > 0);
+        assert_eq!(0, alloc_delta("x.star.g")?);
+        // No retained profiles were given.
+        assert_eq!("", row(&rows, "x.star.f")?[9]);
+
+        let retained_rows = read_csv(
+            &ProfileDiff::new(&before, &after)?
+                .with_retained(&before, &after)?
+                .gen_csv(),
+        )?;
+        assert_eq!(
+            row(&rows, "x.star.f")?[6],
+            row(&retained_rows, "x.star.f")?[9]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_diff_invalid() {
+        assert!(ProfileDiff::new("", "").is_err());
+        assert!(ProfileDiff::new("Function,Calls\n", "Function,Calls\n").is_err());
+    }
+}
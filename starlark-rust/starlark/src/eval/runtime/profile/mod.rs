@@ -22,8 +22,10 @@ use allocative::Allocative;
 use dupe::Dupe;
 
 pub(crate) mod bc;
+pub(crate) mod coverage;
 pub(crate) mod csv;
 pub(crate) mod data;
+pub(crate) mod diff;
 pub(crate) mod flamegraph;
 pub(crate) mod heap;
 pub(crate) mod or_instrumentation;
@@ -48,7 +50,7 @@ pub enum ProfileMode {
     HeapFlameRetained,
     /// The statement profile mode provides information about time spent in each statement.
     Statement,
-    /// Code coverage.
+    /// Code coverage, written as an LCOV tracefile with the lines of each file that ran.
     Coverage,
     /// The bytecode profile mode provides information about bytecode instructions.
     Bytecode,
@@ -29,10 +29,14 @@ use crate::codemap::FileSpan;
 use crate::codemap::FileSpanRef;
 use crate::codemap::ResolvedFileSpan;
 use crate::codemap::Span;
+use crate::eval::runtime::profile::coverage::CoverageData;
 use crate::eval::runtime::profile::csv::CsvWriter;
 use crate::eval::runtime::profile::data::ProfileData;
+use crate::eval::runtime::profile::data::ProfileDataImpl;
 use crate::eval::runtime::small_duration::SmallDuration;
 use crate::eval::ProfileMode;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::Stmt;
 
 #[derive(Debug, thiserror::Error)]
 enum StmtProfileError {
@@ -49,6 +53,10 @@ pub(crate) struct StmtProfile(Option<Box<StmtProfileData>>);
 struct StmtProfileData {
     files: HashMap<CodeMapId, CodeMap>,
     stmts: HashMap<(CodeMapId, Span), (usize, SmallDuration)>,
+    /// Statements of the evaluated modules, whether or not they ran, for coverage.
+    module_stmts: HashSet<(CodeMapId, Span)>,
+    /// The top-level statements among `module_stmts`.
+    top_level_stmts: HashSet<(CodeMapId, Span)>,
     next_file: CodeMapId,
     last_span: (CodeMapId, Span),
     last_start: Instant,
@@ -59,6 +67,8 @@ impl StmtProfileData {
         StmtProfileData {
             files: HashMap::new(),
             stmts: HashMap::new(),
+            module_stmts: HashSet::new(),
+            top_level_stmts: HashSet::new(),
             next_file: CodeMapId::EMPTY,
             last_span: (CodeMapId::EMPTY, Span::default()),
             last_start: Instant::now(),
@@ -151,6 +161,74 @@ impl StmtProfileData {
         csv.finish()
     }
 
+    fn add_module_stmts(&mut self, codemap: &CodeMap, stmt: &AstStmt) {
+        // Match the statements which are instrumented with `before_stmt`.
+        fn collect(stmt: &AstStmt, spans: &mut Vec<Span>) {
+            match &stmt.node {
+                Stmt::Statements(_) | Stmt::Pass | Stmt::Load(_) => {}
+                _ => spans.push(stmt.span),
+            }
+            stmt.visit_stmt(|x| collect(x, spans));
+        }
+
+        // Top-level statements are those of the module, flattening nested statement lists.
+        fn collect_top_level(stmt: &AstStmt, spans: &mut Vec<Span>) {
+            match &stmt.node {
+                Stmt::Statements(stmts) => {
+                    for stmt in stmts {
+                        collect_top_level(stmt, spans);
+                    }
+                }
+                Stmt::Pass | Stmt::Load(_) => {}
+                _ => spans.push(stmt.span),
+            }
+        }
+
+        let mut spans = Vec::new();
+        collect(stmt, &mut spans);
+        let mut top_level = Vec::new();
+        collect_top_level(stmt, &mut top_level);
+        self.files
+            .entry(codemap.id())
+            .or_insert_with(|| codemap.dupe());
+        self.module_stmts
+            .extend(spans.into_iter().map(|span| (codemap.id(), span)));
+        self.top_level_stmts
+            .extend(top_level.into_iter().map(|span| (codemap.id(), span)));
+    }
+
+    fn coverage_data(&self, now: Instant) -> CoverageData {
+        // As in `write_to_string`, count the statement that is running now.
+        let mut data = self.clone();
+        data.add_last(now);
+
+        let mut coverage = CoverageData::default();
+        let stmts = data
+            .module_stmts
+            .iter()
+            .map(|key| (key, 0))
+            .chain(data.stmts.iter().map(|(key, (count, _))| (key, *count)));
+        for (key, count) in stmts {
+            let (file, span) = key;
+            // A top-level statement is reported twice, the first time for the possible GC before
+            // it, but it runs at most once, as the module is only evaluated once.
+            let count = if data.top_level_stmts.contains(key) {
+                count.min(1)
+            } else {
+                count
+            };
+            if *file != CodeMapId::EMPTY {
+                let codemap = &data.files[file];
+                coverage.add(
+                    codemap.filename(),
+                    codemap.find_line(span.begin()) + 1,
+                    count,
+                );
+            }
+        }
+        coverage
+    }
+
     fn coverage(&self) -> HashSet<ResolvedFileSpan> {
         self.stmts
             .keys()
@@ -194,6 +272,25 @@ impl StmtProfile {
         }
     }
 
+    /// Record the statements of a module about to be evaluated, so coverage includes
+    /// those which never run.
+    pub(crate) fn add_module_stmts(&mut self, codemap: &CodeMap, stmt: &AstStmt) {
+        if let Some(data) = &mut self.0 {
+            data.add_module_stmts(codemap, stmt)
+        }
+    }
+
+    pub(crate) fn gen_coverage(&self) -> anyhow::Result<ProfileData> {
+        let now = Instant::now();
+        match &self.0 {
+            Some(data) => Ok(ProfileData {
+                profile_mode: ProfileMode::Coverage,
+                profile: ProfileDataImpl::Coverage(data.coverage_data(now)),
+            }),
+            None => Err(StmtProfileError::NotEnabled.into()),
+        }
+    }
+
     pub(crate) fn coverage(&self) -> anyhow::Result<HashSet<ResolvedFileSpan>> {
         Ok(self
             .0
@@ -0,0 +1,165 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Work out what sort of completion is being requested at a position in a file.
+//!
+//! Completion is requested while a file is being edited, so most of the time the file
+//! does not parse. Rather than relying on the AST, this looks at the raw text before
+//! the cursor.
+
+/// The kind of completion requested, based on the text before the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CompletionContext {
+    /// Completing an attribute, e.g. `foo.bar.b|`. Holds the dotted path before
+    /// the final `.` (`["foo", "bar"]` in the example).
+    Member(Vec<String>),
+    /// Completing a name within the argument list of a call, e.g. `foo(a = 1, b|`. Holds
+    /// the dotted path of the function being called.
+    Argument(Vec<String>),
+    /// Completing a plain name.
+    Name,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The text of `contents` before a zero based `line` and `character`.
+fn text_before(contents: &str, line: u32, character: u32) -> String {
+    let mut res = String::new();
+    for (i, l) in contents.split('\n').enumerate() {
+        if i == line as usize {
+            res.extend(l.chars().take(character as usize));
+            break;
+        }
+        res.push_str(l);
+        res.push('\n');
+    }
+    res
+}
+
+/// The dotted path of identifiers at the very end of `s`, e.g. `x = foo.bar` gives
+/// `["foo", "bar"]`. Returns an empty path if `s` does not end in one.
+fn trailing_dotted_path(s: &str) -> Vec<String> {
+    let start = s
+        .trim_end_matches(|c| is_identifier_char(c) || c == '.')
+        .len();
+    let path: Vec<_> = s[start..].split('.').collect();
+    let valid = path.iter().all(|segment| {
+        segment
+            .chars()
+            .next()
+            .map_or(false, |c| !c.is_ascii_digit())
+    });
+    if valid {
+        path.into_iter().map(str::to_owned).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+impl CompletionContext {
+    /// Determine the completion context at a zero based line and character in `contents`.
+    pub(crate) fn at_position(contents: &str, line: u32, character: u32) -> Self {
+        Self::from_prefix(&text_before(contents, line, character))
+    }
+
+    fn from_prefix(prefix: &str) -> Self {
+        // Ignore the partially typed word at the cursor.
+        let before_word = prefix.trim_end_matches(is_identifier_char);
+        if let Some(before_dot) = before_word.strip_suffix('.') {
+            let path = trailing_dotted_path(before_dot);
+            return if path.is_empty() {
+                CompletionContext::Name
+            } else {
+                CompletionContext::Member(path)
+            };
+        }
+
+        // Look backwards for an unclosed bracket. Only the arguments of a call are interesting,
+        // inside a list or dict literal we are just completing names.
+        let mut depth = 0;
+        for (i, c) in before_word.char_indices().rev() {
+            match c {
+                ')' | ']' | '}' => depth += 1,
+                '(' | '[' | '{' if depth > 0 => depth -= 1,
+                '(' => {
+                    let path = trailing_dotted_path(before_word[..i].trim_end());
+                    return if path.is_empty() {
+                        CompletionContext::Name
+                    } else {
+                        CompletionContext::Argument(path)
+                    };
+                }
+                '[' | '{' => return CompletionContext::Name,
+                _ => {}
+            }
+        }
+        CompletionContext::Name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(x: &[&str]) -> Vec<String> {
+        x.iter().map(|x| (*x).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_completion_context() {
+        assert_eq!(
+            CompletionContext::Name,
+            CompletionContext::from_prefix("fo")
+        );
+        assert_eq!(
+            CompletionContext::Name,
+            CompletionContext::from_prefix("x = 1.")
+        );
+        assert_eq!(
+            CompletionContext::Member(path(&["foo"])),
+            CompletionContext::from_prefix("x = foo.")
+        );
+        assert_eq!(
+            CompletionContext::Member(path(&["foo", "bar"])),
+            CompletionContext::from_prefix("foo.bar.ba")
+        );
+        assert_eq!(
+            CompletionContext::Argument(path(&["rule"])),
+            CompletionContext::from_prefix("rule(\n    name = \"x\",\n    sr")
+        );
+        assert_eq!(
+            CompletionContext::Argument(path(&["foo", "bar"])),
+            CompletionContext::from_prefix("foo.bar(baz(1), ")
+        );
+        assert_eq!(
+            CompletionContext::Name,
+            CompletionContext::from_prefix("rule(\n    srcs = [\n        ")
+        );
+        assert_eq!(
+            CompletionContext::Name,
+            CompletionContext::from_prefix("(1, ")
+        );
+    }
+
+    #[test]
+    fn test_text_before() {
+        assert_eq!("ab\ncd", text_before("ab\ncde\nf", 1, 2));
+        assert_eq!("ab\n", text_before("ab\n", 1, 5));
+    }
+}
@@ -0,0 +1,235 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Find the outline of a file, i.e. the symbols it defines and where they are nested.
+
+use lsp_types::DocumentSymbol;
+use lsp_types::SymbolKind as LspSymbolKind;
+
+use crate::analysis::exported::SymbolKind;
+use crate::codemap::CodeMap;
+use crate::codemap::Span;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::Expr;
+use crate::syntax::ast::StmtP;
+use crate::syntax::AstModule;
+
+/// The LSP equivalent of the kind of a symbol.
+pub(crate) fn lsp_symbol_kind(kind: SymbolKind) -> LspSymbolKind {
+    match kind {
+        SymbolKind::Function => LspSymbolKind::FUNCTION,
+        SymbolKind::Any => LspSymbolKind::VARIABLE,
+    }
+}
+
+#[allow(deprecated)] // The `deprecated` field is deprecated, but has to be filled in.
+fn make_symbol(
+    codemap: &CodeMap,
+    name: &str,
+    detail: Option<String>,
+    kind: LspSymbolKind,
+    span: Span,
+    selection_span: Span,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_owned(),
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: codemap.resolve_span(span).into(),
+        selection_range: codemap.resolve_span(selection_span).into(),
+        children,
+    }
+}
+
+/// A call with a `name = "..."` argument, which in a build file is usually a target.
+fn target_symbol(codemap: &CodeMap, expr: &AstExpr) -> Option<DocumentSymbol> {
+    let (function, args) = match &**expr {
+        Expr::Call(function, args) => (function, args),
+        _ => return None,
+    };
+    args.iter().find_map(|arg| match &**arg {
+        ArgumentP::Named(arg_name, value) if arg_name.node == "name" => match &**value {
+            Expr::Literal(AstLiteral::String(name)) => Some(make_symbol(
+                codemap,
+                &name.node,
+                Some(function.node.to_string()),
+                LspSymbolKind::CONSTRUCTOR,
+                expr.span,
+                name.span,
+                None,
+            )),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn walk(codemap: &CodeMap, stmt: &AstStmt, symbols: &mut Vec<DocumentSymbol>) {
+    match &**stmt {
+        StmtP::Load(load) => {
+            let children = load
+                .args
+                .iter()
+                .map(|(name, symbol)| {
+                    make_symbol(
+                        codemap,
+                        &name.0,
+                        // Only worth showing the original name if it was renamed.
+                        (name.0 != symbol.node).then(|| symbol.node.clone()),
+                        LspSymbolKind::VARIABLE,
+                        name.span.merge(symbol.span),
+                        name.span,
+                        None,
+                    )
+                })
+                .collect();
+            symbols.push(make_symbol(
+                codemap,
+                &load.module.node,
+                None,
+                LspSymbolKind::MODULE,
+                stmt.span,
+                load.module.span,
+                Some(children),
+            ));
+        }
+        StmtP::Def(def) => {
+            let mut children = Vec::new();
+            for param in &def.params {
+                if let Some(name) = param.split().0 {
+                    children.push(make_symbol(
+                        codemap,
+                        &name.0,
+                        None,
+                        LspSymbolKind::VARIABLE,
+                        param.span,
+                        name.span,
+                        None,
+                    ));
+                }
+            }
+            walk(codemap, &def.body, &mut children);
+            symbols.push(make_symbol(
+                codemap,
+                &def.name.0,
+                None,
+                LspSymbolKind::FUNCTION,
+                stmt.span,
+                def.name.span,
+                Some(children),
+            ));
+        }
+        StmtP::Assign(dest, rhs) => {
+            let kind = lsp_symbol_kind(SymbolKind::from_expr(&rhs.1));
+            dest.visit_lvalue(|name| {
+                symbols.push(make_symbol(
+                    codemap, &name.0, None, kind, stmt.span, name.span, None,
+                ))
+            });
+        }
+        StmtP::Expression(expr) => symbols.extend(target_symbol(codemap, expr)),
+        _ => stmt.visit_stmt(|x| walk(codemap, x, symbols)),
+    }
+}
+
+/// Find the symbols defined in a module, with the symbols defined within a function
+/// (including its parameters) as its children.
+///
+/// As well as assignments and functions, this includes each `load()` (with the loaded symbols as
+/// children), and calls with a `name` argument, which in a build file are usually targets.
+pub(crate) fn find_document_symbols(module: &AstModule) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    walk(&module.codemap, &module.statement, &mut symbols);
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::Dialect;
+
+    /// Flatten the symbols to `(depth, name, kind)` so they are easy to compare.
+    fn flatten(
+        symbols: &[DocumentSymbol],
+        depth: usize,
+        res: &mut Vec<(usize, String, LspSymbolKind)>,
+    ) {
+        for symbol in symbols {
+            res.push((depth, symbol.name.clone(), symbol.kind));
+            flatten(
+                symbol.children.as_deref().unwrap_or_default(),
+                depth + 1,
+                res,
+            );
+        }
+    }
+
+    #[test]
+    fn test_document_symbols() {
+        let modu = AstModule::parse(
+            "t.star",
+            r#"
+load("foo.star", "exported_a", renamed = "exported_b")
+def my_macro(name, *args, **kwargs):
+    inner = lambda x: x
+    if name:
+        _local = 1
+my_var = True
+my_macro(name = "target")
+print("not a target")
+"#
+            .to_owned(),
+            &Dialect::Extended,
+        )
+        .unwrap();
+
+        let symbols = find_document_symbols(&modu);
+        let mut flat = Vec::new();
+        flatten(&symbols, 0, &mut flat);
+        let expected = [
+            (0, "foo.star", LspSymbolKind::MODULE),
+            (1, "exported_a", LspSymbolKind::VARIABLE),
+            (1, "renamed", LspSymbolKind::VARIABLE),
+            (0, "my_macro", LspSymbolKind::FUNCTION),
+            (1, "name", LspSymbolKind::VARIABLE),
+            (1, "args", LspSymbolKind::VARIABLE),
+            (1, "kwargs", LspSymbolKind::VARIABLE),
+            (1, "inner", LspSymbolKind::FUNCTION),
+            (1, "_local", LspSymbolKind::VARIABLE),
+            (0, "my_var", LspSymbolKind::VARIABLE),
+            (0, "target", LspSymbolKind::CONSTRUCTOR),
+        ];
+        assert_eq!(
+            expected
+                .iter()
+                .map(|(depth, name, kind)| (*depth, (*name).to_owned(), *kind))
+                .collect::<Vec<_>>(),
+            flat
+        );
+
+        let target = symbols.last().unwrap();
+        assert_eq!(Some("my_macro"), target.detail.as_deref());
+        let renamed = &symbols[0].children.as_ref().unwrap()[1];
+        assert_eq!(Some("exported_b"), renamed.detail.as_deref());
+    }
+}
@@ -18,6 +18,7 @@
 //! Based on the reference lsp-server example at <https://github.com/rust-analyzer/lsp-server/blob/master/examples/goto_def.rs>.
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::path::Path;
 use std::path::PathBuf;
@@ -40,26 +41,62 @@ use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
 use lsp_types::notification::LogMessage;
 use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::CodeActionRequest;
+use lsp_types::request::Completion;
+use lsp_types::request::DocumentSymbolRequest;
+use lsp_types::request::Formatting;
 use lsp_types::request::GotoDefinition;
+use lsp_types::request::HoverRequest;
+use lsp_types::request::References;
+use lsp_types::request::Rename;
+use lsp_types::request::WorkspaceSymbol;
+use lsp_types::CodeAction;
+use lsp_types::CodeActionKind;
+use lsp_types::CodeActionOrCommand;
+use lsp_types::CodeActionParams;
+use lsp_types::CodeActionProviderCapability;
+use lsp_types::CompletionItem;
+use lsp_types::CompletionItemKind;
+use lsp_types::CompletionOptions;
+use lsp_types::CompletionParams;
+use lsp_types::CompletionResponse;
 use lsp_types::DefinitionOptions;
 use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidCloseTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
+use lsp_types::DocumentFormattingParams;
+use lsp_types::DocumentSymbolParams;
+use lsp_types::DocumentSymbolResponse;
+use lsp_types::Documentation;
 use lsp_types::GotoDefinitionParams;
 use lsp_types::GotoDefinitionResponse;
+use lsp_types::Hover;
+use lsp_types::HoverContents;
+use lsp_types::HoverParams;
+use lsp_types::HoverProviderCapability;
 use lsp_types::InitializeParams;
+use lsp_types::Location;
 use lsp_types::LocationLink;
 use lsp_types::LogMessageParams;
+use lsp_types::MarkupContent;
+use lsp_types::MarkupKind;
 use lsp_types::MessageType;
+use lsp_types::NumberOrString;
 use lsp_types::OneOf;
 use lsp_types::PublishDiagnosticsParams;
 use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameParams;
 use lsp_types::ServerCapabilities;
+use lsp_types::SymbolInformation;
 use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
+use lsp_types::TextEdit;
 use lsp_types::Url;
 use lsp_types::WorkDoneProgressOptions;
+use lsp_types::WorkspaceEdit;
+use lsp_types::WorkspaceSymbolParams;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -70,8 +107,25 @@ use crate::analysis::definition::Definition;
 use crate::analysis::definition::DottedDefinition;
 use crate::analysis::definition::IdentifierDefinition;
 use crate::analysis::definition::LspModule;
+use crate::analysis::exported::SymbolKind;
+use crate::analysis::references::Binding;
+use crate::codemap::LineCol;
 use crate::codemap::ResolvedSpan;
+use crate::docs::Doc;
+use crate::docs::DocItem;
+use crate::docs::DocMember;
+use crate::docs::DocModule;
+use crate::docs::DocObject;
+use crate::docs::DocParam;
+use crate::docs::Identifier;
+use crate::docs::MarkdownFlavor;
+use crate::docs::RenderMarkdown;
+use crate::lsp::completion::CompletionContext;
+use crate::lsp::document_symbols::find_document_symbols;
+use crate::lsp::document_symbols::lsp_symbol_kind;
 use crate::lsp::server::LoadContentsError::WrongScheme;
+use crate::lsp::symbols::find_symbols_at_position;
+use crate::syntax::lexer::is_identifier;
 use crate::syntax::AstModule;
 
 /// The request to get the file contents for a starlark: URI
@@ -293,6 +347,27 @@ pub trait LspContext {
         current_file: &LspUrl,
         symbol: &str,
     ) -> anyhow::Result<Option<LspUrl>>;
+
+    /// Get the documentation for all of the global symbols that are available to a file,
+    /// e.g. builtin functions and anything from a prelude.
+    ///
+    /// This is used to document and complete symbols that are not defined in the file itself.
+    /// It is called for every hover and completion, so implementations should keep the
+    /// environment around rather than rebuild it. By default no global symbols are known.
+    fn get_environment(&self, _uri: &LspUrl) -> Arc<DocModule> {
+        Arc::new(DocModule::default())
+    }
+
+    /// List the Starlark files in the workspace that might `load()` from `uri`, e.g. the
+    /// `.bzl` and build files in the same cell. If `uri` is `None`, list all the Starlark
+    /// files in the workspace.
+    ///
+    /// These are searched when renaming a symbol, or finding references and workspace symbols.
+    /// Open files are always searched, so if enumerating files is not possible this may
+    /// return an empty list, which is the default.
+    fn get_workspace_files(&self, _uri: Option<&LspUrl>) -> anyhow::Result<Vec<LspUrl>> {
+        Ok(Vec::new())
+    }
 }
 
 /// Errors when [`LspContext::resolve_load()`] cannot resolve a given path.
@@ -303,6 +378,20 @@ enum ResolveLoadError {
     WrongScheme(String, LspUrl),
 }
 
+/// Errors when renaming a symbol.
+#[derive(thiserror::Error, Debug)]
+enum RenameError {
+    /// The new name is a keyword, or not an identifier at all.
+    #[error("`{0}` is not a valid identifier")]
+    InvalidIdentifier(String),
+    /// Symbols starting with `_` cannot be loaded, so the rename would break other files.
+    #[error("Cannot rename `{0}` to `{1}`, as it is exported, and `{1}` could not be loaded")]
+    PrivateName(String, String),
+    /// The new name is already a top level symbol in one of the files that would use it.
+    #[error("Cannot rename to `{0}`, as `{0}` is already defined in `{1}`")]
+    Collision(String, LspUrl),
+}
+
 /// Errors when loading contents of a starlark program.
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum LoadContentsError {
@@ -317,6 +406,49 @@ struct Backend<T: LspContext> {
     /// The `AstModule` from the last time that a file was opened / changed and parsed successfully.
     /// Entries are evicted when the file is closed.
     last_valid_parse: RwLock<HashMap<LspUrl, Arc<LspModule>>>,
+    /// The current contents of each open file, whether or not they parse. Entries are evicted
+    /// when the file is closed.
+    open_files: RwLock<HashMap<LspUrl, String>>,
+}
+
+/// Look up the documentation for `member` within an object or module.
+fn member_docs(item: DocItem, member: &str) -> Option<DocItem> {
+    match item {
+        DocItem::Object(DocObject { members, .. }) | DocItem::Module(DocModule { members, .. }) => {
+            members.get(member).cloned().map(DocMember::to_doc_item)
+        }
+        DocItem::Function(_) | DocItem::Property(_) => None,
+    }
+}
+
+fn render_lsp_summary(name: &str, item: DocItem) -> MarkupContent {
+    let doc = Doc {
+        id: Identifier {
+            name: name.to_owned(),
+            location: None,
+        },
+        item,
+        custom_attrs: Default::default(),
+    };
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: doc.render_markdown(MarkdownFlavor::LspSummary),
+    }
+}
+
+/// A completion item for a symbol whose documentation is known.
+fn documented_completion_item(name: &str, item: DocItem) -> CompletionItem {
+    let kind = match &item {
+        DocItem::Function(_) => CompletionItemKind::FUNCTION,
+        DocItem::Object(_) | DocItem::Module(_) => CompletionItemKind::MODULE,
+        DocItem::Property(_) => CompletionItemKind::VARIABLE,
+    };
+    CompletionItem {
+        label: name.to_owned(),
+        kind: Some(kind),
+        documentation: Some(Documentation::MarkupContent(render_lsp_summary(name, item))),
+        ..Default::default()
+    }
 }
 
 /// The logic implementations of stuff
@@ -332,6 +464,17 @@ impl<T: LspContext> Backend<T> {
         ServerCapabilities {
             text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
             definition_provider,
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_owned()]),
+                ..Default::default()
+            }),
+            references_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -353,7 +496,11 @@ impl<T: LspContext> Backend<T> {
     }
 
     fn validate(&self, uri: Url, version: Option<i64>, text: String) -> anyhow::Result<()> {
-        let uri = uri.try_into()?;
+        let uri: LspUrl = uri.try_into()?;
+        self.open_files
+            .write()
+            .unwrap()
+            .insert(uri.clone(), text.clone());
         let eval_result = self.context.parse_file_with_contents(&uri, text);
         if let Some(ast) = eval_result.ast {
             let module = Arc::new(LspModule::new(ast));
@@ -384,8 +531,9 @@ impl<T: LspContext> Backend<T> {
 
     fn did_close(&self, params: DidCloseTextDocumentParams) -> anyhow::Result<()> {
         {
-            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.remove(&params.text_document.uri.clone().try_into()?);
+            let uri = params.text_document.uri.clone().try_into()?;
+            self.last_valid_parse.write().unwrap().remove(&uri);
+            self.open_files.write().unwrap().remove(&uri);
         }
         self.publish_diagnostics(params.text_document.uri, Vec::new(), None);
         Ok(())
@@ -587,6 +735,727 @@ impl<T: LspContext> Backend<T> {
         };
         Ok(GotoDefinitionResponse::Link(response))
     }
+
+    /// Show the documentation for the symbol at the current cursor.
+    fn hover(&self, id: RequestId, params: HoverParams) {
+        self.send_response(new_response(id, self.hover_info(params)));
+    }
+
+    /// Find references to the symbol at the current cursor. If the symbol is exported from,
+    /// or loaded into, the current file, this also finds uses in other open files that load it.
+    fn references(&self, id: RequestId, params: ReferenceParams) {
+        self.send_response(new_response(id, self.find_references(params)));
+    }
+
+    /// Rename the symbol at the current cursor. If the symbol is exported from, or loaded into,
+    /// the current file, this also renames it in every file in the workspace that loads it.
+    fn rename(&self, id: RequestId, params: RenameParams) {
+        self.send_response(new_response(id, self.rename_symbol(params)));
+    }
+
+    /// List the symbols defined in a file, nested by the function they are defined in.
+    fn document_symbol(&self, id: RequestId, params: DocumentSymbolParams) {
+        self.send_response(new_response(id, self.find_document_symbols(params)));
+    }
+
+    /// Find the symbols exported from any file in the workspace that match a query.
+    fn workspace_symbol(&self, id: RequestId, params: WorkspaceSymbolParams) {
+        self.send_response(new_response(id, self.find_workspace_symbols(params)));
+    }
+
+    /// Format a whole file as canonical Starlark.
+    fn formatting(&self, id: RequestId, params: DocumentFormattingParams) {
+        self.send_response(new_response(id, self.format_document(params)));
+    }
+
+    /// Offer quick fixes for the diagnostics in the requested range that can be fixed mechanically,
+    /// e.g. removing an unused `load()`.
+    fn code_action(&self, id: RequestId, params: CodeActionParams) {
+        self.send_response(new_response(id, self.find_code_actions(params)));
+    }
+
+    /// Offer completions for the partial symbol at the current cursor.
+    ///
+    /// Unlike the other requests, this uses the current contents of the file rather than
+    /// the last valid parse to decide what is being completed, as a file that is being
+    /// typed into often does not parse.
+    fn completion(&self, id: RequestId, params: CompletionParams) {
+        self.send_response(new_response(id, self.completion_options(params)));
+    }
+
+    /// Get the documentation for a global symbol available to `uri`.
+    fn global_docs(&self, uri: &LspUrl, name: &str) -> Option<DocItem> {
+        self.context
+            .get_environment(uri)
+            .members
+            .get(name)
+            .cloned()
+            .map(DocMember::to_doc_item)
+    }
+
+    /// Get the documentation for a symbol exported from the file at `uri`, following
+    /// re-exports of loaded symbols.
+    fn docs_for_exported_symbol(
+        &self,
+        uri: &LspUrl,
+        name: &str,
+        visited: &mut HashSet<(LspUrl, String)>,
+    ) -> anyhow::Result<Option<DocItem>> {
+        if !visited.insert((uri.clone(), name.to_owned())) {
+            return Ok(None);
+        }
+        let module = match self.get_ast_or_load_from_disk(uri)? {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        match module.find_top_level_binding(name) {
+            Some(Binding {
+                loaded_from: Some((path, name)),
+                ..
+            }) => {
+                let load_uri = self.resolve_load_path(&path, uri)?;
+                self.docs_for_exported_symbol(&load_uri, &name, visited)
+            }
+            Some(binding) => Ok(module.find_docs_for_binding(binding.span)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the documentation for a variable bound within `module`.
+    fn docs_for_binding(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        binding: &Binding,
+    ) -> anyhow::Result<Option<DocItem>> {
+        match &binding.loaded_from {
+            Some((path, name)) => {
+                let load_uri = self.resolve_load_path(path, uri)?;
+                self.docs_for_exported_symbol(&load_uri, name, &mut HashSet::new())
+            }
+            None => Ok(module.find_docs_for_binding(binding.span)),
+        }
+    }
+
+    /// Get the documentation for the identifier at the root of `definition`, along with
+    /// the name of that identifier.
+    fn docs_for_definition(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        definition: &IdentifierDefinition,
+    ) -> anyhow::Result<Option<(String, DocItem)>> {
+        match definition {
+            IdentifierDefinition::Location { source, .. }
+            | IdentifierDefinition::LoadedLocation { source, .. } => {
+                match module.find_binding(source.begin_line as u32, source.begin_column as u32) {
+                    Some(binding) => Ok(self
+                        .docs_for_binding(uri, module, &binding)?
+                        .map(|docs| (binding.name, docs))),
+                    None => Ok(None),
+                }
+            }
+            IdentifierDefinition::Unresolved { name, .. } => {
+                Ok(self.global_docs(uri, name).map(|docs| (name.clone(), docs)))
+            }
+            IdentifierDefinition::LoadPath { .. }
+            | IdentifierDefinition::StringLiteral { .. }
+            | IdentifierDefinition::NotFound => Ok(None),
+        }
+    }
+
+    /// Get the documentation for a dotted path like `foo.bar`, where `foo` is either bound
+    /// at the top level of `module`, or is a global.
+    fn docs_for_path(
+        &self,
+        uri: &LspUrl,
+        module: Option<&LspModule>,
+        path: &[String],
+    ) -> anyhow::Result<Option<DocItem>> {
+        let (root, members) = match path.split_first() {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        let binding =
+            module.and_then(|module| Some((module, module.find_top_level_binding(root)?)));
+        let root_docs = match binding {
+            Some((module, binding)) => self.docs_for_binding(uri, module, &binding)?,
+            None => self.global_docs(uri, root),
+        };
+        Ok(root_docs.and_then(|docs| {
+            members
+                .iter()
+                .try_fold(docs, |docs, member| member_docs(docs, member))
+        }))
+    }
+
+    fn hover_info(&self, params: HoverParams) -> anyhow::Result<Option<Hover>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .try_into()?;
+        let line = params.text_document_position_params.position.line;
+        let character = params.text_document_position_params.position.character;
+
+        let module = match self.get_ast(&uri) {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        let definition = module.find_definition(line, character);
+        let source = match definition.source() {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+        let docs = match &definition {
+            Definition::Identifier(definition) => {
+                self.docs_for_definition(&uri, &module, definition)?
+            }
+            Definition::Dotted(DottedDefinition {
+                root_definition_location,
+                segments,
+                ..
+            }) => self
+                .docs_for_definition(&uri, &module, root_definition_location)?
+                .and_then(|(name, docs)| {
+                    segments
+                        .iter()
+                        .skip(1)
+                        .try_fold((name, docs), |(_, docs), member| {
+                            Some((member.clone(), member_docs(docs, member)?))
+                        })
+                }),
+        };
+        Ok(docs.map(|(name, docs)| Hover {
+            contents: HoverContents::Markup(render_lsp_summary(&name, docs)),
+            range: Some(source.into()),
+        }))
+    }
+
+    /// The modules that might load from `scope` (or any module in the workspace if `scope`
+    /// is `None`). Open files use their last valid parse, other files are only read and parsed
+    /// if their contents satisfy `matches`, and are skipped if that fails.
+    fn workspace_modules(
+        &self,
+        scope: Option<&LspUrl>,
+        matches: impl Fn(&str) -> bool,
+    ) -> anyhow::Result<Vec<(LspUrl, Arc<LspModule>)>> {
+        let mut res: Vec<_> = self
+            .last_valid_parse
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(uri, module)| (uri.clone(), module.dupe()))
+            .collect();
+        let open: HashSet<_> = res.iter().map(|(uri, _)| uri.clone()).collect();
+        for uri in self.context.get_workspace_files(scope)? {
+            if open.contains(&uri) {
+                continue;
+            }
+            let contents = match self.context.get_load_contents(&uri) {
+                Ok(Some(contents)) if matches(&contents) => contents,
+                _ => continue,
+            };
+            if let Some(ast) = self.context.parse_file_with_contents(&uri, contents).ast {
+                res.push((uri, Arc::new(LspModule::new(ast))));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Find every binding of the symbol `name` exported from `origin` by a `load()` in
+    /// another module.
+    fn find_loads_of(
+        &self,
+        origin: &LspUrl,
+        name: &str,
+    ) -> anyhow::Result<Vec<(LspUrl, Arc<LspModule>, Binding)>> {
+        let mut res = Vec::new();
+        for (uri, module) in self.workspace_modules(Some(origin), |x| x.contains(name))? {
+            if &uri == origin {
+                continue;
+            }
+            for binding in module.find_loaded_bindings() {
+                let loads_symbol = match &binding.loaded_from {
+                    Some((path, loaded_name)) => {
+                        loaded_name == name
+                            && self.resolve_load_path(path, &uri).ok().as_ref() == Some(origin)
+                    }
+                    None => false,
+                };
+                if loads_symbol {
+                    res.push((uri.clone(), module.dupe(), binding));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Find all of the references to the symbol `name` exported from `origin`, both within
+    /// `origin` and within the other files in the workspace that load it. Also returns the
+    /// location of the definition of the symbol, if it could be found.
+    fn find_exported_references(
+        &self,
+        origin: &LspUrl,
+        name: &str,
+    ) -> anyhow::Result<(Vec<(LspUrl, ResolvedSpan)>, Option<(LspUrl, ResolvedSpan)>)> {
+        let mut references = Vec::new();
+        let mut declaration = None;
+        if let Some(module) = self.get_ast_or_load_from_disk(origin)? {
+            if let Some(binding) = module.find_top_level_binding(name) {
+                declaration = Some((
+                    origin.clone(),
+                    module.ast.codemap.resolve_span(binding.span),
+                ));
+                references.extend(
+                    module
+                        .find_references(&binding)
+                        .into_iter()
+                        .map(|span| (origin.clone(), span)),
+                );
+            }
+        }
+
+        for (uri, module, binding) in self.find_loads_of(origin, name)? {
+            references.extend(
+                module
+                    .find_references(&binding)
+                    .into_iter()
+                    .map(|span| (uri.clone(), span)),
+            );
+        }
+        Ok((references, declaration))
+    }
+
+    fn find_references(&self, params: ReferenceParams) -> anyhow::Result<Vec<Location>> {
+        let uri: LspUrl = params.text_document_position.text_document.uri.try_into()?;
+        let line = params.text_document_position.position.line;
+        let character = params.text_document_position.position.character;
+
+        let module = match self.get_ast(&uri) {
+            Some(module) => module,
+            None => return Ok(Vec::new()),
+        };
+        let binding = match module.find_binding(line, character) {
+            Some(binding) => binding,
+            None => return Ok(Vec::new()),
+        };
+
+        // Symbols that are loaded, or that could be loaded by other files, need to be tracked
+        // back to the file that they are defined in.
+        let origin = match &binding.loaded_from {
+            Some((path, name)) => Some((self.resolve_load_path(path, &uri)?, name.clone())),
+            None if binding.top_level && !binding.name.starts_with('_') => {
+                Some((uri.clone(), binding.name.clone()))
+            }
+            None => None,
+        };
+        let (references, declaration) = match origin {
+            Some((origin, name)) => self.find_exported_references(&origin, &name)?,
+            None => (
+                module
+                    .find_references(&binding)
+                    .into_iter()
+                    .map(|span| (uri.clone(), span))
+                    .collect(),
+                Some((uri, module.ast.codemap.resolve_span(binding.span))),
+            ),
+        };
+
+        let mut res = Vec::new();
+        for (uri, span) in references {
+            if !params.context.include_declaration
+                && declaration.as_ref() == Some(&(uri.clone(), span))
+            {
+                continue;
+            }
+            res.push(Location::new((&uri).try_into()?, span.into()));
+        }
+        Ok(res)
+    }
+
+    /// Find the module and name that the symbol `name` exported from `uri` is originally
+    /// defined as, following re-exports of loaded symbols.
+    fn find_symbol_origin(
+        &self,
+        uri: LspUrl,
+        name: String,
+        visited: &mut HashSet<(LspUrl, String)>,
+    ) -> anyhow::Result<(LspUrl, String)> {
+        if !visited.insert((uri.clone(), name.clone())) {
+            return Ok((uri, name));
+        }
+        let loaded_from = self
+            .get_ast_or_load_from_disk(&uri)?
+            .and_then(|module| module.find_top_level_binding(&name))
+            .and_then(|binding| binding.loaded_from);
+        match loaded_from {
+            Some((path, name)) => {
+                let load_uri = self.resolve_load_path(&path, &uri)?;
+                self.find_symbol_origin(load_uri, name, visited)
+            }
+            None => Ok((uri, name)),
+        }
+    }
+
+    /// The edits needed to rename the symbol `name` exported from `origin` to `new_name`,
+    /// both in `origin` and in every file that loads it.
+    fn rename_exported_symbol(
+        &self,
+        origin: &LspUrl,
+        name: &str,
+        new_name: &str,
+        visited: &mut HashSet<LspUrl>,
+    ) -> anyhow::Result<Vec<(LspUrl, ResolvedSpan, String)>> {
+        let mut res = Vec::new();
+        if !visited.insert(origin.clone()) {
+            return Ok(res);
+        }
+        if let Some(module) = self.get_ast_or_load_from_disk(origin)? {
+            match module.find_top_level_binding(name) {
+                // Re-exports are renamed along with the other loads of the original symbol.
+                Some(binding) if binding.loaded_from.is_none() => res.extend(
+                    module
+                        .rename_binding(&binding, new_name)
+                        .into_iter()
+                        .map(|(span, text)| (origin.clone(), span, text)),
+                ),
+                _ => {}
+            }
+        }
+        for (uri, module, binding) in self.find_loads_of(origin, name)? {
+            res.extend(
+                module
+                    .rename_loaded_symbol(&binding, new_name)
+                    .into_iter()
+                    .map(|(span, text)| (uri.clone(), span, text)),
+            );
+            // If the symbol is re-exported under the same name, other files may load it from here.
+            if binding.name == name && module.ast.dialect.enable_load_reexport {
+                res.extend(self.rename_exported_symbol(&uri, name, new_name, visited)?);
+            }
+        }
+        Ok(res)
+    }
+
+    fn rename_symbol(&self, params: RenameParams) -> anyhow::Result<Option<WorkspaceEdit>> {
+        let uri: LspUrl = params.text_document_position.text_document.uri.try_into()?;
+        let line = params.text_document_position.position.line;
+        let character = params.text_document_position.position.character;
+        let new_name = params.new_name;
+        if !is_identifier(&new_name) {
+            return Err(RenameError::InvalidIdentifier(new_name).into());
+        }
+
+        let module = match self.get_ast(&uri) {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        let binding = match module.find_binding(line, character) {
+            Some(binding) => binding,
+            None => return Ok(None),
+        };
+        if binding.name == new_name {
+            return Ok(Some(WorkspaceEdit::default()));
+        }
+
+        // As with references, symbols that are loaded, or that could be loaded by other files,
+        // have to be renamed everywhere they are loaded. A symbol loaded under another name is
+        // only an alias though, so only the alias is renamed.
+        let is_alias = matches!(&binding.loaded_from, Some((_, name)) if *name != binding.name);
+        let origin = match &binding.loaded_from {
+            Some(_) if is_alias => None,
+            Some((path, name)) => Some((self.resolve_load_path(path, &uri)?, name.clone())),
+            None if binding.top_level && !binding.name.starts_with('_') => {
+                Some((uri.clone(), binding.name.clone()))
+            }
+            None => None,
+        };
+        let edits = match origin {
+            Some((origin, name)) => {
+                let (origin, name) = self.find_symbol_origin(origin, name, &mut HashSet::new())?;
+                if new_name.starts_with('_') {
+                    return Err(RenameError::PrivateName(name, new_name).into());
+                }
+                self.rename_exported_symbol(&origin, &name, &new_name, &mut HashSet::new())?
+            }
+            None => {
+                let mut edits: Vec<_> = module
+                    .rename_binding(&binding, &new_name)
+                    .into_iter()
+                    .map(|(span, text)| (uri.clone(), span, text))
+                    .collect();
+                // Other files may load the alias from this one.
+                if is_alias && module.ast.dialect.enable_load_reexport {
+                    if new_name.starts_with('_') && !binding.name.starts_with('_') {
+                        return Err(RenameError::PrivateName(binding.name, new_name).into());
+                    }
+                    edits.extend(self.rename_exported_symbol(
+                        &uri,
+                        &binding.name,
+                        &new_name,
+                        &mut HashSet::new(),
+                    )?);
+                }
+                edits
+            }
+        };
+
+        // The files where the new name becomes a variable, rather than only a loaded string.
+        let uses_new_name: HashSet<&LspUrl> = edits
+            .iter()
+            .filter(|(_, _, text)| *text == new_name)
+            .map(|(uri, _, _)| uri)
+            .collect();
+        for uri in uses_new_name {
+            let defined = self
+                .get_ast_or_load_from_disk(uri)?
+                .map_or(false, |module| {
+                    module.find_top_level_binding(&new_name).is_some()
+                });
+            if defined {
+                return Err(RenameError::Collision(new_name, uri.clone()).into());
+            }
+        }
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (uri, span, new_text) in edits {
+            changes
+                .entry((&uri).try_into()?)
+                .or_default()
+                .push(TextEdit::new(span.into(), new_text));
+        }
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    fn find_document_symbols(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> anyhow::Result<Option<DocumentSymbolResponse>> {
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        Ok(self
+            .get_ast(&uri)
+            .map(|module| DocumentSymbolResponse::Nested(find_document_symbols(&module.ast))))
+    }
+
+    fn find_workspace_symbols(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> anyhow::Result<Option<Vec<SymbolInformation>>> {
+        let query = params.query.to_lowercase();
+        let mut res = Vec::new();
+        for (uri, module) in self.workspace_modules(None, |x| x.to_lowercase().contains(&query))? {
+            for symbol in module.ast.exported_symbols() {
+                if !symbol.name.to_lowercase().contains(&query) {
+                    continue;
+                }
+                // The `deprecated` field is deprecated, but has to be filled in.
+                #[allow(deprecated)]
+                let info = SymbolInformation {
+                    name: symbol.name.to_owned(),
+                    kind: lsp_symbol_kind(symbol.kind),
+                    tags: None,
+                    deprecated: None,
+                    location: Location::new((&uri).try_into()?, symbol.span.resolve_span().into()),
+                    container_name: Some(uri.path().display().to_string()),
+                };
+                res.push(info);
+            }
+        }
+        res.sort_by(|a, b| (&a.name, &a.container_name).cmp(&(&b.name, &b.container_name)));
+        Ok(Some(res))
+    }
+
+    fn format_document(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        let module = match self.get_ast(&uri) {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        // If the file has been edited since it last parsed, formatting the last valid parse
+        // would throw the edits away, so leave it alone until it parses again.
+        let source = module.ast.codemap.source();
+        if self
+            .open_files
+            .read()
+            .unwrap()
+            .get(&uri)
+            .map(String::as_str)
+            != Some(source)
+        {
+            return Ok(None);
+        }
+        let formatted = module.ast.format();
+        if formatted == source {
+            return Ok(Some(Vec::new()));
+        }
+        let whole_file = module
+            .ast
+            .codemap
+            .resolve_span(module.ast.codemap.full_span());
+        Ok(Some(vec![TextEdit::new(whole_file.into(), formatted)]))
+    }
+
+    fn find_code_actions(
+        &self,
+        params: CodeActionParams,
+    ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        let uri: LspUrl = params.text_document.uri.clone().try_into()?;
+        let module = match self.get_ast(&uri) {
+            Some(module) => module,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut res = Vec::new();
+        for diagnostic in params.context.diagnostics {
+            let short_name = match &diagnostic.code {
+                Some(NumberOrString::String(short_name)) => short_name,
+                _ => continue,
+            };
+            let span = ResolvedSpan {
+                begin_line: diagnostic.range.start.line as usize,
+                begin_column: diagnostic.range.start.character as usize,
+                end_line: diagnostic.range.end.line as usize,
+                end_column: diagnostic.range.end.character as usize,
+            };
+            if let Some(fix) = module.find_quick_fix(short_name, span) {
+                let edits = fix
+                    .edits
+                    .into_iter()
+                    .map(|(span, new_text)| TextEdit::new(span.into(), new_text))
+                    .collect();
+                res.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic]),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(params.text_document.uri.clone(), edits)])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Completions for names in scope at the cursor: local and loaded symbols, then globals.
+    fn name_completions(
+        &self,
+        uri: &LspUrl,
+        module: Option<&LspModule>,
+        line: u32,
+        character: u32,
+    ) -> Vec<CompletionItem> {
+        let mut seen = HashSet::new();
+        let mut res = Vec::new();
+        if let Some(module) = module {
+            let position = LineCol {
+                line: line as usize,
+                column: character as usize,
+            };
+            for symbol in find_symbols_at_position(&module.ast, position) {
+                seen.insert(symbol.name.to_owned());
+                res.push(CompletionItem {
+                    label: symbol.name.to_owned(),
+                    kind: Some(match symbol.kind {
+                        SymbolKind::Function => CompletionItemKind::FUNCTION,
+                        SymbolKind::Any => CompletionItemKind::VARIABLE,
+                    }),
+                    detail: symbol
+                        .loaded_from
+                        .map(|path| format!("Loaded from {}", path)),
+                    ..Default::default()
+                });
+            }
+        }
+        for (name, member) in &self.context.get_environment(uri).members {
+            if !seen.contains(name) {
+                res.push(documented_completion_item(
+                    name,
+                    member.clone().to_doc_item(),
+                ));
+            }
+        }
+        res
+    }
+
+    /// Completions for the members of the object at `path`.
+    fn member_completions(
+        &self,
+        uri: &LspUrl,
+        module: Option<&LspModule>,
+        path: &[String],
+    ) -> anyhow::Result<Vec<CompletionItem>> {
+        let members = match self.docs_for_path(uri, module, path)? {
+            Some(DocItem::Object(DocObject { members, .. }))
+            | Some(DocItem::Module(DocModule { members, .. })) => members,
+            _ => return Ok(Vec::new()),
+        };
+        Ok(members
+            .into_iter()
+            .map(|(name, member)| documented_completion_item(&name, member.to_doc_item()))
+            .collect())
+    }
+
+    /// Completions for the named parameters of the function at `path`.
+    fn argument_completions(
+        &self,
+        uri: &LspUrl,
+        module: Option<&LspModule>,
+        path: &[String],
+    ) -> anyhow::Result<Vec<CompletionItem>> {
+        let function = match self.docs_for_path(uri, module, path)? {
+            Some(DocItem::Function(function)) => function,
+            _ => return Ok(Vec::new()),
+        };
+        Ok(function
+            .params
+            .into_iter()
+            .filter_map(|param| match param {
+                DocParam::Arg { name, .. } => Some(CompletionItem {
+                    insert_text: Some(format!("{} = ", name)),
+                    label: name,
+                    kind: Some(CompletionItemKind::PROPERTY),
+                    ..Default::default()
+                }),
+                DocParam::NoArgs | DocParam::Args { .. } | DocParam::Kwargs { .. } => None,
+            })
+            .collect())
+    }
+
+    fn completion_options(&self, params: CompletionParams) -> anyhow::Result<CompletionResponse> {
+        let uri: LspUrl = params.text_document_position.text_document.uri.try_into()?;
+        let line = params.text_document_position.position.line;
+        let character = params.text_document_position.position.character;
+
+        let contents = self
+            .open_files
+            .read()
+            .unwrap()
+            .get(&uri)
+            .cloned()
+            .unwrap_or_default();
+        let module = self.get_ast(&uri);
+        let module = module.as_deref();
+        let items = match CompletionContext::at_position(&contents, line, character) {
+            CompletionContext::Member(path) => self.member_completions(&uri, module, &path)?,
+            CompletionContext::Argument(path) => {
+                let mut items = self.argument_completions(&uri, module, &path)?;
+                items.extend(self.name_completions(&uri, module, line, character));
+                items
+            }
+            CompletionContext::Name => self.name_completions(&uri, module, line, character),
+        };
+        Ok(CompletionResponse::Array(items))
+    }
 }
 
 /// The library style pieces
@@ -624,6 +1493,22 @@ impl<T: LspContext> Backend<T> {
                     //            be handled client side.
                     if let Some(params) = as_request::<GotoDefinition>(&req) {
                         self.goto_definition(req.id, params);
+                    } else if let Some(params) = as_request::<HoverRequest>(&req) {
+                        self.hover(req.id, params);
+                    } else if let Some(params) = as_request::<Completion>(&req) {
+                        self.completion(req.id, params);
+                    } else if let Some(params) = as_request::<References>(&req) {
+                        self.references(req.id, params);
+                    } else if let Some(params) = as_request::<CodeActionRequest>(&req) {
+                        self.code_action(req.id, params);
+                    } else if let Some(params) = as_request::<Rename>(&req) {
+                        self.rename(req.id, params);
+                    } else if let Some(params) = as_request::<DocumentSymbolRequest>(&req) {
+                        self.document_symbol(req.id, params);
+                    } else if let Some(params) = as_request::<WorkspaceSymbol>(&req) {
+                        self.workspace_symbol(req.id, params);
+                    } else if let Some(params) = as_request::<Formatting>(&req) {
+                        self.formatting(req.id, params);
                     } else if let Some(params) = as_request::<StarlarkFileContentsRequest>(&req) {
                         self.get_starlark_file_contents(req.id, params);
                     } else if self.connection.handle_shutdown(&req)? {
@@ -689,6 +1574,7 @@ pub fn server_with_connection<T: LspContext>(
         connection,
         context,
         last_valid_parse: RwLock::default(),
+        open_files: RwLock::default(),
     }
     .main_loop(initialization_params)?;
 
@@ -768,21 +1654,52 @@ where
 //            some paths. Revisit later.
 #[cfg(all(test, not(windows)))]
 mod test {
+    use std::collections::HashMap;
     use std::path::Path;
     use std::path::PathBuf;
 
     use anyhow::Context;
     use lsp_server::Request;
     use lsp_server::RequestId;
+    use lsp_types::request::CodeActionRequest;
+    use lsp_types::request::Completion;
+    use lsp_types::request::DocumentSymbolRequest;
+    use lsp_types::request::Formatting;
     use lsp_types::request::GotoDefinition;
+    use lsp_types::request::HoverRequest;
+    use lsp_types::request::References;
+    use lsp_types::request::Rename;
+    use lsp_types::request::WorkspaceSymbol;
+    use lsp_types::CodeActionContext;
+    use lsp_types::CodeActionOrCommand;
+    use lsp_types::CodeActionParams;
+    use lsp_types::CompletionParams;
+    use lsp_types::CompletionResponse;
+    use lsp_types::Diagnostic;
+    use lsp_types::DocumentFormattingParams;
+    use lsp_types::DocumentSymbolParams;
+    use lsp_types::DocumentSymbolResponse;
+    use lsp_types::FormattingOptions;
     use lsp_types::GotoDefinitionParams;
     use lsp_types::GotoDefinitionResponse;
+    use lsp_types::Hover;
+    use lsp_types::HoverContents;
+    use lsp_types::HoverParams;
+    use lsp_types::Location;
     use lsp_types::LocationLink;
+    use lsp_types::NumberOrString;
     use lsp_types::Position;
     use lsp_types::Range;
+    use lsp_types::ReferenceContext;
+    use lsp_types::ReferenceParams;
+    use lsp_types::RenameParams;
+    use lsp_types::SymbolInformation;
     use lsp_types::TextDocumentIdentifier;
     use lsp_types::TextDocumentPositionParams;
+    use lsp_types::TextEdit;
     use lsp_types::Url;
+    use lsp_types::WorkspaceEdit;
+    use lsp_types::WorkspaceSymbolParams;
     use textwrap::dedent;
 
     use crate::analysis::definition::helpers::FixtureWithRanges;
@@ -1809,4 +2726,477 @@ mod test {
         }
         Ok(())
     }
+
+    fn text_document_position(uri: Url, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: Position { line, character },
+        }
+    }
+
+    #[test]
+    fn hovers_over_symbols() -> anyhow::Result<()> {
+        let uri = temp_file_uri("foo.star");
+        let contents = dedent(
+            r#"
+            def foo(x: "int") -> "int":
+                """ Foo docs. """
+                return x
+
+            bar = struct(baz = foo)
+
+            <foo_click>f<foo>o</foo>o</foo_click>(1)
+            bar.<baz_click>b<baz>a</baz>z</baz_click>(1)
+            <native_click>native_<native>f</native>unction1</native_click>()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let foo = FixtureWithRanges::from_fixture(uri.path(), &contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), foo.program())?;
+
+        for (id, expected) in [
+            ("foo", "def foo(x: \"int\") -> \"int\""),
+            ("baz", "def baz(x: \"int\") -> \"int\""),
+            ("native", "def native_function1()"),
+        ] {
+            let request = server.new_request::<HoverRequest>(HoverParams {
+                text_document_position_params: text_document_position(
+                    uri.clone(),
+                    foo.begin_line(id),
+                    foo.begin_column(id),
+                ),
+                work_done_progress_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            let hover = server
+                .get_response::<Option<Hover>>(request_id)?
+                .with_context(|| format!("expected hover for `{}`", id))?;
+            match hover.contents {
+                HoverContents::Markup(markup) => {
+                    assert!(
+                        markup.value.contains(expected),
+                        "Hover for `{}` was `{}`",
+                        id,
+                        markup.value
+                    );
+                }
+                contents => panic!("Unexpected hover contents: {:?}", contents),
+            }
+            assert_eq!(Some(foo.span(&format!("{}_click", id)).into()), hover.range);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn completes_symbols_in_unparseable_files() -> anyhow::Result<()> {
+        let uri = temp_file_uri("foo.star");
+        let contents = dedent(
+            r#"
+            def my_function(first, second):
+                return first + second
+
+            s = struct(member = 1)
+            "#,
+        )
+        .trim()
+        .to_owned();
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), contents.clone())?;
+
+        let complete =
+            |server: &mut TestServer, new_contents: &str| -> anyhow::Result<Vec<String>> {
+                let contents = format!("{}\n{}", contents, new_contents);
+                let line = contents.lines().count() as u32 - 1;
+                let character = contents.lines().last().unwrap().len() as u32;
+                server.change_file(uri.clone(), contents)?;
+                let request = server.new_request::<Completion>(CompletionParams {
+                    text_document_position: text_document_position(uri.clone(), line, character),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                    context: None,
+                });
+                let request_id = server.send_request(request)?;
+                match server.get_response::<CompletionResponse>(request_id)? {
+                    CompletionResponse::Array(items) => {
+                        Ok(items.into_iter().map(|item| item.label).collect())
+                    }
+                    response => Err(anyhow::anyhow!("Unexpected response: {:?}", response)),
+                }
+            };
+
+        let names = complete(&mut server, "my_")?;
+        assert!(names.contains(&"my_function".to_owned()));
+        assert!(names.contains(&"native_function1".to_owned()));
+        assert!(!names.contains(&"first".to_owned()));
+
+        let names = complete(&mut server, "my_function(fi")?;
+        assert_eq!(vec!["first", "second"], names[..2].to_vec());
+        assert!(names.contains(&"my_function".to_owned()));
+
+        let names = complete(&mut server, "s.me")?;
+        assert_eq!(vec!["member".to_owned()], names);
+        Ok(())
+    }
+
+    #[test]
+    fn finds_references_across_open_files() -> anyhow::Result<()> {
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let foo_contents = dedent(
+            r#"
+            load("bar.star", <foo_load>"baz"</foo_load>)
+            <foo_use>baz</foo_use>()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            def <bar_def>baz</bar_def>():
+                pass
+
+            <bar_use>b<click>a</click>z</bar_use>()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let foo = FixtureWithRanges::from_fixture(foo_uri.path(), &foo_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(foo_uri.clone(), foo.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+
+        let mut references = |include_declaration| -> anyhow::Result<Vec<Location>> {
+            let request = server.new_request::<References>(ReferenceParams {
+                text_document_position: text_document_position(
+                    bar_uri.clone(),
+                    bar.begin_line("click"),
+                    bar.begin_column("click"),
+                ),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: ReferenceContext {
+                    include_declaration,
+                },
+            });
+            let request_id = server.send_request(request)?;
+            server.get_response::<Vec<Location>>(request_id)
+        };
+
+        let bar_def = Location::new(bar_uri.clone(), bar.span("bar_def").into());
+        let bar_use = Location::new(bar_uri.clone(), bar.span("bar_use").into());
+        let foo_load = Location::new(foo_uri.clone(), foo.span("foo_load").into());
+        let foo_use = Location::new(foo_uri.clone(), foo.span("foo_use").into());
+
+        assert_eq!(
+            vec![bar_def, bar_use.clone(), foo_load.clone(), foo_use.clone()],
+            references(true)?
+        );
+        assert_eq!(vec![bar_use, foo_load, foo_use], references(false)?);
+        Ok(())
+    }
+
+    #[test]
+    fn offers_quick_fixes_for_lints() -> anyhow::Result<()> {
+        let uri = temp_file_uri("foo.star");
+        let contents = dedent(
+            r#"
+            load("bar.star", "used"<removed>, <unused>"unused"</unused></removed>)
+            used()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let fixture = FixtureWithRanges::from_fixture(uri.path(), &contents)?;
+
+        let mut server = TestServer::new()?;
+        let diagnostics = server.open_file_with_diagnostics(uri.clone(), fixture.program())?;
+        assert_eq!(
+            vec![Some(NumberOrString::String("unused-load".to_owned()))],
+            diagnostics.into_iter().map(|d| d.code).collect::<Vec<_>>()
+        );
+
+        let diagnostic = |code: &str| Diagnostic {
+            range: fixture.span("unused").into(),
+            code: Some(NumberOrString::String(code.to_owned())),
+            ..Default::default()
+        };
+        let mut code_actions = |code| -> anyhow::Result<Vec<CodeActionOrCommand>> {
+            let request = server.new_request::<CodeActionRequest>(CodeActionParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                range: fixture.span("unused").into(),
+                context: CodeActionContext {
+                    diagnostics: vec![diagnostic(code)],
+                    ..Default::default()
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            server.get_response::<Vec<CodeActionOrCommand>>(request_id)
+        };
+
+        let actions = code_actions("unused-load")?;
+        assert_eq!(1, actions.len());
+        let action = match &actions[0] {
+            CodeActionOrCommand::CodeAction(action) => action,
+            x => panic!("Expected a code action, got {:?}", x),
+        };
+        assert_eq!(Some(vec![diagnostic("unused-load")]), action.diagnostics);
+        assert_eq!(
+            Some(&vec![TextEdit::new(
+                fixture.span("removed").into(),
+                String::new()
+            )]),
+            action
+                .edit
+                .as_ref()
+                .and_then(|edit| edit.changes.as_ref())
+                .and_then(|changes| changes.get(&uri))
+        );
+
+        assert!(code_actions("using-undefined")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn renames_symbols_across_files() -> anyhow::Result<()> {
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+        let baz_uri = temp_file_uri("baz.star");
+
+        let foo_contents = dedent(
+            r#"
+            load("bar.star", <foo_load>"baz"</foo_load>)
+            <foo_use>baz</foo_use>()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            def <bar_def>baz</bar_def>(<param_def>x</param_def>):
+                return <param_use>x</param_use>
+
+            <bar_use>b<click>a</click>z</bar_use>(1)
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let baz_contents = dedent(
+            r#"
+            load("bar.star", local = <baz_load>"baz"</baz_load>)
+            local()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let foo = FixtureWithRanges::from_fixture(foo_uri.path(), &foo_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+        let baz = FixtureWithRanges::from_fixture(baz_uri.path(), &baz_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(foo_uri.clone(), foo.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+        // Not open, so has to be found in the workspace.
+        server.set_file_contents(PathBuf::from(baz_uri.path()), baz.program())?;
+
+        let mut rename = |at: &str, new_name: &str| -> anyhow::Result<WorkspaceEdit> {
+            let request = server.new_request::<Rename>(RenameParams {
+                text_document_position: text_document_position(
+                    bar_uri.clone(),
+                    bar.begin_line(at),
+                    bar.begin_column(at),
+                ),
+                new_name: new_name.to_owned(),
+                work_done_progress_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            server.get_response::<WorkspaceEdit>(request_id)
+        };
+        let edit = |fixture: &FixtureWithRanges, name: &str, new_text: &str| {
+            TextEdit::new(fixture.span(name).into(), new_text.to_owned())
+        };
+
+        assert_eq!(
+            Some(HashMap::from([
+                (
+                    bar_uri.clone(),
+                    vec![
+                        edit(&bar, "bar_def", "renamed"),
+                        edit(&bar, "bar_use", "renamed")
+                    ]
+                ),
+                (
+                    foo_uri.clone(),
+                    vec![
+                        edit(&foo, "foo_load", "\"renamed\""),
+                        edit(&foo, "foo_use", "renamed")
+                    ]
+                ),
+                (baz_uri.clone(), vec![edit(&baz, "baz_load", "\"renamed\"")]),
+            ])),
+            rename("click", "renamed")?.changes
+        );
+        assert_eq!(
+            Some(HashMap::from([(
+                bar_uri.clone(),
+                vec![edit(&bar, "param_def", "y"), edit(&bar, "param_use", "y")]
+            )])),
+            rename("param_use", "y")?.changes
+        );
+        assert!(rename("click", "not valid").is_err());
+        assert!(rename("click", "lambda").is_err());
+        // The symbol couldn't be loaded by the other files any more.
+        assert!(rename("click", "_private").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn renames_load_aliases_and_rejects_collisions() -> anyhow::Result<()> {
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let foo_contents = dedent(
+            r#"
+            load("bar.star", <alias_def>local</alias_def> = "baz")
+            <alias_use>loc<click>a</click>l</alias_use>()
+            existing = 1
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            def b<bar_click>a</bar_click>z():
+                pass
+            other = 1
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let foo = FixtureWithRanges::from_fixture(foo_uri.path(), &foo_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(foo_uri.clone(), foo.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+
+        let mut rename = |uri: &Url,
+                          fixture: &FixtureWithRanges,
+                          at: &str,
+                          new_name: &str|
+         -> anyhow::Result<WorkspaceEdit> {
+            let request = server.new_request::<Rename>(RenameParams {
+                text_document_position: text_document_position(
+                    uri.clone(),
+                    fixture.begin_line(at),
+                    fixture.begin_column(at),
+                ),
+                new_name: new_name.to_owned(),
+                work_done_progress_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            server.get_response::<WorkspaceEdit>(request_id)
+        };
+        let edit =
+            |name: &str, new_text: &str| TextEdit::new(foo.span(name).into(), new_text.to_owned());
+
+        // Only the alias is renamed, `baz` keeps its name in `bar.star`.
+        assert_eq!(
+            Some(HashMap::from([(
+                foo_uri.clone(),
+                vec![edit("alias_def", "renamed"), edit("alias_use", "renamed")]
+            )])),
+            rename(&foo_uri, &foo, "click", "renamed")?.changes
+        );
+        assert!(rename(&foo_uri, &foo, "click", "existing").is_err());
+        assert!(rename(&bar_uri, &bar, "bar_click", "other").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn finds_document_and_workspace_symbols() -> anyhow::Result<()> {
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let mut server = TestServer::new()?;
+        server.open_file(
+            foo_uri.clone(),
+            "def my_macro():\n    pass\n_private_macro = my_macro\nx = _private_macro\n".to_owned(),
+        )?;
+        server.set_file_contents(
+            PathBuf::from(bar_uri.path()),
+            "def my_other_macro():\n    pass\n".to_owned(),
+        )?;
+
+        let request = server.new_request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(foo_uri.clone()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let names = match server.get_response::<DocumentSymbolResponse>(request_id)? {
+            DocumentSymbolResponse::Nested(symbols) => {
+                symbols.into_iter().map(|symbol| symbol.name).collect()
+            }
+            DocumentSymbolResponse::Flat(_) => Vec::new(),
+        };
+        assert_eq!(vec!["my_macro", "_private_macro", "x"], names);
+
+        let request = server.new_request::<WorkspaceSymbol>(WorkspaceSymbolParams {
+            query: "MACRO".to_owned(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = server.get_response::<Vec<SymbolInformation>>(request_id)?;
+        assert_eq!(
+            vec![
+                ("my_macro".to_owned(), foo_uri),
+                ("my_other_macro".to_owned(), bar_uri)
+            ],
+            symbols
+                .into_iter()
+                .map(|symbol| (symbol.name, symbol.location.uri))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn formats_documents() -> anyhow::Result<()> {
+        let uri = temp_file_uri("foo.star");
+        let mut server = TestServer::new()?;
+        let format = |server: &mut TestServer| -> anyhow::Result<Option<Vec<TextEdit>>> {
+            let request = server.new_request::<Formatting>(DocumentFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                options: FormattingOptions::default(),
+                work_done_progress_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            server.get_response::<Option<Vec<TextEdit>>>(request_id)
+        };
+
+        server.open_file(uri.clone(), "x=[1,\n  2]\n".to_owned())?;
+        let expected = TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(2, 0)),
+            "x = [1, 2]\n".to_owned(),
+        );
+        assert_eq!(Some(vec![expected]), format(&mut server)?);
+
+        server.change_file(uri.clone(), "x = [1, 2]\n".to_owned())?;
+        assert_eq!(Some(Vec::new()), format(&mut server)?);
+
+        // Don't format the last valid parse, as that would lose the edits.
+        server.change_file(uri.clone(), "x = [1,\n".to_owned())?;
+        assert_eq!(None, format(&mut server)?);
+        Ok(())
+    }
 }
@@ -42,7 +42,6 @@ pub(crate) struct SymbolWithDetails<'a> {
 ///
 /// * Currently does not look into variables bound in list/dict comprehensions (should be fixed one day).
 /// * Does not return local variables that start with an underscore (since they )
-#[allow(dead_code)] // Used shortly by the LSP
 pub(crate) fn find_symbols_at_position<'a>(
     module: &'a AstModule,
     position: LineCol,
@@ -41,6 +41,7 @@ use lsp_types::request::Initialize;
 use lsp_types::request::Request;
 use lsp_types::request::Shutdown;
 use lsp_types::ClientCapabilities;
+use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
 use lsp_types::GotoCapability;
@@ -61,6 +62,8 @@ use crate::docs::render_docs_as_code;
 use crate::docs::Doc;
 use crate::docs::DocFunction;
 use crate::docs::DocItem;
+use crate::docs::DocMember;
+use crate::docs::DocModule;
 use crate::docs::Identifier;
 use crate::docs::Location;
 use crate::errors::EvalMessage;
@@ -120,6 +123,7 @@ struct TestServerContext {
     dirs: Arc<RwLock<HashSet<PathBuf>>>,
     builtin_docs: Arc<HashMap<LspUrl, String>>,
     builtin_symbols: Arc<HashMap<String, LspUrl>>,
+    environment: Arc<DocModule>,
 }
 
 impl LspContext for TestServerContext {
@@ -227,6 +231,19 @@ impl LspContext for TestServerContext {
     ) -> anyhow::Result<Option<LspUrl>> {
         Ok(self.builtin_symbols.get(symbol).cloned())
     }
+
+    fn get_environment(&self, _uri: &LspUrl) -> Arc<DocModule> {
+        self.environment.dupe()
+    }
+
+    fn get_workspace_files(&self, _uri: Option<&LspUrl>) -> anyhow::Result<Vec<LspUrl>> {
+        self.file_contents
+            .read()
+            .unwrap()
+            .keys()
+            .map(|path| Ok(Url::from_file_path(path).unwrap().try_into()?))
+            .collect()
+    }
 }
 
 /// A server for use in testing that provides helpers for sending requests, correlating
@@ -357,10 +374,19 @@ impl TestServer {
         let builtin = Self::testing_builtins(&std::env::current_dir()?)?;
         let mut builtin_docs = HashMap::with_capacity(builtin.len());
         let mut builtin_symbols = HashMap::new();
+        let mut environment = DocModule::default();
 
         for (u, ds) in builtin {
             builtin_docs.insert(u.clone(), render_docs_as_code(&ds));
             for d in ds {
+                let member = match d.item {
+                    DocItem::Function(f) => Some(DocMember::Function(f)),
+                    DocItem::Property(p) => Some(DocMember::Property(p)),
+                    DocItem::Module(_) | DocItem::Object(_) => None,
+                };
+                if let Some(member) = member {
+                    environment.members.insert(d.id.name.clone(), member);
+                }
                 builtin_symbols.insert(d.id.name, u.clone());
             }
         }
@@ -382,6 +408,7 @@ impl TestServer {
             dirs: dirs.dupe(),
             builtin_docs: builtin_docs.dupe(),
             builtin_symbols,
+            environment: Arc::new(environment),
         };
 
         let server_thread = std::thread::spawn(|| {
@@ -556,6 +583,25 @@ impl TestServer {
     ///
     /// This will return an error if there were any diagnostic messages.
     pub fn open_file(&mut self, uri: Url, contents: String) -> anyhow::Result<()> {
+        let diagnostics = self.open_file_with_diagnostics(uri.clone(), contents)?;
+        if !diagnostics.is_empty() {
+            Err(anyhow::anyhow!(
+                "Got unexpected diagnostic messages when opening {}, got {:?}",
+                uri,
+                diagnostics
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Send a notification saying that a file was opened with the given contents, and return
+    /// the diagnostics that were published for it.
+    pub fn open_file_with_diagnostics(
+        &mut self,
+        uri: Url,
+        contents: String,
+    ) -> anyhow::Result<Vec<Diagnostic>> {
         let open_params = DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
                 uri: uri.clone(),
@@ -573,14 +619,8 @@ impl TestServer {
                 notification.uri,
                 uri
             ))
-        } else if !notification.diagnostics.is_empty() {
-            Err(anyhow::anyhow!(
-                "Got unexpected diagnostic messages when opening {}, got {:?}",
-                uri,
-                notification.diagnostics
-            ))
         } else {
-            Ok(())
+            Ok(notification.diagnostics)
         }
     }
 
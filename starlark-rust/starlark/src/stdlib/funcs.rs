@@ -31,6 +31,7 @@ use crate::environment::GlobalsBuilder;
 use crate::eval::Arguments;
 use crate::eval::Evaluator;
 use crate::values::bool::BOOL_TYPE;
+use crate::values::bytes::StarlarkBytes;
 use crate::values::dict::Dict;
 use crate::values::dict::DictRef;
 use crate::values::float::StarlarkFloat;
@@ -52,6 +53,11 @@ use crate::values::Value;
 use crate::values::ValueError;
 use crate::values::ValueLike;
 
+/// The error raised by `fail()`.
+#[derive(Debug, thiserror::Error)]
+#[error("fail:{0}")]
+pub(crate) struct FailError(String);
+
 fn unpack_pair<'v>(pair: Value<'v>, heap: &'v Heap) -> anyhow::Result<(Value<'v>, Value<'v>)> {
     let mut it = pair.iterate(heap)?;
     if let Some(first) = it.next() {
@@ -156,7 +162,7 @@ pub(crate) fn global_functions(builder: &mut GlobalsBuilder) {
                 None => x.collect_repr(&mut s),
             }
         }
-        Err(anyhow::anyhow!("fail:{}", s))
+        Err(FailError(s).into())
     }
 
     /// [any](
@@ -247,6 +253,47 @@ pub(crate) fn global_functions(builder: &mut GlobalsBuilder) {
         }
     }
 
+    /// [bytes](
+    /// https://github.com/bazelbuild/starlark/blob/689f54426951638ef5b7c41a14d8fc48e65c5f77/spec.md#bytes
+    /// ): creates a bytes value.
+    ///
+    /// `bytes(x)` returns the UTF-8 encoding of `x` if it is a string, `x` itself if it is
+    /// bytes, or the bytes with the given values if `x` is an iterable of ints in `0..256`.
+    ///
+    /// ```
+    /// # starlark::assert::all_true(r#"
+    /// bytes("abc") == b"abc"
+    /// bytes([104, 105]) == b"hi"
+    /// bytes(b"x") == b"x"
+    /// # "#);
+    /// ```
+    #[starlark(dot_type = StarlarkBytes::TYPE, speculative_exec_safe)]
+    fn bytes<'v>(
+        #[starlark(require = pos, type = "[str.type, \"bytes\", iter(int.type)]")] x: Value<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        if let Some(s) = x.unpack_str() {
+            Ok(heap.alloc(StarlarkBytes::new(s.as_bytes())))
+        } else if x.downcast_ref::<StarlarkBytes>().is_some() {
+            Ok(x)
+        } else {
+            let mut res = Vec::new();
+            for b in x.iterate(heap)? {
+                match b.unpack_int() {
+                    Some(b @ 0..=255) => res.push(b as u8),
+                    _ => {
+                        return Err(ValueError::IncorrectParameterTypeWithExpected(
+                            "int in 0..256".to_owned(),
+                            b.to_repr(),
+                        )
+                        .into());
+                    }
+                }
+            }
+            Ok(heap.alloc(StarlarkBytes::new(&res)))
+        }
+    }
+
     /// [chr](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#bool
     /// ): returns a string encoding a codepoint.
@@ -1036,6 +1083,8 @@ pub(crate) fn global_functions(builder: &mut GlobalsBuilder) {
     /// ): formats its argument as a string.
     ///
     /// If x is a string, the result is x (without quotation).
+    /// If x is bytes, the result is x decoded as UTF-8, with invalid sequences
+    /// replaced by U+FFFD.
     /// All other strings, such as elements of a list of strings, are
     /// double-quoted.
     ///
@@ -1043,6 +1092,7 @@ pub(crate) fn global_functions(builder: &mut GlobalsBuilder) {
     /// # starlark::assert::all_true(r#"
     /// str(1)                          == '1'
     /// str("x")                        == 'x'
+    /// str(b"x")                       == 'x'
     /// str([1, "x"])                   == "[1, \"x\"]"
     /// # "#);
     /// ```
@@ -1054,6 +1104,10 @@ pub(crate) fn global_functions(builder: &mut GlobalsBuilder) {
         if let Some(a) = StringValue::new(a) {
             // Special case that can avoid reallocating, but is equivalent.
             Ok(a)
+        } else if let Some(a) = a.downcast_ref::<StarlarkBytes>() {
+            Ok(eval
+                .heap()
+                .alloc_str(&String::from_utf8_lossy(a.as_bytes())))
         } else {
             let mut s = eval.string_pool.alloc();
             a.collect_repr(&mut s);
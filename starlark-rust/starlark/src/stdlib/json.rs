@@ -15,16 +15,27 @@
  * limitations under the License.
  */
 
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
 use std::str::FromStr;
 
 use either::Either;
 use num_bigint::BigInt;
+use serde::de::DeserializeSeed;
+use serde::de::Error as _;
+use serde::de::IgnoredAny;
+use serde::de::MapAccess;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
+use serde::Deserializer;
 use starlark_derive::starlark_module;
 
 use crate as starlark;
 use crate::collections::SmallMap;
 use crate::environment::GlobalsBuilder;
 use crate::values::dict::AllocDict;
+use crate::values::dict::Dict;
 use crate::values::type_repr::StarlarkTypeRepr;
 use crate::values::types::bigint::StarlarkBigInt;
 use crate::values::AllocFrozenValue;
@@ -125,18 +136,252 @@ impl AllocFrozenValue for serde_json::Value {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+enum JsonError {
+    #[error("Invalid JSON: {0}")]
+    Invalid(serde_json::Error),
+}
+
+/// Decodes JSON directly into Starlark values, so that objects keep the order of their keys.
+#[derive(Clone, Copy)]
+struct JsonDecoder<'a, 'v> {
+    heap: &'v Heap,
+    /// What the numbers `serde_json` decodes as floats are, as found by `float_literals`.
+    floats: &'a RefCell<VecDeque<Option<BigInt>>>,
+}
+
+/// The numbers of a JSON document that `serde_json` decodes as floats, in order: the integers
+/// that don't fit in `i64` or `u64`, which are rounded, and `None` for the actual floats.
+fn float_literals(json: &str) -> VecDeque<Option<BigInt>> {
+    let mut res = VecDeque::new();
+    let mut chars = json.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '-' | '0'..='9' => {
+                let mut end = start + 1;
+                while let Some((i, _)) =
+                    chars.next_if(|(_, c)| matches!(c, '0'..='9' | '.' | 'e' | 'E' | '+' | '-'))
+                {
+                    end = i + 1;
+                }
+                let literal = &json[start..end];
+                if literal.parse::<i64>().is_err() && literal.parse::<u64>().is_err() {
+                    res.push_back(BigInt::from_str(literal).ok());
+                }
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+impl<'de, 'a, 'v> DeserializeSeed<'de> for JsonDecoder<'a, 'v> {
+    type Value = Value<'v>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value<'v>, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a, 'v> Visitor<'de> for JsonDecoder<'a, 'v> {
+    type Value = Value<'v>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value<'v>, E> {
+        Ok(Value::new_none())
+    }
+
+    fn visit_bool<E>(self, x: bool) -> Result<Value<'v>, E> {
+        Ok(Value::new_bool(x))
+    }
+
+    fn visit_i64<E>(self, x: i64) -> Result<Value<'v>, E> {
+        Ok(self.heap.alloc(x))
+    }
+
+    fn visit_u64<E>(self, x: u64) -> Result<Value<'v>, E> {
+        Ok(self.heap.alloc(x))
+    }
+
+    fn visit_f64<E>(self, x: f64) -> Result<Value<'v>, E> {
+        match self.floats.borrow_mut().pop_front() {
+            Some(Some(big)) => Ok(StarlarkBigInt::alloc_bigint(big, self.heap)),
+            _ => Ok(self.heap.alloc(x)),
+        }
+    }
+
+    fn visit_str<E>(self, x: &str) -> Result<Value<'v>, E> {
+        Ok(self.heap.alloc(x))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value<'v>, A::Error> {
+        let mut xs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(x) = seq.next_element_seed(self)? {
+            xs.push(x);
+        }
+        Ok(self.heap.alloc_list(&xs))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value<'v>, A::Error> {
+        let mut content = SmallMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(key) = map.next_key::<String>()? {
+            let k = self.heap.alloc_str(&key).get_hashed_value();
+            let v = map.next_value_seed(self)?;
+            if content.insert_hashed(k, v).is_some() {
+                return Err(A::Error::custom(format!("duplicate key `{}`", key)));
+            }
+        }
+        Ok(self.heap.alloc(Dict::new(content)))
+    }
+}
+
+/// Reformat valid JSON like Go's `json.Indent`: every array element and object member
+/// starts on a new line, beginning with `prefix` followed by `indent` once per level of nesting.
+fn indent_json(json: &str, prefix: &str, indent: &str) -> anyhow::Result<String> {
+    serde_json::from_str::<IgnoredAny>(json).map_err(JsonError::Invalid)?;
+
+    let mut res = String::with_capacity(json.len());
+    let newline = |res: &mut String, depth: usize| {
+        res.push('\n');
+        res.push_str(prefix);
+        for _ in 0..depth {
+            res.push_str(indent);
+        }
+    };
+    let mut depth = 0;
+    let mut chars = json.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                res.push(c);
+                while let Some(c) = chars.next() {
+                    res.push(c);
+                    match c {
+                        '\\' => res.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                res.push(c);
+                while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+                match chars.peek() {
+                    Some('}' | ']') => res.extend(chars.next()),
+                    _ => {
+                        depth += 1;
+                        newline(&mut res, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut res, depth);
+                res.push(c);
+            }
+            ',' => {
+                res.push(c);
+                newline(&mut res, depth);
+            }
+            ':' => res.push_str(": "),
+            c if c.is_ascii_whitespace() => {}
+            c => res.push(c),
+        }
+    }
+    Ok(res)
+}
+
 pub(crate) fn json(globals: &mut GlobalsBuilder) {
     #[starlark_module]
     fn json_members(globals: &mut GlobalsBuilder) {
+        /// Encode a value as JSON. Dicts must only have string keys, and floats must be finite.
+        ///
+        /// ```
+        /// # starlark::assert::is_true(r#"
+        /// json.encode({"a": [1, None, True]}) == '{"a":[1,null,true]}'
+        /// # "#);
+        /// ```
         fn encode(#[starlark(require = pos)] x: Value) -> anyhow::Result<String> {
             x.to_json()
         }
 
+        /// Decode a JSON string into dicts, lists, strings, numbers, bools and `None`.
+        /// Objects are decoded to dicts with their keys in the same order as in `x`,
+        /// and must not have the same key twice. Integers may be arbitrarily large.
+        ///
+        /// If `x` is not valid JSON, this fails, unless `default` is given,
+        /// in which case `default` is returned instead.
+        ///
+        /// ```
+        /// # starlark::assert::all_true(r#"
+        /// json.decode('{"b": [1, 2.5], "a": null}') == {"b": [1, 2.5], "a": None}
+        /// json.decode('[1', default = 7) == 7
+        /// # "#);
+        /// ```
         fn decode<'v>(
             #[starlark(require = pos)] x: &str,
+            default: Option<Value<'v>>,
             heap: &'v Heap,
         ) -> anyhow::Result<Value<'v>> {
-            Ok(heap.alloc(serde_json::from_str::<serde_json::Value>(x)?))
+            let mut deserializer = serde_json::Deserializer::from_str(x);
+            let floats = RefCell::new(float_literals(x));
+            let res = JsonDecoder {
+                heap,
+                floats: &floats,
+            }
+            .deserialize(&mut deserializer)
+            .and_then(|v| deserializer.end().map(|()| v));
+            match (res, default) {
+                (Ok(v), _) => Ok(v),
+                (Err(_), Some(default)) => Ok(default),
+                (Err(e), None) => Err(JsonError::Invalid(e).into()),
+            }
+        }
+
+        /// Encode a value as JSON like `json.encode`, then indent it like `json.indent`.
+        ///
+        /// ```
+        /// # starlark::assert::is_true(r#"
+        /// json.encode_indent({"a": [1]}, indent = " ") == '{\n "a": [\n  1\n ]\n}'
+        /// # "#);
+        /// ```
+        fn encode_indent(
+            #[starlark(require = pos)] x: Value,
+            #[starlark(require = named, default = "")] prefix: &str,
+            #[starlark(require = named, default = "\t")] indent: &str,
+        ) -> anyhow::Result<String> {
+            indent_json(&x.to_json()?, prefix, indent)
+        }
+
+        /// Reformat a JSON string so that every array element and object member is on its own
+        /// line, beginning with `prefix` followed by `indent` once per level of nesting.
+        /// Fails if `s` is not valid JSON.
+        ///
+        /// ```
+        /// # starlark::assert::is_true(r#"
+        /// json.indent('{"a":[1,{}]}', prefix = ">", indent = " ") == '{\n> "a": [\n>  1,\n>  {}\n> ]\n>}'
+        /// # "#);
+        /// ```
+        fn indent(
+            #[starlark(require = pos)] s: &str,
+            #[starlark(require = named, default = "")] prefix: &str,
+            #[starlark(require = named, default = "\t")] indent: &str,
+        ) -> anyhow::Result<String> {
+            indent_json(s, prefix, indent)
         }
     }
 
@@ -170,4 +415,58 @@ mod tests {
             "json.decode('123456789123456789123456789')",
         );
     }
+
+    #[test]
+    fn test_json_decode_big_int() {
+        let a = Assert::new();
+        a.eq(
+            "['123456789123456789123456789', '-98765432109876543210', '2.5']",
+            "[str(x) for x in json.decode('[123456789123456789123456789, -98765432109876543210, 2.5]')]",
+        );
+        a.is_true("type(json.decode('18446744073709551616')) == 'int'");
+        // Numbers in strings are not numbers.
+        a.is_true(
+            r#"type(json.decode('["1.5 \\\\\\"2.5", 18446744073709551616, 0.5]')[1]) == 'int'"#,
+        );
+    }
+
+    #[test]
+    fn test_json_decode_duplicate_key() {
+        let a = Assert::new();
+        a.fail(
+            "json.decode('{\"a\": 1, \"b\": 2, \"a\": 3}')",
+            "duplicate key `a`",
+        );
+        a.eq(
+            "None",
+            "json.decode('{\"a\": 1, \"a\": 1}', default = None)",
+        );
+    }
+
+    #[test]
+    fn test_json_decode_order() {
+        let a = Assert::new();
+        a.eq(
+            "['z', 'a', 'm']",
+            "list(json.decode('{\"z\": 1, \"a\": 2, \"m\": 3}').keys())",
+        );
+    }
+
+    #[test]
+    fn test_json_decode_invalid() {
+        let a = Assert::new();
+        a.fail("json.decode('[1,')", "line 1 column 3");
+        a.fail("json.decode('[1] 2')", "trailing characters");
+        a.eq("None", "json.decode('nope', default = None)");
+    }
+
+    #[test]
+    fn test_json_indent() {
+        let a = Assert::new();
+        a.eq(
+            "'[\\n\\t1,\\n\\t\"a, b\",\\n\\t[]\\n]'",
+            "json.indent(' [1, \"a, b\", [ ]]')",
+        );
+        a.fail("json.indent('{')", "Invalid JSON");
+    }
 }
@@ -26,12 +26,13 @@ pub(crate) mod breakpoint;
 pub(crate) mod dict;
 pub(crate) mod enumeration;
 pub(crate) mod extra;
-mod funcs;
+pub(crate) mod funcs;
 pub(crate) mod json;
 pub(crate) mod partial;
 
 pub(crate) mod list;
 pub(crate) mod record;
+pub(crate) mod set;
 pub(crate) mod string;
 pub(crate) mod structs;
 pub(crate) mod util;
@@ -75,10 +76,13 @@ pub enum LibraryExtension {
     Pprint,
     /// Add a function `breakpoint()` which will drop into a console-module evaluation prompt.
     Breakpoint,
-    /// Add a function `json()` which will generate JSON for a module.
+    /// Add a `json` namespace with `encode`, `decode`, `encode_indent` and `indent`,
+    /// compatible with Bazel's `json` module.
     Json,
     /// Add a function `abs()` which will take the absolute value of an int.
     Abs,
+    /// Definitions to support the `set` type, the `set()` constructor.
+    SetType,
     // Make sure if you add anything new, you add it to `all` below.
 }
 
@@ -100,6 +104,7 @@ impl LibraryExtension {
             Breakpoint,
             Json,
             Abs,
+            SetType,
         ]
     }
 
@@ -120,6 +125,7 @@ impl LibraryExtension {
             Breakpoint => breakpoint::global(builder),
             Json => json::json(builder),
             Abs => extra::abs(builder),
+            SetType => set::global(builder),
         }
     }
 }
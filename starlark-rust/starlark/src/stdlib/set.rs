@@ -0,0 +1,231 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `set()` constructor and the methods of the `set` type.
+
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::collections::SmallSet;
+use crate::environment::GlobalsBuilder;
+use crate::environment::MethodsBuilder;
+use crate::values::error::ValueError;
+use crate::values::none::NoneType;
+use crate::values::set::Set;
+use crate::values::set::SetMut;
+use crate::values::set::SetRef;
+use crate::values::Heap;
+use crate::values::Value;
+
+/// Collect the elements of an iterable into a set, failing if any of them is not hashable.
+fn collect_set<'v>(it: Value<'v>, heap: &'v Heap) -> anyhow::Result<Set<'v>> {
+    let mut content = SmallSet::new();
+    for x in it.iterate(heap)? {
+        content.insert_hashed(x.get_hashed()?);
+    }
+    Ok(Set::new(content))
+}
+
+/// Apply `f` to `other` as a set, converting it first if it is some other iterable.
+fn with_set<'v, R>(
+    other: Value<'v>,
+    heap: &'v Heap,
+    f: impl FnOnce(&Set<'v>) -> R,
+) -> anyhow::Result<R> {
+    match SetRef::from_value(other) {
+        Some(other) => Ok(f(&other)),
+        None => Ok(f(&collect_set(other, heap)?)),
+    }
+}
+
+#[starlark_module]
+pub(crate) fn global(builder: &mut GlobalsBuilder) {
+    /// Creates a set, a collection of unique hashable values which iterates in insertion order.
+    ///
+    /// `set()` returns a new empty set, and `set(x)` returns a new set containing the elements
+    /// of the iterable `x`, ignoring duplicates.
+    ///
+    /// ```
+    /// # starlark::assert::all_true(r#"
+    /// len(set()) == 0
+    /// set([1, 2, 1]) == set([2, 1])
+    /// list(set("abc".elems())) == ["a", "b", "c"]
+    /// # "#);
+    /// ```
+    #[starlark(dot_type = Set::TYPE, speculative_exec_safe)]
+    fn set<'v>(
+        #[starlark(require = pos, type = "iter(\"\")")] a: Option<Value<'v>>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Set<'v>> {
+        match a {
+            None => Ok(Set::default()),
+            Some(a) => collect_set(a, heap),
+        }
+    }
+}
+
+#[starlark_module]
+pub(crate) fn set_methods(registry: &mut MethodsBuilder) {
+    /// `S.add(x)` adds `x` to the set S if it is not already present, and returns `None`.
+    /// It fails if `x` is unhashable, or the set is frozen or has active iterators.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// x = set([1])
+    /// x.add(2)
+    /// x.add(1)
+    /// x == set([1, 2])
+    /// # "#);
+    /// ```
+    fn add<'v>(
+        this: Value<'v>,
+        #[starlark(require = pos)] value: Value<'v>,
+    ) -> anyhow::Result<NoneType> {
+        let mut this = SetMut::from_value(this)?;
+        this.insert_hashed(value.get_hashed()?);
+        Ok(NoneType)
+    }
+
+    /// `S.clear()` removes all the elements of the set S and returns `None`.
+    /// It fails if the set is frozen or has active iterators.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// x = set([1, 2])
+    /// x.clear()
+    /// x == set()
+    /// # "#);
+    /// ```
+    fn clear(this: Value) -> anyhow::Result<NoneType> {
+        let mut this = SetMut::from_value(this)?;
+        this.clear();
+        Ok(NoneType)
+    }
+
+    /// `S.discard(x)` removes `x` from the set S if it is present, and returns `None`.
+    /// It fails if `x` is unhashable, or the set is frozen or has active iterators.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// x = set([1, 2])
+    /// x.discard(2)
+    /// x.discard(3)
+    /// x == set([1])
+    /// # "#);
+    /// ```
+    fn discard<'v>(
+        this: Value<'v>,
+        #[starlark(require = pos)] value: Value<'v>,
+    ) -> anyhow::Result<NoneType> {
+        let mut this = SetMut::from_value(this)?;
+        this.remove_hashed(value.get_hashed()?);
+        Ok(NoneType)
+    }
+
+    /// `S.remove(x)` removes `x` from the set S, and returns `None`.
+    /// Unlike `discard`, it fails if `x` is not present.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// x = set([1, 2])
+    /// x.remove(2)
+    /// x == set([1])
+    /// # "#);
+    /// # starlark::assert::fail(r#"
+    /// set([1]).remove(2) # error: not found
+    /// # "#, "not found");
+    /// ```
+    fn remove<'v>(
+        this: Value<'v>,
+        #[starlark(require = pos)] value: Value<'v>,
+    ) -> anyhow::Result<NoneType> {
+        let mut this = SetMut::from_value(this)?;
+        if this.remove_hashed(value.get_hashed()?) {
+            Ok(NoneType)
+        } else {
+            Err(ValueError::KeyNotFound(value.to_repr()).into())
+        }
+    }
+
+    /// `S.union(x)` returns a new set with the elements of the set S followed by those of the
+    /// iterable `x` which are not already in S. `S | x` is equivalent when `x` is a set.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// set([1, 2]).union([2, 3]) == set([1, 2, 3])
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn union<'v>(
+        this: SetRef<'v>,
+        #[starlark(require = pos, type = "iter(\"\")")] other: Value<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Set<'v>> {
+        with_set(other, heap, |other| this.union(other))
+    }
+
+    /// `S.intersection(x)` returns a new set with the elements of the set S which are also in
+    /// the iterable `x`. `S & x` is equivalent when `x` is a set.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// set([1, 2]).intersection([2, 3]) == set([2])
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn intersection<'v>(
+        this: SetRef<'v>,
+        #[starlark(require = pos, type = "iter(\"\")")] other: Value<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Set<'v>> {
+        with_set(other, heap, |other| this.intersection(other))
+    }
+
+    /// `S.difference(x)` returns a new set with the elements of the set S which are not in
+    /// the iterable `x`. `S - x` is equivalent when `x` is a set.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// set([1, 2]).difference([2, 3]) == set([1])
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn difference<'v>(
+        this: SetRef<'v>,
+        #[starlark(require = pos, type = "iter(\"\")")] other: Value<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Set<'v>> {
+        with_set(other, heap, |other| this.difference(other))
+    }
+
+    /// `S.issubset(x)` returns `True` if every element of the set S is in the iterable `x`.
+    ///
+    /// ```
+    /// # starlark::assert::all_true(r#"
+    /// set([1, 2]).issubset([3, 2, 1])
+    /// not set([1, 4]).issubset(set([1, 2]))
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn issubset<'v>(
+        this: SetRef<'v>,
+        #[starlark(require = pos, type = "iter(\"\")")] other: Value<'v>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<bool> {
+        with_set(other, heap, |other| this.is_subset(other))
+    }
+}
@@ -32,12 +32,13 @@ use crate::codemap::Span;
 use crate::codemap::Spanned;
 use crate::syntax::lexer::TokenInt;
 use crate::syntax::Dialect;
+use crate::values::bytes::StarlarkBytes;
 
 /// Payload types attached to AST nodes.
 pub(crate) trait AstPayload: Debug {
-    type IdentPayload: Debug;
-    type IdentAssignPayload: Debug;
-    type DefPayload: Debug;
+    type IdentPayload: Debug + Clone;
+    type IdentAssignPayload: Debug + Clone;
+    type DefPayload: Debug + Clone;
 }
 
 /// Default implementation of payload, which attaches `()` to nodes.
@@ -75,6 +76,7 @@ pub(crate) type AstAssign = AstAssignP<AstNoPayload>;
 pub(crate) type AstAssignIdent = AstAssignIdentP<AstNoPayload>;
 pub(crate) type AstArgument = AstArgumentP<AstNoPayload>;
 pub(crate) type AstString = Spanned<String>;
+pub(crate) type AstBytes = Spanned<Vec<u8>>;
 pub(crate) type AstParameter = AstParameterP<AstNoPayload>;
 pub(crate) type AstInt = Spanned<TokenInt>;
 pub(crate) type AstFloat = Spanned<f64>;
@@ -87,7 +89,7 @@ pub(crate) type AstStmt = AstStmtP<AstNoPayload>;
 ///
 /// The internal details (statements/expressions) are deliberately omitted, as they change
 /// more regularly. A few methods to obtain information about the AST are provided.
-#[derive(Derivative)]
+#[derive(Derivative, Clone)]
 #[derivative(Debug)]
 pub struct AstModule {
     #[derivative(Debug = "ignore")]
@@ -128,7 +130,7 @@ pub(crate) trait ToAst: Sized {
 
 impl<T> ToAst for T {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ArgumentP<P: AstPayload> {
     Positional(AstExprP<P>),
     Named(AstString, AstExprP<P>),
@@ -136,7 +138,7 @@ pub(crate) enum ArgumentP<P: AstPayload> {
     KwArgs(AstExprP<P>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ParameterP<P: AstPayload> {
     Normal(AstAssignIdentP<P>, Option<Box<AstExprP<P>>>),
     WithDefaultValue(
@@ -154,9 +156,10 @@ pub(crate) enum AstLiteral {
     Int(AstInt),
     Float(AstFloat),
     String(AstString),
+    Bytes(AstBytes),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LambdaP<P: AstPayload> {
     pub(crate) params: Vec<AstParameterP<P>>,
     pub(crate) body: Box<AstExprP<P>>,
@@ -176,7 +179,7 @@ impl<P: AstPayload> LambdaP<P> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ExprP<P: AstPayload> {
     Tuple(Vec<AstExprP<P>>),
     Dot(Box<AstExprP<P>>, AstString),
@@ -208,7 +211,7 @@ pub(crate) enum ExprP<P: AstPayload> {
 }
 
 /// In some places e.g. AssignModify, the Tuple case is not allowed.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum AssignP<P: AstPayload> {
     // We use Tuple for both Tuple and List,
     // as these have the same semantics in Starlark.
@@ -223,19 +226,19 @@ pub(crate) enum AssignP<P: AstPayload> {
 pub(crate) struct AssignIdentP<P: AstPayload>(pub String, pub P::IdentAssignPayload);
 
 /// `load` statement.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LoadP<P: AstPayload> {
     pub module: AstString,
     pub args: Vec<(AstAssignIdentP<P>, AstString)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct ForClauseP<P: AstPayload> {
     pub(crate) var: AstAssignP<P>,
     pub(crate) over: AstExprP<P>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ClauseP<P: AstPayload> {
     For(ForClauseP<P>),
     If(AstExprP<P>),
@@ -287,7 +290,7 @@ pub enum Visibility {
     Public,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct DefP<P: AstPayload> {
     pub(crate) name: AstAssignIdentP<P>,
     pub(crate) params: Vec<AstParameterP<P>>,
@@ -309,7 +312,7 @@ impl<P: AstPayload> DefP<P> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum StmtP<P: AstPayload> {
     Break,
     Continue,
@@ -446,6 +449,7 @@ impl Display for AstLiteral {
             AstLiteral::Int(i) => write!(f, "{}", &i.node),
             AstLiteral::Float(n) => write!(f, "{}", &n.node),
             AstLiteral::String(s) => fmt_string_literal(f, &s.node),
+            AstLiteral::Bytes(b) => write!(f, "{}", StarlarkBytes::new(&b.node)),
         }
     }
 }
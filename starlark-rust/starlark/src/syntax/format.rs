@@ -0,0 +1,938 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Print an [`AstModule`] as canonical source code, keeping its comments.
+//!
+//! Most of the layout comes from the AST alone, but a few choices are taken from the
+//! original source:
+//!
+//! * Blank lines between statements (or between the items of a bracketed list) are kept,
+//!   although several in a row are collapsed into one.
+//! * A bracketed list (e.g. the arguments of a call) is written with one item per line if
+//!   its first item started on a new line, or it contains a comment. Otherwise it is
+//!   written on a single line.
+//! * String literals keep their original escapes, and are only switched to double quotes
+//!   when that doesn't require escaping anything.
+//!
+//! Comments are not part of the AST, so they are recovered from the source and written out
+//! before the statement or list item that follows them, or at the end of the line they
+//! were on.
+
+use crate::codemap::CodeMap;
+use crate::codemap::Pos;
+use crate::codemap::Span;
+use crate::codemap::Spanned;
+use crate::syntax::ast::Argument;
+use crate::syntax::ast::Assign;
+use crate::syntax::ast::AstArgument;
+use crate::syntax::ast::AstAssign;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstNoPayload;
+use crate::syntax::ast::AstParameter;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::BinOp;
+use crate::syntax::ast::Clause;
+use crate::syntax::ast::DefP;
+use crate::syntax::ast::Expr;
+use crate::syntax::ast::ForClause;
+use crate::syntax::ast::Load;
+use crate::syntax::ast::Parameter;
+use crate::syntax::ast::Stmt;
+use crate::syntax::AstModule;
+
+const INDENT: &str = "    ";
+
+// How tightly each kind of expression binds, from loosest to tightest.
+const PREC_TEST: u8 = 0; // `lambda` and `x if c else y`
+const PREC_OR: u8 = 1;
+const PREC_AND: u8 = 2;
+const PREC_NOT: u8 = 3;
+const PREC_COMPARE: u8 = 4;
+const PREC_BIT_OR: u8 = 5;
+const PREC_BIT_XOR: u8 = 6;
+const PREC_BIT_AND: u8 = 7;
+const PREC_SHIFT: u8 = 8;
+const PREC_ARITH: u8 = 9;
+const PREC_PRODUCT: u8 = 10;
+const PREC_UNARY: u8 = 11;
+const PREC_PRIMARY: u8 = 12;
+
+fn binop_precedence(op: BinOp) -> u8 {
+    match op {
+        BinOp::Or => PREC_OR,
+        BinOp::And => PREC_AND,
+        BinOp::Equal
+        | BinOp::NotEqual
+        | BinOp::Less
+        | BinOp::Greater
+        | BinOp::LessOrEqual
+        | BinOp::GreaterOrEqual
+        | BinOp::In
+        | BinOp::NotIn => PREC_COMPARE,
+        BinOp::BitOr => PREC_BIT_OR,
+        BinOp::BitXor => PREC_BIT_XOR,
+        BinOp::BitAnd => PREC_BIT_AND,
+        BinOp::LeftShift | BinOp::RightShift => PREC_SHIFT,
+        BinOp::Add | BinOp::Subtract => PREC_ARITH,
+        BinOp::Multiply | BinOp::Percent | BinOp::Divide | BinOp::FloorDivide => PREC_PRODUCT,
+    }
+}
+
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Lambda(_) | Expr::If(_) => PREC_TEST,
+        Expr::Not(_) => PREC_NOT,
+        Expr::Op(_, op, _) => binop_precedence(*op),
+        Expr::Minus(_) | Expr::Plus(_) | Expr::BitNot(_) => PREC_UNARY,
+        _ => PREC_PRIMARY,
+    }
+}
+
+/// Find the comments in `source`, not including the newline that ends them.
+fn find_comments(source: &str) -> Vec<Span> {
+    let span = |begin: usize, end: usize| Span::new(Pos::new(begin as u32), Pos::new(end as u32));
+    let bytes = source.as_bytes();
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                let end = source[i..].find('\n').map_or(source.len(), |x| i + x);
+                res.push(span(i, i + source[i..end].trim_end().len()));
+                i = end;
+            }
+            // Skip over strings, so a `#` within one is not mistaken for a comment.
+            quote @ (b'"' | b'\'') => {
+                let triple = bytes[i..].starts_with(&[quote; 3]);
+                i += if triple { 3 } else { 1 };
+                while i < bytes.len() {
+                    if bytes[i] == b'\\' {
+                        i += 2;
+                    } else if triple && bytes[i..].starts_with(&[quote; 3]) {
+                        i += 3;
+                        break;
+                    } else if !triple && (bytes[i] == quote || bytes[i] == b'\n') {
+                        i += 1;
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    res
+}
+
+/// The statements within `stmt`, with nested [`Stmt::Statements`] flattened.
+fn flatten_statements(stmt: &AstStmt) -> Vec<&AstStmt> {
+    fn f<'a>(stmt: &'a AstStmt, res: &mut Vec<&'a AstStmt>) {
+        match &**stmt {
+            Stmt::Statements(xs) => {
+                for x in xs {
+                    f(x, res);
+                }
+            }
+            _ => res.push(stmt),
+        }
+    }
+
+    let mut res = Vec::new();
+    f(stmt, &mut res);
+    res
+}
+
+/// The position just before `pos`.
+fn before(pos: Pos) -> Pos {
+    Pos::new(pos.get() - 1)
+}
+
+fn spans<T>(xs: &[Spanned<T>]) -> Vec<Span> {
+    xs.iter().map(|x| x.span).collect()
+}
+
+fn for_clause_span(clause: &ForClause) -> Span {
+    clause.var.span.merge(clause.over.span)
+}
+
+/// How the items of a bracketed list are separated.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Separator {
+    /// By commas, e.g. the elements of a list.
+    Comma,
+    /// By commas, with a trailing comma if there is only one item, i.e. the elements of a tuple.
+    TupleComma,
+    /// By spaces, i.e. the clauses of a comprehension.
+    Space,
+}
+
+struct Printer<'a> {
+    codemap: &'a CodeMap,
+    /// Every comment in the file, in order.
+    comments: Vec<Span>,
+    /// The index of the first comment that hasn't been written yet.
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    /// Whether nothing has been written to the current line yet.
+    line_start: bool,
+    /// Whether the next line should be preceded by a blank line.
+    want_blank: bool,
+    /// Whether a blank line is allowed before the next line, which it isn't at the start of
+    /// a file, block or bracketed list.
+    allow_blank: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(codemap: &'a CodeMap) -> Self {
+        Self {
+            codemap,
+            comments: find_comments(codemap.source()),
+            next_comment: 0,
+            out: String::new(),
+            indent: 0,
+            line_start: true,
+            want_blank: false,
+            allow_blank: false,
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        if self.line_start {
+            if self.want_blank && self.allow_blank {
+                self.out.push('\n');
+            }
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.line_start = false;
+            self.want_blank = false;
+        }
+        self.out.push_str(s);
+        self.allow_blank = true;
+    }
+
+    fn new_line(&mut self) {
+        self.out.push('\n');
+        self.line_start = true;
+    }
+
+    fn source(&self) -> &'a str {
+        self.codemap.source()
+    }
+
+    fn char_at(&self, pos: Pos) -> Option<char> {
+        self.source()[pos.get() as usize..].chars().next()
+    }
+
+    fn line(&self, pos: Pos) -> usize {
+        self.codemap.find_line(pos)
+    }
+
+    fn column(&self, pos: Pos) -> usize {
+        self.codemap.resolve_span(Span::new(pos, pos)).begin_column
+    }
+
+    /// Whether there is a line break in the source between `begin` and `end`.
+    fn has_break(&self, begin: Pos, end: Pos) -> bool {
+        self.codemap
+            .source_span(Span::new(begin, end))
+            .contains('\n')
+    }
+
+    /// Whether there is a comment in the source between `begin` and `end`.
+    fn has_comment(&self, begin: Pos, end: Pos) -> bool {
+        let i = self.comments.partition_point(|c| c.begin() <= begin);
+        self.comments.get(i).map_or(false, |c| c.begin() < end)
+    }
+
+    /// The first position at or after `pos` that isn't whitespace or part of a comment.
+    fn skip_forward(&self, mut pos: Pos) -> Pos {
+        loop {
+            let rest = &self.source()[pos.get() as usize..];
+            let trimmed = rest.trim_start();
+            pos = pos + (rest.len() - trimmed.len()) as u32;
+            if trimmed.starts_with('#') {
+                pos = pos + trimmed.find('\n').unwrap_or(trimmed.len()) as u32;
+            } else if trimmed.starts_with("\\\n") {
+                pos = pos + 2;
+            } else {
+                return pos;
+            }
+        }
+    }
+
+    /// The position of the last character before `pos` that isn't whitespace or part of
+    /// a comment.
+    fn skip_backward(&self, mut pos: Pos) -> Pos {
+        loop {
+            let before = self.source()[..pos.get() as usize].trim_end();
+            pos = Pos::new(before.len() as u32);
+            let i = self.comments.partition_point(|c| c.end() < pos);
+            match self.comments.get(i) {
+                Some(c) if c.end() == pos => pos = c.begin(),
+                _ => {
+                    let last = before.char_indices().next_back().map_or(0, |(i, _)| i);
+                    return Pos::new(last as u32);
+                }
+            }
+        }
+    }
+
+    /// The position of the bracket that closes a list opened at `open`, whose last item
+    /// (if any) ends at `last`.
+    fn closing_bracket(&self, open: Pos, last: Option<Pos>) -> Pos {
+        let pos = self.skip_forward(last.unwrap_or(open + 1));
+        if self.char_at(pos) == Some(',') {
+            self.skip_forward(pos + 1)
+        } else {
+            pos
+        }
+    }
+
+    /// If the tuple at `span` was written in brackets, the position of the opening bracket.
+    fn tuple_bracket(&self, span: Span) -> Option<Pos> {
+        let pos = self.skip_backward(span.begin());
+        (self.char_at(pos) == Some('(')).then_some(pos)
+    }
+
+    /// Ask for a blank line before the line that `pos` is on, if there was one in the source.
+    fn blank_before(&mut self, pos: Pos) {
+        let line = self.line(pos);
+        if line > 0 && self.codemap.source_line(line - 1).trim().is_empty() {
+            self.want_blank = true;
+        }
+    }
+
+    fn comment(&mut self, comment: Span) {
+        self.next_comment += 1;
+        let codemap = self.codemap;
+        self.write(codemap.source_span(comment));
+    }
+
+    /// Write the comments that come before `pos`, each on its own line.
+    fn comments_before(&mut self, pos: Pos) {
+        while let Some(c) = self.comments.get(self.next_comment).copied() {
+            if c.begin() >= pos {
+                break;
+            }
+            self.blank_before(c.begin());
+            self.comment(c);
+            self.new_line();
+        }
+    }
+
+    /// Write the comments that come before `pos` and are indented by at least `column`,
+    /// which belong at the end of a block rather than with whatever follows it.
+    fn block_end_comments(&mut self, pos: Pos, column: usize) {
+        while let Some(c) = self.comments.get(self.next_comment).copied() {
+            if c.begin() >= pos || self.column(c.begin()) < column {
+                break;
+            }
+            self.blank_before(c.begin());
+            self.comment(c);
+            self.new_line();
+        }
+    }
+
+    /// End the current line, which ended at `pos` in the source, along with any comment that
+    /// followed `pos` on the same line.
+    fn end_line(&mut self, pos: Pos) {
+        if let Some(c) = self.comments.get(self.next_comment).copied() {
+            if c.begin() >= pos && self.line(c.begin()) == self.line(pos) {
+                self.write("  ");
+                self.comment(c);
+            }
+        }
+        self.new_line();
+    }
+
+    /// Write a bracketed list of items (at `items` in the source), between brackets at `open`
+    /// and `close`, calling `item` with the index of each item to write it.
+    fn bracketed(
+        &mut self,
+        brackets: (&str, &str),
+        open: Pos,
+        close: Pos,
+        items: &[Span],
+        separator: Separator,
+        mut item: impl FnMut(&mut Self, usize),
+    ) {
+        self.write(brackets.0);
+        let expand = items
+            .first()
+            .map_or(false, |first| self.has_break(open, first.begin()))
+            || self.has_comment(open, close);
+        if expand {
+            self.indent += 1;
+            self.new_line();
+            self.allow_blank = false;
+            for (i, span) in items.iter().enumerate() {
+                self.comments_before(span.begin());
+                self.blank_before(span.begin());
+                item(self, i);
+                if separator != Separator::Space {
+                    self.write(",");
+                }
+                self.end_line(span.end());
+            }
+            self.comments_before(close);
+            self.indent -= 1;
+        } else {
+            for i in 0..items.len() {
+                if i != 0 {
+                    self.write(if separator == Separator::Space {
+                        " "
+                    } else {
+                        ", "
+                    });
+                }
+                item(self, i);
+            }
+            if separator == Separator::TupleComma && items.len() == 1 {
+                self.write(",");
+            }
+        }
+        self.write(brackets.1);
+    }
+
+    /// Write the statements of a block, where `next` is the position of whatever follows
+    /// the block in the source.
+    fn statements(&mut self, stmts: &[&AstStmt], next: Pos, top_level: bool) {
+        let is_def = |x: &AstStmt| matches!(&**x, Stmt::Def(_));
+        for (i, stmt) in stmts.iter().copied().enumerate() {
+            // Top level functions always get some space around them.
+            if top_level && i != 0 && (is_def(stmt) || is_def(stmts[i - 1])) {
+                self.want_blank = true;
+            }
+            self.comments_before(stmt.span.begin());
+            self.blank_before(stmt.span.begin());
+            let stmt_next = stmts.get(i + 1).map_or(next, |x| x.span.begin());
+            self.statement(stmt, stmt_next);
+        }
+    }
+
+    /// Write an indented block, which is followed by `next` in the source.
+    fn block(&mut self, body: &AstStmt, next: Pos) {
+        let stmts = flatten_statements(body);
+        let column = self.column(stmts[0].span.begin());
+        self.indent += 1;
+        self.allow_blank = false;
+        self.statements(&stmts, next, false);
+        self.block_end_comments(next, column);
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, stmt: &AstStmt, next: Pos) {
+        match &**stmt {
+            Stmt::Break => self.write("break"),
+            Stmt::Continue => self.write("continue"),
+            Stmt::Pass => self.write("pass"),
+            Stmt::Return(None) => self.write("return"),
+            Stmt::Return(Some(x)) => {
+                self.write("return ");
+                self.expr_list(x);
+            }
+            Stmt::Expression(x) => self.expr_list(x),
+            Stmt::Assign(lhs, ty_rhs) => {
+                let (ty, rhs) = &**ty_rhs;
+                self.assign(lhs, true);
+                if let Some(ty) = ty {
+                    self.write(": ");
+                    self.expr(ty, PREC_TEST);
+                }
+                self.write(" = ");
+                self.expr_list(rhs);
+            }
+            Stmt::AssignModify(lhs, op, rhs) => {
+                self.assign(lhs, true);
+                self.write(&op.to_string());
+                self.expr_list(rhs);
+            }
+            Stmt::Load(load) => self.load(stmt, load),
+            Stmt::Statements(_) => return self.statements(&flatten_statements(stmt), next, false),
+            Stmt::If(cond, then) => return self.if_statement("if", cond, then, None, next),
+            Stmt::IfElse(cond, then_else) => {
+                let (then, else_) = &**then_else;
+                return self.if_statement("if", cond, then, Some(else_), next);
+            }
+            Stmt::For(var, over_body) => {
+                let (over, body) = &**over_body;
+                self.write("for ");
+                self.assign(var, true);
+                self.write(" in ");
+                self.expr(over, PREC_TEST);
+                self.write(":");
+                self.end_line(over.span.end());
+                return self.block(body, next);
+            }
+            Stmt::Def(def) => return self.def(def, next),
+        }
+        self.end_line(stmt.span.end());
+    }
+
+    /// Write an `if` statement, or an `elif` branch of one if `keyword` is `elif`.
+    fn if_statement(
+        &mut self,
+        keyword: &str,
+        cond: &AstExpr,
+        then: &AstStmt,
+        else_: Option<&AstStmt>,
+        next: Pos,
+    ) {
+        self.write(keyword);
+        self.write(" ");
+        self.expr(cond, PREC_TEST);
+        self.write(":");
+        self.end_line(cond.span.end());
+        let else_ = match else_ {
+            None => return self.block(then, next),
+            Some(else_) => else_,
+        };
+
+        // The last thing before the `else` branch is either the `:` after `else`, or part
+        // of the `elif` line.
+        let else_pos = self.skip_backward(flatten_statements(else_)[0].span.begin());
+        let else_line = self.codemap.line_span(self.line(else_pos)).begin();
+        self.block(then, else_line);
+        self.comments_before(else_line);
+
+        // An `elif` branch is an `if` statement that starts with its condition, whereas an
+        // `if` nested within an `else` starts with the `if` keyword.
+        let source = self.codemap.source_span(else_.span);
+        let nested_if = source.starts_with("if")
+            && !source[2..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        match &**else_ {
+            Stmt::If(cond, then) if !nested_if => self.if_statement("elif", cond, then, None, next),
+            Stmt::IfElse(cond, then_else) if !nested_if => {
+                let (then, else_) = &**then_else;
+                self.if_statement("elif", cond, then, Some(else_), next)
+            }
+            _ => {
+                self.write("else:");
+                self.end_line(else_pos + 1);
+                self.block(else_, next);
+            }
+        }
+    }
+
+    fn def(&mut self, def: &DefP<AstNoPayload>, next: Pos) {
+        self.write("def ");
+        self.write(&def.name.0);
+        let open = self.skip_forward(def.name.span.end());
+        let close = self.closing_bracket(open, def.params.last().map(|x| x.span.end()));
+        self.bracketed(
+            ("(", ")"),
+            open,
+            close,
+            &spans(&def.params),
+            Separator::Comma,
+            |this, i| this.parameter(&def.params[i]),
+        );
+        let mut header_end = close + 1;
+        if let Some(return_type) = &def.return_type {
+            self.write(" -> ");
+            self.expr(return_type, PREC_TEST);
+            header_end = return_type.span.end();
+        }
+        self.write(":");
+        self.end_line(header_end);
+        self.block(&def.body, next);
+    }
+
+    fn load(&mut self, stmt: &AstStmt, load: &Load) {
+        self.write("load");
+        let open = self.skip_forward(stmt.span.begin() + "load".len() as u32);
+        let items: Vec<Span> = std::iter::once(load.module.span)
+            .chain(
+                load.args
+                    .iter()
+                    .map(|(name, symbol)| name.span.merge(symbol.span)),
+            )
+            .collect();
+        self.bracketed(
+            ("(", ")"),
+            open,
+            before(stmt.span.end()),
+            &items,
+            Separator::Comma,
+            |this, i| {
+                if i == 0 {
+                    return this.string(load.module.span);
+                }
+                let (name, symbol) = &load.args[i - 1];
+                // Without an alias, the name is the string itself.
+                if name.span != symbol.span {
+                    this.write(&name.0);
+                    this.write(" = ");
+                }
+                this.string(symbol.span);
+            },
+        );
+    }
+
+    fn parameter(&mut self, param: &AstParameter) {
+        let (prefix, name, ty, default) = match &**param {
+            Parameter::Normal(name, ty) => ("", name, ty, None),
+            Parameter::WithDefaultValue(name, ty, default) => ("", name, ty, Some(default)),
+            Parameter::NoArgs => return self.write("*"),
+            Parameter::Args(name, ty) => ("*", name, ty, None),
+            Parameter::KwArgs(name, ty) => ("**", name, ty, None),
+        };
+        self.write(prefix);
+        self.write(&name.0);
+        if let Some(ty) = ty {
+            self.write(": ");
+            self.expr(ty, PREC_TEST);
+        }
+        if let Some(default) = default {
+            self.write(" = ");
+            self.expr(default, PREC_TEST);
+        }
+    }
+
+    fn argument(&mut self, arg: &AstArgument) {
+        match &**arg {
+            Argument::Positional(x) => self.expr(x, PREC_TEST),
+            Argument::Named(name, x) => {
+                self.write(&name.node);
+                self.write(" = ");
+                self.expr(x, PREC_TEST);
+            }
+            Argument::Args(x) => {
+                self.write("*");
+                self.expr(x, PREC_TEST);
+            }
+            Argument::KwArgs(x) => {
+                self.write("**");
+                self.expr(x, PREC_TEST);
+            }
+        }
+    }
+
+    /// Write the target of an assignment, leaving out the brackets around a tuple if `bare`.
+    fn assign(&mut self, target: &AstAssign, bare: bool) {
+        match &**target {
+            Assign::Tuple(xs) => {
+                let bare = bare && !xs.is_empty();
+                if !bare {
+                    self.write("(");
+                }
+                for (i, x) in xs.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.assign(x, false);
+                }
+                if xs.len() == 1 {
+                    self.write(",");
+                }
+                if !bare {
+                    self.write(")");
+                }
+            }
+            Assign::ArrayIndirection(array_index) => {
+                let (array, index) = &**array_index;
+                self.expr(array, PREC_PRIMARY);
+                self.write("[");
+                self.expr_list(index);
+                self.write("]");
+            }
+            Assign::Dot(object, field) => {
+                self.expr(object, PREC_PRIMARY);
+                self.write(".");
+                self.write(&field.node);
+            }
+            Assign::Identifier(name) => self.write(&name.0),
+        }
+    }
+
+    /// Write an expression where a tuple doesn't need brackets, e.g. the right hand side of
+    /// an assignment. The brackets are kept if they were in the source.
+    fn expr_list(&mut self, expr: &AstExpr) {
+        match &**expr {
+            Expr::Tuple(xs) if !xs.is_empty() && self.tuple_bracket(expr.span).is_none() => {
+                for (i, x) in xs.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.expr(x, PREC_TEST);
+                }
+                if xs.len() == 1 {
+                    self.write(",");
+                }
+            }
+            _ => self.expr(expr, PREC_TEST),
+        }
+    }
+
+    /// Write an expression, in brackets if it binds less tightly than `min`.
+    fn expr(&mut self, expr: &AstExpr, min: u8) {
+        if precedence(expr) < min {
+            self.write("(");
+            self.expr_unbracketed(expr);
+            self.write(")");
+        } else {
+            self.expr_unbracketed(expr);
+        }
+    }
+
+    fn expr_unbracketed(&mut self, expr: &AstExpr) {
+        let codemap = self.codemap;
+        let open = expr.span.begin();
+        let close = before(expr.span.end());
+        match &**expr {
+            Expr::Tuple(xs) => {
+                // The span of an empty tuple includes its brackets, otherwise it doesn't.
+                let (open, close) = match xs.last() {
+                    None => (open, close),
+                    Some(last) => {
+                        let open = self.tuple_bracket(expr.span).unwrap_or(open);
+                        (open, self.closing_bracket(open, Some(last.span.end())))
+                    }
+                };
+                self.bracketed(
+                    ("(", ")"),
+                    open,
+                    close,
+                    &spans(xs),
+                    Separator::TupleComma,
+                    |this, i| this.expr(&xs[i], PREC_TEST),
+                );
+            }
+            Expr::Dot(object, field) => {
+                // `1.x` would be read as a float followed by an identifier.
+                if matches!(object.node, Expr::Literal(AstLiteral::Int(_))) {
+                    self.write("(");
+                    self.expr(object, PREC_TEST);
+                    self.write(")");
+                } else {
+                    self.expr(object, PREC_PRIMARY);
+                }
+                self.write(".");
+                self.write(&field.node);
+            }
+            Expr::Call(function, args) => {
+                self.expr(function, PREC_PRIMARY);
+                let open = self.skip_forward(function.span.end());
+                self.bracketed(
+                    ("(", ")"),
+                    open,
+                    close,
+                    &spans(args),
+                    Separator::Comma,
+                    |this, i| this.argument(&args[i]),
+                );
+            }
+            Expr::ArrayIndirection(array_index) => {
+                let (array, index) = &**array_index;
+                self.expr(array, PREC_PRIMARY);
+                self.write("[");
+                self.expr_list(index);
+                self.write("]");
+            }
+            Expr::Slice(array, start, stop, step) => {
+                self.expr(array, PREC_PRIMARY);
+                self.write("[");
+                if let Some(start) = start {
+                    self.expr(start, PREC_TEST);
+                }
+                self.write(":");
+                if let Some(stop) = stop {
+                    self.expr(stop, PREC_TEST);
+                }
+                if let Some(step) = step {
+                    self.write(":");
+                    self.expr(step, PREC_TEST);
+                }
+                self.write("]");
+            }
+            Expr::Identifier(name, _) => self.write(&name.node),
+            Expr::Lambda(lambda) => {
+                self.write("lambda");
+                for (i, param) in lambda.params.iter().enumerate() {
+                    self.write(if i == 0 { " " } else { ", " });
+                    self.parameter(param);
+                }
+                self.write(": ");
+                self.expr(&lambda.body, PREC_TEST);
+            }
+            Expr::Literal(AstLiteral::String(x)) => self.string(x.span),
+            Expr::Literal(AstLiteral::Bytes(x)) => self.string(x.span),
+            // Keep numbers as they were written, e.g. in hex.
+            Expr::Literal(AstLiteral::Int(x)) => self.write(codemap.source_span(x.span)),
+            Expr::Literal(AstLiteral::Float(x)) => self.write(codemap.source_span(x.span)),
+            Expr::Not(x) => {
+                self.write("not ");
+                self.expr(x, PREC_NOT);
+            }
+            Expr::Minus(x) => {
+                self.write("-");
+                self.expr(x, PREC_UNARY);
+            }
+            Expr::Plus(x) => {
+                self.write("+");
+                self.expr(x, PREC_UNARY);
+            }
+            Expr::BitNot(x) => {
+                self.write("~");
+                self.expr(x, PREC_UNARY);
+            }
+            Expr::Op(lhs, op, rhs) => {
+                let prec = binop_precedence(*op);
+                // Comparisons don't chain, so neither side can be another comparison.
+                let lhs_prec = if prec == PREC_COMPARE { prec + 1 } else { prec };
+                self.expr(lhs, lhs_prec);
+                self.write(&op.to_string());
+                self.expr(rhs, prec + 1);
+            }
+            Expr::If(cond_then_else) => {
+                let (cond, then, else_) = &**cond_then_else;
+                self.expr(then, PREC_OR);
+                self.write(" if ");
+                self.expr(cond, PREC_OR);
+                self.write(" else ");
+                self.expr(else_, PREC_TEST);
+            }
+            Expr::List(xs) => self.bracketed(
+                ("[", "]"),
+                open,
+                close,
+                &spans(xs),
+                Separator::Comma,
+                |this, i| this.expr(&xs[i], PREC_TEST),
+            ),
+            Expr::Dict(xs) => {
+                let items: Vec<Span> = xs.iter().map(|(k, v)| k.span.merge(v.span)).collect();
+                self.bracketed(
+                    ("{", "}"),
+                    open,
+                    close,
+                    &items,
+                    Separator::Comma,
+                    |this, i| {
+                        let (k, v) = &xs[i];
+                        this.expr(k, PREC_TEST);
+                        this.write(": ");
+                        this.expr(v, PREC_TEST);
+                    },
+                );
+            }
+            Expr::ListComprehension(x, for_, clauses) => self.comprehension(
+                ("[", "]"),
+                expr,
+                x.span,
+                |this| this.expr(x, PREC_TEST),
+                for_,
+                clauses,
+            ),
+            Expr::DictComprehension(k_v, for_, clauses) => {
+                let (k, v) = &**k_v;
+                self.comprehension(
+                    ("{", "}"),
+                    expr,
+                    k.span.merge(v.span),
+                    |this| {
+                        this.expr(k, PREC_TEST);
+                        this.write(": ");
+                        this.expr(v, PREC_TEST);
+                    },
+                    for_,
+                    clauses,
+                )
+            }
+        }
+    }
+
+    /// Write a comprehension, whose first part (e.g. the `x` in `[x for x in xs]`) is at
+    /// `head` in the source, and is written by `write_head`.
+    fn comprehension(
+        &mut self,
+        brackets: (&str, &str),
+        expr: &AstExpr,
+        head: Span,
+        write_head: impl Fn(&mut Self),
+        for_: &ForClause,
+        clauses: &[Clause],
+    ) {
+        let items: Vec<Span> = [head, for_clause_span(for_)]
+            .into_iter()
+            .chain(clauses.iter().map(|clause| match clause {
+                Clause::For(x) => for_clause_span(x),
+                Clause::If(x) => x.span,
+            }))
+            .collect();
+        self.bracketed(
+            brackets,
+            expr.span.begin(),
+            before(expr.span.end()),
+            &items,
+            Separator::Space,
+            |this, i| match i {
+                0 => write_head(this),
+                1 => this.for_clause(for_),
+                _ => match &clauses[i - 2] {
+                    Clause::For(x) => this.for_clause(x),
+                    Clause::If(x) => {
+                        this.write("if ");
+                        this.expr(x, PREC_OR);
+                    }
+                },
+            },
+        );
+    }
+
+    fn for_clause(&mut self, clause: &ForClause) {
+        self.write("for ");
+        self.assign(&clause.var, true);
+        self.write(" in ");
+        self.expr(&clause.over, PREC_OR);
+    }
+
+    /// Write a string literal, switching it to double quotes if that doesn't need any escapes.
+    fn string(&mut self, span: Span) {
+        let source = self.codemap.source_span(span);
+        // Split off the `r` or `b` prefix of a raw string or bytes.
+        let (prefix, literal) = source.split_at(source.find(['\'', '"']).unwrap_or(0));
+        let quotes = if literal.starts_with("'''") { 3 } else { 1 };
+        let contents = &literal[quotes..literal.len() - quotes];
+        if literal.starts_with('\'') && !contents.contains('"') {
+            let quote = &"\"\"\""[..quotes];
+            self.write(prefix);
+            self.write(quote);
+            self.write(contents);
+            self.write(quote);
+        } else {
+            self.write(source);
+        }
+    }
+}
+
+impl AstModule {
+    /// Format the module as canonical source code, keeping its comments.
+    ///
+    /// The result parses to the same AST, and formatting it again leaves it unchanged.
+    pub fn format(&self) -> String {
+        let mut printer = Printer::new(&self.codemap);
+        let end = Pos::new(self.codemap.source().len() as u32);
+        printer.statements(&self.top_level_statements(), end, true);
+        printer.comments_before(end);
+        printer.out
+    }
+}
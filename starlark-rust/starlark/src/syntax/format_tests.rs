@@ -0,0 +1,138 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+/// Format `program`, checking that formatting the result again doesn't change it.
+fn format(program: &str) -> String {
+    let parse = |x: &str| AstModule::parse("x.star", x.to_owned(), &Dialect::Extended).unwrap();
+    let res = parse(program).format();
+    assert_eq!(res, parse(&res).format());
+    res
+}
+
+#[test]
+fn test_format_statements() {
+    assert_eq!(
+        format(
+            r#"
+load('defs.bzl', 'rule', my_lib = "library")
+x=1;y=2
+def   f(a,*args,b:int=3,**kwargs)->str:
+  if a: return 'a'
+  elif b:
+    pass
+  else:
+      for k,v in kwargs.items(): print(k,v)
+  return  (a,b)
+z = (1,)
+"#
+        ),
+        r#"load("defs.bzl", "rule", my_lib = "library")
+x = 1
+y = 2
+
+def f(a, *args, b: int = 3, **kwargs) -> str:
+    if a:
+        return "a"
+    elif b:
+        pass
+    else:
+        for k, v in kwargs.items():
+            print(k, v)
+    return (a, b)
+
+z = (1,)
+"#
+    );
+}
+
+#[test]
+fn test_format_comments() {
+    assert_eq!(
+        format(
+            r#"# Header comment.
+
+load("a.bzl", "a")  # trailing
+
+
+# Before the rule.
+a(
+  name = "x",
+  srcs = [ "a.c",  # first
+    # leading
+    "b.c"],
+  deps = [":y"]
+)
+def f():
+    x = 1
+    # end of f
+# top level
+"#
+        ),
+        r#"# Header comment.
+
+load("a.bzl", "a")  # trailing
+
+# Before the rule.
+a(
+    name = "x",
+    srcs = [
+        "a.c",  # first
+        # leading
+        "b.c",
+    ],
+    deps = [":y"],
+)
+
+def f():
+    x = 1
+    # end of f
+# top level
+"#
+    );
+}
+
+#[test]
+fn test_format_expressions() {
+    assert_eq!(
+        format(
+            r#"
+x = (a + b) * c
+y = a + (b * c)
+z = not (a and b) or c
+w = (lambda: 1)()
+t = [i for i in (1, 2) if i]
+d = {k: v for k, v in {"a": 1}.items()}
+s = 'it"s'
+r = r'\d'
+n = 0x7F
+"#
+        ),
+        r#"x = (a + b) * c
+y = a + b * c
+z = not (a and b) or c
+w = (lambda: 1)()
+t = [i for i in (1, 2) if i]
+d = {k: v for k, v in {"a": 1}.items()}
+s = 'it"s'
+r = r"\d"
+n = 0x7F
+"#
+    );
+}
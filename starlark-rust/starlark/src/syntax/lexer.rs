@@ -383,6 +383,68 @@ impl<'a> Lexer<'a> {
         )
     }
 
+    /// Whether the opening quote of a string, e.g. `rb'`, makes it raw, and makes it bytes.
+    fn quote_prefix(quote: &str) -> (bool, bool) {
+        (quote.contains('r'), quote.contains('b'))
+    }
+
+    /// Bytes literals are lexed as raw strings, so that escapes which denote single bytes,
+    /// e.g. `\xff`, can be decoded here rather than as characters.
+    fn string_or_bytes(&self, string: Lexeme, raw: bool, bytes: bool) -> Lexeme {
+        if !bytes {
+            return string;
+        }
+        let (start, contents, end) = match string? {
+            (start, Token::String(contents), end) => (start, contents, end),
+            _ => unreachable!("Strings are lexed as Token::String"),
+        };
+        if raw {
+            return Ok((start, Token::Bytes(contents.into_bytes()), end));
+        }
+        let mut res = Vec::with_capacity(contents.len());
+        let mut it = CursorChars::new_offset(&contents, 0);
+        while let Some(c) = it.next() {
+            if c != '\\' {
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+            let pos = it.pos();
+            if Self::escape_byte(&mut it, &mut res).is_err() {
+                let bad = contents[pos..it.pos()].to_owned();
+                return self.err_span(
+                    if bad.is_empty() {
+                        LexemeError::EmptyEscapeSequence
+                    } else {
+                        LexemeError::InvalidEscapeSequence(bad)
+                    },
+                    start,
+                    end,
+                );
+            }
+        }
+        Ok((start, Token::Bytes(res), end))
+    }
+
+    // Like `escape`, but hex and octal escapes denote a single byte, rather than a character.
+    fn escape_byte(it: &mut CursorChars, res: &mut Vec<u8>) -> Result<(), ()> {
+        let byte = |c: char| u8::try_from(u32::from(c)).map_err(|_| ());
+        match it.next() {
+            Some('x') => res.push(byte(Self::escape_char(it, 2, 2, 16)?)?),
+            Some(c @ '0'..='7') => {
+                it.unnext(c);
+                res.push(byte(Self::escape_char(it, 1, 3, 8)?)?)
+            }
+            Some(c) => {
+                it.unnext(c);
+                let mut s = String::new();
+                Self::escape(it, &mut s)?;
+                res.extend_from_slice(s.as_bytes());
+            }
+            None => return Err(()),
+        }
+        Ok(())
+    }
+
     fn int(&self, s: &str, radix: u32) -> Lexeme {
         let span = self.lexer.span();
         match i32::from_str_radix(s, radix) {
@@ -460,10 +522,10 @@ impl<'a> Lexer<'a> {
                         }
                         Token::Int(..) => unreachable!("Lexer does not produce Int tokens"),
                         Token::RawDoubleQuote => {
-                            let raw = self.lexer.span().len() == 2;
-                            if self.lexer.remainder().starts_with("\"\"") {
+                            let (raw, bytes) = Self::quote_prefix(self.lexer.slice());
+                            let string = if self.lexer.remainder().starts_with("\"\"") {
                                 let mut qs = 0;
-                                Some(self.string(true, raw, |c| {
+                                self.string(true, raw || bytes, |c| {
                                     if c == '\"' {
                                         qs += 1;
                                         qs == 3
@@ -471,16 +533,17 @@ impl<'a> Lexer<'a> {
                                         qs = 0;
                                         false
                                     }
-                                }))
+                                })
                             } else {
-                                Some(self.string(false, raw, |c| c == '\"'))
-                            }
+                                self.string(false, raw || bytes, |c| c == '\"')
+                            };
+                            Some(self.string_or_bytes(string, raw, bytes))
                         }
                         Token::RawSingleQuote => {
-                            let raw = self.lexer.span().len() == 2;
-                            if self.lexer.remainder().starts_with("''") {
+                            let (raw, bytes) = Self::quote_prefix(self.lexer.slice());
+                            let string = if self.lexer.remainder().starts_with("''") {
                                 let mut qs = 0;
-                                Some(self.string(true, raw, |c| {
+                                self.string(true, raw || bytes, |c| {
                                     if c == '\'' {
                                         qs += 1;
                                         qs == 3
@@ -488,10 +551,11 @@ impl<'a> Lexer<'a> {
                                         qs = 0;
                                         false
                                     }
-                                }))
+                                })
                             } else {
-                                Some(self.string(false, raw, |c| c == '\''))
-                            }
+                                self.string(false, raw || bytes, |c| c == '\'')
+                            };
+                            Some(self.string_or_bytes(string, raw, bytes))
                         }
                         Token::OpeningCurly | Token::OpeningRound | Token::OpeningSquare => {
                             self.parens += 1;
@@ -538,9 +602,15 @@ pub enum Token {
     // things ourselves
     #[token("'")]
     #[token("r'")]
+    #[token("b'")]
+    #[token("br'")]
+    #[token("rb'")]
     RawSingleQuote,
     #[token("\"")]
     #[token("r\"")]
+    #[token("b\"")]
+    #[token("br\"")]
+    #[token("rb\"")]
     RawDoubleQuote,
 
     #[regex(
@@ -587,6 +657,7 @@ pub enum Token {
     Float(f64), // A float literal (3.14, .3, 1e6, 0.)
 
     String(String), // A string literal
+    Bytes(Vec<u8>), // A bytes literal
 
     // Keywords
     #[token("and")]
@@ -708,6 +779,15 @@ pub enum Token {
     ClosingRound,
 }
 
+/// Is `s` a single identifier, rather than a keyword, reserved word, or something else.
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut lexer = Token::lexer(s);
+    matches!(
+        (lexer.next(), lexer.next()),
+        (Some(Token::Identifier(_)), None)
+    )
+}
+
 impl Token {
     /// Used for testing
     pub(crate) fn unlex(&self) -> String {
@@ -721,6 +801,14 @@ impl Token {
                 // Reuse the StarlarkValue implementation since it's close to hand.
                 serde_json::to_string(x).unwrap()
             }
+            Token::Bytes(x) => {
+                let mut res = "b\"".to_owned();
+                for b in x {
+                    res.push_str(&format!("\\x{:02x}", b));
+                }
+                res.push('"');
+                res
+            }
             _ => {
                 let s = self.to_string();
                 // Out display is often: keyword 'lambda'
@@ -810,6 +898,7 @@ impl Display for Token {
             Token::RawBinInt => write!(f, "binary integer literal"),
             Token::Float(n) => write!(f, "float literal '{}'", n),
             Token::String(s) => write!(f, "string literal '{}'", s),
+            Token::Bytes(s) => write!(f, "bytes literal '{}'", String::from_utf8_lossy(s)),
             Token::RawSingleQuote => write!(f, "starting '"),
             Token::RawDoubleQuote => write!(f, "starting \""),
             Token::Tabs => Ok(()),
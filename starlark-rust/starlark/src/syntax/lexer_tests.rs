@@ -16,6 +16,7 @@
  */
 
 use crate::assert;
+use crate::syntax::lexer::is_identifier;
 use crate::syntax::lexer::Token::*;
 
 #[test]
@@ -103,6 +104,16 @@ fn test_identifier() {
     )
 }
 
+#[test]
+fn test_is_identifier() {
+    assert!(is_identifier("_CAPS_0123"));
+    assert!(!is_identifier("0123"));
+    assert!(!is_identifier("lambda"));
+    assert!(!is_identifier("while"));
+    assert!(!is_identifier("a b"));
+    assert!(!is_identifier(""));
+}
+
 #[test]
 fn test_string_lit() {
     assert_eq!(
@@ -129,6 +140,16 @@ fn test_string_lit() {
     );
 }
 
+#[test]
+fn test_bytes_lit() {
+    assert_eq!(
+        assert::lex("b'ab' b\"\" b'\\xff\\377' br'\\x00' rb\"\\n\""),
+        "b\"\\x61\\x62\" b\"\" b\"\\xff\\xff\" b\"\\x5c\\x78\\x30\\x30\" b\"\\x5c\\x6e\" \n"
+    );
+    assert::parse_fail("x = !b'\\400'!");
+    assert::parse_fail("x = !b'\\xT'!");
+}
+
 #[test]
 fn test_string_escape() {
     assert_eq!(assert::lex("'\\0\\0\\1n'"), "\"\\u0000\\u0000\\u0001n\" \n");
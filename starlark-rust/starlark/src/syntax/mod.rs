@@ -22,6 +22,8 @@ pub use dialect::Dialect;
 pub use dialect::DialectTypes;
 pub use parser::AstLoad;
 
+#[cfg(test)]
+mod format_tests;
 #[cfg(test)]
 mod grammar_tests;
 #[cfg(test)]
@@ -32,6 +34,7 @@ mod testcases;
 pub(crate) mod ast;
 pub(crate) mod cursors;
 mod dialect;
+mod format;
 pub(crate) mod lexer;
 pub(crate) mod payload_map;
 pub(crate) mod validate;
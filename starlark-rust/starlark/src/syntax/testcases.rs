@@ -16,6 +16,8 @@
  */
 
 use crate::assert;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
 
 macro_rules! testcases_parse {
     ($($x:expr)*) => {
@@ -215,3 +217,20 @@ fn parsing_testcases() {
         assert::parse(content);
     }
 }
+
+#[test]
+fn formatting_testcases() {
+    for (name, content) in TESTCASE_FILES {
+        let module = AstModule::parse(name, (*content).to_owned(), &Dialect::Extended).unwrap();
+        let formatted = module.format();
+        let reparsed = AstModule::parse(name, formatted.clone(), &Dialect::Extended).unwrap();
+        // Formatting must not change the meaning, and must be stable.
+        assert_eq!(
+            module.statement.to_string(),
+            reparsed.statement.to_string(),
+            "{}",
+            name
+        );
+        assert_eq!(formatted, reparsed.format(), "{}", name);
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::environment::Globals;
+use crate::environment::Module;
+use crate::eval::Evaluator;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+fn eval_with_limits(
+    program: &str,
+    max_instructions: Option<u64>,
+    max_heap_bytes: Option<usize>,
+) -> anyhow::Result<()> {
+    let module = Module::new();
+    let globals = Globals::standard();
+    let mut eval = Evaluator::new(&module);
+    if let Some(max) = max_instructions {
+        eval.set_max_instructions(max);
+    }
+    if let Some(max) = max_heap_bytes {
+        eval.set_max_heap_bytes(max);
+    }
+    let ast = AstModule::parse("a.star", program.to_owned(), &Dialect::Extended).unwrap();
+    eval.eval_module(ast, &globals)?;
+    Ok(())
+}
+
+#[test]
+fn test_instruction_limit() {
+    let program = "\
+def f():
+    x = 0
+    for i in range(1000000):
+        x += i
+    return x
+f()
+";
+    let err = eval_with_limits(program, Some(1000), None).unwrap_err();
+    let err = err.to_string();
+    assert!(
+        err.contains("Instruction limit of 1000 exceeded"),
+        "{}",
+        err
+    );
+    // The error is reported with the call stack.
+    assert!(err.contains("f()"), "{}", err);
+
+    eval_with_limits("x = [i for i in range(10)]", Some(1000), None).unwrap();
+}
+
+#[test]
+fn test_heap_limit() {
+    let program = "\
+x = []
+for i in range(1000000):
+    x.append(str(i))
+";
+    let err = eval_with_limits(program, None, Some(100_000)).unwrap_err();
+    let err = err.to_string();
+    assert!(
+        err.contains("Heap limit of 100000 bytes exceeded"),
+        "{}",
+        err
+    );
+
+    eval_with_limits("x = [str(i) for i in range(10)]", None, Some(100_000)).unwrap();
+}
+
+#[test]
+fn test_heap_limit_single_allocation() {
+    // Each of these would allocate hundreds of megabytes in one operation,
+    // so they must fail before allocating rather than at the next instruction.
+    for program in [
+        "x = 'x' * 1000000000",
+        "x = [None] * 100000000",
+        "x = (None,) * 100000000",
+    ] {
+        let err = eval_with_limits(
+            &format!("def f():\n    {}\nf()", program),
+            None,
+            Some(100_000),
+        )
+        .unwrap_err();
+        let err = err.to_string();
+        assert!(
+            err.contains("Heap limit of 100000 bytes exceeded by allocating"),
+            "{}",
+            err
+        );
+        // The error is reported with the call stack.
+        assert!(err.contains("f()"), "{}", err);
+    }
+
+    eval_with_limits("x = 'x' * 1000", None, Some(100_000)).unwrap();
+}
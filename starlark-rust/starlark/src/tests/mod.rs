@@ -27,6 +27,7 @@ mod for_loop;
 mod freeze_access_value;
 mod go;
 mod interop;
+mod limits;
 mod opt;
 mod runtime;
 mod type_annot;
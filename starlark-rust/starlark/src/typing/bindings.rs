@@ -21,6 +21,7 @@ use std::sync::Arc;
 use dupe::Dupe;
 
 use crate::codemap::Span;
+use crate::docs::DocModule;
 use crate::eval::compiler::scope::BindingId;
 use crate::eval::compiler::scope::CstAssign;
 use crate::eval::compiler::scope::CstAssignIdent;
@@ -93,6 +94,17 @@ impl Interface {
         Self(Arc::new(bindings))
     }
 
+    /// Create an interface from the documentation of a module, usually from
+    /// [`FrozenModule::documentation`](crate::environment::FrozenModule::documentation).
+    pub fn from_docs(docs: &DocModule) -> Self {
+        Self::new(
+            docs.members
+                .iter()
+                .map(|(name, member)| (name.clone(), Ty::from_docs_member(member)))
+                .collect(),
+        )
+    }
+
     /// Get the type for a given binding.
     pub fn get(&self, name: &str) -> Option<&Ty> {
         self.0.get(name)
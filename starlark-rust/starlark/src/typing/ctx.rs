@@ -79,6 +79,22 @@ pub(crate) enum TypingError {
     TooManyPositionalArguments { loc: ResolvedFileSpan },
 }
 
+impl TypingError {
+    /// The location the error was reported at.
+    pub(crate) fn loc(&self) -> &ResolvedFileSpan {
+        match self {
+            Self::AttributeNotAvailable { loc, .. }
+            | Self::UnknownBuiltin { loc, .. }
+            | Self::InvalidBuiltinCall { loc, .. }
+            | Self::IncompatibleType { loc, .. }
+            | Self::CallToNonCallable { loc, .. }
+            | Self::MissingRequiredParameter { loc, .. }
+            | Self::UnexpectedNamedArgument { loc, .. }
+            | Self::TooManyPositionalArguments { loc } => loc,
+        }
+    }
+}
+
 pub(crate) struct TypingContext<'a> {
     pub(crate) codemap: CodeMap,
     pub(crate) oracle: &'a dyn TypingOracle,
@@ -454,6 +470,7 @@ impl TypingContext<'_> {
                 AstLiteral::Int(_) => Ty::int(),
                 AstLiteral::Float(_) => Ty::float(),
                 AstLiteral::String(_) => Ty::string(),
+                AstLiteral::Bytes(_) => Ty::name("bytes"),
             },
             ExprP::Not(x) => {
                 if self.expression_type(x).is_void() {
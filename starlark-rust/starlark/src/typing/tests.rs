@@ -20,11 +20,15 @@ use std::collections::HashMap;
 use maplit::hashmap;
 use once_cell::sync::Lazy;
 
+use crate::environment::Globals;
+use crate::environment::Module;
+use crate::eval::Evaluator;
 use crate::stdlib::LibraryExtension;
 use crate::syntax::AstModule;
 use crate::syntax::Dialect;
 use crate::typing::Approximation;
 use crate::typing::Interface;
+use crate::typing::OracleDocs;
 use crate::typing::OracleNoBuiltins;
 use crate::typing::OracleStandard;
 use crate::typing::Param;
@@ -125,6 +129,40 @@ res = [foo([])]
     assert_eq!(interface.get("res").unwrap(), &Ty::list(Ty::string()));
 }
 
+#[test]
+fn test_load_from_docs() {
+    let module = Module::new();
+    let mut eval = Evaluator::new(&module);
+    let ast = AstModule::parse(
+        "foo.bzl",
+        r#"
+def foo(x: str.type) -> int.type:
+    return len(x)
+_private = 1
+"#
+        .to_owned(),
+        &Dialect::Extended,
+    )
+    .unwrap();
+    eval.eval_module(ast, &Globals::standard()).unwrap();
+    drop(eval);
+    let interface = Interface::from_docs(&module.freeze().unwrap().documentation());
+    assert!(interface.get("_private").is_none());
+
+    let (errs, _, _, _) = typecheck(
+        r#"
+load("foo.bzl", "foo")
+foo(1)
+   "#,
+        &hashmap!["foo.bzl".to_owned() => interface],
+    );
+    assert_eq!(errs.len(), 1);
+    assert_eq!(
+        format!("{:#}", errs[0]),
+        r#"Expected type `"string"` but got `"int"`, at filename:3:1-7"#
+    );
+}
+
 /// Test things that have previous claimed incorrectly they were type errors
 #[test]
 fn test_false_negative() {
@@ -152,3 +190,70 @@ foo(**{1: "x"})
         r#"Expected type `{"string": ""}` but got `{"int": "string"}`, at filename:4:7-15"#
     );
 }
+
+#[test]
+fn test_generic_types() {
+    let (errs, _, interface, approx) = typecheck(
+        r#"
+def f(x: list[str], y: dict[str, int]) -> tuple[int, str]:
+    return (1, "a")
+a = f(["a"], {"a": 1})
+f([1], {})
+"#,
+        &HashMap::new(),
+    );
+    assert!(approx.is_empty());
+    assert_eq!(
+        interface.get("a").unwrap(),
+        &Ty::Tuple(vec![Ty::int(), Ty::string()])
+    );
+    assert_eq!(errs.len(), 1);
+    assert_eq!(
+        format!("{:#}", errs[0]),
+        r#"Expected type `["string"]` but got `["int"]`, at filename:5:1-11"#
+    );
+}
+
+#[test]
+fn test_record_fields() {
+    let module = Module::new();
+    let mut eval = Evaluator::new(&module);
+    let ast = AstModule::parse(
+        "foo.bzl",
+        r#"
+Foo = record(x=int.type, y=field(str.type, ""))
+def make() -> Foo.type:
+    return Foo(x=1)
+"#
+        .to_owned(),
+        &Dialect::Extended,
+    )
+    .unwrap();
+    eval.eval_module(ast, &Globals::extended()).unwrap();
+    drop(eval);
+    let module = module.freeze().unwrap();
+    let records = OracleDocs::new(&module.type_documentation());
+    let standard = mk_oracle();
+    let oracle: [&dyn TypingOracle; 2] = [&records, &standard];
+
+    let (errs, _, interface, _) = AstModule::parse(
+        "filename",
+        r#"
+load("foo.bzl", "Foo", "make")
+a = make().x
+b = Foo(x=1).y
+make().z
+Foo(x="bad")
+"#
+        .to_owned(),
+        &Dialect::Extended,
+    )
+    .unwrap()
+    .typecheck(
+        &oracle.as_slice(),
+        &hashmap!["foo.bzl".to_owned() => Interface::from_docs(&module.documentation())],
+    );
+    assert_eq!(interface.get("a").unwrap(), &Ty::int());
+    assert_eq!(interface.get("b").unwrap(), &Ty::string());
+    assert_eq!(errs.len(), 2);
+}
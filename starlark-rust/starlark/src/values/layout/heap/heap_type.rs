@@ -48,6 +48,7 @@ use crate::collections::maybe_uninit_backport::maybe_uninit_write_slice_cloned;
 use crate::collections::Hashed;
 use crate::collections::StarlarkHashValue;
 use crate::eval::compiler::def::FrozenDef;
+use crate::eval::runtime::limits::EvalLimitsError;
 use crate::values::any::StarlarkAny;
 use crate::values::array::Array;
 use crate::values::layout::avalue::any_array_avalue;
@@ -105,6 +106,8 @@ pub(crate) enum HeapKind {
 pub struct Heap {
     /// Peak memory seen when a garbage collection takes place (may be lower than currently allocated)
     peak_allocated: Cell<usize>,
+    /// Limit on the allocated bytes, checked by [`Heap::check_alloc`].
+    max_allocated: Cell<Option<usize>>,
     arena: FastCell<Arena<Bump>>,
 }
 
@@ -583,6 +586,27 @@ impl Heap {
         self.arena.borrow().available_bytes()
     }
 
+    /// Limit the bytes allocated on this heap, see [`Heap::check_alloc`].
+    pub(crate) fn set_max_allocated_bytes(&self, max: Option<usize>) {
+        self.max_allocated.set(max);
+    }
+
+    /// Check that `bytes` more can be allocated without going over the limit set with
+    /// [`Evaluator::set_max_heap_bytes`](crate::eval::Evaluator::set_max_heap_bytes).
+    ///
+    /// Operations which allocate an amount of memory that depends on their arguments
+    /// call this before allocating anything, so that they fail instead of allocating
+    /// far more than the limit.
+    pub(crate) fn check_alloc(&self, bytes: usize) -> anyhow::Result<()> {
+        if let Some(max) = self.max_allocated.get() {
+            let allocated = self.allocated_bytes();
+            if allocated.saturating_add(bytes) > max {
+                return Err(EvalLimitsError::HeapLimitAllocation(max, bytes, allocated).into());
+            }
+        }
+        Ok(())
+    }
+
     fn alloc_raw<'v, 'v2: 'v2>(&'v self, x: impl AValue<'v2, ExtraElem = ()>) -> Value<'v> {
         let arena = self.arena.borrow();
         let v: &AValueRepr<_> = arena.alloc(x);
@@ -56,6 +56,7 @@ use crate::coerce::CoerceKey;
 use crate::collections::Hashed;
 use crate::collections::StarlarkHashValue;
 use crate::collections::StarlarkHasher;
+use crate::docs::Doc;
 use crate::docs::DocItem;
 use crate::eval::compiler::def::Def;
 use crate::eval::compiler::def::FrozenDef;
@@ -729,6 +730,11 @@ impl<'v> Value<'v> {
         self.get_ref().documentation()
     }
 
+    /// Forwards to [`StarlarkValue::type_documentation`].
+    pub fn type_documentation(self) -> Option<Doc> {
+        self.get_ref().type_documentation()
+    }
+
     /// Produce an iterable from a value.
     #[inline]
     pub fn iterate(self, heap: &'v Heap) -> anyhow::Result<StarlarkIterator<'v>> {
@@ -34,6 +34,7 @@ use crate::cast::transmute;
 use crate::collections::Hashed;
 use crate::collections::StarlarkHashValue;
 use crate::collections::StarlarkHasher;
+use crate::docs::Doc;
 use crate::docs::DocItem;
 use crate::environment::Methods;
 use crate::eval::Arguments;
@@ -288,6 +289,11 @@ impl<'v> AValueDyn<'v> {
         (self.vtable.starlark_value.documentation)(StarlarkValueRawPtr::new(self.value))
     }
 
+    #[inline]
+    pub(crate) fn type_documentation(self) -> Option<Doc> {
+        (self.vtable.starlark_value.type_documentation)(StarlarkValueRawPtr::new(self.value))
+    }
+
     #[inline]
     pub(crate) fn get_methods(self) -> Option<&'static Methods> {
         (self.vtable.starlark_value.get_methods)()
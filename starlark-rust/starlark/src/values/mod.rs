@@ -71,6 +71,7 @@ pub use crate::values::traits::StarlarkValue;
 pub use crate::values::types::any;
 pub use crate::values::types::array;
 pub use crate::values::types::bool;
+pub use crate::values::types::bytes;
 pub use crate::values::types::dict;
 pub use crate::values::types::enumeration;
 pub use crate::values::types::exported_name;
@@ -82,6 +83,7 @@ pub use crate::values::types::none;
 pub use crate::values::types::range;
 pub use crate::values::types::record;
 pub use crate::values::types::regex;
+pub use crate::values::types::set;
 pub use crate::values::types::string;
 pub use crate::values::types::structs;
 pub use crate::values::types::tuple;
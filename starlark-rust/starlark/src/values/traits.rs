@@ -40,6 +40,7 @@ use starlark_derive::starlark_internal_vtable;
 use crate::any::ProvidesStaticType;
 use crate::collections::Hashed;
 use crate::collections::StarlarkHasher;
+use crate::docs::Doc;
 use crate::docs::DocItem;
 use crate::environment::Methods;
 use crate::eval::Arguments;
@@ -270,6 +271,12 @@ pub trait StarlarkValue<'v>:
         Self::get_methods().map(|methods| methods.documentation())
     }
 
+    /// For values which are types, e.g. record types, the documentation of the values of
+    /// that type, identified by the type name. Used by the typechecker to find their attributes.
+    fn type_documentation(&self) -> Option<Doc> {
+        None
+    }
+
     /// Return a string representation of self, as returned by the `repr()` function.
     /// Defaults to the `Display` instance - which should be fine for nearly all types.
     /// In many cases the `repr()` representation will also be a Starlark expression
@@ -0,0 +1,247 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `bytes` type, an immutable sequence of bytes.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Write;
+use std::hash::Hash;
+
+use allocative::Allocative;
+use starlark_derive::starlark_module;
+use starlark_derive::NoSerialize;
+use starlark_derive::StarlarkDocs;
+
+use crate as starlark;
+use crate::any::ProvidesStaticType;
+use crate::collections::StarlarkHasher;
+use crate::environment::Methods;
+use crate::environment::MethodsBuilder;
+use crate::environment::MethodsStatic;
+use crate::starlark_simple_value;
+use crate::starlark_type;
+use crate::values::index::apply_slice;
+use crate::values::index::convert_index;
+use crate::values::Heap;
+use crate::values::StarlarkValue;
+use crate::values::Value;
+use crate::values::ValueError;
+use crate::values::ValueLike;
+
+/// An immutable sequence of bytes, written as `b"..."`.
+#[derive(
+    ProvidesStaticType,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    NoSerialize,
+    StarlarkDocs,
+    Allocative
+)]
+#[starlark_docs(builtin = "standard")]
+pub struct StarlarkBytes(Box<[u8]>);
+
+starlark_simple_value!(StarlarkBytes);
+
+impl StarlarkBytes {
+    /// The result of calling `type()` on bytes.
+    pub const TYPE: &'static str = "bytes";
+
+    /// Create a new [`StarlarkBytes`] value, which can be allocated on a heap with
+    /// `heap.alloc(StarlarkBytes::new(x))`.
+    pub fn new(x: &[u8]) -> Self {
+        Self(x.into())
+    }
+
+    /// The contents.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for StarlarkBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("b\"")?;
+        for &b in self.0.iter() {
+            match b {
+                b'"' => f.write_str("\\\"")?,
+                b'\\' => f.write_str("\\\\")?,
+                b'\n' => f.write_str("\\n")?,
+                b'\r' => f.write_str("\\r")?,
+                b'\t' => f.write_str("\\t")?,
+                b' '..=b'~' => f.write_char(b as char)?,
+                _ => write!(f, "\\x{:02x}", b)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl<'v> StarlarkValue<'v> for StarlarkBytes {
+    starlark_type!(StarlarkBytes::TYPE);
+
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(bytes_methods)
+    }
+
+    fn collect_repr(&self, collector: &mut String) {
+        write!(collector, "{}", self).unwrap()
+    }
+
+    fn to_bool(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn write_hash(&self, hasher: &mut StarlarkHasher) -> anyhow::Result<()> {
+        self.0.hash(hasher);
+        Ok(())
+    }
+
+    fn equals(&self, other: Value<'v>) -> anyhow::Result<bool> {
+        match other.downcast_ref::<Self>() {
+            Some(other) => Ok(self.0 == other.0),
+            None => Ok(false),
+        }
+    }
+
+    fn compare(&self, other: Value<'v>) -> anyhow::Result<Ordering> {
+        match other.downcast_ref::<Self>() {
+            Some(other) => Ok(self.0.cmp(&other.0)),
+            None => ValueError::unsupported_with(self, "cmp()", other),
+        }
+    }
+
+    fn at(&self, index: Value, _heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        let i = convert_index(index, self.0.len() as i32)? as usize;
+        Ok(Value::new_int(self.0[i] as i32))
+    }
+
+    fn slice(
+        &self,
+        start: Option<Value>,
+        stop: Option<Value>,
+        stride: Option<Value>,
+        heap: &'v Heap,
+    ) -> anyhow::Result<Value<'v>> {
+        Ok(heap.alloc(StarlarkBytes::new(&apply_slice(
+            &self.0, start, stop, stride,
+        )?)))
+    }
+
+    fn length(&self) -> anyhow::Result<i32> {
+        Ok(self.0.len() as i32)
+    }
+
+    fn is_in(&self, other: Value<'v>) -> anyhow::Result<bool> {
+        if let Some(needle) = other.downcast_ref::<Self>() {
+            Ok(needle.0.is_empty() || self.0.windows(needle.0.len()).any(|w| *w == *needle.0))
+        } else {
+            match other.unpack_int() {
+                Some(b @ 0..=255) => Ok(self.0.contains(&(b as u8))),
+                _ => Err(ValueError::IncorrectParameterTypeWithExpected(
+                    "bytes or int in 0..256".to_owned(),
+                    other.to_repr(),
+                )
+                .into()),
+            }
+        }
+    }
+
+    fn add(&self, other: Value<'v>, heap: &'v Heap) -> Option<anyhow::Result<Value<'v>>> {
+        let other = other.downcast_ref::<Self>()?;
+        Some(Ok(
+            heap.alloc(StarlarkBytes::new(&[&*self.0, &*other.0].concat()))
+        ))
+    }
+}
+
+#[starlark_module]
+fn bytes_methods(builder: &mut MethodsBuilder) {
+    /// `b.elems()` returns an iterable of the byte values of `b`, as ints in `0..256`.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// list(b"ab\x00".elems()) == [97, 98, 0]
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe, return_type = "[int.type]")]
+    fn elems<'v>(this: &StarlarkBytes, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        Ok(heap.alloc_list_iter(this.0.iter().map(|&b| Value::new_int(b as i32))))
+    }
+
+    /// `b.hex()` returns the bytes of `b` as a string of lowercase hexadecimal digits,
+    /// two per byte.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// b"\x01\xab".hex() == "01ab"
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn hex(this: &StarlarkBytes) -> anyhow::Result<String> {
+        let mut res = String::with_capacity(this.0.len() * 2);
+        for b in this.0.iter() {
+            write!(res, "{:02x}", b).unwrap();
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+
+    #[test]
+    fn test_bytes_literal() {
+        assert::all_true(
+            r#"
+len(b"abc") == 3
+b"a\x80" == bytes([97, 128])
+b"é" == bytes("é")
+rb"\x00" == bytes("\\x00")
+type(b"") == "bytes"
+repr(b"a\"\n\xff") == 'b"a\\"\\n\\xff"'
+"#,
+        );
+        assert::fail("b'\\xZZ'", "escape sequence");
+        assert::fail("b'\\777'", "escape sequence");
+    }
+
+    #[test]
+    fn test_bytes_ops() {
+        assert::all_true(
+            r#"
+b"abc"[1] == 98
+b"abc"[-1] == 99
+b"abcdef"[1:5:2] == b"bd"
+b"ab" + b"c" == b"abc"
+b"bc" in b"abc"
+98 in b"abc"
+b"ab" < b"b"
+str(b"h\xc3\xa9") == "hé"
+str(b"\xff") == "�"
+{b"x": 1}[b"x"] == 1
+"#,
+        );
+        assert::fail("bytes([256])", "0..256");
+    }
+}
@@ -41,6 +41,7 @@ use crate::private::Private;
 use crate::starlark_complex_value;
 use crate::starlark_simple_value;
 use crate::starlark_type;
+use crate::values::typing::generic_type;
 use crate::values::AllocFrozenValue;
 use crate::values::AllocValue;
 use crate::values::Freeze;
@@ -51,6 +52,7 @@ use crate::values::Heap;
 use crate::values::StarlarkValue;
 use crate::values::Trace;
 use crate::values::Value;
+use crate::values::ValueError;
 use crate::values::ValueLike;
 
 /// Return value of `type(any function)`.
@@ -254,6 +256,16 @@ impl<'v> StarlarkValue<'v> for NativeFunction {
         false
     }
 
+    fn at(&self, index: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        // Type constructors can be indexed to give generic types, e.g. `list[str]`.
+        if let Some(typ) = self.typ.and_then(|t| t.to_value().unpack_str()) {
+            if let Some(res) = generic_type(typ, index, heap)? {
+                return Ok(res);
+            }
+        }
+        ValueError::unsupported_with(self, "[]", index)
+    }
+
     fn dir_attr(&self) -> Vec<String> {
         if self.typ.is_some() {
             vec!["type".to_owned()]
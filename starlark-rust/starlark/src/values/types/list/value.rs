@@ -23,6 +23,7 @@ use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::mem;
 use std::slice;
 
 use allocative::Allocative;
@@ -514,7 +515,13 @@ where
 
     fn mul(&self, other: Value, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
         let l = i32::unpack_param(other)?;
-        let mut result = Vec::with_capacity(self.0.content().len() * cmp::max(0, l) as usize);
+        let len = self
+            .0
+            .content()
+            .len()
+            .saturating_mul(cmp::max(0, l) as usize);
+        heap.check_alloc(len.saturating_mul(mem::size_of::<Value>()))?;
+        let mut result = Vec::with_capacity(len);
         for _ in 0..l {
             result.extend(self.0.content().iter());
         }
@@ -41,6 +41,7 @@
 //! # "#);
 //! ```
 
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -61,6 +62,16 @@ use crate::coerce::Coerce;
 use crate::collections::Hashed;
 use crate::collections::SmallMap;
 use crate::collections::StarlarkHasher;
+use crate::docs::Doc;
+use crate::docs::DocFunction;
+use crate::docs::DocItem;
+use crate::docs::DocMember;
+use crate::docs::DocObject;
+use crate::docs::DocParam;
+use crate::docs::DocProperty;
+use crate::docs::DocReturn;
+use crate::docs::DocType;
+use crate::docs::Identifier;
 use crate::eval::Arguments;
 use crate::eval::Evaluator;
 use crate::eval::ParametersSpec;
@@ -376,6 +387,60 @@ where
     fn export_as(&self, variable_name: &str, _eval: &mut Evaluator<'v, '_>) {
         self.typ.try_export_as(variable_name);
     }
+
+    fn documentation(&self) -> Option<DocItem> {
+        let mut params = Vec::with_capacity(self.fields.len() + 1);
+        params.push(DocParam::NoArgs);
+        for (name, field) in &self.fields {
+            params.push(DocParam::Arg {
+                name: name.clone(),
+                docs: None,
+                typ: Some(DocType {
+                    raw_type: field.0.typ.to_value().to_repr(),
+                }),
+                default_value: field.0.default.map(|d| d.to_value().to_repr()),
+            });
+        }
+        let ret = DocReturn {
+            docs: None,
+            typ: self.typ.borrow().as_ref().map(|name| DocType {
+                raw_type: format!("{}.type", name.as_str()),
+            }),
+        };
+        Some(DocItem::Function(DocFunction {
+            docs: None,
+            params,
+            ret,
+        }))
+    }
+
+    fn type_documentation(&self) -> Option<Doc> {
+        let name = self.typ.borrow().as_ref()?.as_str().to_owned();
+        let members = self
+            .fields
+            .iter()
+            .map(|(field, typ)| {
+                let prop = DocProperty {
+                    docs: None,
+                    typ: Some(DocType {
+                        raw_type: typ.0.typ.to_value().to_repr(),
+                    }),
+                };
+                (field.clone(), DocMember::Property(prop))
+            })
+            .collect();
+        Some(Doc {
+            id: Identifier {
+                name,
+                location: None,
+            },
+            item: DocItem::Object(DocObject {
+                docs: None,
+                members,
+            }),
+            custom_attrs: HashMap::new(),
+        })
+    }
 }
 
 impl<'v, V: ValueLike<'v> + 'v> StarlarkValue<'v> for RecordGen<V>
@@ -0,0 +1,456 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::mem;
+use std::ops::Deref;
+
+use allocative::Allocative;
+use display_container::fmt_container;
+use serde::Serialize;
+use starlark_derive::StarlarkDocs;
+
+use crate as starlark;
+use crate::any::ProvidesStaticType;
+use crate::coerce::coerce;
+use crate::coerce::Coerce;
+use crate::collections::Hashed;
+use crate::collections::SmallSet;
+use crate::environment::Methods;
+use crate::environment::MethodsStatic;
+use crate::starlark_type;
+use crate::values::dict::refcell::unleak_borrow;
+use crate::values::error::ValueError;
+use crate::values::set::SetRef;
+use crate::values::type_repr::StarlarkTypeRepr;
+use crate::values::AllocFrozenValue;
+use crate::values::AllocValue;
+use crate::values::Freeze;
+use crate::values::Freezer;
+use crate::values::FrozenHeap;
+use crate::values::FrozenStringValue;
+use crate::values::FrozenValue;
+use crate::values::Heap;
+use crate::values::StarlarkValue;
+use crate::values::Trace;
+use crate::values::Value;
+
+#[derive(
+    Clone,
+    Default,
+    Trace,
+    Debug,
+    ProvidesStaticType,
+    StarlarkDocs,
+    Allocative
+)]
+#[starlark_docs(builtin = "extension")]
+pub(crate) struct SetGen<T>(pub(crate) T);
+
+impl<'v, T: SetLike<'v>> Display for SetGen<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_container(f, "set([", "])", self.0.content().iter())
+    }
+}
+
+impl<'v> Display for Set<'v> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_container(f, "set([", "])", self.iter())
+    }
+}
+
+/// Define the set type.
+#[derive(Clone, Default, Trace, Debug, ProvidesStaticType, Allocative)]
+#[repr(transparent)]
+pub struct Set<'v> {
+    /// The data stored by the set. The elements must all be hashable values.
+    content: SmallSet<Value<'v>>,
+}
+
+impl<'v> StarlarkTypeRepr for Set<'v> {
+    fn starlark_type_repr() -> String {
+        Set::TYPE.to_owned()
+    }
+}
+
+#[derive(Clone, Default, Debug, ProvidesStaticType, Allocative)]
+#[repr(transparent)]
+pub(crate) struct FrozenSetData {
+    /// The data stored by the set. The elements must all be hashable values.
+    pub(crate) content: SmallSet<FrozenValue>,
+}
+
+/// Alias is used in `StarlarkDocs` derive.
+type FrozenSet = SetGen<FrozenSetData>;
+
+unsafe impl<'v> Coerce<Set<'v>> for FrozenSetData {}
+
+impl<'v> AllocValue<'v> for Set<'v> {
+    fn alloc_value(self, heap: &'v Heap) -> Value<'v> {
+        heap.alloc_complex(SetGen(RefCell::new(self)))
+    }
+}
+
+impl AllocFrozenValue for FrozenSetData {
+    fn alloc_frozen_value(self, heap: &FrozenHeap) -> FrozenValue {
+        heap.alloc_simple(SetGen(self))
+    }
+}
+
+impl<'v> Set<'v> {
+    /// The result of calling `type()` on sets.
+    pub const TYPE: &'static str = "set";
+
+    /// Set type string as Starlark frozen string value.
+    pub fn get_type_value_static() -> FrozenStringValue {
+        FrozenSet::get_type_value_static()
+    }
+
+    /// Create a new [`Set`].
+    pub fn new(content: SmallSet<Value<'v>>) -> Self {
+        Self { content }
+    }
+
+    /// Number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Is the set empty?
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Iterate through the elements of the set, in insertion order.
+    pub fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = Value<'v>> + 'a {
+        self.content.iter().copied()
+    }
+
+    /// Iterate through the elements of the set, but retaining their hashes.
+    pub fn iter_hashed<'a>(&'a self) -> impl Iterator<Item = Hashed<Value<'v>>> + 'a
+    where
+        'v: 'a,
+    {
+        self.content.iter_hashed().map(|x| x.copied())
+    }
+
+    /// Is the given prehashed value an element of the set?
+    pub fn contains_hashed(&self, value: Hashed<Value<'v>>) -> bool {
+        self.content.contains_hashed(value.as_ref())
+    }
+
+    /// Add a value to the set, returning `true` if it was not already present.
+    pub fn insert_hashed(&mut self, value: Hashed<Value<'v>>) -> bool {
+        self.content.insert_hashed(value)
+    }
+
+    /// Remove a value from the set, returning `true` if it was present.
+    pub fn remove_hashed(&mut self, value: Hashed<Value<'v>>) -> bool {
+        self.content.remove_hashed(value.as_ref())
+    }
+
+    /// Remove all elements from the set.
+    pub fn clear(&mut self) {
+        self.content.clear();
+    }
+
+    /// Is every element of this set also an element of `other`?
+    pub fn is_subset(&self, other: &Set<'v>) -> bool {
+        self.len() <= other.len() && self.iter_hashed().all(|x| other.contains_hashed(x))
+    }
+
+    /// The elements in either this set or `other`.
+    pub fn union(&self, other: &Set<'v>) -> Set<'v> {
+        let mut content = self.content.clone();
+        for x in other.iter_hashed() {
+            content.insert_hashed(x);
+        }
+        Set::new(content)
+    }
+
+    /// The elements in both this set and `other`.
+    pub fn intersection(&self, other: &Set<'v>) -> Set<'v> {
+        Set::new(
+            self.iter_hashed()
+                .filter(|x| other.contains_hashed(*x))
+                .fold(SmallSet::new(), |mut content, x| {
+                    content.insert_hashed_unique_unchecked(x);
+                    content
+                }),
+        )
+    }
+
+    /// The elements in this set but not in `other`.
+    pub fn difference(&self, other: &Set<'v>) -> Set<'v> {
+        Set::new(
+            self.iter_hashed()
+                .filter(|x| !other.contains_hashed(*x))
+                .fold(SmallSet::new(), |mut content, x| {
+                    content.insert_hashed_unique_unchecked(x);
+                    content
+                }),
+        )
+    }
+
+    /// The elements in exactly one of this set and `other`.
+    pub fn symmetric_difference(&self, other: &Set<'v>) -> Set<'v> {
+        let mut res = self.difference(other);
+        for x in other.iter_hashed() {
+            if !self.contains_hashed(x) {
+                res.content.insert_hashed_unique_unchecked(x);
+            }
+        }
+        res
+    }
+}
+
+impl<'v> Freeze for SetGen<RefCell<Set<'v>>> {
+    type Frozen = SetGen<FrozenSetData>;
+    fn freeze(self, freezer: &Freezer) -> anyhow::Result<Self::Frozen> {
+        let content = self.0.into_inner().content.freeze(freezer)?;
+        Ok(SetGen(FrozenSetData { content }))
+    }
+}
+
+pub(crate) trait SetLike<'v>: Debug + Allocative {
+    type ContentRef<'a>: Deref<Target = Set<'v>>
+    where
+        Self: 'a,
+        'v: 'a;
+    fn content<'a>(&'a self) -> Self::ContentRef<'a>;
+    // These functions are unsafe for the same reason
+    // `StarlarkValue` iterator functions are unsafe.
+    unsafe fn iter_start(&self);
+    unsafe fn content_unchecked(&self) -> &Set<'v>;
+    unsafe fn iter_stop(&self);
+}
+
+impl<'v> SetLike<'v> for RefCell<Set<'v>> {
+    type ContentRef<'a> = Ref<'a, Set<'v>> where Self: 'a, 'v: 'a;
+
+    fn content<'a>(&'a self) -> Ref<'a, Set<'v>> {
+        self.borrow()
+    }
+
+    #[inline]
+    unsafe fn iter_start(&self) {
+        mem::forget(self.borrow());
+    }
+
+    #[inline]
+    unsafe fn iter_stop(&self) {
+        unleak_borrow(self);
+    }
+
+    #[inline]
+    unsafe fn content_unchecked(&self) -> &Set<'v> {
+        // SAFETY: this function contract is, caller must ensure that the value is borrowed.
+        self.try_borrow_unguarded().ok().unwrap_unchecked()
+    }
+}
+
+impl<'v> SetLike<'v> for FrozenSetData {
+    type ContentRef<'a> = &'a Set<'v> where Self: 'a, 'v: 'a;
+
+    fn content<'a>(&'a self) -> &'a Set<'v> {
+        coerce(self)
+    }
+
+    unsafe fn iter_start(&self) {}
+
+    unsafe fn iter_stop(&self) {}
+
+    unsafe fn content_unchecked(&self) -> &Set<'v> {
+        coerce(self)
+    }
+}
+
+pub(crate) fn set_methods() -> Option<&'static Methods> {
+    static RES: MethodsStatic = MethodsStatic::new();
+    RES.methods(crate::stdlib::set::set_methods)
+}
+
+impl<'v, T: SetLike<'v> + 'v> SetGen<T>
+where
+    Self: ProvidesStaticType,
+{
+    fn binary_op(
+        &self,
+        op: &str,
+        rhs: Value<'v>,
+        heap: &'v Heap,
+        f: impl FnOnce(&Set<'v>, &Set<'v>) -> Set<'v>,
+    ) -> anyhow::Result<Value<'v>> {
+        let rhs = SetRef::from_value(rhs)
+            .map_or_else(|| ValueError::unsupported_with(self, op, rhs), Ok)?;
+        Ok(heap.alloc(f(&self.0.content(), &rhs)))
+    }
+}
+
+impl<'v, T: SetLike<'v> + 'v> StarlarkValue<'v> for SetGen<T>
+where
+    Self: ProvidesStaticType,
+{
+    starlark_type!(Set::TYPE);
+
+    fn get_methods() -> Option<&'static Methods> {
+        set_methods()
+    }
+
+    fn collect_repr(&self, r: &mut String) {
+        r.push_str("set([");
+        for (i, x) in self.0.content().iter().enumerate() {
+            if i != 0 {
+                r.push_str(", ");
+            }
+            x.collect_repr(r);
+        }
+        r.push_str("])");
+    }
+
+    fn collect_repr_cycle(&self, collector: &mut String) {
+        collector.push_str("set(...)");
+    }
+
+    fn to_bool(&self) -> bool {
+        !self.0.content().is_empty()
+    }
+
+    fn equals(&self, other: Value<'v>) -> anyhow::Result<bool> {
+        match SetRef::from_value(other) {
+            None => Ok(false),
+            Some(other) => {
+                let this = self.0.content();
+                Ok(this.len() == other.len() && this.is_subset(&other))
+            }
+        }
+    }
+
+    fn length(&self) -> anyhow::Result<i32> {
+        Ok(self.0.content().len() as i32)
+    }
+
+    fn is_in(&self, other: Value<'v>) -> anyhow::Result<bool> {
+        Ok(self.0.content().contains_hashed(other.get_hashed()?))
+    }
+
+    unsafe fn iterate(&self, me: Value<'v>, _heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        self.0.iter_start();
+        Ok(me)
+    }
+
+    unsafe fn iter_size_hint(&self, index: usize) -> (usize, Option<usize>) {
+        debug_assert!(index <= self.0.content().len());
+        let rem = self.0.content().len() - index;
+        (rem, Some(rem))
+    }
+
+    unsafe fn iter_next(&self, index: usize, _heap: &'v Heap) -> Option<Value<'v>> {
+        self.0.content_unchecked().content.get_index(index).copied()
+    }
+
+    unsafe fn iter_stop(&self) {
+        self.0.iter_stop();
+    }
+
+    fn bit_or(&self, rhs: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        self.binary_op("|", rhs, heap, Set::union)
+    }
+
+    fn bit_and(&self, rhs: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        self.binary_op("&", rhs, heap, Set::intersection)
+    }
+
+    fn bit_xor(&self, rhs: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        self.binary_op("^", rhs, heap, Set::symmetric_difference)
+    }
+
+    fn sub(&self, rhs: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+        self.binary_op("-", rhs, heap, Set::difference)
+    }
+}
+
+impl<'v, T: SetLike<'v>> Serialize for SetGen<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(self.0.content().iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+    use crate::assert::Assert;
+
+    #[test]
+    fn test_set_operators() {
+        assert::all_true(
+            r#"
+set([1, 2]) | set([2, 3]) == set([1, 2, 3])
+set([1, 2]) & set([2, 3]) == set([2])
+set([1, 2]) - set([2, 3]) == set([1])
+set([1, 2]) ^ set([2, 3]) == set([1, 3])
+set([1, 2]) == set([2, 1])
+set([1, 2]) != set([1])
+2 in set([1, 2])
+3 not in set([1, 2])
+"#,
+        );
+    }
+
+    #[test]
+    fn test_mutate_set() {
+        assert::is_true(
+            r#"
+x = set([1, 2])
+b1 = repr(x) == "set([1, 2])"
+x.add(3)
+b2 = repr(x) == "set([1, 2, 3])"
+x.discard(1)
+b3 = repr(x) == "set([2, 3])"
+b1 and b2 and b3
+"#,
+        );
+    }
+
+    #[test]
+    fn test_frozen_set() {
+        let mut a = Assert::new();
+        a.module("m", "x = set([1, 2])");
+        a.fail("load('m', 'x')\nx.add(3)", "Immutable");
+        a.is_true("load('m', 'x')\nx | set([3]) == set([1, 2, 3])");
+    }
+
+    #[test]
+    fn test_mutate_during_iteration() {
+        assert::fail(
+            "def f():\n  x = set([1])\n  for y in x:\n    x.add(2)\nf()",
+            "mutate an iterable for an iterator",
+        );
+    }
+
+    #[test]
+    fn test_unhashable() {
+        assert::fail("set([[1]])", "not hashable");
+    }
+}
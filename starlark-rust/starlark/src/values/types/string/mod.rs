@@ -367,7 +367,9 @@ impl<'v> StarlarkValue<'v> for StarlarkStr {
 
     fn mul(&self, other: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
         let l = i32::unpack_param(other)?;
-        let mut result = String::with_capacity(self.len() * cmp::max(0, l) as usize);
+        let len = self.len().saturating_mul(cmp::max(0, l) as usize);
+        heap.check_alloc(len)?;
+        let mut result = String::with_capacity(len);
         for _i in 0..l {
             result.push_str(self)
         }
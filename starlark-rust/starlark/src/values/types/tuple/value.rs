@@ -15,11 +15,13 @@
  * limitations under the License.
  */
 
+use std::cmp;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::mem;
 use std::slice;
 
 use allocative::Allocative;
@@ -234,7 +236,9 @@ where
 
     fn mul(&self, other: Value, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
         let l = i32::unpack_param(other)?;
-        let mut result = Vec::new();
+        let len = self.len().saturating_mul(cmp::max(0, l) as usize);
+        heap.check_alloc(len.saturating_mul(mem::size_of::<Value>()))?;
+        let mut result = Vec::with_capacity(len);
         for _i in 0..l {
             result.extend(self.content().iter().map(|e| e.to_value()));
         }
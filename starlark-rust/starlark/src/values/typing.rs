@@ -24,6 +24,7 @@ use thiserror::Error;
 
 use crate as starlark;
 use crate::coerce::Coerce;
+use crate::collections::SmallMap;
 use crate::slice_vec_ext::SliceExt;
 use crate::values::dict::Dict;
 use crate::values::dict::DictRef;
@@ -47,6 +48,9 @@ enum TypingError {
     /// it
     #[error(r#"Found `{0}` instead of a valid type annotation. Perhaps you meant `"{1}"`?"#)]
     PerhapsYouMeant(String, String),
+    /// A generic type was given the wrong number of type arguments, e.g. `dict[str]`
+    #[error("Type `{0}[...]` expects {1} type arguments, but got {2}")]
+    GenericArity(String, usize, usize),
 }
 
 trait TypeCompiledImpl: Allocative + Send + Sync + 'static {
@@ -357,6 +361,53 @@ impl TypeCompiled {
     }
 }
 
+/// A type argument of a generic type, where `str` means the same as `str.type`, as it does in
+/// Python. Other values with a string `.type` attribute, e.g. record types, are replaced by it.
+fn generic_type_argument<'v>(x: Value<'v>, heap: &'v Heap) -> anyhow::Result<Value<'v>> {
+    // Check it is a valid type, so that errors are reported where the generic type is written.
+    if TypeCompiled::new(x, heap).is_ok() {
+        return Ok(x);
+    }
+    match x.get_attr("type", heap)? {
+        Some(t) if t.unpack_str().is_some() => Ok(t),
+        _ => Err(TypingError::InvalidTypeAnnotation(x.to_str()).into()),
+    }
+}
+
+/// PEP 585 style generic types, `list[t]`, `dict[k, v]` and `tuple[t1, t2, ...]`, given the
+/// `.type` of the builtin being indexed. These produce the same types as `[t]`, `{k: v}` and
+/// `(t1, t2, ...)`. Returns `None` if `typ` has no generic form.
+pub(crate) fn generic_type<'v>(
+    typ: &str,
+    index: Value<'v>,
+    heap: &'v Heap,
+) -> anyhow::Result<Option<Value<'v>>> {
+    let args = match Tuple::from_value(index) {
+        Some(xs) => xs.content().try_map(|x| generic_type_argument(*x, heap))?,
+        None => vec![generic_type_argument(index, heap)?],
+    };
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(TypingError::GenericArity(typ.to_owned(), n, args.len()))
+        }
+    };
+    if typ == ListRef::TYPE {
+        arity(1)?;
+        Ok(Some(heap.alloc_list(&args)))
+    } else if typ == Dict::TYPE {
+        arity(2)?;
+        let mut content = SmallMap::with_capacity(1);
+        content.insert_hashed(args[0].get_hashed()?, args[1]);
+        Ok(Some(heap.alloc(Dict::new(content))))
+    } else if typ == Tuple::TYPE {
+        Ok(Some(heap.alloc_tuple(&args)))
+    } else {
+        Ok(None)
+    }
+}
+
 fn invalid_type_annotation<'v>(ty: Value<'v>, heap: &'v Heap) -> TypingError {
     if let Some(name) = ty
         .get_attr("type", heap)
@@ -515,4 +566,38 @@ def foo(f: int.type = None):
             "`None` of type `NoneType` does not match the type annotation `int`",
         );
     }
+
+    #[test]
+    fn test_generic_types() {
+        let a = assert::Assert::new();
+        a.all_true(
+            r#"
+list[str] == [str.type]
+dict[str, int] == {str.type: int.type}
+tuple[int, str.type] == (int.type, str.type)
+is_type(["a", "b"], list[str])
+is_type({"a": [1]}, dict[str, list[int]])
+is_type((1, "a"), tuple[int, str])
+is_type([None, 1], list[[None, int.type]])
+not is_type(["a", 1], list[str])
+not is_type({"a": "b"}, dict[str, int])
+"#,
+        );
+        a.pass(
+            r#"
+Foo = record(x=int.type)
+def f(xs: list[Foo]) -> dict[str, Foo]:
+    return {"a": xs[0]}
+f([Foo(x=1)])
+"#,
+        );
+        a.fails(
+            "def f(xs: list[str]):\n pass\nf([1])",
+            &["type annotation", "`[1]`", "`xs`"],
+        );
+        a.fail("list[str, int]", "expects 1 type arguments, but got 2");
+        a.fail("dict[str]", "expects 2 type arguments, but got 1");
+        a.fail("list[1]", "not a valid type");
+        a.fail("str[int]", "not supported");
+    }
 }
@@ -40,6 +40,7 @@ pub use crate::small_set::iter::IterMutUnchecked;
 
 /// An memory-efficient set with deterministic order, based on [`SmallMap`].
 #[derive(Clone, Allocative)]
+#[repr(transparent)]
 pub struct SmallSet<T>(SmallMap<T, ()>);
 
 impl<T> Default for SmallSet<T> {
@@ -244,6 +245,17 @@ impl<T> SmallSet<T> {
         self.0.remove(key).is_some()
     }
 
+    /// Remove the element with the given hash from the set if it is present.
+    ///
+    /// Time complexity of this operation is *O(N)* where *N* is the number of entries in the set.
+    #[inline]
+    pub fn remove_hashed<Q>(&mut self, key: Hashed<&Q>) -> bool
+    where
+        Q: ?Sized + Equivalent<T>,
+    {
+        self.0.remove_hashed(key).is_some()
+    }
+
     /// Insert entry if it doesn't exist.
     ///
     /// Return the resulting entry in the map.
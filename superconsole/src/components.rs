@@ -18,6 +18,8 @@ pub use bordering::Bordered;
 pub use bounding::Bounded;
 pub(crate) use canvas::Canvas;
 pub use padding::Padded;
+pub use scrolling::ScrollState;
+pub use scrolling::ScrollableList;
 pub use splitting::Split;
 
 pub use crate::components::draw_horizontal::DrawHorizontal;
@@ -34,6 +36,7 @@ mod draw_horizontal;
 mod draw_vertical;
 pub(crate) mod echo;
 pub mod padding;
+mod scrolling;
 pub mod splitting;
 
 /// Used to mark whether a draw is final.
@@ -0,0 +1,265 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cell::Cell;
+use std::ops::Range;
+
+use crossterm::style::Attribute;
+
+use crate::input::Focusable;
+use crate::input::Key;
+use crate::Component;
+use crate::Dimensions;
+use crate::DrawMode;
+use crate::Line;
+use crate::Lines;
+
+/// The selection and scroll position of a list that may be longer than the space it is drawn in.
+///
+/// The caller keeps the length up to date with [`set_len`](ScrollState::set_len) as items come and
+/// go, and forwards key presses through [`Focusable`](Focusable).
+/// The visible window is only known once the list is drawn, so it is adjusted at draw time to keep
+/// the selection in view.
+#[derive(Debug, Default)]
+pub struct ScrollState {
+    selected: usize,
+    len: usize,
+    /// Index of the first visible item, as of the last draw.
+    offset: Cell<usize>,
+    /// Number of visible items, as of the last draw. Used to move by a page.
+    page: Cell<usize>,
+}
+
+impl ScrollState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index of the selected item, or `None` if the list is empty.
+    pub fn selected(&self) -> Option<usize> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(self.selected.min(self.len - 1))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Updates the number of items, keeping the selection within bounds.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.len.saturating_sub(1));
+    }
+
+    fn page(&self) -> usize {
+        self.page.get().max(1)
+    }
+
+    /// The range of `len` items visible in `height` rows. Scrolls just enough to keep the selection
+    /// visible, so that the view doesn't jump around while moving within it.
+    fn window(&self, height: usize, len: usize) -> Range<usize> {
+        let height = height.min(len);
+        let selected = self.selected.min(len.saturating_sub(1));
+        let mut offset = self.offset.get();
+        if selected < offset {
+            offset = selected;
+        } else if height > 0 && selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+        // Don't leave empty space at the end after the list shrinks.
+        offset = offset.min(len - height);
+        self.offset.set(offset);
+        self.page.set(height);
+        offset..offset + height
+    }
+}
+
+impl Focusable for ScrollState {
+    fn handle_key(&mut self, key: Key) -> bool {
+        let selected = self.selected().unwrap_or(0);
+        let index = match key {
+            Key::Up => selected.saturating_sub(1),
+            Key::Down => selected.saturating_add(1),
+            Key::PageUp => selected.saturating_sub(self.page()),
+            Key::PageDown => selected.saturating_add(self.page()),
+            Key::Home => 0,
+            Key::End => usize::MAX,
+            _ => return false,
+        };
+        self.select(index);
+        true
+    }
+}
+
+/// The `ScrollableList` component draws the part of a list of lines that fits in the given
+/// height, scrolled according to a [`ScrollState`](ScrollState), with the selected line highlighted.
+///
+/// The lines are expected to correspond one-to-one to the items counted in the state. If the list
+/// changed since the state was last updated, the selection is clamped to the lines drawn.
+#[derive(Debug)]
+pub struct ScrollableList<'a> {
+    state: &'a ScrollState,
+    lines: Lines,
+}
+
+impl<'a> ScrollableList<'a> {
+    pub fn new(state: &'a ScrollState, lines: Lines) -> Self {
+        Self { state, lines }
+    }
+}
+
+impl<'a> Component for ScrollableList<'a> {
+    fn draw_unchecked(
+        &self,
+        Dimensions { height, .. }: Dimensions,
+        _mode: DrawMode,
+    ) -> anyhow::Result<Lines> {
+        let len = self.lines.len();
+        let window = self.state.window(height, len);
+        let selected = self.state.selected.min(len.saturating_sub(1));
+
+        Ok(self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(window.start)
+            .take(window.len())
+            .map(|(i, line)| {
+                if i == selected {
+                    highlight(line)
+                } else {
+                    line.clone()
+                }
+            })
+            .collect())
+    }
+}
+
+fn highlight(line: &Line) -> Line {
+    line.iter()
+        .cloned()
+        .map(|mut span| {
+            span.style.attributes.set(Attribute::Reverse);
+            span
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    fn numbered(len: usize) -> Lines {
+        (0..len)
+            .map(|i| Line::unstyled(&i.to_string()).unwrap())
+            .collect()
+    }
+
+    fn draw(state: &ScrollState, len: usize, height: usize) -> Vec<String> {
+        ScrollableList::new(state, numbered(len))
+            .draw(Dimensions::new(10, height), DrawMode::Normal)
+            .unwrap()
+            .iter()
+            .map(|line| {
+                let text = line.to_unstyled();
+                let reversed = line
+                    .iter()
+                    .all(|span| span.style.attributes.has(Attribute::Reverse));
+                if reversed {
+                    format!("[{}]", text)
+                } else {
+                    text
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scroll_down_follows_selection() {
+        let mut state = ScrollState::new();
+        state.set_len(5);
+        assert_eq!(draw(&state, 5, 3), vec!["[0]", "1", "2"]);
+
+        state.handle_key(Key::Down);
+        state.handle_key(Key::Down);
+        assert_eq!(draw(&state, 5, 3), vec!["0", "1", "[2]"]);
+
+        state.handle_key(Key::Down);
+        assert_eq!(draw(&state, 5, 3), vec!["1", "2", "[3]"]);
+
+        // Moving back up within the window doesn't scroll.
+        state.handle_key(Key::Up);
+        assert_eq!(draw(&state, 5, 3), vec!["1", "[2]", "3"]);
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut state = ScrollState::new();
+        state.set_len(10);
+        draw(&state, 10, 4);
+
+        assert!(state.handle_key(Key::PageDown));
+        assert_eq!(state.selected(), Some(4));
+        assert!(state.handle_key(Key::End));
+        assert_eq!(draw(&state, 10, 4), vec!["6", "7", "8", "[9]"]);
+        assert!(state.handle_key(Key::PageUp));
+        assert_eq!(state.selected(), Some(5));
+        assert!(state.handle_key(Key::Home));
+        assert_eq!(draw(&state, 10, 4), vec!["[0]", "1", "2", "3"]);
+
+        assert!(!state.handle_key(Key::Enter));
+        assert!(!state.handle_key(Key::Char('d')));
+    }
+
+    #[test]
+    fn test_shrinking_list() {
+        let mut state = ScrollState::new();
+        state.set_len(10);
+        state.select(9);
+        draw(&state, 10, 4);
+
+        state.set_len(2);
+        assert_eq!(state.selected(), Some(1));
+        assert_eq!(draw(&state, 2, 4), vec!["0", "[1]"]);
+
+        state.set_len(0);
+        assert_eq!(state.selected(), None);
+        assert!(draw(&state, 0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_highlight_keeps_style() -> anyhow::Result<()> {
+        let mut state = ScrollState::new();
+        state.set_len(1);
+        let line = Line::from_iter([Span::new_colored("red", crossterm::style::Color::Red)?]);
+        let output = ScrollableList::new(&state, Lines(vec![line]))
+            .draw(Dimensions::new(10, 10), DrawMode::Normal)?;
+
+        let span = output.iter().next().unwrap().iter().next().unwrap();
+        assert_eq!(
+            span.style.foreground_color,
+            Some(crossterm::style::Color::Red)
+        );
+        assert!(span.style.attributes.has(Attribute::Reverse));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Keyboard input for interactive components.
+//!
+//! Superconsole does not read from the terminal itself. Callers that put the terminal in
+//! non-canonical mode can feed the characters they read to a [`KeyDecoder`](KeyDecoder) and pass
+//! the resulting [`Key`](Key)s to whichever [`Focusable`](Focusable) state currently has focus.
+
+/// A key press, with terminal escape sequences already decoded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Backspace,
+    Escape,
+    Char(char),
+}
+
+/// Decodes the characters read from a terminal into [`Key`](Key)s.
+///
+/// Special keys arrive as escape sequences spanning several characters (e.g. `ESC [ A` for the up
+/// arrow), so the decoder buffers an incomplete sequence until its final character arrives.
+/// A lone `ESC` cannot be told apart from the start of a sequence until the next character is
+/// read, so it is only reported as [`Key::Escape`](Key::Escape) at that point.
+#[derive(Debug, Default)]
+pub struct KeyDecoder {
+    state: DecoderState,
+}
+
+#[derive(Debug, Default)]
+enum DecoderState {
+    #[default]
+    Ground,
+    /// Read `ESC`.
+    Escape,
+    /// Read `ESC [` or `ESC O`, followed by the parameter characters collected so far.
+    Sequence(String),
+}
+
+impl KeyDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next character read from the terminal, and returns the keys it completes.
+    /// Usually that is zero or one key, but an `ESC` followed by a regular character yields both.
+    pub fn push(&mut self, c: char) -> Vec<Key> {
+        match std::mem::take(&mut self.state) {
+            DecoderState::Ground => match Self::decode_single(c) {
+                Some(key) => vec![key],
+                None => {
+                    self.state = DecoderState::Escape;
+                    Vec::new()
+                }
+            },
+            DecoderState::Escape => match c {
+                '[' | 'O' => {
+                    self.state = DecoderState::Sequence(String::new());
+                    Vec::new()
+                }
+                '\x1b' => {
+                    self.state = DecoderState::Escape;
+                    vec![Key::Escape]
+                }
+                c => {
+                    let mut keys = vec![Key::Escape];
+                    keys.extend(self.push(c));
+                    keys
+                }
+            },
+            DecoderState::Sequence(mut params) => {
+                if c.is_ascii_digit() || c == ';' {
+                    params.push(c);
+                    self.state = DecoderState::Sequence(params);
+                    Vec::new()
+                } else {
+                    // Any other character terminates the sequence. Sequences we don't know about
+                    // are dropped rather than leaking their characters as key presses.
+                    Self::decode_sequence(&params, c).into_iter().collect()
+                }
+            }
+        }
+    }
+
+    /// Decodes a character outside of an escape sequence. Returns `None` for `ESC`.
+    fn decode_single(c: char) -> Option<Key> {
+        match c {
+            '\x1b' => None,
+            '\r' | '\n' => Some(Key::Enter),
+            '\x7f' | '\x08' => Some(Key::Backspace),
+            c => Some(Key::Char(c)),
+        }
+    }
+
+    fn decode_sequence(params: &str, last: char) -> Option<Key> {
+        match (params, last) {
+            (_, 'A') => Some(Key::Up),
+            (_, 'B') => Some(Key::Down),
+            (_, 'C') => Some(Key::Right),
+            (_, 'D') => Some(Key::Left),
+            (_, 'H') | ("1" | "7", '~') => Some(Key::Home),
+            (_, 'F') | ("4" | "8", '~') => Some(Key::End),
+            ("5", '~') => Some(Key::PageUp),
+            ("6", '~') => Some(Key::PageDown),
+            _ => None,
+        }
+    }
+}
+
+/// State of an interactive component that can take keyboard focus.
+/// The component itself stays a stateless drawer that borrows this state.
+pub trait Focusable {
+    /// Reacts to a key press while focused.
+    /// Returns whether the key was consumed, so that the caller can otherwise handle it.
+    fn handle_key(&mut self, key: Key) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(input: &str) -> Vec<Key> {
+        let mut decoder = KeyDecoder::new();
+        input.chars().flat_map(|c| decoder.push(c)).collect()
+    }
+
+    #[test]
+    fn test_decode_plain() {
+        assert_eq!(
+            decode("d?\n\x7f"),
+            vec![Key::Char('d'), Key::Char('?'), Key::Enter, Key::Backspace]
+        );
+    }
+
+    #[test]
+    fn test_decode_arrows() {
+        assert_eq!(
+            decode("\x1b[A\x1b[B\x1b[C\x1b[D\x1bOA"),
+            vec![Key::Up, Key::Down, Key::Right, Key::Left, Key::Up]
+        );
+    }
+
+    #[test]
+    fn test_decode_with_params() {
+        assert_eq!(
+            decode("\x1b[5~\x1b[6~\x1b[1~\x1b[F\x1b[1;5A"),
+            vec![Key::PageUp, Key::PageDown, Key::Home, Key::End, Key::Up]
+        );
+    }
+
+    #[test]
+    fn test_decode_escape() {
+        assert_eq!(decode("\x1bq"), vec![Key::Escape, Key::Char('q')]);
+        assert_eq!(decode("\x1b\x1b[A"), vec![Key::Escape, Key::Up]);
+        // Incomplete.
+        assert_eq!(decode("\x1b"), vec![]);
+    }
+
+    #[test]
+    fn test_decode_unknown_sequence() {
+        assert_eq!(decode("\x1b[2~x"), vec![Key::Char('x')]);
+    }
+}
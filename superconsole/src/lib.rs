@@ -20,6 +20,7 @@
 //! Components live in the scratch area.
 //!
 //! A set of pre-baked composition and testing oriented components are provided in the [`components`](components) module.
+//! Interactive components take keyboard input through the [`input`](input) module.
 
 pub use components::Component;
 pub use components::DrawMode;
@@ -37,6 +38,7 @@ pub mod builder;
 pub mod components;
 pub mod content;
 mod dimensions;
+pub mod input;
 pub mod output;
 pub mod style;
 mod superconsole;